@@ -5,7 +5,7 @@ import java.io.File
 
 /**
  * JNI bridge to the Rust scorelib library for MusicXML rendering,
- * playback map generation, and MIDI generation.
+ * playback map generation, MIDI generation and WAV rendering.
  */
 object ScoreLib {
 
@@ -29,6 +29,21 @@ object ScoreLib {
      */
     external fun renderBytes(data: ByteArray, extension: String?, pageWidth: Float, transpose: Int): String?
 
+    /**
+     * Render MusicXML bytes to SVG with render options.
+     * @param pageWidth SVG width in user-units (pass 0f for the default 820).
+     * @param transpose Semitones to transpose (0 = no change).
+     * @param optionsJson JSON string with render options (theme, staff scale,
+     *   fonts, display toggles), or null for defaults.
+     */
+    external fun renderBytesWithOptions(
+        data: ByteArray,
+        extension: String?,
+        pageWidth: Float,
+        transpose: Int,
+        optionsJson: String?
+    ): String?
+
     /**
      * Render a MusicXML asset file to SVG.
      * @param pageWidth SVG width in user-units (pass 0f for the default 820).
@@ -105,4 +120,13 @@ object ScoreLib {
     ): ByteArray? {
         return generateMidi(data, ext.ifEmpty { null }, optionsJson)
     }
+
+    // ── Audio Rendering ─────────────────────────────────────────────────
+
+    /**
+     * Render MusicXML bytes to WAV bytes with the built-in synthesizer.
+     * @param soundFont SoundFont (.sf2) bytes to play the tracks with.
+     * @param optionsJson JSON string with MIDI and synthesizer options, or null for defaults.
+     */
+    external fun generateWav(data: ByteArray, extension: String?, soundFont: ByteArray, optionsJson: String?): ByteArray?
 }
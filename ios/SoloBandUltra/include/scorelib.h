@@ -35,6 +35,68 @@ char* scorelib_render_bytes(const uint8_t* data, size_t len, const char* extensi
  */
 char* scorelib_playback_map(const uint8_t* data, size_t len, const char* extension, double page_width, int32_t transpose);
 
+/**
+ * Parse score data from a byte buffer and render it to SVG with render
+ * options, e.g. for dark mode or low vision.
+ * `options_json` is a JSON object with optional fields: "theme" ("light",
+ * "dark", "high-contrast" or "sepia"), "staff_scale" (1.0 = default size),
+ * "font" ("serif", "sans-serif" or a font name), "chord_symbols",
+ * "lyrics" and "measure_numbers" (true/false), and for beginners
+ * "note_names" ("letters", "fixed-do" or "movable-do"),
+ * "note_name_position" ("below" or "inside"), and "colored_noteheads"
+ * and "finger_numbers" (true/false).  May be NULL for defaults.
+ * Other arguments are as for scorelib_render_bytes().
+ * Returns a null-terminated SVG string, or NULL on error.
+ * The caller must free the returned string with scorelib_free_string().
+ */
+char* scorelib_render_with_options(const uint8_t* data, size_t len, const char* extension, double page_width, int32_t transpose, const char* options_json);
+
+/**
+ * Generate the playback map JSON for scorelib_render_with_options(); pass
+ * the same page width and options so positions match the SVG.
+ * Returns a null-terminated JSON string, or NULL on error.
+ * The caller must free the returned string with scorelib_free_string().
+ */
+char* scorelib_playback_map_with_options(const uint8_t* data, size_t len, const char* extension, double page_width, int32_t transpose, const char* options_json);
+
+/**
+ * Parse score data from a byte buffer and render it as jianpu
+ * (numbered notation) SVG.
+ * Arguments are as for scorelib_render_bytes().
+ * Returns a null-terminated SVG string, or NULL on error.
+ * The caller must free the returned string with scorelib_free_string().
+ */
+char* scorelib_render_jianpu(const uint8_t* data, size_t len, const char* extension, double page_width, int32_t transpose);
+
+/**
+ * Generate the playback map JSON for the jianpu rendering of score data,
+ * so the cursor follows the numbered notation instead of the staves.
+ * Arguments are as for scorelib_playback_map().
+ * Returns a null-terminated JSON string, or NULL on error.
+ * The caller must free the returned string with scorelib_free_string().
+ */
+char* scorelib_jianpu_playback_map(const uint8_t* data, size_t len, const char* extension, double page_width, int32_t transpose);
+
+/**
+ * Parse score data from a byte buffer and render it on one endless
+ * horizontal system, for continuous scrolling under a fixed cursor.
+ * `extension` and `transpose` are as for scorelib_render_bytes(), and
+ * `options_json` as for scorelib_render_with_options() (may be NULL).
+ * Returns a null-terminated SVG string, or NULL on error.
+ * The caller must free the returned string with scorelib_free_string().
+ */
+char* scorelib_render_horizontal(const uint8_t* data, size_t len, const char* extension, int32_t transpose, const char* options_json);
+
+/**
+ * Generate the playback map JSON for the horizontal rendering of score
+ * data; measure x positions run along the single system.
+ * `extension` and `transpose` are as for scorelib_playback_map();
+ * `options_json` must match the one used to render (may be NULL).
+ * Returns a null-terminated JSON string, or NULL on error.
+ * The caller must free the returned string with scorelib_free_string().
+ */
+char* scorelib_horizontal_playback_map(const uint8_t* data, size_t len, const char* extension, int32_t transpose, const char* options_json);
+
 /**
  * Generate MIDI (SMF Type 1) bytes from MusicXML data.
  * `extension` is an optional format hint, may be NULL.
@@ -57,6 +119,46 @@ uint8_t* scorelib_generate_midi_from_bytes(const uint8_t* data, size_t len,
  */
 uint8_t* scorelib_generate_midi(const char* path, const char* options_json, size_t* out_len);
 
+/**
+ * Render a MusicXML file to WAV (16-bit stereo PCM) with the built-in
+ * synthesizer, using the SoundFont at `soundfont_path`.
+ * `options_json` takes the MIDI options plus `sample_rate`, `gain` and
+ * `reverb`; may be NULL for defaults.
+ * `out_len` receives the length of the returned WAV data.
+ * Returns a pointer to the WAV bytes, or NULL on error.
+ * The caller must free the returned buffer with scorelib_free_wav().
+ */
+uint8_t* scorelib_generate_wav(const char* path, const char* soundfont_path,
+                               const char* options_json, size_t* out_len);
+
+/**
+ * Render a MusicXML file to a PDF document.
+ * `paper` selects the page size: 0 for A4, 1 for Letter.
+ * `options_json` is as for scorelib_render_with_options(), may be NULL.
+ * `out_len` receives the length of the returned PDF data.
+ * Returns a pointer to the PDF bytes, or NULL on error.
+ * The caller must free the returned buffer with scorelib_free_pdf().
+ */
+uint8_t* scorelib_render_pdf(const char* path, int32_t paper, int32_t transpose,
+                             const char* options_json, size_t* out_len);
+
+/**
+ * Render part of a MusicXML file to a PNG image.
+ * `dpi` sets the resolution; 0 for the default (150).
+ * With `page` > 0 that page is rendered as printed; otherwise with
+ * `first_measure` > 0 measures `first_measure`..`last_measure` (1-based,
+ * inclusive); otherwise the first system.
+ * `paper` selects the page size: 0 for A4, 1 for Letter.
+ * `options_json` is as for scorelib_render_with_options(), may be NULL.
+ * `out_len` receives the length of the returned PNG data.
+ * Returns a pointer to the PNG bytes, or NULL on error.
+ * The caller must free the returned buffer with scorelib_free_png().
+ */
+uint8_t* scorelib_render_png(const char* path, double dpi, int32_t page,
+                             int32_t first_measure, int32_t last_measure,
+                             int32_t paper, int32_t transpose,
+                             const char* options_json, size_t* out_len);
+
 /**
  * Free a string previously returned by scorelib functions.
  * Safe to call with NULL.
@@ -69,4 +171,22 @@ void scorelib_free_string(char* ptr);
  */
 void scorelib_free_midi(uint8_t* ptr, size_t len);
 
+/**
+ * Free WAV bytes previously returned by scorelib_generate_wav().
+ * Safe to call with NULL.
+ */
+void scorelib_free_wav(uint8_t* ptr, size_t len);
+
+/**
+ * Free PDF bytes previously returned by scorelib_render_pdf().
+ * Safe to call with NULL.
+ */
+void scorelib_free_pdf(uint8_t* ptr, size_t len);
+
+/**
+ * Free PNG bytes previously returned by scorelib_render_png().
+ * Safe to call with NULL.
+ */
+void scorelib_free_png(uint8_t* ptr, size_t len);
+
 #endif /* SCORELIB_H */
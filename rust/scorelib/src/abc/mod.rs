@@ -0,0 +1,124 @@
+//! ABC notation support — parses ABC tunes into the Score data model and
+//! writes scores back out as ABC.
+//!
+//! The parser reads the first tune in the text: header fields (T:, C:,
+//! M:, L:, Q:, K:, V:), notes, rests, chords, ties, slurs, tuplets,
+//! broken rhythms, grace notes, chord symbols and annotations, common
+//! decorations, repeats with numbered endings, voice overlays (`&`),
+//! voices (V:) and lyrics (w:).  Beams follow the ABC rule: notes written
+//! without a space between them are beamed together.
+//!
+//! The writer produces one tune with `L:1/8`, one voice per part and
+//! `&` overlays for additional voices inside a part.
+
+mod parser;
+mod writer;
+
+pub use parser::parse_abc;
+pub use writer::write_abc;
+
+use crate::model::*;
+
+// ─── Fractions ───────────────────────────────────────────────────────
+
+/// An exact fraction of a whole note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Frac {
+    num: i64,
+    den: i64,
+}
+
+impl Frac {
+    fn new(num: i64, den: i64) -> Self {
+        let g = gcd(num.abs(), den.abs()).max(1);
+        let sign = if den < 0 { -1 } else { 1 };
+        Frac { num: sign * num / g, den: sign * den / g }
+    }
+
+    const ZERO: Frac = Frac { num: 0, den: 1 };
+
+    fn add(self, o: Frac) -> Frac {
+        Frac::new(self.num * o.den + o.num * self.den, self.den * o.den)
+    }
+
+    fn sub(self, o: Frac) -> Frac {
+        Frac::new(self.num * o.den - o.num * self.den, self.den * o.den)
+    }
+
+    fn mul(self, o: Frac) -> Frac {
+        Frac::new(self.num * o.num, self.den * o.den)
+    }
+
+    fn lt(self, o: Frac) -> bool {
+        self.num * o.den < o.num * self.den
+    }
+
+    /// Value in divisions (per quarter note).
+    fn to_divisions(self, divisions: i64) -> i32 {
+        (self.num * 4 * divisions / self.den) as i32
+    }
+}
+
+impl Default for Frac {
+    fn default() -> Self {
+        Frac::ZERO
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Written note values, longest first: (length, type, dotted).
+const NOTE_VALUES: [(i64, i64, &str, bool); 15] = [
+    (2, 1, "breve", false),
+    (3, 2, "whole", true),
+    (1, 1, "whole", false),
+    (3, 4, "half", true),
+    (1, 2, "half", false),
+    (3, 8, "quarter", true),
+    (1, 4, "quarter", false),
+    (3, 16, "eighth", true),
+    (1, 8, "eighth", false),
+    (3, 32, "16th", true),
+    (1, 16, "16th", false),
+    (3, 64, "32nd", true),
+    (1, 32, "32nd", false),
+    (1, 64, "64th", false),
+    (1, 128, "128th", false),
+];
+
+// ─── Shared helpers ──────────────────────────────────────────────────
+
+/// Alteration of a step in a key signature.
+fn key_alter(fifths: i32, step: char) -> i32 {
+    const SHARP_ORDER: [char; 7] = ['F', 'C', 'G', 'D', 'A', 'E', 'B'];
+    let pos = SHARP_ORDER.iter().position(|&s| s == step).unwrap_or(0) as i32;
+    if fifths > pos {
+        1
+    } else if fifths < 0 && -fifths >= 7 - pos {
+        -1
+    } else {
+        0
+    }
+}
+
+/// MusicXML chord kinds and their ABC spellings; the first is written.
+const CHORD_KINDS: [(&str, &[&str]); 16] = [
+    ("major", &["", "maj", "M"]),
+    ("minor", &["m", "min", "-"]),
+    ("dominant", &["7"]),
+    ("major-seventh", &["maj7", "M7", "Δ", "Δ7"]),
+    ("minor-seventh", &["m7", "min7", "-7"]),
+    ("diminished", &["dim", "°", "o"]),
+    ("diminished-seventh", &["dim7", "°7", "o7"]),
+    ("half-diminished", &["m7b5", "ø", "ø7"]),
+    ("augmented", &["aug", "+"]),
+    ("major-sixth", &["6"]),
+    ("minor-sixth", &["m6"]),
+    ("dominant-ninth", &["9"]),
+    ("major-ninth", &["maj9"]),
+    ("minor-ninth", &["m9"]),
+    ("suspended-fourth", &["sus4", "sus"]),
+    ("suspended-second", &["sus2"]),
+];
@@ -0,0 +1,1614 @@
+//! ABC parser — reads the first tune of an ABC document into a Score.
+
+use std::collections::HashMap;
+
+use super::*;
+
+/// Parse the first tune of an ABC document into a Score.
+pub fn parse_abc(text: &str) -> Result<Score, String> {
+    let mut parser = AbcParser::new();
+    let mut started = false;
+    let has_reference = text.lines().any(|l| l.starts_with("X:"));
+
+    for raw_line in text.lines() {
+        let line = strip_comment(raw_line);
+        if raw_line.starts_with("X:") {
+            if started {
+                break; // next tune
+            }
+            started = true;
+            continue;
+        }
+        if has_reference && !started {
+            continue; // file header or free text before the tune
+        }
+        started = true;
+        if raw_line.trim().is_empty() {
+            if parser.in_body && parser.has_music() {
+                break; // a blank line ends the tune
+            }
+            continue;
+        }
+        if raw_line.starts_with("%%") || line.trim().is_empty() {
+            continue;
+        }
+
+        let bytes = line.as_bytes();
+        if bytes.len() >= 2 && bytes[1] == b':' && bytes[0].is_ascii_alphabetic() {
+            parser.field(bytes[0] as char, line[2..].trim());
+        } else if parser.in_body {
+            parser.music_line(line);
+        }
+    }
+
+    if !parser.in_body {
+        return Err("ABC tune has no K: field".to_string());
+    }
+    Ok(parser.finish())
+}
+
+// ─── Parser state ────────────────────────────────────────────────────
+
+/// A measure being built, with positions as fractions of a whole note.
+#[derive(Default)]
+struct RawMeasure {
+    notes: Vec<(Note, Frac, Frac)>,
+    harmonies: Vec<(Harmony, Frac)>,
+    directions: Vec<(Direction, Frac)>,
+    barlines: Vec<Barline>,
+    attributes: Option<Attributes>,
+    length: Frac,
+    new_system: bool,
+}
+
+impl RawMeasure {
+    fn barline_mut(&mut self, location: &str) -> &mut Barline {
+        if let Some(i) = self.barlines.iter().position(|b| b.location == location) {
+            return &mut self.barlines[i];
+        }
+        self.barlines.push(Barline {
+            location: location.to_string(),
+            bar_style: None,
+            repeat: None,
+            ending: None,
+        });
+        self.barlines.last_mut().unwrap()
+    }
+
+    fn attributes_mut(&mut self) -> &mut Attributes {
+        self.attributes.get_or_insert_with(|| Attributes {
+            divisions: None,
+            key: None,
+            time: None,
+            clefs: Vec::new(),
+            transpose: None,
+            staves: None,
+            staff_details: Vec::new(),
+        })
+    }
+}
+
+/// A pitch as written: letter, octave and explicit accidental.
+struct WrittenPitch {
+    step: char,
+    octave: i32,
+    accidental: Option<i32>,
+}
+
+/// Notation waiting for the next note.
+#[derive(Default)]
+struct PendingMarks {
+    articulations: Vec<String>,
+    ornaments: Vec<String>,
+    fermata: Option<String>,
+    slurs: usize,
+}
+
+/// Tuplet in progress: p notes in the time of q, for r more notes.
+struct TupletState {
+    p: i32,
+    q: i32,
+    remaining: i32,
+    started: bool,
+}
+
+/// One ABC voice; becomes a part.
+struct Voice {
+    id: String,
+    name: Option<String>,
+    measures: Vec<RawMeasure>,
+    current: RawMeasure,
+    next_attributes: Option<Attributes>,
+    key: Key,
+    meter: Option<(i32, i32)>,
+    unit: Frac,
+    cursor: Frac,
+    main_length: Frac,
+    overlay: i32,
+    accidentals: HashMap<(char, i32), i32>,
+    ties: Vec<Pitch>,
+    last_event: Option<std::ops::Range<usize>>,
+    marks: PendingMarks,
+    open_slurs: i32,
+    tuplet: Option<TupletState>,
+    broken: Option<Frac>,
+    broken_next: Option<Frac>,
+    grace: Option<bool>,
+    beam_run: Vec<usize>,
+    open_ending: bool,
+    /// Notes of the latest music line that take lyrics: (measure, note)
+    line_notes: Vec<(usize, usize)>,
+}
+
+impl Voice {
+    fn new(id: &str, key: Key, meter: Option<(i32, i32)>, unit: Frac, clef: Clef) -> Self {
+        let mut current = RawMeasure::default();
+        let attrs = current.attributes_mut();
+        attrs.key = Some(key.clone());
+        attrs.time = meter.map(|(beats, beat_type)| TimeSignature { beats, beat_type });
+        attrs.clefs = vec![clef];
+        Voice {
+            id: id.to_string(),
+            name: None,
+            measures: Vec::new(),
+            current,
+            next_attributes: None,
+            key,
+            meter,
+            unit,
+            cursor: Frac::ZERO,
+            main_length: Frac::ZERO,
+            overlay: 1,
+            accidentals: HashMap::new(),
+            ties: Vec::new(),
+            last_event: None,
+            marks: PendingMarks::default(),
+            open_slurs: 0,
+            tuplet: None,
+            broken: None,
+            broken_next: None,
+            grace: None,
+            beam_run: Vec::new(),
+            open_ending: false,
+            line_notes: Vec::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.measures.is_empty() && self.current.notes.is_empty()
+    }
+
+    /// Attributes for a change at the current position: this measure if
+    /// nothing has been played in it yet, otherwise the next one.
+    fn change_attributes(&mut self) -> &mut Attributes {
+        if self.current.notes.is_empty() {
+            self.current.attributes_mut()
+        } else {
+            self.next_attributes.get_or_insert_with(|| Attributes {
+                divisions: None,
+                key: None,
+                time: None,
+                clefs: Vec::new(),
+                transpose: None,
+                staves: None,
+                staff_details: Vec::new(),
+            })
+        }
+    }
+
+    // ── Notes ──
+
+    /// Add a note, chord or rest of `length` units at the cursor.
+    fn add_event(&mut self, pitches: &[WrittenPitch], length: Frac, rest: bool) {
+        let grace = self.grace;
+        let mut written = self.unit.mul(length);
+        if grace.is_none() {
+            if let Some(factor) = self.broken.take() {
+                written = written.mul(factor);
+            }
+            self.broken = self.broken_next.take();
+        }
+        let tuplet = match (&self.tuplet, grace) {
+            (Some(t), None) => Some((t.p, t.q)),
+            _ => None,
+        };
+        let ratio = tuplet.map_or(Frac::new(1, 1), |(p, q)| Frac::new(q as i64, p as i64));
+
+        // Pitches, with accidentals carried through the measure and ties
+        let resolved: Vec<(Pitch, Option<String>, bool)> = pitches.iter()
+            .map(|wp| {
+                let step = wp.step.to_string();
+                let tied = self.ties.iter().find(|p| p.step == step && p.octave == wp.octave).cloned();
+                let alter = match wp.accidental {
+                    Some(a) => {
+                        self.accidentals.insert((wp.step, wp.octave), a);
+                        a
+                    }
+                    None => match &tied {
+                        Some(p) if grace.is_none() => p.alter.unwrap_or(0.0) as i32,
+                        _ => self.accidentals.get(&(wp.step, wp.octave)).copied()
+                            .unwrap_or_else(|| key_alter(self.key.fifths, wp.step)),
+                    },
+                };
+                let pitch = Pitch { step, octave: wp.octave, alter: (alter != 0).then_some(alter as f64) };
+                (pitch, wp.accidental.map(accidental_name), tied.is_some() && grace.is_none())
+            })
+            .collect();
+        if grace.is_none() {
+            self.ties.clear();
+        }
+
+        let pieces = split_length(written);
+        let first_index = self.current.notes.len();
+        let mut last_piece_start = first_index;
+        let piece_count = pieces.len();
+        for (pi, &(piece, note_type, dot)) in pieces.iter().enumerate() {
+            last_piece_start = self.current.notes.len();
+            let duration = if grace.is_some() { Frac::ZERO } else { piece.mul(ratio) };
+            let members: Vec<Option<&(Pitch, Option<String>, bool)>> =
+                if rest { vec![None] } else { resolved.iter().map(Some).collect() };
+            for (mi, member) in members.into_iter().enumerate() {
+                let mut note = blank_note(self.overlay);
+                note.rest = rest;
+                note.note_type = Some(note_type.to_string());
+                note.dot = dot;
+                note.chord = mi > 0;
+                if let Some(is_slash) = grace {
+                    note.grace = true;
+                    note.grace_slash = is_slash;
+                }
+                if let Some((pitch, accidental, tied_in)) = member {
+                    note.pitch = Some(pitch.clone());
+                    note.accidental = if pi == 0 { accidental.clone() } else { None };
+                    note.tie_stop = if pi == 0 { *tied_in } else { true };
+                    note.tie_start = pi + 1 < piece_count;
+                }
+                if let Some((p, q)) = tuplet {
+                    note.time_modification = Some(TimeModification {
+                        actual_notes: p,
+                        normal_notes: q,
+                        normal_type: None,
+                    });
+                }
+                self.current.notes.push((note, self.cursor, duration));
+            }
+            self.cursor = self.cursor.add(duration);
+        }
+
+        // Marks go on the first note, tuplet brackets on the first and last
+        let marks = std::mem::take(&mut self.marks);
+        let first = &mut self.current.notes[first_index].0;
+        first.articulations = marks.articulations;
+        first.ornaments = marks.ornaments;
+        first.fermata = marks.fermata;
+        for _ in 0..marks.slurs {
+            self.open_slurs += 1;
+            first.slurs.push(SlurEvent { slur_type: "start".to_string(), number: self.open_slurs, placement: None });
+        }
+        if tuplet.is_some() {
+            let t = self.tuplet.as_mut().unwrap();
+            if !t.started {
+                t.started = true;
+                first.tuplets.push(tuplet_event("start"));
+            }
+            t.remaining -= 1;
+            if t.remaining <= 0 {
+                self.current.notes[last_piece_start].0.tuplets.push(tuplet_event("stop"));
+                self.tuplet = None;
+            }
+        }
+
+        // Lyrics go on the first note of each sounded note (not rests,
+        // grace notes, or notes continuing a tie)
+        let first = &self.current.notes[first_index].0;
+        if !rest && grace.is_none() && !first.tie_stop && self.overlay == 1 {
+            self.line_notes.push((self.measures.len(), first_index));
+        }
+
+        // Beaming: runs of eighths and shorter written without spaces
+        if grace.is_none() {
+            for idx in first_index..self.current.notes.len() {
+                let note = &self.current.notes[idx].0;
+                if note.chord {
+                    continue;
+                }
+                let beamable = !note.rest
+                    && matches!(note.note_type.as_deref(), Some("eighth" | "16th" | "32nd" | "64th" | "128th"));
+                if beamable {
+                    self.beam_run.push(idx);
+                } else {
+                    self.flush_beams();
+                }
+            }
+        }
+        self.last_event = Some(last_piece_start..self.current.notes.len());
+    }
+
+    /// Whole-measure rests for `Z`/`X`, `count` measures long.
+    fn add_measure_rests(&mut self, count: usize) {
+        let length = self.meter.map_or(Frac::new(1, 1), |(b, t)| Frac::new(b as i64, t as i64));
+        for i in 0..count.max(1) {
+            if i > 0 {
+                self.finish_measure();
+            }
+            let mut note = blank_note(self.overlay);
+            note.rest = true;
+            note.measure_rest = true;
+            self.current.notes.push((note, self.cursor, length));
+            self.cursor = self.cursor.add(length);
+        }
+        self.flush_beams();
+        self.marks = PendingMarks::default();
+        self.last_event = None;
+    }
+
+    /// Whole-measure rests after the voice's last measure, one for each
+    /// length in `lengths` past the measures it has.
+    fn pad_to(&mut self, lengths: &[Frac]) {
+        for &length in lengths.iter().skip(self.measures.len()) {
+            let mut note = blank_note(1);
+            note.rest = true;
+            note.measure_rest = true;
+            self.current.notes.push((note, Frac::ZERO, length));
+            self.cursor = length;
+            self.finish_measure();
+        }
+    }
+
+    fn tie_last(&mut self) {
+        let Some(range) = self.last_event.clone() else { return };
+        for (note, _, _) in &mut self.current.notes[range] {
+            if let Some(pitch) = &note.pitch {
+                note.tie_start = true;
+                self.ties.push(pitch.clone());
+            }
+        }
+    }
+
+    fn close_slur(&mut self) {
+        let Some(range) = self.last_event.clone() else { return };
+        if self.open_slurs > 0 {
+            let note = &mut self.current.notes[range.start].0;
+            note.slurs.push(SlurEvent { slur_type: "stop".to_string(), number: self.open_slurs, placement: None });
+            self.open_slurs -= 1;
+        }
+    }
+
+    fn flush_beams(&mut self) {
+        let run = std::mem::take(&mut self.beam_run);
+        if run.len() < 2 {
+            return;
+        }
+        for (k, &idx) in run.iter().enumerate() {
+            let beam_type = beam_position(k, run.len());
+            self.current.notes[idx].0.beams.push(Beam { number: 1, beam_type: beam_type.to_string() });
+        }
+        // Secondary beams over runs of sixteenths and shorter
+        let short = |n: &Note| n.note_type.as_deref() != Some("eighth");
+        let mut k = 0;
+        while k < run.len() {
+            let mut r = k;
+            while r < run.len() && short(&self.current.notes[run[r]].0) {
+                r += 1;
+            }
+            for (m, &idx) in run[k..r].iter().enumerate() {
+                if r - k >= 2 {
+                    let beam_type = beam_position(m, r - k);
+                    self.current.notes[idx].0.beams.push(Beam { number: 2, beam_type: beam_type.to_string() });
+                }
+            }
+            k = r.max(k + 1);
+        }
+    }
+
+    // ── Barlines ──
+
+    /// Handle a bar token such as `|`, `||`, `|]`, `|:`, `:|`, `::`,
+    /// optionally followed by an ending number.
+    fn bar(&mut self, token: &str, ending: Option<String>) {
+        self.flush_beams();
+        let back = token.starts_with(':');
+        let forward = token.ends_with(':');
+        let style = match token {
+            "||" => Some("light-light"),
+            "|]" => Some("light-heavy"),
+            _ => None,
+        };
+        let closes_ending = back || forward || style.is_some() || ending.is_some();
+
+        if self.current.notes.is_empty() {
+            // A bar before any music: close the previous measure's repeat
+            // or ending; the rest applies to the measure about to start
+            if let Some(prev) = self.measures.last_mut() {
+                if back {
+                    let b = prev.barline_mut("right");
+                    b.bar_style = Some("light-heavy".to_string());
+                    b.repeat = Some(Repeat { direction: "backward".to_string() });
+                } else if let Some(style) = style {
+                    prev.barline_mut("right").bar_style = Some(style.to_string());
+                }
+                if self.open_ending && closes_ending {
+                    close_ending(prev);
+                    self.open_ending = false;
+                }
+            }
+        } else {
+            if back {
+                let b = self.current.barline_mut("right");
+                b.bar_style = Some("light-heavy".to_string());
+                b.repeat = Some(Repeat { direction: "backward".to_string() });
+            } else if let Some(style) = style {
+                self.current.barline_mut("right").bar_style = Some(style.to_string());
+            }
+            if self.open_ending && closes_ending {
+                close_ending(&mut self.current);
+                self.open_ending = false;
+            }
+            self.finish_measure();
+        }
+
+        if forward {
+            let b = self.current.barline_mut("left");
+            b.bar_style = Some("heavy-light".to_string());
+            b.repeat = Some(Repeat { direction: "forward".to_string() });
+        } else if token == "[|" {
+            self.current.barline_mut("left").bar_style = Some("heavy-light".to_string());
+        }
+        if let Some(number) = ending {
+            self.start_ending(&number);
+        }
+    }
+
+    fn start_ending(&mut self, number: &str) {
+        if self.open_ending {
+            if let Some(prev) = self.measures.last_mut() {
+                close_ending(prev);
+            }
+        }
+        let number = number.split(',').map(str::trim).collect::<Vec<_>>().join(", ");
+        self.current.barline_mut("left").ending = Some(Ending {
+            number,
+            ending_type: "start".to_string(),
+            text: None,
+        });
+        self.open_ending = true;
+    }
+
+    fn overlay(&mut self) {
+        self.flush_beams();
+        if self.overlay == 1 {
+            self.main_length = self.cursor;
+        }
+        self.overlay += 1;
+        self.cursor = Frac::ZERO;
+        self.last_event = None;
+    }
+
+    fn finish_measure(&mut self) {
+        self.flush_beams();
+        let mut measure = std::mem::take(&mut self.current);
+        measure.length = if self.overlay > 1 { self.main_length } else { self.cursor };
+        self.measures.push(measure);
+        self.current.attributes = self.next_attributes.take();
+        self.cursor = Frac::ZERO;
+        self.overlay = 1;
+        self.accidentals.clear();
+        self.last_event = None;
+    }
+
+    // ── Lyrics ──
+
+    /// Align a `w:` line with the notes of the latest music line.
+    fn lyrics(&mut self, text: &str, verse: i32) {
+        let mut slot = 0;
+        let mut prev_hyphen = false;
+        for token in lyric_tokens(text) {
+            match token {
+                LyricToken::Syllable(syllable, hyphen) => {
+                    let Some(&(m, n)) = self.line_notes.get(slot) else { break };
+                    let syllabic = match (prev_hyphen, hyphen) {
+                        (false, false) => "single",
+                        (false, true) => "begin",
+                        (true, true) => "middle",
+                        (true, false) => "end",
+                    };
+                    prev_hyphen = hyphen;
+                    let note = self.note_at(m, n);
+                    note.lyrics.push(Lyric { number: verse, text: syllable, syllabic: Some(syllabic.to_string()) });
+                    slot += 1;
+                }
+                LyricToken::Skip => slot += 1,
+                LyricToken::Bar if slot > 0 => {
+                    // Move on to the first note of the next measure
+                    let measure = self.line_notes[slot - 1].0;
+                    while self.line_notes.get(slot).is_some_and(|&(m, _)| m == measure) {
+                        slot += 1;
+                    }
+                }
+                LyricToken::Bar => {}
+            }
+        }
+    }
+
+    fn note_at(&mut self, measure: usize, note: usize) -> &mut Note {
+        match self.measures.get_mut(measure) {
+            Some(m) => &mut m.notes[note].0,
+            None => &mut self.current.notes[note].0,
+        }
+    }
+}
+
+fn close_ending(measure: &mut RawMeasure) {
+    let number = measure.barlines.iter()
+        .find_map(|b| b.ending.as_ref().map(|e| e.number.clone()));
+    let b = measure.barline_mut("right");
+    b.ending = Some(Ending {
+        number: number.unwrap_or_else(|| "1".to_string()),
+        ending_type: "stop".to_string(),
+        text: None,
+    });
+}
+
+// ─── Tune parser ─────────────────────────────────────────────────────
+
+struct AbcParser {
+    in_body: bool,
+    titles: Vec<String>,
+    composer: Option<String>,
+    key: Key,
+    clef: Option<Clef>,
+    meter: Option<(i32, i32)>,
+    unit: Option<Frac>,
+    tempo: Option<Direction>,
+    voices: Vec<Voice>,
+    current: usize,
+    verse: i32,
+    /// Broken-rhythm factors for the note just read and the next one
+    broken: Option<(Frac, Frac)>,
+}
+
+impl AbcParser {
+    fn new() -> Self {
+        AbcParser {
+            in_body: false,
+            titles: Vec::new(),
+            composer: None,
+            key: Key { fifths: 0, mode: Some("major".to_string()) },
+            clef: None,
+            meter: Some((4, 4)),
+            unit: None,
+            tempo: None,
+            voices: Vec::new(),
+            current: 0,
+            verse: 0,
+            broken: None,
+        }
+    }
+
+    fn has_music(&self) -> bool {
+        self.voices.iter().any(|v| !v.is_empty())
+    }
+
+    /// The default unit length: 1/16 for meters under 3/4, else 1/8.
+    fn unit(&self) -> Frac {
+        self.unit.unwrap_or(match self.meter {
+            Some((b, t)) if (b as f64 / t as f64) < 0.75 => Frac::new(1, 16),
+            _ => Frac::new(1, 8),
+        })
+    }
+
+    fn voice(&mut self) -> &mut Voice {
+        if self.voices.is_empty() {
+            let clef = self.clef.clone().unwrap_or_else(treble_clef);
+            self.voices.push(Voice::new("", self.key.clone(), self.meter, self.unit(), clef));
+            self.current = 0;
+        }
+        &mut self.voices[self.current]
+    }
+
+    fn field(&mut self, letter: char, value: &str) {
+        match letter {
+            'T' if !self.in_body => self.titles.push(value.to_string()),
+            'C' if !self.in_body => self.composer = Some(value.to_string()),
+            'M' => {
+                let meter = parse_meter(value);
+                if self.in_body {
+                    let v = self.voice();
+                    v.meter = meter;
+                    if let Some((beats, beat_type)) = meter {
+                        v.change_attributes().time = Some(TimeSignature { beats, beat_type });
+                    }
+                } else {
+                    self.meter = meter;
+                }
+            }
+            'L' => {
+                if let Some(unit) = parse_fraction(value) {
+                    if self.in_body {
+                        self.voice().unit = unit;
+                    } else {
+                        self.unit = Some(unit);
+                    }
+                }
+            }
+            'Q' => {
+                let unit = if self.in_body { self.voice().unit } else { self.unit() };
+                if let Some(direction) = parse_tempo(value, unit) {
+                    if self.in_body {
+                        let v = self.voice();
+                        v.current.directions.push((direction, v.cursor));
+                    } else {
+                        self.tempo = Some(direction);
+                    }
+                }
+            }
+            'K' => {
+                let (key, clef) = parse_key(value);
+                if self.in_body {
+                    let v = self.voice();
+                    v.key = key.clone();
+                    let attrs = v.change_attributes();
+                    attrs.key = Some(key);
+                    if let Some(clef) = clef {
+                        attrs.clefs = vec![clef];
+                    }
+                } else {
+                    // The header is complete: voices declared so far take the key
+                    self.in_body = true;
+                    self.key = key.clone();
+                    if clef.is_some() {
+                        self.clef = clef.clone();
+                    }
+                    let (meter, unit) = (self.meter, self.unit());
+                    for v in &mut self.voices {
+                        v.key = key.clone();
+                        v.meter = meter;
+                        v.unit = unit;
+                        let attrs = v.current.attributes_mut();
+                        attrs.key = Some(key.clone());
+                        attrs.time = meter.map(|(beats, beat_type)| TimeSignature { beats, beat_type });
+                        // A clef from V: takes precedence
+                        if let Some(clef) = clef.as_ref().filter(|_| attrs.clefs.iter().all(|c| c.sign == "G")) {
+                            attrs.clefs = vec![clef.clone()];
+                        }
+                    }
+                }
+            }
+            'V' => self.select_voice(value),
+            'w' if self.in_body => {
+                self.verse += 1;
+                let verse = self.verse;
+                self.voice().lyrics(value, verse);
+            }
+            _ => {}
+        }
+    }
+
+    /// Switch to (or declare) a voice: `V:id name="..." clef=bass`.
+    fn select_voice(&mut self, value: &str) {
+        let id = value.split_whitespace().next().unwrap_or("").to_string();
+        let name = quoted_property(value, "name").or_else(|| quoted_property(value, "nm"));
+        let clef = value.split_whitespace().skip(1).find_map(parse_clef);
+
+        let existing = self.voices.iter().position(|v| v.id == id);
+        let index = match existing {
+            Some(i) => i,
+            // Music before the first V: belongs to the first voice
+            None if self.voices.len() == 1 && self.voices[0].id.is_empty() && self.voices[0].is_empty() => {
+                self.voices[0].id = id;
+                0
+            }
+            None => {
+                let clef = self.clef.clone().unwrap_or_else(treble_clef);
+                self.voices.push(Voice::new(&id, self.key.clone(), self.meter, self.unit(), clef));
+                self.voices.len() - 1
+            }
+        };
+        self.current = index;
+        let v = &mut self.voices[index];
+        if name.is_some() {
+            v.name = name;
+        }
+        if let Some(clef) = clef {
+            v.change_attributes().clefs = vec![clef];
+        }
+    }
+
+    fn music_line(&mut self, line: &str) {
+        let chars: Vec<char> = line.chars().collect();
+        self.verse = 0;
+        let v = self.voice();
+        v.line_notes.clear();
+        // A line of music that starts with a new measure starts a new system
+        if v.current.notes.is_empty() && !v.measures.is_empty() {
+            v.current.new_system = true;
+        }
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            match c {
+                ' ' | '\t' => {
+                    self.voice().flush_beams();
+                    i += 1;
+                }
+                '"' => {
+                    let end = find_from(&chars, i + 1, '"');
+                    let text: String = chars[i + 1..end].iter().collect();
+                    self.annotation(&text);
+                    i = end + 1;
+                }
+                '!' | '+' => {
+                    let end = find_from(&chars, i + 1, c);
+                    let name: String = chars[i + 1..end].iter().collect();
+                    self.decoration(&name);
+                    i = end + 1;
+                }
+                '.' | '~' | 'H' | 'L' | 'M' | 'O' | 'P' | 'S' | 'T' | 'u' | 'v' => {
+                    let name = match c {
+                        '.' => "staccato",
+                        '~' => "roll",
+                        'H' => "fermata",
+                        'L' => "accent",
+                        'M' => "lowermordent",
+                        'O' => "coda",
+                        'P' => "uppermordent",
+                        'S' => "segno",
+                        'T' => "trill",
+                        _ => "",
+                    };
+                    self.decoration(name);
+                    i += 1;
+                }
+                '{' => {
+                    let slash = chars.get(i + 1) == Some(&'/');
+                    self.voice().grace = Some(slash);
+                    i += if slash { 2 } else { 1 };
+                }
+                '}' => {
+                    self.voice().grace = None;
+                    i += 1;
+                }
+                '(' => {
+                    if chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()) {
+                        i = self.tuplet(&chars, i + 1);
+                    } else {
+                        self.voice().marks.slurs += 1;
+                        i += 1;
+                    }
+                }
+                ')' => {
+                    self.voice().close_slur();
+                    i += 1;
+                }
+                '-' => {
+                    self.voice().tie_last();
+                    i += 1;
+                }
+                '&' => {
+                    self.voice().overlay();
+                    i += 1;
+                }
+                '[' => {
+                    let next = chars.get(i + 1).copied().unwrap_or(' ');
+                    if next.is_ascii_alphabetic() && chars.get(i + 2) == Some(&':') {
+                        let end = find_from(&chars, i + 1, ']');
+                        let field: String = chars[i + 3..end].iter().collect();
+                        self.field(next, field.trim());
+                        i = end + 1;
+                    } else if next.is_ascii_digit() {
+                        let (number, end) = read_ending(&chars, i + 1);
+                        self.voice().bar("", Some(number));
+                        i = end;
+                    } else if next == '|' {
+                        i = self.bar(&chars, i);
+                    } else {
+                        i = self.chord(&chars, i + 1);
+                    }
+                }
+                '|' | ':' => {
+                    i = self.bar(&chars, i);
+                }
+                'A'..='G' | 'a'..='g' | '^' | '_' | '=' => {
+                    match read_pitch(&chars, i) {
+                        Some((pitch, end)) => {
+                            let (length, end) = read_length(&chars, end);
+                            let end = self.broken_rhythm(&chars, end);
+                            let length = self.take_own_factor(length);
+                            self.voice().add_event(&[pitch], length, false);
+                            i = end;
+                        }
+                        None => i += 1,
+                    }
+                }
+                'z' | 'x' => {
+                    let (length, end) = read_length(&chars, i + 1);
+                    let end = self.broken_rhythm(&chars, end);
+                    let length = self.take_own_factor(length);
+                    let v = self.voice();
+                    if c == 'z' {
+                        v.add_event(&[], length, true);
+                    } else {
+                        // Invisible rest: just move the cursor
+                        v.flush_beams();
+                        let written = v.unit.mul(length);
+                        v.cursor = v.cursor.add(written.mul(v.broken.take().unwrap_or(Frac::new(1, 1))));
+                        v.broken = v.broken_next.take();
+                    }
+                    i = end;
+                }
+                'Z' | 'X' => {
+                    let mut end = i + 1;
+                    while end < chars.len() && chars[end].is_ascii_digit() {
+                        end += 1;
+                    }
+                    let count: String = chars[i + 1..end].iter().collect();
+                    self.voice().add_measure_rests(count.parse().unwrap_or(1));
+                    i = end;
+                }
+                _ => i += 1,
+            }
+        }
+    }
+
+    /// Broken rhythm after a note (`>`, `<`, `>>`…): returns the index
+    /// after the operator and stores the factors for this and the next note.
+    fn broken_rhythm(&mut self, chars: &[char], mut i: usize) -> usize {
+        let Some(&op) = chars.get(i).filter(|c| **c == '>' || **c == '<') else { return i };
+        let mut count = 0;
+        while chars.get(i) == Some(&op) {
+            count += 1;
+            i += 1;
+        }
+        // > : 3/2 then 1/2; >> : 7/4 then 1/4; >>> : 15/8 then 1/8
+        let short = Frac::new(1, 1 << count);
+        let long = Frac::new(2, 1).sub(short);
+        let (this, next) = if op == '>' { (long, short) } else { (short, long) };
+        self.broken = Some((this, next));
+        i
+    }
+
+    /// Apply a broken rhythm to the note just read, and keep its partner's
+    /// factor for the next note.
+    fn take_own_factor(&mut self, length: Frac) -> Frac {
+        match self.broken.take() {
+            Some((this, next)) => {
+                self.voice().broken_next = Some(next);
+                length.mul(this)
+            }
+            None => length,
+        }
+    }
+
+    /// A chord `[CEG]` with an optional length; returns the index after it.
+    fn chord(&mut self, chars: &[char], mut i: usize) -> usize {
+        let mut pitches = Vec::new();
+        let mut inner = Frac::new(1, 1);
+        let mut tied = false;
+        while i < chars.len() && chars[i] != ']' {
+            match read_pitch(chars, i) {
+                Some((pitch, end)) => {
+                    let (length, end) = read_length(chars, end);
+                    if pitches.is_empty() {
+                        inner = length;
+                    }
+                    pitches.push(pitch);
+                    i = end;
+                }
+                None => {
+                    tied |= chars[i] == '-';
+                    i += 1;
+                }
+            }
+        }
+        let (length, end) = read_length(chars, (i + 1).min(chars.len()));
+        let end = self.broken_rhythm(chars, end);
+        let length = self.take_own_factor(length.mul(inner));
+        if !pitches.is_empty() {
+            let v = self.voice();
+            v.add_event(&pitches, length, false);
+            if tied {
+                v.tie_last();
+            }
+        }
+        end
+    }
+
+    /// A tuplet `(p:q:r`; returns the index after it.
+    fn tuplet(&mut self, chars: &[char], mut i: usize) -> usize {
+        let mut numbers: Vec<Option<i32>> = Vec::new();
+        loop {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let digits: String = chars[start..i].iter().collect();
+            numbers.push(digits.parse().ok());
+            if chars.get(i) == Some(&':') && numbers.len() < 3 {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+        let p = numbers[0].unwrap_or(3).max(1);
+        let v = self.voice();
+        let compound = v.meter.is_some_and(|(b, _)| b % 3 == 0 && b > 3);
+        let default_q = match p {
+            2 | 4 | 8 => 3,
+            3 | 6 => 2,
+            _ if compound => 3,
+            _ => 2,
+        };
+        let q = numbers.get(1).copied().flatten().unwrap_or(default_q);
+        let r = numbers.get(2).copied().flatten().unwrap_or(p);
+        v.tuplet = Some(TupletState { p, q, remaining: r, started: false });
+        i
+    }
+
+    /// A bar token starting at `i`; returns the index after it.
+    fn bar(&mut self, chars: &[char], mut i: usize) -> usize {
+        let start = i;
+        while i < chars.len() {
+            let c = chars[i];
+            if c == '|' || c == ':' || (c == ']' && i > start) || (c == '[' && chars.get(i + 1) == Some(&'|')) {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+        let token: String = chars[start..i].iter().collect();
+        // An ending may follow directly (`|1`, `:|2`) or as `[2`
+        let mut ending = None;
+        if chars.get(i).is_some_and(|c| c.is_ascii_digit()) {
+            let (number, end) = read_ending(chars, i);
+            ending = Some(number);
+            i = end;
+        } else if chars.get(i) == Some(&'[') && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()) {
+            let (number, end) = read_ending(chars, i + 1);
+            ending = Some(number);
+            i = end;
+        }
+        self.voice().bar(&token, ending);
+        i
+    }
+
+    /// Text in double quotes: a chord symbol, or an annotation when it
+    /// starts with a placement character (`^ _ < > @`).
+    fn annotation(&mut self, text: &str) {
+        let v = self.voice();
+        let placement = match text.chars().next() {
+            Some('^') => Some("above"),
+            Some('_') => Some("below"),
+            Some('<' | '>' | '@') => Some("above"),
+            _ => None,
+        };
+        if placement.is_none() {
+            if let Some(harmony) = parse_chord_symbol(text) {
+                v.current.harmonies.push((harmony, v.cursor));
+                return;
+            }
+        }
+        let words = if placement.is_some() { &text[1..] } else { text };
+        if words.trim().is_empty() {
+            return;
+        }
+        let mut direction = blank_direction(placement.unwrap_or("above"));
+        direction.words = Some(words.trim().to_string());
+        v.current.directions.push((direction, v.cursor));
+    }
+
+    fn decoration(&mut self, name: &str) {
+        let v = self.voice();
+        let articulation = match name {
+            "staccato" => Some("staccato"),
+            "tenuto" => Some("tenuto"),
+            "accent" | ">" | "emphasis" => Some("accent"),
+            "marcato" | "^" => Some("strong-accent"),
+            "wedge" | "staccatissimo" => Some("staccatissimo"),
+            _ => None,
+        };
+        if let Some(a) = articulation {
+            v.marks.articulations.push(a.to_string());
+            return;
+        }
+        let ornament = match name {
+            "trill" => Some("trill-mark"),
+            "lowermordent" | "mordent" => Some("mordent"),
+            "uppermordent" | "pralltriller" => Some("inverted-mordent"),
+            "roll" | "turn" => Some("turn"),
+            _ => None,
+        };
+        if let Some(o) = ornament {
+            v.marks.ornaments.push(o.to_string());
+            return;
+        }
+        match name {
+            "fermata" => v.marks.fermata = Some("upright".to_string()),
+            "invertedfermata" => v.marks.fermata = Some("inverted".to_string()),
+            _ => {}
+        }
+
+        let mut d = blank_direction("above");
+        match name {
+            "p" | "pp" | "ppp" | "pppp" | "mp" | "mf" | "f" | "ff" | "fff" | "ffff" | "sfz" | "sf" | "fp" => {
+                d.placement = Some("below".to_string());
+                d.dynamics = Some(name.to_string());
+            }
+            "segno" => d.segno = true,
+            "coda" => d.coda = true,
+            "D.C." => {
+                d.words = Some("D.C.".to_string());
+                d.sound_dacapo = true;
+            }
+            "D.S." => {
+                d.words = Some("D.S.".to_string());
+                d.sound_dalsegno = true;
+            }
+            "fine" => {
+                d.words = Some("Fine".to_string());
+                d.sound_fine = true;
+            }
+            "dacoda" => {
+                d.words = Some("To Coda".to_string());
+                d.sound_tocoda = true;
+            }
+            "crescendo(" | "<(" | "diminuendo(" | ">(" => {
+                d.placement = Some("below".to_string());
+                d.wedge_type = Some(if name.starts_with('c') || name.starts_with('<') {
+                    "crescendo"
+                } else {
+                    "diminuendo"
+                }.to_string());
+                d.wedge_number = 1;
+            }
+            "crescendo)" | "<)" | "diminuendo)" | ">)" => {
+                d.placement = Some("below".to_string());
+                d.wedge_type = Some("stop".to_string());
+                d.wedge_number = 1;
+            }
+            _ => return,
+        }
+        v.current.directions.push((d, v.cursor));
+    }
+
+    fn finish(mut self) -> Score {
+        let header_tempo = self.tempo.take();
+        let voices = std::mem::take(&mut self.voices);
+        let mut voices: Vec<Voice> = voices.into_iter()
+            .map(|mut v| {
+                if !v.current.notes.is_empty() {
+                    v.finish_measure();
+                } else if let Some(prev) = v.measures.last_mut() {
+                    // Directions after the final bar belong at its end
+                    let end = prev.length;
+                    prev.directions.extend(v.current.directions.drain(..).map(|(d, _)| (d, end)));
+                }
+                if v.open_ending {
+                    if let Some(last) = v.measures.last_mut() {
+                        close_ending(last);
+                    }
+                }
+                v
+            })
+            .filter(|v| !v.measures.is_empty())
+            .collect();
+
+        // Parts all have the same measures: rests fill out shorter voices
+        let lengths: Vec<Frac> = voices.iter()
+            .max_by_key(|v| v.measures.len())
+            .map(|v| v.measures.iter().map(|m| m.length).collect())
+            .unwrap_or_default();
+        for v in &mut voices {
+            v.pad_to(&lengths);
+        }
+
+        // Divisions: fine enough for every position in the tune
+        let mut divisions: i64 = 1;
+        for v in &voices {
+            for m in &v.measures {
+                let fracs = m.notes.iter().flat_map(|(_, a, b)| [*a, *b])
+                    .chain(m.harmonies.iter().map(|h| h.1))
+                    .chain(m.directions.iter().map(|d| d.1));
+                for f in fracs {
+                    let den = Frac::new(f.num * 4, f.den).den;
+                    divisions = divisions / gcd(divisions, den) * den;
+                }
+            }
+        }
+
+        let mut score = Score::new();
+        let mut titles = self.titles.into_iter();
+        score.title = titles.next();
+        score.subtitle = titles.next();
+        score.composer = self.composer;
+
+        let single = voices.len() == 1;
+        for (idx, v) in voices.iter_mut().enumerate() {
+            let name = v.name.clone().unwrap_or_else(|| {
+                if single || v.id.is_empty() { "Melody".to_string() } else { format!("Voice {}", v.id) }
+            });
+            let mut part = Part {
+                id: format!("P{}", idx + 1),
+                name,
+                abbreviation: None,
+                midi_program: None,
+                midi_channel: None,
+                measures: Vec::new(),
+            };
+            let meter = v.measures[0].attributes.as_ref()
+                .and_then(|a| a.time.as_ref())
+                .map(|t| Frac::new(t.beats as i64, t.beat_type as i64));
+            let pickup = meter.is_some_and(|len| v.measures[0].length.lt(len) && v.measures.len() > 1);
+
+            for (midx, raw) in v.measures.drain(..).enumerate() {
+                let number = if pickup { midx as i32 } else { midx as i32 + 1 };
+                let mut measure = Measure {
+                    number,
+                    implicit: pickup && midx == 0,
+                    width: None,
+                    attributes: raw.attributes,
+                    notes: raw.notes.into_iter()
+                        .map(|(mut note, onset, duration)| {
+                            note.onset = onset.to_divisions(divisions);
+                            note.duration = duration.to_divisions(divisions);
+                            note
+                        })
+                        .collect(),
+                    harmonies: raw.harmonies.into_iter()
+                        .map(|(mut h, onset)| {
+                            h.onset = onset.to_divisions(divisions);
+                            h
+                        })
+                        .collect(),
+                    barlines: raw.barlines,
+                    directions: raw.directions.into_iter()
+                        .map(|(mut d, onset)| {
+                            d.onset = onset.to_divisions(divisions);
+                            d
+                        })
+                        .collect(),
+                    new_system: raw.new_system,
+                    new_page: false,
+                };
+                measure.barlines.sort_by_key(|b| b.location != "left");
+                if midx == 0 {
+                    measure.attributes.get_or_insert_with(|| Attributes {
+                        divisions: None,
+                        key: None,
+                        time: None,
+                        clefs: Vec::new(),
+                        transpose: None,
+                        staves: None,
+                        staff_details: Vec::new(),
+                    }).divisions = Some(divisions as i32);
+                    if idx == 0 {
+                        if let Some(tempo) = &header_tempo {
+                            measure.directions.insert(0, tempo.clone());
+                        }
+                    }
+                }
+                part.measures.push(measure);
+            }
+            score.parts.push(part);
+        }
+        score
+    }
+}
+
+// ─── Lexing helpers ──────────────────────────────────────────────────
+
+/// Remove a `%` comment (but not an escaped `\%`).
+fn strip_comment(line: &str) -> &str {
+    let bytes = line.as_bytes();
+    for i in 0..bytes.len() {
+        if bytes[i] == b'%' && (i == 0 || bytes[i - 1] != b'\\') {
+            return &line[..i];
+        }
+    }
+    line
+}
+
+fn find_from(chars: &[char], start: usize, target: char) -> usize {
+    (start..chars.len()).find(|&j| chars[j] == target).unwrap_or(chars.len())
+}
+
+/// Accidentals, letter and octave marks; returns the index after them.
+fn read_pitch(chars: &[char], mut i: usize) -> Option<(WrittenPitch, usize)> {
+    let mut accidental: Option<i32> = None;
+    while let Some(&c) = chars.get(i) {
+        match c {
+            '^' => accidental = Some(accidental.unwrap_or(0) + 1),
+            '_' => accidental = Some(accidental.unwrap_or(0) - 1),
+            '=' => accidental = Some(0),
+            _ => break,
+        }
+        i += 1;
+    }
+    let letter = *chars.get(i)?;
+    let mut octave = match letter {
+        'A'..='G' => 4,
+        'a'..='g' => 5,
+        _ => return None,
+    };
+    i += 1;
+    while let Some(&c) = chars.get(i) {
+        match c {
+            '\'' => octave += 1,
+            ',' => octave -= 1,
+            _ => break,
+        }
+        i += 1;
+    }
+    Some((WrittenPitch { step: letter.to_ascii_uppercase(), octave, accidental }, i))
+}
+
+/// A length multiplier (`2`, `/2`, `3/2`, `/`, `//`); returns it and the
+/// index after it.
+fn read_length(chars: &[char], mut i: usize) -> (Frac, usize) {
+    let read_number = |i: &mut usize| {
+        let start = *i;
+        while *i < chars.len() && chars[*i].is_ascii_digit() {
+            *i += 1;
+        }
+        chars[start..*i].iter().collect::<String>().parse::<i64>().ok()
+    };
+    let num = read_number(&mut i).unwrap_or(1);
+    let mut den = 1;
+    while chars.get(i) == Some(&'/') {
+        i += 1;
+        match read_number(&mut i) {
+            Some(d) if d > 0 => den *= d,
+            _ => den *= 2,
+        }
+    }
+    (Frac::new(num.max(1), den), i)
+}
+
+/// An ending number list such as `1`, `1,2` or `1-3`.
+fn read_ending(chars: &[char], mut i: usize) -> (String, usize) {
+    let start = i;
+    while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == ',' || chars[i] == '-') {
+        i += 1;
+    }
+    (chars[start..i].iter().collect(), i)
+}
+
+enum LyricToken {
+    /// Syllable text and whether a hyphen joins it to the next one
+    Syllable(String, bool),
+    Skip,
+    Bar,
+}
+
+fn lyric_tokens(text: &str) -> Vec<LyricToken> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut chars = text.chars().peekable();
+    let flush = |word: &mut String, tokens: &mut Vec<LyricToken>, hyphen: bool| {
+        if !word.is_empty() {
+            tokens.push(LyricToken::Syllable(std::mem::take(word), hyphen));
+            true
+        } else {
+            false
+        }
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' => {
+                flush(&mut word, &mut tokens, false);
+            }
+            '-' => {
+                if !flush(&mut word, &mut tokens, true) {
+                    // A hyphen with no syllable before it holds a note
+                    if !matches!(tokens.last(), Some(LyricToken::Syllable(_, true))) {
+                        tokens.push(LyricToken::Skip);
+                    }
+                }
+            }
+            '_' | '*' => {
+                flush(&mut word, &mut tokens, false);
+                tokens.push(LyricToken::Skip);
+            }
+            '|' => {
+                flush(&mut word, &mut tokens, false);
+                tokens.push(LyricToken::Bar);
+            }
+            '~' => word.push(' '),
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    word.push(next);
+                }
+            }
+            _ => word.push(c),
+        }
+    }
+    flush(&mut word, &mut tokens, false);
+    tokens
+}
+
+// ─── Field values ────────────────────────────────────────────────────
+
+/// Meter: `3/4`, `C`, `C|`, `2+3/8` or `none`.
+fn parse_meter(value: &str) -> Option<(i32, i32)> {
+    let value = value.trim();
+    match value {
+        "C" => return Some((4, 4)),
+        "C|" => return Some((2, 2)),
+        _ => {}
+    }
+    let (num, den) = value.split_once('/')?;
+    let beats: i32 = num.trim_matches(|c| c == '(' || c == ')')
+        .split('+')
+        .map(|n| n.trim().parse::<i32>().ok())
+        .sum::<Option<i32>>()?;
+    let beat_type = den.trim().parse().ok()?;
+    (beats > 0 && beat_type > 0).then_some((beats, beat_type))
+}
+
+fn parse_fraction(value: &str) -> Option<Frac> {
+    let (num, den) = value.trim().split_once('/')?;
+    let (num, den): (i64, i64) = (num.trim().parse().ok()?, den.trim().parse().ok()?);
+    (num > 0 && den > 0).then(|| Frac::new(num, den))
+}
+
+/// Tempo: `1/4=120`, `3/8=60`, `120` (in units of L) and optional
+/// quoted text such as `"Allegro" 1/4=120`.
+fn parse_tempo(value: &str, unit: Frac) -> Option<Direction> {
+    let words = quoted_text(value);
+    let rest: String = value.split('"').step_by(2).collect::<Vec<_>>().join(" ");
+    let rest = rest.trim();
+
+    let mut direction = blank_direction("above");
+    direction.words = words;
+    if let Some((beat, bpm)) = rest.split_once('=') {
+        let bpm: f64 = bpm.trim().parse().ok()?;
+        let beat = beat.split_whitespace()
+            .filter_map(parse_fraction)
+            .fold(Frac::ZERO, Frac::add);
+        let beat = if beat.num == 0 { unit } else { beat };
+        set_tempo(&mut direction, beat, bpm);
+    } else if let Ok(bpm) = rest.parse::<f64>() {
+        set_tempo(&mut direction, unit, bpm);
+    }
+    (direction.sound_tempo.is_some() || direction.words.is_some()).then_some(direction)
+}
+
+fn set_tempo(direction: &mut Direction, beat: Frac, bpm: f64) {
+    let quarter_bpm = bpm * beat.num as f64 * 4.0 / beat.den as f64;
+    direction.sound_tempo = Some((quarter_bpm * 100.0).round() / 100.0);
+    let (unit, dotted) = NOTE_VALUES.iter()
+        .find(|&&(n, d, _, _)| Frac::new(n, d) == beat)
+        .map_or(("quarter", false), |&(_, _, t, dot)| (t, dot));
+    direction.metronome = Some(MetronomeMark {
+        beat_unit: unit.to_string(),
+        per_minute: bpm.round() as i32,
+        dotted,
+    });
+}
+
+/// Key: tonic and mode (`G`, `F#m`, `Bbmix`, `D dorian`, `none`), with an
+/// optional clef (`clef=bass` or just `bass`).
+fn parse_key(value: &str) -> (Key, Option<Clef>) {
+    let mut tokens = value.split_whitespace().peekable();
+    let clef = value.split_whitespace().find_map(parse_clef);
+    let Some(first) = tokens.next().filter(|t| t.starts_with(|c: char| ('A'..='G').contains(&c))) else {
+        return (Key { fifths: 0, mode: Some("major".to_string()) }, clef);
+    };
+
+    let mut chars = first.chars();
+    let letter = chars.next().unwrap();
+    let mut tonic = match letter {
+        'F' => -1,
+        'C' => 0,
+        'G' => 1,
+        'D' => 2,
+        'A' => 3,
+        'E' => 4,
+        _ => 5,
+    };
+    let mut rest: String = chars.collect();
+    if rest.starts_with('#') {
+        tonic += 7;
+        rest.remove(0);
+    } else if rest.starts_with('b') {
+        tonic -= 7;
+        rest.remove(0);
+    }
+    if rest.is_empty() {
+        if let Some(next) = tokens.peek().filter(|t| !t.contains('=') && parse_clef(t).is_none()) {
+            rest = next.to_string();
+        }
+    }
+    let mode_word = rest.to_lowercase();
+    let (offset, mode) = match mode_word.get(..3).unwrap_or(&mode_word) {
+        "m" | "min" | "aeo" => (-3, if mode_word.starts_with("aeo") { "aeolian" } else { "minor" }),
+        "mix" => (-1, "mixolydian"),
+        "dor" => (-2, "dorian"),
+        "phr" => (-4, "phrygian"),
+        "lyd" => (1, "lydian"),
+        "loc" => (-5, "locrian"),
+        _ => (0, "major"),
+    };
+    (Key { fifths: tonic + offset, mode: Some(mode.to_string()) }, clef)
+}
+
+fn parse_clef(token: &str) -> Option<Clef> {
+    let name = token.strip_prefix("clef=").unwrap_or(token);
+    let (name, octave_change) = match name.strip_suffix("-8") {
+        Some(base) => (base, Some(-1)),
+        None => (name, None),
+    };
+    let (sign, line) = match name {
+        "treble" => ("G", 2),
+        "bass" => ("F", 4),
+        "alto" => ("C", 3),
+        "tenor" => ("C", 4),
+        _ => return None,
+    };
+    Some(Clef { number: 1, sign: sign.to_string(), line, octave_change })
+}
+
+fn treble_clef() -> Clef {
+    Clef { number: 1, sign: "G".to_string(), line: 2, octave_change: None }
+}
+
+/// The value of `name="..."` in a field.
+fn quoted_property(value: &str, name: &str) -> Option<String> {
+    let start = value.find(&format!("{name}=\""))? + name.len() + 2;
+    let end = value[start..].find('"')? + start;
+    Some(value[start..end].to_string())
+}
+
+fn quoted_text(value: &str) -> Option<String> {
+    let start = value.find('"')? + 1;
+    let end = value[start..].find('"')? + start;
+    Some(value[start..end].to_string())
+}
+
+/// Chord symbols: root, quality and optional bass (`Am7`, `F#dim`, `C/G`).
+fn parse_chord_symbol(text: &str) -> Option<Harmony> {
+    let (main, bass) = match text.split_once('/') {
+        Some((m, b)) => (m, Some(b)),
+        None => (text, None),
+    };
+    let (root, kind_text) = parse_root(main)?;
+    let kind = CHORD_KINDS.iter().find(|k| k.1.contains(&kind_text))?.0;
+    let bass = match bass {
+        Some(b) => Some(parse_root(b).filter(|(_, rest)| rest.is_empty())?.0),
+        None => None,
+    };
+    Some(Harmony { root, kind: kind.to_string(), bass, onset: 0, frame: None })
+}
+
+fn parse_root(text: &str) -> Option<(HarmonyRoot, &str)> {
+    let step = text.chars().next().filter(|c| ('A'..='G').contains(c))?;
+    let rest = &text[1..];
+    let (alter, rest) = if let Some(r) = rest.strip_prefix('#') {
+        (Some(1.0), r)
+    } else if let Some(r) = rest.strip_prefix('b') {
+        (Some(-1.0), r)
+    } else {
+        (None, rest)
+    };
+    Some((HarmonyRoot { step: step.to_string(), alter }, rest))
+}
+
+// ─── Helpers ─────────────────────────────────────────────────────────
+
+/// Split a written length into note values joined by ties.
+fn split_length(len: Frac) -> Vec<(Frac, &'static str, bool)> {
+    let mut out: Vec<(Frac, &'static str, bool)> = Vec::new();
+    let mut rest = len;
+    while rest.num > 0 {
+        let value = NOTE_VALUES.iter()
+            .map(|&(n, d, t, dot)| (Frac::new(n, d), t, dot))
+            .find(|(v, _, _)| !rest.lt(*v));
+        match value {
+            Some((v, t, dot)) => {
+                out.push((v, t, dot));
+                rest = rest.sub(v);
+            }
+            None => {
+                // Shorter than any written value: fold into the last piece
+                match out.last_mut() {
+                    Some(last) => last.0 = last.0.add(rest),
+                    None => out.push((rest, "128th", false)),
+                }
+                break;
+            }
+        }
+    }
+    out
+}
+
+fn accidental_name(alter: i32) -> String {
+    match alter {
+        2 => "double-sharp",
+        1 => "sharp",
+        -1 => "flat",
+        -2 => "flat-flat",
+        _ => "natural",
+    }
+    .to_string()
+}
+
+fn beam_position(k: usize, len: usize) -> &'static str {
+    if k == 0 {
+        "begin"
+    } else if k + 1 == len {
+        "end"
+    } else {
+        "continue"
+    }
+}
+
+fn tuplet_event(tuplet_type: &str) -> TupletEvent {
+    TupletEvent {
+        tuplet_type: tuplet_type.to_string(),
+        number: 1,
+        bracket: None,
+        show_number: None,
+        placement: None,
+    }
+}
+
+fn blank_note(voice: i32) -> Note {
+    Note {
+        pitch: None,
+        duration: 0,
+        voice: Some(voice),
+        note_type: None,
+        stem: None,
+        beams: Vec::new(),
+        rest: false,
+        measure_rest: false,
+        chord: false,
+        dot: false,
+        accidental: None,
+        tie_start: false,
+        tie_stop: false,
+        staff: None,
+        default_x: None,
+        default_y: None,
+        lyrics: Vec::new(),
+        grace: false,
+        grace_slash: false,
+        slurs: Vec::new(),
+        onset: 0,
+        time_modification: None,
+        tuplets: Vec::new(),
+        articulations: Vec::new(),
+        ornaments: Vec::new(),
+        ornament_upper_alter: None,
+        ornament_lower_alter: None,
+        fermata: None,
+        notehead: None,
+        string: None,
+        fret: None,
+        fingering: None,
+    }
+}
+
+fn blank_direction(placement: &str) -> Direction {
+    Direction {
+        placement: Some(placement.to_string()),
+        sound_tempo: None,
+        metronome: None,
+        words: None,
+        segno: false,
+        coda: false,
+        rehearsal: None,
+        sound_dacapo: false,
+        sound_dalsegno: false,
+        sound_fine: false,
+        sound_tocoda: false,
+        words_font_style: None,
+        octave_shift_type: None,
+        octave_shift_size: 0,
+        onset: 0,
+        staff: None,
+        dynamics: None,
+        sound_dynamics: None,
+        wedge_type: None,
+        wedge_number: 0,
+    }
+}
@@ -0,0 +1,550 @@
+//! ABC writer — serializes a Score as a single ABC tune.
+
+use std::collections::HashMap;
+
+use super::*;
+
+/// Serialize a score as a single ABC tune (`L:1/8`, one voice per part).
+pub fn write_abc(score: &Score) -> String {
+    let mut out = String::from("X:1\n");
+    for title in [&score.title, &score.subtitle].into_iter().flatten() {
+        out.push_str(&format!("T:{}\n", one_line(title)));
+    }
+    if let Some(composer) = &score.composer {
+        out.push_str(&format!("C:{}\n", one_line(composer)));
+    }
+
+    let first_attrs = |part: &Part| part.measures.first().and_then(|m| m.attributes.clone());
+    let attrs = score.parts.first().and_then(first_attrs);
+    let time = attrs.as_ref().and_then(|a| a.time.clone());
+    let key = attrs.as_ref().and_then(|a| a.key.clone()).unwrap_or(Key { fifths: 0, mode: None });
+    match &time {
+        Some(t) => out.push_str(&format!("M:{}/{}\n", t.beats, t.beat_type)),
+        None => out.push_str("M:none\n"),
+    }
+    out.push_str("L:1/8\n");
+
+    // A tempo at the very start goes in the header
+    let header_tempo = score.parts.first()
+        .and_then(|p| p.measures.first())
+        .and_then(|m| m.directions.iter().position(|d| d.onset == 0 && d.sound_tempo.is_some()));
+    if let Some(i) = header_tempo {
+        out.push_str(&format!("Q:{}\n", tempo_text(&score.parts[0].measures[0].directions[i])));
+    }
+
+    let single = score.parts.len() == 1;
+    for (idx, part) in score.parts.iter().enumerate() {
+        if !single {
+            let clef = first_attrs(part).and_then(|a| a.clefs.first().map(clef_name)).unwrap_or_default();
+            out.push_str(&format!("V:{} name=\"{}\"{clef}\n", idx + 1, part.name.replace('"', "")));
+        }
+    }
+    let clef = match single {
+        true => attrs.as_ref().and_then(|a| a.clefs.first().map(clef_name)).unwrap_or_default(),
+        false => String::new(),
+    };
+    out.push_str(&format!("K:{}{clef}\n", key_name(&key)));
+
+    // Music lines follow the score's system breaks, or 4 measures a line
+    let measure_count = score.parts.iter().map(|p| p.measures.len()).max().unwrap_or(0);
+    let breaks: Vec<bool> = (0..measure_count)
+        .map(|i| score.parts.first().and_then(|p| p.measures.get(i)).is_some_and(|m| m.new_system))
+        .collect();
+    let use_breaks = breaks.iter().skip(1).any(|b| *b);
+    let mut lines: Vec<std::ops::Range<usize>> = Vec::new();
+    let mut start = 0;
+    for i in 1..=measure_count {
+        let brk = i == measure_count || if use_breaks { breaks.get(i) == Some(&true) } else { i - start == 4 };
+        if brk {
+            lines.push(start..i);
+            start = i;
+        }
+    }
+
+    let mut writers: Vec<PartWriter> = score.parts.iter().enumerate()
+        .map(|(i, p)| PartWriter::new(p, header_tempo.filter(|_| i == 0)))
+        .collect();
+    for range in lines {
+        for (idx, writer) in writers.iter_mut().enumerate() {
+            if !single {
+                out.push_str(&format!("[V:{}] ", idx + 1));
+            }
+            writer.line(range.clone(), &mut out);
+        }
+    }
+    out
+}
+
+/// Writes one part, carrying key and accidental state between measures.
+struct PartWriter<'a> {
+    part: &'a Part,
+    divisions: i32,
+    fifths: i32,
+    /// Index of the first measure's direction already written as Q:
+    header_tempo: Option<usize>,
+    open_wedge: Option<String>,
+}
+
+impl<'a> PartWriter<'a> {
+    fn new(part: &'a Part, header_tempo: Option<usize>) -> Self {
+        PartWriter { part, divisions: 1, fifths: 0, header_tempo, open_wedge: None }
+    }
+
+    /// Write the measures in `range` as one music line plus its lyrics.
+    fn line(&mut self, range: std::ops::Range<usize>, out: &mut String) {
+        let mut music = String::new();
+        let mut lyric_notes: Vec<&Note> = Vec::new();
+        let (first_in_line, last_in_line) = (range.start, range.end);
+        for midx in range {
+            let Some(measure) = self.part.measures.get(midx) else { break };
+            if midx == first_in_line {
+                // Endings start after the bar that ended the previous line
+                if let Some(bar) = left_bar(measure, midx == 0) {
+                    music.push_str(&bar);
+                }
+            }
+            self.measure(measure, midx, &mut music);
+            lyric_notes.extend(measure.notes.iter().filter(|n| takes_lyric(n)));
+
+            let next = self.part.measures.get(midx + 1);
+            let line_end = midx + 1 == last_in_line;
+            music.push_str(&bar_between(measure, next, !line_end));
+            if next.is_some() {
+                music.push(' ');
+            }
+        }
+        out.push_str(music.trim_end());
+        out.push('\n');
+
+        let mut verses: Vec<i32> = lyric_notes.iter().flat_map(|n| n.lyrics.iter().map(|l| l.number)).collect();
+        verses.sort_unstable();
+        verses.dedup();
+        for verse in verses {
+            let mut words = String::from("w:");
+            for note in &lyric_notes {
+                match note.lyrics.iter().find(|l| l.number == verse) {
+                    Some(lyric) => {
+                        let text = lyric.text.replace('-', "\\-").replace(' ', "~");
+                        words.push_str(&text);
+                        let joined = matches!(lyric.syllabic.as_deref(), Some("begin" | "middle"));
+                        words.push_str(if joined { "-" } else { " " });
+                    }
+                    None => words.push_str("* "),
+                }
+            }
+            out.push_str(words.trim_end_matches([' ', '*']));
+            out.push('\n');
+        }
+    }
+
+    fn measure(&mut self, measure: &Measure, midx: usize, out: &mut String) {
+        if let Some(attrs) = &measure.attributes {
+            if let Some(d) = attrs.divisions {
+                self.divisions = d.max(1);
+            }
+            if let Some(key) = &attrs.key {
+                self.fifths = key.fifths;
+                if midx > 0 {
+                    out.push_str(&format!("[K:{}] ", key_name(key)));
+                }
+            }
+            if let (Some(t), true) = (&attrs.time, midx > 0) {
+                out.push_str(&format!("[M:{}/{}] ", t.beats, t.beat_type));
+            }
+        }
+
+        // Voices in order of appearance; the first carries the symbols
+        let mut voices: Vec<i32> = Vec::new();
+        for note in &measure.notes {
+            let v = note.voice.unwrap_or(1);
+            if !voices.contains(&v) {
+                voices.push(v);
+            }
+        }
+        if voices.is_empty() {
+            voices.push(1);
+        }
+        for (vi, &voice) in voices.iter().enumerate() {
+            if vi > 0 {
+                out.push_str(" & ");
+            }
+            let notes: Vec<&Note> = measure.notes.iter().filter(|n| n.voice.unwrap_or(1) == voice).collect();
+            self.voice(measure, midx, &notes, vi == 0, out);
+        }
+    }
+
+    fn voice(&mut self, measure: &Measure, midx: usize, notes: &[&Note], main: bool, out: &mut String) {
+        let mut harmonies = measure.harmonies.iter().filter(|_| main).peekable();
+        let skip = self.header_tempo.filter(|_| midx == 0);
+        let mut directions = measure.directions.iter().enumerate()
+            .filter(|&(i, _)| main && Some(i) != skip)
+            .map(|(_, d)| d)
+            .peekable();
+        let mut accidentals: HashMap<(String, i32), i32> = HashMap::new();
+        let mut cursor = 0;
+        let mut i = 0;
+        while i < notes.len() {
+            let note = notes[i];
+            // Gaps in a voice are written as invisible rests
+            if !note.grace && note.onset > cursor {
+                out.push_str(&format!("x{} ", self.length(note.onset - cursor)));
+                cursor = note.onset;
+            }
+            let at = if note.grace { cursor } else { note.onset };
+            while let Some(h) = harmonies.next_if(|h| h.onset <= at) {
+                out.push_str(&format!("\"{}\"", chord_symbol(h)));
+            }
+            while let Some(d) = directions.next_if(|d| d.onset <= at) {
+                out.push_str(&self.direction(d));
+            }
+
+            if note.grace {
+                let start = i;
+                while i < notes.len() && notes[i].grace {
+                    i += 1;
+                }
+                out.push('{');
+                if note.grace_slash {
+                    out.push('/');
+                }
+                let mut g = start;
+                while g < i {
+                    let mut end = g + 1;
+                    while end < i && notes[end].chord {
+                        end += 1;
+                    }
+                    let pitches: String = notes[g..end].iter()
+                        .map(|n| self.pitch(n, &mut accidentals))
+                        .collect();
+                    if end - g > 1 {
+                        out.push_str(&format!("[{pitches}]"));
+                    } else {
+                        out.push_str(&pitches);
+                    }
+                    out.push_str(&written_length(grace_length(notes[g])));
+                    g = end;
+                }
+                out.push('}');
+                continue;
+            }
+
+            // The note, with any chord notes that follow it
+            let mut end = i + 1;
+            while end < notes.len() && notes[end].chord {
+                end += 1;
+            }
+            let chord = &notes[i..end];
+            out.push_str(&self.note_prefix(note, notes, i));
+            if note.measure_rest && note.rest {
+                out.push('Z');
+            } else if note.rest {
+                out.push('z');
+                out.push_str(&self.notated_length(note));
+            } else if chord.len() == 1 {
+                out.push_str(&self.pitch(note, &mut accidentals));
+                out.push_str(&self.notated_length(note));
+                if note.tie_start {
+                    out.push('-');
+                }
+            } else {
+                out.push('[');
+                for n in chord {
+                    out.push_str(&self.pitch(n, &mut accidentals));
+                    if n.tie_start {
+                        out.push('-');
+                    }
+                }
+                out.push(']');
+                out.push_str(&self.notated_length(note));
+            }
+            for slur in note.slurs.iter().filter(|s| s.slur_type == "stop") {
+                let _ = slur;
+                out.push(')');
+            }
+            cursor = note.onset + note.duration;
+            // Beamed notes are written without a space between them
+            let beamed = note.beams.iter().any(|b| b.number == 1 && b.beam_type != "end");
+            if !beamed {
+                out.push(' ');
+            }
+            i = end;
+        }
+        // Symbols after the last note
+        for h in harmonies {
+            out.push_str(&format!("\"{}\"", chord_symbol(h)));
+        }
+        for d in directions {
+            out.push_str(&self.direction(d));
+        }
+        if out.ends_with(' ') {
+            out.pop();
+        }
+    }
+
+    /// Tuplet, slur and decoration marks written before a note.
+    fn note_prefix(&self, note: &Note, notes: &[&Note], i: usize) -> String {
+        let mut s = String::new();
+        if note.tuplets.iter().any(|t| t.tuplet_type == "start") {
+            if let Some(tm) = &note.time_modification {
+                let count = notes[i..].iter()
+                    .filter(|n| !n.chord && !n.grace)
+                    .position(|n| n.tuplets.iter().any(|t| t.tuplet_type == "stop"))
+                    .map_or(tm.actual_notes, |p| p as i32 + 1);
+                let default_q = match tm.actual_notes {
+                    2 | 4 | 8 => 3,
+                    _ => 2,
+                };
+                if tm.normal_notes == default_q && count == tm.actual_notes {
+                    s.push_str(&format!("({}", tm.actual_notes));
+                } else {
+                    s.push_str(&format!("({}:{}:{}", tm.actual_notes, tm.normal_notes, count));
+                }
+            }
+        }
+        for _ in note.slurs.iter().filter(|sl| sl.slur_type == "start") {
+            s.push('(');
+        }
+        for a in &note.articulations {
+            s.push_str(match a.as_str() {
+                "staccato" => ".",
+                "tenuto" => "!tenuto!",
+                "accent" => "!accent!",
+                "strong-accent" => "!marcato!",
+                "staccatissimo" => "!wedge!",
+                _ => "",
+            });
+        }
+        for o in &note.ornaments {
+            s.push_str(match o.as_str() {
+                "trill-mark" => "!trill!",
+                "mordent" => "!mordent!",
+                "inverted-mordent" => "!uppermordent!",
+                "turn" => "!turn!",
+                _ => "",
+            });
+        }
+        match note.fermata.as_deref() {
+            Some("inverted") => s.push_str("!invertedfermata!"),
+            Some(_) => s.push_str("!fermata!"),
+            None => {}
+        }
+        s
+    }
+
+    /// A pitch with the accidental it needs in this measure.
+    fn pitch(&self, note: &Note, accidentals: &mut HashMap<(String, i32), i32>) -> String {
+        let Some(p) = &note.pitch else { return String::new() };
+        let step = p.step.chars().next().unwrap_or('C');
+        let alter = p.alter.unwrap_or(0.0).round() as i32;
+        let line = (p.step.clone(), p.octave);
+        let expected = accidentals.get(&line).copied().unwrap_or_else(|| key_alter(self.fifths, step));
+
+        let mut s = String::new();
+        if !note.tie_stop && (alter != expected || note.accidental.is_some()) {
+            s.push_str(match alter {
+                2 => "^^",
+                1 => "^",
+                -1 => "_",
+                -2 => "__",
+                _ => "=",
+            });
+            accidentals.insert(line, alter);
+        }
+        if p.octave >= 5 {
+            s.push(step.to_ascii_lowercase());
+            s.push_str(&"'".repeat((p.octave - 5) as usize));
+        } else {
+            s.push(step);
+            s.push_str(&",".repeat((4 - p.octave).max(0) as usize));
+        }
+        s
+    }
+
+    /// A duration in divisions as a length in eighths.
+    fn length(&self, duration: i32) -> String {
+        written_length(Frac::new(duration as i64, 4 * self.divisions as i64))
+    }
+
+    /// The written length of a note (tuplet notes are longer than they sound).
+    fn notated_length(&self, note: &Note) -> String {
+        let mut len = Frac::new(note.duration as i64, 4 * self.divisions as i64);
+        if let Some(tm) = &note.time_modification {
+            len = len.mul(Frac::new(tm.actual_notes as i64, tm.normal_notes.max(1) as i64));
+        }
+        written_length(len)
+    }
+
+    fn direction(&mut self, d: &Direction) -> String {
+        let mut s = String::new();
+        if d.sound_tempo.is_some() {
+            s.push_str(&format!("[Q:{}]", tempo_text(d)));
+        }
+        if d.segno {
+            s.push_str("!segno!");
+        }
+        if d.coda {
+            s.push_str("!coda!");
+        }
+        if let Some(dynamics) = &d.dynamics {
+            s.push_str(&format!("!{dynamics}!"));
+        }
+        match d.wedge_type.as_deref() {
+            Some("crescendo") => {
+                s.push_str("!<(!");
+                self.open_wedge = Some("<".to_string());
+            }
+            Some("diminuendo") => {
+                s.push_str("!>(!");
+                self.open_wedge = Some(">".to_string());
+            }
+            Some("stop") => {
+                let kind = self.open_wedge.take().unwrap_or_else(|| "<".to_string());
+                s.push_str(&format!("!{kind})!"));
+            }
+            _ => {}
+        }
+        let jump = [
+            (d.sound_dacapo, "!D.C.!"),
+            (d.sound_dalsegno, "!D.S.!"),
+            (d.sound_fine, "!fine!"),
+            (d.sound_tocoda, "!dacoda!"),
+        ];
+        if let Some((_, mark)) = jump.iter().find(|j| j.0) {
+            s.push_str(mark);
+        } else if let (Some(words), None) = (&d.words, d.sound_tempo) {
+            let prefix = if d.placement.as_deref() == Some("below") { '_' } else { '^' };
+            s.push_str(&format!("\"{prefix}{}\"", one_line(words).replace('"', "")));
+        }
+        s
+    }
+}
+
+/// Notes that take a lyric syllable: sounded, not grace, chord or tied-over notes.
+fn takes_lyric(note: &Note) -> bool {
+    note.voice.unwrap_or(1) == 1 && !note.rest && !note.grace && !note.chord && !note.tie_stop
+}
+
+/// The bar written at the start of a line: a start repeat before the
+/// first measure, and an ending's number.
+fn left_bar(measure: &Measure, first: bool) -> Option<String> {
+    let left = measure.barlines.iter().find(|b| b.location == "left")?;
+    let mut s = String::new();
+    if first && left.repeat.as_ref().is_some_and(|r| r.direction == "forward") {
+        s.push_str("|:");
+    } else if first && left.bar_style.as_deref() == Some("heavy-light") {
+        s.push_str("[|");
+    }
+    if let Some(e) = left.ending.as_ref().filter(|e| e.ending_type == "start") {
+        s.push_str(&format!("[{}", e.number.replace(' ', "")));
+    }
+    (!s.is_empty()).then(|| s + " ")
+}
+
+/// The bar between a measure and the next (or the final bar), with the
+/// number of an ending that starts there when `with_ending` is set.
+fn bar_between(measure: &Measure, next: Option<&Measure>, with_ending: bool) -> String {
+    let right = measure.barlines.iter().find(|b| b.location == "right");
+    let left = next.and_then(|m| m.barlines.iter().find(|b| b.location == "left"));
+    let back = right.and_then(|b| b.repeat.as_ref()).is_some_and(|r| r.direction == "backward");
+    let forward = left.and_then(|b| b.repeat.as_ref()).is_some_and(|r| r.direction == "forward");
+
+    let mut bar = match (back, forward) {
+        (true, true) => "::".to_string(),
+        (true, false) => ":|".to_string(),
+        (false, true) => "|:".to_string(),
+        (false, false) => match right.and_then(|b| b.bar_style.as_deref()) {
+            Some("light-light") => "||".to_string(),
+            Some("light-heavy") => "|]".to_string(),
+            _ if left.and_then(|b| b.bar_style.as_deref()) == Some("heavy-light") => "[|".to_string(),
+            _ => "|".to_string(),
+        },
+    };
+    if let Some(e) = left.and_then(|b| b.ending.as_ref()).filter(|e| with_ending && e.ending_type == "start") {
+        bar.push_str(&e.number.replace(' ', ""));
+    }
+    bar
+}
+
+/// A length in eighths: `` (1), `2`, `/2`, `3/2`…
+fn written_length(len: Frac) -> String {
+    let eighths = len.mul(Frac::new(8, 1));
+    match (eighths.num, eighths.den) {
+        (1, 1) => String::new(),
+        (n, 1) => n.to_string(),
+        (1, 2) => "/".to_string(),
+        (1, d) => format!("/{d}"),
+        (n, d) => format!("{n}/{d}"),
+    }
+}
+
+/// The written length of a grace note, from its type.
+fn grace_length(note: &Note) -> Frac {
+    let base = NOTE_VALUES.iter()
+        .find(|v| !v.3 && Some(v.2) == note.note_type.as_deref())
+        .map_or(Frac::new(1, 8), |v| Frac::new(v.0, v.1));
+    if note.dot { base.mul(Frac::new(3, 2)) } else { base }
+}
+
+fn tempo_text(d: &Direction) -> String {
+    match &d.metronome {
+        Some(m) => {
+            let value = NOTE_VALUES.iter()
+                .find(|v| v.2 == m.beat_unit && v.3 == m.dotted)
+                .map_or(Frac::new(1, 4), |v| Frac::new(v.0, v.1));
+            format!("{}/{}={}", value.num, value.den, m.per_minute)
+        }
+        None => format!("1/4={}", d.sound_tempo.unwrap_or(120.0).round()),
+    }
+}
+
+/// Key name for K: (`G`, `Em`, `DMix`…).
+fn key_name(key: &Key) -> String {
+    let (offset, suffix) = match key.mode.as_deref() {
+        Some("minor") => (-3, "m"),
+        Some("aeolian") => (-3, "Aeo"),
+        Some("mixolydian") => (-1, "Mix"),
+        Some("dorian") => (-2, "Dor"),
+        Some("phrygian") => (-4, "Phr"),
+        Some("lydian") => (1, "Lyd"),
+        Some("locrian") => (-5, "Loc"),
+        _ => (0, ""),
+    };
+    let tonic = key.fifths - offset;
+    let letter = ['F', 'C', 'G', 'D', 'A', 'E', 'B'][(tonic + 1).rem_euclid(7) as usize];
+    let sharps = (tonic + 1).div_euclid(7);
+    let accidental = match sharps {
+        s if s > 0 => "#".repeat(s as usize),
+        s => "b".repeat((-s) as usize),
+    };
+    format!("{letter}{accidental}{suffix}")
+}
+
+fn clef_name(clef: &Clef) -> String {
+    let name = match (clef.sign.as_str(), clef.line) {
+        ("F", _) => "bass",
+        ("C", 3) => "alto",
+        ("C", 4) => "tenor",
+        _ => return String::new(),
+    };
+    format!(" clef={name}")
+}
+
+fn chord_symbol(h: &Harmony) -> String {
+    let root = |r: &HarmonyRoot| {
+        let alter = match r.alter {
+            Some(a) if a > 0.0 => "#",
+            Some(a) if a < 0.0 => "b",
+            _ => "",
+        };
+        format!("{}{alter}", r.step)
+    };
+    let kind = CHORD_KINDS.iter()
+        .find(|k| k.0 == h.kind || h.kind == "dominant-seventh" && k.0 == "dominant")
+        .map_or("", |k| k.1[0]);
+    let bass = h.bass.as_ref().map(|b| format!("/{}", root(b))).unwrap_or_default();
+    format!("{}{kind}{bass}", root(&h.root))
+}
+
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -6,8 +6,8 @@
 //! implementation).
 
 use crate::midi::{Energy, MidiEvent, TICKS_PER_QUARTER, ms_to_ticks};
-use crate::model::Part;
-use crate::timemap::TimemapEntry;
+use crate::model::{Part, Score};
+use crate::timemap::{self, TimemapEntry};
 use crate::unroller::UnrolledMeasure;
 
 // ═══════════════════════════════════════════════════════════════════════
@@ -47,8 +47,11 @@ pub enum ChordKind {
 /// 2. If no harmonies are present, infer chords from the melody note pitch classes
 ///    using the key signature and diatonic priority — like a pianist reading a lead
 ///    sheet and creating their own chord voicings on the fly.
+///
+/// Chord symbols are taken from the first part that has any; inference
+/// listens to every part, so a quartet's inner voices fill in the harmony.
 pub fn analyze_chords(
-    part: &Part,
+    score: &Score,
     unrolled: &[UnrolledMeasure],
     timemap: &[TimemapEntry],
 ) -> Vec<Chord> {
@@ -61,12 +64,12 @@ pub fn analyze_chords(
     );
 
     // Check whether the score has *any* harmonies at all
-    let has_harmonies = part.measures.iter().any(|m| !m.harmonies.is_empty());
+    let harmony_part = score.parts.iter()
+        .find(|p| p.measures.iter().any(|m| !m.harmonies.is_empty()));
 
-    if has_harmonies {
-        analyze_chords_from_harmonies(part, unrolled, timemap)
-    } else {
-        analyze_chords_from_melody(part, unrolled, timemap)
+    match harmony_part {
+        Some(part) => analyze_chords_from_harmonies(part, unrolled, &timemap::part_timemap(part, timemap)),
+        None => analyze_chords_from_melody(&score.parts, unrolled, timemap),
     }
 }
 
@@ -79,10 +82,11 @@ fn analyze_chords_from_harmonies(
     let mut chords: Vec<Chord> = Vec::new();
 
     for (i, um) in unrolled.iter().enumerate() {
-        let measure = &part.measures[um.original_index];
         let entry = &timemap[i];
+        // A part shorter than the conductor part has no harmonies past its end
+        let measure = part.measures.get(um.original_index).filter(|m| !m.harmonies.is_empty());
 
-        if measure.harmonies.is_empty() {
+        let Some(measure) = measure else {
             // No chord symbol in this measure — repeat the previous chord
             if let Some(prev) = chords.last().cloned() {
                 chords.push(Chord {
@@ -100,18 +104,33 @@ fn analyze_chords_from_harmonies(
                 });
             }
             continue;
-        }
+        };
 
-        let h = &measure.harmonies[0];
-        let root = step_to_pitch_class(&h.root.step, h.root.alter.unwrap_or(0.0));
-        let kind = parse_chord_kind(&h.kind);
+        // Each chord symbol lasts until the next one in the measure (or the
+        // barline), positioned by the onset the parser resolved.
+        let divisions = entry.divisions.max(1) as f64;
+        let offset_ms = |onset: i32| entry.time_at_quarters(onset as f64 / divisions) - entry.timestamp_ms;
+        for (j, h) in measure.harmonies.iter().enumerate() {
+            let start_ms = if j == 0 { 0.0 } else { offset_ms(h.onset) };
+            let end_ms = measure
+                .harmonies
+                .get(j + 1)
+                .map_or(entry.duration_ms, |next| offset_ms(next.onset))
+                .min(entry.duration_ms);
+            if end_ms <= start_ms {
+                continue;
+            }
 
-        chords.push(Chord {
-            root,
-            kind,
-            time_ms: entry.timestamp_ms,
-            duration_ms: entry.duration_ms,
-        });
+            let root = step_to_pitch_class(&h.root.step, h.root.alter.unwrap_or(0.0));
+            let kind = parse_chord_kind(&h.kind);
+
+            chords.push(Chord {
+                root,
+                kind,
+                time_ms: entry.timestamp_ms + start_ms,
+                duration_ms: end_ms - start_ms,
+            });
+        }
     }
 
     chords
@@ -123,22 +142,24 @@ fn analyze_chords_from_harmonies(
 /// then picks the most likely chord root and quality using the key
 /// signature and standard diatonic harmony rules.
 fn analyze_chords_from_melody(
-    part: &Part,
+    parts: &[Part],
     unrolled: &[UnrolledMeasure],
     timemap: &[TimemapEntry],
 ) -> Vec<Chord> {
     // Detect key from the first key signature found
-    let key_root = detect_key_root(part);
+    let key_root = parts.first().map_or(0, detect_key_root);
 
     let mut chords: Vec<Chord> = Vec::new();
 
     for (i, um) in unrolled.iter().enumerate() {
-        let measure = &part.measures[um.original_index];
         let entry = &timemap[i];
 
         // Collect unique pitch classes from all sounding notes in this measure
         let mut pitch_classes: Vec<u8> = Vec::new();
-        for note in &measure.notes {
+        let notes = parts.iter()
+            .filter_map(|p| p.measures.get(um.original_index))
+            .flat_map(|m| &m.notes);
+        for note in notes {
             if note.rest || note.grace || note.chord {
                 continue;
             }
@@ -280,7 +301,7 @@ fn step_to_pitch_class(step: &str, alter: f64) -> u8 {
         "G" => 7, "A" => 9, "B" => 11,
         _ => 0,
     };
-    ((base as i32 + alter.round() as i32).rem_euclid(12)) as u8
+    ((base + alter.round() as i32).rem_euclid(12)) as u8
 }
 
 fn parse_chord_kind(kind: &str) -> ChordKind {
@@ -387,7 +408,7 @@ fn energy_multipliers(energy: Energy) -> EnergyMultipliers {
 }
 
 fn velocity(base: f64, multiplier: f64) -> u8 {
-    (base * multiplier).round().max(1.0).min(127.0) as u8
+    (base * multiplier).round().clamp(1.0, 127.0) as u8
 }
 
 // ═══════════════════════════════════════════════════════════════════════
@@ -397,8 +418,8 @@ fn velocity(base: f64, multiplier: f64) -> u8 {
 /// MIDI drum notes for metronome clicks.
 const CLICK_HI: u8 = 76; // Hi Wood Block — downbeat
 const CLICK_LO: u8 = 77; // Lo Wood Block — other beats
-#[allow(dead_code)]
-const DRUM_CHANNEL: u8 = 9;
+/// General MIDI percussion channel, shared by the metronome and drums.
+pub(crate) const DRUM_CHANNEL: u8 = 9;
 
 /// Generate metronome click events from the timemap.
 pub fn generate_metronome(timemap: &[TimemapEntry]) -> Vec<MidiEvent> {
@@ -410,10 +431,11 @@ pub fn generate_metronome(timemap: &[TimemapEntry]) -> Vec<MidiEvent> {
         // Number of beats: effective_quarters scaled by beat type
         // e.g. 4/4 with 1 quarter note → 1 beat; 6/8 with 1.5 quarters → 3 beats
         let actual_beats = (entry.effective_quarters * beat_type / 4.0).round().max(1.0) as i32;
-        let beat_dur_ms = entry.duration_ms / actual_beats as f64;
+        let beat_quarters = entry.effective_quarters / actual_beats as f64;
 
         for b in 0..actual_beats {
-            let beat_time_ms = entry.timestamp_ms + b as f64 * beat_dur_ms;
+            // Follows fermata holds, so clicks wait for the held note
+            let beat_time_ms = entry.time_at_quarters(b as f64 * beat_quarters);
             let note = if b == 0 { CLICK_HI } else { CLICK_LO };
             let vel = if b == 0 { 127 } else { 100 };
 
@@ -422,11 +444,11 @@ pub fn generate_metronome(timemap: &[TimemapEntry]) -> Vec<MidiEvent> {
 
             events.push(MidiEvent {
                 tick: on_tick,
-                bytes: vec![0x99, note, vel], // Channel 9 note on
+                bytes: vec![0x90 | DRUM_CHANNEL, note, vel], // Channel 9 note on
             });
             events.push(MidiEvent {
                 tick: off_tick,
-                bytes: vec![0x89, note, 0], // Channel 9 note off
+                bytes: vec![0x80 | DRUM_CHANNEL, note, 0], // Channel 9 note off
             });
         }
     }
@@ -438,7 +460,7 @@ pub fn generate_metronome(timemap: &[TimemapEntry]) -> Vec<MidiEvent> {
 // Piano accompaniment
 // ═══════════════════════════════════════════════════════════════════════
 
-const PIANO_CHANNEL: u8 = 1;
+pub(crate) const PIANO_CHANNEL: u8 = 1;
 
 /// Generate piano accompaniment events (broken chord / arpeggio pattern).
 pub fn generate_piano(chords: &[Chord], energy: Energy, timemap: &[TimemapEntry]) -> Vec<MidiEvent> {
@@ -488,7 +510,7 @@ pub fn generate_piano(chords: &[Chord], energy: Energy, timemap: &[TimemapEntry]
                 let on_tick = ms_to_ticks(note_time_ms, timemap);
                 let off_tick = ms_to_ticks(note_time_ms + dur_ms * 0.8, timemap);
 
-                let note_vel = (base_vel as f64 * 0.85).round().max(1.0).min(127.0) as u8;
+                let note_vel = (base_vel as f64 * 0.85).round().clamp(1.0, 127.0) as u8;
                 events.push(MidiEvent {
                     tick: on_tick,
                     bytes: vec![0x90 | PIANO_CHANNEL, note.min(127), note_vel],
@@ -510,7 +532,7 @@ pub fn generate_piano(chords: &[Chord], energy: Energy, timemap: &[TimemapEntry]
 // Bass accompaniment
 // ═══════════════════════════════════════════════════════════════════════
 
-const BASS_CHANNEL: u8 = 2;
+pub(crate) const BASS_CHANNEL: u8 = 2;
 
 /// Generate walking bass events.
 pub fn generate_bass(chords: &[Chord], energy: Energy, timemap: &[TimemapEntry]) -> Vec<MidiEvent> {
@@ -575,7 +597,7 @@ pub fn generate_bass(chords: &[Chord], energy: Energy, timemap: &[TimemapEntry])
 // String accompaniment
 // ═══════════════════════════════════════════════════════════════════════
 
-const STRING_CHANNEL: u8 = 3;
+pub(crate) const STRING_CHANNEL: u8 = 3;
 
 /// Generate sustained string pad events.
 pub fn generate_strings(chords: &[Chord], energy: Energy, timemap: &[TimemapEntry]) -> Vec<MidiEvent> {
@@ -638,11 +660,11 @@ pub fn generate_drums(chords: &[Chord], energy: Energy, timemap: &[TimemapEntry]
                 let vel = velocity(100.0, em.drums);
                 events.push(MidiEvent {
                     tick: on_tick,
-                    bytes: vec![0x99, KICK, vel],
+                    bytes: vec![0x90 | DRUM_CHANNEL, KICK, vel],
                 });
                 events.push(MidiEvent {
                     tick: off_tick,
-                    bytes: vec![0x89, KICK, 0],
+                    bytes: vec![0x80 | DRUM_CHANNEL, KICK, 0],
                 });
             }
 
@@ -651,11 +673,11 @@ pub fn generate_drums(chords: &[Chord], energy: Energy, timemap: &[TimemapEntry]
                 let vel = velocity(90.0, em.drums);
                 events.push(MidiEvent {
                     tick: on_tick,
-                    bytes: vec![0x99, SNARE, vel],
+                    bytes: vec![0x90 | DRUM_CHANNEL, SNARE, vel],
                 });
                 events.push(MidiEvent {
                     tick: off_tick,
-                    bytes: vec![0x89, SNARE, 0],
+                    bytes: vec![0x80 | DRUM_CHANNEL, SNARE, 0],
                 });
             }
 
@@ -663,11 +685,11 @@ pub fn generate_drums(chords: &[Chord], energy: Energy, timemap: &[TimemapEntry]
             let hh_vel = velocity(70.0, em.drums);
             events.push(MidiEvent {
                 tick: on_tick,
-                bytes: vec![0x99, HIHAT_CLOSED, hh_vel],
+                bytes: vec![0x90 | DRUM_CHANNEL, HIHAT_CLOSED, hh_vel],
             });
             events.push(MidiEvent {
                 tick: off_tick,
-                bytes: vec![0x89, HIHAT_CLOSED, 0],
+                bytes: vec![0x80 | DRUM_CHANNEL, HIHAT_CLOSED, 0],
             });
 
             // Hi-hat eighth notes between beats
@@ -677,11 +699,11 @@ pub fn generate_drums(chords: &[Chord], energy: Energy, timemap: &[TimemapEntry]
                 let eighth_vel = velocity(50.0, em.drums);
                 events.push(MidiEvent {
                     tick: eighth_tick,
-                    bytes: vec![0x99, HIHAT_CLOSED, eighth_vel],
+                    bytes: vec![0x90 | DRUM_CHANNEL, HIHAT_CLOSED, eighth_vel],
                 });
                 events.push(MidiEvent {
                     tick: eighth_tick + dur_ticks,
-                    bytes: vec![0x89, HIHAT_CLOSED, 0],
+                    bytes: vec![0x80 | DRUM_CHANNEL, HIHAT_CLOSED, 0],
                 });
             }
         }
@@ -6,7 +6,8 @@ use jni::objects::{JByteArray, JClass, JString};
 use jni::sys::{jfloat, jint, jstring};
 use jni::JNIEnv;
 
-use crate::{render_bytes_to_svg, render_file_to_svg, playback_map_from_bytes, generate_midi_from_bytes, MidiOptions, Energy};
+use crate::{render_bytes_to_svg, render_bytes_to_svg_with_options, render_file_to_svg, playback_map_from_bytes, generate_midi_from_bytes, generate_wav_from_bytes, MidiOptions, SynthOptions, Energy};
+use crate::renderer::{render_options_from_json, RenderOptions};
 
 /// Render a MusicXML file at the given path to SVG.
 ///
@@ -71,6 +72,53 @@ pub extern "system" fn Java_com_solobandultra_app_ScoreLib_renderBytes(
     }
 }
 
+/// Render MusicXML bytes to SVG with render options (theme, staff scale,
+/// fonts and display toggles) given as JSON, as for
+/// `scorelib_render_with_options`.
+///
+/// Called from Kotlin as:
+///   external fun renderBytesWithOptions(data: ByteArray, extension: String?, pageWidth: Float, transpose: Int, optionsJson: String?): String?
+#[no_mangle]
+pub extern "system" fn Java_com_solobandultra_app_ScoreLib_renderBytesWithOptions(
+    mut env: JNIEnv,
+    _class: JClass,
+    data: JByteArray,
+    extension: JString,
+    page_width: jfloat,
+    transpose: jint,
+    options_json: JString,
+) -> jstring {
+    let bytes = match env.convert_byte_array(&data) {
+        Ok(b) => b,
+        Err(_) => return std::ptr::null_mut(),
+    };
+
+    let ext: Option<String> = if extension.is_null() {
+        None
+    } else {
+        env.get_string(&extension).ok().map(|s| s.into())
+    };
+
+    let options = if options_json.is_null() {
+        RenderOptions::default()
+    } else {
+        match env.get_string(&options_json) {
+            Ok(s) => render_options_from_json(&String::from(s)),
+            Err(_) => RenderOptions::default(),
+        }
+    };
+
+    let pw = if page_width > 0.0 { Some(page_width as f64) } else { None };
+
+    match render_bytes_to_svg_with_options(&bytes, ext.as_deref(), pw, transpose, &options) {
+        Ok(svg) => match env.new_string(&svg) {
+            Ok(js) => js.into_raw(),
+            Err(_) => std::ptr::null_mut(),
+        },
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
 /// Generate a playback map JSON from MusicXML bytes.
 ///
 /// Called from Kotlin as:
@@ -149,6 +197,52 @@ pub extern "system" fn Java_com_solobandultra_app_ScoreLib_generateMidi(
     }
 }
 
+/// Render MusicXML bytes to WAV bytes with the built-in synthesizer.
+///
+/// Called from Kotlin as:
+///   external fun generateWav(data: ByteArray, extension: String?, soundFont: ByteArray, optionsJson: String?): ByteArray?
+#[no_mangle]
+pub extern "system" fn Java_com_solobandultra_app_ScoreLib_generateWav(
+    mut env: JNIEnv,
+    _class: JClass,
+    data: JByteArray,
+    extension: JString,
+    sound_font: JByteArray,
+    options_json: JString,
+) -> jni::sys::jbyteArray {
+    let (Ok(bytes), Ok(sf_bytes)) = (env.convert_byte_array(&data), env.convert_byte_array(&sound_font)) else {
+        return std::ptr::null_mut() as jni::sys::jbyteArray;
+    };
+
+    let ext: Option<String> = if extension.is_null() {
+        None
+    } else {
+        env.get_string(&extension).ok().map(|s| s.into())
+    };
+
+    let (midi_options, synth_options) = if options_json.is_null() {
+        (MidiOptions::default(), SynthOptions::default())
+    } else {
+        match env.get_string(&options_json) {
+            Ok(s) => {
+                let json = String::from(s);
+                (parse_midi_options_str(&json), crate::synth::synth_options_from_json(&json))
+            }
+            Err(_) => (MidiOptions::default(), SynthOptions::default()),
+        }
+    };
+
+    match generate_wav_from_bytes(&bytes, ext.as_deref(), &midi_options, &sf_bytes, &synth_options) {
+        Ok(wav) => {
+            match env.byte_array_from_slice(&wav) {
+                Ok(arr) => arr.into_raw(),
+                Err(_) => std::ptr::null_mut() as jni::sys::jbyteArray,
+            }
+        }
+        Err(_) => std::ptr::null_mut() as jni::sys::jbyteArray,
+    }
+}
+
 /// Simple MIDI options parser from a JSON string (mirrors lib.rs helper).
 fn parse_midi_options_str(json_str: &str) -> MidiOptions {
     let mut opts = MidiOptions::default();
@@ -194,5 +288,6 @@ fn parse_midi_options_str(json_str: &str) -> MidiOptions {
             opts.transpose = val;
         }
     }
+    opts.parts = crate::midi::part_options_from_json(json_str);
     opts
 }
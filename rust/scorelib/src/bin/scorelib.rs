@@ -0,0 +1,609 @@
+//! `scorelib` command-line tool.
+//!
+//! Renders scores to SVG, PDF and PNG, exports MIDI and playback maps, and prints score
+//! information — one file or a whole library at a time.  Input arguments
+//! may be glob patterns (`*`, `?`, `**`), so quoted patterns work the same
+//! on every shell.
+//!
+//! Exit codes: 0 on success, 1 when any input failed to parse or write,
+//! 2 on a usage error (including two inputs that would write the same
+//! output file).
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use scorelib::{
+    add_chord_diagrams, add_tab_staff, generate_midi_from_score, generate_timemap, horizontal_playback_map_from_score,
+    jianpu_playback_map_from_score, paged_playback_map_from_score, parse_file, parse_tuning,
+    playback_map_with_options_from_score, render_score_to_horizontal_svg, render_score_to_jianpu_svg, render_score_to_pdf,
+    render_score_to_png, render_score_to_svg_pages, render_score_to_svg_with_options, transpose_score, unroll, Energy,
+    FontFamily, LabelPosition, MidiOptions, NoteNames, PageOptions, PageSize, PartOptions, RasterOptions, RasterRegion,
+    RenderOptions, Score, TabOptions, Theme,
+};
+use scorelib::timemap::{self, total_duration_ms};
+
+const USAGE: &str = "\
+Usage: scorelib <command> [options] <files or globs>...
+
+Commands:
+  render         Render scores to SVG
+  pdf            Render scores to printable PDF pages
+  png            Render the first system, a page or some measures to PNG
+  midi           Export scores to Standard MIDI Files
+  playback-map   Print the playback-map JSON
+  info           Print title, parts, key, time signatures, measures and duration
+  unroll         Print the play order (measure numbers after repeats and jumps)
+
+Options:
+  -o, --output <path>    Output file (one input) or directory (several inputs);
+                         '-' writes to stdout.  Without it, render and midi
+                         write next to each input; the other commands print
+                         to stdout
+  -w, --width <units>    Page width for render and playback-map (default 820)
+  -t, --transpose <n>    Transpose by n semitones
+  --paper <size>         Paper size for pdf and png: a4 (default) or letter
+  --jianpu               Render and map numbered notation instead of staves
+  --pages                Render and map the score on pages, one SVG per page
+                         (<name>-1.svg, <name>-2.svg, ...), keeping its page
+                         size, margins and system and page breaks
+  --horizontal           Render and map the score on one endless system for
+                         horizontal scrolling
+  --tab <part>           Add a guitar tab staff under a part (0-based index;
+                         repeatable)
+  --diagrams             Draw a chord diagram over every chord symbol, with a
+                         legend of the chords under the title
+  --tuning <tuning>      Tuning for --tab and --diagrams: standard (default),
+                         drop-d, dadgad, open-g, open-d, bass, bass5, ukulele,
+                         or open-string notes from the lowest string, e.g. \"D2 A2 D3 G3 B3 E4\"
+
+Display options (render, pdf, png and playback-map, not with --jianpu):
+  --theme <theme>        Colors: light (default), dark, high-contrast or sepia
+  --staff-scale <x>      Staff size relative to the default, e.g. 1.5
+  --font <font>          Text font: serif (default), sans-serif or a font name
+  --no-chords            Leave out chord symbols and diagrams
+  --no-lyrics            Leave out lyrics
+  --no-measure-numbers   Leave out measure numbers
+  --note-names <names>   Name every note: letters, fixed-do or movable-do
+  --names-inside         Write note names inside the noteheads, not under the staff
+  --color-notes          Color noteheads by pitch, in Boomwhacker colors
+  --fingering            Draw the finger numbers in the score
+
+PNG options:
+  --dpi <n>              Resolution in pixels per inch (default 150)
+  --page <n>             Render page n as printed instead of the first system
+  --measures <a>-<b>     Render measures a to b (1-based, inclusive)
+
+MIDI options:
+  --no-melody            Leave out the score's own parts
+  --piano, --bass, --strings, --drums
+                         Add accompaniment tracks
+  --no-metronome         Leave out the metronome track
+  --melody-channel <n>   Channel (0-15) for the first part (default 0)
+  --energy <level>       Accompaniment energy: soft, medium or strong
+  --exclude <part>       Leave out a part (0-based index; repeatable)
+  --mute <part>          Keep a part's tracks but silence them (repeatable)
+  --volume <part>=<v>    Set a part's volume (0-127; repeatable)
+
+  -h, --help             Show this help
+";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    Render,
+    Pdf,
+    Png,
+    Midi,
+    PlaybackMap,
+    Info,
+    Unroll,
+}
+
+impl Command {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "render" => Some(Self::Render),
+            "pdf" => Some(Self::Pdf),
+            "png" => Some(Self::Png),
+            "midi" => Some(Self::Midi),
+            "playback-map" => Some(Self::PlaybackMap),
+            "info" => Some(Self::Info),
+            "unroll" => Some(Self::Unroll),
+            _ => None,
+        }
+    }
+
+    /// Extension of the file written for each input.
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Render => "svg",
+            Self::Pdf => "pdf",
+            Self::Png => "png",
+            Self::Midi => "mid",
+            Self::PlaybackMap => "json",
+            Self::Info | Self::Unroll => "txt",
+        }
+    }
+
+    /// Whether output goes to files next to the inputs when no `-o` is given.
+    fn writes_files_by_default(self) -> bool {
+        matches!(self, Self::Render | Self::Pdf | Self::Png | Self::Midi)
+    }
+}
+
+struct Args {
+    command: Command,
+    inputs: Vec<String>,
+    output: Option<String>,
+    width: Option<f64>,
+    paper: PageSize,
+    transpose: i32,
+    jianpu: bool,
+    pages: bool,
+    horizontal: bool,
+    tab_parts: Vec<usize>,
+    tab: TabOptions,
+    diagrams: bool,
+    display: RenderOptions,
+    midi: MidiOptions,
+    png: RasterOptions,
+}
+
+fn main() -> ExitCode {
+    let argv: Vec<String> = std::env::args().skip(1).collect();
+    if argv.is_empty() || argv.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{USAGE}");
+        return if argv.is_empty() { ExitCode::from(2) } else { ExitCode::SUCCESS };
+    }
+    let args = match parse_args(&argv) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("scorelib: {e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut files = Vec::new();
+    let mut failed = false;
+    for pattern in &args.inputs {
+        let matches = expand_glob(pattern);
+        if matches.is_empty() {
+            eprintln!("scorelib: no files match '{pattern}'");
+            failed = true;
+        }
+        files.extend(matches);
+    }
+
+    let to_dir = files.len() > 1;
+
+    // Two inputs with the same name would write over each other's output
+    let mut claimed: std::collections::HashMap<PathBuf, &Path> = std::collections::HashMap::new();
+    for file in &files {
+        let Some(path) = destination(&args, file, to_dir) else { continue };
+        if let Some(other) = claimed.insert(path.clone(), file).filter(|other| other != file) {
+            eprintln!(
+                "scorelib: {} and {} would both write '{}'",
+                other.display(), file.display(), path.display(),
+            );
+            return ExitCode::from(2);
+        }
+    }
+
+    if to_dir {
+        if let Some(dir) = args.output.as_deref().filter(|o| *o != "-") {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                eprintln!("scorelib: cannot create '{dir}': {e}");
+                return ExitCode::from(1);
+            }
+        }
+    }
+
+    for file in &files {
+        if let Err(e) = run(&args, file, to_dir) {
+            eprintln!("scorelib: {}: {e}", file.display());
+            failed = true;
+        }
+    }
+
+    if failed { ExitCode::from(1) } else { ExitCode::SUCCESS }
+}
+
+/// Run the command on one input file.
+fn run(args: &Args, file: &Path, to_dir: bool) -> Result<(), String> {
+    let mut score = parse_file(file)?;
+    transpose_score(&mut score, args.transpose);
+    for &part in &args.tab_parts {
+        add_tab_staff(&mut score, part, &args.tab)?;
+    }
+    if args.diagrams {
+        add_chord_diagrams(&mut score, &args.tab.tuning);
+    }
+
+    if args.pages && args.command == Command::Render {
+        let path = destination(args, file, to_dir)
+            .ok_or("--pages writes a file per page and cannot write to stdout")?;
+        for (i, page) in render_score_to_svg_pages(&score, &PageOptions::default(), &args.display).iter().enumerate() {
+            write_output(file, &page_path(&path, i + 1), page.as_bytes())?;
+        }
+        return Ok(());
+    }
+
+    let output: Vec<u8> = match args.command {
+        Command::Render if args.jianpu => render_score_to_jianpu_svg(&score, args.width).into_bytes(),
+        Command::Render if args.horizontal => render_score_to_horizontal_svg(&score, &args.display).into_bytes(),
+        Command::Render => render_score_to_svg_with_options(&score, args.width, &args.display).into_bytes(),
+        Command::Pdf => render_score_to_pdf(&score, args.paper, &args.display),
+        Command::Png => render_score_to_png(&score, &args.png, &args.display)?,
+        Command::Midi => generate_midi_from_score(&score, &args.midi),
+        Command::PlaybackMap if args.jianpu => {
+            jianpu_playback_map_from_score(&score, args.width).into_bytes()
+        }
+        Command::PlaybackMap if args.horizontal => horizontal_playback_map_from_score(&score, &args.display).into_bytes(),
+        Command::PlaybackMap if args.pages => {
+            paged_playback_map_from_score(&score, &PageOptions::default(), &args.display).into_bytes()
+        }
+        Command::PlaybackMap => playback_map_with_options_from_score(&score, args.width, &args.display).into_bytes(),
+        Command::Info => score_info(&score).into_bytes(),
+        Command::Unroll => play_order(&score).into_bytes(),
+    };
+
+    match destination(args, file, to_dir) {
+        Some(path) => write_output(file, &path, &output)?,
+        None => {
+            use std::io::Write;
+            let mut stdout = std::io::stdout().lock();
+            if to_dir && matches!(args.command, Command::Info | Command::Unroll) {
+                let _ = writeln!(stdout, "── {} ──", file.display());
+            }
+            stdout.write_all(&output).map_err(|e| format!("cannot write to stdout: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Where the output for `file` goes; `None` is stdout.
+fn destination(args: &Args, file: &Path, to_dir: bool) -> Option<PathBuf> {
+    match args.output.as_deref() {
+        Some("-") => None,
+        Some(dir) if to_dir => Some(Path::new(dir).join(output_name(file, args.command))),
+        Some(path) => Some(PathBuf::from(path)),
+        None if args.command.writes_files_by_default() => {
+            Some(file.with_extension(args.command.extension()))
+        }
+        None => None,
+    }
+}
+
+fn write_output(file: &Path, path: &Path, output: &[u8]) -> Result<(), String> {
+    std::fs::write(path, output).map_err(|e| format!("cannot write '{}': {e}", path.display()))?;
+    eprintln!("{} → {}", file.display(), path.display());
+    Ok(())
+}
+
+/// `<stem>-<n>.<ext>` next to `path` for page `n`.
+fn page_path(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("score");
+    let name = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{stem}-{n}.{ext}"),
+        None => format!("{stem}-{n}"),
+    };
+    path.with_file_name(name)
+}
+
+/// `<input stem>.<ext>` for an input file.
+fn output_name(file: &Path, command: Command) -> String {
+    let stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or("score");
+    format!("{stem}.{}", command.extension())
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Argument parsing
+// ═══════════════════════════════════════════════════════════════════════
+
+fn parse_args(argv: &[String]) -> Result<Args, String> {
+    let command = Command::from_name(&argv[0])
+        .ok_or_else(|| format!("unknown command '{}'", argv[0]))?;
+    let mut args = Args {
+        command,
+        inputs: Vec::new(),
+        output: None,
+        width: None,
+        paper: PageSize::A4,
+        transpose: 0,
+        jianpu: false,
+        pages: false,
+        horizontal: false,
+        tab_parts: Vec::new(),
+        tab: TabOptions::default(),
+        diagrams: false,
+        display: RenderOptions::default(),
+        midi: MidiOptions::default(),
+        png: RasterOptions::default(),
+    };
+
+    let mut iter = argv[1..].iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next().cloned().ok_or_else(|| format!("{name} needs a value"))
+        };
+        match arg.as_str() {
+            "-o" | "--output" => args.output = Some(value(arg)?),
+            "-w" | "--width" => args.width = Some(parse_number(arg, &value(arg)?)?),
+            "-t" | "--transpose" => args.transpose = parse_number(arg, &value(arg)?)?,
+            "--paper" => {
+                args.paper = match value(arg)?.to_ascii_lowercase().as_str() {
+                    "a4" => PageSize::A4,
+                    "letter" => PageSize::Letter,
+                    other => return Err(format!("unknown paper size '{other}'")),
+                }
+            }
+            "--jianpu" => args.jianpu = true,
+            "--pages" => args.pages = true,
+            "--horizontal" => args.horizontal = true,
+            "--tab" => args.tab_parts.push(parse_number(arg, &value(arg)?)?),
+            "--diagrams" => args.diagrams = true,
+            "--tuning" => args.tab.tuning = parse_tuning(&value(arg)?)?,
+            "--theme" => {
+                let name = value(arg)?;
+                args.display.theme = Theme::from_name(&name).ok_or_else(|| format!("unknown theme '{name}'"))?;
+            }
+            "--staff-scale" => args.display.staff_scale = parse_number(arg, &value(arg)?)?,
+            "--font" => args.display.font = FontFamily::from_name(&value(arg)?),
+            "--no-chords" => args.display.chord_symbols = false,
+            "--no-lyrics" => args.display.lyrics = false,
+            "--no-measure-numbers" => args.display.measure_numbers = false,
+            "--note-names" => {
+                let names = value(arg)?;
+                args.display.note_names =
+                    NoteNames::from_name(&names).ok_or_else(|| format!("unknown note names '{names}'"))?;
+            }
+            "--names-inside" => args.display.note_name_position = LabelPosition::Inside,
+            "--color-notes" => args.display.colored_noteheads = true,
+            "--fingering" => args.display.finger_numbers = true,
+            "--dpi" => args.png.dpi = parse_number(arg, &value(arg)?)?,
+            "--page" => args.png.region = RasterRegion::Page(parse_number(arg, &value(arg)?)?),
+            "--measures" => {
+                let spec = value(arg)?;
+                let (first, last) = spec.split_once('-')
+                    .ok_or_else(|| format!("{arg} expects <first>-<last>, got '{spec}'"))?;
+                args.png.region = RasterRegion::Measures {
+                    first: parse_number(arg, first)?,
+                    last: parse_number(arg, last)?,
+                };
+            }
+            "--no-melody" => args.midi.include_melody = false,
+            "--piano" => args.midi.include_piano = true,
+            "--bass" => args.midi.include_bass = true,
+            "--strings" => args.midi.include_strings = true,
+            "--drums" => args.midi.include_drums = true,
+            "--no-metronome" => args.midi.include_metronome = false,
+            "--melody-channel" => {
+                let channel: u8 = parse_number(arg, &value(arg)?)?;
+                if channel > 15 {
+                    return Err(format!("{arg} must be 0-15"));
+                }
+                args.midi.melody_channel = channel;
+            }
+            "--energy" => {
+                args.midi.energy = match value(arg)?.as_str() {
+                    "soft" => Energy::Soft,
+                    "medium" => Energy::Medium,
+                    "strong" => Energy::Strong,
+                    other => return Err(format!("unknown energy '{other}'")),
+                }
+            }
+            "--exclude" => part_options(&mut args.midi, parse_number(arg, &value(arg)?)?).include = false,
+            "--mute" => part_options(&mut args.midi, parse_number(arg, &value(arg)?)?).mute = true,
+            "--volume" => {
+                let spec = value(arg)?;
+                let (part, volume) = spec.split_once('=')
+                    .ok_or_else(|| format!("{arg} expects <part>=<volume>, got '{spec}'"))?;
+                let volume: u8 = parse_number(arg, volume)?;
+                part_options(&mut args.midi, parse_number(arg, part)?).volume = volume.min(127);
+            }
+            s if s.starts_with('-') && s != "-" => return Err(format!("unknown option '{s}'")),
+            _ => args.inputs.push(arg.clone()),
+        }
+    }
+
+    args.midi.transpose = args.transpose;
+    args.png.page_size = args.paper;
+    if [args.jianpu, args.pages, args.horizontal].iter().filter(|&&b| b).count() > 1 {
+        return Err("--jianpu, --pages and --horizontal cannot be combined".to_string());
+    }
+    if args.jianpu && args.display != RenderOptions::default() {
+        return Err("display options cannot be used with --jianpu".to_string());
+    }
+    if args.inputs.is_empty() {
+        return Err("no input files".to_string());
+    }
+    Ok(args)
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value.trim().parse().map_err(|_| format!("invalid value '{value}' for {option}"))
+}
+
+/// The options of part `idx`, growing the list as needed.
+fn part_options(options: &mut MidiOptions, idx: usize) -> &mut PartOptions {
+    if options.parts.len() <= idx {
+        options.parts.resize(idx + 1, PartOptions::default());
+    }
+    &mut options.parts[idx]
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Globs
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Expand a glob pattern into the matching files, sorted.  A pattern
+/// without wildcards is returned as-is (missing files fail when read).
+fn expand_glob(pattern: &str) -> Vec<PathBuf> {
+    if !pattern.contains(['*', '?']) {
+        return vec![PathBuf::from(pattern)];
+    }
+    let path = Path::new(pattern);
+    let (root, components): (PathBuf, Vec<String>) = {
+        let mut root = PathBuf::new();
+        let mut components = Vec::new();
+        for c in path.components() {
+            let s = c.as_os_str().to_string_lossy().into_owned();
+            if components.is_empty() && !s.contains(['*', '?']) {
+                root.push(c);
+            } else {
+                components.push(s);
+            }
+        }
+        (root, components)
+    };
+
+    let mut out = Vec::new();
+    let start = if root.as_os_str().is_empty() { PathBuf::from(".") } else { root.clone() };
+    glob_walk(&start, root.as_os_str().is_empty(), &components, &mut out);
+    out.sort();
+    out
+}
+
+/// Match `components` against the entries below `dir`.  `relative` strips
+/// the leading "./" so results look like the pattern the user typed.
+fn glob_walk(dir: &Path, relative: bool, components: &[String], out: &mut Vec<PathBuf>) {
+    let Some((first, rest)) = components.split_first() else { return };
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    let mut entries: Vec<_> = entries.flatten().collect();
+    entries.sort_by_key(|e| e.file_name());
+
+    if first == "**" {
+        // Zero directories…
+        glob_walk(dir, relative, rest, out);
+    }
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        let path = if relative && dir == Path::new(".") { PathBuf::from(&name) } else { dir.join(&name) };
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        if first == "**" {
+            // …or one more, keeping the `**`
+            if is_dir {
+                glob_walk(&path, false, components, out);
+            }
+        } else if wildcard_match(first, &name) {
+            if rest.is_empty() {
+                if !is_dir {
+                    out.push(path);
+                }
+            } else if is_dir {
+                glob_walk(&path, false, rest, out);
+            }
+        }
+    }
+}
+
+/// Match one path component against a pattern with `*` and `?`.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    let (mut pi, mut ni) = (0, 0);
+    // Position after the last `*`, and the name position it matched up to
+    let mut star: Option<(usize, usize)> = None;
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            pi += 1;
+            star = Some((pi, ni));
+        } else if let Some((sp, sn)) = star {
+            pi = sp;
+            ni = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// info / unroll
+// ═══════════════════════════════════════════════════════════════════════
+
+fn score_info(score: &Score) -> String {
+    let mut out = String::new();
+    // Credits may span several lines; keep each field on one
+    let mut line = |label: &str, value: &str| {
+        let value = value.lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>().join(" / ");
+        out.push_str(&format!("{label:<10} {value}\n"));
+    };
+
+    line("Title", score.title.as_deref().unwrap_or("(untitled)"));
+    if let Some(composer) = &score.composer {
+        line("Composer", composer);
+    }
+
+    for (i, part) in score.parts.iter().enumerate() {
+        let staves = part.measures.iter()
+            .filter_map(|m| m.attributes.as_ref()?.staves)
+            .max()
+            .unwrap_or(1);
+        let mut desc = if part.name.is_empty() { part.id.clone() } else { part.name.clone() };
+        if staves > 1 {
+            desc.push_str(&format!(", {staves} staves"));
+        }
+        if let Some(program) = part.midi_program {
+            desc.push_str(&format!(", program {program}"));
+        }
+        line(if i == 0 { "Parts" } else { "" }, &format!("{}. {desc}", i + 1));
+    }
+
+    let conductor = score.parts.get(timemap::CONDUCTOR_PART);
+    let attributes = || conductor.into_iter().flat_map(|p| &p.measures).filter_map(|m| m.attributes.as_ref());
+    if let Some(key) = attributes().find_map(|a| a.key.as_ref()) {
+        line("Key", &key_name(key.fifths, key.mode.as_deref()));
+    }
+    let mut times: Vec<String> = Vec::new();
+    for t in attributes().filter_map(|a| a.time.as_ref()) {
+        let t = format!("{}/{}", t.beats, t.beat_type);
+        if times.last() != Some(&t) {
+            times.push(t);
+        }
+    }
+    if !times.is_empty() {
+        line("Time", &times.join(", "));
+    }
+
+    let measures = conductor.map_or(0, |p| p.measures.len());
+    let unrolled = unroll(score, timemap::CONDUCTOR_PART);
+    line("Measures", &format!("{measures} ({} played)", unrolled.len()));
+
+    let tmap = generate_timemap(score, timemap::CONDUCTOR_PART, &unrolled);
+    let secs = (total_duration_ms(&tmap) / 1000.0).round() as u64;
+    line("Duration", &format!("{}:{:02}", secs / 60, secs % 60));
+    out
+}
+
+/// Key name from a key signature, e.g. "D major" or "B minor".
+fn key_name(fifths: i32, mode: Option<&str>) -> String {
+    const MAJOR: [&str; 15] = ["Cb", "Gb", "Db", "Ab", "Eb", "Bb", "F", "C", "G", "D", "A", "E", "B", "F#", "C#"];
+    const MINOR: [&str; 15] = ["Ab", "Eb", "Bb", "F", "C", "G", "D", "A", "E", "B", "F#", "C#", "G#", "D#", "A#"];
+    let idx = (fifths.clamp(-7, 7) + 7) as usize;
+    match mode {
+        Some("minor") => format!("{} minor", MINOR[idx]),
+        Some(m) if m != "major" && m != "none" => format!("{} major ({m})", MAJOR[idx]),
+        _ => format!("{} major", MAJOR[idx]),
+    }
+}
+
+/// The play order as measure numbers, ten to a line.
+fn play_order(score: &Score) -> String {
+    let Some(part) = score.parts.get(timemap::CONDUCTOR_PART) else { return String::new() };
+    let numbers: Vec<String> = unroll(score, timemap::CONDUCTOR_PART).iter()
+        .filter_map(|u| part.measures.get(u.original_index))
+        .map(|m| m.number.to_string())
+        .collect();
+    let mut out: String = numbers.chunks(10).map(|c| c.join(" ") + "\n").collect();
+    if out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
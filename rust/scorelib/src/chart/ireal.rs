@@ -0,0 +1,318 @@
+//! iReal Pro reader — `irealb://` and `irealbook://` song URLs.
+//!
+//! A URL holds one or more songs separated by `===`; a playlist ends with
+//! its name.  Each song is a list of `=`-separated fields (title,
+//! composer, style, key, progression…), and the newer `irealb://` format
+//! scrambles the progression.  The progression is a run of cells —
+//! chords, empty cells (spaces) and slashes (`p`) — between barlines
+//! `|`, `[`, `]`, `{`, `}` and `Z`, with time signatures (`T44`),
+//! sections (`*A`), endings (`N1`), segno (`S`), coda (`Q`), bar repeats
+//! (`x`, `r`), no-chord cells (`n`) and staff text in `<…>`.
+
+use super::*;
+
+/// Marks the start of a scrambled `irealb://` progression.
+const MUSIC_PREFIX: &str = "1r34LbKcu7";
+
+/// Parse the first song of an iReal Pro URL into a Score.  The URL may
+/// be embedded in other text, such as the HTML page iReal Pro exports.
+pub fn parse_ireal(url: &str) -> Result<Score, String> {
+    parse_ireal_playlist(url)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "iReal Pro URL has no songs".to_string())
+}
+
+/// Parse every song of an iReal Pro URL (a single song or a playlist).
+pub fn parse_ireal_playlist(url: &str) -> Result<Vec<Score>, String> {
+    let start = url.find("irealb://")
+        .or_else(|| url.find("irealbook://"))
+        .ok_or_else(|| "No irealb:// or irealbook:// URL found".to_string())?;
+    let link = &url[start..];
+    let end = link.find(['"', '\n', '\r']).unwrap_or(link.len());
+    let link = percent_decode(&link[..end]);
+    let (body, scrambled) = match link.strip_prefix("irealb://") {
+        Some(body) => (body, true),
+        None => (&link["irealbook://".len()..], false),
+    };
+
+    let mut songs: Vec<&str> = body.split("===").collect();
+    if songs.len() > 1 && songs.last().is_some_and(|s| !s.contains('=')) {
+        songs.pop(); // playlist name
+    }
+    songs.into_iter()
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| parse_song(s, scrambled))
+        .collect()
+}
+
+fn parse_song(text: &str, scrambled: bool) -> Result<Score, String> {
+    let fields: Vec<&str> = text.split('=').collect();
+    let field = |i: usize| fields.get(i).map(|f| f.trim()).filter(|f| !f.is_empty());
+    // irealb: title, composer, -, style, key, -, progression, -, bpm
+    // irealbook: title, composer, style, key, -, progression
+    let (style, key, music, bpm) = match scrambled {
+        true => (field(3), field(4), field(6), field(8)),
+        false => (field(2), field(3), field(5), None),
+    };
+    let title = field(0).map(song_title);
+    let music = music.ok_or_else(|| format!("iReal Pro song {:?} has no chord progression", title.as_deref().unwrap_or("")))?;
+    let music = match scrambled {
+        true => unscramble(music.split_once(MUSIC_PREFIX).map_or(music, |(_, m)| m)),
+        false => music.to_string(),
+    };
+
+    let header = ChartHeader {
+        title,
+        composer: field(1).map(composer_name),
+        style: style.map(String::from),
+        key: key.and_then(|k| match k.strip_suffix('-') {
+            Some(tonic) => key_for(tonic, true),
+            None => key_for(k, false),
+        }),
+        bpm: bpm.and_then(|b| b.parse::<f64>().ok()).filter(|&b| b > 0.0),
+    };
+    build_score(header, parse_progression(&music)?)
+}
+
+/// iReal files titles as "Girl From Ipanema, The".
+fn song_title(title: &str) -> String {
+    for article in ["The", "A", "An"] {
+        if let Some(rest) = title.strip_suffix(&format!(", {article}")) {
+            return format!("{article} {rest}");
+        }
+    }
+    title.to_string()
+}
+
+/// iReal files composers as "Last First"; two-word names are swapped back.
+fn composer_name(name: &str) -> String {
+    let words: Vec<&str> = name.split_whitespace().collect();
+    match words.as_slice() {
+        [last, first] => format!("{first} {last}"),
+        _ => words.join(" "),
+    }
+}
+
+// ─── URL decoding ────────────────────────────────────────────────────
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Undo the `irealb://` scrambling: each full 50-character block has its
+/// outer characters mirrored, then three shorthand substitutions expand.
+fn unscramble(music: &str) -> String {
+    let chars: Vec<char> = music.chars().collect();
+    let mut out = String::with_capacity(music.len());
+    let mut rest = chars.as_slice();
+    while rest.len() > 51 {
+        let (block, tail) = rest.split_at(50);
+        let mut mirrored = block.to_vec();
+        for i in (0..5).chain(10..24) {
+            mirrored[i] = block[49 - i];
+            mirrored[49 - i] = block[i];
+        }
+        out.extend(mirrored);
+        rest = tail;
+    }
+    out.extend(rest);
+    out.replace("Kcl", "| x").replace("LZ", " |").replace("XyQ", "   ")
+}
+
+// ─── Progression ─────────────────────────────────────────────────────
+
+/// Bars being collected from a progression.
+#[derive(Default)]
+struct Progression {
+    bars: Vec<ChartBar>,
+    bar: ChartBar,
+    /// Empty cells seen before the bar's first chord
+    leading_blanks: usize,
+    /// Between a closing barline and the next opening one, where blank
+    /// cells only pad out the chart's row
+    between_bars: bool,
+    /// The bar repeats the previous one (`x`)
+    repeat_one: bool,
+    /// The bar repeats the two before it (`r`)
+    repeat_two: bool,
+    /// Cells for the bar after an `r` bar
+    second_of_two: Option<Vec<Option<Harmony>>>,
+}
+
+impl Progression {
+    /// Whether the current bar has cells yet, rather than only marks
+    /// such as a section or time signature waiting for its first chord.
+    fn has_music(&self) -> bool {
+        !self.bar.cells.is_empty() || self.leading_blanks > 0 || self.repeat_one || self.repeat_two
+    }
+
+    /// Close the current bar, if it has any cells.
+    fn end_bar(&mut self) {
+        if !self.has_music() {
+            return;
+        }
+        let n = self.bars.len();
+        let pending = self.second_of_two.take();
+        if self.repeat_one && n >= 1 {
+            self.bar.cells = self.bars[n - 1].cells.clone();
+        } else if self.repeat_two && n >= 2 {
+            self.bar.cells = self.bars[n - 2].cells.clone();
+            self.second_of_two = Some(self.bars[n - 1].cells.clone());
+        } else if let Some(cells) = pending.filter(|_| self.bar.cells.iter().all(Option::is_none)) {
+            self.bar.cells = cells;
+        }
+        if self.bar.cells.is_empty() && self.leading_blanks > 0 {
+            self.bar.cells.push(None);
+        }
+        self.bars.push(std::mem::take(&mut self.bar));
+        self.leading_blanks = 0;
+        self.repeat_one = false;
+        self.repeat_two = false;
+    }
+
+    /// The bar a closing barline belongs to: the current one, or the one
+    /// just closed when nothing has been written since.
+    fn closing_bar(&mut self) -> &mut ChartBar {
+        if !self.has_music() {
+            if let Some(last) = self.bars.last_mut() {
+                return last;
+            }
+        }
+        &mut self.bar
+    }
+}
+
+fn parse_progression(music: &str) -> Result<Vec<ChartBar>, String> {
+    let chars: Vec<char> = music.chars().collect();
+    let mut p = Progression::default();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        if !matches!(c, ' ' | ',' | 'Y') {
+            p.between_bars = false;
+        }
+        match c {
+            '|' => p.end_bar(),
+            '[' => {
+                p.end_bar();
+                if let Some(last) = p.bars.last_mut().filter(|b| b.bar_style.is_none() && !b.repeat_end) {
+                    last.bar_style = Some("light-light");
+                }
+            }
+            '{' => {
+                p.end_bar();
+                p.bar.repeat_start = true;
+            }
+            ']' | '}' | 'Z' => {
+                let bar = p.closing_bar();
+                match c {
+                    '}' => bar.repeat_end = true,
+                    ']' => bar.bar_style = Some("light-light"),
+                    _ => bar.bar_style = Some("light-heavy"),
+                }
+                p.end_bar();
+                p.between_bars = true;
+            }
+            'T' if i + 1 < chars.len() && chars[i].is_ascii_digit() && chars[i + 1].is_ascii_digit() => {
+                let (a, b) = (chars[i].to_digit(10).unwrap() as i32, chars[i + 1].to_digit(10).unwrap() as i32);
+                p.bar.time = Some(if (a, b) == (1, 2) { (12, 8) } else { (a, b) });
+                i += 2;
+            }
+            '*' if i < chars.len() => {
+                p.bar.section = Some(match chars[i] {
+                    'i' => "Intro".to_string(),
+                    'v' | 'V' => "Verse".to_string(),
+                    s => s.to_string(),
+                });
+                i += 1;
+            }
+            'N' if i < chars.len() && chars[i].is_ascii_digit() => {
+                if chars[i] != '0' {
+                    p.bar.ending = Some(chars[i].to_string());
+                }
+                i += 1;
+            }
+            'S' => p.bar.segno = true,
+            'Q' => p.bar.coda = true,
+            '<' => {
+                let end = chars[i..].iter().position(|&ch| ch == '>').map_or(chars.len(), |e| i + e);
+                let text: String = chars[i..end].iter().collect();
+                let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !text.is_empty() {
+                    p.bar.text.push(text);
+                }
+                i = end + 1;
+            }
+            '(' => {
+                // Alternate chords are shown small above and not played
+                i = chars[i..].iter().position(|&ch| ch == ')').map_or(chars.len(), |e| i + e + 1);
+            }
+            ' ' if p.between_bars => {}
+            ' ' if p.bar.cells.is_empty() => p.leading_blanks += 1,
+            ' ' | 'p' => p.bar.cells.push(None),
+            'x' => p.repeat_one = true,
+            'r' => p.repeat_two = true,
+            'n' => p.bar.cells.push(Some(no_chord())),
+            'W' => {
+                // Invisible root: the chord carries on with a new bass
+                i = skip_chord(&chars, i);
+                p.bar.cells.push(None);
+            }
+            'A'..='G' => {
+                let end = skip_chord(&chars, i);
+                let symbol: String = chars[i - 1..end].iter().collect();
+                let harmony = parse_chord(&symbol)
+                    .ok_or_else(|| format!("Unrecognized chord '{symbol}' in iReal Pro progression"))?;
+                p.bar.cells.push(Some(harmony));
+                i = end;
+            }
+            // Separators, fermatas, spacing and chord size
+            _ => {}
+        }
+    }
+    p.end_bar();
+    // Staff text after the final barline belongs to the last bar
+    let trailing = std::mem::take(&mut p.bar.text);
+    if let Some(last) = p.bars.last_mut() {
+        last.text.extend(trailing);
+    }
+    Ok(p.bars)
+}
+
+/// End of a chord symbol whose root letter ends at `i`: accidental,
+/// quality and an optional `/bass`.
+fn skip_chord(chars: &[char], mut i: usize) -> usize {
+    if chars.get(i).is_some_and(|&c| c == 'b' || c == '#') {
+        i += 1;
+    }
+    while chars.get(i).is_some_and(|&c| c.is_ascii_digit() || "^-+#bhosuadlt".contains(c)) {
+        i += 1;
+    }
+    if chars.get(i) == Some(&'/') && chars.get(i + 1).is_some_and(|c| ('A'..='G').contains(c)) {
+        i += 2;
+        if chars.get(i).is_some_and(|&c| c == 'b' || c == '#') {
+            i += 1;
+        }
+    }
+    i
+}
@@ -0,0 +1,457 @@
+//! Chord chart import — builds lead-sheet scores from iReal Pro song URLs
+//! and plain-text chord charts, so the accompaniment can back songs that
+//! exist only as chord changes.
+//!
+//! Both readers produce a list of bars, each holding the chart's cells
+//! (a chord, or an empty cell that carries the previous one on) plus its
+//! barlines, endings, section marks and navigation.  The builder turns
+//! that into a single-part score with one slash note per beat and a
+//! `Harmony` on the beat where each chord starts, so rendering, MIDI
+//! export and the accompaniment engine all work unchanged.  Slash notes
+//! don't sound; the chords do, through the accompaniment tracks.
+
+mod ireal;
+mod text;
+
+pub use ireal::{parse_ireal, parse_ireal_playlist};
+pub use text::parse_chord_chart;
+
+use crate::model::*;
+
+/// Divisions per quarter note in built scores (enough for dotted quarters).
+const DIVISIONS: i32 = 2;
+
+// ─── Chart model ─────────────────────────────────────────────────────
+
+/// Song-level fields of a chart.
+#[derive(Debug, Default)]
+struct ChartHeader {
+    title: Option<String>,
+    composer: Option<String>,
+    /// Style or groove, e.g. "Medium Swing"
+    style: Option<String>,
+    key: Option<Key>,
+    bpm: Option<f64>,
+}
+
+/// One bar of a chart as written.
+#[derive(Debug, Default, Clone)]
+struct ChartBar {
+    /// Chord cells in order; `None` is an empty or slash cell
+    cells: Vec<Option<Harmony>>,
+    /// Time signature change at the start of the bar
+    time: Option<(i32, i32)>,
+    /// Key change at the start of the bar
+    key: Option<Key>,
+    /// Section label (rehearsal mark)
+    section: Option<String>,
+    repeat_start: bool,
+    repeat_end: bool,
+    /// Ending number starting at this bar
+    ending: Option<String>,
+    /// How an ending finishes at this bar: "stop" or "discontinue"
+    ending_stop: Option<&'static str>,
+    /// Right barline style: "light-light" or "light-heavy"
+    bar_style: Option<&'static str>,
+    segno: bool,
+    /// Coda sign: the first one in a chart is the "To Coda" jump
+    coda: bool,
+    /// Staff text such as "D.C. al Coda"
+    text: Vec<String>,
+    /// Whether the bar starts a new line of the chart
+    new_system: bool,
+}
+
+// ─── Chord symbols ───────────────────────────────────────────────────
+
+/// Parse a chord symbol in iReal or common spelling: `C^7`, `Cmaj7`,
+/// `F#-7b5`, `Bbø`, `G7alt`, `D/F#`.
+fn parse_chord(text: &str) -> Option<Harmony> {
+    let text = text.replace("6/9", "69");
+    let (main, bass) = match text.split_once('/') {
+        Some((m, b)) => (m, Some(b)),
+        None => (text.as_str(), None),
+    };
+    let (root, quality) = parse_root(main)?;
+    let bass = match bass {
+        Some(b) => Some(parse_root(b).filter(|(_, rest)| rest.is_empty())?.0),
+        None => None,
+    };
+    Some(Harmony { root, kind: chord_kind(quality)?.to_string(), bass, onset: 0, frame: None })
+}
+
+fn parse_root(text: &str) -> Option<(HarmonyRoot, &str)> {
+    let step = text.chars().next().filter(|c| ('A'..='G').contains(c))?;
+    let rest = &text[1..];
+    let (alter, rest) = if let Some(r) = rest.strip_prefix('#') {
+        (Some(1.0), r)
+    } else if let Some(r) = rest.strip_prefix('b') {
+        (Some(-1.0), r)
+    } else {
+        (None, rest)
+    };
+    Some((HarmonyRoot { step: step.to_string(), alter }, rest))
+}
+
+/// MusicXML kind for a chord quality.  Common spellings are first
+/// rewritten in iReal's shorthand (`^` major seventh, `-` minor, `h`
+/// half-diminished, `o` diminished, `+` augmented); extensions and
+/// alterations then fall to the nearest kind (`7b9#11` is dominant).
+fn chord_kind(quality: &str) -> Option<&'static str> {
+    let q = quality
+        .replace(['(', ')'], "")
+        .replace("maj", "^")
+        .replace(['Δ', 'M'], "^")
+        .replace("dim", "o")
+        .replace('°', "o")
+        .replace("min", "-")
+        .replace("mi", "-")
+        .replace('m', "-")
+        .replace('ø', "h")
+        .replace("aug", "+");
+    if !q.chars().all(|c| c.is_ascii_digit() || "^-+#bhosuadlt".contains(c)) {
+        return None;
+    }
+    let kind = match q.as_str() {
+        "" | "2" | "add9" | "add2" => "major",
+        "5" => "power",
+        "6" | "69" => "major-sixth",
+        "-6" | "-69" => "minor-sixth",
+        "o7" => "diminished-seventh",
+        "sus2" => "suspended-second",
+        q if q.starts_with("-^") => "major-minor",
+        q if q.starts_with("-7b5") || q.starts_with('h') => "half-diminished",
+        q if q.starts_with("-7") => "minor-seventh",
+        q if q.starts_with("-9") => "minor-ninth",
+        q if q.starts_with("-11") => "minor-11th",
+        q if q.starts_with('-') => "minor",
+        q if q.starts_with('o') => "diminished",
+        q if q.starts_with('+') => "augmented",
+        q if q.contains("sus") => "suspended-fourth",
+        q if q.starts_with("^9") => "major-ninth",
+        q if q.starts_with("^13") => "major-13th",
+        q if q.starts_with('^') => "major-seventh",
+        q if q.starts_with("7#5") || q.starts_with("7+") => "augmented-seventh",
+        q if q.starts_with("13") => "dominant-13th",
+        q if q.starts_with("11") => "dominant-11th",
+        q if q.starts_with('9') => "dominant-ninth",
+        q if q.starts_with('7') => "dominant",
+        _ => return None,
+    };
+    Some(kind)
+}
+
+/// A "no chord" (N.C.) cell.
+fn no_chord() -> Harmony {
+    Harmony {
+        root: HarmonyRoot { step: "C".to_string(), alter: None },
+        kind: "none".to_string(),
+        bass: None,
+        onset: 0,
+        frame: None,
+    }
+}
+
+/// Key signature for a tonic such as "Eb" or "F#", major or minor.
+fn key_for(tonic: &str, minor: bool) -> Option<Key> {
+    let (root, rest) = parse_root(tonic)?;
+    if !rest.is_empty() {
+        return None;
+    }
+    let base = match root.step.as_str() {
+        "F" => -1,
+        "C" => 0,
+        "G" => 1,
+        "D" => 2,
+        "A" => 3,
+        "E" => 4,
+        _ => 5,
+    };
+    let fifths = base + 7 * root.alter.unwrap_or(0.0) as i32 - if minor { 3 } else { 0 };
+    Some(Key {
+        fifths,
+        mode: Some(if minor { "minor" } else { "major" }.to_string()),
+    })
+}
+
+// ─── Score building ──────────────────────────────────────────────────
+
+/// Build a one-part slash-notation score from a chart.
+fn build_score(header: ChartHeader, mut bars: Vec<ChartBar>) -> Result<Score, String> {
+    if bars.iter().all(|b| b.cells.iter().all(Option::is_none)) {
+        return Err("Chord chart has no chords".to_string());
+    }
+    close_endings(&mut bars);
+
+    let mut time = bars[0].time.unwrap_or((4, 4));
+    let mut seen_coda = false;
+    let mut measures = Vec::with_capacity(bars.len());
+    for (i, bar) in bars.iter().enumerate() {
+        let mut attributes = None;
+        if i == 0 || bar.time.is_some() || bar.key.is_some() {
+            if let Some(t) = bar.time {
+                time = t;
+            }
+            attributes = Some(Attributes {
+                divisions: (i == 0).then_some(DIVISIONS),
+                key: if i == 0 {
+                    Some(bar.key.clone().or_else(|| header.key.clone()).unwrap_or(Key { fifths: 0, mode: Some("major".to_string()) }))
+                } else {
+                    bar.key.clone()
+                },
+                time: (i == 0 || bar.time.is_some()).then_some(TimeSignature { beats: time.0, beat_type: time.1 }),
+                clefs: if i == 0 { vec![Clef { number: 1, sign: "G".to_string(), line: 2, octave_change: None }] } else { Vec::new() },
+                transpose: None,
+                staves: None,
+                staff_details: Vec::new(),
+            });
+        }
+
+        let (beats, beat_len, beat_type, dotted) = beat_grid(time);
+        let notes = (0..beats)
+            .map(|b| slash_note(b * beat_len, beat_len, beat_type, dotted))
+            .collect();
+        let harmonies = place_chords(&bar.cells, beats)
+            .into_iter()
+            .map(|(beat, mut h)| {
+                h.onset = beat * beat_len;
+                h
+            })
+            .collect();
+
+        let mut directions = Vec::new();
+        if i == 0 && (header.bpm.is_some() || header.style.is_some()) {
+            let mut d = blank_direction();
+            d.words = header.style.clone();
+            if let Some(bpm) = header.bpm {
+                d.sound_tempo = Some(bpm);
+                d.metronome = Some(MetronomeMark { beat_unit: "quarter".to_string(), per_minute: bpm.round() as i32, dotted: false });
+            }
+            directions.push(d);
+        }
+        if let Some(section) = &bar.section {
+            let mut d = blank_direction();
+            d.rehearsal = Some(section.clone());
+            directions.push(d);
+        }
+        if bar.segno {
+            let mut d = blank_direction();
+            d.segno = true;
+            directions.push(d);
+        }
+        if bar.coda {
+            let mut d = blank_direction();
+            if seen_coda {
+                d.coda = true;
+            } else {
+                d.sound_tocoda = true;
+                d.words = Some("To Coda".to_string());
+            }
+            seen_coda = true;
+            directions.push(d);
+        }
+        for text in &bar.text {
+            let mut d = blank_direction();
+            let upper = text.to_uppercase();
+            d.sound_dacapo = upper.starts_with("D.C.");
+            d.sound_dalsegno = upper.starts_with("D.S.");
+            d.sound_fine = upper == "FINE";
+            d.words = Some(text.clone());
+            directions.push(d);
+        }
+
+        measures.push(Measure {
+            number: i as i32 + 1,
+            implicit: false,
+            width: None,
+            attributes,
+            notes,
+            harmonies,
+            barlines: bar_barlines(bar),
+            directions,
+            new_system: bar.new_system && i > 0,
+            new_page: false,
+        });
+    }
+
+    let mut score = Score::new();
+    score.title = header.title;
+    score.composer = header.composer;
+    score.parts.push(Part {
+        id: "P1".to_string(),
+        name: "Chords".to_string(),
+        abbreviation: None,
+        midi_program: None,
+        midi_channel: None,
+        measures,
+    });
+    Ok(score)
+}
+
+/// Beats of a time signature as (count, length in divisions, note type,
+/// dotted).  Compound meters (6/8, 9/8, 12/8) count dotted quarters.
+fn beat_grid((beats, beat_type): (i32, i32)) -> (i32, i32, &'static str, bool) {
+    let beat_type = beat_type.max(1);
+    if beat_type == 8 && beats % 3 == 0 && beats > 3 {
+        return (beats / 3, 3 * DIVISIONS / 2, "quarter", true);
+    }
+    let note_type = match beat_type {
+        1 => "whole",
+        2 => "half",
+        8 => "eighth",
+        _ => "quarter",
+    };
+    (beats.max(1), (4 * DIVISIONS / beat_type).max(1), note_type, false)
+}
+
+/// Beat on which each chord of a bar starts.  Cells are spread evenly
+/// over the beats, so `D-7 G7` with an empty cell after each falls on
+/// beats 1 and 3.  When every cell holds a chord, or the cells don't
+/// line up with the beats, the beats are shared out with the first
+/// chords taking any extra (three chords in 4/4 get 2 + 1 + 1).
+fn place_chords(cells: &[Option<Harmony>], beats: i32) -> Vec<(i32, Harmony)> {
+    let chords: Vec<(usize, &Harmony)> = cells.iter()
+        .enumerate()
+        .filter_map(|(i, c)| c.as_ref().map(|h| (i, h)))
+        .collect();
+    let n = cells.len() as f64;
+    let spread: Vec<i32> = chords.iter()
+        .map(|&(i, _)| (i as f64 * beats as f64 / n).round() as i32)
+        .collect();
+    let distinct = spread.windows(2).all(|w| w[0] < w[1]) && spread.last().is_none_or(|&b| b < beats);
+    let starts = if chords.len() < cells.len() && distinct {
+        spread
+    } else {
+        let count = chords.len().min(beats as usize).max(1) as i32;
+        let (base, extra) = (beats / count, beats % count);
+        (0..count).map(|k| k * base + k.min(extra)).collect()
+    };
+    starts.into_iter().zip(chords).map(|(beat, (_, h))| (beat, h.clone())).collect()
+}
+
+/// Give every ending a stop: a first ending stops at its repeat, and
+/// later ones at the next double or final barline, the next repeat,
+/// section or ending, or the end of the chart.
+fn close_endings(bars: &mut [ChartBar]) {
+    let mut open: Option<usize> = None;
+    let mut closes: Vec<(usize, &'static str)> = Vec::new();
+    for (i, bar) in bars.iter().enumerate() {
+        if open.is_some() && i > 0 && (bar.ending.is_some() || bar.repeat_start || bar.section.is_some()) {
+            closes.push((i - 1, "discontinue"));
+            open = None;
+        }
+        if bar.ending.is_some() {
+            open = Some(i);
+        }
+        if open.is_some() {
+            if bar.repeat_end {
+                closes.push((i, "stop"));
+                open = None;
+            } else if bar.bar_style.is_some() {
+                closes.push((i, "discontinue"));
+                open = None;
+            }
+        }
+    }
+    if open.is_some() {
+        closes.push((bars.len() - 1, "discontinue"));
+    }
+    for (i, kind) in closes {
+        bars[i].ending_stop = Some(kind);
+    }
+}
+
+/// Left and right barlines of a bar: repeats, endings and double bars.
+fn bar_barlines(bar: &ChartBar) -> Vec<Barline> {
+    let mut barlines = Vec::new();
+    if bar.repeat_start || bar.ending.is_some() {
+        barlines.push(Barline {
+            location: "left".to_string(),
+            bar_style: bar.repeat_start.then(|| "heavy-light".to_string()),
+            repeat: bar.repeat_start.then(|| Repeat { direction: "forward".to_string() }),
+            ending: bar.ending.as_ref().map(|number| Ending {
+                number: number.clone(),
+                ending_type: "start".to_string(),
+                text: Some(format!("{number}.")),
+            }),
+        });
+    }
+    if bar.repeat_end || bar.bar_style.is_some() || bar.ending_stop.is_some() {
+        barlines.push(Barline {
+            location: "right".to_string(),
+            bar_style: match bar.repeat_end {
+                true => Some("light-heavy".to_string()),
+                false => bar.bar_style.map(String::from),
+            },
+            repeat: bar.repeat_end.then(|| Repeat { direction: "backward".to_string() }),
+            ending: bar.ending_stop.map(|kind| Ending {
+                number: String::new(),
+                ending_type: kind.to_string(),
+                text: None,
+            }),
+        });
+    }
+    barlines
+}
+
+fn slash_note(onset: i32, duration: i32, note_type: &str, dot: bool) -> Note {
+    Note {
+        pitch: Some(Pitch { step: "B".to_string(), octave: 4, alter: None }),
+        duration,
+        voice: Some(1),
+        note_type: Some(note_type.to_string()),
+        stem: Some("none".to_string()),
+        beams: Vec::new(),
+        rest: false,
+        measure_rest: false,
+        chord: false,
+        dot,
+        accidental: None,
+        tie_start: false,
+        tie_stop: false,
+        staff: None,
+        default_x: None,
+        default_y: None,
+        lyrics: Vec::new(),
+        grace: false,
+        grace_slash: false,
+        slurs: Vec::new(),
+        onset,
+        time_modification: None,
+        tuplets: Vec::new(),
+        articulations: Vec::new(),
+        ornaments: Vec::new(),
+        ornament_upper_alter: None,
+        ornament_lower_alter: None,
+        fermata: None,
+        notehead: Some("slash".to_string()),
+        string: None,
+        fret: None,
+        fingering: None,
+    }
+}
+
+fn blank_direction() -> Direction {
+    Direction {
+        placement: Some("above".to_string()),
+        sound_tempo: None,
+        metronome: None,
+        words: None,
+        segno: false,
+        coda: false,
+        rehearsal: None,
+        sound_dacapo: false,
+        sound_dalsegno: false,
+        sound_fine: false,
+        sound_tocoda: false,
+        words_font_style: None,
+        octave_shift_type: None,
+        octave_shift_size: 0,
+        onset: 0,
+        staff: None,
+        dynamics: None,
+        sound_dynamics: None,
+        wedge_type: None,
+        wedge_number: 0,
+    }
+}
@@ -0,0 +1,182 @@
+//! Plain-text chord chart reader.
+//!
+//! ```text
+//! Title: Autumn Leaves
+//! Composer: Joseph Kosma
+//! Key: G minor
+//! Time: 4/4
+//! Tempo: 120
+//! Style: Medium Swing
+//!
+//! [A]
+//! |: Cm7 | F7 | Bbmaj7 | Ebmaj7 |
+//! | Am7b5 | D7 |1 Gm | % :|2 Gm / G7 / ||
+//! ```
+//!
+//! Header fields are `Name: value` lines.  Bars are separated by `|`,
+//! with `|:` and `:|` for repeats, `||` for a double bar, `|]` for the
+//! final bar and `|1`, `|2` starting numbered endings.  Inside a bar,
+//! chords share the beats evenly (the first ones taking any extra) unless
+//! `/` marks the beats a chord is held for.  `%` repeats the previous
+//! bar, `N.C.` is no chord, a time signature such as `3/4` changes the
+//! meter, and a line holding only `[label]` or `label:` starts a section.
+//! Lines starting with `#` are comments.
+
+use super::*;
+
+/// Parse a plain-text chord chart into a Score.
+pub fn parse_chord_chart(text: &str) -> Result<Score, String> {
+    let mut header = ChartHeader::default();
+    let mut bars: Vec<ChartBar> = Vec::new();
+    let mut next = ChartBar::default();
+
+    for (line_no, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if !line.contains('|') {
+            if let Some((name, value)) = line.split_once(':').filter(|(n, _)| !n.trim().is_empty()) {
+                let value = value.trim();
+                match name.trim().to_lowercase().as_str() {
+                    "title" => header.title = Some(value.to_string()),
+                    "composer" => header.composer = Some(value.to_string()),
+                    "style" => header.style = Some(value.to_string()),
+                    "tempo" | "bpm" => {
+                        header.bpm = Some(value.parse::<f64>().map_err(|_| format!("Line {}: invalid tempo '{value}'", line_no + 1))?);
+                    }
+                    "key" => {
+                        let key = parse_key(value).ok_or_else(|| format!("Line {}: invalid key '{value}'", line_no + 1))?;
+                        match bars.is_empty() {
+                            true => header.key = Some(key),
+                            false => next.key = Some(key),
+                        }
+                    }
+                    "time" => {
+                        next.time = Some(parse_time(value).ok_or_else(|| format!("Line {}: invalid time '{value}'", line_no + 1))?);
+                    }
+                    _ if value.is_empty() => next.section = Some(name.trim().to_string()),
+                    _ => return Err(format!("Line {}: unknown field '{}'", line_no + 1, name.trim())),
+                }
+                continue;
+            }
+            if let Some(label) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                next.section = Some(label.trim().to_string());
+                continue;
+            }
+        }
+        next.new_system = true;
+        read_bars(line, line_no + 1, &mut bars, &mut next)?;
+    }
+
+    build_score(header, bars)
+}
+
+/// Read one line of bars, starting each with the marks in `next`.
+fn read_bars(line: &str, line_no: usize, bars: &mut Vec<ChartBar>, next: &mut ChartBar) -> Result<(), String> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut content = String::new();
+    let mut after_barline = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let repeat_end = chars[i] == ':' && chars.get(i + 1) == Some(&'|');
+        if chars[i] != '|' && !repeat_end {
+            content.push(chars[i]);
+            i += 1;
+            continue;
+        }
+
+        // A barline: close the bar before it, then read its marks
+        i += if repeat_end { 2 } else { 1 };
+        if !content.trim().is_empty() || after_barline {
+            bars.push(read_bar(&content, line_no, bars.last(), std::mem::take(next))?);
+        }
+        content.clear();
+        let last = bars.last_mut();
+        match chars.get(i) {
+            Some('|') => {
+                i += 1;
+                if let Some(bar) = last {
+                    bar.bar_style = Some("light-light");
+                }
+            }
+            Some(']') => {
+                i += 1;
+                if let Some(bar) = last {
+                    bar.bar_style = Some("light-heavy");
+                }
+            }
+            _ => {
+                if let Some(bar) = last.filter(|_| repeat_end) {
+                    bar.repeat_end = true;
+                }
+            }
+        }
+        if chars.get(i) == Some(&':') {
+            next.repeat_start = true;
+            i += 1;
+        }
+        let digits: String = chars[i..].iter().take_while(|c| c.is_ascii_digit() || **c == ',').collect();
+        let is_time = chars.get(i + digits.chars().count()) == Some(&'/');
+        if !digits.is_empty() && !is_time {
+            next.ending = Some(digits.trim_end_matches(',').to_string());
+            i += digits.chars().count();
+            if chars.get(i) == Some(&'.') {
+                i += 1;
+            }
+        }
+        after_barline = true;
+    }
+    if !content.trim().is_empty() {
+        bars.push(read_bar(&content, line_no, bars.last(), std::mem::take(next))?);
+    }
+    Ok(())
+}
+
+/// The cells of one bar: chords, and `/` or `.` for held beats.
+fn read_bar(content: &str, line_no: usize, previous: Option<&ChartBar>, mut bar: ChartBar) -> Result<ChartBar, String> {
+    for token in content.split_whitespace() {
+        match token {
+            "/" | "." => bar.cells.push(None),
+            "%" => bar.cells = previous.map(|p| p.cells.clone()).unwrap_or_default(),
+            "N.C." | "NC" | "N.C" => bar.cells.push(Some(no_chord())),
+            _ => {
+                if let Some(time) = parse_time(token) {
+                    bar.time = Some(time);
+                    continue;
+                }
+                let harmony = parse_chord(token)
+                    .ok_or_else(|| format!("Line {line_no}: unrecognized chord '{token}'"))?;
+                bar.cells.push(Some(harmony));
+            }
+        }
+    }
+    if bar.cells.is_empty() {
+        bar.cells.push(None);
+    }
+    Ok(bar)
+}
+
+/// `3/4`, `6/8`…
+fn parse_time(text: &str) -> Option<(i32, i32)> {
+    let (beats, beat_type) = text.split_once('/')?;
+    let beats: i32 = beats.trim().parse().ok()?;
+    let beat_type: i32 = beat_type.trim().parse().ok()?;
+    (beats > 0 && [1, 2, 4, 8, 16].contains(&beat_type)).then_some((beats, beat_type))
+}
+
+/// `G`, `Gm`, `G minor`, `Bb major`, `F#-`.
+fn parse_key(text: &str) -> Option<Key> {
+    let text = text.trim();
+    let split = text.char_indices()
+        .skip(1)
+        .find(|&(_, c)| c != '#' && c != 'b')
+        .map_or(text.len(), |(i, _)| i);
+    let (tonic, mode) = text.split_at(split);
+    let minor = match mode.trim().to_lowercase().as_str() {
+        "" | "maj" | "major" => false,
+        "m" | "-" | "min" | "minor" => true,
+        _ => return None,
+    };
+    key_for(tonic, minor)
+}
@@ -0,0 +1,326 @@
+//! Chord diagrams: fretboard voicings for chord symbols.
+//!
+//! A harmony's diagram comes from its `<frame>` when the score has one.
+//! Otherwise `add_chord_diagrams` voices it: common chords on standard
+//! guitar and ukulele use the familiar open and barre shapes from a
+//! built-in library, and everything else — other chords, other tunings —
+//! is found by searching four-fret hand positions up the neck for the
+//! easiest shape that sounds every chord tone.
+
+use crate::model::*;
+
+/// Highest fret a searched voicing starts at.
+const MAX_POSITION: i32 = 12;
+/// Frets under the hand in one position.
+const POSITION_SPAN: i32 = 4;
+/// Frets a diagram shows.
+const DIAGRAM_FRETS: i32 = 4;
+
+/// Cost of each string left unplayed.
+const MUTE_COST: f64 = 2.0;
+/// Extra cost of an unplayed string between played ones.
+const INNER_MUTE_COST: f64 = 6.0;
+/// Cost of a lowest note other than the root (or the slash bass).
+const BASS_COST: f64 = 5.0;
+/// Cost of a barre.
+const BARRE_COST: f64 = 1.5;
+/// Cost per fret of the hand position, so open chords win ties.
+const POSITION_COST: f64 = 0.5;
+
+/// Chord shapes on standard guitar, lowest string first ("x" = not
+/// played), indexed by root from C to B.
+const GUITAR_SHAPES: &[(&str, [&str; 12])] = &[
+    ("major", ["x32010", "x46664", "xx0232", "x68886", "022100", "133211",
+               "244322", "320003", "466544", "x02220", "x13331", "x24442"]),
+    ("minor", ["x35543", "x46654", "xx0231", "x68876", "022000", "133111",
+               "244222", "355333", "466444", "x02210", "x13321", "x24432"]),
+    ("dominant", ["x32310", "x46464", "xx0212", "x68686", "020100", "131211",
+                  "242322", "320001", "464544", "x02020", "x13131", "x21202"]),
+    ("major-seventh", ["x32000", "x46564", "xx0222", "x68786", "021100", "xx3210",
+                       "xx4321", "320002", "xx6543", "x02120", "x13231", "x24342"]),
+    ("minor-seventh", ["x35343", "x46454", "xx0211", "x68676", "020000", "131111",
+                       "242222", "353333", "464444", "x02010", "x13121", "x20202"]),
+];
+
+/// Chord shapes on ukulele (G4 C4 E4 A4), as for the guitar.
+const UKULELE_SHAPES: &[(&str, [&str; 12])] = &[
+    ("major", ["0003", "1114", "2220", "0331", "4442", "2010",
+               "3121", "0232", "5343", "2100", "3211", "4322"]),
+    ("minor", ["0333", "1104", "2210", "3321", "0432", "1013",
+               "2120", "0231", "1342", "2000", "3111", "4222"]),
+    ("dominant", ["0001", "1112", "2223", "3334", "1202", "2313",
+                  "3424", "0212", "1323", "0100", "1211", "2322"]),
+    ("major-seventh", ["0002", "1113", "2224", "3335", "1302", "2413",
+                       "3524", "0222", "1333", "1100", "3210", "4321"]),
+    ("minor-seventh", ["3333", "1102", "2213", "3324", "0202", "1313",
+                       "2424", "0211", "1322", "0000", "1111", "2222"]),
+];
+
+/// Open strings, as MIDI notes from the lowest string, of the tunings
+/// with a shape library.
+const GUITAR_TUNING: [i32; 6] = [40, 45, 50, 55, 59, 64];
+const UKULELE_TUNING: [i32; 4] = [67, 60, 64, 69];
+
+/// Give every chord symbol without a diagram one voiced for `tuning`
+/// (open strings from the lowest string up, as `parse_tuning` returns).
+/// Chords the tuning can't voice, and "N.C.", are left without.
+pub fn add_chord_diagrams(score: &mut Score, tuning: &[Pitch]) {
+    for harmony in score.parts.iter_mut().flat_map(|p| &mut p.measures).flat_map(|m| &mut m.harmonies) {
+        if harmony.frame.is_none() {
+            harmony.frame = chord_frame(harmony, tuning);
+        }
+    }
+}
+
+/// The diagram for a chord symbol on an instrument tuned to `tuning`.
+/// Slash chords are searched for, so the bass note is the lowest.
+pub fn chord_frame(harmony: &Harmony, tuning: &[Pitch]) -> Option<Frame> {
+    let open: Vec<i32> = tuning.iter().map(Pitch::to_midi).collect();
+    let root = pitch_class(&harmony.root);
+    let bass = harmony.bass.as_ref().map_or(root, pitch_class);
+    let shape = (bass == root)
+        .then(|| library_shape(&harmony.kind, root, &open))
+        .flatten()
+        .or_else(|| search_voicing(root, bass, chord_tones(&harmony.kind)?, &open))?;
+    Some(frame_for(&shape))
+}
+
+/// Move a diagram's shape by `semitones`, up or down the neck, whichever
+/// keeps it lower.  Every string moves by the same interval, so the
+/// shape sounds the transposed chord on any tuning.
+pub(crate) fn transpose_frame(frame: &mut Frame, semitones: i32) {
+    let up = semitones.rem_euclid(12);
+    let lowest = frame.notes.iter().map(|n| n.fret).min().unwrap_or(0);
+    let shift = if up != 0 && lowest >= 12 - up { up - 12 } else { up };
+    if shift == 0 {
+        return;
+    }
+    let mut shape: Vec<Option<i32>> = vec![None; frame.strings.max(0) as usize];
+    for note in &frame.notes {
+        let i = frame.strings - note.string;
+        if (0..frame.strings).contains(&i) {
+            shape[i as usize] = Some(note.fret + shift);
+        }
+    }
+    *frame = frame_for(&shape);
+}
+
+fn pitch_class(root: &HarmonyRoot) -> i32 {
+    let step = match root.step.as_str() {
+        "C" => 0, "D" => 2, "E" => 4, "F" => 5,
+        "G" => 7, "A" => 9, "B" => 11,
+        _ => 0,
+    };
+    (step + root.alter.unwrap_or(0.0).round() as i32).rem_euclid(12)
+}
+
+/// Intervals above the root of each chord kind.
+fn chord_tones(kind: &str) -> Option<&'static [i32]> {
+    let tones: &[i32] = match kind {
+        "major" => &[0, 4, 7],
+        "minor" => &[0, 3, 7],
+        "augmented" => &[0, 4, 8],
+        "diminished" => &[0, 3, 6],
+        "dominant" | "dominant-seventh" => &[0, 4, 7, 10],
+        "major-seventh" => &[0, 4, 7, 11],
+        "minor-seventh" => &[0, 3, 7, 10],
+        "diminished-seventh" => &[0, 3, 6, 9],
+        "augmented-seventh" => &[0, 4, 8, 10],
+        "half-diminished" => &[0, 3, 6, 10],
+        "major-minor" => &[0, 3, 7, 11],
+        "major-sixth" => &[0, 4, 7, 9],
+        "minor-sixth" => &[0, 3, 7, 9],
+        "dominant-ninth" => &[0, 4, 7, 10, 2],
+        "major-ninth" => &[0, 4, 7, 11, 2],
+        "minor-ninth" => &[0, 3, 7, 10, 2],
+        "dominant-11th" => &[0, 7, 10, 5],
+        "minor-11th" => &[0, 3, 7, 10, 5],
+        "dominant-13th" => &[0, 4, 10, 9],
+        "major-13th" => &[0, 4, 11, 9],
+        "minor-13th" => &[0, 3, 10, 9],
+        "suspended-second" => &[0, 2, 7],
+        "suspended-fourth" => &[0, 5, 7],
+        "power" => &[0, 7],
+        _ => return None,
+    };
+    Some(tones)
+}
+
+fn library_shape(kind: &str, root: i32, open: &[i32]) -> Option<Vec<Option<i32>>> {
+    let library = if open == GUITAR_TUNING {
+        GUITAR_SHAPES
+    } else if open == UKULELE_TUNING {
+        UKULELE_SHAPES
+    } else {
+        return None;
+    };
+    let kind = if kind == "dominant-seventh" { "dominant" } else { kind };
+    let (_, shapes) = library.iter().find(|(k, _)| *k == kind)?;
+    Some(parse_shape(shapes[root as usize]))
+}
+
+/// "x32010" → [None, Some(3), Some(2), Some(0), Some(1), Some(0)].
+fn parse_shape(shape: &str) -> Vec<Option<i32>> {
+    shape.chars().map(|c| c.to_digit(10).map(|d| d as i32)).collect()
+}
+
+/// The cheapest playable shape sounding every tone of the chord.  The
+/// fifth may be left out of chords of four or more notes.
+fn search_voicing(root: i32, bass: i32, tones: &[i32], open: &[i32]) -> Option<Vec<Option<i32>>> {
+    let chord: Vec<i32> = tones.iter().map(|t| (root + t).rem_euclid(12)).collect();
+    let required: Vec<i32> = chord.iter().copied()
+        .filter(|&pc| tones.len() < 4 || pc != (root + 7) % 12)
+        .collect();
+    if required.len() > open.len() {
+        return None;
+    }
+
+    let mut best: Option<(f64, Vec<Option<i32>>)> = None;
+    for position in 1..=MAX_POSITION {
+        // Each string is unplayed, open, or fretted within the position
+        let choices: Vec<Vec<Option<i32>>> = open.iter()
+            .map(|&o| {
+                std::iter::once(None)
+                    .chain((std::iter::once(0).chain(position..position + POSITION_SPAN))
+                        .filter(|f| chord.contains(&((o + f) % 12)))
+                        .map(Some))
+                    .collect()
+            })
+            .collect();
+        let mut shape = vec![None; open.len()];
+        search(&choices, 0, &mut shape, &mut |shape| {
+            if let Some(cost) = voicing_cost(shape, open, root, bass, &required) {
+                if best.as_ref().is_none_or(|(c, _)| cost < *c) {
+                    best = Some((cost, shape.to_vec()));
+                }
+            }
+        });
+    }
+    best.map(|(_, shape)| shape)
+}
+
+fn search(
+    choices: &[Vec<Option<i32>>],
+    string: usize,
+    shape: &mut Vec<Option<i32>>,
+    visit: &mut dyn FnMut(&[Option<i32>]),
+) {
+    if string == choices.len() {
+        visit(shape);
+        return;
+    }
+    for &choice in &choices[string] {
+        shape[string] = choice;
+        search(choices, string + 1, shape, visit);
+    }
+}
+
+/// Cost of a shape, or `None` when it misses a chord tone or needs more
+/// than four fingers.
+fn voicing_cost(shape: &[Option<i32>], open: &[i32], root: i32, bass: i32, required: &[i32]) -> Option<f64> {
+    let notes: Vec<i32> = shape.iter().zip(open)
+        .filter_map(|(f, o)| f.map(|f| o + f))
+        .collect();
+    if notes.len() < required.len().max(open.len().min(3))
+        || !required.iter().all(|pc| notes.iter().any(|n| n % 12 == *pc))
+    {
+        return None;
+    }
+    let (fingers, barre) = fingers(shape)?;
+
+    let played: Vec<usize> = (0..shape.len()).filter(|&i| shape[i].is_some()).collect();
+    let (first, last) = (played[0], played[played.len() - 1]);
+    let inner_mutes = (first..=last).filter(|&i| shape[i].is_none()).count();
+    let lowest = notes.iter().min().copied().unwrap_or(0);
+    let position = shape.iter().flatten().filter(|&&f| f > 0).min().copied().unwrap_or(0);
+
+    let mut cost = (shape.len() - played.len()) as f64 * MUTE_COST
+        + inner_mutes as f64 * INNER_MUTE_COST
+        + position as f64 * POSITION_COST
+        + fingers as f64 * 0.1;
+    // A re-entrant tuning like the ukulele's has no bass string to care about
+    let ascending = open.windows(2).all(|w| w[0] < w[1]);
+    if ascending && lowest % 12 != bass && lowest % 12 != root {
+        cost += BASS_COST;
+    }
+    if barre.is_some() {
+        cost += BARRE_COST;
+    }
+    Some(cost)
+}
+
+/// Fingers a shape needs, and the strings (by index from the lowest)
+/// a first-finger barre covers when it needs one.  `None` when it can't
+/// be fingered with four.
+fn fingers(shape: &[Option<i32>]) -> Option<(usize, Option<(usize, usize)>)> {
+    let fretted: Vec<(usize, i32)> = shape.iter().enumerate()
+        .filter_map(|(i, f)| f.filter(|&f| f > 0).map(|f| (i, f)))
+        .collect();
+    if fretted.len() <= 4 {
+        return Some((fretted.len(), None));
+    }
+    // The barre lies across the lowest fret, from its lowest string up;
+    // every string under it must be fretted
+    let low = fretted.iter().map(|&(_, f)| f).min()?;
+    let start = fretted.iter().find(|&&(_, f)| f == low)?.0;
+    let end = fretted.iter().rev().find(|&&(_, f)| f == low)?.0;
+    if (start..=end).any(|i| shape[i] == Some(0)) {
+        return None;
+    }
+    let fingers = 1 + fretted.iter().filter(|&&(_, f)| f > low).count();
+    (fingers <= 4).then_some((fingers, Some((start, end))))
+}
+
+/// A diagram of a shape given lowest string first.
+fn frame_for(shape: &[Option<i32>]) -> Frame {
+    let strings = shape.len() as i32;
+    let fretted = shape.iter().flatten().filter(|&&f| f > 0);
+    let low = fretted.clone().min().copied().unwrap_or(1);
+    let high = fretted.max().copied().unwrap_or(0);
+    let barre = fingers(shape).and_then(|(_, barre)| barre);
+
+    let notes = shape.iter().enumerate()
+        .filter_map(|(i, fret)| {
+            let fret = (*fret)?;
+            let barre = barre.and_then(|(start, end)| match i {
+                _ if fret != low => None,
+                i if i == start => Some("start".to_string()),
+                i if i == end => Some("stop".to_string()),
+                _ => None,
+            });
+            Some(FrameNote { string: strings - i as i32, fret, fingering: None, barre })
+        })
+        .collect();
+    Frame {
+        strings,
+        frets: DIAGRAM_FRETS.max(high - low + 1),
+        first_fret: (high > DIAGRAM_FRETS).then_some(low),
+        notes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every library shape sounds exactly its chord's tones.
+    #[test]
+    fn library_shapes_spell_their_chords() {
+        for (open, library) in [(&GUITAR_TUNING[..], GUITAR_SHAPES), (&UKULELE_TUNING[..], UKULELE_SHAPES)] {
+            for (kind, shapes) in library {
+                let tones = chord_tones(kind).unwrap();
+                for (root, shape) in shapes.iter().enumerate() {
+                    let chord: Vec<i32> = tones.iter().map(|t| (root as i32 + t) % 12).collect();
+                    let notes: Vec<i32> = parse_shape(shape).iter().zip(open)
+                        .filter_map(|(f, o)| f.map(|f| (o + f) % 12))
+                        .collect();
+                    assert_eq!(notes.len(), open.len() - shape.matches('x').count(), "{shape}");
+                    assert!(notes.iter().all(|n| chord.contains(n)), "{kind} on {root}: {shape}");
+                    let required = chord.iter().filter(|&&pc| tones.len() < 4 || pc != (root as i32 + 7) % 12);
+                    assert!(required.clone().all(|pc| notes.contains(pc)), "{kind} on {root}: {shape}");
+                    assert!(fingers(&parse_shape(shape)).is_some(), "{kind} on {root}: {shape}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Dynamics timeline: turns the score's dynamics markings into note-on
+//! velocities for the melody tracks.
+//!
+//! Markings are collected from the part's directions in play order (so a
+//! `p` in the first ending doesn't leak into the second one) and resolved
+//! into a step function of wall-clock time.  Sforzando-type marks (`sfz`,
+//! `fz`, `fp`…) accent the notes that start with them without changing the
+//! running level (except that `fp`/`sfp` drop to piano afterwards).
+//!
+//! Hairpins (`<wedge>`) ramp the level linearly from the level in force at
+//! their start to the dynamics mark that follows their end.  A hairpin with
+//! no mark after it moves one dynamic step up or down.
+
+use std::collections::HashMap;
+
+use crate::model::{Direction, Part};
+use crate::timemap::TimemapEntry;
+use crate::unroller::UnrolledMeasure;
+
+/// Velocity used until the score gives a dynamics marking (mezzo-forte).
+pub const DEFAULT_VELOCITY: u8 = 80;
+
+/// How far a sforzando lifts the running level.
+const ACCENT_BOOST: u8 = 24;
+/// Minimum velocity of an accented (sforzando) note.
+const ACCENT_MIN_VELOCITY: u8 = 100;
+/// Two events closer than this (in ms) are considered simultaneous.
+const TIME_EPSILON_MS: f64 = 1.0;
+/// Velocity change of a hairpin that isn't followed by a dynamics mark
+/// (about one step, e.g. mp → mf).
+const HAIRPIN_DEFAULT_STEP: i32 = 16;
+
+/// Velocity for a dynamics level marking.  Returns None for marks that
+/// don't set a level on their own (e.g. `sfz`) or are unknown.
+pub fn dynamics_velocity(mark: &str) -> Option<u8> {
+    match mark {
+        "pppppp" | "ppppp" | "pppp" => Some(8),
+        "ppp" => Some(16),
+        "pp" => Some(33),
+        "p" => Some(49),
+        "mp" => Some(64),
+        "mf" => Some(80),
+        "f" => Some(96),
+        "ff" => Some(112),
+        "fff" | "ffff" | "fffff" | "ffffff" => Some(127),
+        // Forte-piano family: accent, then continue at the trailing level
+        "fp" | "sfp" | "sfzp" => Some(49),
+        "sfpp" => Some(33),
+        _ => None,
+    }
+}
+
+/// Whether a dynamics mark accents the note it's attached to.
+fn is_accent_mark(mark: &str) -> bool {
+    matches!(
+        mark,
+        "sf" | "sfz" | "sffz" | "fz" | "rf" | "rfz" | "fp" | "sfp" | "sfpp" | "sfzp"
+    )
+}
+
+/// Running dynamic level over time for one melody track.
+#[derive(Debug, Clone, Default)]
+pub struct DynamicsTimeline {
+    /// (time_ms, velocity) level changes, sorted by time
+    levels: Vec<(f64, u8)>,
+    /// Times (ms) of sforzando-type accents, sorted
+    accents: Vec<f64>,
+    /// Hairpin ramps: (start_ms, end_ms, from_velocity, to_velocity), sorted by start
+    ramps: Vec<(f64, f64, u8, u8)>,
+}
+
+impl DynamicsTimeline {
+    /// Collect the dynamics of `part` in play order.
+    ///
+    /// With a `staff_filter` (one track per staff of a piano part), markings
+    /// attached to that staff are used; if the staff has none of its own, it
+    /// follows the markings of the whole part, the way pianists read dynamics
+    /// written once between the staves.
+    pub fn build(
+        part: &Part,
+        unrolled: &[UnrolledMeasure],
+        timemap: &[TimemapEntry],
+        staff_filter: Option<i32>,
+    ) -> Self {
+        let staff_has_own = staff_filter.is_some_and(|sf| {
+            part.measures.iter()
+                .flat_map(|m| &m.directions)
+                .any(|d| has_dynamics(d) && d.staff == Some(sf))
+        });
+
+        let mut timeline = DynamicsTimeline::default();
+        // Hairpins in play order: (start_ms, end_ms, crescendo)
+        let mut hairpins: Vec<(f64, f64, bool)> = Vec::new();
+        let mut open_wedges: HashMap<i32, (f64, bool)> = HashMap::new();
+
+        for (i, um) in unrolled.iter().enumerate() {
+            // Parts shorter than the conductor part end early
+            let Some(measure) = part.measures.get(um.original_index) else { continue };
+            let entry = &timemap[i];
+            let divisions = entry.divisions.max(1) as f64;
+
+            for dir in &measure.directions {
+                if !has_dynamics(dir) && dir.wedge_type.is_none() {
+                    continue;
+                }
+                if staff_has_own && dir.staff != staff_filter {
+                    continue;
+                }
+
+                let time_ms = direction_time_ms(dir, entry, divisions);
+                match dir.wedge_type.as_deref() {
+                    Some("crescendo") => {
+                        open_wedges.insert(dir.wedge_number, (time_ms, true));
+                    }
+                    Some("diminuendo") => {
+                        open_wedges.insert(dir.wedge_number, (time_ms, false));
+                    }
+                    Some("stop") => {
+                        if let Some((start_ms, crescendo)) = open_wedges.remove(&dir.wedge_number) {
+                            if time_ms > start_ms {
+                                hairpins.push((start_ms, time_ms, crescendo));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+
+                let mark = dir.dynamics.as_deref().unwrap_or("");
+                let level = dir
+                    .sound_dynamics
+                    .map(|pct| (pct * 0.9).round().clamp(1.0, 127.0) as u8)
+                    .or_else(|| dynamics_velocity(mark));
+
+                if let Some(vel) = level {
+                    timeline.levels.push((time_ms, vel));
+                }
+                if is_accent_mark(mark) {
+                    timeline.accents.push(time_ms);
+                }
+            }
+        }
+
+        timeline.levels.sort_by(|a, b| a.0.total_cmp(&b.0));
+        timeline.accents.sort_by(|a, b| a.total_cmp(b));
+
+        hairpins.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (h, &(start_ms, end_ms, crescendo)) in hairpins.iter().enumerate() {
+            let from = timeline.step_level_at(start_ms);
+            // Target: the first mark at or after the hairpin's end, as long as
+            // it comes before the next hairpin starts
+            let next_start = hairpins.get(h + 1).map_or(f64::MAX, |n| n.0);
+            let to = timeline.levels.iter()
+                .find(|&&(t, _)| t >= end_ms - TIME_EPSILON_MS && t < next_start)
+                .map(|&(_, v)| v)
+                .unwrap_or_else(|| {
+                    let step = if crescendo { HAIRPIN_DEFAULT_STEP } else { -HAIRPIN_DEFAULT_STEP };
+                    (from as i32 + step).clamp(1, 127) as u8
+                });
+
+            timeline.ramps.push((start_ms, end_ms, from, to));
+            // Hold the reached level until the next mark
+            let pos = timeline.levels.partition_point(|&(t, _)| t <= end_ms);
+            timeline.levels.insert(pos, (end_ms, to));
+        }
+
+        timeline
+    }
+
+    /// Level from the marks alone (ignoring hairpin ramps).
+    fn step_level_at(&self, time_ms: f64) -> u8 {
+        let mut vel = DEFAULT_VELOCITY;
+        for &(t, v) in &self.levels {
+            if t > time_ms + TIME_EPSILON_MS {
+                break;
+            }
+            vel = v;
+        }
+        vel
+    }
+
+    /// Running dynamic level just before any accent at `time_ms`,
+    /// interpolated inside hairpins.
+    fn level_at(&self, time_ms: f64) -> u8 {
+        for &(start, end, from, to) in &self.ramps {
+            if time_ms >= start && time_ms < end {
+                let frac = (time_ms - start) / (end - start);
+                let vel = from as f64 + (to as f64 - from as f64) * frac;
+                return vel.round().clamp(1.0, 127.0) as u8;
+            }
+        }
+        self.step_level_at(time_ms)
+    }
+
+    /// Note-on velocity for a note starting at `time_ms`.
+    pub fn velocity_at(&self, time_ms: f64) -> u8 {
+        let accented = self.accents.iter().any(|&t| (t - time_ms).abs() <= TIME_EPSILON_MS);
+        if accented {
+            // Accent relative to the level in force before the mark
+            let before = self.level_at(time_ms - 2.0 * TIME_EPSILON_MS);
+            before.saturating_add(ACCENT_BOOST).clamp(ACCENT_MIN_VELOCITY, 127)
+        } else {
+            self.level_at(time_ms)
+        }
+    }
+}
+
+fn has_dynamics(dir: &Direction) -> bool {
+    dir.dynamics.is_some() || dir.sound_dynamics.is_some()
+}
+
+/// Absolute time of a direction, from its onset within the measure.
+fn direction_time_ms(dir: &Direction, entry: &TimemapEntry, divisions: f64) -> f64 {
+    entry.time_at_quarters(dir.onset as f64 / divisions)
+}
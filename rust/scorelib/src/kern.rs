@@ -0,0 +1,940 @@
+//! Humdrum **kern import — converts a kern file into the Score model.
+//!
+//! A Humdrum file is a grid of tab-separated spines read top to bottom.
+//! Each `**kern` spine is one staff, with the lowest staff leftmost;
+//! spines tagged with the same `*partN` (a piano's two staves) share a
+//! part.  `*^` splits a spine into two voices and `*v` joins them again.
+//! A `**text` or `**silbe` spine holds lyrics for the kern spine to its
+//! left.
+//!
+//! Interpretations set clefs (`*clefG2`), keys (`*k[f#]`, `*G:`), meters
+//! (`*M3/4`), tempo (`*MM120`) and part names (`*I"Violin`); `=` records
+//! are barlines, and the `!!!OTL` and `!!!COM` reference records give the
+//! title and composer.  Note tokens carry the duration, pitch, ties,
+//! slurs, beams (`L`/`J`), grace notes, stems and common articulations.
+//! Kern spells every pitch in full, so the accidentals shown are worked
+//! out from the key and the earlier notes of the measure.
+
+use std::collections::HashMap;
+
+use crate::model::*;
+
+/// Working resolution in divisions per quarter note, fine enough for
+/// tuplets up to nine; reduced to the coarsest one that fits every note
+/// once the file is read.
+const TICKS_PER_QUARTER: i64 = 40320;
+
+/// Parse a Humdrum file's **kern spines into a Score.
+pub fn parse_kern(text: &str) -> Result<Score, String> {
+    let mut reader = KernReader::default();
+    for (line_no, raw) in text.lines().enumerate() {
+        let line = raw.trim_end_matches('\r');
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(record) = line.strip_prefix("!!!") {
+            reader.reference(record);
+            continue;
+        }
+        if line.starts_with('!') {
+            continue;
+        }
+        let tokens: Vec<&str> = line.split('\t').collect();
+        if reader.spines.is_empty() {
+            if tokens[0].starts_with("**") {
+                reader.start(&tokens);
+            }
+            continue;
+        }
+        if reader.columns.is_empty() {
+            break; // every spine has ended
+        }
+        if tokens.len() != reader.columns.len() {
+            return Err(format!(
+                "Line {}: expected {} spines, found {}",
+                line_no + 1,
+                reader.columns.len(),
+                tokens.len()
+            ));
+        }
+        if tokens[0].starts_with('*') {
+            reader.interpretations(&tokens);
+        } else if tokens[0].starts_with('=') {
+            reader.barline(tokens[0]);
+        } else {
+            reader.data(&tokens);
+        }
+    }
+    reader.finish()
+}
+
+// ─── Reader state ────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SpineKind {
+    Kern,
+    /// Lyrics for a kern spine: (spine, verse)
+    Lyrics(usize, i32),
+    Other,
+}
+
+/// One `**kern` spine: a staff.
+#[derive(Default)]
+struct Staff {
+    name: Option<String>,
+    abbreviation: Option<String>,
+    /// `*partN` tag grouping staves into one part
+    part: Option<String>,
+    verses: i32,
+    /// One entry per bar read so far, the last being the current one
+    bars: Vec<StaffBar>,
+}
+
+/// A staff's content in one bar.
+#[derive(Default)]
+struct StaffBar {
+    /// Notes with the voice they belong to
+    notes: Vec<(i32, Note)>,
+    clef: Option<Clef>,
+    key: Option<Key>,
+    time: Option<TimeSignature>,
+}
+
+/// Information shared by every spine in one bar.
+#[derive(Default)]
+struct BarInfo {
+    number: Option<i32>,
+    repeat_start: bool,
+    repeat_end: bool,
+    bar_style: Option<&'static str>,
+    tempo: Option<(i64, f64)>,
+    /// End of the longest voice, in ticks
+    length: i64,
+    /// Meter in effect, for spotting a pickup bar
+    meter: Option<(i32, i32)>,
+}
+
+/// A column of the grid: one voice of a spine.
+#[derive(Debug, Clone)]
+struct Column {
+    spine: usize,
+    voice: i32,
+    cursor: i64,
+    beams: usize,
+    slurs: i32,
+    tuplet: Option<TupletRun>,
+}
+
+/// Tuplet notes being bracketed: the latest note and the ticks left
+/// until the group is complete.
+#[derive(Debug, Clone)]
+struct TupletRun {
+    last: usize,
+    left: i64,
+}
+
+#[derive(Default)]
+struct KernReader {
+    title: Option<String>,
+    composer: Option<String>,
+    spines: Vec<SpineKind>,
+    staves: Vec<Staff>,
+    columns: Vec<Column>,
+    bars: Vec<BarInfo>,
+    meter: Option<(i32, i32)>,
+}
+
+impl KernReader {
+    /// `!!!KEY: value` reference records.
+    fn reference(&mut self, record: &str) {
+        let Some((key, value)) = record.split_once(':') else { return };
+        let value = value.trim();
+        if value.is_empty() {
+            return;
+        }
+        match key.trim() {
+            "OTL" if self.title.is_none() => self.title = Some(value.to_string()),
+            "COM" if self.composer.is_none() => self.composer = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    /// The exclusive interpretations naming each spine.
+    fn start(&mut self, tokens: &[&str]) {
+        for (i, token) in tokens.iter().enumerate() {
+            let kind = match *token {
+                "**kern" => SpineKind::Kern,
+                "**text" | "**silbe" => {
+                    match (0..i).rev().find(|&s| self.spines[s] == SpineKind::Kern) {
+                        Some(kern) => {
+                            self.staves[kern].verses += 1;
+                            SpineKind::Lyrics(kern, self.staves[kern].verses)
+                        }
+                        None => SpineKind::Other,
+                    }
+                }
+                _ => SpineKind::Other,
+            };
+            self.spines.push(kind);
+            self.staves.push(Staff { bars: vec![StaffBar::default()], ..Staff::default() });
+            self.columns.push(Column { spine: i, voice: 1, cursor: 0, beams: 0, slurs: 0, tuplet: None });
+        }
+        self.bars.push(BarInfo::default());
+    }
+
+    fn interpretations(&mut self, tokens: &[&str]) {
+        if tokens.iter().any(|t| matches!(*t, "*^" | "*v" | "*-" | "*x")) {
+            self.manipulate(tokens);
+            return;
+        }
+        for (i, token) in tokens.iter().enumerate() {
+            let (spine, cursor) = (self.columns[i].spine, self.columns[i].cursor);
+            if self.spines[spine] == SpineKind::Kern {
+                self.interpretation(spine, cursor, token);
+            }
+        }
+    }
+
+    /// Split, join, end and exchange spines.
+    fn manipulate(&mut self, tokens: &[&str]) {
+        let columns = std::mem::take(&mut self.columns);
+        let mut next: Vec<Column> = Vec::new();
+        let mut i = 0;
+        while i < columns.len() {
+            let column = columns[i].clone();
+            match tokens[i] {
+                "*^" => {
+                    let voice = columns.iter().chain(&next)
+                        .filter(|c| c.spine == column.spine)
+                        .map(|c| c.voice)
+                        .max()
+                        .unwrap_or(1);
+                    let mut right = column.clone();
+                    right.voice = voice + 1;
+                    right.beams = 0;
+                    right.slurs = 0;
+                    right.tuplet = None;
+                    next.push(column);
+                    next.push(right);
+                }
+                "*v" => {
+                    let mut merged = column;
+                    while i + 1 < columns.len() && tokens[i + 1] == "*v" && columns[i + 1].spine == merged.spine {
+                        i += 1;
+                        merged.cursor = merged.cursor.max(columns[i].cursor);
+                        merged.voice = merged.voice.min(columns[i].voice);
+                    }
+                    next.push(merged);
+                }
+                "*-" => {}
+                "*x" if i + 1 < columns.len() && tokens[i + 1] == "*x" => {
+                    next.push(columns[i + 1].clone());
+                    next.push(column);
+                    i += 1;
+                }
+                _ => next.push(column),
+            }
+            i += 1;
+        }
+        self.columns = next;
+    }
+
+    /// A tandem interpretation on a kern spine.
+    fn interpretation(&mut self, spine: usize, cursor: i64, token: &str) {
+        let staff = &mut self.staves[spine];
+        let bar = staff.bars.last_mut().unwrap();
+        if let Some(clef) = token.strip_prefix("*clef") {
+            if let Some(clef) = parse_clef(clef) {
+                bar.clef = Some(clef);
+            }
+        } else if let Some(signature) = token.strip_prefix("*k[") {
+            let fifths = signature.matches('#').count() as i32 - signature.matches('-').count() as i32;
+            let mode = bar.key.take().and_then(|k| k.mode);
+            bar.key = Some(Key { fifths, mode });
+        } else if let Some(bpm) = token.strip_prefix("*MM") {
+            let info = self.bars.last_mut().unwrap();
+            if let Ok(bpm) = bpm.parse::<f64>() {
+                info.tempo.get_or_insert((cursor, bpm));
+            }
+        } else if let Some(meter) = token.strip_prefix("*M") {
+            let time = meter.split_once('/').and_then(|(b, t)| Some((b.parse::<i32>().ok()?, t.parse::<i32>().ok()?)));
+            if let Some((beats, beat_type)) = time.filter(|&(b, t)| b > 0 && t > 0) {
+                bar.time = Some(TimeSignature { beats, beat_type });
+                self.meter = Some((beats, beat_type));
+                self.bars.last_mut().unwrap().meter = self.meter;
+            }
+        } else if let Some(name) = token.strip_prefix("*I\"") {
+            staff.name = Some(name.to_string());
+        } else if let Some(abbreviation) = token.strip_prefix("*I'") {
+            staff.abbreviation = Some(abbreviation.to_string());
+        } else if token.starts_with("*part") {
+            staff.part = Some(token[1..].to_string());
+        } else if let Some(tonic) = token.strip_prefix('*').and_then(|t| t.strip_suffix(':')) {
+            // Key designation: `*G:` for G major, `*e-:` for E♭ minor
+            let mut chars = tonic.chars();
+            if chars.next().is_some_and(|c| ('a'..='g').contains(&c.to_ascii_lowercase()))
+                && chars.all(|c| c == '#' || c == '-')
+            {
+                let minor = tonic.starts_with(|c: char| c.is_ascii_lowercase());
+                let mode = Some(if minor { "minor" } else { "major" }.to_string());
+                match bar.key.as_mut() {
+                    Some(key) => key.mode = mode,
+                    None => bar.key = Some(Key { fifths: 0, mode }),
+                }
+            }
+        }
+    }
+
+    /// A barline ends the current bar and starts the next.
+    fn barline(&mut self, token: &str) {
+        let marks = token.trim_start_matches('=');
+        let digits: String = marks.chars().take_while(|c| c.is_ascii_digit()).collect();
+        let number = digits.parse::<i32>().ok();
+        let has_music = self.staves.iter().any(|s| !s.bars.last().unwrap().notes.is_empty());
+
+        if has_music {
+            let length = self.columns.iter().map(|c| c.cursor).max().unwrap_or(0);
+            let info = self.bars.last_mut().unwrap();
+            info.length = length;
+            if token.starts_with("==") {
+                info.bar_style = Some("light-heavy");
+            } else if marks.contains(":|") {
+                info.repeat_end = true;
+            } else if marks.contains("||") {
+                info.bar_style = Some("light-light");
+            } else if marks.contains("|!") {
+                info.bar_style = Some("light-heavy");
+            }
+            self.bars.push(BarInfo { meter: self.meter, ..BarInfo::default() });
+            for staff in &mut self.staves {
+                staff.bars.push(StaffBar::default());
+            }
+            for column in &mut self.columns {
+                column.cursor = 0;
+                column.tuplet = None;
+            }
+        }
+        let info = self.bars.last_mut().unwrap();
+        if number.is_some() {
+            info.number = number;
+        }
+        if marks.contains("|:") {
+            info.repeat_start = true;
+        }
+    }
+
+    /// A data record: notes, rests and lyrics.
+    fn data(&mut self, tokens: &[&str]) {
+        // First pitched note started on this line in each kern spine
+        let mut line_notes: HashMap<usize, usize> = HashMap::new();
+        for (i, token) in tokens.iter().enumerate() {
+            let spine = self.columns[i].spine;
+            if self.spines[spine] == SpineKind::Kern && *token != "." {
+                if let Some(idx) = self.note_token(i, token) {
+                    line_notes.entry(spine).or_insert(idx);
+                }
+            }
+        }
+        for (i, token) in tokens.iter().enumerate() {
+            let SpineKind::Lyrics(kern, verse) = self.spines[self.columns[i].spine] else { continue };
+            let Some(&idx) = line_notes.get(&kern) else { continue };
+            if let Some(lyric) = read_syllable(token, verse) {
+                self.staves[kern].bars.last_mut().unwrap().notes[idx].1.lyrics.push(lyric);
+            }
+        }
+    }
+
+    /// Read a note, chord or rest token in a column; returns the index of
+    /// its first note in the bar when it is pitched and takes time.
+    fn note_token(&mut self, col: usize, token: &str) -> Option<usize> {
+        let parts: Vec<KernToken> = token.split(' ').map(read_token).collect();
+        let value = parts.iter().find_map(|p| p.value)?;
+        let column = &mut self.columns[col];
+        let notes = &mut self.staves[column.spine].bars.last_mut().unwrap().notes;
+        let grace = parts.iter().find_map(|p| p.grace);
+        let ticks = if grace.is_some() { 0 } else { value.ticks() };
+
+        if parts.iter().all(|p| p.hidden || (p.pitch.is_none() && !p.rest)) {
+            column.cursor += ticks;
+            return None;
+        }
+
+        let first = notes.len();
+        let (note_type, ratio) = value.written();
+        for part in &parts {
+            if part.pitch.is_none() && !part.rest {
+                continue;
+            }
+            let mut note = blank_note(column.voice);
+            note.pitch = part.pitch.clone();
+            note.rest = part.rest;
+            note.measure_rest = part.measure_rest;
+            note.duration = ticks as i32;
+            note.onset = column.cursor as i32;
+            note.note_type = Some(note_type.to_string());
+            note.dot = value.dots > 0;
+            if let Some((actual, normal)) = ratio.filter(|_| grace.is_none()) {
+                note.time_modification = Some(TimeModification { actual_notes: actual, normal_notes: normal, normal_type: None });
+            }
+            note.chord = notes.len() > first;
+            note.tie_start = part.tie_start;
+            note.tie_stop = part.tie_stop;
+            if part.natural {
+                note.accidental = Some("natural".to_string());
+            }
+            note.grace = grace.is_some();
+            note.grace_slash = grace == Some(true);
+            note.stem = part.stem.or(parts[0].stem).map(String::from);
+            note.articulations = part.articulations.clone();
+            note.ornaments = part.ornaments.clone();
+            if part.fermata {
+                note.fermata = Some("upright".to_string());
+            }
+            for _ in 0..part.slur_stops {
+                note.slurs.push(SlurEvent { slur_type: "stop".to_string(), number: column.slurs.max(1), placement: None });
+                column.slurs = (column.slurs - 1).max(0);
+            }
+            for _ in 0..part.slur_starts {
+                column.slurs += 1;
+                note.slurs.push(SlurEvent { slur_type: "start".to_string(), number: column.slurs, placement: None });
+            }
+            notes.push((column.voice, note));
+        }
+        if notes.len() == first {
+            column.cursor += ticks;
+            return None;
+        }
+
+        // Beams sit on the chord's first note; rests leave them open
+        let begins: usize = parts.iter().map(|p| p.beam_begins).sum();
+        let ends: usize = parts.iter().map(|p| p.beam_ends).sum();
+        let total = column.beams + begins;
+        if !notes[first].1.rest {
+            for level in 1..=total {
+                let ending = level + ends > total;
+                let beam_type = match (level > column.beams, ending) {
+                    (true, true) => continue,
+                    (true, false) => "begin",
+                    (false, true) => "end",
+                    (false, false) => "continue",
+                };
+                notes[first].1.beams.push(Beam { number: level as i32, beam_type: beam_type.to_string() });
+            }
+        }
+        column.beams = total.saturating_sub(ends);
+
+        // Tuplet brackets over each complete group
+        if grace.is_none() {
+            match (ratio, column.tuplet.take()) {
+                (Some((actual, _)), run) => {
+                    let mut run = run.unwrap_or_else(|| {
+                        notes[first].1.tuplets.push(tuplet_event("start"));
+                        TupletRun { last: first, left: value.undotted_ticks() * actual as i64 }
+                    });
+                    run.last = first;
+                    run.left -= ticks;
+                    if run.left <= 0 {
+                        notes[first].1.tuplets.push(tuplet_event("stop"));
+                    } else {
+                        column.tuplet = Some(run);
+                    }
+                }
+                (None, Some(run)) => notes[run.last].1.tuplets.push(tuplet_event("stop")),
+                (None, None) => {}
+            }
+        }
+
+        column.cursor += ticks;
+        let pitched = !notes[first].1.rest && grace.is_none();
+        pitched.then_some(first)
+    }
+
+    fn finish(mut self) -> Result<Score, String> {
+        let kern: Vec<usize> = (0..self.spines.len()).filter(|&s| self.spines[s] == SpineKind::Kern).collect();
+        if kern.is_empty() {
+            return Err("Humdrum file has no **kern spines".to_string());
+        }
+        let length = self.columns.iter().map(|c| c.cursor).max().unwrap_or(0);
+        if let Some(info) = self.bars.last_mut() {
+            info.length = length;
+        }
+        // Drop the empty bar after the final barline
+        while self.bars.len() > 1 && kern.iter().all(|&s| self.staves[s].bars.last().unwrap().notes.is_empty()) {
+            self.bars.pop();
+            for staff in &mut self.staves {
+                staff.bars.pop();
+            }
+        }
+
+        // Parts top to bottom: spines right to left, grouped by `*partN`
+        let mut groups: Vec<(Option<String>, Vec<usize>)> = Vec::new();
+        for &s in kern.iter().rev() {
+            let tag = self.staves[s].part.clone();
+            match groups.iter_mut().find(|g| tag.is_some() && g.0 == tag) {
+                Some(group) => group.1.push(s),
+                None => groups.push((tag, vec![s])),
+            }
+        }
+
+        // Divisions: the coarsest that still fits every note
+        let mut step = TICKS_PER_QUARTER;
+        for staff in &self.staves {
+            for (_, note) in staff.bars.iter().flat_map(|b| &b.notes) {
+                step = gcd(step, gcd(note.duration as i64, note.onset as i64));
+            }
+        }
+        for info in &self.bars {
+            if let Some((onset, _)) = info.tempo {
+                step = gcd(step, onset);
+            }
+        }
+        let step = step.max(1);
+        let divisions = (TICKS_PER_QUARTER / step) as i32;
+
+        // A short first bar is a pickup
+        let pickup = self.bars.len() > 1
+            && self.bars[0].meter.is_some_and(|(beats, beat_type)| {
+                self.bars[0].length < beats as i64 * 4 * TICKS_PER_QUARTER / beat_type as i64
+            });
+
+        let mut score = Score::new();
+        score.title = self.title;
+        score.composer = self.composer;
+        for (pi, (_, spines)) in groups.iter().enumerate() {
+            let multi = spines.len() > 1;
+            let top = &self.staves[spines[0]];
+            let mut part = Part {
+                id: format!("P{}", pi + 1),
+                name: spines.iter().find_map(|&s| self.staves[s].name.clone()).unwrap_or_default(),
+                abbreviation: top.abbreviation.clone(),
+                midi_program: None,
+                midi_channel: None,
+                measures: Vec::new(),
+            };
+            let mut fifths = vec![0; spines.len()];
+            let mut number = 0;
+            for (b, info) in self.bars.iter().enumerate() {
+                number = match (b, info.number) {
+                    (_, Some(n)) => n,
+                    (0, None) => if pickup { 0 } else { 1 },
+                    _ => number + 1,
+                };
+                let mut measure = Measure {
+                    number,
+                    implicit: b == 0 && pickup,
+                    width: None,
+                    attributes: None,
+                    notes: Vec::new(),
+                    harmonies: Vec::new(),
+                    barlines: Vec::new(),
+                    directions: Vec::new(),
+                    new_system: false,
+                    new_page: false,
+                };
+
+                let mut attributes = Attributes {
+                    divisions: (b == 0).then_some(divisions),
+                    key: None,
+                    time: None,
+                    clefs: Vec::new(),
+                    transpose: None,
+                    staves: (b == 0 && multi).then_some(spines.len() as i32),
+                    staff_details: Vec::new(),
+                };
+                for (si, &s) in spines.iter().enumerate() {
+                    let bar = &self.staves[s].bars[b];
+                    let staff_number = si as i32 + 1;
+                    if let Some(key) = &bar.key {
+                        fifths[si] = key.fifths;
+                        attributes.key.get_or_insert_with(|| key.clone());
+                    }
+                    if let Some(time) = &bar.time {
+                        attributes.time.get_or_insert_with(|| time.clone());
+                    }
+                    match &bar.clef {
+                        Some(clef) => attributes.clefs.push(Clef { number: staff_number, ..clef.clone() }),
+                        None if b == 0 => attributes.clefs.push(Clef { number: staff_number, sign: "G".to_string(), line: 2, octave_change: None }),
+                        None => {}
+                    }
+                    if b == 0 && attributes.key.is_none() {
+                        attributes.key = Some(Key { fifths: 0, mode: None });
+                    }
+
+                    // Voices in order, each keeping its own note order
+                    let first = measure.notes.len();
+                    let mut voices: Vec<i32> = bar.notes.iter().map(|(v, _)| *v).collect();
+                    voices.sort_unstable();
+                    voices.dedup();
+                    for voice in voices {
+                        for (_, note) in bar.notes.iter().filter(|(v, _)| *v == voice) {
+                            let mut note = note.clone();
+                            note.duration = (note.duration as i64 / step) as i32;
+                            note.onset = (note.onset as i64 / step) as i32;
+                            note.voice = Some(voice + 4 * si as i32);
+                            note.staff = multi.then_some(staff_number);
+                            measure.notes.push(note);
+                        }
+                    }
+                    add_accidentals(&mut measure.notes[first..], fifths[si]);
+                }
+                if b == 0 || attributes.key.is_some() || attributes.time.is_some() || !attributes.clefs.is_empty() {
+                    measure.attributes = Some(attributes);
+                }
+
+                if info.repeat_start {
+                    measure.barlines.push(Barline {
+                        location: "left".to_string(),
+                        bar_style: Some("heavy-light".to_string()),
+                        repeat: Some(Repeat { direction: "forward".to_string() }),
+                        ending: None,
+                    });
+                }
+                if info.repeat_end || info.bar_style.is_some() {
+                    measure.barlines.push(Barline {
+                        location: "right".to_string(),
+                        bar_style: Some(info.bar_style.unwrap_or("light-heavy").to_string()),
+                        repeat: info.repeat_end.then(|| Repeat { direction: "backward".to_string() }),
+                        ending: None,
+                    });
+                }
+                if let Some((onset, bpm)) = info.tempo.filter(|_| pi == 0) {
+                    measure.directions.push(tempo_direction(bpm, (onset / step) as i32));
+                }
+                part.measures.push(measure);
+            }
+            score.parts.push(part);
+        }
+        Ok(score)
+    }
+}
+
+// ─── Tokens ──────────────────────────────────────────────────────────
+
+/// A kern duration: a reciprocal value (`4` = quarter, `0` = breve,
+/// `3%2` = two thirds of a whole) with dots.
+#[derive(Debug, Clone, Copy)]
+struct NoteValue {
+    /// Undotted length as a fraction of a whole note
+    num: i64,
+    den: i64,
+    dots: u32,
+}
+
+impl NoteValue {
+    fn undotted_ticks(&self) -> i64 {
+        self.num * 4 * TICKS_PER_QUARTER / self.den
+    }
+
+    fn ticks(&self) -> i64 {
+        let factor = (1i64 << (self.dots + 1)) - 1;
+        let scale = 1i64 << self.dots;
+        (self.num * 4 * TICKS_PER_QUARTER * factor + self.den * scale / 2) / (self.den * scale)
+    }
+
+    /// Written note type, and the tuplet ratio when the value isn't a
+    /// plain power of two: a sixth of a whole is a triplet quarter.
+    fn written(&self) -> (&'static str, Option<(i32, i32)>) {
+        const TYPES: [(i64, i64, &str); 11] = [
+            (4, 1, "long"),
+            (2, 1, "breve"),
+            (1, 1, "whole"),
+            (1, 2, "half"),
+            (1, 4, "quarter"),
+            (1, 8, "eighth"),
+            (1, 16, "16th"),
+            (1, 32, "32nd"),
+            (1, 64, "64th"),
+            (1, 128, "128th"),
+            (1, 256, "256th"),
+        ];
+        // The shortest written value at least as long as this one
+        let (tn, td, name) = TYPES.iter()
+            .rev()
+            .find(|&&(n, d, _)| n * self.den >= self.num * d)
+            .copied()
+            .unwrap_or(TYPES[0]);
+        let (actual, normal) = (tn * self.den, td * self.num);
+        let g = gcd(actual, normal).max(1);
+        let ratio = (actual != normal).then_some(((actual / g) as i32, (normal / g) as i32));
+        (name, ratio)
+    }
+}
+
+/// One note or rest of a kern token.
+#[derive(Debug, Default)]
+struct KernToken {
+    value: Option<NoteValue>,
+    pitch: Option<Pitch>,
+    rest: bool,
+    measure_rest: bool,
+    hidden: bool,
+    natural: bool,
+    /// Grace note, with whether it has a slash
+    grace: Option<bool>,
+    tie_start: bool,
+    tie_stop: bool,
+    slur_starts: i32,
+    slur_stops: i32,
+    beam_begins: usize,
+    beam_ends: usize,
+    stem: Option<&'static str>,
+    articulations: Vec<String>,
+    ornaments: Vec<String>,
+    fermata: bool,
+}
+
+fn read_token(token: &str) -> KernToken {
+    let chars: Vec<char> = token.chars().collect();
+    let mut t = KernToken::default();
+    let mut alter = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '0'..='9' if t.value.is_none() => {
+                let digits: String = chars[i..].iter().take_while(|c| c.is_ascii_digit()).collect();
+                i += digits.len();
+                let (mut num, mut den) = match digits.trim_start_matches('0') {
+                    "" => (1 << digits.len(), 1), // 0 = breve, 00 = long
+                    n => (1, n.parse::<i64>().unwrap_or(4)),
+                };
+                if chars.get(i) == Some(&'%') {
+                    let ratio: String = chars[i + 1..].iter().take_while(|c| c.is_ascii_digit()).collect();
+                    if let Ok(m) = ratio.parse::<i64>() {
+                        (num, den) = (m, den * num);
+                    }
+                    i += 1 + ratio.len();
+                }
+                t.value = Some(NoteValue { num: num.max(1), den: den.max(1), dots: 0 });
+                continue;
+            }
+            '.' => {
+                if let Some(value) = t.value.as_mut() {
+                    value.dots += 1;
+                }
+            }
+            'a'..='g' | 'A'..='G' => {
+                let count = chars[i..].iter().take_while(|&&x| x == c).count();
+                i += count;
+                let octave = if c.is_ascii_lowercase() { 3 + count as i32 } else { 4 - count as i32 };
+                t.pitch = Some(Pitch { step: c.to_ascii_uppercase().to_string(), octave, alter: None });
+                continue;
+            }
+            '#' => alter += 1,
+            '-' => alter -= 1,
+            'n' => t.natural = true,
+            'r' => {
+                t.rest = true;
+                if chars.get(i + 1) == Some(&'r') {
+                    t.measure_rest = true;
+                    i += 1;
+                }
+            }
+            'y' => t.hidden = t.rest || chars.get(i + 1) == Some(&'y'),
+            '[' => t.tie_start = true,
+            ']' => t.tie_stop = true,
+            '_' => {
+                t.tie_start = true;
+                t.tie_stop = true;
+            }
+            '(' => t.slur_starts += 1,
+            ')' => t.slur_stops += 1,
+            'L' => t.beam_begins += 1,
+            'J' => t.beam_ends += 1,
+            'q' => t.grace = Some(true),
+            'Q' => t.grace = Some(false),
+            '/' => t.stem = Some("up"),
+            '\\' => t.stem = Some("down"),
+            '\'' => t.articulations.push("staccato".to_string()),
+            '`' => t.articulations.push("staccatissimo".to_string()),
+            '~' => t.articulations.push("tenuto".to_string()),
+            '^' => {
+                if chars.get(i + 1) == Some(&'^') {
+                    t.articulations.push("strong-accent".to_string());
+                    i += 1;
+                } else {
+                    t.articulations.push("accent".to_string());
+                }
+            }
+            ';' => t.fermata = true,
+            'T' | 't' => t.ornaments.push("trill-mark".to_string()),
+            'M' | 'm' => t.ornaments.push("mordent".to_string()),
+            'W' | 'w' => t.ornaments.push("inverted-mordent".to_string()),
+            'S' => t.ornaments.push("turn".to_string()),
+            '$' => t.ornaments.push("inverted-turn".to_string()),
+            // Phrases, partial beams, editorial marks, bowings, layout
+            _ => {}
+        }
+        i += 1;
+    }
+    if let Some(pitch) = t.pitch.as_mut() {
+        pitch.alter = (alter != 0).then_some(alter as f64);
+    }
+    if t.grace.is_some() && t.value.is_none() {
+        t.value = Some(NoteValue { num: 1, den: 8, dots: 0 });
+    }
+    t
+}
+
+/// A `**text` syllable: a leading `-` continues a word and a trailing one
+/// carries it on to the next note.
+fn read_syllable(token: &str, verse: i32) -> Option<Lyric> {
+    if token == "." || token.starts_with('!') || token.starts_with('*') {
+        return None;
+    }
+    let continues = token.starts_with('-');
+    let hyphen = token.len() > 1 && token.ends_with('-');
+    let text = token.trim_matches('-').trim();
+    if text.is_empty() {
+        return None;
+    }
+    let syllabic = match (continues, hyphen) {
+        (false, false) => "single",
+        (false, true) => "begin",
+        (true, true) => "middle",
+        (true, false) => "end",
+    };
+    Some(Lyric { number: verse, text: text.to_string(), syllabic: Some(syllabic.to_string()) })
+}
+
+/// `G2`, `F4`, `C3`, `Gv2` (sounding an octave lower).
+fn parse_clef(text: &str) -> Option<Clef> {
+    let mut chars = text.chars();
+    let sign = chars.next().filter(|c| matches!(c, 'G' | 'F' | 'C'))?;
+    let rest: String = chars.collect();
+    let octave_change = match rest.matches('v').count() as i32 - rest.matches('^').count() as i32 {
+        0 => None,
+        n => Some(-n),
+    };
+    let line = rest.trim_start_matches(['v', '^']).parse().ok()
+        .unwrap_or(match sign { 'F' => 4, 'C' => 3, _ => 2 });
+    Some(Clef { number: 1, sign: sign.to_string(), line, octave_change })
+}
+
+// ─── Helpers ─────────────────────────────────────────────────────────
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+/// Mark accidentals where a note's alteration differs from the key or
+/// from an earlier note on the same line in this measure.  Tied-over
+/// notes set the alteration without showing it.
+fn add_accidentals(notes: &mut [Note], fifths: i32) {
+    let mut order: Vec<usize> = (0..notes.len()).filter(|&i| notes[i].pitch.is_some()).collect();
+    order.sort_by_key(|&i| notes[i].onset);
+
+    let key_alters = key_alterations(fifths);
+    let mut current: HashMap<(String, i32), f64> = HashMap::new();
+    for i in order {
+        let pitch = notes[i].pitch.as_ref().unwrap();
+        let alter = pitch.alter.unwrap_or(0.0);
+        let line = (pitch.step.clone(), pitch.octave);
+        let step_idx = "CDEFGAB".find(pitch.step.as_str()).unwrap_or(0);
+        let expected = current.get(&line).copied().unwrap_or(key_alters[step_idx] as f64);
+        if alter != expected && !notes[i].tie_stop {
+            notes[i].accidental = Some(
+                match alter as i32 {
+                    -2 => "flat-flat",
+                    -1 => "flat",
+                    1 => "sharp",
+                    2 => "double-sharp",
+                    _ => "natural",
+                }
+                .to_string(),
+            );
+        }
+        current.insert(line, alter);
+    }
+}
+
+/// Alteration of each step C…B in a key signature.
+fn key_alterations(fifths: i32) -> [i32; 7] {
+    // Sharps are added in the order F C G D A E B; flats in reverse
+    const ORDER: [usize; 7] = [3, 0, 4, 1, 5, 2, 6];
+    let mut alters = [0; 7];
+    for k in 0..fifths.unsigned_abs().min(7) as usize {
+        if fifths > 0 {
+            alters[ORDER[k]] = 1;
+        } else {
+            alters[ORDER[6 - k]] = -1;
+        }
+    }
+    alters
+}
+
+fn tuplet_event(tuplet_type: &str) -> TupletEvent {
+    TupletEvent {
+        tuplet_type: tuplet_type.to_string(),
+        number: 1,
+        bracket: None,
+        show_number: None,
+        placement: None,
+    }
+}
+
+fn tempo_direction(bpm: f64, onset: i32) -> Direction {
+    Direction {
+        placement: Some("above".to_string()),
+        sound_tempo: Some(bpm),
+        metronome: Some(MetronomeMark {
+            beat_unit: "quarter".to_string(),
+            per_minute: bpm.round() as i32,
+            dotted: false,
+        }),
+        words: None,
+        segno: false,
+        coda: false,
+        rehearsal: None,
+        sound_dacapo: false,
+        sound_dalsegno: false,
+        sound_fine: false,
+        sound_tocoda: false,
+        words_font_style: None,
+        octave_shift_type: None,
+        octave_shift_size: 0,
+        onset,
+        staff: None,
+        dynamics: None,
+        sound_dynamics: None,
+        wedge_type: None,
+        wedge_number: 0,
+    }
+}
+
+fn blank_note(voice: i32) -> Note {
+    Note {
+        pitch: None,
+        duration: 0,
+        voice: Some(voice),
+        note_type: None,
+        stem: None,
+        beams: Vec::new(),
+        rest: false,
+        measure_rest: false,
+        chord: false,
+        dot: false,
+        accidental: None,
+        tie_start: false,
+        tie_stop: false,
+        staff: None,
+        default_x: None,
+        default_y: None,
+        lyrics: Vec::new(),
+        grace: false,
+        grace_slash: false,
+        slurs: Vec::new(),
+        onset: 0,
+        time_modification: None,
+        tuplets: Vec::new(),
+        articulations: Vec::new(),
+        ornaments: Vec::new(),
+        ornament_upper_alter: None,
+        ornament_lower_alter: None,
+        fermata: None,
+        notehead: None,
+        string: None,
+        fret: None,
+        fingering: None,
+    }
+}
@@ -1,6 +1,10 @@
 //! scorelib — MusicXML parser and score rendering library for SoloBand Ultra.
 //!
-//! Supports both uncompressed MusicXML (.musicxml) and compressed MXL (.mxl) files.
+//! Supports uncompressed MusicXML (.musicxml), compressed MXL (.mxl), ABC
+//! notation (.abc), MEI (.mei), Humdrum **kern (.krn), Standard MIDI
+//! Files (.mid), which are imported by quantizing their notes, and chord
+//! charts — iReal Pro URLs and plain text (.chords) — which become
+//! slash-notation lead sheets.
 //!
 //! # Example
 //! ```no_run
@@ -14,13 +18,24 @@
 
 pub mod model;
 pub mod mxl;
+pub mod abc;
+pub mod chart;
+pub mod mei;
+pub mod kern;
 pub mod parser;
+pub mod writer;
+pub mod lilypond;
+pub mod tab;
+pub mod diagrams;
 pub mod renderer;
 pub mod unroller;
 pub mod timemap;
 pub mod midi;
+pub mod midi_import;
 pub mod accompaniment;
+pub mod dynamics;
 pub mod playback;
+pub mod synth;
 
 #[cfg(target_os = "android")]
 pub mod android;
@@ -29,12 +44,29 @@ use std::path::Path;
 
 pub use model::*;
 pub use parser::parse_musicxml;
-pub use mxl::parse_mxl;
-pub use renderer::render_score_to_svg;
-pub use midi::{generate_midi, MidiOptions, Energy};
+pub use mxl::{parse_mxl, write_mxl};
+pub use writer::write_musicxml;
+pub use abc::{parse_abc, write_abc};
+pub use chart::{parse_chord_chart, parse_ireal, parse_ireal_playlist};
+pub use mei::parse_mei;
+pub use kern::parse_kern;
+pub use lilypond::write_lilypond;
+pub use tab::{add_tab_staff, parse_tuning, TabOptions};
+pub use diagrams::{add_chord_diagrams, chord_frame};
+pub use renderer::{
+    render_score_to_horizontal_svg, render_score_to_jianpu_svg, render_score_to_pdf, render_score_to_png, render_score_to_svg,
+    render_score_to_svg_pages, render_score_to_svg_with_options, note_name, FontFamily, LabelPosition, NoteNames,
+    PageOptions, PageSize, RasterOptions, RasterRegion, RenderOptions, Theme,
+};
+pub use midi::{generate_midi, MidiOptions, PartOptions, Energy};
+pub use midi_import::{parse_midi, parse_midi_with_options, MidiImportOptions};
 pub use unroller::unroll;
 pub use timemap::generate_timemap;
-pub use playback::{generate_playback_map, PlaybackMap};
+pub use playback::{
+    generate_horizontal_playback_map, generate_jianpu_playback_map, generate_paged_playback_map,
+    generate_playback_map, generate_playback_map_with_options, PlaybackMap,
+};
+pub use synth::{SoundFont, SynthOptions};
 
 // ═══════════════════════════════════════════════════════════════════════
 // Score transposition
@@ -44,7 +76,8 @@ pub use playback::{generate_playback_map, PlaybackMap};
 /// by the given number of semitones.  Positive = up, negative = down.
 ///
 /// This modifies the `Score` in-place so that both rendering and MIDI
-/// generation produce transposed output.
+/// generation produce transposed output.  Tab staves are fingered again
+/// for the new pitches and chord diagrams move along the neck.
 pub fn transpose_score(score: &mut Score, semitones: i32) {
     if semitones == 0 {
         return;
@@ -85,8 +118,14 @@ pub fn transpose_score(score: &mut Score, semitones: i32) {
                 if let Some(ref mut bass) = harmony.bass {
                     transpose_harmony_root(bass, semitones, use_sharps);
                 }
+                if let Some(ref mut frame) = harmony.frame {
+                    diagrams::transpose_frame(frame, semitones);
+                }
             }
         }
+
+        // Frets no longer match the new pitches
+        tab::refinger_tab_staves(part, tab::DEFAULT_MAX_FRET);
     }
 }
 
@@ -170,6 +209,14 @@ fn transpose_harmony_root(root: &mut model::HarmonyRoot, semitones: i32, use_sha
 /// Automatically detects format based on file extension:
 /// - `.musicxml` or `.xml` → uncompressed MusicXML
 /// - `.mxl` → compressed MXL (ZIP archive)
+/// - `.abc` → ABC notation (the first tune in the file)
+/// - `.mei` → MEI (Music Encoding Initiative) XML
+/// - `.krn` → Humdrum **kern
+/// - `.mid` or `.midi` → Standard MIDI File
+/// - `.chords` → plain-text chord chart
+/// - `.html` holding an `irealb://` link → iReal Pro song
+/// - `.txt` → iReal Pro song if it holds an `irealb://` link, else a
+///   plain-text chord chart
 pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<Score, String> {
     let path = path.as_ref();
     let data = std::fs::read(path)
@@ -178,22 +225,79 @@ pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<Score, String> {
     parse_bytes(&data, path.extension().and_then(|e| e.to_str()))
 }
 
-/// Parse MusicXML from raw bytes with an optional format hint.
+/// Parse MusicXML, MXL, ABC, MEI, **kern, MIDI or a chord chart from raw
+/// bytes with an optional format hint.
 /// If `extension` is None, tries to auto-detect the format.
 pub fn parse_bytes(data: &[u8], extension: Option<&str>) -> Result<Score, String> {
     match extension {
         Some("mxl") => parse_mxl(data),
+        Some("mid") | Some("midi") => parse_midi(data),
+        Some("abc") => {
+            let text = std::str::from_utf8(data)
+                .map_err(|e| format!("Invalid UTF-8 in ABC file: {e}"))?;
+            parse_abc(text)
+        }
+        Some("mei") => {
+            let xml = std::str::from_utf8(data)
+                .map_err(|e| format!("Invalid UTF-8 in MEI file: {e}"))?;
+            parse_mei(xml)
+        }
+        Some("krn") | Some("kern") => {
+            let text = std::str::from_utf8(data)
+                .map_err(|e| format!("Invalid UTF-8 in Humdrum file: {e}"))?;
+            parse_kern(text)
+        }
+        Some("chords") => {
+            let text = std::str::from_utf8(data)
+                .map_err(|e| format!("Invalid UTF-8 in chord chart: {e}"))?;
+            parse_chord_chart(text)
+        }
+        Some("html") | Some("htm") => {
+            let text = String::from_utf8_lossy(data);
+            parse_ireal(&text)
+        }
+        Some("txt") => {
+            let text = String::from_utf8_lossy(data);
+            if text.contains("irealb://") || text.contains("irealbook://") {
+                parse_ireal(&text)
+            } else {
+                parse_chord_chart(&text)
+            }
+        }
         Some("musicxml") | Some("xml") => {
             let xml = std::str::from_utf8(data)
                 .map_err(|e| format!("Invalid UTF-8 in MusicXML file: {e}"))?;
             parse_musicxml(xml)
         }
         _ => {
-            // Auto-detect: try as XML first, then as MXL
+            // Auto-detect: MIDI by its header, then iReal Pro, MEI, XML,
+            // **kern, ABC, a chord chart, then MXL
+            if data.starts_with(b"MThd") {
+                return parse_midi(data);
+            }
             if let Ok(xml) = std::str::from_utf8(data) {
+                if xml.trim_start().starts_with("irealb://") || xml.trim_start().starts_with("irealbook://") {
+                    return parse_ireal(xml);
+                }
                 if xml.trim_start().starts_with("<?xml") || xml.trim_start().starts_with('<') {
+                    if xml.contains("<mei ") || xml.contains("<mei>") {
+                        return parse_mei(xml);
+                    }
                     return parse_musicxml(xml);
                 }
+                if xml.lines().any(|l| l.split('\t').any(|t| t == "**kern")) {
+                    return parse_kern(xml);
+                }
+                if xml.lines().any(|l| l.starts_with("X:")) && xml.lines().any(|l| l.starts_with("K:")) {
+                    return parse_abc(xml);
+                }
+                // A `Title:` header or a line of `|`-separated bars
+                let chart = xml.lines().map(str::trim).any(|l| {
+                    l.starts_with("Title:") || (!l.starts_with('#') && l.matches('|').count() >= 2)
+                });
+                if chart {
+                    return parse_chord_chart(xml);
+                }
             }
             // Try as MXL (ZIP)
             parse_mxl(data)
@@ -201,6 +305,21 @@ pub fn parse_bytes(data: &[u8], extension: Option<&str>) -> Result<Score, String
     }
 }
 
+/// Write a score to a file, as compressed MXL when the extension is `.mxl`,
+/// as ABC when it is `.abc`, as LilyPond when it is `.ly` and as
+/// uncompressed MusicXML otherwise.
+pub fn write_file<P: AsRef<Path>>(score: &Score, path: P) -> Result<(), String> {
+    let path = path.as_ref();
+    let data = match path.extension().and_then(|e| e.to_str()) {
+        Some("mxl") => write_mxl(score)?,
+        Some("abc") => write_abc(score).into_bytes(),
+        Some("ly") => write_lilypond(score).into_bytes(),
+        _ => write_musicxml(score).into_bytes(),
+    };
+    std::fs::write(path, data)
+        .map_err(|e| format!("Failed to write file '{}': {e}", path.display()))
+}
+
 /// Convert a parsed score to a JSON string.
 /// Useful for passing data across FFI boundaries.
 pub fn score_to_json(score: &Score) -> Result<String, String> {
@@ -242,16 +361,77 @@ pub fn render_bytes_to_svg(
     Ok(render_score_to_svg(&score, page_width))
 }
 
+/// Parse score bytes and render them to SVG with the theme, staff size,
+/// fonts and visible elements of `options`.
+///
+/// Other arguments are as for `render_bytes_to_svg`.
+pub fn render_bytes_to_svg_with_options(
+    data: &[u8],
+    extension: Option<&str>,
+    page_width: Option<f64>,
+    transpose: i32,
+    options: &RenderOptions,
+) -> Result<String, String> {
+    let mut score = parse_bytes(data, extension)?;
+    transpose_score(&mut score, transpose);
+    Ok(render_score_to_svg_with_options(&score, page_width, options))
+}
+
+/// Parse score bytes and render them as jianpu (numbered notation) SVG.
+///
+/// Arguments are as for `render_bytes_to_svg`.
+pub fn render_bytes_to_jianpu_svg(
+    data: &[u8],
+    extension: Option<&str>,
+    page_width: Option<f64>,
+    transpose: i32,
+) -> Result<String, String> {
+    let mut score = parse_bytes(data, extension)?;
+    transpose_score(&mut score, transpose);
+    Ok(render_score_to_jianpu_svg(&score, page_width))
+}
+
+/// Parse score bytes and render them on one horizontal system for
+/// continuous scrolling.
+///
+/// `transpose` is as for `render_bytes_to_svg`, and `options` as for
+/// `render_bytes_to_svg_with_options`.
+pub fn render_bytes_to_horizontal_svg(
+    data: &[u8],
+    extension: Option<&str>,
+    transpose: i32,
+    options: &RenderOptions,
+) -> Result<String, String> {
+    let mut score = parse_bytes(data, extension)?;
+    transpose_score(&mut score, transpose);
+    Ok(render_score_to_horizontal_svg(&score, options))
+}
+
+/// Parse score bytes and render them as one SVG per page.
+///
+/// Arguments are as for `render_bytes_to_svg`, with the page size,
+/// margins and breaks in `options` and the look of the score in `render`.
+pub fn render_bytes_to_svg_pages(
+    data: &[u8],
+    extension: Option<&str>,
+    options: &PageOptions,
+    transpose: i32,
+    render: &RenderOptions,
+) -> Result<Vec<String>, String> {
+    let mut score = parse_bytes(data, extension)?;
+    transpose_score(&mut score, transpose);
+    Ok(render_score_to_svg_pages(&score, options, render))
+}
+
 /// Generate MIDI bytes from a parsed score.
 ///
-/// Unrolls repeats/jumps, computes the timemap, extracts melody and
+/// Unrolls repeats/jumps, computes the timemap, extracts every part and
 /// optionally generates accompaniment tracks.  Returns a Standard MIDI
 /// File (SMF Type 1) as raw bytes.
 pub fn generate_midi_from_score(score: &Score, options: &MidiOptions) -> Vec<u8> {
-    let part_idx = 0; // melody from first part
-    let unrolled = unroll(score, part_idx);
-    let tmap = generate_timemap(score, part_idx, &unrolled);
-    generate_midi(score, part_idx, &unrolled, &tmap, options)
+    let unrolled = unroll(score, timemap::CONDUCTOR_PART);
+    let tmap = generate_timemap(score, timemap::CONDUCTOR_PART, &unrolled);
+    generate_midi(score, &unrolled, &tmap, options)
 }
 
 /// Parse a MusicXML file and generate MIDI bytes.
@@ -275,6 +455,33 @@ pub fn generate_midi_from_bytes(
     Ok(generate_midi_from_score(&score, options))
 }
 
+/// Render a parsed score to a 16-bit stereo WAV file with the built-in
+/// SoundFont synthesizer.
+pub fn generate_wav_from_score(
+    score: &Score,
+    midi_options: &MidiOptions,
+    font: &SoundFont,
+    synth_options: &SynthOptions,
+) -> Result<Vec<u8>, String> {
+    let midi = generate_midi_from_score(score, midi_options);
+    synth::render_midi_to_wav(font, &midi, synth_options)
+}
+
+/// Parse MusicXML bytes and render them to WAV bytes with the SoundFont
+/// given as `soundfont` (.sf2 bytes).
+pub fn generate_wav_from_bytes(
+    data: &[u8],
+    extension: Option<&str>,
+    midi_options: &MidiOptions,
+    soundfont: &[u8],
+    synth_options: &SynthOptions,
+) -> Result<Vec<u8>, String> {
+    let mut score = parse_bytes(data, extension)?;
+    transpose_score(&mut score, midi_options.transpose);
+    let font = SoundFont::parse(soundfont)?;
+    generate_wav_from_score(&score, midi_options, &font, synth_options)
+}
+
 /// Generate a playback map from a parsed score (JSON string).
 ///
 /// The playback map contains measure positions, system positions and the
@@ -299,6 +506,92 @@ pub fn playback_map_from_bytes(
     Ok(playback_map_from_score(&score, page_width))
 }
 
+/// Playback map JSON for `render_score_to_svg_with_options`.
+pub fn playback_map_with_options_from_score(
+    score: &Score,
+    page_width: Option<f64>,
+    options: &RenderOptions,
+) -> String {
+    let map = generate_playback_map_with_options(score, page_width, options);
+    playback::playback_map_to_json(&map)
+}
+
+/// Parse score bytes and return the playback map JSON for their rendering
+/// with `options`.  `transpose` and `options` must match the ones used to
+/// render.
+pub fn playback_map_with_options_from_bytes(
+    data: &[u8],
+    extension: Option<&str>,
+    page_width: Option<f64>,
+    transpose: i32,
+    options: &RenderOptions,
+) -> Result<String, String> {
+    let mut score = parse_bytes(data, extension)?;
+    transpose_score(&mut score, transpose);
+    Ok(playback_map_with_options_from_score(&score, page_width, options))
+}
+
+/// Playback map JSON for the jianpu rendering of a parsed score.
+pub fn jianpu_playback_map_from_score(score: &Score, page_width: Option<f64>) -> String {
+    let map = generate_jianpu_playback_map(score, page_width);
+    playback::playback_map_to_json(&map)
+}
+
+/// Parse score bytes and return the playback map JSON for their jianpu
+/// rendering.  `transpose` must match the one used to render.
+pub fn jianpu_playback_map_from_bytes(
+    data: &[u8],
+    extension: Option<&str>,
+    page_width: Option<f64>,
+    transpose: i32,
+) -> Result<String, String> {
+    let mut score = parse_bytes(data, extension)?;
+    transpose_score(&mut score, transpose);
+    Ok(jianpu_playback_map_from_score(&score, page_width))
+}
+
+/// Playback map JSON for the horizontal rendering of a parsed score.
+pub fn horizontal_playback_map_from_score(score: &Score, options: &RenderOptions) -> String {
+    let map = generate_horizontal_playback_map(score, options);
+    playback::playback_map_to_json(&map)
+}
+
+/// Parse score bytes and return the playback map JSON for their
+/// horizontal rendering.  `transpose` and `options` must match the ones
+/// used to render.
+pub fn horizontal_playback_map_from_bytes(
+    data: &[u8],
+    extension: Option<&str>,
+    transpose: i32,
+    options: &RenderOptions,
+) -> Result<String, String> {
+    let mut score = parse_bytes(data, extension)?;
+    transpose_score(&mut score, transpose);
+    Ok(horizontal_playback_map_from_score(&score, options))
+}
+
+/// Playback map JSON for the paged rendering of a parsed score, with the
+/// page of every system.
+pub fn paged_playback_map_from_score(score: &Score, options: &PageOptions, render: &RenderOptions) -> String {
+    let map = generate_paged_playback_map(score, options, render);
+    playback::playback_map_to_json(&map)
+}
+
+/// Parse score bytes and return the playback map JSON for their paged
+/// rendering.  `transpose` and `render` must match the ones used to
+/// render.
+pub fn paged_playback_map_from_bytes(
+    data: &[u8],
+    extension: Option<&str>,
+    options: &PageOptions,
+    transpose: i32,
+    render: &RenderOptions,
+) -> Result<String, String> {
+    let mut score = parse_bytes(data, extension)?;
+    transpose_score(&mut score, transpose);
+    Ok(paged_playback_map_from_score(&score, options, render))
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 // C FFI — for iOS (static library) and Android (JNI)
 // ═══════════════════════════════════════════════════════════════════════
@@ -369,6 +662,120 @@ pub unsafe extern "C" fn scorelib_render_bytes(
     }
 }
 
+/// Parse score bytes and return jianpu (numbered notation) SVG as a C
+/// string.  The caller must free the returned string with
+/// `scorelib_free_string`.
+///
+/// `page_width` sets the SVG width in user units. Pass 0.0 to use the default.
+///
+/// # Safety
+/// `data` must point to `len` valid bytes. `extension` may be null.
+#[no_mangle]
+pub unsafe extern "C" fn scorelib_render_jianpu(
+    data: *const u8,
+    len: usize,
+    extension: *const c_char,
+    page_width: f64,
+    transpose: i32,
+) -> *mut c_char {
+    if data.is_null() || len == 0 {
+        return std::ptr::null_mut();
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(data, len) };
+    let ext = if extension.is_null() {
+        None
+    } else {
+        unsafe { CStr::from_ptr(extension) }.to_str().ok()
+    };
+
+    let pw = if page_width > 0.0 { Some(page_width) } else { None };
+
+    match render_bytes_to_jianpu_svg(bytes, ext, pw, transpose) {
+        Ok(svg) => CString::new(svg).unwrap_or_default().into_raw(),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Parse score bytes and return SVG drawn with render options as a C
+/// string.  The caller must free the returned string with
+/// `scorelib_free_string`.
+///
+/// `page_width` sets the SVG width in user units. Pass 0.0 to use the default.
+///
+/// `options_json` is a JSON string with fields:
+///   `theme` ("light"/"dark"/"high-contrast"/"sepia"), `staff_scale`
+///   (1.0 = default size), `font` ("serif"/"sans-serif"/a font name),
+///   `chord_symbols`, `lyrics`, `measure_numbers` (true/false),
+///   `note_names` ("letters"/"fixed-do"/"movable-do"),
+///   `note_name_position` ("below"/"inside"), `colored_noteheads`,
+///   `finger_numbers` (true/false).
+/// Pass null to use defaults.
+///
+/// # Safety
+/// `data` must point to `len` valid bytes. `extension` and `options_json`
+/// may be null.
+#[no_mangle]
+pub unsafe extern "C" fn scorelib_render_with_options(
+    data: *const u8,
+    len: usize,
+    extension: *const c_char,
+    page_width: f64,
+    transpose: i32,
+    options_json: *const c_char,
+) -> *mut c_char {
+    if data.is_null() || len == 0 {
+        return std::ptr::null_mut();
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(data, len) };
+    let ext = if extension.is_null() {
+        None
+    } else {
+        unsafe { CStr::from_ptr(extension) }.to_str().ok()
+    };
+
+    let pw = if page_width > 0.0 { Some(page_width) } else { None };
+    let options = unsafe { parse_render_options_json(options_json) };
+
+    match render_bytes_to_svg_with_options(bytes, ext, pw, transpose, &options) {
+        Ok(svg) => CString::new(svg).unwrap_or_default().into_raw(),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Parse score bytes and return them rendered on one horizontal system
+/// as a C string.  The caller must free the returned string with
+/// `scorelib_free_string`.  `options_json` is as for
+/// `scorelib_render_with_options`; pass null for defaults.
+///
+/// # Safety
+/// `data` must point to `len` valid bytes. `extension` and `options_json`
+/// may be null.
+#[no_mangle]
+pub unsafe extern "C" fn scorelib_render_horizontal(
+    data: *const u8,
+    len: usize,
+    extension: *const c_char,
+    transpose: i32,
+    options_json: *const c_char,
+) -> *mut c_char {
+    if data.is_null() || len == 0 {
+        return std::ptr::null_mut();
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(data, len) };
+    let ext = if extension.is_null() {
+        None
+    } else {
+        unsafe { CStr::from_ptr(extension) }.to_str().ok()
+    };
+
+    let options = unsafe { parse_render_options_json(options_json) };
+
+    match render_bytes_to_horizontal_svg(bytes, ext, transpose, &options) {
+        Ok(svg) => CString::new(svg).unwrap_or_default().into_raw(),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
 /// Free a string previously returned by scorelib functions.
 ///
 /// # Safety
@@ -394,7 +801,8 @@ pub unsafe extern "C" fn scorelib_free_string(ptr: *mut c_char) {
 ///
 /// `options_json` is a JSON string with fields:
 ///   `include_melody`, `include_piano`, `include_bass`, `include_strings`,
-///   `include_drums`, `include_metronome`, `energy` ("soft"/"medium"/"strong").
+///   `include_drums`, `include_metronome`, `energy` ("soft"/"medium"/"strong"),
+///   `parts` (per-part `{"include", "mute", "volume"}` objects, by part index).
 /// Pass null to use defaults.
 ///
 /// # Safety
@@ -442,6 +850,214 @@ pub unsafe extern "C" fn scorelib_free_midi(ptr: *mut u8, len: usize) {
     }
 }
 
+/// Render a MusicXML file to WAV bytes with the built-in synthesizer.
+///
+/// The caller must free the returned buffer with `scorelib_free_wav`.
+/// Returns null on error.
+///
+/// `soundfont_path` is the .sf2 file to play with.  `options_json` takes
+/// the same fields as `scorelib_generate_midi`, plus `sample_rate`,
+/// `gain` and `reverb` (0.0–1.0).  Pass null to use defaults.
+///
+/// # Safety
+/// `path` and `soundfont_path` must be valid null-terminated UTF-8 C strings.
+/// `out_len` must point to valid writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn scorelib_generate_wav(
+    path: *const c_char,
+    soundfont_path: *const c_char,
+    options_json: *const c_char,
+    out_len: *mut usize,
+) -> *mut u8 {
+    if path.is_null() || soundfont_path.is_null() || out_len.is_null() {
+        return std::ptr::null_mut();
+    }
+    let (Ok(path_str), Ok(sf_str)) = (
+        unsafe { CStr::from_ptr(path) }.to_str(),
+        unsafe { CStr::from_ptr(soundfont_path) }.to_str(),
+    ) else {
+        return std::ptr::null_mut();
+    };
+
+    let midi_options = parse_midi_options_json(options_json);
+    let synth_options = if options_json.is_null() {
+        SynthOptions::default()
+    } else {
+        match unsafe { CStr::from_ptr(options_json) }.to_str() {
+            Ok(s) => synth::synth_options_from_json(s),
+            Err(_) => SynthOptions::default(),
+        }
+    };
+
+    let result = parse_file(path_str).and_then(|mut score| {
+        transpose_score(&mut score, midi_options.transpose);
+        let font = SoundFont::from_file(sf_str)?;
+        generate_wav_from_score(&score, &midi_options, &font, &synth_options)
+    });
+    match result {
+        Ok(wav) => {
+            let wav = wav.into_boxed_slice();
+            let len = wav.len();
+            let ptr = Box::leak(wav).as_mut_ptr();
+            unsafe { *out_len = len; }
+            ptr
+        }
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Free WAV bytes previously returned by `scorelib_generate_wav`.
+///
+/// # Safety
+/// `ptr` must be a buffer previously returned by `scorelib_generate_wav`,
+/// or null. `len` must be the length returned via `out_len`.
+#[no_mangle]
+pub unsafe extern "C" fn scorelib_free_wav(ptr: *mut u8, len: usize) {
+    if !ptr.is_null() && len > 0 {
+        unsafe {
+            let _ = Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len));
+        }
+    }
+}
+
+/// Render a MusicXML file to a PDF document.
+///
+/// Returns a pointer to the PDF data and writes the length to `out_len`.
+/// The caller must free the returned buffer with `scorelib_free_pdf`.
+/// Returns null on error.
+///
+/// `paper` selects the page size: 0 for A4, 1 for Letter.  `options_json`
+/// is as for `scorelib_render_with_options`; pass null for defaults.
+///
+/// # Safety
+/// `path` must be a valid null-terminated UTF-8 C string.
+/// `options_json` may be null.
+/// `out_len` must point to valid writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn scorelib_render_pdf(
+    path: *const c_char,
+    paper: i32,
+    transpose: i32,
+    options_json: *const c_char,
+    out_len: *mut usize,
+) -> *mut u8 {
+    if path.is_null() || out_len.is_null() {
+        return std::ptr::null_mut();
+    }
+    let Ok(path_str) = unsafe { CStr::from_ptr(path) }.to_str() else {
+        return std::ptr::null_mut();
+    };
+    let page_size = if paper == 1 { PageSize::Letter } else { PageSize::A4 };
+    let options = unsafe { parse_render_options_json(options_json) };
+
+    match parse_file(path_str) {
+        Ok(mut score) => {
+            transpose_score(&mut score, transpose);
+            let pdf = render_score_to_pdf(&score, page_size, &options).into_boxed_slice();
+            let len = pdf.len();
+            let ptr = Box::leak(pdf).as_mut_ptr();
+            unsafe { *out_len = len; }
+            ptr
+        }
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Free PDF bytes previously returned by `scorelib_render_pdf`.
+///
+/// # Safety
+/// `ptr` must be a buffer previously returned by `scorelib_render_pdf`,
+/// or null. `len` must be the length returned via `out_len`.
+#[no_mangle]
+pub unsafe extern "C" fn scorelib_free_pdf(ptr: *mut u8, len: usize) {
+    if !ptr.is_null() && len > 0 {
+        unsafe {
+            let _ = Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len));
+        }
+    }
+}
+
+/// Render part of a MusicXML file to a PNG image.
+///
+/// Returns a pointer to the PNG data and writes the length to `out_len`.
+/// The caller must free the returned buffer with `scorelib_free_png`.
+/// Returns null on error, including a page or measure range that is not
+/// in the score.
+///
+/// `dpi` sets the resolution; pass 0.0 for the default (150).  With
+/// `page` > 0 that page is rendered as printed; otherwise with
+/// `first_measure` > 0 measures `first_measure` to `last_measure`
+/// (1-based, inclusive) are rendered; otherwise the first system.
+/// `paper` selects the page size: 0 for A4, 1 for Letter.  `options_json`
+/// is as for `scorelib_render_with_options`; pass null for defaults.
+///
+/// # Safety
+/// `path` must be a valid null-terminated UTF-8 C string.
+/// `options_json` may be null.
+/// `out_len` must point to valid writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn scorelib_render_png(
+    path: *const c_char,
+    dpi: f64,
+    page: i32,
+    first_measure: i32,
+    last_measure: i32,
+    paper: i32,
+    transpose: i32,
+    options_json: *const c_char,
+    out_len: *mut usize,
+) -> *mut u8 {
+    if path.is_null() || out_len.is_null() {
+        return std::ptr::null_mut();
+    }
+    let Ok(path_str) = unsafe { CStr::from_ptr(path) }.to_str() else {
+        return std::ptr::null_mut();
+    };
+    let mut options = RasterOptions::default();
+    if dpi > 0.0 {
+        options.dpi = dpi;
+    }
+    if page > 0 {
+        options.region = RasterRegion::Page(page as usize);
+    } else if first_measure > 0 {
+        options.region = RasterRegion::Measures {
+            first: first_measure as usize,
+            last: last_measure.max(0) as usize,
+        };
+    }
+    options.page_size = if paper == 1 { PageSize::Letter } else { PageSize::A4 };
+
+    let Ok(mut score) = parse_file(path_str) else {
+        return std::ptr::null_mut();
+    };
+    transpose_score(&mut score, transpose);
+    let render = unsafe { parse_render_options_json(options_json) };
+    match render_score_to_png(&score, &options, &render) {
+        Ok(png) => {
+            let png = png.into_boxed_slice();
+            let len = png.len();
+            let ptr = Box::leak(png).as_mut_ptr();
+            unsafe { *out_len = len; }
+            ptr
+        }
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Free PNG bytes previously returned by `scorelib_render_png`.
+///
+/// # Safety
+/// `ptr` must be a buffer previously returned by `scorelib_render_png`,
+/// or null. `len` must be the length returned via `out_len`.
+#[no_mangle]
+pub unsafe extern "C" fn scorelib_free_png(ptr: *mut u8, len: usize) {
+    if !ptr.is_null() && len > 0 {
+        unsafe {
+            let _ = Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len));
+        }
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 // Playback map FFI
 // ═══════════════════════════════════════════════════════════════════════
@@ -480,6 +1096,112 @@ pub unsafe extern "C" fn scorelib_playback_map(
     }
 }
 
+/// Generate the playback map JSON for score bytes rendered with
+/// `scorelib_render_with_options`.
+///
+/// The caller must free the returned string with `scorelib_free_string`.
+///
+/// `page_width` and `options_json` must match the ones used to render.
+///
+/// # Safety
+/// `data` must point to `len` valid bytes. `extension` and `options_json`
+/// may be null.
+#[no_mangle]
+pub unsafe extern "C" fn scorelib_playback_map_with_options(
+    data: *const u8,
+    len: usize,
+    extension: *const c_char,
+    page_width: f64,
+    transpose: i32,
+    options_json: *const c_char,
+) -> *mut c_char {
+    if data.is_null() || len == 0 {
+        return std::ptr::null_mut();
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(data, len) };
+    let ext = if extension.is_null() {
+        None
+    } else {
+        unsafe { CStr::from_ptr(extension) }.to_str().ok()
+    };
+
+    let pw = if page_width > 0.0 { Some(page_width) } else { None };
+    let options = unsafe { parse_render_options_json(options_json) };
+
+    match playback_map_with_options_from_bytes(bytes, ext, pw, transpose, &options) {
+        Ok(json) => CString::new(json).unwrap_or_default().into_raw(),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Generate the playback map JSON for the jianpu rendering of score bytes.
+///
+/// The caller must free the returned string with `scorelib_free_string`.
+///
+/// `page_width` sets the SVG width in user units. Pass 0.0 to use the default.
+///
+/// # Safety
+/// `data` must point to `len` valid bytes. `extension` may be null.
+#[no_mangle]
+pub unsafe extern "C" fn scorelib_jianpu_playback_map(
+    data: *const u8,
+    len: usize,
+    extension: *const c_char,
+    page_width: f64,
+    transpose: i32,
+) -> *mut c_char {
+    if data.is_null() || len == 0 {
+        return std::ptr::null_mut();
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(data, len) };
+    let ext = if extension.is_null() {
+        None
+    } else {
+        unsafe { CStr::from_ptr(extension) }.to_str().ok()
+    };
+
+    let pw = if page_width > 0.0 { Some(page_width) } else { None };
+
+    match jianpu_playback_map_from_bytes(bytes, ext, pw, transpose) {
+        Ok(json) => CString::new(json).unwrap_or_default().into_raw(),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Generate the playback map JSON for the horizontal rendering of score
+/// bytes.  `options_json` must match the one used to render.
+///
+/// The caller must free the returned string with `scorelib_free_string`.
+///
+/// # Safety
+/// `data` must point to `len` valid bytes. `extension` and `options_json`
+/// may be null.
+#[no_mangle]
+pub unsafe extern "C" fn scorelib_horizontal_playback_map(
+    data: *const u8,
+    len: usize,
+    extension: *const c_char,
+    transpose: i32,
+    options_json: *const c_char,
+) -> *mut c_char {
+    if data.is_null() || len == 0 {
+        return std::ptr::null_mut();
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(data, len) };
+    let ext = if extension.is_null() {
+        None
+    } else {
+        unsafe { CStr::from_ptr(extension) }.to_str().ok()
+    };
+
+    let options = unsafe { parse_render_options_json(options_json) };
+
+    match horizontal_playback_map_from_bytes(bytes, ext, transpose, &options) {
+        Ok(json) => CString::new(json).unwrap_or_default().into_raw(),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
 /// Generate MIDI bytes from MusicXML bytes.
 ///
 /// Returns a pointer to the MIDI data and writes the length to `out_len`.
@@ -519,6 +1241,17 @@ pub unsafe extern "C" fn scorelib_generate_midi_from_bytes(
     }
 }
 
+/// Parse RenderOptions from a JSON C string (internal helper).
+unsafe fn parse_render_options_json(json_ptr: *const c_char) -> RenderOptions {
+    if json_ptr.is_null() {
+        return RenderOptions::default();
+    }
+    match unsafe { CStr::from_ptr(json_ptr) }.to_str() {
+        Ok(s) => renderer::render_options_from_json(s),
+        Err(_) => RenderOptions::default(),
+    }
+}
+
 /// Parse MidiOptions from a JSON C string (internal helper).
 unsafe fn parse_midi_options_json(json_ptr: *const c_char) -> MidiOptions {
     if json_ptr.is_null() {
@@ -575,5 +1308,6 @@ unsafe fn parse_midi_options_json(json_ptr: *const c_char) -> MidiOptions {
             opts.transpose = val;
         }
     }
+    opts.parts = crate::midi::part_options_from_json(json_str);
     opts
 }
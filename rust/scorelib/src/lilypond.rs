@@ -0,0 +1,964 @@
+//! LilyPond writer — exports a Score as a `.ly` file for engraving.
+//!
+//! The output targets LilyPond 2.24 and uses absolute pitches, so each
+//! note can be read on its own.  Every part becomes a variable holding its
+//! music (one per staff for grand-staff parts), with chord symbols in a
+//! `\chordmode` variable and each lyric verse in a `\lyricmode` one; the
+//! `\score` block at the end puts them together.  Repeats are written as
+//! `\repeat volta` with `\alternative` endings, so the engraved layout
+//! matches the original rather than the unrolled playback order.
+
+use std::collections::BTreeSet;
+
+use crate::model::*;
+
+/// Serialize a score as a LilyPond source file.
+pub fn write_lilypond(score: &Score) -> String {
+    let mut out = String::from("\\version \"2.24.0\"\n\n");
+    write_header(&mut out, score);
+
+    let mut staff_groups = Vec::new();
+    for (idx, part) in score.parts.iter().enumerate() {
+        let name = format!("part{}", letters(idx));
+        let writer = PartWriter::new(part);
+        let sections = repeat_sections(part);
+
+        // Staves, each with its voices in order of number
+        let staff_count = part.measures.iter()
+            .filter_map(|m| m.attributes.as_ref().and_then(|a| a.staves))
+            .max()
+            .unwrap_or(1)
+            .max(1);
+        let mut staves = Vec::new();
+        for staff in 1..=staff_count {
+            let var = match staff_count {
+                1 => name.clone(),
+                _ => format!("{name}Staff{}", letters(staff as usize - 1)),
+            };
+            let voices: BTreeSet<i32> = part.measures.iter()
+                .flat_map(|m| &m.notes)
+                .filter(|n| n.staff.unwrap_or(1) == staff)
+                .map(|n| n.voice.unwrap_or(1))
+                .collect();
+            let voices: Vec<i32> = if voices.is_empty() { vec![1] } else { voices.into_iter().collect() };
+            out.push_str(&format!("{var} = "));
+            if voices.len() == 1 {
+                writer.voice_music(&sections, staff, voices[0], true, "", &mut out);
+            } else {
+                out.push_str("<<\n");
+                for (vi, &voice) in voices.iter().enumerate() {
+                    let context = match vi {
+                        0 => format!("  \\new Voice = \"{var}\" "),
+                        _ => "  \\new Voice ".to_string(),
+                    };
+                    out.push_str(&context);
+                    let setting = ["voiceOne", "voiceTwo", "voiceThree", "voiceFour"][vi.min(3)];
+                    writer.voice_music(&sections, staff, voice, vi == 0, setting, &mut out);
+                }
+                out.push_str(">>\n");
+            }
+            out.push('\n');
+            staves.push((var, voices));
+        }
+
+        // Chord symbols
+        let chords = part.measures.iter().any(|m| !m.harmonies.is_empty());
+        if chords {
+            out.push_str(&format!("{name}Chords = \\chordmode "));
+            writer.chord_music(&sections, &mut out);
+            out.push('\n');
+        }
+
+        // Lyrics, sung by the first voice of the first staff
+        let first_voice = staves[0].1[0];
+        let verses: BTreeSet<i32> = part.measures.iter()
+            .flat_map(|m| &m.notes)
+            .filter(|n| n.staff.unwrap_or(1) == 1 && n.voice.unwrap_or(1) == first_voice)
+            .flat_map(|n| n.lyrics.iter().map(|l| l.number))
+            .collect();
+        let mut verse_vars = Vec::new();
+        for (vi, &verse) in verses.iter().enumerate() {
+            let var = format!("{name}Verse{}", letters(vi));
+            out.push_str(&format!("{var} = \\lyricmode {{\n"));
+            out.push_str("  \\set ignoreMelismata = ##t\n");
+            writer.lyrics(first_voice, verse, &mut out);
+            out.push_str("}\n\n");
+            verse_vars.push(var);
+        }
+
+        staff_groups.push(StaffGroup {
+            name: (score.parts.len() > 1 && !part.name.is_empty()).then(|| part.name.clone()),
+            chords: chords.then(|| format!("{name}Chords")),
+            staves,
+            verses: verse_vars,
+        });
+    }
+
+    write_score_block(&mut out, &staff_groups);
+    out
+}
+
+// ─── Header ──────────────────────────────────────────────────────────
+
+fn write_header(out: &mut String, score: &Score) {
+    out.push_str("\\header {\n");
+    let fields = [
+        ("title", &score.title),
+        ("subtitle", &score.subtitle),
+        ("composer", &score.composer),
+        ("arranger", &score.arranger),
+    ];
+    for (field, value) in fields {
+        if let Some(text) = value {
+            out.push_str(&format!("  {field} = {}\n", quoted(text)));
+        }
+    }
+    out.push_str("  tagline = ##f\n}\n\n");
+}
+
+// ─── Score block ─────────────────────────────────────────────────────
+
+/// The variables written for one part.
+struct StaffGroup {
+    /// Instrument name, shown when the score has several parts
+    name: Option<String>,
+    chords: Option<String>,
+    /// Staff variables with the voice numbers they hold
+    staves: Vec<(String, Vec<i32>)>,
+    verses: Vec<String>,
+}
+
+fn write_score_block(out: &mut String, groups: &[StaffGroup]) {
+    out.push_str("\\score {\n  <<\n");
+    for group in groups {
+        if let Some(chords) = &group.chords {
+            out.push_str(&format!("    \\new ChordNames \\{chords}\n"));
+        }
+        let with = group.name.as_ref()
+            .map(|n| format!("\\with {{ instrumentName = {} }} ", quoted(n)))
+            .unwrap_or_default();
+        let grand = group.staves.len() > 1;
+        let indent = if grand { "      " } else { "    " };
+        if grand {
+            out.push_str(&format!("    \\new PianoStaff {with}<<\n"));
+        }
+        for (si, (var, voices)) in group.staves.iter().enumerate() {
+            let with = if grand { "" } else { with.as_str() };
+            out.push_str(&format!("{indent}\\new Staff {with}\\{var}\n"));
+            if si > 0 {
+                continue;
+            }
+            for verse in &group.verses {
+                match voices.len() {
+                    1 => out.push_str(&format!("{indent}\\addlyrics {{ \\{verse} }}\n")),
+                    _ => out.push_str(&format!("{indent}\\new Lyrics \\lyricsto \"{var}\" {{ \\{verse} }}\n")),
+                }
+            }
+        }
+        if grand {
+            out.push_str("    >>\n");
+        }
+    }
+    out.push_str("  >>\n  \\layout { }\n}\n");
+}
+
+// ─── Repeats ─────────────────────────────────────────────────────────
+
+/// Measures grouped the way they are written: plain runs and volta
+/// repeats with their alternative endings.
+enum Section {
+    Plain(Vec<usize>),
+    Volta {
+        times: usize,
+        body: Vec<usize>,
+        alternatives: Vec<Vec<usize>>,
+    },
+}
+
+/// Group a part's measures by its repeat barlines and endings.  A repeat
+/// without a start barline goes back to the beginning, or to the end of
+/// the previous repeat.
+fn repeat_sections(part: &Part) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut plain: Vec<usize> = Vec::new();
+    let mut body: Option<Vec<usize>> = None;
+    let mut alternatives: Vec<Vec<usize>> = Vec::new();
+    let mut times = 2;
+    let mut in_ending = false;
+
+    for (i, measure) in part.measures.iter().enumerate() {
+        let left = measure.barlines.iter().find(|b| b.location == "left");
+        let right = measure.barlines.iter().find(|b| b.location == "right");
+        let forward = left.and_then(|b| b.repeat.as_ref()).is_some_and(|r| r.direction == "forward");
+        let ending_start = left.and_then(|b| b.ending.as_ref()).filter(|e| e.ending_type == "start");
+
+        // A repeat whose last ending was closed by its repeat barline
+        if !alternatives.is_empty() && !in_ending && (ending_start.is_none() || forward) {
+            finish(&mut sections, &mut body, &mut alternatives, &mut times);
+        }
+        if forward {
+            if in_ending {
+                in_ending = false;
+                finish(&mut sections, &mut body, &mut alternatives, &mut times);
+            }
+            if !plain.is_empty() {
+                sections.push(Section::Plain(std::mem::take(&mut plain)));
+            }
+            body = Some(Vec::new());
+        }
+        if let Some(ending) = ending_start {
+            if body.is_none() {
+                body = Some(std::mem::take(&mut plain));
+            }
+            alternatives.push(Vec::new());
+            in_ending = true;
+            let numbers = ending_numbers(&ending.number);
+            times = times.max(alternatives.len()).max(numbers.into_iter().max().unwrap_or(0));
+        }
+
+        match (&mut body, in_ending) {
+            (Some(_), true) => alternatives.last_mut().unwrap().push(i),
+            (Some(b), false) => b.push(i),
+            (None, _) => plain.push(i),
+        }
+
+        let backward = right.and_then(|b| b.repeat.as_ref()).is_some_and(|r| r.direction == "backward");
+        let ending_end = right.and_then(|b| b.ending.as_ref()).is_some_and(|e| e.ending_type != "start");
+        if in_ending {
+            if backward || ending_end {
+                in_ending = false;
+                if !backward {
+                    // The last ending carries on without going back
+                    finish(&mut sections, &mut body, &mut alternatives, &mut times);
+                }
+            }
+        } else if backward {
+            if body.is_none() {
+                body = Some(std::mem::take(&mut plain));
+            }
+            finish(&mut sections, &mut body, &mut alternatives, &mut times);
+        }
+    }
+    finish(&mut sections, &mut body, &mut alternatives, &mut times);
+    if !plain.is_empty() {
+        sections.push(Section::Plain(plain));
+    }
+    sections
+}
+
+/// Close the open repeat, if any, with the alternatives collected so far.
+fn finish(sections: &mut Vec<Section>, body: &mut Option<Vec<usize>>, alternatives: &mut Vec<Vec<usize>>, times: &mut usize) {
+    if let Some(body) = body.take() {
+        sections.push(Section::Volta { times: *times, body, alternatives: std::mem::take(alternatives) });
+    }
+    *times = 2;
+}
+
+/// Ending numbers such as "1", "1, 2" or "1-3".
+fn ending_numbers(text: &str) -> Vec<usize> {
+    let mut numbers = Vec::new();
+    for token in text.split([',', ' ']).filter(|t| !t.is_empty()) {
+        match token.split_once('-') {
+            Some((a, b)) => {
+                if let (Ok(a), Ok(b)) = (a.trim().parse::<usize>(), b.trim().parse::<usize>()) {
+                    numbers.extend(a..=b);
+                }
+            }
+            None => numbers.extend(token.trim().parse::<usize>().ok()),
+        }
+    }
+    numbers
+}
+
+/// Write a music expression, calling `measure` for each measure in the
+/// order the sections give.
+fn write_sections(sections: &[Section], out: &mut String, mut measure: impl FnMut(usize) -> String) {
+    let mut line = |out: &mut String, indent: &str, idx: usize| {
+        out.push_str(indent);
+        out.push_str(&measure(idx));
+        out.push('\n');
+    };
+    for section in sections {
+        match section {
+            Section::Plain(measures) => {
+                for &m in measures {
+                    line(out, "  ", m);
+                }
+            }
+            Section::Volta { times, body, alternatives } => {
+                out.push_str(&format!("  \\repeat volta {times} {{\n"));
+                for &m in body {
+                    line(out, "    ", m);
+                }
+                out.push_str("  }\n");
+                if alternatives.is_empty() {
+                    continue;
+                }
+                out.push_str("  \\alternative {\n");
+                for alternative in alternatives {
+                    out.push_str("    {\n");
+                    for &m in alternative {
+                        line(out, "      ", m);
+                    }
+                    out.push_str("    }\n");
+                }
+                out.push_str("  }\n");
+            }
+        }
+    }
+}
+
+// ─── Part ────────────────────────────────────────────────────────────
+
+/// Divisions and length in effect for each measure.
+struct MeasureInfo {
+    divisions: i32,
+    /// Length in divisions (the notes' length for a pickup)
+    length: i32,
+    /// Whether the measure is shorter than its time signature
+    short: bool,
+}
+
+struct PartWriter<'a> {
+    part: &'a Part,
+    info: Vec<MeasureInfo>,
+}
+
+impl<'a> PartWriter<'a> {
+    fn new(part: &'a Part) -> Self {
+        let mut divisions = 1;
+        let mut time = (4, 4);
+        let info = part.measures.iter()
+            .map(|m| {
+                if let Some(attrs) = &m.attributes {
+                    if let Some(d) = attrs.divisions {
+                        divisions = d.max(1);
+                    }
+                    if let Some(t) = &attrs.time {
+                        time = (t.beats, t.beat_type.max(1));
+                    }
+                }
+                let full = time.0 * 4 * divisions / time.1;
+                let content = m.notes.iter()
+                    .filter(|n| !n.grace)
+                    .map(|n| n.onset + n.duration)
+                    .max()
+                    .unwrap_or(0);
+                let length = if m.implicit && content > 0 { content.min(full) } else { full };
+                MeasureInfo { divisions, length, short: length < full }
+            })
+            .collect();
+        PartWriter { part, info }
+    }
+
+    /// Write the music of one voice on one staff as a `{ … }` expression.
+    /// The staff's first voice also carries clefs, keys, times, barlines
+    /// and directions.
+    fn voice_music(&self, sections: &[Section], staff: i32, voice: i32, main: bool, setting: &str, out: &mut String) {
+        out.push_str("{\n");
+        if !setting.is_empty() {
+            out.push_str(&format!("  \\{setting}\n"));
+        }
+        write_sections(sections, out, |midx| self.measure(midx, staff, voice, main));
+        out.push_str("}\n");
+    }
+
+    /// One measure of a voice, ending in a bar check.
+    fn measure(&self, midx: usize, staff: i32, voice: i32, main: bool) -> String {
+        let measure = &self.part.measures[midx];
+        let info = &self.info[midx];
+        let div = info.divisions;
+        let mut tokens: Vec<String> = Vec::new();
+
+        if main {
+            self.attributes(measure, midx, staff, &mut tokens);
+        }
+
+        let notes: Vec<&Note> = measure.notes.iter()
+            .filter(|n| n.staff.unwrap_or(1) == staff && n.voice.unwrap_or(1) == voice)
+            .collect();
+        let mut directions: Vec<&Direction> = measure.directions.iter()
+            .filter(|d| main && d.staff.unwrap_or(1) == staff)
+            .collect();
+        directions.sort_by_key(|d| d.onset);
+        let mut directions = directions.into_iter().peekable();
+        let mut pending: Vec<&Direction> = Vec::new();
+
+        let lone = notes.iter().filter(|n| !n.grace && !n.chord).count() == 1;
+        let mut cursor = 0;
+        let mut open_tuplets = 0;
+        let mut i = 0;
+        while i < notes.len() {
+            let note = notes[i];
+            if note.grace {
+                let start = i;
+                while i < notes.len() && notes[i].grace {
+                    i += 1;
+                }
+                tokens.push(grace_group(&notes[start..i]));
+                continue;
+            }
+            // Gaps in a voice are written as spacer rests
+            if note.onset > cursor {
+                self.spacer(note.onset - cursor, cursor, div, &mut directions, &mut tokens);
+            }
+            while let Some(d) = directions.next_if(|d| d.onset <= note.onset) {
+                tokens.extend(direction_commands(d));
+                pending.push(d);
+            }
+
+            let mut end = i + 1;
+            while end < notes.len() && notes[end].chord {
+                end += 1;
+            }
+            let chord = &notes[i..end];
+
+            if note.tuplets.iter().any(|t| t.tuplet_type == "start") {
+                if let Some(tm) = &note.time_modification {
+                    tokens.push(format!("\\tuplet {}/{} {{", tm.actual_notes, tm.normal_notes));
+                    open_tuplets += 1;
+                }
+            }
+
+            let mut s = if note.rest && (note.measure_rest || lone && note.duration >= info.length) {
+                format!("R{}", length(info.length, div))
+            } else if note.rest {
+                format!("r{}", note_length(note, div, open_tuplets > 0))
+            } else if chord.len() == 1 {
+                let mut s = pitch(note);
+                s.push_str(&note_length(note, div, open_tuplets > 0));
+                if note.tie_start {
+                    s.push('~');
+                }
+                s
+            } else {
+                let all_tied = chord.iter().all(|n| n.tie_start);
+                let pitches: Vec<String> = chord.iter()
+                    .map(|n| pitch(n) + if n.tie_start && !all_tied { "~" } else { "" })
+                    .collect();
+                let mut s = format!("<{}>{}", pitches.join(" "), note_length(note, div, open_tuplets > 0));
+                if all_tied {
+                    s.push('~');
+                }
+                s
+            };
+            s.push_str(&note_marks(note));
+            for d in pending.drain(..) {
+                s.push_str(&direction_marks(d));
+            }
+            s.push_str(&slur_marks(note));
+            match note.beams.iter().find(|b| b.number == 1).map(|b| b.beam_type.as_str()) {
+                Some("begin") => s.push('['),
+                Some("end") => s.push(']'),
+                _ => {}
+            }
+            tokens.push(s);
+
+            if open_tuplets > 0 && note.tuplets.iter().any(|t| t.tuplet_type == "stop") {
+                tokens.push("}".to_string());
+                open_tuplets -= 1;
+            }
+            cursor = note.onset + note.duration;
+            i = end;
+        }
+        for _ in 0..open_tuplets {
+            tokens.push("}".to_string());
+        }
+
+        // Fill the rest of the measure
+        if notes.iter().all(|n| n.grace) {
+            let rest = if main && voice == 1 { 'R' } else { 's' };
+            let mut s = format!("{rest}{}", length(info.length, div));
+            for d in directions.by_ref() {
+                tokens.extend(direction_commands(d));
+                s.push_str(&direction_marks(d));
+            }
+            tokens.push(s);
+        } else if cursor < info.length {
+            self.spacer(info.length - cursor, cursor, div, &mut directions, &mut tokens);
+        }
+        // Directions after the last note go on it
+        let tail: String = directions.map(direction_marks).collect();
+        if let Some(last) = tokens.iter_mut().rev().find(|t| t.starts_with(|c: char| c.is_ascii_lowercase() || c == '<' || c == 'R')) {
+            last.push_str(&tail);
+        }
+
+        if main {
+            if let Some(bar) = bar_command(measure) {
+                tokens.push(bar);
+            }
+        }
+        // Pickups in the middle of a piece complete the measure after them
+        if !info.short || midx == 0 {
+            tokens.push("|".to_string());
+        }
+        tokens.join(" ")
+    }
+
+    /// Clef, key and time changes at the start of a measure, and the
+    /// `\partial` of a pickup.
+    fn attributes(&self, measure: &Measure, midx: usize, staff: i32, tokens: &mut Vec<String>) {
+        if let Some(attrs) = &measure.attributes {
+            let clef = attrs.clefs.iter().find(|c| c.number.max(1) == staff);
+            if let Some(clef) = clef {
+                tokens.push(format!("\\clef {}", clef_name(clef)));
+            }
+            if let Some(key) = &attrs.key {
+                tokens.push(key_command(key));
+            }
+            if let Some(t) = &attrs.time {
+                tokens.push(format!("\\time {}/{}", t.beats, t.beat_type));
+            }
+        }
+        let info = &self.info[midx];
+        if midx == 0 && info.short {
+            tokens.push(format!("\\partial {}", length(info.length, info.divisions)));
+        }
+    }
+
+    /// A spacer rest for a gap, carrying any directions that fall in it.
+    fn spacer<'d>(&self, len: i32, at: i32, div: i32, directions: &mut std::iter::Peekable<impl Iterator<Item = &'d Direction>>, tokens: &mut Vec<String>) {
+        let mut s = format!("s{}", length(len, div));
+        while let Some(d) = directions.next_if(|d| d.onset < at + len) {
+            tokens.extend(direction_commands(d));
+            s.push_str(&direction_marks(d));
+        }
+        tokens.push(s);
+    }
+
+    /// The chord symbols of the part as a `\chordmode` expression, each
+    /// lasting until the next one or the end of the measure.
+    fn chord_music(&self, sections: &[Section], out: &mut String) {
+        out.push_str("{\n");
+        write_sections(sections, out, |midx| {
+            let measure = &self.part.measures[midx];
+            let info = &self.info[midx];
+            let mut harmonies: Vec<&Harmony> = measure.harmonies.iter().collect();
+            harmonies.sort_by_key(|h| h.onset);
+            let mut tokens = Vec::new();
+            let mut cursor = 0;
+            for (hi, h) in harmonies.iter().enumerate() {
+                let onset = h.onset.clamp(0, info.length);
+                if onset > cursor {
+                    tokens.push(format!("s{}", length(onset - cursor, info.divisions)));
+                    cursor = onset;
+                }
+                let end = harmonies.get(hi + 1).map_or(info.length, |n| n.onset.min(info.length));
+                if end > cursor {
+                    tokens.push(chord_symbol(h, &length(end - cursor, info.divisions)));
+                    cursor = end;
+                }
+            }
+            if cursor < info.length {
+                tokens.push(format!("s{}", length(info.length - cursor, info.divisions)));
+            }
+            if !info.short || midx == 0 {
+                tokens.push("|".to_string());
+            }
+            tokens.join(" ")
+        });
+        out.push_str("}\n");
+    }
+
+    /// The syllables of one verse, one per note of the voice (so tied
+    /// and slurred notes get a `_` skip).
+    fn lyrics(&self, voice: i32, verse: i32, out: &mut String) {
+        for measure in &self.part.measures {
+            let syllables: Vec<String> = measure.notes.iter()
+                .filter(|n| n.staff.unwrap_or(1) == 1 && n.voice.unwrap_or(1) == voice)
+                .filter(|n| !n.rest && !n.grace && !n.chord)
+                .map(|n| match n.lyrics.iter().find(|l| l.number == verse) {
+                    Some(lyric) if !lyric.text.trim().is_empty() => {
+                        let joined = matches!(lyric.syllabic.as_deref(), Some("begin" | "middle"));
+                        format!("{}{}", quoted(lyric.text.trim()), if joined { " --" } else { "" })
+                    }
+                    _ => "_".to_string(),
+                })
+                .collect();
+            if !syllables.is_empty() {
+                out.push_str(&format!("  {}\n", syllables.join(" ")));
+            }
+        }
+    }
+}
+
+// ─── Notes ───────────────────────────────────────────────────────────
+
+/// Note types and their LilyPond durations.
+const NOTE_TYPES: [(&str, i64); 9] = [
+    ("breve", 0),
+    ("whole", 1),
+    ("half", 2),
+    ("quarter", 4),
+    ("eighth", 8),
+    ("16th", 16),
+    ("32nd", 32),
+    ("64th", 64),
+    ("128th", 128),
+];
+
+/// A pitch in absolute octave notation with Dutch note names (`c'`, `fis`, `bes,`).
+fn pitch(note: &Note) -> String {
+    let Some(p) = &note.pitch else { return "c'".to_string() };
+    let mut s = note_name(&p.step, p.alter.unwrap_or(0.0));
+    match p.octave - 3 {
+        o if o > 0 => s.push_str(&"'".repeat(o as usize)),
+        o => s.push_str(&",".repeat((-o) as usize)),
+    }
+    s
+}
+
+/// A Dutch note name: `c`, `cis`, `es`, `as`, `bes`, `fisis`…
+fn note_name(step: &str, alter: f64) -> String {
+    let step = step.to_lowercase();
+    let suffix = match (alter * 2.0).round() as i32 {
+        4 => "isis",
+        2 => "is",
+        1 => "ih",
+        -1 => "eh",
+        -2 => "es",
+        -4 => "eses",
+        _ => "",
+    };
+    // E and A drop the e of a flat: es, as, eses, ases
+    if suffix.starts_with("es") && (step == "e" || step == "a") {
+        format!("{step}{}", &suffix[1..])
+    } else {
+        format!("{step}{suffix}")
+    }
+}
+
+/// The written duration of a note: its type and dot when they match the
+/// sounding duration, scaled for tuplets written without a bracket.
+fn note_length(note: &Note, divisions: i32, in_tuplet: bool) -> String {
+    let typed = NOTE_TYPES.iter().find(|t| Some(t.0) == note.note_type.as_deref());
+    let (actual, normal) = note.time_modification.as_ref()
+        .map_or((1, 1), |tm| (tm.actual_notes.max(1) as i64, tm.normal_notes.max(1) as i64));
+    let whole = 4 * divisions as i64;
+    let duration = note.duration as i64;
+
+    if let Some(&(_, base)) = typed {
+        // The type's value as a fraction of a whole note
+        let (mut tn, mut td) = if base == 0 { (2, 1) } else { (1, base) };
+        if note.dot {
+            (tn, td) = (tn * 3, td * 2);
+        }
+        let written = match base {
+            0 => "\\breve".to_string(),
+            _ => base.to_string(),
+        } + if note.dot { "." } else { "" };
+        let as_tuplet = duration * actual * td == tn * whole * normal;
+        if in_tuplet && as_tuplet || !in_tuplet && duration * td == tn * whole {
+            return written;
+        }
+        if !in_tuplet && as_tuplet {
+            return format!("{written}*{normal}/{actual}");
+        }
+    }
+    match in_tuplet {
+        true => fraction_length(duration * actual, whole * normal),
+        false => fraction_length(duration, whole),
+    }
+}
+
+/// A duration in divisions as a LilyPond duration.
+fn length(duration: i32, divisions: i32) -> String {
+    fraction_length(duration as i64, 4 * divisions.max(1) as i64)
+}
+
+/// A fraction of a whole note as a LilyPond duration: `4`, `2.`,
+/// `\breve`, or a scaled whole note such as `1*5/8`.
+fn fraction_length(num: i64, den: i64) -> String {
+    let g = gcd(num, den).max(1);
+    let (n, d) = (num / g, den / g);
+    let power_of_two = |v: i64| v > 0 && v & (v - 1) == 0;
+    match (n, d) {
+        (2, 1) => "\\breve".to_string(),
+        (3, 1) => "\\breve.".to_string(),
+        (1, d) if power_of_two(d) && d <= 128 => d.to_string(),
+        (3, d) if power_of_two(d) && (2..=256).contains(&d) => format!("{}.", d / 2),
+        (7, d) if power_of_two(d) && (4..=512).contains(&d) => format!("{}..", d / 4),
+        (n, 1) => format!("1*{n}"),
+        (n, d) => format!("1*{n}/{d}"),
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+/// A run of grace notes, with any grace chords.
+fn grace_group(notes: &[&Note]) -> String {
+    let mut items = Vec::new();
+    let mut i = 0;
+    while i < notes.len() {
+        let mut end = i + 1;
+        while end < notes.len() && notes[end].chord {
+            end += 1;
+        }
+        let base = NOTE_TYPES.iter()
+            .find(|t| t.1 > 0 && Some(t.0) == notes[i].note_type.as_deref())
+            .map_or(8, |t| t.1);
+        let dur = format!("{base}{}", if notes[i].dot { "." } else { "" });
+        let pitches: Vec<String> = notes[i..end].iter().map(|n| pitch(n)).collect();
+        let mut s = match pitches.len() {
+            1 => format!("{}{dur}", pitches[0]),
+            _ => format!("<{}>{dur}", pitches.join(" ")),
+        };
+        if notes[i].tie_start {
+            s.push('~');
+        }
+        s.push_str(&slur_marks(notes[i]));
+        items.push(s);
+        i = end;
+    }
+    let command = if notes[0].grace_slash { "\\slashedGrace" } else { "\\grace" };
+    format!("{command} {{ {} }}", items.join(" "))
+}
+
+/// Articulations, ornaments and fermatas.
+fn note_marks(note: &Note) -> String {
+    let mut s = String::new();
+    for a in &note.articulations {
+        s.push_str(match a.as_str() {
+            "staccato" => "-.",
+            "accent" => "->",
+            "strong-accent" => "-^",
+            "tenuto" => "--",
+            "staccatissimo" => "-!",
+            "detached-legato" => "-_",
+            _ => "",
+        });
+    }
+    for o in &note.ornaments {
+        s.push_str(match o.as_str() {
+            "trill-mark" => "\\trill",
+            "mordent" => "\\mordent",
+            "inverted-mordent" => "\\prall",
+            "turn" => "\\turn",
+            "inverted-turn" => "\\reverseturn",
+            _ => "",
+        });
+    }
+    match note.fermata.as_deref() {
+        Some("inverted") => s.push_str("_\\fermata"),
+        Some(_) => s.push_str("\\fermata"),
+        None => {}
+    }
+    s
+}
+
+/// Slur ends, then slur starts; slurs other than number 1 are named.
+fn slur_marks(note: &Note) -> String {
+    let mut s = String::new();
+    for kind in ["stop", "start"] {
+        for slur in note.slurs.iter().filter(|sl| sl.slur_type == kind) {
+            let mark = if kind == "start" { '(' } else { ')' };
+            match slur.number {
+                n if n <= 1 => s.push(mark),
+                n => s.push_str(&format!("\\={n}{mark}")),
+            }
+        }
+    }
+    s
+}
+
+// ─── Attributes ──────────────────────────────────────────────────────
+
+fn clef_name(clef: &Clef) -> String {
+    let name = match (clef.sign.as_str(), clef.line) {
+        ("G", 1) => "french",
+        ("G", _) => "treble",
+        ("F", 3) => "varbaritone",
+        ("F", 5) => "subbass",
+        ("F", _) => "bass",
+        ("C", 1) => "soprano",
+        ("C", 2) => "mezzosoprano",
+        ("C", 4) => "tenor",
+        ("C", 5) => "baritone",
+        ("C", _) => "alto",
+        ("percussion", _) => "percussion",
+        ("TAB", _) => "tab",
+        _ => "treble",
+    };
+    match clef.octave_change {
+        Some(-1) => format!("\"{name}_8\""),
+        Some(1) => format!("\"{name}^8\""),
+        Some(-2) => format!("\"{name}_15\""),
+        Some(2) => format!("\"{name}^15\""),
+        _ => name.to_string(),
+    }
+}
+
+/// `\key g \major`, `\key d \dorian`…
+fn key_command(key: &Key) -> String {
+    const MODES: [&str; 8] = ["minor", "dorian", "phrygian", "lydian", "mixolydian", "aeolian", "locrian", "ionian"];
+    let mode = key.mode.as_deref().filter(|m| MODES.contains(m)).unwrap_or("major");
+    let offset = match mode {
+        "minor" | "aeolian" => -3,
+        "mixolydian" => -1,
+        "dorian" => -2,
+        "phrygian" => -4,
+        "lydian" => 1,
+        "locrian" => -5,
+        _ => 0,
+    };
+    let tonic = key.fifths - offset;
+    let step = ["F", "C", "G", "D", "A", "E", "B"][(tonic + 1).rem_euclid(7) as usize];
+    let alter = (tonic + 1).div_euclid(7);
+    format!("\\key {} \\{mode}", note_name(step, alter as f64))
+}
+
+/// A `\bar` for barlines that repeats don't already draw.
+fn bar_command(measure: &Measure) -> Option<String> {
+    let right = measure.barlines.iter().find(|b| b.location == "right")?;
+    if right.repeat.is_some() {
+        return None;
+    }
+    let bar = match right.bar_style.as_deref()? {
+        "light-light" => "||",
+        "light-heavy" => "|.",
+        "heavy-light" => ".|",
+        "heavy-heavy" => "..",
+        "dashed" => "!",
+        "none" => "",
+        _ => return None,
+    };
+    Some(format!("\\bar \"{bar}\""))
+}
+
+// ─── Directions ──────────────────────────────────────────────────────
+
+/// Directions written as commands before the note: tempo, rehearsal
+/// marks and octave shifts.
+fn direction_commands(d: &Direction) -> Vec<String> {
+    let mut commands = Vec::new();
+    if let Some(rehearsal) = &d.rehearsal {
+        commands.push(format!("\\mark {}", quoted(rehearsal)));
+    }
+    if let Some(bpm) = d.sound_tempo {
+        let text = d.words.as_ref().map(|w| format!("{} ", quoted(w))).unwrap_or_default();
+        let mark = match &d.metronome {
+            Some(m) => {
+                let base = NOTE_TYPES.iter().find(|t| t.1 > 0 && t.0 == m.beat_unit).map_or(4, |t| t.1);
+                format!("{base}{} = {}", if m.dotted { "." } else { "" }, m.per_minute)
+            }
+            None => format!("4 = {}", bpm.round() as i32),
+        };
+        commands.push(format!("\\tempo {text}{mark}"));
+    }
+    match (d.octave_shift_type.as_deref(), d.octave_shift_size) {
+        (Some("down"), size) => commands.push(format!("\\ottava #{}", if size >= 15 { 2 } else { 1 })),
+        (Some("up"), size) => commands.push(format!("\\ottava #{}", if size >= 15 { -2 } else { -1 })),
+        (Some("stop"), _) => commands.push("\\ottava #0".to_string()),
+        _ => {}
+    }
+    commands
+}
+
+/// Directions attached to a note: dynamics, hairpins, text and signs.
+fn direction_marks(d: &Direction) -> String {
+    const DYNAMICS: [&str; 20] = [
+        "ppppp", "pppp", "ppp", "pp", "p", "mp", "mf", "f", "ff", "fff", "ffff", "fffff",
+        "fp", "sf", "sff", "sfz", "sp", "spp", "fz", "rfz",
+    ];
+    let mut s = String::new();
+    if let Some(dynamics) = d.dynamics.as_deref().filter(|dy| DYNAMICS.contains(dy)) {
+        s.push_str(&format!("\\{dynamics}"));
+    }
+    match d.wedge_type.as_deref() {
+        Some("crescendo") => s.push_str("\\<"),
+        Some("diminuendo") => s.push_str("\\>"),
+        Some("stop") => s.push_str("\\!"),
+        _ => {}
+    }
+    let glyph = |name: &str| format!("^\\markup {{ \\musicglyph #\"scripts.{name}\" }}");
+    if d.segno {
+        s.push_str(&glyph("segno"));
+    }
+    if d.coda {
+        s.push_str(&glyph("coda"));
+    }
+    // Tempo words are part of \tempo
+    if d.sound_tempo.is_none() {
+        let jump = [
+            (d.sound_dacapo, "D.C."),
+            (d.sound_dalsegno, "D.S."),
+            (d.sound_fine, "Fine"),
+            (d.sound_tocoda, "To Coda"),
+        ];
+        let text = d.words.clone().or_else(|| jump.iter().find(|j| j.0).map(|j| j.1.to_string()));
+        if let Some(text) = text.filter(|t| !t.trim().is_empty()) {
+            let side = if d.placement.as_deref() == Some("below") { '_' } else { '^' };
+            let style = if d.words_font_style.as_deref() == Some("italic") { "\\italic " } else { "" };
+            s.push_str(&format!("{side}\\markup {{ {style}{} }}", quoted(&text)));
+        }
+    }
+    s
+}
+
+// ─── Chord symbols ───────────────────────────────────────────────────
+
+/// A `\chordmode` chord such as `g2:m7/d`; "none" is written as a rest,
+/// which chord names show as N.C.
+fn chord_symbol(h: &Harmony, duration: &str) -> String {
+    if h.kind == "none" {
+        return format!("r{duration}");
+    }
+    let modifier = match h.kind.as_str() {
+        "minor" => "m",
+        "augmented" => "aug",
+        "diminished" => "dim",
+        "dominant" | "dominant-seventh" => "7",
+        "major-seventh" => "maj7",
+        "minor-seventh" => "m7",
+        "diminished-seventh" => "dim7",
+        "augmented-seventh" => "aug7",
+        "half-diminished" => "m7.5-",
+        "major-minor" => "m7+",
+        "major-sixth" => "6",
+        "minor-sixth" => "m6",
+        "dominant-ninth" => "9",
+        "major-ninth" => "maj9",
+        "minor-ninth" => "m9",
+        "dominant-11th" => "11",
+        "minor-11th" => "m11",
+        "dominant-13th" => "13",
+        "suspended-second" => "sus2",
+        "suspended-fourth" => "sus4",
+        "power" => "1.5",
+        _ => "",
+    };
+    let mut s = note_name(&h.root.step, h.root.alter.unwrap_or(0.0));
+    s.push_str(duration);
+    if !modifier.is_empty() {
+        s.push(':');
+        s.push_str(modifier);
+    }
+    if let Some(bass) = &h.bass {
+        s.push('/');
+        s.push_str(&note_name(&bass.step, bass.alter.unwrap_or(0.0)));
+    }
+    s
+}
+
+// ─── Helpers ─────────────────────────────────────────────────────────
+
+/// Letters for variable names, which LilyPond doesn't allow digits in:
+/// 0 → "A", 25 → "Z", 26 → "AA".
+fn letters(mut n: usize) -> String {
+    let mut s = String::new();
+    loop {
+        s.insert(0, (b'A' + (n % 26) as u8) as char);
+        if n < 26 {
+            return s;
+        }
+        n = n / 26 - 1;
+    }
+}
+
+/// A LilyPond string literal.
+fn quoted(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
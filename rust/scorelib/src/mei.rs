@@ -0,0 +1,1087 @@
+//! MEI import — converts Music Encoding Initiative XML into the Score model.
+//!
+//! Each `<staffDef>` becomes a part, except that staves braced together
+//! in a `<staffGrp symbol="brace">` (a piano's two staves) share one, and
+//! each `<layer>` is a voice.  Clefs, keys and meters come from
+//! `<scoreDef>` and `<staffDef>`, as attributes (`meter.count`,
+//! `key.sig`, `clef.shape`…) or as `<meterSig>`, `<keySig>` and `<clef>`
+//! elements.  Notes, rests, chords, spaces and measure rests are read
+//! through `<beam>` and `<tuplet>` groups; ties and slurs come from
+//! `@tie`/`@slur` or from `<tie>`/`<slur>` control events; lyrics from
+//! `<verse>`/`<syl>`; and `<ending>`, repeat barlines, `<tempo>` and
+//! `<dynam>` are kept too.  Of an `<app>` or `<choice>`, only the first
+//! reading is used.
+//!
+//! A note sounds its `@accid.ges`, else its written `@accid`, else what the
+//! key signature and earlier accidentals in the measure make it.
+
+use std::collections::HashMap;
+
+use roxmltree::{Document, Node};
+
+use crate::model::*;
+
+/// Working resolution in divisions per quarter note, fine enough for
+/// tuplets up to nine; reduced to the coarsest one that fits every note
+/// once the file is read.
+const TICKS_PER_QUARTER: i64 = 40320;
+
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Parse an MEI document into a Score.
+pub fn parse_mei(xml: &str) -> Result<Score, String> {
+    let options = roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    let doc = Document::parse_with_options(xml, options)
+        .map_err(|e| format!("XML parse error: {e}"))?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "mei" {
+        return Err(format!(
+            "Unsupported root element: '{}'. Expected 'mei'.",
+            root.tag_name().name()
+        ));
+    }
+
+    let mut reader = MeiReader::default();
+    if let Some(head) = child(root, "meiHead") {
+        reader.header(head);
+    }
+    let score = child(root, "music")
+        .and_then(|music| music.descendants().find(|n| n.tag_name().name() == "score"))
+        .ok_or_else(|| "MEI file has no <music> with a <score>".to_string())?;
+    reader.walk(score);
+    reader.finish()
+}
+
+// ─── Reader state ────────────────────────────────────────────────────
+
+/// A staff (`<staffDef n>`) and the part it belongs to.
+struct StaffState {
+    part: usize,
+    /// Staff number within the part
+    number: i32,
+    fifths: i32,
+    /// Changes waiting for the next measure
+    clef: Option<Clef>,
+    key: Option<Key>,
+    /// Accidentals written so far in the current measure
+    accidentals: HashMap<(String, i32), f64>,
+}
+
+struct PartState {
+    name: Option<String>,
+    abbreviation: Option<String>,
+    staves: i32,
+    measures: Vec<Measure>,
+}
+
+/// A control event waiting for every note to be read.
+enum Control {
+    Tie(String, String),
+    Slur(String, String),
+    Fermata(String, String),
+}
+
+/// Where a layer is being read.
+struct LayerContext {
+    part: usize,
+    staff: String,
+    voice: i32,
+    multi: bool,
+    cursor: i64,
+    /// Product of the enclosing tuplets: (num, numbase)
+    ratio: (i64, i64),
+    /// Most recent duration, for notes that leave it out
+    last_dur: String,
+}
+
+#[derive(Default)]
+struct MeiReader {
+    title: Option<String>,
+    subtitle: Option<String>,
+    composer: Option<String>,
+    staves: HashMap<String, StaffState>,
+    parts: Vec<PartState>,
+    /// Meter in effect and whether it changed since the last measure
+    meter: Option<(i32, i32)>,
+    meter_changed: bool,
+    number: i32,
+    repeat_next: bool,
+    new_system: bool,
+    new_page: bool,
+    ids: HashMap<String, (usize, usize, usize)>,
+    controls: Vec<Control>,
+}
+
+impl MeiReader {
+    fn header(&mut self, head: Node) {
+        if let Some(stmt) = head.descendants().find(|n| n.tag_name().name() == "titleStmt") {
+            for title in stmt.children().filter(|n| n.tag_name().name() == "title") {
+                let text = text_of(title);
+                if text.is_empty() {
+                    continue;
+                }
+                match title.attribute("type") {
+                    Some("subtitle") => self.subtitle = self.subtitle.take().or(Some(text)),
+                    _ => self.title = self.title.take().or(Some(text)),
+                }
+            }
+        }
+        self.composer = head.descendants()
+            .find(|n| {
+                n.tag_name().name() == "composer"
+                    || (n.tag_name().name() == "persName" && n.attribute("role") == Some("composer"))
+            })
+            .map(text_of)
+            .filter(|t| !t.is_empty());
+    }
+
+    /// Score structure: definitions, sections, endings and measures.
+    fn walk(&mut self, node: Node) {
+        for el in node.children().filter(|n| n.is_element()) {
+            match el.tag_name().name() {
+                "scoreDef" => self.score_def(el),
+                "staffDef" => self.staff_def(el),
+                "section" | "lem" | "rdg" | "corr" | "reg" | "sic" | "orig" | "add" | "supplied" => self.walk(el),
+                "app" | "choice" => {
+                    if let Some(reading) = chosen(el) {
+                        self.walk(reading);
+                    }
+                }
+                "ending" => {
+                    let first = self.measure_count();
+                    self.walk(el);
+                    self.ending(el, first);
+                }
+                "measure" => self.measure(el),
+                "pb" => self.new_page = true,
+                "sb" => self.new_system = true,
+                _ => {}
+            }
+        }
+    }
+
+    fn measure_count(&self) -> usize {
+        self.parts.first().map_or(0, |p| p.measures.len())
+    }
+
+    // ── Definitions ──
+
+    fn score_def(&mut self, node: Node) {
+        self.read_meter(node);
+        let groups: Vec<Node> = node.children().filter(|n| n.tag_name().name() == "staffGrp").collect();
+        if self.parts.is_empty() {
+            if let Some(grp) = groups.first() {
+                self.staff_group(*grp);
+            }
+        }
+        // The score-wide key applies to every staff unless its staffDef overrides it
+        if let Some(key) = read_key(node) {
+            for staff in self.staves.values_mut() {
+                staff.fifths = key.fifths;
+                staff.key = Some(key.clone());
+            }
+        }
+        for grp in groups {
+            for def in grp.descendants().filter(|n| n.tag_name().name() == "staffDef") {
+                self.staff_def(def);
+            }
+        }
+    }
+
+    /// Parts from the first `<staffGrp>`: a braced group of staves is
+    /// one part, any other staff a part of its own.
+    fn staff_group(&mut self, grp: Node) {
+        let defs: Vec<Node> = grp.children().filter(|n| n.tag_name().name() == "staffDef").collect();
+        let nested = grp.children().any(|n| n.tag_name().name() == "staffGrp");
+        if grp.attribute("symbol") == Some("brace") && defs.len() > 1 && !nested {
+            let name = label(grp).or_else(|| defs.iter().find_map(|d| label(*d)));
+            let part = self.add_part(name, grp.attribute("label.abbr").map(String::from));
+            for def in defs {
+                self.add_staff(def.attribute("n").unwrap_or("1"), part);
+            }
+            return;
+        }
+        for el in grp.children().filter(|n| n.is_element()) {
+            match el.tag_name().name() {
+                "staffGrp" => self.staff_group(el),
+                "staffDef" => {
+                    let part = self.add_part(label(el), el.attribute("label.abbr").map(String::from));
+                    self.add_staff(el.attribute("n").unwrap_or("1"), part);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn add_part(&mut self, name: Option<String>, abbreviation: Option<String>) -> usize {
+        // A part added after the first measure starts with empty ones
+        let measures = (0..self.measure_count()).map(|i| blank_measure(i as i32 + 1)).collect();
+        self.parts.push(PartState { name, abbreviation, staves: 0, measures });
+        self.parts.len() - 1
+    }
+
+    fn add_staff(&mut self, n: &str, part: usize) {
+        self.parts[part].staves += 1;
+        self.staves.insert(n.to_string(), StaffState {
+            part,
+            number: self.parts[part].staves,
+            fifths: 0,
+            clef: None,
+            key: None,
+            accidentals: HashMap::new(),
+        });
+    }
+
+    fn staff_def(&mut self, node: Node) {
+        self.read_meter(node);
+        let Some(staff) = node.attribute("n").and_then(|n| self.staves.get_mut(n)) else { return };
+        if let Some(clef) = read_clef(node) {
+            staff.clef = Some(clef);
+        }
+        if let Some(key) = read_key(node) {
+            staff.fifths = key.fifths;
+            staff.key = Some(key);
+        }
+        let part = staff.part;
+        if self.parts[part].name.is_none() {
+            self.parts[part].name = label(node);
+        }
+    }
+
+    fn read_meter(&mut self, node: Node) {
+        let meter_sig = child(node, "meterSig");
+        let attr = |name: &str| {
+            node.attribute(format!("meter.{name}").as_str())
+                .or_else(|| meter_sig.and_then(|m| m.attribute(name)))
+        };
+        let meter = match (attr("count"), attr("unit"), attr("sym")) {
+            (Some(count), Some(unit), _) => {
+                // Additive meters such as 3+2 add up
+                let beats = count.split('+').filter_map(|c| c.trim().parse::<i32>().ok()).sum::<i32>();
+                unit.parse::<i32>().ok().map(|u| (beats, u))
+            }
+            (_, _, Some("common")) => Some((4, 4)),
+            (_, _, Some("cut")) => Some((2, 2)),
+            _ => None,
+        };
+        if let Some(meter) = meter.filter(|&(b, u)| b > 0 && u > 0) {
+            self.meter = Some(meter);
+            self.meter_changed = true;
+        }
+    }
+
+    // ── Measures ──
+
+    fn measure(&mut self, node: Node) {
+        let index = self.measure_count();
+        let number = node.attribute("n")
+            .and_then(|n| n.chars().take_while(|c| c.is_ascii_digit()).collect::<String>().parse().ok())
+            .unwrap_or(self.number + 1);
+        self.number = number;
+        let right = node.attribute("right");
+        let left = node.attribute("left");
+        let repeat_start = std::mem::take(&mut self.repeat_next) || matches!(left, Some("rptstart" | "rptboth"));
+        self.repeat_next = right == Some("rptboth");
+
+        // Staves in the file without a definition get a part each
+        for staff in node.children().filter(|n| n.tag_name().name() == "staff") {
+            let n = staff.attribute("n").unwrap_or("1");
+            if !self.staves.contains_key(n) {
+                let part = self.add_part(None, None);
+                self.add_staff(n, part);
+            }
+        }
+
+        for (pi, part) in self.parts.iter_mut().enumerate() {
+            let mut measure = blank_measure(number);
+            measure.implicit = number == 0 || node.attribute("metcon") == Some("false");
+            measure.new_system = self.new_system;
+            measure.new_page = self.new_page;
+
+            let mut staves: Vec<&mut StaffState> = self.staves.values_mut().filter(|s| s.part == pi).collect();
+            staves.sort_by_key(|s| s.number);
+            let mut attributes = Attributes {
+                divisions: (index == 0).then_some(TICKS_PER_QUARTER as i32),
+                key: None,
+                time: self.meter
+                    .filter(|_| self.meter_changed || index == 0)
+                    .map(|(beats, beat_type)| TimeSignature { beats, beat_type }),
+                clefs: Vec::new(),
+                transpose: None,
+                staves: (index == 0 && part.staves > 1).then_some(part.staves),
+                staff_details: Vec::new(),
+            };
+            for staff in staves {
+                if let Some(key) = staff.key.take() {
+                    attributes.key.get_or_insert(key);
+                }
+                match staff.clef.take() {
+                    Some(clef) => attributes.clefs.push(Clef { number: staff.number, ..clef }),
+                    None if index == 0 => attributes.clefs.push(Clef { number: staff.number, sign: "G".to_string(), line: 2, octave_change: None }),
+                    None => {}
+                }
+                if index == 0 && attributes.key.is_none() {
+                    attributes.key = Some(Key { fifths: staff.fifths, mode: None });
+                }
+                staff.accidentals.clear();
+            }
+            if index == 0 || attributes.key.is_some() || attributes.time.is_some() || !attributes.clefs.is_empty() {
+                measure.attributes = Some(attributes);
+            }
+
+            if repeat_start {
+                barline_mut(&mut measure, "left").bar_style = Some("heavy-light".to_string());
+                barline_mut(&mut measure, "left").repeat = Some(Repeat { direction: "forward".to_string() });
+            }
+            let style = match right {
+                Some("rptend" | "rptboth" | "end") => Some("light-heavy"),
+                Some("dbl") => Some("light-light"),
+                _ => None,
+            };
+            if let Some(style) = style {
+                let barline = barline_mut(&mut measure, "right");
+                barline.bar_style = Some(style.to_string());
+                if matches!(right, Some("rptend" | "rptboth")) {
+                    barline.repeat = Some(Repeat { direction: "backward".to_string() });
+                }
+            }
+            part.measures.push(measure);
+        }
+        self.meter_changed = false;
+        self.new_system = false;
+        self.new_page = false;
+
+        let (beats, beat_type) = self.meter.unwrap_or((4, 4));
+        let measure_len = beats as i64 * 4 * TICKS_PER_QUARTER / beat_type as i64;
+        for el in node.children().filter(|n| n.is_element()) {
+            match el.tag_name().name() {
+                "staff" => self.staff(el, measure_len),
+                "tie" | "slur" | "fermata" => {
+                    let Some(start) = el.attribute("startid").map(id_ref) else { continue };
+                    let control = match el.tag_name().name() {
+                        "tie" | "slur" => {
+                            let Some(end) = el.attribute("endid").map(id_ref) else { continue };
+                            if el.tag_name().name() == "tie" { Control::Tie(start, end) } else { Control::Slur(start, end) }
+                        }
+                        _ => Control::Fermata(start, el.attribute("place").unwrap_or("above").to_string()),
+                    };
+                    self.controls.push(control);
+                }
+                "tempo" | "dynam" => self.direction(el, index),
+                _ => {}
+            }
+        }
+    }
+
+    fn staff(&mut self, node: Node, measure_len: i64) {
+        let n = node.attribute("n").unwrap_or("1");
+        let Some(staff) = self.staves.get(n) else { return };
+        let (part, number) = (staff.part, staff.number);
+        let multi = self.parts[part].staves > 1;
+        for (li, layer) in node.children().filter(|c| c.tag_name().name() == "layer").enumerate() {
+            let voice = layer.attribute("n").and_then(|v| v.parse().ok()).unwrap_or(li as i32 + 1);
+            let mut ctx = LayerContext {
+                part,
+                staff: n.to_string(),
+                voice: if multi { (number - 1) * 4 + voice } else { voice },
+                multi,
+                cursor: 0,
+                ratio: (1, 1),
+                last_dur: "4".to_string(),
+            };
+            self.events(layer, &mut ctx, measure_len);
+        }
+    }
+
+    /// Notes, rests and groups in a layer.
+    fn events(&mut self, node: Node, ctx: &mut LayerContext, measure_len: i64) {
+        for el in node.children().filter(|n| n.is_element()) {
+            match el.tag_name().name() {
+                "note" => {
+                    let ticks = self.note(el, None, ctx);
+                    ctx.cursor += ticks;
+                }
+                "chord" => self.chord(el, ctx),
+                "rest" | "mRest" | "multiRest" => {
+                    let mut note = blank_note(ctx.voice);
+                    note.rest = true;
+                    note.onset = ctx.cursor as i32;
+                    if el.tag_name().name() == "rest" {
+                        let value = self.value(el, None, ctx);
+                        note.duration = value.ticks as i32;
+                        note.note_type = Some(value.note_type.to_string());
+                        note.dot = value.dots > 0;
+                        note.time_modification = value.time_modification();
+                    } else {
+                        note.measure_rest = true;
+                        note.duration = measure_len as i32;
+                        note.note_type = Some("whole".to_string());
+                    }
+                    ctx.cursor += note.duration as i64;
+                    self.push_note(el, note, ctx);
+                }
+                "space" => ctx.cursor += self.value(el, None, ctx).ticks,
+                "beam" => {
+                    let start = self.current_notes(ctx.part).len();
+                    self.events(el, ctx, measure_len);
+                    add_beams(&mut self.current_notes(ctx.part)[start..]);
+                }
+                "tuplet" => {
+                    let num = el.attribute("num").and_then(|n| n.parse::<i64>().ok()).unwrap_or(3);
+                    let numbase = el.attribute("numbase").and_then(|n| n.parse::<i64>().ok()).unwrap_or(2);
+                    let start = self.current_notes(ctx.part).len();
+                    let outer = ctx.ratio;
+                    ctx.ratio = (outer.0 * num.max(1), outer.1 * numbase.max(1));
+                    self.events(el, ctx, measure_len);
+                    ctx.ratio = outer;
+                    let notes = self.current_notes(ctx.part);
+                    let principals: Vec<usize> = (start..notes.len()).filter(|&i| !notes[i].chord && !notes[i].grace).collect();
+                    if let (Some(&first), Some(&last)) = (principals.first(), principals.last()) {
+                        notes[first].tuplets.push(tuplet_event("start"));
+                        notes[last].tuplets.push(tuplet_event("stop"));
+                    }
+                }
+                "clef" => {
+                    if let Some(clef) = read_clef(el) {
+                        let number = self.staves[&ctx.staff].number;
+                        let measure = self.parts[ctx.part].measures.last_mut().unwrap();
+                        let attributes = measure.attributes.get_or_insert_with(blank_attributes);
+                        attributes.clefs.retain(|c| c.number != number);
+                        attributes.clefs.push(Clef { number, ..clef });
+                    }
+                }
+                "graceGrp" | "bTrem" | "fTrem" | "lem" | "rdg" | "corr" | "reg" | "sic" | "orig" | "add" | "supplied" => {
+                    self.events(el, ctx, measure_len)
+                }
+                "app" | "choice" => {
+                    if let Some(reading) = chosen(el) {
+                        self.events(reading, ctx, measure_len);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn chord(&mut self, node: Node, ctx: &mut LayerContext) {
+        let start = self.current_notes(ctx.part).len();
+        let mut ticks = 0;
+        for el in node.children().filter(|n| n.tag_name().name() == "note") {
+            ticks = ticks.max(self.note(el, Some(node), ctx));
+        }
+        let notes = self.current_notes(ctx.part);
+        if notes.len() > start {
+            for note in &mut notes[start + 1..] {
+                note.chord = true;
+            }
+            notes[start].lyrics.extend(read_lyrics(node));
+            if let Some(id) = node.attribute((XML_NS, "id")) {
+                let measure = self.parts[ctx.part].measures.len() - 1;
+                self.ids.insert(id.to_string(), (ctx.part, measure, start));
+            }
+        }
+        ctx.cursor += ticks;
+    }
+
+    /// Read a note (of `chord`, when given); returns the time it takes.
+    fn note(&mut self, node: Node, chord: Option<Node>, ctx: &mut LayerContext) -> i64 {
+        let attr = |name: &str| node.attribute(name).or_else(|| chord.and_then(|c| c.attribute(name)));
+        let grace = attr("grace");
+        let value = self.value(node, chord, ctx);
+        let ticks = if grace.is_some() { 0 } else { value.ticks };
+
+        let mut note = blank_note(ctx.voice);
+        note.onset = ctx.cursor as i32;
+        note.duration = ticks as i32;
+        note.note_type = Some(value.note_type.to_string());
+        note.dot = value.dots > 0;
+        if grace.is_none() {
+            note.time_modification = value.time_modification();
+        }
+        note.grace = grace.is_some();
+        note.grace_slash = grace == Some("unacc") || attr("stem.mod") == Some("1slash");
+        note.stem = attr("stem.dir").filter(|d| matches!(*d, "up" | "down")).map(String::from);
+
+        // Pitch: sounding accidental, written one, the measure, the key
+        let (Some(pname), Some(oct)) = (node.attribute("pname"), node.attribute("oct").and_then(|o| o.parse::<i32>().ok())) else {
+            return ticks;
+        };
+        let step = pname.to_uppercase();
+        let accid = child(node, "accid");
+        let written = node.attribute("accid").or_else(|| accid.and_then(|a| a.attribute("accid"))).and_then(accid_alter);
+        let sounding = node.attribute("accid.ges").or_else(|| accid.and_then(|a| a.attribute("accid.ges"))).and_then(accid_alter);
+        let staff = self.staves.get_mut(&ctx.staff).unwrap();
+        let line = (step.clone(), oct);
+        if let Some(alter) = written {
+            staff.accidentals.insert(line.clone(), alter);
+            note.accidental = Some(accidental_name(alter));
+        }
+        let alter = sounding.or(written)
+            .or_else(|| staff.accidentals.get(&line).copied())
+            .unwrap_or_else(|| key_alter(staff.fifths, &step));
+        note.pitch = Some(Pitch { step, octave: oct, alter: (alter != 0.0).then_some(alter) });
+
+        match node.attribute("tie") {
+            Some("i") => note.tie_start = true,
+            Some("m") => {
+                note.tie_start = true;
+                note.tie_stop = true;
+            }
+            Some("t") => note.tie_stop = true,
+            _ => {}
+        }
+        for mark in attr("slur").unwrap_or("").split_whitespace() {
+            let (kind, number) = mark.split_at(1);
+            let number = number.parse().unwrap_or(1);
+            match kind {
+                "i" => note.slurs.push(SlurEvent { slur_type: "start".to_string(), number, placement: None }),
+                "t" => note.slurs.push(SlurEvent { slur_type: "stop".to_string(), number, placement: None }),
+                _ => {}
+            }
+        }
+        let artics = attr("artic").unwrap_or("").split_whitespace()
+            .chain(node.children().chain(chord.iter().flat_map(|c| c.children()))
+                .filter(|n| n.tag_name().name() == "artic")
+                .filter_map(|n| n.attribute("artic"))
+                .flat_map(str::split_whitespace));
+        for artic in artics {
+            let name = match artic {
+                "stacc" => "staccato",
+                "acc" => "accent",
+                "ten" => "tenuto",
+                "marc" => "strong-accent",
+                "stacciss" => "staccatissimo",
+                "spicc" => "spiccato",
+                _ => continue,
+            };
+            if !note.articulations.iter().any(|a| a == name) {
+                note.articulations.push(name.to_string());
+            }
+        }
+        if let Some(place) = attr("fermata") {
+            note.fermata = Some(if place == "below" { "inverted" } else { "upright" }.to_string());
+        }
+        note.lyrics = read_lyrics(node);
+
+        self.push_note(node, note, ctx);
+        ticks
+    }
+
+    fn push_note(&mut self, node: Node, mut note: Note, ctx: &LayerContext) {
+        if ctx.multi {
+            note.staff = Some(self.staves[&ctx.staff].number);
+        }
+        let measures = &mut self.parts[ctx.part].measures;
+        let measure = measures.len() - 1;
+        let notes = &mut measures[measure].notes;
+        if let Some(id) = node.attribute((XML_NS, "id")) {
+            self.ids.insert(id.to_string(), (ctx.part, measure, notes.len()));
+        }
+        notes.push(note);
+    }
+
+    /// Written value and length of an event, inside the current tuplets.
+    fn value(&self, node: Node, chord: Option<Node>, ctx: &mut LayerContext) -> NoteValue {
+        let attr = |name: &str| node.attribute(name).or_else(|| chord.and_then(|c| c.attribute(name)));
+        let dur = attr("dur").unwrap_or(&ctx.last_dur).to_string();
+        let dots = attr("dots").and_then(|d| d.parse::<u32>().ok())
+            .unwrap_or_else(|| node.children().filter(|n| n.tag_name().name() == "dot").count() as u32);
+        let (num, den, note_type) = match dur.as_str() {
+            "long" => (4, 1, "long"),
+            "breve" => (2, 1, "breve"),
+            d => {
+                let den = d.parse::<i64>().unwrap_or(4).max(1);
+                let note_type = match den {
+                    1 => "whole",
+                    2 => "half",
+                    4 => "quarter",
+                    8 => "eighth",
+                    16 => "16th",
+                    32 => "32nd",
+                    64 => "64th",
+                    128 => "128th",
+                    _ => "256th",
+                };
+                (1, den, note_type)
+            }
+        };
+        ctx.last_dur = dur;
+        let factor = (1i64 << (dots + 1)) - 1;
+        let scale = 1i64 << dots;
+        let (tn, tb) = ctx.ratio;
+        let whole = 4 * TICKS_PER_QUARTER;
+        let ticks = (num * whole * factor * tb + den * scale * tn / 2) / (den * scale * tn);
+        NoteValue { note_type, dots, ticks, ratio: ctx.ratio }
+    }
+
+    fn current_notes(&mut self, part: usize) -> &mut Vec<Note> {
+        &mut self.parts[part].measures.last_mut().unwrap().notes
+    }
+
+    // ── Control events ──
+
+    /// `<tempo>` and `<dynam>` as directions at their time stamp.
+    fn direction(&mut self, node: Node, measure: usize) {
+        let staff = node.attribute("staff")
+            .and_then(|s| s.split_whitespace().next())
+            .and_then(|s| self.staves.get(s));
+        let (part, number, multi) = match staff {
+            Some(s) => (s.part, s.number, self.parts[s.part].staves > 1),
+            None => (0, 1, false),
+        };
+        let Some(target) = self.parts.get_mut(part) else { return };
+        let (_, beat_type) = self.meter.unwrap_or((4, 4));
+        let beat = node.attribute("tstamp").and_then(|t| t.parse::<f64>().ok()).unwrap_or(1.0);
+        let onset = ((beat - 1.0).max(0.0) * 4.0 / beat_type as f64 * TICKS_PER_QUARTER as f64).round() as i32;
+
+        let text = text_of(node);
+        let mut direction = blank_direction();
+        direction.onset = onset;
+        direction.staff = multi.then_some(number);
+        if node.tag_name().name() == "dynam" {
+            const DYNAMICS: [&str; 16] = ["pppp", "ppp", "pp", "p", "mp", "mf", "f", "ff", "fff", "ffff", "sf", "sfz", "sffz", "fp", "rf", "fz"];
+            if DYNAMICS.contains(&text.as_str()) {
+                direction.dynamics = Some(text);
+            } else if !text.is_empty() {
+                direction.words = Some(text);
+            } else {
+                return;
+            }
+            direction.placement = Some("below".to_string());
+        } else {
+            let unit = node.attribute("mm.unit").and_then(|u| u.parse::<f64>().ok()).unwrap_or(4.0);
+            let dotted = node.attribute("mm.dots").is_some_and(|d| d != "0");
+            let quarters = 4.0 / unit * if dotted { 1.5 } else { 1.0 };
+            let mm = node.attribute("mm").and_then(|m| m.parse::<f64>().ok());
+            direction.sound_tempo = node.attribute("midi.bpm").and_then(|b| b.parse::<f64>().ok())
+                .or(mm.map(|m| m * quarters));
+            direction.metronome = mm.map(|m| MetronomeMark {
+                beat_unit: match unit as i32 {
+                    1 => "whole",
+                    2 => "half",
+                    8 => "eighth",
+                    16 => "16th",
+                    _ => "quarter",
+                }
+                .to_string(),
+                per_minute: m.round() as i32,
+                dotted,
+            });
+            direction.words = Some(text).filter(|t| !t.is_empty());
+            if direction.sound_tempo.is_none() && direction.words.is_none() {
+                return;
+            }
+        }
+        target.measures[measure].directions.push(direction);
+    }
+
+    /// Mark the first and last measures of an `<ending>`.
+    fn ending(&mut self, node: Node, first: usize) {
+        let count = self.measure_count();
+        if count <= first {
+            return;
+        }
+        let number = node.attribute("n").or_else(|| node.attribute("label")).unwrap_or("1").to_string();
+        for part in &mut self.parts {
+            let start = barline_mut(&mut part.measures[first], "left");
+            start.ending = Some(Ending { number: number.clone(), ending_type: "start".to_string(), text: Some(format!("{number}.")) });
+            let last = &mut part.measures[count - 1];
+            let repeats = last.barlines.iter().any(|b| b.repeat.as_ref().is_some_and(|r| r.direction == "backward"));
+            barline_mut(last, "right").ending = Some(Ending {
+                number: number.clone(),
+                ending_type: if repeats { "stop" } else { "discontinue" }.to_string(),
+                text: None,
+            });
+        }
+    }
+
+    fn finish(mut self) -> Result<Score, String> {
+        if self.parts.is_empty() || self.measure_count() == 0 {
+            return Err("MEI score has no measures".to_string());
+        }
+
+        // Ties, slurs and fermatas between notes found by their IDs
+        let mut open_slurs: Vec<(usize, (usize, usize), i32)> = Vec::new();
+        for control in std::mem::take(&mut self.controls) {
+            let find = |id: &String| self.ids.get(id).copied();
+            match control {
+                Control::Tie(start, end) => {
+                    if let (Some(a), Some(b)) = (find(&start), find(&end)) {
+                        self.parts[a.0].measures[a.1].notes[a.2].tie_start = true;
+                        self.parts[b.0].measures[b.1].notes[b.2].tie_stop = true;
+                    }
+                }
+                Control::Slur(start, end) => {
+                    let (Some(a), Some(b)) = (find(&start), find(&end)) else { continue };
+                    // Number each slur apart from those still open at its start
+                    open_slurs.retain(|&(part, stop, _)| part != a.0 || stop >= (a.1, a.2));
+                    let number = (1..).find(|n| !open_slurs.iter().any(|s| s.0 == a.0 && s.2 == *n)).unwrap();
+                    open_slurs.push((a.0, (b.1, b.2), number));
+                    self.parts[a.0].measures[a.1].notes[a.2].slurs.push(SlurEvent { slur_type: "start".to_string(), number, placement: None });
+                    self.parts[b.0].measures[b.1].notes[b.2].slurs.push(SlurEvent { slur_type: "stop".to_string(), number, placement: None });
+                }
+                Control::Fermata(start, place) => {
+                    if let Some(a) = find(&start) {
+                        let fermata = if place == "below" { "inverted" } else { "upright" };
+                        self.parts[a.0].measures[a.1].notes[a.2].fermata = Some(fermata.to_string());
+                    }
+                }
+            }
+        }
+
+        // Divisions: the coarsest that still fits every note
+        let mut step = TICKS_PER_QUARTER;
+        for measure in self.parts.iter().flat_map(|p| &p.measures) {
+            for note in &measure.notes {
+                step = gcd(step, gcd(note.duration as i64, note.onset as i64));
+            }
+            for direction in &measure.directions {
+                step = gcd(step, direction.onset as i64);
+            }
+        }
+        let step = step.max(1);
+
+        let mut score = Score::new();
+        score.title = self.title;
+        score.subtitle = self.subtitle;
+        score.composer = self.composer;
+        for (pi, part) in self.parts.into_iter().enumerate() {
+            let mut measures = part.measures;
+            for measure in &mut measures {
+                if let Some(divisions) = measure.attributes.as_mut().and_then(|a| a.divisions.as_mut()) {
+                    *divisions = (TICKS_PER_QUARTER / step) as i32;
+                }
+                for note in &mut measure.notes {
+                    note.duration = (note.duration as i64 / step) as i32;
+                    note.onset = (note.onset as i64 / step) as i32;
+                }
+                for direction in &mut measure.directions {
+                    direction.onset = (direction.onset as i64 / step) as i32;
+                }
+            }
+            score.parts.push(Part {
+                id: format!("P{}", pi + 1),
+                name: part.name.unwrap_or_default(),
+                abbreviation: part.abbreviation,
+                midi_program: None,
+                midi_channel: None,
+                measures,
+            });
+        }
+        Ok(score)
+    }
+}
+
+/// The written value of an event and its length in ticks.
+struct NoteValue {
+    note_type: &'static str,
+    dots: u32,
+    ticks: i64,
+    /// Enclosing tuplets: (num, numbase)
+    ratio: (i64, i64),
+}
+
+impl NoteValue {
+    fn time_modification(&self) -> Option<TimeModification> {
+        let (num, numbase) = self.ratio;
+        (num != numbase).then_some(TimeModification {
+            actual_notes: num as i32,
+            normal_notes: numbase as i32,
+            normal_type: None,
+        })
+    }
+}
+
+// ─── Element readers ─────────────────────────────────────────────────
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.tag_name().name() == name)
+}
+
+/// The reading of an `<app>` or `<choice>` to follow: the lemma or
+/// correction when there is one, else the first.
+fn chosen<'a, 'input>(node: Node<'a, 'input>) -> Option<Node<'a, 'input>> {
+    let readings: Vec<Node> = node.children().filter(|n| n.is_element()).collect();
+    readings.iter()
+        .find(|n| matches!(n.tag_name().name(), "lem" | "corr" | "reg"))
+        .or(readings.first())
+        .copied()
+}
+
+/// Whitespace-normalized text content of an element.
+fn text_of(node: Node) -> String {
+    let text: String = node.descendants().filter(|n| n.is_text()).filter_map(|n| n.text()).collect();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn label(node: Node) -> Option<String> {
+    node.attribute("label").map(String::from)
+        .or_else(|| child(node, "label").map(text_of))
+        .filter(|l| !l.is_empty())
+}
+
+/// `@id` references are written `#id`.
+fn id_ref(reference: &str) -> String {
+    reference.trim_start_matches('#').to_string()
+}
+
+/// Key from `@key.sig` or a `<keySig>` child: `0`, `3s`, `2f`.
+fn read_key(node: Node) -> Option<Key> {
+    let key_sig = child(node, "keySig");
+    let sig = node.attribute("key.sig").or_else(|| key_sig.and_then(|k| k.attribute("sig")))?;
+    let count: i32 = sig.trim_end_matches(['s', 'f']).parse().ok()?;
+    let fifths = if sig.ends_with('f') { -count } else { count };
+    let mode = node.attribute("key.mode").or_else(|| key_sig.and_then(|k| k.attribute("mode"))).map(String::from);
+    Some(Key { fifths: fifths.clamp(-7, 7), mode })
+}
+
+/// Clef from `@clef.shape`/`@clef.line` or a `<clef>` element.
+fn read_clef(node: Node) -> Option<Clef> {
+    let (shape, line, dis, place) = match node.tag_name().name() {
+        "clef" => (node.attribute("shape"), node.attribute("line"), node.attribute("dis"), node.attribute("dis.place")),
+        _ => match child(node, "clef") {
+            Some(clef) => return read_clef(clef),
+            None => (node.attribute("clef.shape"), node.attribute("clef.line"), node.attribute("clef.dis"), node.attribute("clef.dis.place")),
+        },
+    };
+    let sign = shape.filter(|s| matches!(*s, "G" | "F" | "C"))?;
+    let line = line.and_then(|l| l.parse().ok()).unwrap_or(match sign { "F" => 4, "C" => 3, _ => 2 });
+    let octaves = match dis {
+        Some("8") => 1,
+        Some("15") => 2,
+        _ => 0,
+    };
+    let octave_change = match (octaves, place) {
+        (0, _) => None,
+        (n, Some("above")) => Some(n),
+        (n, _) => Some(-n),
+    };
+    Some(Clef { number: 1, sign: sign.to_string(), line, octave_change })
+}
+
+/// `<verse n><syl wordpos="i|m|t">` lyrics of a note or chord.
+fn read_lyrics(node: Node) -> Vec<Lyric> {
+    let mut lyrics = Vec::new();
+    for (i, verse) in node.children().filter(|n| n.tag_name().name() == "verse").enumerate() {
+        let syllables: Vec<Node> = verse.descendants().filter(|n| n.tag_name().name() == "syl").collect();
+        let text = syllables.iter().map(|s| text_of(*s)).collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            continue;
+        }
+        let syllabic = match syllables.first().and_then(|s| s.attribute("wordpos")) {
+            Some("i") => "begin",
+            Some("m") => "middle",
+            Some("t") => "end",
+            _ => "single",
+        };
+        lyrics.push(Lyric {
+            number: verse.attribute("n").and_then(|n| n.parse().ok()).unwrap_or(i as i32 + 1),
+            text,
+            syllabic: Some(syllabic.to_string()),
+        });
+    }
+    lyrics
+}
+
+fn accid_alter(accid: &str) -> Option<f64> {
+    Some(match accid {
+        "s" => 1.0,
+        "f" => -1.0,
+        "ss" | "x" => 2.0,
+        "ff" => -2.0,
+        "n" => 0.0,
+        "su" => 1.5,
+        "fd" => -1.5,
+        _ => return None,
+    })
+}
+
+fn accidental_name(alter: f64) -> String {
+    match alter as i32 {
+        2 => "double-sharp",
+        1 => "sharp",
+        -1 => "flat",
+        -2 => "flat-flat",
+        _ => "natural",
+    }
+    .to_string()
+}
+
+/// Alteration of a step in a key signature.
+fn key_alter(fifths: i32, step: &str) -> f64 {
+    const SHARP_ORDER: [&str; 7] = ["F", "C", "G", "D", "A", "E", "B"];
+    let pos = SHARP_ORDER.iter().position(|&s| s == step).unwrap_or(0) as i32;
+    if fifths > pos {
+        1.0
+    } else if fifths < 0 && -fifths >= 7 - pos {
+        -1.0
+    } else {
+        0.0
+    }
+}
+
+// ─── Helpers ─────────────────────────────────────────────────────────
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+/// Beam the notes of a `<beam>`: level 1 across the group, and a further
+/// level across each run of notes short enough to need it.
+fn add_beams(notes: &mut [Note]) {
+    let levels = |n: &Note| match n.note_type.as_deref() {
+        Some("eighth") => 1,
+        Some("16th") => 2,
+        Some("32nd") => 3,
+        Some("64th") => 4,
+        Some("128th") => 5,
+        _ => 0,
+    };
+    let group: Vec<usize> = (0..notes.len()).filter(|&i| !notes[i].chord && !notes[i].rest && !notes[i].grace).collect();
+    if group.len() < 2 {
+        return;
+    }
+    for (k, &n) in group.iter().enumerate() {
+        notes[n].beams.push(Beam { number: 1, beam_type: beam_position(k, group.len()).to_string() });
+    }
+    for level in 2..=5 {
+        let mut k = 0;
+        while k < group.len() {
+            let mut r = k;
+            while r < group.len() && levels(&notes[group[r]]) >= level {
+                r += 1;
+            }
+            if r - k >= 2 {
+                for (m, &n) in group[k..r].iter().enumerate() {
+                    notes[n].beams.push(Beam { number: level, beam_type: beam_position(m, r - k).to_string() });
+                }
+            }
+            k = r.max(k + 1);
+        }
+    }
+}
+
+fn beam_position(k: usize, len: usize) -> &'static str {
+    if k == 0 {
+        "begin"
+    } else if k + 1 == len {
+        "end"
+    } else {
+        "continue"
+    }
+}
+
+fn barline_mut<'a>(measure: &'a mut Measure, location: &str) -> &'a mut Barline {
+    if let Some(i) = measure.barlines.iter().position(|b| b.location == location) {
+        return &mut measure.barlines[i];
+    }
+    measure.barlines.push(Barline {
+        location: location.to_string(),
+        bar_style: None,
+        repeat: None,
+        ending: None,
+    });
+    measure.barlines.last_mut().unwrap()
+}
+
+fn tuplet_event(tuplet_type: &str) -> TupletEvent {
+    TupletEvent {
+        tuplet_type: tuplet_type.to_string(),
+        number: 1,
+        bracket: None,
+        show_number: None,
+        placement: None,
+    }
+}
+
+fn blank_measure(number: i32) -> Measure {
+    Measure {
+        number,
+        implicit: false,
+        width: None,
+        attributes: None,
+        notes: Vec::new(),
+        harmonies: Vec::new(),
+        barlines: Vec::new(),
+        directions: Vec::new(),
+        new_system: false,
+        new_page: false,
+    }
+}
+
+fn blank_attributes() -> Attributes {
+    Attributes {
+        divisions: None,
+        key: None,
+        time: None,
+        clefs: Vec::new(),
+        transpose: None,
+        staves: None,
+        staff_details: Vec::new(),
+    }
+}
+
+fn blank_note(voice: i32) -> Note {
+    Note {
+        pitch: None,
+        duration: 0,
+        voice: Some(voice),
+        note_type: None,
+        stem: None,
+        beams: Vec::new(),
+        rest: false,
+        measure_rest: false,
+        chord: false,
+        dot: false,
+        accidental: None,
+        tie_start: false,
+        tie_stop: false,
+        staff: None,
+        default_x: None,
+        default_y: None,
+        lyrics: Vec::new(),
+        grace: false,
+        grace_slash: false,
+        slurs: Vec::new(),
+        onset: 0,
+        time_modification: None,
+        tuplets: Vec::new(),
+        articulations: Vec::new(),
+        ornaments: Vec::new(),
+        ornament_upper_alter: None,
+        ornament_lower_alter: None,
+        fermata: None,
+        notehead: None,
+        string: None,
+        fret: None,
+        fingering: None,
+    }
+}
+
+fn blank_direction() -> Direction {
+    Direction {
+        placement: Some("above".to_string()),
+        sound_tempo: None,
+        metronome: None,
+        words: None,
+        segno: false,
+        coda: false,
+        rehearsal: None,
+        sound_dacapo: false,
+        sound_dalsegno: false,
+        sound_fine: false,
+        sound_tocoda: false,
+        words_font_style: None,
+        octave_shift_type: None,
+        octave_shift_size: 0,
+        onset: 0,
+        staff: None,
+        dynamics: None,
+        sound_dynamics: None,
+        wedge_type: None,
+        wedge_number: 0,
+    }
+}
@@ -1,14 +1,16 @@
 //! MIDI file generation from a parsed and unrolled score.
 //!
 //! Produces a Standard MIDI File (SMF) Type 1 as raw bytes.
-//! Track 0 is the tempo map; subsequent tracks are the melody (one per
-//! staff for multi-staff parts like piano — each on its own MIDI channel
-//! to prevent note-off conflicts on shared pitches).  Accompaniment
-//! tracks (piano, bass, strings, drums, metronome) follow.
+//! Track 0 is the tempo map; subsequent tracks are the score's parts (one
+//! per part and per staff, so multi-staff parts like piano get a track and
+//! MIDI channel per staff to prevent note-off conflicts on shared
+//! pitches).  Accompaniment tracks (piano, bass, strings, drums,
+//! metronome) follow.
 
 use crate::accompaniment;
+use crate::dynamics::DynamicsTimeline;
 use crate::model::Score;
-use crate::timemap::TimemapEntry;
+use crate::timemap::{self, TimemapEntry};
 use crate::unroller::UnrolledMeasure;
 
 // ═══════════════════════════════════════════════════════════════════════
@@ -16,19 +18,14 @@ use crate::unroller::UnrolledMeasure;
 // ═══════════════════════════════════════════════════════════════════════
 
 /// Energy level for accompaniment velocity scaling.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum Energy {
     Soft,
+    #[default]
     Medium,
     Strong,
 }
 
-impl Default for Energy {
-    fn default() -> Self {
-        Energy::Medium
-    }
-}
-
 /// Options controlling which MIDI tracks to generate.
 #[derive(Debug, Clone)]
 pub struct MidiOptions {
@@ -42,6 +39,57 @@ pub struct MidiOptions {
     pub energy: Energy,
     /// Transposition in semitones (applied to the Score before generation).
     pub transpose: i32,
+    /// Per-part settings, by part index.  Parts without an entry use
+    /// `PartOptions::default()`.
+    pub parts: Vec<PartOptions>,
+}
+
+/// Mixer settings for one part of the score.
+#[derive(Debug, Clone)]
+pub struct PartOptions {
+    /// Whether the part's tracks are written at all.
+    pub include: bool,
+    /// Keep the part's tracks but leave out their notes.
+    pub mute: bool,
+    /// Channel volume (controller 7), 0–127.
+    pub volume: u8,
+}
+
+impl Default for PartOptions {
+    fn default() -> Self {
+        Self {
+            include: true,
+            mute: false,
+            volume: 100,
+        }
+    }
+}
+
+/// Read the `"parts"` list of an options JSON string, e.g.
+/// `{"parts":[{"include":true},{"mute":true},{"volume":64}]}`.
+/// Missing keys keep their defaults; an absent or malformed list gives none.
+pub(crate) fn part_options_from_json(json_str: &str) -> Vec<PartOptions> {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(json_str) else {
+        return Vec::new();
+    };
+    let Some(parts) = value.get("parts").and_then(|p| p.as_array()) else {
+        return Vec::new();
+    };
+    parts.iter()
+        .map(|p| {
+            let mut po = PartOptions::default();
+            if let Some(b) = p.get("include").and_then(|v| v.as_bool()) {
+                po.include = b;
+            }
+            if let Some(b) = p.get("mute").and_then(|v| v.as_bool()) {
+                po.mute = b;
+            }
+            if let Some(v) = p.get("volume").and_then(|v| v.as_u64()) {
+                po.volume = v.min(127) as u8;
+            }
+            po
+        })
+        .collect()
 }
 
 impl Default for MidiOptions {
@@ -56,6 +104,7 @@ impl Default for MidiOptions {
             melody_channel: 0,
             energy: Energy::Medium,
             transpose: 0,
+            parts: Vec::new(),
         }
     }
 }
@@ -73,17 +122,19 @@ pub struct MidiEvent {
 pub const TICKS_PER_QUARTER: u16 = 480;
 
 /// Generate a complete Standard MIDI File (SMF Type 1).
+///
+/// `unrolled` and `timemap` come from the conductor part
+/// ([`timemap::CONDUCTOR_PART`]); every part of the score is played
+/// against them.
 pub fn generate_midi(
     score: &Score,
-    part_idx: usize,
     unrolled: &[UnrolledMeasure],
     timemap: &[TimemapEntry],
     options: &MidiOptions,
 ) -> Vec<u8> {
-    let part = match score.parts.get(part_idx) {
-        Some(p) => p,
-        None => return Vec::new(),
-    };
+    if score.parts.is_empty() {
+        return Vec::new();
+    }
 
     debug_assert_eq!(
         unrolled.len(),
@@ -98,56 +149,87 @@ pub fn generate_midi(
     // ── Track 0: Tempo map ──────────────────────────────────────────
     tracks.push(build_tempo_track(timemap));
 
-    // ── Track 1+ : Melody (one track per staff) ────────────────────
+    // ── Track 1+ : Parts (one track per part and staff) ────────────
     // For multi-staff parts (e.g. piano with treble + bass), each staff
     // gets its own MIDI channel to prevent note-off/note-on conflicts
     // when both staves play the same pitch at overlapping times.
     if options.include_melody {
-        let num_staves = detect_staves(part);
-        let program = part.midi_program.unwrap_or(0).max(0).min(127) as u8;
-
-        if num_staves <= 1 {
-            // Single-staff part: all notes on one channel/track.
-            let melody_events = extract_melody(part, unrolled, timemap, options.melody_channel, None);
-            let mut track_events = Vec::new();
-            track_events.push(MidiEvent {
-                tick: 0,
-                bytes: vec![0xC0 | options.melody_channel, program],
-            });
-            track_events.extend(melody_events);
-            tracks.push(encode_track(&track_events, "Melody"));
-        } else {
-            // Multi-staff part: one track per staff, each on its own channel.
-            // Channels 0, 7, 8, 11, 12… (avoiding 1-3 for accompaniment, 9 for drums).
-            let staff_channels: Vec<u8> = (0..num_staves as u8)
-                .map(|s| match s {
-                    0 => options.melody_channel,
-                    1 => 7,
-                    2 => 8,
-                    3 => 11,
-                    _ => (12 + s - 4).min(15),
-                })
+        let mut channels = ChannelAllocator::new(options.melody_channel);
+        let mut part_tracks: Vec<PartTrack> = Vec::new();
+
+        for (part_idx, part) in score.parts.iter().enumerate() {
+            let part_options = options.parts.get(part_idx).cloned().unwrap_or_default();
+            if !part_options.include {
+                continue;
+            }
+            // MusicXML numbers programs 1–128 and channels 1–16
+            let program = part.midi_program.map_or(0, |p| (p - 1).clamp(0, 127)) as u8;
+            let volume = part_options.volume.min(127);
+            let part_tmap = timemap::part_timemap(part, timemap);
+            let num_staves = detect_staves(part);
+            let name = if part.name.is_empty() { "Melody" } else { part.name.as_str() };
+
+            // A tab staff that doubles a notation staff isn't played twice
+            let has_notation = (1..=num_staves).any(|s| !part.is_tab_staff(s as i32));
+            let played: Vec<usize> = (1..=num_staves)
+                .filter(|&s| !has_notation || !part.is_tab_staff(s as i32))
                 .collect();
 
-            for staff_num in 1..=num_staves {
-                let ch = staff_channels[staff_num - 1];
-                let events = extract_melody(
-                    part, unrolled, timemap, ch, Some(staff_num as i32),
-                );
-                let mut track_events = Vec::new();
-                track_events.push(MidiEvent {
-                    tick: 0,
-                    bytes: vec![0xC0 | ch, program],
-                });
-                track_events.extend(events);
-                let name = if staff_num == 1 { "Treble" } else { "Bass" };
-                tracks.push(encode_track(&track_events, name));
+            for &staff_num in &played {
+                let preferred = if staff_num == 1 {
+                    part.midi_channel.map(|c| (c - 1).clamp(0, 15) as u8)
+                } else {
+                    None
+                };
+                let staff_filter = (num_staves > 1).then_some(staff_num as i32);
+                let track_name = if played.len() > 1 {
+                    format!("{} {}", name, staff_num)
+                } else {
+                    name.to_string()
+                };
+                let notes = |ch: u8| -> Vec<MidiEvent> {
+                    // Muted parts keep their tracks but play nothing
+                    if part_options.mute {
+                        return Vec::new();
+                    }
+                    let mut events = extract_melody(part, unrolled, &part_tmap, ch, staff_filter);
+                    if ch == PERCUSSION_CHANNEL {
+                        scale_velocities(&mut events, volume);
+                    }
+                    events
+                };
+
+                match channels.allocate(preferred) {
+                    Some(ch) => {
+                        let mut events = vec![MidiEvent { tick: 0, bytes: vec![0xC0 | ch, program] }];
+                        // The percussion channel's volume is the metronome's
+                        // and drums' too: its parts scale their velocities
+                        if ch != PERCUSSION_CHANNEL {
+                            events.push(MidiEvent { tick: 0, bytes: vec![0xB0 | ch, 7, volume] });
+                        }
+                        events.extend(notes(ch));
+                        part_tracks.push(PartTrack { name: track_name, channel: ch, program, events });
+                    }
+                    None => {
+                        // Out of channels: play along on the track with the
+                        // same instrument, else the latest one
+                        let Some(track) = part_tracks.iter().rposition(|t| t.program == program)
+                            .or(part_tracks.len().checked_sub(1))
+                            .map(|i| &mut part_tracks[i]) else { continue };
+                        track.events.extend(notes(track.channel));
+                        track.events.sort_by_key(|e| e.tick);
+                        track.name = format!("{} + {}", track.name, track_name);
+                    }
+                }
             }
         }
+        for track in &part_tracks {
+            tracks.push(encode_track(&track.events, &track.name));
+        }
     }
 
     // ── Accompaniment tracks ────────────────────────────────────────
-    let chords = accompaniment::analyze_chords(part, unrolled, timemap);
+    let chords = accompaniment::analyze_chords(score, unrolled, timemap);
 
     if options.include_metronome {
         let events = accompaniment::generate_metronome(timemap);
@@ -189,6 +271,63 @@ pub fn generate_midi(
     build_smf(&tracks)
 }
 
+// ═══════════════════════════════════════════════════════════════════════
+// Channel allocation
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Channels used by the accompaniment tracks (piano, bass, strings).
+const ACCOMPANIMENT_CHANNELS: [u8; 3] = [
+    accompaniment::PIANO_CHANNEL,
+    accompaniment::BASS_CHANNEL,
+    accompaniment::STRING_CHANNEL,
+];
+/// General MIDI percussion channel (drums and metronome).
+const PERCUSSION_CHANNEL: u8 = accompaniment::DRUM_CHANNEL;
+
+/// A part track before encoding.
+struct PartTrack {
+    name: String,
+    channel: u8,
+    program: u8,
+    events: Vec<MidiEvent>,
+}
+
+/// Hands out one MIDI channel per part track, never one of the
+/// accompaniment channels.
+struct ChannelAllocator {
+    /// Channel of the first track, if free
+    first: u8,
+    used: Vec<u8>,
+}
+
+impl ChannelAllocator {
+    fn new(first: u8) -> Self {
+        Self { first, used: Vec::new() }
+    }
+
+    /// The part's own channel if it's free, else the next free one; `None`
+    /// once every channel is taken.  The percussion channel is only given
+    /// to parts that ask for it (percussion instruments), and may be
+    /// given to several.
+    fn allocate(&mut self, preferred: Option<u8>) -> Option<u8> {
+        let is_free = |ch: u8, used: &[u8]| !ACCOMPANIMENT_CHANNELS.contains(&ch) && !used.contains(&ch);
+        let ch = preferred
+            .filter(|&c| c == PERCUSSION_CHANNEL || is_free(c, &self.used))
+            .or_else(|| Some(self.first).filter(|&c| c != PERCUSSION_CHANNEL && is_free(c, &self.used)))
+            .or_else(|| (0..16u8).find(|&c| c != PERCUSSION_CHANNEL && is_free(c, &self.used)))?;
+        self.used.push(ch);
+        Some(ch)
+    }
+}
+
+/// Scale note-on velocities by a channel volume (100 leaves them as
+/// they are), for parts that can't set the volume of their channel.
+fn scale_velocities(events: &mut [MidiEvent], volume: u8) {
+    for event in events.iter_mut().filter(|e| e.bytes[0] & 0xF0 == 0x90 && e.bytes[2] > 0) {
+        event.bytes[2] = (event.bytes[2] as u32 * volume as u32 / 100).clamp(1, 127) as u8;
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 // Melody extraction
 // ═══════════════════════════════════════════════════════════════════════
@@ -199,6 +338,8 @@ pub fn generate_midi(
 /// When `Some(n)`, only notes belonging to staff `n` are included — used for
 /// multi-staff parts where each staff gets its own MIDI channel to prevent
 /// note-off/note-on conflicts on identical pitches.
+///
+/// Note-on velocities follow the part's dynamics markings (see [`DynamicsTimeline`]).
 fn extract_melody(
     part: &crate::model::Part,
     unrolled: &[UnrolledMeasure],
@@ -207,111 +348,86 @@ fn extract_melody(
     staff_filter: Option<i32>,
 ) -> Vec<MidiEvent> {
     let mut events: Vec<MidiEvent> = Vec::new();
+    let dynamics = DynamicsTimeline::build(part, unrolled, timemap, staff_filter);
+    let key_fifths = key_fifths_by_measure(part);
 
     for (i, um) in unrolled.iter().enumerate() {
-        let measure = &part.measures[um.original_index];
+        // Parts shorter than the conductor part end early
+        let Some(measure) = part.measures.get(um.original_index) else { continue };
         let entry = &timemap[i];
-        let divisions = entry.divisions.max(1) as f64;
-        let quarter_notes_in_measure = entry.effective_quarters;
-
-        // Per-(staff, voice) position tracking for correct multi-voice timing.
-        // MusicXML lists notes in document order; after a <backup> element
-        // (which our parser ignores), a new voice's notes appear at the same
-        // beat positions.  Using (staff, voice) as the key handles the case
-        // where voice numbers overlap across staves (common in MuseScore exports).
-        use std::collections::HashMap;
-        type VoiceKey = (i32, i32); // (staff, voice)
-        let mut voice_positions: HashMap<VoiceKey, f64> = HashMap::new();
-        let mut voice_last_onset: HashMap<VoiceKey, f64> = HashMap::new();
+        // Exact tuplet-aware (onset, duration) in quarter notes
+        let note_times = measure.note_times_quarters(entry.divisions);
+        let ms_per_quarter = 60_000.0 / entry.tempo_bpm.max(1.0);
 
-        for note in &measure.notes {
-            if note.grace {
+        for (note, &(onset_q, dur_q)) in measure.notes.iter().zip(&note_times) {
+            // Rhythm slashes show the beat, not a pitch to play
+            if note.grace || note.rest || note.notehead.as_deref() == Some("slash") {
                 continue;
             }
 
-            let note_staff = note.staff.unwrap_or(1);
-            let vk: VoiceKey = (note_staff, note.voice.unwrap_or(1));
-            let pos_div = voice_positions.entry(vk).or_insert(0.0);
-
-            // Staff filter: always advance position tracking (so timing
-            // stays correct for notes we DO include), but only emit MIDI
-            // events for notes on the target staff.
-            let emit = staff_filter.map_or(true, |sf| note_staff == sf);
-
-            // Chord notes share the same onset as their principal note
-            if note.chord {
-                if emit && !note.rest {
-                    if let Some(ref pitch) = note.pitch {
-                        let midi_note = pitch.to_midi().max(0).min(127) as u8;
-                        let onset = voice_last_onset.get(&vk).copied().unwrap_or(0.0);
-                        let note_time_ms = entry.timestamp_ms
-                            + (onset / divisions / quarter_notes_in_measure)
-                                * entry.duration_ms;
-                        let note_dur_ms = (note.duration as f64 / divisions
-                            / quarter_notes_in_measure)
-                            * entry.duration_ms;
-                        let on_tick = ms_to_ticks(note_time_ms, timemap);
-                        let off_tick = ms_to_ticks(note_time_ms + note_dur_ms, timemap);
-                        // Only emit note-on for the FIRST note in a tie chain.
-                        // Middle notes (tie_stop && tie_start) must NOT re-trigger
-                        // — that would leak a synth voice with no matching note-off.
-                        if !note.tie_stop {
+            // Staff filter: only emit MIDI events for notes on the target staff.
+            if staff_filter.is_some_and(|sf| note.staff.unwrap_or(1) != sf) {
+                continue;
+            }
+
+            if let Some(ref pitch) = note.pitch {
+                let midi_note = pitch.to_midi().clamp(0, 127) as u8;
+                // time_at_quarters stretches notes under a fermata
+                let note_time_ms = entry.time_at_quarters(onset_q);
+                let note_end_ms = entry.time_at_quarters(onset_q + dur_q);
+                let velocity = dynamics
+                    .velocity_at(note_time_ms)
+                    .saturating_add(articulation_velocity_boost(note))
+                    .min(127);
+
+                // Ornaments expand into note sequences (untied notes only)
+                let tied = note.tie_start || note.tie_stop;
+                if !tied && !note.ornaments.is_empty() {
+                    let fifths = key_fifths[um.original_index];
+                    let segments = ornament_segments(
+                        note, pitch, fifths, note_time_ms, note_end_ms, ms_per_quarter,
+                    );
+                    if !segments.is_empty() {
+                        for (seg_start, seg_end, seg_note) in segments {
+                            let seg_note = seg_note.clamp(0, 127) as u8;
                             events.push(MidiEvent {
-                                tick: on_tick,
-                                bytes: vec![0x90 | channel, midi_note, 80],
+                                tick: ms_to_ticks(seg_start, timemap),
+                                bytes: vec![0x90 | channel, seg_note, velocity],
                             });
-                        }
-                        if !note.tie_start {
                             events.push(MidiEvent {
-                                tick: off_tick,
-                                bytes: vec![0x80 | channel, midi_note, 0],
+                                tick: ms_to_ticks(seg_end, timemap),
+                                bytes: vec![0x80 | channel, seg_note, 0],
                             });
                         }
+                        continue;
                     }
                 }
-                continue;
-            }
-
-            if note.rest {
-                *pos_div += note.duration as f64;
-                continue;
-            }
 
-            if let Some(ref pitch) = note.pitch {
-                let midi_note = pitch.to_midi().max(0).min(127) as u8;
-                voice_last_onset.insert(vk, *pos_div);
-
-                if emit {
-                    let note_time_ms = entry.timestamp_ms
-                        + (*pos_div / divisions / quarter_notes_in_measure)
-                            * entry.duration_ms;
-                    let note_dur_ms =
-                        (note.duration as f64 / divisions / quarter_notes_in_measure)
-                            * entry.duration_ms;
-
-                    let on_tick = ms_to_ticks(note_time_ms, timemap);
-                    let off_tick = ms_to_ticks(note_time_ms + note_dur_ms, timemap);
-
-                    // For tied notes: only emit note-on for the FIRST note in
-                    // a tie chain (!tie_stop), and note-off for the LAST
-                    // (!tie_start).  Middle notes (tie_stop && tie_start)
-                    // emit neither — the pitch sustains through.
-                    if !note.tie_stop {
-                        events.push(MidiEvent {
-                            tick: on_tick,
-                            bytes: vec![0x90 | channel, midi_note, 80],
-                        });
-                    }
-                    if !note.tie_start {
-                        events.push(MidiEvent {
-                            tick: off_tick,
-                            bytes: vec![0x80 | channel, midi_note, 0],
-                        });
-                    }
+                // Staccato & co. shorten the sounding length of untied notes
+                let length = if tied { 1.0 } else { articulation_length(note) };
+                let note_dur_ms = (note_end_ms - note_time_ms) * length;
+
+                let on_tick = ms_to_ticks(note_time_ms, timemap);
+                let off_tick = ms_to_ticks(note_time_ms + note_dur_ms, timemap);
+
+                // For tied notes: only emit note-on for the FIRST note in
+                // a tie chain (!tie_stop), and note-off for the LAST
+                // (!tie_start).  Middle notes (tie_stop && tie_start)
+                // emit neither — the pitch sustains through and must not
+                // leak a synth voice with no matching note-off.
+                if !note.tie_stop {
+                    events.push(MidiEvent {
+                        tick: on_tick,
+                        bytes: vec![0x90 | channel, midi_note, velocity],
+                    });
+                }
+                if !note.tie_start {
+                    events.push(MidiEvent {
+                        tick: off_tick,
+                        bytes: vec![0x80 | channel, midi_note, 0],
+                    });
                 }
             }
-
-            *pos_div += note.duration as f64;
         }
     }
 
@@ -319,6 +435,132 @@ fn extract_melody(
     events
 }
 
+// ═══════════════════════════════════════════════════════════════════════
+// Articulations & ornaments
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Fraction of the written value that sounds, from the note's articulations.
+fn articulation_length(note: &crate::model::Note) -> f64 {
+    let mut length: f64 = 1.0;
+    for art in &note.articulations {
+        let l = match art.as_str() {
+            "staccatissimo" => 0.25,
+            "spiccato" => 0.35,
+            "staccato" => 0.5,
+            "detached-legato" => 0.75,
+            _ => 1.0,
+        };
+        length = length.min(l);
+    }
+    length
+}
+
+/// Extra velocity from accent-type articulations.
+fn articulation_velocity_boost(note: &crate::model::Note) -> u8 {
+    note.articulations
+        .iter()
+        .map(|art| match art.as_str() {
+            "strong-accent" => 30,
+            "accent" => 20,
+            _ => 0,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Key signature (fifths) in force at each original measure.
+fn key_fifths_by_measure(part: &crate::model::Part) -> Vec<i32> {
+    let mut fifths = 0;
+    part.measures
+        .iter()
+        .map(|m| {
+            if let Some(k) = m.attributes.as_ref().and_then(|a| a.key.as_ref()) {
+                fifths = k.fifths;
+            }
+            fifths
+        })
+        .collect()
+}
+
+/// MIDI pitch of the diatonic neighbour above or below `pitch` in the key,
+/// or with alteration `alter` when an accidental mark gives one.
+fn diatonic_neighbor(pitch: &crate::model::Pitch, key_fifths: i32, up: bool, alter: Option<f64>) -> i32 {
+    const STEPS: [&str; 7] = ["C", "D", "E", "F", "G", "A", "B"];
+    const SHARP_ORDER: [&str; 7] = ["F", "C", "G", "D", "A", "E", "B"];
+    const FLAT_ORDER: [&str; 7] = ["B", "E", "A", "D", "G", "C", "F"];
+
+    let idx = STEPS.iter().position(|&s| s == pitch.step).unwrap_or(0) as i32;
+    let (new_idx, octave) = match (up, idx) {
+        (true, 6) => (0, pitch.octave + 1),
+        (false, 0) => (6, pitch.octave - 1),
+        (true, i) => (i + 1, pitch.octave),
+        (false, i) => (i - 1, pitch.octave),
+    };
+    let step = STEPS[new_idx as usize];
+    let alter = if let Some(alter) = alter {
+        alter
+    } else if key_fifths > 0 && SHARP_ORDER[..key_fifths.min(7) as usize].contains(&step) {
+        1.0
+    } else if key_fifths < 0 && FLAT_ORDER[..key_fifths.unsigned_abs().min(7) as usize].contains(&step) {
+        -1.0
+    } else {
+        0.0
+    };
+    crate::model::Pitch { step: step.to_string(), octave, alter: Some(alter) }.to_midi()
+}
+
+/// Expand an ornamented note into `(start_ms, end_ms, midi_note)` segments.
+/// Only the first ornament we play is used, so a note marked with both a
+/// trill and a mordent plays just the one written first.  Accidental
+/// marks alter the upper and lower auxiliary notes.  Returns an empty
+/// list when the note has no ornament we play.
+fn ornament_segments(
+    note: &crate::model::Note,
+    pitch: &crate::model::Pitch,
+    key_fifths: i32,
+    start_ms: f64,
+    end_ms: f64,
+    ms_per_quarter: f64,
+) -> Vec<(f64, f64, i32)> {
+    let main = pitch.to_midi();
+    let upper = diatonic_neighbor(pitch, key_fifths, true, note.ornament_upper_alter);
+    let lower = diatonic_neighbor(pitch, key_fifths, false, note.ornament_lower_alter);
+    let total = end_ms - start_ms;
+    // Ornament notes are 32nds, but never shorter than 50 ms
+    let fast = (ms_per_quarter / 8.0).max(50.0);
+
+    let ornament = note.ornaments.iter().map(String::as_str).find(|o| {
+        matches!(*o, "trill-mark" | "shake" | "mordent" | "inverted-mordent" | "turn" | "inverted-turn")
+    });
+    let pattern: Vec<(f64, i32)> = match ornament {
+        Some("trill-mark") | Some("shake") => {
+            // Alternate main/upper, ending on the main note
+            let count = ((total / fast).floor() as usize).max(2) | 1;
+            let len = total / count as f64;
+            (0..count).map(|k| (len, if k % 2 == 0 { main } else { upper })).collect()
+        }
+        Some("mordent") | Some("inverted-mordent") => {
+            let neighbor = if ornament == Some("mordent") { lower } else { upper };
+            let len = fast.min(total / 4.0);
+            vec![(len, main), (len, neighbor), (total - 2.0 * len, main)]
+        }
+        Some("turn") | Some("inverted-turn") => {
+            let (first, third) = if ornament == Some("turn") { (upper, lower) } else { (lower, upper) };
+            let len = total / 4.0;
+            vec![(len, first), (len, main), (len, third), (len, main)]
+        }
+        _ => return Vec::new(),
+    };
+
+    let mut segments = Vec::with_capacity(pattern.len());
+    let mut t = start_ms;
+    for (len, midi) in pattern {
+        segments.push((t, t + len, midi));
+        t += len;
+    }
+    segments
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 // SMF byte encoding
 // ═══════════════════════════════════════════════════════════════════════
@@ -424,6 +666,133 @@ fn write_vlq(out: &mut Vec<u8>, mut value: u32) {
     }
 }
 
+// ═══════════════════════════════════════════════════════════════════════
+// SMF byte decoding
+// ═══════════════════════════════════════════════════════════════════════
+
+/// A Standard MIDI File read back into memory.
+#[derive(Debug, Clone)]
+pub struct SmfData {
+    /// Ticks per quarter note from the header
+    pub ticks_per_quarter: u16,
+    /// Tracks in file order
+    pub tracks: Vec<SmfTrack>,
+}
+
+/// One track of an [`SmfData`].
+#[derive(Debug, Clone, Default)]
+pub struct SmfTrack {
+    /// Track name meta event, if any
+    pub name: Option<String>,
+    /// Events with absolute ticks.  Channel messages carry their full
+    /// status byte (running status is expanded); meta events are stored
+    /// as written (`FF type len data`), the same form `generate_midi`
+    /// uses.  Sysex events are dropped.
+    pub events: Vec<MidiEvent>,
+}
+
+/// Parse Standard MIDI File bytes (format 0 or 1).
+pub fn read_smf(bytes: &[u8]) -> Result<SmfData, String> {
+    if bytes.len() < 14 || &bytes[0..4] != b"MThd" {
+        return Err("Not a Standard MIDI File (missing MThd)".to_string());
+    }
+    let header_len = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize;
+    let division = u16::from_be_bytes([bytes[12], bytes[13]]);
+    if division & 0x8000 != 0 {
+        return Err("SMPTE time division is not supported".to_string());
+    }
+
+    let mut tracks = Vec::new();
+    let mut pos = 8 + header_len;
+    while pos + 8 <= bytes.len() {
+        let chunk_len = u32::from_be_bytes([bytes[pos + 4], bytes[pos + 5], bytes[pos + 6], bytes[pos + 7]]) as usize;
+        let body_end = (pos + 8 + chunk_len).min(bytes.len());
+        if &bytes[pos..pos + 4] == b"MTrk" {
+            tracks.push(read_track(&bytes[pos + 8..body_end])?);
+        }
+        pos = body_end;
+    }
+
+    Ok(SmfData { ticks_per_quarter: division, tracks })
+}
+
+/// Decode one MTrk chunk body.
+fn read_track(data: &[u8]) -> Result<SmfTrack, String> {
+    let truncated = || "Truncated MIDI track".to_string();
+    let mut track = SmfTrack::default();
+    let mut i = 0;
+    let mut tick: u32 = 0;
+    let mut status: u8 = 0;
+
+    while i < data.len() {
+        tick = tick.saturating_add(read_vlq(data, &mut i).ok_or_else(truncated)?);
+        let first = *data.get(i).ok_or_else(truncated)?;
+        if first & 0x80 != 0 {
+            status = first;
+            i += 1;
+        } else if status == 0 {
+            return Err("Running status without a previous status byte".to_string());
+        }
+
+        match status {
+            0xFF => {
+                let start = i - 1;
+                let meta_type = *data.get(i).ok_or_else(truncated)?;
+                i += 1;
+                let len = read_vlq(data, &mut i).ok_or_else(truncated)? as usize;
+                let end = i + len;
+                if end > data.len() {
+                    return Err(truncated());
+                }
+                if meta_type == 0x2F {
+                    break; // end of track
+                }
+                if meta_type == 0x03 && track.name.is_none() {
+                    track.name = Some(String::from_utf8_lossy(&data[i..end]).into_owned());
+                }
+                track.events.push(MidiEvent { tick, bytes: data[start..end].to_vec() });
+                i = end;
+                // Meta and sysex events cancel running status
+                status = 0;
+            }
+            0xF0 | 0xF7 => {
+                let len = read_vlq(data, &mut i).ok_or_else(truncated)? as usize;
+                i += len;
+                status = 0;
+            }
+            _ => {
+                let data_len = match status & 0xF0 {
+                    0xC0 | 0xD0 => 1,
+                    _ => 2,
+                };
+                if i + data_len > data.len() {
+                    return Err(truncated());
+                }
+                let mut msg = vec![status];
+                msg.extend_from_slice(&data[i..i + data_len]);
+                track.events.push(MidiEvent { tick, bytes: msg });
+                i += data_len;
+            }
+        }
+    }
+
+    Ok(track)
+}
+
+/// Read a variable-length quantity, advancing `pos`.
+fn read_vlq(data: &[u8], pos: &mut usize) -> Option<u32> {
+    let mut value: u32 = 0;
+    for _ in 0..4 {
+        let b = *data.get(*pos)?;
+        *pos += 1;
+        value = (value << 7) | (b & 0x7F) as u32;
+        if b & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
 /// Convert milliseconds to MIDI ticks, respecting tempo changes in the timemap.
 pub fn ms_to_ticks(target_ms: f64, timemap: &[TimemapEntry]) -> u32 {
     if timemap.is_empty() {
@@ -509,4 +878,25 @@ mod tests {
         // Should contain MTrk
         assert!(smf.windows(4).any(|w| w == b"MTrk"));
     }
+
+    #[test]
+    fn smf_round_trip() {
+        let events = vec![
+            MidiEvent { tick: 0, bytes: vec![0xC0, 40] },
+            MidiEvent { tick: 0, bytes: vec![0x90, 60, 100] },
+            MidiEvent { tick: 480, bytes: vec![0x80, 60, 0] },
+            MidiEvent { tick: 70000, bytes: vec![0x90, 62, 90] },
+        ];
+        let smf = build_smf(&[encode_track(&events, "Violin")]);
+        let data = read_smf(&smf).unwrap();
+        assert_eq!(data.ticks_per_quarter, TICKS_PER_QUARTER);
+        assert_eq!(data.tracks.len(), 1);
+        assert_eq!(data.tracks[0].name.as_deref(), Some("Violin"));
+        let channel: Vec<(u32, Vec<u8>)> = data.tracks[0].events.iter()
+            .filter(|e| e.bytes[0] != 0xFF)
+            .map(|e| (e.tick, e.bytes.clone()))
+            .collect();
+        let expected: Vec<(u32, Vec<u8>)> = events.into_iter().map(|e| (e.tick, e.bytes)).collect();
+        assert_eq!(channel, expected);
+    }
 }
@@ -0,0 +1,641 @@
+//! Standard MIDI File import — converts SMF Type 0/1 into the Score model.
+//!
+//! Notes are quantized to a grid and grouped into one part per track and
+//! channel (a Type 0 file is split by channel).  Program changes set each
+//! part's `midi_program`; time and key signature meta events set the
+//! measure attributes, and tempo events become tempo directions.  Without
+//! a key signature event the key is guessed from the pitches.
+//!
+//! Within a measure, notes that start and end together form a chord;
+//! overlapping notes go to further voices.  Notes are split at barlines
+//! and into writable durations, tied with `tie_start`/`tie_stop`, and
+//! spelled in the key with accidentals where they leave it.
+
+use std::collections::HashMap;
+
+use crate::midi::read_smf;
+use crate::model::*;
+
+/// General MIDI percussion channel (0-based).
+const PERCUSSION_CHANNEL: u8 = 9;
+/// MIDI key at or below which a part's average pitch gets a bass clef.
+const BASS_CLEF_MAX_AVERAGE: f64 = 57.0;
+
+/// Options for converting a MIDI file into a score.
+#[derive(Debug, Clone)]
+pub struct MidiImportOptions {
+    /// Quantization grid in subdivisions of a quarter note (4 = sixteenths,
+    /// 2 = eighths).  Rounded to a power of two between 1 and 16.
+    pub grid: u32,
+}
+
+impl Default for MidiImportOptions {
+    fn default() -> Self {
+        Self { grid: 4 }
+    }
+}
+
+/// Parse Standard MIDI File bytes into a Score with default options.
+pub fn parse_midi(data: &[u8]) -> Result<Score, String> {
+    parse_midi_with_options(data, &MidiImportOptions::default())
+}
+
+/// A note from the file, quantized to divisions.
+#[derive(Debug, Clone, Copy)]
+struct RawNote {
+    start: i32,
+    end: i32,
+    key: u8,
+}
+
+/// The notes of one track on one channel.
+struct NoteGroup {
+    track: usize,
+    channel: u8,
+    program: Option<u8>,
+    notes: Vec<RawNote>,
+}
+
+/// One measure of the output: its position and the attributes in effect.
+struct MeasureSpan {
+    start: i32,
+    len: i32,
+    time: (i32, i32),
+    key: Key,
+    time_changed: bool,
+    key_changed: bool,
+}
+
+/// Parse Standard MIDI File bytes into a Score.
+pub fn parse_midi_with_options(data: &[u8], options: &MidiImportOptions) -> Result<Score, String> {
+    let smf = read_smf(data)?;
+    let tpq = smf.ticks_per_quarter.max(1) as f64;
+
+    // ── Meta events (from any track) ──
+    let mut time_sigs: Vec<(u32, i32, i32)> = Vec::new();
+    let mut key_sigs: Vec<(u32, Key)> = Vec::new();
+    let mut tempos: Vec<(u32, f64)> = Vec::new();
+    for event in smf.tracks.iter().flat_map(|t| &t.events) {
+        let b = &event.bytes;
+        if b[0] != 0xFF || b.len() < 3 {
+            continue;
+        }
+        match b[1] {
+            // FF 51 03 tt tt tt — microseconds per quarter
+            0x51 if b.len() >= 6 => {
+                let uspq = (b[3] as u32) << 16 | (b[4] as u32) << 8 | b[5] as u32;
+                if uspq > 0 {
+                    tempos.push((event.tick, 60_000_000.0 / uspq as f64));
+                }
+            }
+            // FF 58 04 nn dd cc bb — numerator, log2(denominator)
+            0x58 if b.len() >= 5 && b[3] > 0 && b[4] <= 6 => {
+                time_sigs.push((event.tick, b[3] as i32, 1 << b[4]));
+            }
+            // FF 59 02 sf mi — sharps/flats, minor flag
+            0x59 if b.len() >= 5 => {
+                let fifths = (b[3] as i8 as i32).clamp(-7, 7);
+                let mode = if b[4] == 1 { "minor" } else { "major" };
+                key_sigs.push((event.tick, Key { fifths, mode: Some(mode.to_string()) }));
+            }
+            _ => {}
+        }
+    }
+    time_sigs.sort_by_key(|t| t.0);
+    key_sigs.sort_by_key(|k| k.0);
+    tempos.sort_by_key(|t| t.0);
+
+    // Divisions: the grid, fine enough for every time signature's beat
+    let grid = options.grid.clamp(1, 16).next_power_of_two().min(16) as i32;
+    let divisions = time_sigs.iter().map(|t| t.2 / 4).fold(grid, i32::max);
+    let to_div = |tick: u32| (tick as f64 * divisions as f64 / tpq).round() as i32;
+
+    // ── Notes, per track and channel ──
+    let mut groups: Vec<NoteGroup> = Vec::new();
+    for (track_idx, track) in smf.tracks.iter().enumerate() {
+        let mut programs: [Option<u8>; 16] = [None; 16];
+        let mut sounding: HashMap<(u8, u8), Vec<u32>> = HashMap::new();
+        let mut notes: Vec<(u8, u32, u32, u8)> = Vec::new();
+        let last_tick = track.events.last().map_or(0, |e| e.tick);
+
+        for event in &track.events {
+            let b = &event.bytes;
+            if b[0] == 0xFF || b.len() < 2 {
+                continue;
+            }
+            let channel = b[0] & 0x0F;
+            match b[0] & 0xF0 {
+                0xC0 => {
+                    programs[channel as usize].get_or_insert(b[1]);
+                }
+                0x90 if b.len() >= 3 && b[2] > 0 => {
+                    sounding.entry((channel, b[1])).or_default().push(event.tick);
+                }
+                0x80 | 0x90 if b.len() >= 3 => {
+                    let starts = sounding.entry((channel, b[1])).or_default();
+                    if !starts.is_empty() {
+                        notes.push((channel, starts.remove(0), event.tick, b[1]));
+                    }
+                }
+                _ => {}
+            }
+        }
+        // Notes never released end with the track
+        for ((channel, key), starts) in sounding {
+            for start in starts {
+                notes.push((channel, start, last_tick.max(start), key));
+            }
+        }
+
+        let mut channels: Vec<u8> = notes.iter().map(|n| n.0).collect();
+        channels.sort_unstable();
+        channels.dedup();
+        for channel in channels {
+            let mut group_notes: Vec<RawNote> = notes.iter()
+                .filter(|n| n.0 == channel)
+                .map(|&(_, start, end, key)| {
+                    let start = to_div(start);
+                    RawNote { start, end: to_div(end).max(start + 1), key }
+                })
+                .collect();
+            group_notes.sort_by_key(|n| (n.start, n.key));
+            // Quantizing can make repeated notes overlap; cut the earlier one
+            for i in 0..group_notes.len() {
+                let next = group_notes[i + 1..].iter()
+                    .find(|n| n.key == group_notes[i].key && n.start > group_notes[i].start)
+                    .map(|n| n.start);
+                if let Some(next) = next {
+                    group_notes[i].end = group_notes[i].end.min(next);
+                }
+            }
+            group_notes.dedup_by(|b, a| a.key == b.key && a.start == b.start);
+            groups.push(NoteGroup {
+                track: track_idx,
+                channel,
+                program: programs[channel as usize],
+                notes: group_notes,
+            });
+        }
+    }
+    if groups.is_empty() {
+        return Err("MIDI file contains no notes".to_string());
+    }
+
+    // ── Measures ──
+    let default_key = if key_sigs.is_empty() {
+        guess_key(groups.iter().filter(|g| g.channel != PERCUSSION_CHANNEL).flat_map(|g| &g.notes))
+    } else {
+        Key { fifths: 0, mode: Some("major".to_string()) }
+    };
+    let total_end = groups.iter().flat_map(|g| &g.notes).map(|n| n.end).max().unwrap_or(0);
+    let mut spans: Vec<MeasureSpan> = Vec::new();
+    let mut time = (4, 4);
+    let mut key = default_key;
+    let mut pos = 0;
+    while pos < total_end || spans.is_empty() {
+        // Changes take effect at the first barline at or after them
+        let new_time = time_sigs.iter().rev().find(|t| to_div(t.0) <= pos).map(|t| (t.1, t.2));
+        let time_changed = new_time.is_some_and(|t| t != time) || spans.is_empty();
+        time = new_time.unwrap_or(time);
+        let new_key = key_sigs.iter().rev().find(|k| to_div(k.0) <= pos).map(|k| k.1.clone());
+        let key_changed = new_key.as_ref().is_some_and(|k| k.fifths != key.fifths || k.mode != key.mode)
+            || spans.is_empty();
+        key = new_key.unwrap_or(key);
+
+        let len = (time.0 * 4 * divisions / time.1).max(1);
+        spans.push(MeasureSpan { start: pos, len, time, key: key.clone(), time_changed, key_changed });
+        pos += len;
+    }
+
+    // ── Score ──
+    let mut score = Score::new();
+    let single_track = smf.tracks.len() == 1;
+    if let Some(first) = smf.tracks.first() {
+        if single_track || !groups.iter().any(|g| g.track == 0) {
+            score.title = first.name.clone().filter(|n| !n.trim().is_empty());
+        }
+    }
+
+    for (idx, group) in groups.iter().enumerate() {
+        let channels_in_track = groups.iter().filter(|g| g.track == group.track).count();
+        let track_name = smf.tracks[group.track].name.as_deref()
+            .filter(|n| !n.trim().is_empty() && (channels_in_track == 1 || single_track && score.title.is_none()));
+        let name = match track_name {
+            Some(n) => n.trim().to_string(),
+            None if group.channel == PERCUSSION_CHANNEL => "Drums".to_string(),
+            None => gm_family_name(group.program.unwrap_or(0)).to_string(),
+        };
+        let mut part = Part {
+            id: format!("P{}", idx + 1),
+            name,
+            abbreviation: None,
+            midi_program: Some(group.program.unwrap_or(0) as i32 + 1),
+            midi_channel: Some(group.channel as i32 + 1),
+            measures: Vec::new(),
+        };
+
+        let average = group.notes.iter().map(|n| n.key as f64).sum::<f64>() / group.notes.len() as f64;
+        let clef = if group.channel != PERCUSSION_CHANNEL && average <= BASS_CLEF_MAX_AVERAGE {
+            Clef { number: 1, sign: "F".to_string(), line: 4, octave_change: None }
+        } else {
+            Clef { number: 1, sign: "G".to_string(), line: 2, octave_change: None }
+        };
+
+        for (midx, span) in spans.iter().enumerate() {
+            let mut measure = build_measure(midx, span, &group.notes, divisions);
+            if midx == 0 || span.time_changed || span.key_changed {
+                measure.attributes = Some(Attributes {
+                    divisions: (midx == 0).then_some(divisions),
+                    key: (midx == 0 || span.key_changed).then(|| span.key.clone()),
+                    time: (midx == 0 || span.time_changed)
+                        .then_some(TimeSignature { beats: span.time.0, beat_type: span.time.1 }),
+                    clefs: if midx == 0 { vec![clef.clone()] } else { Vec::new() },
+                    transpose: None,
+                    staves: None,
+                    staff_details: Vec::new(),
+                });
+            }
+            // Tempo marks go on the first part only
+            if idx == 0 {
+                let mut last_bpm = None;
+                for &(tick, bpm) in &tempos {
+                    let at = to_div(tick);
+                    let same = last_bpm.is_some_and(|b: f64| (b - bpm).abs() < 0.5);
+                    last_bpm = Some(bpm);
+                    if same || at < span.start || at >= span.start + span.len {
+                        continue;
+                    }
+                    measure.directions.push(tempo_direction(bpm, at - span.start));
+                }
+            }
+            part.measures.push(measure);
+        }
+        score.parts.push(part);
+    }
+
+    Ok(score)
+}
+
+/// Build one measure of a part from the notes sounding in `span`.
+fn build_measure(idx: usize, span: &MeasureSpan, notes: &[RawNote], divisions: i32) -> Measure {
+    let (start, end) = (span.start, span.start + span.len);
+
+    // Pieces of the notes inside this measure, grouped into chords that
+    // start and end together: (start, end, keys with tie flags)
+    type Chord = (i32, i32, Vec<(u8, bool, bool)>);
+    let mut chords: Vec<Chord> = Vec::new();
+    for n in notes.iter().filter(|n| n.start < end && n.end > start) {
+        let s = n.start.max(start) - start;
+        let e = n.end.min(end) - start;
+        let member = (n.key, n.end > end, n.start < start);
+        match chords.iter_mut().find(|c| c.0 == s && c.1 == e) {
+            Some(chord) => chord.2.push(member),
+            None => chords.push((s, e, vec![member])),
+        }
+    }
+    chords.sort_by_key(|c| (c.0, -c.1));
+
+    // Voices: each takes chords that don't overlap its previous one
+    let mut voices: Vec<Vec<Chord>> = Vec::new();
+    for chord in chords {
+        match voices.iter_mut().find(|v| v.last().is_some_and(|last| last.1 <= chord.0)) {
+            Some(voice) => voice.push(chord),
+            None => voices.push(vec![chord]),
+        }
+    }
+
+    let mut measure = Measure {
+        number: idx as i32 + 1,
+        implicit: false,
+        width: None,
+        attributes: None,
+        notes: Vec::new(),
+        harmonies: Vec::new(),
+        barlines: Vec::new(),
+        directions: Vec::new(),
+        new_system: false,
+        new_page: false,
+    };
+
+    if voices.is_empty() {
+        let mut rest = blank_note(span.len, 0, 1);
+        rest.rest = true;
+        rest.measure_rest = true;
+        measure.notes.push(rest);
+        return measure;
+    }
+
+    let multi_voice = voices.len() > 1;
+    let beat_len = if span.time.1 == 8 && span.time.0 % 3 == 0 {
+        divisions * 3 / 2
+    } else {
+        divisions * 4 / span.time.1
+    }
+    .max(1);
+
+    for (vidx, voice) in voices.iter().enumerate() {
+        let voice_num = vidx as i32 + 1;
+        let first_note = measure.notes.len();
+        let mut cursor = 0;
+        for (s, e, members) in voice {
+            // Only the first voice fills its gaps with rests
+            if vidx == 0 && *s > cursor {
+                push_rests(&mut measure.notes, cursor, *s, divisions, voice_num);
+            }
+            let mut members = members.clone();
+            members.sort_by_key(|m| m.0);
+            let pieces = split_duration(*e - *s, divisions);
+            let mut onset = *s;
+            for (pi, &(dur, note_type, dot)) in pieces.iter().enumerate() {
+                for (mi, &(key, tie_out, tie_in)) in members.iter().enumerate() {
+                    let mut note = blank_note(dur, onset, voice_num);
+                    note.pitch = Some(spell(key, span.key.fifths));
+                    note.note_type = Some(note_type.to_string());
+                    note.dot = dot;
+                    note.chord = mi > 0;
+                    note.tie_stop = if pi == 0 { tie_in } else { true };
+                    note.tie_start = if pi + 1 == pieces.len() { tie_out } else { true };
+                    if multi_voice {
+                        note.stem = Some(if vidx % 2 == 0 { "up" } else { "down" }.to_string());
+                    }
+                    measure.notes.push(note);
+                }
+                onset += dur;
+            }
+            cursor = *e;
+        }
+        if vidx == 0 && cursor < span.len {
+            push_rests(&mut measure.notes, cursor, span.len, divisions, voice_num);
+        }
+        add_beams(&mut measure.notes[first_note..], beat_len);
+    }
+
+    add_accidentals(&mut measure.notes, span.key.fifths);
+    measure
+}
+
+/// Rests filling `[from, to)`.
+fn push_rests(notes: &mut Vec<Note>, from: i32, to: i32, divisions: i32, voice: i32) {
+    let mut onset = from;
+    for (dur, note_type, dot) in split_duration(to - from, divisions) {
+        let mut rest = blank_note(dur, onset, voice);
+        rest.rest = true;
+        rest.note_type = Some(note_type.to_string());
+        rest.dot = dot;
+        notes.push(rest);
+        onset += dur;
+    }
+}
+
+/// Split a duration into writable note values, longest first:
+/// (divisions, note type, dotted).
+fn split_duration(mut dur: i32, divisions: i32) -> Vec<(i32, &'static str, bool)> {
+    const VALUES: [(f64, &str, bool); 12] = [
+        (4.0, "whole", false),
+        (3.0, "half", true),
+        (2.0, "half", false),
+        (1.5, "quarter", true),
+        (1.0, "quarter", false),
+        (0.75, "eighth", true),
+        (0.5, "eighth", false),
+        (0.375, "16th", true),
+        (0.25, "16th", false),
+        (0.1875, "32nd", true),
+        (0.125, "32nd", false),
+        (0.0625, "64th", false),
+    ];
+    let mut out = Vec::new();
+    while dur > 0 {
+        let value = VALUES.iter()
+            .map(|&(q, t, dot)| (q * divisions as f64, t, dot))
+            .find(|&(d, _, _)| d.fract() == 0.0 && d >= 1.0 && d as i32 <= dur);
+        let Some((d, note_type, dot)) = value else { break };
+        out.push((d as i32, note_type, dot));
+        dur -= d as i32;
+    }
+    out
+}
+
+/// Beam eighths and shorter within each beat: level 1 across the group,
+/// level 2 across runs of sixteenths and shorter.
+fn add_beams(notes: &mut [Note], beat_len: i32) {
+    let beamable = |n: &Note| {
+        !n.rest && !n.chord && matches!(n.note_type.as_deref(), Some("eighth" | "16th" | "32nd" | "64th"))
+    };
+    let principals: Vec<usize> = (0..notes.len()).filter(|&i| !notes[i].chord).collect();
+
+    let mut i = 0;
+    while i < principals.len() {
+        let beat = notes[principals[i]].onset / beat_len;
+        let mut j = i;
+        while j < principals.len()
+            && beamable(&notes[principals[j]])
+            && notes[principals[j]].onset / beat_len == beat
+        {
+            j += 1;
+        }
+        if j - i >= 2 {
+            let group = &principals[i..j];
+            for (k, &n) in group.iter().enumerate() {
+                let beam_type = beam_position(k, group.len());
+                notes[n].beams.push(Beam { number: 1, beam_type: beam_type.to_string() });
+            }
+            // Level 2 runs
+            let mut k = 0;
+            while k < group.len() {
+                let short = |n: &Note| n.note_type.as_deref() != Some("eighth");
+                let mut r = k;
+                while r < group.len() && short(&notes[group[r]]) {
+                    r += 1;
+                }
+                if r - k >= 2 {
+                    for (m, &n) in group[k..r].iter().enumerate() {
+                        let beam_type = beam_position(m, r - k);
+                        notes[n].beams.push(Beam { number: 2, beam_type: beam_type.to_string() });
+                    }
+                }
+                k = r.max(k + 1);
+            }
+            i = j;
+        } else {
+            i = j.max(i + 1);
+        }
+    }
+}
+
+fn beam_position(k: usize, len: usize) -> &'static str {
+    if k == 0 {
+        "begin"
+    } else if k + 1 == len {
+        "end"
+    } else {
+        "continue"
+    }
+}
+
+/// Mark accidentals where a note's alteration differs from the key or
+/// from an earlier note on the same line in this measure.  Tied-over
+/// notes set the alteration without showing it.
+fn add_accidentals(notes: &mut [Note], fifths: i32) {
+    let mut order: Vec<usize> = (0..notes.len()).filter(|&i| notes[i].pitch.is_some()).collect();
+    order.sort_by_key(|&i| notes[i].onset);
+
+    let key_alters = key_alterations(fifths);
+    let mut current: HashMap<(String, i32), f64> = HashMap::new();
+    for i in order {
+        let pitch = notes[i].pitch.as_ref().unwrap();
+        let alter = pitch.alter.unwrap_or(0.0);
+        let line = (pitch.step.clone(), pitch.octave);
+        let step_idx = "CDEFGAB".find(pitch.step.as_str()).unwrap_or(0);
+        let expected = current.get(&line).copied().unwrap_or(key_alters[step_idx] as f64);
+        if alter != expected && !notes[i].tie_stop {
+            notes[i].accidental = Some(
+                match alter as i32 {
+                    -2 => "flat-flat",
+                    -1 => "flat",
+                    1 => "sharp",
+                    2 => "double-sharp",
+                    _ => "natural",
+                }
+                .to_string(),
+            );
+        }
+        current.insert(line, alter);
+    }
+}
+
+/// Alteration of each step C…B in a key signature.
+fn key_alterations(fifths: i32) -> [i32; 7] {
+    // Sharps are added in the order F C G D A E B; flats in reverse
+    const ORDER: [usize; 7] = [3, 0, 4, 1, 5, 2, 6];
+    let mut alters = [0; 7];
+    for k in 0..fifths.unsigned_abs().min(7) as usize {
+        if fifths > 0 {
+            alters[ORDER[k]] = 1;
+        } else {
+            alters[ORDER[6 - k]] = -1;
+        }
+    }
+    alters
+}
+
+/// Spell a MIDI key in a key signature: the key's own spelling when the
+/// pitch is in the scale, otherwise sharps in sharp keys and flats in flat keys.
+fn spell(key: u8, fifths: i32) -> Pitch {
+    const STEPS: [(&str, i32); 7] = [("C", 0), ("D", 2), ("E", 4), ("F", 5), ("G", 7), ("A", 9), ("B", 11)];
+    let pc = key as i32 % 12;
+    let alters = key_alterations(fifths);
+    let diatonic = STEPS.iter().zip(alters)
+        .find(|((_, base), alter)| (base + alter).rem_euclid(12) == pc)
+        .map(|(&(step, base), alter)| (step, base, alter));
+    let (step, base, alter) = diatonic.unwrap_or_else(|| {
+        let (names, alter): (&[&str; 12], i32) = if fifths < 0 {
+            (&["C", "D", "D", "E", "E", "F", "G", "G", "A", "A", "B", "B"], -1)
+        } else {
+            (&["C", "C", "D", "D", "E", "F", "F", "G", "G", "A", "A", "B"], 1)
+        };
+        let step = names[pc as usize];
+        let base = STEPS.iter().find(|s| s.0 == step).unwrap().1;
+        (step, base, if base == pc { 0 } else { alter })
+    });
+    Pitch {
+        step: step.to_string(),
+        octave: (key as i32 - base - alter).div_euclid(12) - 1,
+        alter: (alter != 0).then_some(alter as f64),
+    }
+}
+
+/// Guess a major key from the notes' pitch classes (weighted by length):
+/// the signature whose scale covers the most sound.
+fn guess_key<'a>(notes: impl Iterator<Item = &'a RawNote>) -> Key {
+    let mut weights = [0i64; 12];
+    for n in notes {
+        weights[n.key as usize % 12] += (n.end - n.start) as i64;
+    }
+    let best = (-6..=6)
+        .max_by_key(|&fifths: &i32| {
+            let tonic = (fifths * 7).rem_euclid(12);
+            let covered: i64 = [0, 2, 4, 5, 7, 9, 11].iter()
+                .map(|i| weights[((tonic + i) % 12) as usize])
+                .sum();
+            // Prefer fewer accidentals on a tie
+            (covered, -fifths.abs())
+        })
+        .unwrap_or(0);
+    Key { fifths: best, mode: Some("major".to_string()) }
+}
+
+fn blank_note(duration: i32, onset: i32, voice: i32) -> Note {
+    Note {
+        pitch: None,
+        duration,
+        voice: Some(voice),
+        note_type: None,
+        stem: None,
+        beams: Vec::new(),
+        rest: false,
+        measure_rest: false,
+        chord: false,
+        dot: false,
+        accidental: None,
+        tie_start: false,
+        tie_stop: false,
+        staff: None,
+        default_x: None,
+        default_y: None,
+        lyrics: Vec::new(),
+        grace: false,
+        grace_slash: false,
+        slurs: Vec::new(),
+        onset,
+        time_modification: None,
+        tuplets: Vec::new(),
+        articulations: Vec::new(),
+        ornaments: Vec::new(),
+        ornament_upper_alter: None,
+        ornament_lower_alter: None,
+        fermata: None,
+        notehead: None,
+        string: None,
+        fret: None,
+        fingering: None,
+    }
+}
+
+fn tempo_direction(bpm: f64, onset: i32) -> Direction {
+    Direction {
+        placement: Some("above".to_string()),
+        sound_tempo: Some((bpm * 100.0).round() / 100.0),
+        metronome: Some(MetronomeMark {
+            beat_unit: "quarter".to_string(),
+            per_minute: bpm.round() as i32,
+            dotted: false,
+        }),
+        words: None,
+        segno: false,
+        coda: false,
+        rehearsal: None,
+        sound_dacapo: false,
+        sound_dalsegno: false,
+        sound_fine: false,
+        sound_tocoda: false,
+        words_font_style: None,
+        octave_shift_type: None,
+        octave_shift_size: 0,
+        onset,
+        staff: None,
+        dynamics: None,
+        sound_dynamics: None,
+        wedge_type: None,
+        wedge_number: 0,
+    }
+}
+
+/// General MIDI instrument family of a program (0-based).
+fn gm_family_name(program: u8) -> &'static str {
+    const FAMILIES: [&str; 16] = [
+        "Piano", "Chromatic Percussion", "Organ", "Guitar", "Bass", "Strings", "Ensemble", "Brass",
+        "Reed", "Pipe", "Synth Lead", "Synth Pad", "Synth Effects", "Ethnic", "Percussive", "Sound Effects",
+    ];
+    FAMILIES[(program / 8).min(15) as usize]
+}
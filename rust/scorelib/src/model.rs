@@ -120,6 +120,9 @@ pub struct Attributes {
     pub transpose: Option<Transpose>,
     /// Number of staves in this part (e.g. 2 for piano grand staff)
     pub staves: Option<i32>,
+    /// Line count and string tuning of tablature staves
+    #[serde(default)]
+    pub staff_details: Vec<StaffDetails>,
 }
 
 /// Key signature.
@@ -141,7 +144,7 @@ pub struct TimeSignature {
 }
 
 /// Clef definition.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Clef {
     /// Staff number this clef belongs to (1-based; defaults to 1)
     pub number: i32,
@@ -153,6 +156,18 @@ pub struct Clef {
     pub octave_change: Option<i32>,
 }
 
+/// Staff layout from `<staff-details>`, used for tablature staves.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaffDetails {
+    /// Staff number these details belong to (1-based; defaults to 1)
+    pub number: i32,
+    /// Number of staff lines (e.g. 6 for guitar tab, 4 for bass tab)
+    pub lines: Option<i32>,
+    /// Open-string pitches from `<staff-tuning>`, bottom line (lowest
+    /// string) first
+    pub tuning: Vec<Pitch>,
+}
+
 /// Transposition information.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transpose {
@@ -204,6 +219,73 @@ pub struct Note {
     pub grace_slash: bool,
     /// Slur events on this note (start/stop)
     pub slurs: Vec<SlurEvent>,
+    /// Start position within the measure in divisions, resolved from
+    /// `<backup>`/`<forward>` (chord notes share the onset of their chord)
+    #[serde(default)]
+    pub onset: i32,
+    /// Tuplet ratio from `<time-modification>` (None for ordinary notes)
+    #[serde(default)]
+    pub time_modification: Option<TimeModification>,
+    /// Tuplet bracket events on this note (start/stop)
+    #[serde(default)]
+    pub tuplets: Vec<TupletEvent>,
+    /// Articulations from <notations>/<articulations>: "staccato", "accent",
+    /// "tenuto", "strong-accent" (marcato), "staccatissimo", …
+    #[serde(default)]
+    pub articulations: Vec<String>,
+    /// Ornaments from <notations>/<ornaments>: "trill-mark", "mordent",
+    /// "inverted-mordent", "turn", …
+    #[serde(default)]
+    pub ornaments: Vec<String>,
+    /// Alteration of the ornament's upper auxiliary note from an
+    /// <ornaments>/<accidental-mark> above it, e.g. -1.0 for a trill to
+    /// the flat upper neighbor
+    #[serde(default)]
+    pub ornament_upper_alter: Option<f64>,
+    /// Alteration of the lower auxiliary note, from an accidental mark below
+    #[serde(default)]
+    pub ornament_lower_alter: Option<f64>,
+    /// Fermata from <notations>/<fermata>: "upright" or "inverted"
+    #[serde(default)]
+    pub fermata: Option<String>,
+    /// Notehead shape from <notehead>: "slash", "x", "diamond", … (None = normal)
+    #[serde(default)]
+    pub notehead: Option<String>,
+    /// Tablature string from <technical>/<string> (1 = highest-pitched string)
+    #[serde(default)]
+    pub string: Option<i32>,
+    /// Tablature fret from <technical>/<fret> (0 = open string)
+    #[serde(default)]
+    pub fret: Option<i32>,
+    /// Finger number from <technical>/<fingering>, e.g. "1" or "2-3"
+    #[serde(default)]
+    pub fingering: Option<String>,
+}
+
+/// Tuplet ratio of a note: `actual_notes` in the time of `normal_notes`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeModification {
+    /// Number of notes actually played (e.g. 3 for a triplet)
+    pub actual_notes: i32,
+    /// Number of notes normally played in the same time (e.g. 2 for a triplet)
+    pub normal_notes: i32,
+    /// Note type of the normal notes, when it differs from the note's own type
+    pub normal_type: Option<String>,
+}
+
+/// A tuplet start or stop event on a note (MusicXML `<tuplet>`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TupletEvent {
+    /// "start" or "stop"
+    pub tuplet_type: String,
+    /// Tuplet ID for matching pairs (1, 2…) — nested tuplets use different numbers
+    pub number: i32,
+    /// Whether a bracket is drawn (None = decide from beaming)
+    pub bracket: Option<bool>,
+    /// Which number to show: "actual", "both", or "none"
+    pub show_number: Option<String>,
+    /// Placement hint from MusicXML: "above" or "below"
+    pub placement: Option<String>,
 }
 
 /// A slur start or stop event on a note.
@@ -257,6 +339,12 @@ pub struct Harmony {
     pub kind: String,
     /// Bass note (for slash chords)
     pub bass: Option<HarmonyRoot>,
+    /// Start position within the measure in divisions (including `<offset>`)
+    #[serde(default)]
+    pub onset: i32,
+    /// Fretboard diagram drawn above the symbol
+    #[serde(default)]
+    pub frame: Option<Frame>,
 }
 
 /// Root or bass note of a harmony.
@@ -268,6 +356,33 @@ pub struct HarmonyRoot {
     pub alter: Option<f64>,
 }
 
+/// A chord diagram (`<frame>`): where each string is fretted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    /// Number of strings
+    pub strings: i32,
+    /// Number of frets the grid shows
+    pub frets: i32,
+    /// Fret of the top row when the grid doesn't start at the nut
+    pub first_fret: Option<i32>,
+    /// Played strings; strings without a note are not played
+    pub notes: Vec<FrameNote>,
+}
+
+/// One played string of a chord diagram.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrameNote {
+    /// String number, 1 = highest-pitched string
+    pub string: i32,
+    /// Fret, 0 = open string
+    pub fret: i32,
+    /// Finger to use, e.g. "1"
+    pub fingering: Option<String>,
+    /// Barre over this fret: "start" on the lowest-pitched string it
+    /// covers, "stop" on the highest
+    pub barre: Option<String>,
+}
+
 /// A barline (may include repeat signs).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Barline {
@@ -329,6 +444,24 @@ pub struct Direction {
     /// Octave-shift size: 8 (1 octave), 15 (2 octaves), 22 (3 octaves)
     #[serde(default)]
     pub octave_shift_size: i32,
+    /// Start position within the measure in divisions (including `<offset>`)
+    #[serde(default)]
+    pub onset: i32,
+    /// Staff this direction belongs to (1-based; None = first staff)
+    #[serde(default)]
+    pub staff: Option<i32>,
+    /// Dynamics marking from <direction-type>/<dynamics>: "p", "mf", "sfz", …
+    #[serde(default)]
+    pub dynamics: Option<String>,
+    /// Playback loudness from <sound dynamics="...">, as a percentage of forte (90)
+    #[serde(default)]
+    pub sound_dynamics: Option<f64>,
+    /// Hairpin from <direction-type>/<wedge>: "crescendo", "diminuendo", "stop"
+    #[serde(default)]
+    pub wedge_type: Option<String>,
+    /// Wedge ID for matching start/stop pairs (1, 2…)
+    #[serde(default)]
+    pub wedge_number: i32,
 }
 
 /// A metronome marking (e.g., quarter = 120).
@@ -385,6 +518,122 @@ impl Score {
     }
 }
 
+impl Part {
+    /// Whether staff `staff` (1-based) is a tablature staff, i.e. its first
+    /// clef has the sign "TAB".
+    pub fn is_tab_staff(&self, staff: i32) -> bool {
+        self.measures.iter()
+            .filter_map(|m| m.attributes.as_ref())
+            .flat_map(|a| &a.clefs)
+            .find(|c| c.number == staff)
+            .is_some_and(|c| c.sign == "TAB")
+    }
+}
+
+impl Measure {
+    /// Length of the measure's content in divisions, i.e. the latest
+    /// `onset + duration` over all notes. Grace notes take no time.
+    /// Used for pickup measures whose length isn't given by the time signature.
+    pub fn content_divisions(&self) -> i32 {
+        self.notes
+            .iter()
+            .filter(|n| !n.grace)
+            .map(|n| n.onset + n.duration)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Exact `(onset, duration)` of every note in quarter notes.
+    ///
+    /// Usually this is just `onset / divisions` and `duration / divisions`, but
+    /// when the divisions can't express a tuplet (e.g. triplet eighths with
+    /// `divisions = 4`) exporters round the durations. Inside a run of tuplet
+    /// notes the exact duration is rebuilt from the note type and the
+    /// `<time-modification>` ratio, and each note starts where the previous
+    /// one ended. The first note after the run snaps back to its own onset.
+    ///
+    /// Tuplets that cross a barline are not supported as one run: each
+    /// measure is timed on its own from its first note's onset, so the
+    /// timing is only exact when each side of the barline adds up on its
+    /// own.
+    pub fn note_times_quarters(&self, divisions: i32) -> Vec<(f64, f64)> {
+        let div = divisions.max(1) as f64;
+        let mut times = Vec::with_capacity(self.notes.len());
+        // End of the previous non-chord note: (in divisions, exact quarters, was tuplet)
+        let mut prev_end: Option<(i32, f64, bool)> = None;
+        let mut last_onset = 0.0;
+
+        for note in &self.notes {
+            let dur = if note.grace {
+                0.0
+            } else {
+                note.exact_quarters().unwrap_or(note.duration as f64 / div)
+            };
+            if note.chord {
+                times.push((last_onset, dur));
+                continue;
+            }
+            let onset = match prev_end {
+                Some((end_div, end_exact, true)) if end_div == note.onset => end_exact,
+                _ => note.onset as f64 / div,
+            };
+            times.push((onset, dur));
+            last_onset = onset;
+            if !note.grace {
+                prev_end = Some((
+                    note.onset + note.duration,
+                    onset + dur,
+                    note.time_modification.is_some(),
+                ));
+            }
+        }
+
+        times
+    }
+
+    /// Length of the measure's content in quarter notes, using the exact
+    /// tuplet timing of [`Measure::note_times_quarters`].
+    pub fn content_quarters(&self, divisions: i32) -> f64 {
+        self.note_times_quarters(divisions)
+            .iter()
+            .map(|&(onset, dur)| onset + dur)
+            .fold(0.0, f64::max)
+    }
+}
+
+impl Note {
+    /// Exact duration in quarter notes for tuplet notes, derived from the note
+    /// type, dot and `<time-modification>`. None for ordinary notes or when the
+    /// note type is missing — callers then fall back to `duration / divisions`.
+    ///
+    /// The ratio scales the note's own value whatever `<normal-type>` says:
+    /// a quarter in an eighth-note triplet lasts two thirds of a quarter.
+    pub fn exact_quarters(&self) -> Option<f64> {
+        let tm = self.time_modification.as_ref()?;
+        if tm.actual_notes <= 0 || tm.normal_notes <= 0 {
+            return None;
+        }
+        let base = type_quarters(self.note_type.as_deref()?)?;
+        let dotted = if self.dot { base * 1.5 } else { base };
+        Some(dotted * tm.normal_notes as f64 / tm.actual_notes as f64)
+    }
+}
+
+/// Length in quarter notes of a MusicXML note `<type>`.
+fn type_quarters(note_type: &str) -> Option<f64> {
+    match note_type {
+        "breve" => Some(8.0),
+        "whole" => Some(4.0),
+        "half" => Some(2.0),
+        "quarter" => Some(1.0),
+        "eighth" => Some(0.5),
+        "16th" => Some(0.25),
+        "32nd" => Some(0.125),
+        "64th" => Some(0.0625),
+        _ => None,
+    }
+}
+
 impl Default for Score {
     fn default() -> Self {
         Self::new()
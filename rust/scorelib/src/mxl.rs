@@ -1,15 +1,20 @@
-//! MXL file handler — reads compressed MusicXML (.mxl) archives.
+//! MXL file handler — reads and writes compressed MusicXML (.mxl) archives.
 //!
 //! An .mxl file is a ZIP archive containing:
 //!   - META-INF/container.xml  — declares the root MusicXML file path
 //!   - <rootfile>.xml          — the actual MusicXML content (e.g., score.xml)
 //!   - (optional) other files  — images, sounds, etc.
 
-use std::io::{Cursor, Read};
-use zip::ZipArchive;
+use std::io::{Cursor, Read, Write};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
 
 use crate::model::Score;
 use crate::parser;
+use crate::writer;
+
+/// Name of the MusicXML file inside archives written by `write_mxl`.
+const MXL_ROOT_FILE: &str = "score.musicxml";
 
 /// Read and parse a .mxl file from raw bytes.
 pub fn parse_mxl(data: &[u8]) -> Result<Score, String> {
@@ -90,3 +95,33 @@ fn read_container_xml(archive: &mut ZipArchive<Cursor<&[u8]>>) -> Result<String,
         names
     ))
 }
+
+/// Serialize a score to .mxl bytes: `mimetype` (stored, first),
+/// `META-INF/container.xml` and the MusicXML root file (deflated).
+pub fn write_mxl(score: &Score) -> Result<Vec<u8>, String> {
+    let container = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <container>\n  <rootfiles>\n    \
+         <rootfile full-path=\"{MXL_ROOT_FILE}\" media-type=\"application/vnd.recordare.musicxml+xml\"/>\n  \
+         </rootfiles>\n</container>\n"
+    );
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let musicxml = writer::write_musicxml(score);
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let entries: [(&str, SimpleFileOptions, &[u8]); 3] = [
+        ("mimetype", stored, b"application/vnd.recordare.musicxml"),
+        ("META-INF/container.xml", deflated, container.as_bytes()),
+        (MXL_ROOT_FILE, deflated, musicxml.as_bytes()),
+    ];
+    for (name, options, data) in entries {
+        zip.start_file(name, options)
+            .map_err(|e| format!("Failed to add '{name}' to MXL archive: {e}"))?;
+        zip.write_all(data)
+            .map_err(|e| format!("Failed to write '{name}': {e}"))?;
+    }
+    let cursor = zip.finish().map_err(|e| format!("Failed to finish MXL archive: {e}"))?;
+    Ok(cursor.into_inner())
+}
@@ -66,10 +66,8 @@ fn parse_identification(node: &Node, score: &mut Score) {
                 match creator_type {
                     // Only use <creator type="composer"> as a fallback;
                     // <credit type="composer"> takes priority.
-                    "composer" => {
-                        if score.composer.is_none() {
-                            score.composer = text;
-                        }
+                    "composer" if score.composer.is_none() => {
+                        score.composer = text;
                     }
                     "arranger" => score.arranger = text,
                     _ => {}
@@ -193,12 +191,10 @@ fn parse_credit(node: &Node, score: &mut Score) {
     match credit_type.as_str() {
         // <credit> values are the primary source for title and composer;
         // <work-title> and <creator type="composer"> are fallbacks.
-        "title" => {
-            if !credit_text.is_empty() {
-                score.title = Some(credit_text);
-                if has_style {
-                    score.title_style = Some(style);
-                }
+        "title" if !credit_text.is_empty() => {
+            score.title = Some(credit_text);
+            if has_style {
+                score.title_style = Some(style);
             }
         }
         "subtitle" => {
@@ -207,12 +203,10 @@ fn parse_credit(node: &Node, score: &mut Score) {
                 score.subtitle_style = Some(style);
             }
         }
-        "composer" => {
-            if !credit_text.is_empty() {
-                score.composer = Some(credit_text);
-                if has_style {
-                    score.composer_style = Some(style);
-                }
+        "composer" if !credit_text.is_empty() => {
+            score.composer = Some(credit_text);
+            if has_style {
+                score.composer_style = Some(style);
             }
         }
         _ => {}
@@ -308,23 +302,53 @@ fn parse_measure(node: &Node) -> Measure {
         new_page: false,
     };
 
+    // Position within the measure in divisions. <backup> and <forward>
+    // move it; every note, harmony and direction records where it starts.
+    let mut cursor: i32 = 0;
+    let mut last_onset: i32 = 0;
+
     for child in node.children().filter(|n| n.is_element()) {
         match child.tag_name().name() {
             "attributes" => measure.attributes = Some(parse_attributes(&child)),
-            "note" => measure.notes.push(parse_note(&child)),
-            "harmony" => measure.harmonies.push(parse_harmony(&child)),
+            "note" => {
+                let mut note = parse_note(&child);
+                if note.chord {
+                    note.onset = last_onset;
+                } else {
+                    note.onset = cursor;
+                    last_onset = cursor;
+                    if !note.grace {
+                        cursor += note.duration;
+                    }
+                }
+                measure.notes.push(note);
+            }
+            "backup" => {
+                cursor = (cursor - child_duration(&child)).max(0);
+            }
+            "forward" => {
+                cursor += child_duration(&child);
+            }
+            "harmony" => {
+                let mut harmony = parse_harmony(&child);
+                harmony.onset = (cursor + child_offset(&child)).max(0);
+                measure.harmonies.push(harmony);
+            }
             "barline" => measure.barlines.push(parse_barline(&child)),
             "direction" => {
-                if let Some(dir) = parse_direction(&child) {
+                if let Some(mut dir) = parse_direction(&child) {
+                    dir.onset = (cursor + child_offset(&child)).max(0);
                     measure.directions.push(dir);
                 }
             }
             "sound" => {
                 // <sound> can appear directly in <measure> (not inside <direction>)
-                if let Some(tempo) = child.attribute("tempo").and_then(|t| t.parse::<f64>().ok()) {
+                let tempo = child.attribute("tempo").and_then(|t| t.parse::<f64>().ok());
+                let dynamics = child.attribute("dynamics").and_then(|d| d.parse::<f64>().ok());
+                if tempo.is_some() || dynamics.is_some() {
                     measure.directions.push(Direction {
                         placement: Some("above".to_string()),
-                        sound_tempo: Some(tempo),
+                        sound_tempo: tempo,
                         metronome: None,
                         words: None,
                         segno: false,
@@ -337,6 +361,12 @@ fn parse_measure(node: &Node) -> Measure {
                         words_font_style: None,
                         octave_shift_type: None,
                         octave_shift_size: 0,
+                        onset: cursor,
+                        staff: None,
+                        dynamics: None,
+                        sound_dynamics: dynamics,
+                        wedge_type: None,
+                        wedge_number: 0,
                     });
                 }
             }
@@ -361,6 +391,23 @@ fn parse_measure(node: &Node) -> Measure {
     measure
 }
 
+/// Read the `<duration>` child of a `<backup>` or `<forward>` element.
+fn child_duration(node: &Node) -> i32 {
+    node.children()
+        .find(|n| n.is_element() && n.tag_name().name() == "duration")
+        .and_then(|n| parse_i32(&n))
+        .unwrap_or(0)
+}
+
+/// Read the `<offset>` child of a `<harmony>` or `<direction>` element.
+/// Offsets are in divisions and may be fractional or negative.
+fn child_offset(node: &Node) -> i32 {
+    node.children()
+        .find(|n| n.is_element() && n.tag_name().name() == "offset")
+        .and_then(|n| parse_f64(&n))
+        .map_or(0, |v| v.round() as i32)
+}
+
 // ─── Attributes ──────────────────────────────────────────────────────
 
 fn parse_attributes(node: &Node) -> Attributes {
@@ -371,6 +418,7 @@ fn parse_attributes(node: &Node) -> Attributes {
         clefs: Vec::new(),
         transpose: None,
         staves: None,
+        staff_details: Vec::new(),
     };
 
     for child in node.children().filter(|n| n.is_element()) {
@@ -381,6 +429,7 @@ fn parse_attributes(node: &Node) -> Attributes {
             "staves" => attrs.staves = parse_i32(&child),
             "clef" => attrs.clefs.push(parse_clef(&child)),
             "transpose" => attrs.transpose = Some(parse_transpose(&child)),
+            "staff-details" => attrs.staff_details.push(parse_staff_details(&child)),
             _ => {}
         }
     }
@@ -442,6 +491,49 @@ fn parse_clef(node: &Node) -> Clef {
     clef
 }
 
+fn parse_staff_details(node: &Node) -> StaffDetails {
+    let number = node
+        .attribute("number")
+        .and_then(|n| n.parse::<i32>().ok())
+        .unwrap_or(1);
+    let mut lines = None;
+    let mut tuning: Vec<(i32, Pitch)> = Vec::new();
+    for child in node.children().filter(|n| n.is_element()) {
+        match child.tag_name().name() {
+            "staff-lines" => lines = parse_i32(&child),
+            "staff-tuning" => {
+                let line = child
+                    .attribute("line")
+                    .and_then(|n| n.parse::<i32>().ok())
+                    .unwrap_or(tuning.len() as i32 + 1);
+                let mut pitch = Pitch {
+                    step: "E".to_string(),
+                    octave: 2,
+                    alter: None,
+                };
+                for tc in child.children().filter(|n| n.is_element()) {
+                    match tc.tag_name().name() {
+                        "tuning-step" => {
+                            pitch.step = tc.text().unwrap_or("E").trim().to_string();
+                        }
+                        "tuning-octave" => pitch.octave = parse_i32(&tc).unwrap_or(2),
+                        "tuning-alter" => pitch.alter = parse_f64(&tc),
+                        _ => {}
+                    }
+                }
+                tuning.push((line, pitch));
+            }
+            _ => {}
+        }
+    }
+    tuning.sort_by_key(|&(line, _)| line);
+    StaffDetails {
+        number,
+        lines,
+        tuning: tuning.into_iter().map(|(_, pitch)| pitch).collect(),
+    }
+}
+
 fn parse_transpose(node: &Node) -> Transpose {
     let mut t = Transpose {
         diatonic: 0,
@@ -487,6 +579,18 @@ fn parse_note(node: &Node) -> Note {
         grace: false,
         grace_slash: false,
         slurs: Vec::new(),
+        onset: 0,
+        time_modification: None,
+        tuplets: Vec::new(),
+        articulations: Vec::new(),
+        ornaments: Vec::new(),
+        ornament_upper_alter: None,
+        ornament_lower_alter: None,
+        fermata: None,
+        notehead: None,
+        string: None,
+        fret: None,
+        fingering: None,
     };
 
     for child in node.children().filter(|n| n.is_element()) {
@@ -501,6 +605,9 @@ fn parse_note(node: &Node) -> Note {
             "stem" => {
                 note.stem = child.text().map(|t| t.trim().to_string());
             }
+            "notehead" => {
+                note.notehead = child.text().map(|t| t.trim().to_string());
+            }
             "beam" => {
                 let number = child
                     .attribute("number")
@@ -533,15 +640,97 @@ fn parse_note(node: &Node) -> Note {
                     _ => {}
                 }
             }
+            "time-modification" => {
+                let mut tm = TimeModification {
+                    actual_notes: 1,
+                    normal_notes: 1,
+                    normal_type: None,
+                };
+                for tc in child.children().filter(|n| n.is_element()) {
+                    match tc.tag_name().name() {
+                        "actual-notes" => tm.actual_notes = parse_i32(&tc).unwrap_or(1),
+                        "normal-notes" => tm.normal_notes = parse_i32(&tc).unwrap_or(1),
+                        "normal-type" => {
+                            tm.normal_type = tc.text().map(|t| t.trim().to_string());
+                        }
+                        _ => {}
+                    }
+                }
+                note.time_modification = Some(tm);
+            }
             "notations" => {
                 for nc in child.children().filter(|n| n.is_element()) {
-                    if nc.tag_name().name() == "slur" {
-                        let slur_type = nc.attribute("type").unwrap_or("").to_string();
-                        let number = nc.attribute("number")
-                            .and_then(|n| n.parse().ok())
-                            .unwrap_or(1);
-                        let placement = nc.attribute("placement").map(String::from);
-                        note.slurs.push(SlurEvent { slur_type, number, placement });
+                    match nc.tag_name().name() {
+                        "slur" => {
+                            let slur_type = nc.attribute("type").unwrap_or("").to_string();
+                            let number = nc.attribute("number")
+                                .and_then(|n| n.parse().ok())
+                                .unwrap_or(1);
+                            let placement = nc.attribute("placement").map(String::from);
+                            note.slurs.push(SlurEvent { slur_type, number, placement });
+                        }
+                        "tuplet" => {
+                            let tuplet_type = nc.attribute("type").unwrap_or("").to_string();
+                            let number = nc.attribute("number")
+                                .and_then(|n| n.parse().ok())
+                                .unwrap_or(1);
+                            let bracket = nc.attribute("bracket").map(|b| b == "yes");
+                            let show_number = nc.attribute("show-number").map(String::from);
+                            let placement = nc.attribute("placement").map(String::from);
+                            note.tuplets.push(TupletEvent {
+                                tuplet_type,
+                                number,
+                                bracket,
+                                show_number,
+                                placement,
+                            });
+                        }
+                        "articulations" => {
+                            for ac in nc.children().filter(|n| n.is_element()) {
+                                note.articulations.push(ac.tag_name().name().to_string());
+                            }
+                        }
+                        "ornaments" => {
+                            for oc in nc.children().filter(|n| n.is_element()) {
+                                match oc.tag_name().name() {
+                                    "accidental-mark" => {
+                                        // Marks without a placement go on the ornament's
+                                        // own side: below a mordent, above the rest
+                                        let alter = oc.text().and_then(|t| accidental_mark_alter(t.trim()));
+                                        let below = match oc.attribute("placement") {
+                                            Some(p) => p == "below",
+                                            None => note.ornaments.last().is_some_and(|o| o == "mordent"),
+                                        };
+                                        if below {
+                                            note.ornament_lower_alter = alter;
+                                        } else {
+                                            note.ornament_upper_alter = alter;
+                                        }
+                                    }
+                                    "wavy-line" => {}
+                                    name => note.ornaments.push(name.to_string()),
+                                }
+                            }
+                        }
+                        "fermata" => {
+                            let fermata_type = nc.attribute("type").unwrap_or("upright");
+                            note.fermata = Some(fermata_type.to_string());
+                        }
+                        "technical" => {
+                            for tc in nc.children().filter(|n| n.is_element()) {
+                                match tc.tag_name().name() {
+                                    "string" => note.string = parse_i32(&tc),
+                                    "fret" => note.fret = parse_i32(&tc),
+                                    "fingering" => {
+                                        note.fingering = tc.text()
+                                            .map(|t| t.trim().to_string())
+                                            .filter(|t| !t.is_empty());
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                        _ => {}
                     }
                 }
             }
@@ -602,6 +791,7 @@ fn parse_harmony(node: &Node) -> Harmony {
     };
     let mut kind = "major".to_string();
     let mut bass = None;
+    let mut frame = None;
 
     for child in node.children().filter(|n| n.is_element()) {
         match child.tag_name().name() {
@@ -635,11 +825,40 @@ fn parse_harmony(node: &Node) -> Harmony {
                 }
                 bass = Some(b);
             }
+            "frame" => frame = Some(parse_frame(&child)),
             _ => {}
         }
     }
 
-    Harmony { root, kind, bass }
+    Harmony { root, kind, bass, onset: 0, frame }
+}
+
+fn parse_frame(node: &Node) -> Frame {
+    let mut frame = Frame { strings: 6, frets: 4, first_fret: None, notes: Vec::new() };
+    for child in node.children().filter(|n| n.is_element()) {
+        match child.tag_name().name() {
+            "frame-strings" => frame.strings = parse_i32(&child).unwrap_or(6),
+            "frame-frets" => frame.frets = parse_i32(&child).unwrap_or(4),
+            "first-fret" => frame.first_fret = parse_i32(&child),
+            "frame-note" => {
+                let mut note = FrameNote { string: 0, fret: 0, fingering: None, barre: None };
+                for nc in child.children().filter(|n| n.is_element()) {
+                    match nc.tag_name().name() {
+                        "string" => note.string = parse_i32(&nc).unwrap_or(0),
+                        "fret" => note.fret = parse_i32(&nc).unwrap_or(0),
+                        "fingering" => note.fingering = nc.text().map(|t| t.trim().to_string()),
+                        "barre" => note.barre = nc.attribute("type").map(str::to_string),
+                        _ => {}
+                    }
+                }
+                if note.string > 0 {
+                    frame.notes.push(note);
+                }
+            }
+            _ => {}
+        }
+    }
+    frame
 }
 
 // ─── Barline ─────────────────────────────────────────────────────────
@@ -703,6 +922,11 @@ fn parse_direction(node: &Node) -> Option<Direction> {
     let mut sound_tocoda = false;
     let mut octave_shift_type: Option<String> = None;
     let mut octave_shift_size: i32 = 0;
+    let mut staff = None;
+    let mut dynamics = None;
+    let mut sound_dynamics = None;
+    let mut wedge_type: Option<String> = None;
+    let mut wedge_number: i32 = 0;
 
     for child in node.children().filter(|n| n.is_element()) {
         match child.tag_name().name() {
@@ -735,14 +959,34 @@ fn parse_direction(node: &Node) -> Option<Direction> {
                                 .and_then(|s| s.parse::<i32>().ok())
                                 .unwrap_or(8);
                         }
+                        "dynamics" => {
+                            // <dynamics><mf/></dynamics> or <other-dynamics>text</other-dynamics>
+                            dynamics = dt_child.children()
+                                .find(|n| n.is_element())
+                                .map(|d| match d.tag_name().name() {
+                                    "other-dynamics" => d.text().unwrap_or("").trim().to_string(),
+                                    name => name.to_string(),
+                                })
+                                .filter(|d| !d.is_empty());
+                        }
+                        "wedge" => {
+                            wedge_type = dt_child.attribute("type").map(String::from);
+                            wedge_number = dt_child.attribute("number")
+                                .and_then(|n| n.parse::<i32>().ok())
+                                .unwrap_or(1);
+                        }
                         _ => {}
                     }
                 }
             }
+            "staff" => staff = parse_i32(&child),
             "sound" => {
                 if let Some(tempo) = child.attribute("tempo").and_then(|t| t.parse::<f64>().ok()) {
                     sound_tempo = Some(tempo);
                 }
+                if let Some(dyn_pct) = child.attribute("dynamics").and_then(|d| d.parse::<f64>().ok()) {
+                    sound_dynamics = Some(dyn_pct);
+                }
                 if child.attribute("dacapo") == Some("yes") {
                     sound_dacapo = true;
                 }
@@ -771,7 +1015,10 @@ fn parse_direction(node: &Node) -> Option<Direction> {
         || sound_dalsegno
         || sound_fine
         || sound_tocoda
-        || octave_shift_type.is_some();
+        || octave_shift_type.is_some()
+        || dynamics.is_some()
+        || sound_dynamics.is_some()
+        || wedge_type.is_some();
 
     if has_content {
         Some(Direction {
@@ -789,6 +1036,12 @@ fn parse_direction(node: &Node) -> Option<Direction> {
             words_font_style,
             octave_shift_type,
             octave_shift_size,
+            onset: 0,
+            staff,
+            dynamics,
+            sound_dynamics,
+            wedge_type,
+            wedge_number,
         })
     } else {
         None
@@ -828,6 +1081,18 @@ fn parse_metronome(node: &Node) -> MetronomeMark {
 
 // ─── Helpers ─────────────────────────────────────────────────────────
 
+/// Alteration named by an <accidental-mark>: "flat" → -1, "sharp" → 1, …
+fn accidental_mark_alter(text: &str) -> Option<f64> {
+    match text {
+        "sharp" => Some(1.0),
+        "flat" => Some(-1.0),
+        "natural" => Some(0.0),
+        "double-sharp" | "sharp-sharp" => Some(2.0),
+        "flat-flat" => Some(-2.0),
+        _ => None,
+    }
+}
+
 fn parse_i32(node: &Node) -> Option<i32> {
     node.text()?.trim().parse().ok()
 }
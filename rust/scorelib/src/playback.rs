@@ -9,7 +9,11 @@
 use serde::Serialize;
 
 use crate::model::Score;
-use crate::renderer::compute_measure_positions;
+use crate::renderer::{
+    compute_horizontal_measure_positions, compute_jianpu_measure_positions, compute_measure_positions,
+    compute_measure_positions_with_options, compute_paged_measure_positions, note_id, MeasurePlacement,
+    PageOptions, RenderOptions,
+};
 use crate::timemap::{self, TimemapEntry};
 use crate::unroller;
 
@@ -40,6 +44,31 @@ pub struct MeasurePosition {
     /// where `time_fraction` is 0.0–1.0 within the measure's duration.
     /// Includes a right-edge anchor at time_fraction=1.0.
     pub note_positions: Vec<(f64, f64)>,
+    /// The measure's notes in every part, with the ids of their groups
+    /// in the SVG, for note-level highlighting and tap-to-seek.
+    pub notes: Vec<NoteTiming>,
+}
+
+/// A note's element in the SVG and when it sounds.
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteTiming {
+    /// `id` of the note's group in the SVG (see `renderer::note_id`)
+    pub id: String,
+    /// Index into Score.parts
+    pub part: usize,
+    /// Index into Measure.notes
+    pub note: usize,
+    /// Staff within the part (1-based)
+    pub staff: i32,
+    /// Voice within the part
+    pub voice: i32,
+    /// Onset as a fraction of the measure's duration, as in `note_positions`
+    pub onset: f64,
+    /// End as a fraction of the measure's duration
+    pub end: f64,
+    /// Onset in milliseconds the first time the measure is played; null
+    /// if it is never played
+    pub time_ms: Option<f64>,
 }
 
 /// Visual position and dimensions of a system (line of music).
@@ -49,6 +78,9 @@ pub struct SystemPosition {
     pub y: f64,
     /// Total height of the system (staves + lyrics + spacing)
     pub height: f64,
+    /// Page the system is printed on (0-based); always 0 for a
+    /// continuous render
+    pub page: usize,
 }
 
 /// Serializable version of TimemapEntry for JSON output.
@@ -85,66 +117,134 @@ impl From<&TimemapEntry> for TimemapEntryJson {
 /// Combined with the unrolled timemap, this gives the WebView everything
 /// it needs to position and animate the playback cursor.
 pub fn generate_playback_map(score: &Score, page_width: Option<f64>) -> PlaybackMap {
-    // Get measure and system positions from the renderer's layout
-    let (measure_positions, system_positions) = compute_measure_positions(score, page_width);
+    build_playback_map(score, compute_measure_positions(score, page_width))
+}
+
+/// Generate a playback map for `render_score_to_svg_with_options`, whose
+/// staff scale and hidden chord symbols and lyrics change the layout.
+pub fn generate_playback_map_with_options(
+    score: &Score,
+    page_width: Option<f64>,
+    options: &RenderOptions,
+) -> PlaybackMap {
+    build_playback_map(score, compute_measure_positions_with_options(score, page_width, options))
+}
+
+/// Generate a playback map for the jianpu rendering of a score, from
+/// `render_score_to_jianpu_svg`'s layout at the same page width.
+pub fn generate_jianpu_playback_map(score: &Score, page_width: Option<f64>) -> PlaybackMap {
+    build_playback_map(score, compute_jianpu_measure_positions(score, page_width))
+}
+
+/// Generate a playback map for `render_score_to_horizontal_svg`'s single
+/// line, so the cursor and the scroll position follow the same x.
+pub fn generate_horizontal_playback_map(score: &Score, options: &RenderOptions) -> PlaybackMap {
+    build_playback_map(score, compute_horizontal_measure_positions(score, options))
+}
+
+/// Generate a playback map for the pages of `render_score_to_svg_pages`.
+/// Positions are in the coordinates of each system's page, and every
+/// system carries the index of its page.
+pub fn generate_paged_playback_map(score: &Score, options: &PageOptions, render: &RenderOptions) -> PlaybackMap {
+    let (measures, systems, pages) = compute_paged_measure_positions(score, options, render);
+    let mut map = build_playback_map(score, (measures, systems));
+    for (system, page) in map.systems.iter_mut().zip(pages) {
+        system.page = page;
+    }
+    map
+}
+
+/// Combine measure and system positions from one of the renderers with
+/// the score's unrolled timemap.
+fn build_playback_map(
+    score: &Score,
+    (measure_positions, system_positions): (Vec<MeasurePlacement>, Vec<(f64, f64)>),
+) -> PlaybackMap {
 
     // Unroll and generate timemap
-    let part_idx = 0;
-    let unrolled = unroller::unroll(score, part_idx);
-    let tmap = timemap::generate_timemap(score, part_idx, &unrolled);
+    let unrolled = unroller::unroll(score, timemap::CONDUCTOR_PART);
+    let tmap = timemap::generate_timemap(score, timemap::CONDUCTOR_PART, &unrolled);
 
-    // Build a lookup: original_measure_index → effective_quarters (from first timemap hit)
-    let mut effective_quarters_by_idx: std::collections::HashMap<usize, f64> =
+    // Build a lookup: original_measure_index → timemap entry (first hit)
+    let mut entry_by_idx: std::collections::HashMap<usize, &TimemapEntry> =
         std::collections::HashMap::new();
     for entry in &tmap {
-        effective_quarters_by_idx
-            .entry(entry.original_index)
-            .or_insert(entry.effective_quarters);
+        entry_by_idx.entry(entry.original_index).or_insert(entry);
     }
 
+    // Running divisions of each part at each measure
+    let divisions: Vec<Vec<i32>> = score.parts.iter()
+        .map(|part| {
+            let mut current = 1;
+            part.measures.iter()
+                .map(|m| {
+                    if let Some(d) = m.attributes.as_ref().and_then(|a| a.divisions) {
+                        current = d;
+                    }
+                    current
+                })
+                .collect()
+        })
+        .collect();
+
     let measures = measure_positions
         .into_iter()
         .map(|(measure_idx, x, width, system_idx, beat_x_map)| {
-            // Convert beat_x_map to note_positions with time fractions
-            let eff_q = effective_quarters_by_idx
-                .get(&measure_idx)
-                .copied()
-                .unwrap_or(4.0); // fallback: assume 4/4
+            // Convert beat_x_map to note_positions with time fractions.
+            // Fractions are of the measure's wall-clock duration, so a
+            // fermata hold keeps the cursor on the held note.
+            let entry = entry_by_idx.get(&measure_idx).copied();
 
             let mut note_positions: Vec<(f64, f64)> = beat_x_map
                 .iter()
-                .filter_map(|&(beat_time, svg_x)| {
-                    if eff_q > 0.0 {
-                        let frac = (beat_time / eff_q).min(1.0).max(0.0);
-                        Some((frac, svg_x))
-                    } else {
-                        None
-                    }
-                })
+                .filter_map(|&(beat_time, svg_x)| measure_fraction(entry, beat_time).map(|frac| (frac, svg_x)))
                 .collect();
 
             // Add a right-edge anchor at fraction 1.0 (end of measure)
             let right_edge_x = x + width;
             let needs_anchor = note_positions
                 .last()
-                .map_or(true, |&(frac, _)| (frac - 1.0).abs() > 0.001);
+                .is_none_or(|&(frac, _)| (frac - 1.0).abs() > 0.001);
             if needs_anchor {
                 note_positions.push((1.0, right_edge_x));
             }
 
+            let mut notes = Vec::new();
+            for (part_idx, part) in score.parts.iter().enumerate() {
+                let Some(measure) = part.measures.get(measure_idx) else { continue };
+                let times = measure.note_times_quarters(divisions[part_idx][measure_idx]);
+                for (note_idx, (note, &(onset, duration))) in measure.notes.iter().zip(&times).enumerate() {
+                    if note.rest || note.pitch.is_none() {
+                        continue;
+                    }
+                    let onset_frac = measure_fraction(entry, onset).unwrap_or(0.0);
+                    notes.push(NoteTiming {
+                        id: note_id(part_idx, measure_idx, note_idx),
+                        part: part_idx,
+                        note: note_idx,
+                        staff: note.staff.unwrap_or(1),
+                        voice: note.voice.unwrap_or(1),
+                        onset: onset_frac,
+                        end: measure_fraction(entry, onset + duration).unwrap_or(onset_frac),
+                        time_ms: entry.map(|e| e.time_at_quarters(onset)),
+                    });
+                }
+            }
+
             MeasurePosition {
                 measure_idx,
                 x,
                 width,
                 system_idx,
                 note_positions,
+                notes,
             }
         })
         .collect();
 
     let systems = system_positions
         .into_iter()
-        .map(|(y, height)| SystemPosition { y, height })
+        .map(|(y, height)| SystemPosition { y, height, page: 0 })
         .collect();
 
     let timemap_json = tmap.iter().map(TimemapEntryJson::from).collect();
@@ -156,6 +256,19 @@ pub fn generate_playback_map(score: &Score, page_width: Option<f64>) -> Playback
     }
 }
 
+/// Position `quarters` into a measure as a fraction of its wall-clock
+/// duration, so a fermata hold keeps the cursor on the held note.
+/// Without a timemap entry the measure is taken to be 4/4.
+fn measure_fraction(entry: Option<&TimemapEntry>, quarters: f64) -> Option<f64> {
+    match entry {
+        Some(e) if e.duration_ms > 0.0 => {
+            Some(((e.time_at_quarters(quarters) - e.timestamp_ms) / e.duration_ms).clamp(0.0, 1.0))
+        }
+        Some(_) => None,
+        None => Some((quarters / 4.0).clamp(0.0, 1.0)),
+    }
+}
+
 /// Serialize a PlaybackMap to JSON.
 pub fn playback_map_to_json(map: &PlaybackMap) -> String {
     serde_json::to_string(map).unwrap_or_else(|_| "{}".to_string())
@@ -0,0 +1,219 @@
+//! Articulation, ornament and fermata rendering.
+//!
+//! Marks that belong to the notehead (staccato, tenuto, accent…) go on the
+//! side opposite the stem, centred in a staff space when they fall inside
+//! the staff.  Marcato, fermatas and ornaments always sit above the staff
+//! and the stem (an inverted fermata sits below).  Several marks on one
+//! note are stacked outward from the note.
+
+use crate::model::*;
+use super::constants::*;
+use super::svg_builder::SvgBuilder;
+use super::beat_map::pitch_to_staff_y;
+
+/// Distance from the notehead centre to the first notehead-side mark.
+const ARTIC_NOTE_GAP: f64 = 10.0;
+/// Vertical step between stacked marks.
+const ARTIC_STACK_STEP: f64 = 9.0;
+/// Clearance between the staff/stem top and the first above-staff mark.
+const ORNAMENT_GAP: f64 = 9.0;
+const ORNAMENT_FONT_SIZE: f64 = 14.0;
+
+/// Marks placed next to the notehead, opposite the stem.
+fn is_notehead_side(mark: &str) -> bool {
+    matches!(mark, "staccato" | "staccatissimo" | "spiccato" | "tenuto" | "accent" | "detached-legato")
+}
+
+/// Draw the articulations, ornaments and fermatas of every note on this staff.
+/// `stem_dirs[i]` is the stem direction actually used for note `i` (None
+/// when it has no stem or wasn't drawn).
+#[allow(clippy::too_many_arguments)]
+pub(super) fn render_articulations(
+    svg: &mut SvgBuilder,
+    measure: &Measure,
+    note_positions: &[f64],
+    stem_dirs: &[Option<bool>],
+    staff_y: f64,
+    clef: Option<&Clef>,
+    transpose_octave: i32,
+    staff_filter: Option<i32>,
+) {
+    let staff_bottom = staff_y + STAFF_HEIGHT;
+    let middle_line = staff_y + 20.0;
+
+    for (i, note) in measure.notes.iter().enumerate() {
+        if note.chord || note.grace {
+            continue;
+        }
+        if staff_filter.is_some_and(|sf| note.staff.unwrap_or(1) != sf) {
+            continue;
+        }
+
+        // Marks may be written on any member of a chord; gather them all
+        let members: Vec<&Note> = std::iter::once(note)
+            .chain(measure.notes[i + 1..].iter().take_while(|n| n.chord))
+            .collect();
+        let mut near: Vec<&str> = Vec::new();
+        let mut above: Vec<&str> = Vec::new();
+        let mut fermata: Option<&str> = None;
+        for m in &members {
+            for a in &m.articulations {
+                if near.contains(&a.as_str()) || above.contains(&a.as_str()) {
+                    continue;
+                }
+                if is_notehead_side(a) {
+                    near.push(a);
+                } else if a == "strong-accent" {
+                    above.push(a);
+                }
+            }
+            for o in &m.ornaments {
+                if !above.contains(&o.as_str()) {
+                    above.push(o);
+                }
+            }
+            if m.fermata.is_some() {
+                fermata = m.fermata.as_deref();
+            }
+        }
+        if near.is_empty() && above.is_empty() && fermata.is_none() {
+            continue;
+        }
+
+        let x = note_positions[i];
+        let ys: Vec<f64> = members.iter()
+            .filter_map(|m| m.pitch.as_ref())
+            .map(|p| staff_y + pitch_to_staff_y(p, clef, transpose_octave))
+            .collect();
+        let (min_y, max_y) = if ys.is_empty() {
+            (middle_line, middle_line)
+        } else {
+            (ys.iter().cloned().fold(f64::MAX, f64::min), ys.iter().cloned().fold(f64::MIN, f64::max))
+        };
+        let has_stem = !note.rest && note.note_type.as_deref() != Some("whole");
+        let stem_up = stem_dirs[i].unwrap_or((min_y + max_y) / 2.0 >= middle_line);
+
+        // ── Notehead-side marks ──
+        // Rests take their marks above; notes opposite the stem
+        let below = !note.rest && stem_up;
+        let mut y = if below { max_y + ARTIC_NOTE_GAP } else { min_y - ARTIC_NOTE_GAP };
+        for mark in &near {
+            y = snap_to_space(y, staff_y, below);
+            render_mark(svg, mark, x, y, below);
+            y += if below { ARTIC_STACK_STEP } else { -ARTIC_STACK_STEP };
+        }
+
+        // ── Above-staff marks (innermost first) ──
+        let mut top = min_y.min(staff_y);
+        if has_stem && stem_up {
+            top = top.min(min_y - STEM_LENGTH);
+        }
+        if !below {
+            top = top.min(y + ARTIC_STACK_STEP);
+        }
+        let mut y = top - ORNAMENT_GAP;
+        for mark in &above {
+            render_mark(svg, mark, x, y, false);
+            y -= ORNAMENT_GAP + 4.0;
+        }
+
+        if let Some(kind) = fermata {
+            if kind == "inverted" {
+                let mut bottom = max_y.max(staff_bottom);
+                if has_stem && !stem_up {
+                    bottom = bottom.max(max_y + STEM_LENGTH);
+                }
+                render_fermata(svg, x, bottom + ORNAMENT_GAP + 2.0, true);
+            } else {
+                render_fermata(svg, x, y - 2.0, false);
+            }
+        }
+    }
+}
+
+/// Move a mark that falls inside the staff to the middle of the nearest
+/// space on its side, so it never sits on a staff line.
+fn snap_to_space(y: f64, staff_y: f64, below: bool) -> f64 {
+    if y < staff_y - 2.0 || y > staff_y + STAFF_HEIGHT + 2.0 {
+        return y;
+    }
+    let rel = (y - staff_y - STAFF_LINE_SPACING / 2.0) / STAFF_LINE_SPACING;
+    let space = if below { rel.ceil() } else { rel.floor() };
+    staff_y + STAFF_LINE_SPACING / 2.0 + space * STAFF_LINE_SPACING
+}
+
+/// Draw one articulation or ornament centred at (x, y).
+fn render_mark(svg: &mut SvgBuilder, mark: &str, x: f64, y: f64, below: bool) {
+    // Direction pointing away from the note
+    let out = if below { 1.0 } else { -1.0 };
+    match mark {
+        "staccato" => svg.circle(x, y, 1.8, svg.palette.note),
+        "staccatissimo" | "spiccato" => {
+            let d = format!(
+                "M {:.1} {:.1} L {:.1} {:.1} L {:.1} {:.1} Z",
+                x, y - 3.5 * out, x - 2.0, y + 3.5 * out, x + 2.0, y + 3.5 * out,
+            );
+            svg.path(&d, svg.palette.note, "none", 0.0);
+        }
+        "tenuto" => svg.line(x - 5.5, y, x + 5.5, y, svg.palette.note, 1.5),
+        "detached-legato" => {
+            svg.line(x - 5.5, y - 2.0 * out, x + 5.5, y - 2.0 * out, svg.palette.note, 1.5);
+            svg.circle(x, y + 2.0 * out, 1.8, svg.palette.note);
+        }
+        "accent" => {
+            let d = format!(
+                "M {:.1} {:.1} L {:.1} {:.1} L {:.1} {:.1}",
+                x - 5.5, y - 3.5, x + 5.5, y, x - 5.5, y + 3.5,
+            );
+            svg.path(&d, "none", svg.palette.note, 1.3);
+        }
+        "strong-accent" => {
+            let d = format!(
+                "M {:.1} {:.1} L {:.1} {:.1} L {:.1} {:.1}",
+                x - 4.5, y + 4.0, x, y - 4.0, x + 4.5, y + 4.0,
+            );
+            svg.path(&d, "none", svg.palette.note, 1.8);
+        }
+        "trill-mark" => {
+            svg.styled_text(
+                x, y + ORNAMENT_FONT_SIZE * 0.35, "tr", ORNAMENT_FONT_SIZE, "bold",
+                svg.palette.note, "middle", Some("Times New Roman, Times, serif"), Some("italic"),
+            );
+        }
+        "mordent" | "inverted-mordent" | "shake" => {
+            let d = format!(
+                "M {:.1} {:.1} L {:.1} {:.1} L {:.1} {:.1} L {:.1} {:.1} L {:.1} {:.1}",
+                x - 8.0, y + 2.0, x - 4.0, y - 3.0, x, y + 3.0, x + 4.0, y - 3.0, x + 8.0, y + 2.0,
+            );
+            svg.path(&d, "none", svg.palette.note, 1.5);
+            if mark == "mordent" {
+                svg.line(x, y - 6.0, x, y + 6.0, svg.palette.note, 1.0);
+            }
+        }
+        "turn" | "inverted-turn" => {
+            let d = format!(
+                "M {:.1} {:.1} C {:.1} {:.1} {:.1} {:.1} {:.1} {:.1} \
+                 C {:.1} {:.1} {:.1} {:.1} {:.1} {:.1}",
+                x - 8.0, y + 1.0,
+                x - 8.0, y - 6.0, x - 2.0, y - 4.0, x, y,
+                x + 2.0, y + 4.0, x + 8.0, y + 6.0, x + 8.0, y - 1.0,
+            );
+            svg.path(&d, "none", svg.palette.note, 1.6);
+            if mark == "inverted-turn" {
+                svg.line(x, y - 6.0, x, y + 6.0, svg.palette.note, 1.0);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Fermata arc with its dot; `y` is the baseline (flat side) of the arc.
+fn render_fermata(svg: &mut SvgBuilder, x: f64, y: f64, inverted: bool) {
+    let (dir, dot_y) = if inverted { (1.0, y + 3.5) } else { (-1.0, y - 3.5) };
+    let d = format!(
+        "M {:.1} {:.1} C {:.1} {:.1} {:.1} {:.1} {:.1} {:.1}",
+        x - 9.0, y, x - 8.0, y + 12.0 * dir, x + 8.0, y + 12.0 * dir, x + 9.0, y,
+    );
+    svg.path(&d, "none", svg.palette.note, 1.8);
+    svg.circle(x, dot_y, 1.8, svg.palette.note);
+}
@@ -7,39 +7,14 @@ use super::lyrics::{LyricEvent, lyric_pair_min_spacing};
 /// Width allocated per grace note (px) — roughly 66% of a normal notehead.
 pub(super) const GRACE_NOTE_WIDTH: f64 = 8.0;
 
-/// Compute the beat-time offset for each note in a measure,
-/// using per-voice time tracking to handle MusicXML backup semantics.
-pub(super) fn compute_note_beat_times(notes: &[Note], divisions: i32) -> Vec<f64> {
-    use std::collections::HashMap;
-    // Use (staff, voice) as the key so that overlapping voice numbers
-    // across staves (common in MuseScore exports) are tracked independently.
-    type VoiceKey = (i32, i32);
-    let mut voice_times: HashMap<VoiceKey, f64> = HashMap::new();
-    // Track the beat time of the last non-chord note per voice,
-    // so chord notes can share the same beat position.
-    let mut voice_last_beat: HashMap<VoiceKey, f64> = HashMap::new();
-    let mut beat_times = Vec::with_capacity(notes.len());
-
-    for note in notes {
-        let vk: VoiceKey = (note.staff.unwrap_or(1), note.voice.unwrap_or(1));
-        let current = voice_times.entry(vk).or_insert(0.0);
-
-        if note.grace {
-            beat_times.push(*current);
-        } else if note.chord {
-            // Chord notes share the same beat as their principal note
-            let last = voice_last_beat.get(&vk).copied().unwrap_or(0.0);
-            beat_times.push(last);
-        } else {
-            let beat = *current;
-            voice_last_beat.insert(vk, beat);
-            beat_times.push(beat);
-            let dur = note.duration as f64 / divisions.max(1) as f64;
-            *current += dur;
-        }
-    }
-
-    beat_times
+/// Compute the beat-time offset (in quarter notes) for each note in a measure
+/// from the onsets the parser resolved via `<backup>`/`<forward>`.
+pub(super) fn compute_note_beat_times(measure: &Measure, divisions: i32) -> Vec<f64> {
+    measure
+        .note_times_quarters(divisions)
+        .into_iter()
+        .map(|(onset, _)| onset)
+        .collect()
 }
 
 /// Minimum pixel gap between consecutive note positions.
@@ -138,11 +113,12 @@ pub(super) fn lookup_beat_x(beat_x_map: &[(f64, f64)], beat_time: f64) -> f64 {
 /// Build a Vec<f64> of x positions for each note in a measure, using the beat map.
 /// Grace notes are offset to the left of their principal note.
 pub(super) fn note_x_positions_from_beat_map(
-    notes: &[Note],
+    measure: &Measure,
     divisions: i32,
     beat_x_map: &[(f64, f64)],
 ) -> Vec<f64> {
-    let beat_times = compute_note_beat_times(notes, divisions);
+    let notes = &measure.notes;
+    let beat_times = compute_note_beat_times(measure, divisions);
 
     let mut positions: Vec<f64> = beat_times
         .iter()
@@ -191,7 +167,7 @@ pub(super) fn pitch_to_staff_y(pitch: &Pitch, clef: Option<&Clef>, transpose_oct
         Some("C") => {
             let line = clef.map_or(3, |c| c.line);
             let y = (5 - line) as f64 * STAFF_LINE_SPACING;
-            (4 * 7 + 0, y) // C4
+            (4 * 7, y) // C4
         }
         _ => {
             let line = clef.map_or(2, |c| c.line);
@@ -205,8 +181,5 @@ pub(super) fn pitch_to_staff_y(pitch: &Pitch, clef: Option<&Clef>, transpose_oct
 }
 
 pub(super) fn is_filled_note(note_type: Option<&str>) -> bool {
-    match note_type {
-        Some("whole") | Some("half") => false,
-        _ => true,
-    }
+    !matches!(note_type, Some("whole") | Some("half"))
 }
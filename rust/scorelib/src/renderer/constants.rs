@@ -24,6 +24,7 @@ pub(super) const KEY_SIG_SHARP_SPACE: f64 = 10.0;
 pub(super) const KEY_SIG_FLAT_SPACE: f64 = 8.0;
 pub(super) const KEY_SIG_NATURAL_SPACE: f64 = 8.0;
 pub(super) const TIME_SIG_SPACE: f64 = 24.0;
+pub(super) const CLEF_CHANGE_SPACE: f64 = 28.0; // clef change at the start of a measure
 
 // ── Note dimensions ─────────────────────────────────────────────────
 pub(super) const NOTEHEAD_RX: f64 = 5.5; // notehead ellipse x-radius
@@ -41,10 +42,13 @@ pub(super) const MIN_MEASURE_WIDTH: f64 = 38.0;
 pub(super) const PER_BEAT_MIN_WIDTH: f64 = 55.0;
 pub(super) const CHORD_SYMBOL_OFFSET_Y: f64 = -18.0; // above staff
 
-// ── Colors ──────────────────────────────────────────────────────────
-pub(super) const NOTE_COLOR: &str = "#1a1a1a";
-pub(super) const STAFF_COLOR: &str = "#555555";
-pub(super) const BARLINE_COLOR: &str = "#333333";
-pub(super) const CHORD_COLOR: &str = "#4a4a9a";
-pub(super) const HEADER_COLOR: &str = "#1a1a1a";
-pub(super) const REST_COLOR: &str = "#1a1a1a";
+// ── Single-line layout ──────────────────────────────────────────────
+pub(super) const SINGLE_LINE_TOP: f64 = 80.0; // top staff line, with room above for tempo and chords
+pub(super) const SINGLE_LINE_BOTTOM: f64 = 60.0; // below the staves and lyrics
+pub(super) const SINGLE_LINE_STRETCH: f64 = 1.25; // measure width over its packing minimum
+
+// ── Dynamics ────────────────────────────────────────────────────────
+pub(super) const DYNAMICS_FONT_SIZE: f64 = 15.0;
+pub(super) const DYNAMICS_OFFSET_Y: f64 = 22.0; // baseline below the bottom staff line
+pub(super) const DYNAMICS_NOTE_CLEARANCE: f64 = 16.0; // baseline below the lowest note/stem
+pub(super) const DYNAMICS_ABOVE_GAP: f64 = 8.0; // baseline above the top staff line
@@ -0,0 +1,174 @@
+//! Chord diagrams — fretboard grids above chord symbols and a legend of
+//! every chord under the title.
+
+use crate::model::*;
+use super::constants::*;
+use super::lyrics::estimate_text_width;
+use super::svg_builder::SvgBuilder;
+use super::staff::harmony_label;
+
+/// Grid spacing of an inline diagram; the legend draws them larger.
+const STRING_GAP: f64 = 5.0;
+const FRET_GAP: f64 = 6.0;
+const DOT_RADIUS: f64 = 1.9;
+/// Row above the grid for open and unplayed string marks.
+const MARK_ROW: f64 = 5.0;
+const FRET_LABEL_SIZE: f64 = 7.0;
+
+/// Gap between an inline diagram and the chord symbol under it.
+const SYMBOL_GAP: f64 = 14.0;
+/// Space a system needs above its top staff for inline diagrams.
+pub(super) const CHORD_DIAGRAM_SPACE: f64 = 40.0;
+
+const LEGEND_SCALE: f64 = 1.4;
+const LEGEND_NAME_SIZE: f64 = 13.0;
+/// Narrowest legend cell; cells widen to fit the longest chord name.
+const LEGEND_CELL_WIDTH: f64 = 64.0;
+const LEGEND_NAME_PADDING: f64 = 10.0;
+const LEGEND_ROW_GAP: f64 = 14.0;
+/// Space between the legend and the first system's chord symbols.
+const LEGEND_BOTTOM_GAP: f64 = 20.0;
+
+/// Width and height of a diagram, marks row included.
+fn frame_size(frame: &Frame, scale: f64) -> (f64, f64) {
+    let width = (frame.strings.max(2) - 1) as f64 * STRING_GAP;
+    let height = MARK_ROW + frame.frets.max(1) as f64 * FRET_GAP;
+    (width * scale, height * scale)
+}
+
+/// Whether any of these measures of a part has a chord diagram.
+pub(super) fn has_frames(part: &Part, measures: &[usize]) -> bool {
+    measures.iter()
+        .filter_map(|&mi| part.measures.get(mi))
+        .any(|m| m.harmonies.iter().any(|h| h.frame.is_some()))
+}
+
+/// Draw a diagram with its top-left corner (of the marks row) at `x`, `y`.
+pub(super) fn render_frame(svg: &mut SvgBuilder, frame: &Frame, x: f64, y: f64, scale: f64) {
+    let strings = frame.strings.max(2);
+    let frets = frame.frets.max(1);
+    let (width, height) = frame_size(frame, scale);
+    let string_gap = STRING_GAP * scale;
+    let fret_gap = FRET_GAP * scale;
+    let top = y + MARK_ROW * scale;
+    let first_fret = frame.first_fret.unwrap_or(1).max(1);
+    // Fret of string `s` lies on column `strings - s`, lowest string left
+    let column_x = |string: i32| x + (strings - string) as f64 * string_gap;
+
+    for i in 0..strings {
+        let sx = x + i as f64 * string_gap;
+        svg.line(sx, top, sx, y + height, svg.palette.diagram, 0.6);
+    }
+    for i in 0..=frets {
+        let fy = top + i as f64 * fret_gap;
+        let nut = i == 0 && first_fret == 1;
+        svg.line(x, fy, x + width, fy, svg.palette.diagram, if nut { 2.0 * scale } else { 0.6 });
+    }
+    if first_fret > 1 {
+        svg.text(
+            x + width + 2.0 * scale, top + fret_gap * 0.75, &format!("{}fr", first_fret),
+            FRET_LABEL_SIZE * scale, "normal", svg.palette.diagram, "start",
+        );
+    }
+
+    let mark_y = y + MARK_ROW * scale / 2.0 - 0.5 * scale;
+    let mark = 1.6 * scale;
+    for string in 1..=strings {
+        let sx = column_x(string);
+        match frame.notes.iter().find(|n| n.string == string) {
+            None => {
+                svg.line(sx - mark, mark_y - mark, sx + mark, mark_y + mark, svg.palette.diagram, 0.7);
+                svg.line(sx - mark, mark_y + mark, sx + mark, mark_y - mark, svg.palette.diagram, 0.7);
+            }
+            Some(n) if n.fret == 0 => svg.ring(sx, mark_y, mark, svg.palette.diagram, 0.7),
+            Some(_) => {}
+        }
+    }
+
+    let row_y = |fret: i32| top + ((fret - first_fret) as f64 + 0.5) * fret_gap;
+    for note in frame.notes.iter().filter(|n| n.fret > 0) {
+        if note.barre.as_deref() == Some("start") {
+            let stop = frame.notes.iter()
+                .find(|n| n.fret == note.fret && n.barre.as_deref() == Some("stop"));
+            if let Some(stop) = stop {
+                let (x1, x2) = (column_x(note.string), column_x(stop.string));
+                let thickness = DOT_RADIUS * 2.0 * scale;
+                svg.rect(x1, row_y(note.fret) - thickness / 2.0, x2 - x1, thickness, svg.palette.note, "none", 0.0);
+            }
+        }
+        svg.circle(column_x(note.string), row_y(note.fret), DOT_RADIUS * scale, svg.palette.note);
+    }
+}
+
+/// Draw the diagram of a chord symbol whose text starts at `x`, the
+/// symbol's baseline being `symbol_y`.
+pub(super) fn render_inline_frame(svg: &mut SvgBuilder, frame: &Frame, x: f64, symbol_y: f64) {
+    let (_, height) = frame_size(frame, 1.0);
+    render_frame(svg, frame, x, symbol_y - SYMBOL_GAP - height, 1.0);
+}
+
+/// Each distinct chord with a diagram in the first part, in order of
+/// first appearance.
+fn legend_chords(score: &Score) -> Vec<(String, &Frame)> {
+    let mut chords: Vec<(String, &Frame)> = Vec::new();
+    let harmonies = score.parts.first().into_iter()
+        .flat_map(|p| &p.measures)
+        .flat_map(|m| &m.harmonies);
+    for harmony in harmonies {
+        if let Some(frame) = &harmony.frame {
+            let label = harmony_label(harmony);
+            if !chords.iter().any(|(l, f)| *l == label && *f == frame) {
+                chords.push((label, frame));
+            }
+        }
+    }
+    chords
+}
+
+fn legend_cell_width(chords: &[(String, &Frame)]) -> f64 {
+    chords.iter()
+        .map(|(label, _)| estimate_text_width(label, LEGEND_NAME_SIZE) + LEGEND_NAME_PADDING)
+        .fold(LEGEND_CELL_WIDTH, f64::max)
+}
+
+fn legend_columns(chords: &[(String, &Frame)], page_width: f64) -> usize {
+    let content_width = page_width - PAGE_MARGIN_LEFT - PAGE_MARGIN_RIGHT;
+    ((content_width / legend_cell_width(chords)) as usize).max(1)
+}
+
+fn legend_row_height(chords: &[(String, &Frame)]) -> f64 {
+    let tallest = chords.iter().map(|(_, f)| frame_size(f, LEGEND_SCALE).1).fold(0.0, f64::max);
+    LEGEND_NAME_SIZE + 4.0 + tallest + LEGEND_ROW_GAP
+}
+
+/// Height of the chord legend under the header; 0 without diagrams.
+pub(super) fn legend_height(score: &Score, page_width: f64) -> f64 {
+    let chords = legend_chords(score);
+    if chords.is_empty() {
+        return 0.0;
+    }
+    let rows = chords.len().div_ceil(legend_columns(&chords, page_width));
+    rows as f64 * legend_row_height(&chords) + LEGEND_BOTTOM_GAP
+}
+
+/// Draw the chord legend in rows starting at `top`, centred on the page.
+pub(super) fn render_legend(svg: &mut SvgBuilder, score: &Score, page_width: f64, top: f64) {
+    let chords = legend_chords(score);
+    if chords.is_empty() {
+        return;
+    }
+    let columns = legend_columns(&chords, page_width);
+    let cell_width = legend_cell_width(&chords);
+    let row_height = legend_row_height(&chords);
+    for (row, cells) in chords.chunks(columns).enumerate() {
+        let row_width = cells.len() as f64 * cell_width;
+        let left = (page_width - row_width) / 2.0;
+        let name_y = top + row as f64 * row_height + LEGEND_NAME_SIZE;
+        for (i, (label, frame)) in cells.iter().enumerate() {
+            let center = left + (i as f64 + 0.5) * cell_width;
+            let (width, _) = frame_size(frame, LEGEND_SCALE);
+            svg.chord_text_anchored(center, name_y, label, LEGEND_NAME_SIZE, svg.palette.chord, "middle");
+            render_frame(svg, frame, center - width / 2.0, name_y + 4.0, LEGEND_SCALE);
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//! Text faces for the PDF and PNG backends.
+//!
+//! DejaVu Serif, regular and bold, is bundled with the crate, cut down to
+//! Latin-1, common punctuation and the accidental signs (see
+//! `fonts/LICENSE`), so text comes out the same on every machine and
+//! needs no system fonts.  Characters outside it, such as the title and
+//! lyrics of a Chinese song, are drawn as the missing-glyph box; in a PDF
+//! they still map back to their characters for search and copy.
+
+use super::truetype::TrueType;
+
+const SERIF: &[u8] = include_bytes!("../../fonts/DejaVuSerif.ttf");
+const SERIF_BOLD: &[u8] = include_bytes!("../../fonts/DejaVuSerif-Bold.ttf");
+
+/// The bundled faces.  Sans-serif text is set in the serif face too.
+pub(super) struct Faces {
+    pub regular: TrueType<'static>,
+    pub bold: TrueType<'static>,
+}
+
+impl Faces {
+    pub fn load() -> Result<Self, String> {
+        let parse = |data| TrueType::parse(data).ok_or_else(|| "bundled font is damaged".to_string());
+        Ok(Faces { regular: parse(SERIF)?, bold: parse(SERIF_BOLD)? })
+    }
+
+    pub fn face(&self, bold: bool) -> &TrueType<'static> {
+        if bold { &self.bold } else { &self.regular }
+    }
+}
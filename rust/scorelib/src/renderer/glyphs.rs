@@ -1,6 +1,7 @@
 //! VexFlow font glyph outlines and lookup helpers.
 //!
-//! All glyph data is from OSMD's vexflow_font.js (Gonville font).
+//! All glyph data is from OSMD's vexflow_font.js (Gonville font), except
+//! the dynamic letters, which come from the bundled DejaVu Serif Bold.
 //! Format: m x y | l x y | b endX endY cp1X cp1Y cp2X cp2Y | q endX endY cpX cpY
 
 // ═══════════════════════════════════════════════════════════════════════
@@ -96,6 +97,48 @@ pub(super) fn timesig_number_width(n: i32) -> f64 {
     digits.iter().map(|&d| TIMESIG_DIGIT_HA[d as usize] * s).sum()
 }
 
+// ═══════════════════════════════════════════════════════════════════════
+// Dynamic letter glyphs
+// ═══════════════════════════════════════════════════════════════════════
+
+// The letters of p, mf, sfz and the like.  These are not in the VexFlow
+// font: they are DejaVu Serif Bold (see `fonts/LICENSE`) slanted by 0.2,
+// at 1000 units per em.
+
+/// Scale: font units → SVG pixels, a 15px em like the text it replaced.
+pub(super) const DYNAMIC_GLYPH_SCALE: f64 = 0.015;
+
+pub(super) const DYNAMIC_P: &str = "m 327 285 l 317 234 q 314 103 299 143 q 381 62 329 62 q 464 105 434 62 q 517 260 494 148 q 526 414 539 372 q 460 457 513 457 q 377 417 408 457 q 327 285 345 376 m 189 460 l 115 460 l 127 519 l 374 519 l 361 453 q 432 513 391 494 q 528 533 473 533 q 688 460 638 533 q 711 260 737 387 q 607 59 686 132 q 418 -14 529 -14 q 330 6 363 -14 q 283 66 297 25 l 240 -149 l 320 -149 l 308 -208 l -19 -208 l -7 -149 l 67 -149 l 189 460";
+pub(super) const DYNAMIC_M: &str = "m 694 434 q 787 510 740 486 q 890 533 834 533 q 1013 483 979 533 q 1027 330 1047 432 l 973 59 l 1047 59 l 1035 0 l 726 0 l 738 59 l 800 59 l 849 304 q 859 427 868 401 q 808 452 849 452 q 730 417 762 452 q 684 316 697 381 l 633 59 l 695 59 l 683 0 l 386 0 l 398 59 l 460 59 l 509 304 q 518 427 529 401 q 468 452 508 452 q 390 417 422 452 q 344 316 357 381 l 293 59 l 355 59 l 343 0 l 34 0 l 46 59 l 120 59 l 200 460 l 126 460 l 138 519 l 385 519 l 370 446 q 453 512 409 491 q 550 533 496 533 q 648 509 613 533 q 694 434 682 485";
+pub(super) const DYNAMIC_F: &str = "m 610 630 l 555 630 q 544 687 559 668 q 496 705 530 705 q 434 677 457 705 q 398 586 411 649 l 385 519 l 515 519 l 503 460 l 373 460 l 293 59 l 393 59 l 381 0 l 34 0 l 46 59 l 120 59 l 200 460 l 123 460 l 135 519 l 212 519 l 225 584 q 306 715 242 670 q 477 760 370 760 q 557 755 519 760 q 632 741 596 750 l 610 630";
+pub(super) const DYNAMIC_S: &str = "m 50 15 l 79 160 l 134 160 q 160 72 129 102 q 253 41 190 41 q 338 59 306 41 q 376 112 370 78 q 368 160 383 143 q 305 190 354 177 l 237 208 q 132 265 161 228 q 116 366 104 303 q 200 492 133 451 q 389 533 267 533 q 485 526 435 533 q 596 502 536 518 l 569 371 l 514 371 q 492 451 520 424 q 402 478 464 478 q 319 461 349 478 q 282 412 288 444 q 289 371 276 386 q 340 345 302 355 l 407 327 q 536 263 504 301 q 553 157 567 225 q 465 28 536 69 q 261 -14 394 -14 q 160 -7 213 -14 q 50 15 108 0";
+pub(super) const DYNAMIC_Z: &str = "m 35 0 l 47 59 l 425 462 l 201 462 l 183 373 l 125 373 l 155 519 l 638 519 l 626 461 l 247 58 l 488 58 l 506 152 l 565 152 l 534 0 l 35 0";
+pub(super) const DYNAMIC_R: &str = "m 642 525 l 611 370 l 556 370 q 546 432 561 412 q 493 452 530 452 q 394 402 437 452 q 334 264 351 353 l 293 59 l 387 59 l 375 0 l 34 0 l 46 59 l 120 59 l 200 460 l 121 460 l 133 519 l 385 519 l 366 427 q 449 507 402 481 q 557 533 496 533 q 593 531 572 533 q 642 525 615 529";
+pub(super) const DYNAMIC_N: &str = "m 34 0 l 46 59 l 120 59 l 200 460 l 126 460 l 138 519 l 385 519 l 370 446 q 455 513 410 492 q 560 533 499 533 q 682 482 647 533 q 696 330 716 430 l 642 59 l 716 59 l 704 0 l 394 0 l 406 59 l 469 59 l 524 335 q 525 427 537 401 q 472 452 514 452 q 388 414 420 452 q 339 292 356 375 l 293 59 l 356 59 l 344 0 l 34 0";
+
+/// Look up the outline and advance width (in font units) of a dynamic
+/// letter; None for letters a dynamic mark doesn't use.
+pub(super) fn dynamic_letter_glyph(c: char) -> Option<(&'static str, f64)> {
+    match c {
+        'p' => Some((DYNAMIC_P, 699.0)),
+        'm' => Some((DYNAMIC_M, 1058.0)),
+        'f' => Some((DYNAMIC_F, 430.0)),
+        's' => Some((DYNAMIC_S, 563.0)),
+        'z' => Some((DYNAMIC_Z, 568.0)),
+        'r' => Some((DYNAMIC_R, 527.0)),
+        'n' => Some((DYNAMIC_N, 727.0)),
+        _ => None,
+    }
+}
+
+/// Width in pixels of a dynamic mark drawn with the letter glyphs, or
+/// None when one of its letters has no glyph.
+pub(super) fn dynamic_mark_width(mark: &str) -> Option<f64> {
+    mark.chars()
+        .map(|c| dynamic_letter_glyph(c).map(|(_, advance)| advance * DYNAMIC_GLYPH_SCALE))
+        .sum()
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 // Accidental glyphs
 // ═══════════════════════════════════════════════════════════════════════
@@ -0,0 +1,71 @@
+//! Element ids — stable `id`s, CSS classes and `data-*` attributes for
+//! the groups that draw notes, rests, chord symbols, lyrics and measures,
+//! so a view can highlight, recolor and hit-test them.
+//!
+//! Ids are built from indices into the score (part, measure, and note in
+//! `Measure::notes`), so they are the same in every rendering of a score
+//! and in its playback map:
+//!
+//! - measure: `m{part}-{measure}`, class `measure`
+//! - note or rest: `n{part}-{measure}-{note}`, class `note`, `note grace`
+//!   or `rest`
+//! - chord symbol: `h{part}-{measure}-{harmony}`, class `chord-symbol`
+//! - lyric: `l{part}-{measure}-{note}-{verse}`, class `lyric`
+
+use crate::model::Note;
+
+/// Id of the group that draws note `note` of measure `measure` in part
+/// `part` (all 0-based).
+pub fn note_id(part: usize, measure: usize, note: usize) -> String {
+    format!("n{part}-{measure}-{note}")
+}
+
+/// One part's measure, the parent of the elements drawn for it.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct MeasureRef {
+    pub(super) part: usize,
+    pub(super) measure: usize,
+}
+
+impl MeasureRef {
+    fn data(&self) -> String {
+        format!(r#"data-part="{}" data-measure="{}""#, self.part, self.measure)
+    }
+
+    /// Attributes of the measure's group.
+    pub(super) fn attributes(&self) -> String {
+        format!(r#"id="m{}-{}" class="measure" {}"#, self.part, self.measure, self.data())
+    }
+
+    /// Attributes of the group of note `idx`, `note`.
+    pub(super) fn note(&self, idx: usize, note: &Note) -> String {
+        let class = if note.rest {
+            "rest"
+        } else if note.grace {
+            "note grace"
+        } else {
+            "note"
+        };
+        format!(
+            r#"id="{}" class="{}" {} data-note="{}" data-voice="{}" data-staff="{}""#,
+            note_id(self.part, self.measure, idx), class, self.data(), idx,
+            note.voice.unwrap_or(1), note.staff.unwrap_or(1),
+        )
+    }
+
+    /// Attributes of the group of chord symbol `idx` in `Measure::harmonies`.
+    pub(super) fn harmony(&self, idx: usize) -> String {
+        format!(
+            r#"id="h{}-{}-{}" class="chord-symbol" {} data-harmony="{}""#,
+            self.part, self.measure, idx, self.data(), idx,
+        )
+    }
+
+    /// Attributes of the group of note `idx`'s lyric in `verse`.
+    pub(super) fn lyric(&self, idx: usize, verse: i32) -> String {
+        format!(
+            r#"id="l{}-{}-{}-{}" class="lyric" {} data-note="{}" data-verse="{}""#,
+            self.part, self.measure, idx, verse, self.data(), idx, verse,
+        )
+    }
+}
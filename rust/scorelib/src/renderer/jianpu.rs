@@ -0,0 +1,950 @@
+//! Jianpu (numbered notation) renderer.
+//!
+//! Each staff of each part becomes one row of numbers: a note is drawn as
+//! its scale degree in the current key, counting from the major tonic, so
+//! minor keys read from 6 as usual.  Octave dots go above or below the
+//! number, underlines halve the value (one per beam), each dash holds a
+//! note for another quarter and dots are augmentation dots.  Rests are 0.
+//! Lyrics sit under their row.  Chord symbols, barlines, repeats, voltas
+//! and jump marks reuse the staff renderer's helpers, with the row's band
+//! of `STAFF_HEIGHT` standing in for the staff.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::model::*;
+use super::constants::*;
+use super::glyphs::*;
+use super::svg_builder::{SvgBuilder, empty_svg, vexflow_outline_to_svg};
+use super::beat_map::{lookup_beat_x, note_x_positions_from_beat_map};
+use super::lyrics::*;
+use super::staff::*;
+use super::layout::*;
+use super::{layout_positions, MeasurePlacement};
+use super::ids::MeasureRef;
+
+// ── Jianpu constants ────────────────────────────────────────────────
+
+const NUMBER_SIZE: f64 = 18.0;
+/// Number baseline below the top of its row's band.
+const NUMBER_BASELINE: f64 = 27.0;
+const DIGIT_HEIGHT: f64 = 13.0;
+const DIGIT_HALF_WIDTH: f64 = 5.5;
+const UNDERLINE_OFFSET: f64 = 4.0; // first underline below the baseline
+const UNDERLINE_GAP: f64 = 3.5;
+const UNDERLINE_WIDTH: f64 = 1.2;
+const OCTAVE_DOT_RADIUS: f64 = 1.7;
+const OCTAVE_DOT_GAP: f64 = 4.5;
+const AUGMENTATION_DOT_RADIUS: f64 = 1.8;
+const DASH_HALF_WIDTH: f64 = 5.5;
+const DASH_WIDTH: f64 = 1.6;
+/// Chord members are stacked upward by this much.
+const CHORD_STACK: f64 = 18.0;
+const ACCIDENTAL_SCALE: f64 = ACCIDENTAL_GLYPH_SCALE * 0.5;
+/// Extra room before a number with an accidental.
+const ACCIDENTAL_ROOM: f64 = 8.0;
+/// Gap between rows (or after the lyrics of a row).
+const ROW_GAP: f64 = 20.0;
+/// First lyric baseline below the top of the band.
+const LYRICS_OFFSET: f64 = STAFF_HEIGHT + 18.0;
+const SYSTEM_GAP: f64 = 30.0;
+/// Width of a quarter's cell; shorter values get √(length) of it.
+const CELL_WIDTH: f64 = 32.0;
+const MEASURE_INSET: f64 = 16.0;
+const LABEL_SIZE: f64 = 14.0;
+
+/// Step indices in the order sharps are added to a key signature.
+const SHARP_ORDER: [i32; 7] = [3, 0, 4, 1, 5, 2, 6];
+const STEP_NAMES: [&str; 7] = ["C", "D", "E", "F", "G", "A", "B"];
+
+// ═══════════════════════════════════════════════════════════════════════
+// Public API
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Render a parsed Score as jianpu (numbered notation) SVG.
+///
+/// `page_width` works as for `render_score_to_svg`: pass `None` to use the
+/// default (820), or the screen width on phones.
+pub fn render_score_to_jianpu_svg(score: &Score, page_width: Option<f64>) -> String {
+    let page_width = match page_width {
+        Some(w) if w > 0.0 => w,
+        _ => DEFAULT_PAGE_WIDTH,
+    };
+
+    if score.parts.is_empty() {
+        return empty_svg("No parts in score");
+    }
+
+    let tracks: Vec<PartTrack> = score.parts.iter().map(PartTrack::new).collect();
+    let rows = build_rows(score, &tracks);
+    let (layout, row_pitches) = compute_jianpu_layout(score, &tracks, &rows, page_width);
+    draw_jianpu(score, &tracks, &rows, &layout, &row_pitches, page_width)
+}
+
+/// The jianpu counterpart of `compute_measure_positions`: measure and
+/// system positions in the SVG from `render_score_to_jianpu_svg` at the
+/// same page width, in the same form.  The beat map also has an entry for
+/// each dash and rest digit, so the cursor steps through held notes.
+pub fn compute_jianpu_measure_positions(
+    score: &Score,
+    page_width: Option<f64>,
+) -> (Vec<MeasurePlacement>, Vec<(f64, f64)>) {
+    let page_width = match page_width {
+        Some(w) if w > 0.0 => w,
+        _ => DEFAULT_PAGE_WIDTH,
+    };
+
+    if score.parts.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+
+    let tracks: Vec<PartTrack> = score.parts.iter().map(PartTrack::new).collect();
+    let rows = build_rows(score, &tracks);
+    let (layout, row_pitches) = compute_jianpu_layout(score, &tracks, &rows, page_width);
+    let mut systems = layout.systems.iter().zip(&row_pitches);
+    layout_positions(&layout, |_| {
+        let (system, &pitch) = systems.next().expect("one pitch per system");
+        (system.total_staves as f64 - 1.0) * pitch + STAFF_HEIGHT
+    })
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Scale degrees
+// ═══════════════════════════════════════════════════════════════════════
+
+pub(super) fn step_index(step: &str) -> Option<i32> {
+    STEP_NAMES.iter().position(|&s| s == step).map(|i| i as i32)
+}
+
+/// Step index of the major tonic for a key signature.
+pub(super) fn tonic_step(fifths: i32) -> i32 {
+    (4 * fifths).rem_euclid(7)
+}
+
+/// Alteration the key signature gives a step (+1 sharp, -1 flat).
+pub(super) fn key_alter(fifths: i32, step: i32) -> i32 {
+    let count = fifths.unsigned_abs().min(7) as usize;
+    if fifths > 0 && SHARP_ORDER[..count].contains(&step) {
+        1
+    } else if fifths < 0 && SHARP_ORDER[7 - count..].contains(&step) {
+        -1
+    } else {
+        0
+    }
+}
+
+/// "1=D", "1=♭B": the pitch of degree 1 in a key.
+fn key_label(fifths: i32) -> String {
+    let step = tonic_step(fifths);
+    let accidental = match key_alter(fifths, step) {
+        1 => "♯",
+        -1 => "♭",
+        _ => "",
+    };
+    format!("1={}{}", accidental, STEP_NAMES[step as usize])
+}
+
+/// Steps above the tonic of octave 0; degree and octave follow from it.
+fn diatonic_offset(pitch: &Pitch, fifths: i32) -> Option<i32> {
+    Some(step_index(&pitch.step)? + 7 * pitch.octave - tonic_step(fifths))
+}
+
+/// One number of a slot.
+struct Degree {
+    /// 1–7
+    number: i32,
+    /// Octave dots: positive above, negative below
+    octave: i32,
+    /// Accidental to print, relative to the key: +1 ♯, -1 ♭, 0 ♮
+    accidental: Option<i32>,
+    midi: i32,
+    tie_start: bool,
+    tie_stop: bool,
+    /// Index of its note in `Measure::notes`
+    note: usize,
+}
+
+impl Degree {
+    /// `altered` holds the accidentals already in effect in the measure,
+    /// keyed by step and octave.
+    fn new(
+        note: &Note,
+        idx: usize,
+        pitch: &Pitch,
+        fifths: i32,
+        reference: i32,
+        altered: &mut HashMap<(i32, i32), i32>,
+    ) -> Option<Degree> {
+        let offset = diatonic_offset(pitch, fifths)?;
+        let step = step_index(&pitch.step)?;
+        let alter = pitch.alter.unwrap_or(0.0).round() as i32;
+        let in_effect = altered
+            .get(&(step, pitch.octave))
+            .copied()
+            .unwrap_or_else(|| key_alter(fifths, step));
+        altered.insert((step, pitch.octave), alter);
+        Some(Degree {
+            number: offset.rem_euclid(7) + 1,
+            octave: offset.div_euclid(7) - reference,
+            accidental: (alter != in_effect).then(|| alter - key_alter(fifths, step)),
+            midi: pitch.to_midi(),
+            tie_start: note.tie_start,
+            tie_stop: note.tie_stop,
+            note: idx,
+        })
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Rows and slots
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Running attributes of a part, resolved for each of its measures.
+struct PartTrack {
+    divisions: Vec<i32>,
+    fifths: Vec<i32>,
+    times: Vec<Option<TimeSignature>>,
+}
+
+impl PartTrack {
+    fn new(part: &Part) -> PartTrack {
+        let mut track = PartTrack { divisions: Vec::new(), fifths: Vec::new(), times: Vec::new() };
+        let (mut divisions, mut fifths, mut time) = (1, 0, None);
+        for measure in &part.measures {
+            if let Some(ref attrs) = measure.attributes {
+                if let Some(d) = attrs.divisions {
+                    divisions = d;
+                }
+                if let Some(ref k) = attrs.key {
+                    fifths = k.fifths;
+                }
+                if attrs.time.is_some() {
+                    time = attrs.time.clone();
+                }
+            }
+            track.divisions.push(divisions);
+            track.fifths.push(fifths);
+            track.times.push(time.clone());
+        }
+        track
+    }
+
+    fn divisions_at(&self, mi: usize) -> i32 {
+        self.divisions.get(mi).copied().unwrap_or(1)
+    }
+}
+
+enum SlotKind {
+    /// A note or chord, lowest number first
+    Note(Vec<Degree>),
+    Rest,
+    /// A held quarter of the previous note
+    Dash,
+}
+
+/// One glyph position in a row: a number, a 0 or a dash.
+struct Slot {
+    /// Onset in quarter notes from the start of the measure
+    beat: f64,
+    kind: SlotKind,
+    underlines: usize,
+    dot: bool,
+    /// Underlines continuing to the next slot
+    link: usize,
+    /// Index in `Measure::notes` of the rest the slot starts
+    rest: Option<usize>,
+}
+
+/// One staff of one part, drawn as a row of numbers.
+struct Row {
+    part_idx: usize,
+    staff: usize,
+    /// Slots of each measure of the part
+    measures: Vec<Vec<Slot>>,
+}
+
+impl Row {
+    fn staff_filter(&self, part: &Part) -> Option<i32> {
+        (detect_staves(part) > 1).then_some(self.staff as i32)
+    }
+
+    /// How far the tallest chord in measure `mi` reaches above one number.
+    fn stack_height(&self, mi: usize) -> f64 {
+        self.measures.get(mi)
+            .into_iter()
+            .flatten()
+            .filter_map(|slot| match &slot.kind {
+                SlotKind::Note(degrees) => Some((degrees.len() - 1) as f64 * CHORD_STACK),
+                _ => None,
+            })
+            .fold(0.0, f64::max)
+    }
+}
+
+/// Rows for every staff of every part, in score order.  Only the first
+/// voice of each staff is drawn; grace notes and tab staves under a
+/// notation staff are left out.
+fn build_rows(score: &Score, tracks: &[PartTrack]) -> Vec<Row> {
+    let mut rows = Vec::new();
+    for (pidx, part) in score.parts.iter().enumerate() {
+        let track = &tracks[pidx];
+        let staves = detect_staves(part);
+        let has_notation = (1..=staves).any(|s| !part.is_tab_staff(s as i32));
+        for staff in 1..=staves {
+            // Tab repeats the notation staff above it
+            if has_notation && part.is_tab_staff(staff as i32) {
+                continue;
+            }
+            let on_staff = |n: &Note| staves == 1 || n.staff.unwrap_or(1) == staff as i32;
+            let voice = part.measures.iter()
+                .flat_map(|m| &m.notes)
+                .find(|n| on_staff(n) && !n.grace)
+                .and_then(|n| n.voice);
+            let keep = |n: &Note| {
+                on_staff(n) && !n.grace && (voice.is_none() || n.voice.is_none() || n.voice == voice)
+            };
+            let reference = reference_octave(part, track, &keep);
+            let measures = part.measures.iter()
+                .enumerate()
+                .map(|(mi, m)| measure_slots(m, track, mi, reference, &keep))
+                .collect();
+            rows.push(Row { part_idx: pidx, staff, measures });
+        }
+    }
+    rows
+}
+
+/// The octave (counted from the tonic) drawn without dots: the one most
+/// of the row's notes fall in, preferring the one nearest middle C.
+fn reference_octave(part: &Part, track: &PartTrack, keep: &impl Fn(&Note) -> bool) -> i32 {
+    let mut counts: BTreeMap<i32, usize> = BTreeMap::new();
+    for (mi, measure) in part.measures.iter().enumerate() {
+        for note in measure.notes.iter().filter(|n| keep(n) && !n.rest) {
+            if let Some(offset) = note.pitch.as_ref().and_then(|p| diatonic_offset(p, track.fifths[mi])) {
+                *counts.entry(offset.div_euclid(7)).or_default() += 1;
+            }
+        }
+    }
+    counts.into_iter()
+        .max_by_key(|&(octave, count)| (count, std::cmp::Reverse((octave - 4).abs())))
+        .map_or(4, |(octave, _)| octave)
+}
+
+/// Value of a note as (plain length in quarters, dotted), from its type
+/// when it has one, otherwise from its sounding length.
+fn note_value(note: &Note, quarters: f64) -> (f64, bool) {
+    let typed = match note.note_type.as_deref() {
+        Some("breve") => Some(8.0),
+        Some("whole") => Some(4.0),
+        Some("half") => Some(2.0),
+        Some("quarter") => Some(1.0),
+        Some("eighth") => Some(0.5),
+        Some("16th") => Some(0.25),
+        Some("32nd") => Some(0.125),
+        Some("64th") => Some(0.0625),
+        _ => None,
+    };
+    match typed {
+        Some(base) if !note.measure_rest => (base, note.dot),
+        _ if quarters > 0.0 => {
+            let base = 2f64.powf(quarters.log2().floor());
+            (base, quarters >= base * 1.5 - 0.001)
+        }
+        _ => (1.0, false),
+    }
+}
+
+fn measure_slots(
+    measure: &Measure,
+    track: &PartTrack,
+    mi: usize,
+    reference: i32,
+    keep: &impl Fn(&Note) -> bool,
+) -> Vec<Slot> {
+    let fifths = track.fifths[mi];
+    let times = measure.note_times_quarters(track.divisions[mi]);
+    let mut slots: Vec<Slot> = Vec::new();
+    let mut slot_notes: Vec<Option<usize>> = Vec::new();
+    let mut altered = HashMap::new();
+    let mut chord_slot: Option<usize> = None;
+
+    for (i, note) in measure.notes.iter().enumerate() {
+        if !keep(note) {
+            if !note.chord {
+                chord_slot = None;
+            }
+            continue;
+        }
+        let degree = match note.pitch {
+            Some(ref p) if !note.rest => Degree::new(note, i, p, fifths, reference, &mut altered),
+            _ => None,
+        };
+        if note.chord {
+            if let (Some(si), Some(degree)) = (chord_slot, degree) {
+                if let SlotKind::Note(ref mut stack) = slots[si].kind {
+                    stack.push(degree);
+                    stack.sort_by_key(|d| d.midi);
+                }
+            }
+            continue;
+        }
+
+        let (onset, quarters) = times[i];
+        let (base, dotted) = note_value(note, quarters);
+        let is_rest = degree.is_none();
+        chord_slot = (!is_rest).then_some(slots.len());
+        let kind = match degree {
+            Some(d) => SlotKind::Note(vec![d]),
+            None => SlotKind::Rest,
+        };
+        let rest = is_rest.then_some(i);
+        slot_notes.push(Some(i));
+
+        if base < 1.0 {
+            let underlines = (-base.log2()).round() as usize;
+            slots.push(Slot { beat: onset, kind, underlines, dot: dotted, link: 0, rest });
+            continue;
+        }
+
+        // A digit per quarter: dashes after a note, more zeros for a rest
+        let held = ((quarters + 0.001).floor() as usize).max(1);
+        slots.push(Slot { beat: onset, kind, underlines: 0, dot: false, link: 0, rest });
+        for k in 1..held {
+            let kind = if is_rest { SlotKind::Rest } else { SlotKind::Dash };
+            slots.push(Slot { beat: onset + k as f64, kind, underlines: 0, dot: false, link: 0, rest: None });
+            slot_notes.push(None);
+        }
+        if let Some(last) = slots.last_mut() {
+            last.dot = quarters - held as f64 > 0.25 || (dotted && held == 1);
+        }
+    }
+
+    // Underlines run on along beams; without beams, within a beat
+    let beat_unit = match track.times[mi] {
+        Some(ref ts) if ts.beat_type == 8 && ts.beats % 3 == 0 => 1.5,
+        _ => 1.0,
+    };
+    let group = |beat: f64| ((beat + 0.001) / beat_unit).floor() as i64;
+    for i in 1..slots.len() {
+        let levels = slots[i - 1].underlines.min(slots[i].underlines);
+        let beams = slot_notes[i - 1]
+            .map(|n| &measure.notes[n].beams)
+            .filter(|b| !b.is_empty());
+        let same_group = group(slots[i - 1].beat) == group(slots[i].beat);
+        slots[i - 1].link = (1..=levels)
+            .take_while(|&level| match beams {
+                Some(beams) => beams.iter().any(|b| {
+                    b.number == level as i32 && matches!(b.beam_type.as_str(), "begin" | "continue")
+                }),
+                None => same_group,
+            })
+            .count();
+    }
+
+    slots
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Layout
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Distance between the tops of consecutive rows in a system whose
+/// lyrics run to `verses` lines.
+fn row_pitch(verses: i32) -> f64 {
+    STAFF_HEIGHT + ROW_GAP + verses.max(0) as f64 * LYRICS_LINE_HEIGHT
+}
+
+/// Glyph positions of one measure before it is stretched to its width:
+/// the onsets of all its rows and the least room after each.
+struct Cells {
+    beats: Vec<f64>,
+    gaps: Vec<f64>,
+    left_inset: f64,
+    right_inset: f64,
+}
+
+impl Cells {
+    fn new(score: &Score, rows: &[Row], mi: usize, divisions: &[i32], total_quarters: f64) -> Cells {
+        let slots = || rows.iter().filter_map(|r| r.measures.get(mi)).flatten();
+        let mut beats: Vec<f64> = slots().map(|s| s.beat).collect();
+        beats.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        beats.dedup_by(|a, b| (*a - *b).abs() < 0.001);
+        let has_accidental = |bt: f64| slots().any(|s| {
+            (s.beat - bt).abs() < 0.001
+                && matches!(s.kind, SlotKind::Note(ref stack) if stack.iter().any(|d| d.accidental.is_some()))
+        });
+
+        // Numbers are read one by one, so a cell grows with the root of
+        // its length rather than in proportion, and fits its lyric
+        let events = collect_lyric_events(&score.parts, mi, divisions);
+        let event_at = |bt: f64| events.iter().find(|ev| (ev.beat_time - bt).abs() < 0.001);
+        let gaps = beats.iter()
+            .enumerate()
+            .map(|(i, &beat)| {
+                let next = beats.get(i + 1).copied();
+                let length = next.unwrap_or(total_quarters) - beat;
+                let mut cell = CELL_WIDTH * length.max(0.0).sqrt().max(0.5);
+                if next.is_some_and(has_accidental) {
+                    cell += ACCIDENTAL_ROOM;
+                }
+                let lyric = match (event_at(beat), next.and_then(event_at)) {
+                    (Some(le), Some(re)) => lyric_pair_min_spacing(le, re),
+                    (Some(le), None) => le.text_width / 2.0 + LYRICS_MIN_GAP - MEASURE_INSET,
+                    _ => 0.0,
+                };
+                cell.max(lyric)
+            })
+            .collect();
+
+        let measure = &score.parts[0].measures[mi];
+        let (mut left_inset, mut right_inset) = (MEASURE_INSET, MEASURE_INSET);
+        for barline in &measure.barlines {
+            let special = barline.repeat.is_some()
+                || matches!(barline.bar_style.as_deref(), Some("light-heavy") | Some("heavy-light"));
+            if special && barline.location == "left" {
+                left_inset = left_inset.max(28.0);
+            } else if special {
+                right_inset = right_inset.max(30.0);
+            }
+        }
+
+        Cells { beats, gaps, left_inset, right_inset }
+    }
+
+    fn min_width(&self) -> f64 {
+        (self.left_inset + self.gaps.iter().sum::<f64>() + self.right_inset).max(MIN_MEASURE_WIDTH)
+    }
+
+    /// `(beat, x)` for each onset with the measure at `x`, `width` wide.
+    fn beat_x_map(&self, x: f64, width: f64) -> Vec<(f64, f64)> {
+        let total: f64 = self.gaps.iter().sum();
+        let stretch = if total > 0.0 {
+            ((width - self.left_inset - self.right_inset) / total).max(0.0)
+        } else {
+            1.0
+        };
+        let mut cx = x + self.left_inset;
+        self.beats.iter()
+            .zip(&self.gaps)
+            .map(|(&beat, &gap)| {
+                let entry = (beat, cx);
+                cx += gap * stretch;
+                entry
+            })
+            .collect()
+    }
+}
+
+/// Lay out systems as `compute_layout` does, without clef and key
+/// prefixes.  Each system's width is shared out by the measures'
+/// minimum widths, so every system has an even density.  Also returns
+/// each system's row pitch.
+fn compute_jianpu_layout(
+    score: &Score,
+    tracks: &[PartTrack],
+    rows: &[Row],
+    page_width: f64,
+) -> (ScoreLayout, Vec<f64>) {
+    let content_width = page_width - PAGE_MARGIN_LEFT - PAGE_MARGIN_RIGHT;
+    let ref_track = &tracks[0];
+
+    let cells: Vec<Cells> = score.parts[0].measures.iter()
+        .enumerate()
+        .map(|(mi, measure)| {
+            let nominal = ref_track.times[mi]
+                .as_ref()
+                .map_or(4.0, |ts| ts.beats as f64 * 4.0 / ts.beat_type as f64);
+            // Pickups end at their last note, as in `compute_layout`
+            let actual = measure.content_quarters(ref_track.divisions_at(mi));
+            let total_quarters = if measure.implicit && actual > 0.0 && actual < nominal {
+                actual
+            } else {
+                nominal
+            };
+            let divisions: Vec<i32> = tracks.iter().map(|t| t.divisions_at(mi)).collect();
+            Cells::new(score, rows, mi, &divisions, total_quarters)
+        })
+        .collect();
+    let min_widths: Vec<f64> = cells.iter().map(Cells::min_width).collect();
+
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut current: Vec<usize> = Vec::new();
+    let mut current_width = 0.0;
+    for (mi, &min_w) in min_widths.iter().enumerate() {
+        if !current.is_empty() && current_width + min_w > content_width {
+            groups.push(std::mem::take(&mut current));
+            current_width = 0.0;
+        }
+        current.push(mi);
+        current_width += min_w;
+    }
+    if !current.is_empty() {
+        groups.push(current);
+    }
+
+    let has_early_chords = score.parts.iter().any(|p| {
+        p.measures.iter().take(8).any(|m| !m.harmonies.is_empty())
+    });
+    // The key label shares the space above the first row with any tempo mark
+    let has_opening_directions = score.parts[0].measures.first()
+        .is_some_and(|m| !m.directions.is_empty());
+    let mut current_y = FIRST_SYSTEM_TOP
+        + if has_early_chords { 18.0 } else { 0.0 }
+        + if has_opening_directions { LABEL_SIZE + 10.0 } else { 0.0 };
+
+    let mut systems = Vec::with_capacity(groups.len());
+    let mut pitches = Vec::with_capacity(groups.len());
+    for group in groups {
+        let x_start = PAGE_MARGIN_LEFT;
+        let x_end = PAGE_MARGIN_LEFT + content_width;
+        let total_weight: f64 = group.iter().map(|&mi| min_widths[mi]).sum();
+        let scale = if total_weight > 0.0 { content_width / total_weight } else { 1.0 };
+
+        let mut measures = Vec::with_capacity(group.len());
+        let mut x = x_start;
+        for &mi in &group {
+            let w = min_widths[mi] * scale;
+            measures.push(MeasureLayout {
+                measure_idx: mi,
+                x,
+                width: w,
+                beat_x_map: cells[mi].beat_x_map(x, w),
+                has_key_change: false,
+                has_time_change: false,
+                has_clef_change: false,
+                prev_key_fifths: None,
+                left_inset: cells[mi].left_inset,
+                right_inset: cells[mi].right_inset,
+            });
+            x += w;
+        }
+
+        let verses = group.iter()
+            .flat_map(|&mi| score.parts.iter().filter_map(move |p| p.measures.get(mi)))
+            .flat_map(|m| &m.notes)
+            .flat_map(|n| &n.lyrics)
+            .map(|l| l.number)
+            .max()
+            .unwrap_or(0);
+        // Chords stack upward, so each row makes room for the one below it
+        let stacks: Vec<f64> = rows.iter()
+            .map(|row| group.iter().map(|&mi| row.stack_height(mi)).fold(0.0, f64::max))
+            .collect();
+        let pitch = row_pitch(verses) + stacks.iter().skip(1).copied().fold(0.0, f64::max);
+        current_y += stacks.first().copied().unwrap_or(0.0);
+
+        let mut parts = Vec::with_capacity(score.parts.len());
+        let mut row_idx = 0;
+        for pidx in 0..score.parts.len() {
+            let num_staves = rows.iter().filter(|r| r.part_idx == pidx).count();
+            parts.push(PartStaffInfo { part_idx: pidx, y_offset: row_idx as f64 * pitch, num_staves });
+            row_idx += num_staves;
+        }
+
+        systems.push(SystemLayout {
+            y: current_y,
+            x_start,
+            x_end,
+            measures,
+            parts,
+            show_clef: false,
+            show_time: false,
+            total_staves: rows.len(),
+            new_page: false,
+        });
+        pitches.push(pitch);
+        current_y += rows.len() as f64 * pitch + SYSTEM_GAP;
+    }
+
+    (ScoreLayout { systems, width: page_width, total_height: current_y + 20.0, legend_top: 0.0 }, pitches)
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Drawing
+// ═══════════════════════════════════════════════════════════════════════
+
+/// A tie waiting for its closing note, keyed by MIDI pitch.
+struct OpenTie {
+    midi: i32,
+    x: f64,
+}
+
+fn draw_jianpu(
+    score: &Score,
+    tracks: &[PartTrack],
+    rows: &[Row],
+    layout: &ScoreLayout,
+    row_pitches: &[f64],
+    page_width: f64,
+) -> String {
+    let mut svg = SvgBuilder::new(page_width, layout.total_height);
+    svg.rect(0.0, 0.0, page_width, layout.total_height, svg.palette.background, "none", 0.0);
+    render_header(&mut svg, score, page_width);
+
+    // "1=C 4/4" at the top left, under the title
+    let mut label = key_label(tracks[0].fifths.first().copied().unwrap_or(0));
+    if let Some(Some(ref ts)) = tracks[0].times.first() {
+        label.push_str(&format!("  {}/{}", ts.beats, ts.beat_type));
+    }
+    svg.text(PAGE_MARGIN_LEFT, PAGE_MARGIN_TOP + 42.0, &label, LABEL_SIZE, "bold", svg.palette.header, "start");
+
+    let mut open_ties: Vec<Vec<OpenTie>> = rows.iter().map(|_| Vec::new()).collect();
+
+    for (system, &pitch) in layout.systems.iter().zip(row_pitches) {
+        let band_y = |ri: usize| system.y + ri as f64 * pitch;
+
+        // Measure number in the left margin, and the bracket joining rows
+        if let Some(first_ml) = system.measures.first() {
+            let measure_num = first_ml.measure_idx + 1;
+            if measure_num > 1 {
+                svg.elements.push(format!(
+                    "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"12\" font-style=\"italic\" fill=\"{}\" text-anchor=\"end\">{}</text>",
+                    PAGE_MARGIN_LEFT - 6.0, system.y + NUMBER_BASELINE, svg.palette.muted, measure_num
+                ));
+            }
+        }
+        if rows.len() > 1 {
+            svg.line(
+                PAGE_MARGIN_LEFT, system.y, PAGE_MARGIN_LEFT, band_y(rows.len() - 1) + STAFF_HEIGHT,
+                svg.palette.barline, BARLINE_WIDTH,
+            );
+        }
+
+        // Continued ties restart at the left edge
+        for tie in open_ties.iter_mut().flatten() {
+            tie.x = PAGE_MARGIN_LEFT;
+        }
+
+        for ml in &system.measures {
+            let mi = ml.measure_idx;
+            let (mx, mw) = (ml.x, ml.width);
+
+            // One group per part, around the rows of all its staves
+            let mut measure_start = svg.elements.len();
+            for (ri, row) in rows.iter().enumerate() {
+                if ri > 0 && rows[ri - 1].part_idx != row.part_idx {
+                    measure_start = svg.elements.len();
+                }
+                let at = MeasureRef { part: row.part_idx, measure: mi };
+                let part = &score.parts[row.part_idx];
+                let (Some(measure), Some(slots)) = (part.measures.get(mi), row.measures.get(mi)) else {
+                    continue;
+                };
+                let track = &tracks[row.part_idx];
+                let divisions = track.divisions_at(mi);
+                let band = band_y(ri);
+
+                if ri == 0 {
+                    render_row_directions(&mut svg, measure, track, mi, mx, mw, band);
+                    render_harmonies(&mut svg, measure, divisions, &ml.beat_x_map, mx, mw, band, at);
+                    render_barlines(&mut svg, &measure.barlines, mx, mw, band);
+                } else {
+                    // Volta brackets only over the top row
+                    let barlines: Vec<Barline> = measure.barlines.iter()
+                        .map(|b| Barline { ending: None, ..b.clone() })
+                        .collect();
+                    render_barlines(&mut svg, &barlines, mx, mw, band);
+                }
+                let has_special_right_barline = measure.barlines.iter().any(|b| {
+                    (b.location == "right" || b.location.is_empty()) && b.bar_style.is_some()
+                });
+                if !has_special_right_barline {
+                    svg.line(mx + mw, band, mx + mw, band + STAFF_HEIGHT, svg.palette.barline, BARLINE_WIDTH);
+                }
+
+                let xs: Vec<f64> = slots.iter().map(|s| lookup_beat_x(&ml.beat_x_map, s.beat)).collect();
+                for (slot, &x) in slots.iter().zip(&xs) {
+                    render_slot(&mut svg, slot, x, band, measure, at);
+                }
+                render_underlines(&mut svg, slots, &xs, band);
+                render_ties(&mut svg, slots, &xs, band, &mut open_ties[ri]);
+
+                let note_xs = note_x_positions_from_beat_map(measure, divisions, &ml.beat_x_map);
+                render_lyrics(&mut svg, measure, &note_xs, band + LYRICS_OFFSET, row.staff_filter(part), at);
+                if rows.get(ri + 1).is_none_or(|next| next.part_idx != row.part_idx) {
+                    svg.wrap(measure_start, &at.attributes());
+                }
+            }
+        }
+
+        // Ties into the next system run to the end of this one
+        for (ri, ties) in open_ties.iter().enumerate() {
+            for tie in ties {
+                render_tie(&mut svg, tie.x, system.x_end, band_y(ri));
+            }
+        }
+    }
+
+    svg.build()
+}
+
+/// Tempo, segno, coda, jump marks and words above the top row, plus
+/// "1=D 3/4" where the key or time changes.
+fn render_row_directions(
+    svg: &mut SvgBuilder,
+    measure: &Measure,
+    track: &PartTrack,
+    mi: usize,
+    mx: f64,
+    mw: f64,
+    band: f64,
+) {
+    let mut word_idx = 0;
+    for dir in &measure.directions {
+        if dir.sound_tempo.is_some() || dir.metronome.is_some() {
+            render_tempo_marking(svg, mx + 4.0, band, dir);
+        }
+        if dir.segno {
+            render_segno(svg, mx + 6.0, band);
+        }
+        if dir.coda {
+            render_coda(svg, mx + 6.0, band);
+        }
+        if let Some(ref text) = dir.words {
+            if is_jump_text(text) {
+                render_jump_text(svg, mx + mw - 4.0, band, band, None, text, word_idx);
+                word_idx += 1;
+            } else if dir.placement.as_deref() != Some("below") {
+                render_direction_words(svg, mx, band, band, dir, word_idx);
+                word_idx += 1;
+            }
+        }
+    }
+
+    if mi == 0 {
+        return;
+    }
+    let mut label = String::new();
+    if track.fifths[mi] != track.fifths[mi - 1] {
+        label = key_label(track.fifths[mi]);
+    }
+    let time_changed = match (&track.times[mi], &track.times[mi - 1]) {
+        (Some(t), Some(p)) => t.beats != p.beats || t.beat_type != p.beat_type,
+        (Some(_), None) => true,
+        _ => false,
+    };
+    if time_changed {
+        if let Some(ref ts) = track.times[mi] {
+            if !label.is_empty() {
+                label.push_str("  ");
+            }
+            label.push_str(&format!("{}/{}", ts.beats, ts.beat_type));
+        }
+    }
+    if !label.is_empty() {
+        svg.text(mx + 4.0, band + CHORD_SYMBOL_OFFSET_Y - 14.0, &label, 12.0, "bold", svg.palette.note, "start");
+    }
+}
+
+fn render_slot(svg: &mut SvgBuilder, slot: &Slot, x: f64, band: f64, measure: &Measure, at: MeasureRef) {
+    let baseline = band + NUMBER_BASELINE;
+    match slot.kind {
+        SlotKind::Dash => {
+            svg.line(
+                x - DASH_HALF_WIDTH, baseline - 6.0, x + DASH_HALF_WIDTH, baseline - 6.0,
+                svg.palette.note, DASH_WIDTH,
+            );
+        }
+        SlotKind::Rest => {
+            let start = svg.elements.len();
+            svg.text(x, baseline, "0", NUMBER_SIZE, "normal", svg.palette.note, "middle");
+            if let Some(i) = slot.rest {
+                svg.wrap(start, &at.note(i, &measure.notes[i]));
+            }
+        }
+        SlotKind::Note(ref stack) => {
+            for (k, degree) in stack.iter().enumerate() {
+                let start = svg.elements.len();
+                let y = baseline - k as f64 * CHORD_STACK;
+                svg.text(x, y, &degree.number.to_string(), NUMBER_SIZE, "normal", svg.palette.note, "middle");
+                if let Some(accidental) = degree.accidental {
+                    render_accidental(svg, accidental, x - DIGIT_HALF_WIDTH - 5.0, y - DIGIT_HEIGHT / 2.0);
+                }
+                for j in 0..degree.octave.max(0) {
+                    let dy = y - DIGIT_HEIGHT - 3.5 - j as f64 * OCTAVE_DOT_GAP;
+                    svg.circle(x, dy, OCTAVE_DOT_RADIUS, svg.palette.note);
+                }
+                // Below the underlines for the bottom number of a chord
+                let below = if k == 0 {
+                    y + UNDERLINE_OFFSET + slot.underlines as f64 * UNDERLINE_GAP + 1.0
+                } else {
+                    y + 4.0
+                };
+                for j in 0..(-degree.octave).max(0) {
+                    svg.circle(x, below + j as f64 * OCTAVE_DOT_GAP, OCTAVE_DOT_RADIUS, svg.palette.note);
+                }
+                svg.wrap(start, &at.note(degree.note, &measure.notes[degree.note]));
+            }
+        }
+    }
+    if slot.dot {
+        svg.circle(x + DIGIT_HALF_WIDTH + 4.0, baseline - 6.0, AUGMENTATION_DOT_RADIUS, svg.palette.note);
+    }
+}
+
+/// Sharp, flat or natural at half the staff size, centered on `y`.
+fn render_accidental(svg: &mut SvgBuilder, accidental: i32, x: f64, y: f64) {
+    let glyphs: &[&str] = match accidental {
+        2 => &[SHARP_GLYPH, SHARP_GLYPH],
+        1 => &[SHARP_GLYPH],
+        0 => &[NATURAL_GLYPH],
+        -1 => &[FLAT_GLYPH],
+        _ => &[FLAT_GLYPH, FLAT_GLYPH],
+    };
+    for (i, glyph) in glyphs.iter().enumerate() {
+        let gx = x - (glyphs.len() - 1 - i) as f64 * 5.0;
+        let path = vexflow_outline_to_svg(glyph, ACCIDENTAL_SCALE, gx - 2.5, y);
+        svg.elements.push(format!(r#"<path d="{}" fill="{}" stroke="none"/>"#, path, svg.palette.note));
+    }
+}
+
+/// One underline per level, continuous across linked slots.
+fn render_underlines(svg: &mut SvgBuilder, slots: &[Slot], xs: &[f64], band: f64) {
+    let max_level = slots.iter().map(|s| s.underlines).max().unwrap_or(0);
+    for level in 1..=max_level {
+        let y = band + NUMBER_BASELINE + UNDERLINE_OFFSET + (level - 1) as f64 * UNDERLINE_GAP;
+        let mut i = 0;
+        while i < slots.len() {
+            if slots[i].underlines < level {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i + 1 < slots.len() && slots[i].link >= level {
+                i += 1;
+            }
+            let end_x = xs[i] + if slots[i].dot { DIGIT_HALF_WIDTH + 6.0 } else { DIGIT_HALF_WIDTH };
+            svg.line(xs[start] - DIGIT_HALF_WIDTH, y, end_x, y, svg.palette.note, UNDERLINE_WIDTH);
+            i += 1;
+        }
+    }
+}
+
+fn render_ties(svg: &mut SvgBuilder, slots: &[Slot], xs: &[f64], band: f64, open: &mut Vec<OpenTie>) {
+    for (slot, &x) in slots.iter().zip(xs) {
+        let SlotKind::Note(ref stack) = slot.kind else { continue };
+        for degree in stack {
+            if degree.tie_stop {
+                if let Some(pos) = open.iter().position(|t| t.midi == degree.midi) {
+                    let tie = open.remove(pos);
+                    render_tie(svg, tie.x, x - DIGIT_HALF_WIDTH, band);
+                }
+            }
+            if degree.tie_start {
+                open.push(OpenTie { midi: degree.midi, x: x + DIGIT_HALF_WIDTH });
+            }
+        }
+    }
+}
+
+/// A tie arc over the numbers, clear of the octave dots.
+fn render_tie(svg: &mut SvgBuilder, x1: f64, x2: f64, band: f64) {
+    if x2 <= x1 {
+        return;
+    }
+    let y = band + NUMBER_BASELINE - DIGIT_HEIGHT - 6.0;
+    let d = format!(
+        "M{:.1},{:.1} Q{:.1},{:.1} {:.1},{:.1}",
+        x1, y, (x1 + x2) / 2.0, y - 7.0, x2, y
+    );
+    svg.path(&d, "none", svg.palette.note, 1.0);
+}
@@ -6,14 +6,33 @@ use super::constants::*;
 use super::lyrics::*;
 use super::beat_map::*;
 use super::staff::is_jump_text;
+use super::diagrams::{has_frames, legend_height, CHORD_DIAGRAM_SPACE};
 
 // ═══════════════════════════════════════════════════════════════════════
 // Layout structures
 // ═══════════════════════════════════════════════════════════════════════
 
+/// How measures are broken into systems.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum SystemBreaks {
+    /// Fill each system to the page width
+    Fit,
+    /// Fill systems, and also start one wherever the score has a
+    /// `new-system` or `new-page` break
+    Encoded,
+    /// Every measure on one system, as wide as the music needs
+    Never,
+}
+
 pub(super) struct ScoreLayout {
     pub(super) systems: Vec<SystemLayout>,
+    /// Width of the drawing: the page width, or the length of the single
+    /// system when breaks are `Never`
+    pub(super) width: f64,
     pub(super) total_height: f64,
+    /// Top of the chord diagram legend, between the header and the
+    /// first system
+    pub(super) legend_top: f64,
 }
 
 /// Info about one part's staves within a system
@@ -33,6 +52,9 @@ pub(super) struct SystemLayout {
     pub(super) show_clef: bool,
     pub(super) show_time: bool,
     pub(super) total_staves: usize,
+    /// The system starts a new page (a `new-page` break in its first
+    /// measure, when breaks are honored)
+    pub(super) new_page: bool,
 }
 
 #[allow(dead_code)]
@@ -43,6 +65,8 @@ pub(super) struct MeasureLayout {
     pub(super) beat_x_map: Vec<(f64, f64)>,
     pub(super) has_key_change: bool,
     pub(super) has_time_change: bool,
+    /// A clef of some staff changes at the start of the measure
+    pub(super) has_clef_change: bool,
     pub(super) prev_key_fifths: Option<i32>,
     pub(super) left_inset: f64,
     pub(super) right_inset: f64,
@@ -91,11 +115,7 @@ pub(super) fn cancellation_natural_count(old_fifths: i32, new_fifths: i32) -> u3
     if same_direction {
         let old_abs = old_fifths.unsigned_abs();
         let new_abs = new_fifths.unsigned_abs();
-        if new_abs >= old_abs {
-            0
-        } else {
-            old_abs - new_abs
-        }
+        old_abs.saturating_sub(new_abs)
     } else {
         old_fifths.unsigned_abs()
     }
@@ -105,7 +125,20 @@ pub(super) fn cancellation_natural_count(old_fifths: i32, new_fifths: i32) -> u3
 // Main layout computation
 // ═══════════════════════════════════════════════════════════════════════
 
-pub(super) fn compute_layout(score: &Score, parts_staves: &[(usize, usize)], page_width: f64) -> ScoreLayout {
+/// Group measures into systems at `page_width` and size them.  With
+/// `Encoded` breaks, measures the score marks `new-system` or `new-page`
+/// start a system even when the previous one has room.  With `Never`,
+/// there is no header and the one system runs past `page_width`, each
+/// measure a little wider than its packing minimum.  `name_room` is
+/// kept free under each system for a row of note names.
+pub(super) fn compute_layout(
+    score: &Score,
+    parts_staves: &[(usize, usize)],
+    page_width: f64,
+    breaks: SystemBreaks,
+    name_room: f64,
+) -> ScoreLayout {
+    let single_line = breaks == SystemBreaks::Never;
     let content_width = page_width - PAGE_MARGIN_LEFT - PAGE_MARGIN_RIGHT;
     let mut systems: Vec<SystemLayout> = Vec::new();
 
@@ -113,12 +146,16 @@ pub(super) fn compute_layout(score: &Score, parts_staves: &[(usize, usize)], pag
     let has_early_chords = score.parts.iter().any(|p| {
         p.measures.iter().take(8).any(|m| !m.harmonies.is_empty())
     });
-    let first_system_top = if has_composer && has_early_chords {
+    let legend_top = if has_composer && has_early_chords {
         FIRST_SYSTEM_TOP + 18.0
     } else {
         FIRST_SYSTEM_TOP
     };
-    let mut current_y = first_system_top;
+    let mut current_y = if single_line {
+        SINGLE_LINE_TOP
+    } else {
+        legend_top + legend_height(score, page_width)
+    };
 
     let ref_part = &score.parts[parts_staves[0].0];
 
@@ -139,14 +176,14 @@ pub(super) fn compute_layout(score: &Score, parts_staves: &[(usize, usize)], pag
         if let Some(ref attrs) = measure.attributes {
             if let Some(ref ts) = attrs.time {
                 let new_beats = ts.beats as f64 * 4.0 / ts.beat_type as f64;
-                if current_time.as_ref().map_or(false, |ct| ct.beats != ts.beats || ct.beat_type != ts.beat_type) {
+                if current_time.as_ref().is_some_and(|ct| ct.beats != ts.beats || ct.beat_type != ts.beat_type) {
                     time_changed = true;
                 }
                 current_beats = new_beats;
                 current_time = Some(ts.clone());
             }
             if let Some(ref k) = attrs.key {
-                if current_key.as_ref().map_or(false, |ck| ck.fifths != k.fifths) {
+                if current_key.as_ref().is_some_and(|ck| ck.fifths != k.fifths) {
                     key_changed = true;
                 }
                 current_key = Some(k.clone());
@@ -165,6 +202,24 @@ pub(super) fn compute_layout(score: &Score, parts_staves: &[(usize, usize)], pag
         }
     }
 
+    // Clef changes in any part, after the first clef of each staff
+    let mut running_clefs: std::collections::HashMap<(usize, i32), &Clef> =
+        std::collections::HashMap::new();
+    let has_clef_change: Vec<bool> = (0..ref_part.measures.len())
+        .map(|mi| {
+            let mut changed = false;
+            for &(pidx, _) in parts_staves {
+                let attrs = score.parts[pidx].measures.get(mi).and_then(|m| m.attributes.as_ref());
+                for clef in attrs.into_iter().flat_map(|a| &a.clefs) {
+                    if let Some(old) = running_clefs.insert((pidx, clef.number), clef) {
+                        changed |= old != clef;
+                    }
+                }
+            }
+            changed
+        })
+        .collect();
+
     let mut lyrics_divs: Vec<i32> = vec![1; score.parts.len()];
 
     let measure_min_widths: Vec<f64> = measure_beats
@@ -195,6 +250,9 @@ pub(super) fn compute_layout(score: &Score, parts_staves: &[(usize, usize)], pag
                 w += new_width;
             }
             if has_time_change[mi] { w += TIME_SIG_SPACE; }
+            // Systems on a page share out their width by beats, so only
+            // a single line, sized from these minimums, needs the room
+            if single_line && has_clef_change[mi] { w += CLEF_CHANGE_SPACE; }
 
             let lyrics_w = lyrics_min_measure_width(&score.parts, mi, &lyrics_divs, w);
             if lyrics_w > w {
@@ -221,8 +279,11 @@ pub(super) fn compute_layout(score: &Score, parts_staves: &[(usize, usize)], pag
         let later_prefix = CLEF_SPACE + key_sig_width(key_at_mi);
         let available_later = content_width - later_prefix;
         let available = if is_first_system { available_first } else { available_later };
+        let measure = &ref_part.measures[mi];
+        let forced = breaks == SystemBreaks::Encoded && (measure.new_system || measure.new_page);
+        let full = !single_line && current_width + min_w > available;
 
-        if !current_group.is_empty() && current_width + min_w > available {
+        if !current_group.is_empty() && (forced || full) {
             system_groups.push(current_group);
             current_group = Vec::new();
             current_width = 0.0;
@@ -249,13 +310,19 @@ pub(super) fn compute_layout(score: &Score, parts_staves: &[(usize, usize)], pag
             + if show_time_sig { TIME_SIG_SPACE } else { 0.0 };
 
         let x_start = PAGE_MARGIN_LEFT + prefix_width;
-        let x_end = PAGE_MARGIN_LEFT + content_width;
-        let available = x_end - x_start;
 
+        // A single line takes the room its measures need; a system on a
+        // page shares the page width out in proportion to the beats
         let measure_weights: Vec<f64> = group
             .iter()
-            .map(|&mi| measure_beats[mi])
+            .map(|&mi| if single_line { measure_min_widths[mi] * SINGLE_LINE_STRETCH } else { measure_beats[mi] })
             .collect();
+        let x_end = if single_line {
+            x_start + measure_weights.iter().sum::<f64>()
+        } else {
+            PAGE_MARGIN_LEFT + content_width
+        };
+        let available = x_end - x_start;
 
         let total_weight: f64 = measure_weights.iter().sum();
         let scale = if total_weight > 0.0 {
@@ -280,7 +347,7 @@ pub(super) fn compute_layout(score: &Score, parts_staves: &[(usize, usize)], pag
                         }
                     }
                     let beat_times = compute_note_beat_times(
-                        &part.measures[mi].notes,
+                        &part.measures[mi],
                         divisions_per_part[pidx],
                     );
                     all_beat_times.push(beat_times);
@@ -296,8 +363,12 @@ pub(super) fn compute_layout(score: &Score, parts_staves: &[(usize, usize)], pag
             let is_system_start = j == 0;
             let ml_has_key_change = has_key_change[mi] && !is_system_start;
             let ml_has_time_change = has_time_change[mi] && !is_system_start;
+            let ml_has_clef_change = has_clef_change[mi] && !is_system_start;
 
             let mut left_inset = 14.0;
+            if ml_has_clef_change {
+                left_inset += CLEF_CHANGE_SPACE;
+            }
             if ml_has_key_change {
                 if let Some(pf) = prev_key_fifths {
                     let new_fifths = running_keys[mi].as_ref().map_or(0, |k| k.fifths);
@@ -343,13 +414,7 @@ pub(super) fn compute_layout(score: &Score, parts_staves: &[(usize, usize)], pag
             // so notes are spaced the same as in full measures.
             let total_quarters = if mi < ref_part.measures.len() && ref_part.measures[mi].implicit {
                 let div = divisions_per_part[parts_staves[0].0].max(1);
-                let mut total_divs = 0i32;
-                for note in &ref_part.measures[mi].notes {
-                    if !note.chord && !note.grace {
-                        total_divs += note.duration;
-                    }
-                }
-                let actual = total_divs as f64 / div as f64;
+                let actual = ref_part.measures[mi].content_quarters(div);
                 if actual > 0.0 && actual < nominal_quarters {
                     actual
                 } else {
@@ -367,6 +432,7 @@ pub(super) fn compute_layout(score: &Score, parts_staves: &[(usize, usize)], pag
                 beat_x_map,
                 has_key_change: ml_has_key_change,
                 has_time_change: ml_has_time_change,
+                has_clef_change: ml_has_clef_change,
                 prev_key_fifths,
                 left_inset,
                 right_inset,
@@ -413,7 +479,7 @@ pub(super) fn compute_layout(score: &Score, parts_staves: &[(usize, usize)], pag
                     let count = score.parts[pidx].measures[ml_check2.measure_idx].directions.iter()
                         .filter(|dir| {
                             dir.placement.as_deref() == Some("below")
-                                && dir.words.as_ref().map_or(false, |w| !w.is_empty() && !is_jump_text(w))
+                                && dir.words.as_ref().is_some_and(|w| !w.is_empty() && !is_jump_text(w))
                         })
                         .count();
                     if count > max_below_dir_lines {
@@ -436,6 +502,11 @@ pub(super) fn compute_layout(score: &Score, parts_staves: &[(usize, usize)], pag
             0.0
         };
 
+        // Room above the top staff for chord diagrams
+        if has_frames(ref_part, group) {
+            current_y += CHORD_DIAGRAM_SPACE;
+        }
+
         systems.push(SystemLayout {
             y: current_y,
             x_start,
@@ -445,16 +516,24 @@ pub(super) fn compute_layout(score: &Score, parts_staves: &[(usize, usize)], pag
             show_clef: true,
             show_time: show_time_sig,
             total_staves,
+            new_page: breaks == SystemBreaks::Encoded && sys_idx > 0 && ref_part.measures[first_mi].new_page,
         });
 
         let system_height = y_offset;
-        current_y += system_height + lyrics_extra + SYSTEM_SPACING;
+        current_y += system_height + lyrics_extra + name_room + SYSTEM_SPACING;
     }
 
-    let total_height = current_y + 40.0;
+    let (width, total_height) = match systems.first() {
+        Some(system) if single_line => {
+            (system.x_end + PAGE_MARGIN_RIGHT, current_y - SYSTEM_SPACING + SINGLE_LINE_BOTTOM)
+        }
+        _ => (page_width, current_y + 40.0),
+    };
 
     ScoreLayout {
         systems,
+        width,
         total_height,
+        legend_top,
     }
 }
@@ -4,10 +4,10 @@ use crate::model::*;
 use super::constants::*;
 use super::svg_builder::SvgBuilder;
 use super::beat_map::compute_note_beat_times;
+use super::ids::MeasureRef;
 
 // ── Lyrics constants ────────────────────────────────────────────────
 
-pub(super) const LYRICS_COLOR: &str = "#333333";
 pub(super) const LYRICS_FONT_SIZE: f64 = 13.0;
 pub(super) const LYRICS_PAD_BELOW: f64 = 16.0;
 pub(super) const LYRICS_LINE_HEIGHT: f64 = 16.0;
@@ -51,7 +51,7 @@ pub(super) fn collect_lyric_events(parts: &[Part], mi: usize, divisions_map: &[i
         if mi >= part.measures.len() { continue; }
         let measure = &part.measures[mi];
         let divisions = divisions_map[pidx].max(1);
-        let beat_times = compute_note_beat_times(&measure.notes, divisions);
+        let beat_times = compute_note_beat_times(measure, divisions);
 
         for (i, note) in measure.notes.iter().enumerate() {
             if note.lyrics.is_empty() { continue; }
@@ -175,6 +175,7 @@ pub(super) fn render_lyrics(
     note_positions: &[f64],
     lyrics_base_y: f64,
     staff_filter: Option<i32>,
+    at: MeasureRef,
 ) {
     for (i, note) in measure.notes.iter().enumerate() {
         if let Some(sf) = staff_filter {
@@ -192,14 +193,16 @@ pub(super) fn render_lyrics(
                 _ => lyric.text.clone(),
             };
 
+            let start = svg.elements.len();
             svg.text(
                 nx, ly,
                 &display_text,
                 LYRICS_FONT_SIZE,
                 "normal",
-                LYRICS_COLOR,
+                svg.palette.lyrics,
                 "middle",
             );
+            svg.wrap(start, &at.lyric(i, lyric.number));
         }
     }
 }
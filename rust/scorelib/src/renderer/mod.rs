@@ -1,8 +1,11 @@
-//! Score renderer — converts a parsed Score into SVG output.
+//! Score renderer — converts a parsed Score into SVG, PDF or PNG output.
 //!
 //! The renderer computes its own layout from the musical content (pitch,
 //! duration, time signature) and produces a self-contained SVG string
-//! that can be displayed in any SVG-capable view.
+//! that can be displayed in any SVG-capable view, one SVG per printed
+//! page, a paginated PDF for printing, or a PNG image for thumbnails and
+//! sharing.  A second SVG mode draws the score in jianpu (numbered
+//! notation), and a third on one endless system for horizontal scrolling.
 
 mod constants;
 mod glyphs;
@@ -11,8 +14,24 @@ mod beat_map;
 mod lyrics;
 mod slurs;
 mod notes;
+mod articulations;
 mod staff;
 mod layout;
+mod wedges;
+mod ties;
+mod scene;
+mod pages;
+mod pdf;
+mod faces;
+mod truetype;
+mod raster;
+mod jianpu;
+mod tab;
+mod diagrams;
+mod paged;
+mod ids;
+mod options;
+mod teaching;
 
 use crate::model::*;
 use constants::*;
@@ -20,9 +39,26 @@ use svg_builder::{SvgBuilder, empty_svg};
 use beat_map::note_x_positions_from_beat_map;
 use lyrics::*;
 use slurs::SlurStart;
-use notes::render_notes;
+use wedges::WedgeStart;
+use ties::TieStart;
+use notes::{render_notes, TupletStart};
 use staff::*;
 use layout::*;
+use ids::MeasureRef;
+
+pub use pages::{PageOptions, PageSize};
+pub use paged::{compute_paged_measure_positions, render_score_to_svg_pages};
+pub use pdf::render_score_to_pdf;
+pub use raster::{render_score_to_png, RasterOptions, RasterRegion};
+pub use jianpu::{compute_jianpu_measure_positions, render_score_to_jianpu_svg};
+pub use ids::note_id;
+pub use options::{FontFamily, LabelPosition, NoteNames, RenderOptions, Theme};
+pub use teaching::note_name;
+pub(crate) use options::render_options_from_json;
+
+/// Where a measure is drawn: its index, x, width, system index and the
+/// `(beat, x)` pairs of its onsets.
+pub type MeasurePlacement = (usize, f64, f64, usize, Vec<(f64, f64)>);
 
 // ═══════════════════════════════════════════════════════════════════════
 // Helpers
@@ -51,6 +87,21 @@ fn octave_shift_amount(size: i32) -> i32 {
 /// to use the default (820). On phones, pass the screen width in points so the
 /// renderer fits fewer measures per system and keeps notes readable.
 pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
+    render_score_to_svg_with_options(score, page_width, &RenderOptions::default())
+}
+
+/// Render a parsed Score into a complete SVG string in the theme, staff
+/// size and fonts of `options`, leaving out the chord symbols, lyrics
+/// and measure numbers it switches off.
+///
+/// `page_width` is as for `render_score_to_svg`.  A staff scale above 1
+/// draws everything larger within the same width, with fewer measures on
+/// each system.
+pub fn render_score_to_svg_with_options(
+    score: &Score,
+    page_width: Option<f64>,
+    options: &RenderOptions,
+) -> String {
     let page_width = match page_width {
         Some(w) if w > 0.0 => w,
         _ => DEFAULT_PAGE_WIDTH,
@@ -60,6 +111,49 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
         return empty_svg("No parts in score");
     }
 
+    draw_score(score, page_width, 0, SystemBreaks::Fit, options).svg.build()
+}
+
+/// Render a parsed Score as one endless system for continuous scrolling.
+///
+/// Every measure sits on a single line as wide as the music needs, so
+/// the SVG is as tall as one system and grows in width with the score.
+/// The clef, key and time signature open the line and are drawn again
+/// only where they change.  There is no title header.  `options` are as
+/// for `render_score_to_svg_with_options`.
+pub fn render_score_to_horizontal_svg(score: &Score, options: &RenderOptions) -> String {
+    if score.parts.is_empty() {
+        return empty_svg("No parts in score");
+    }
+
+    draw_score(score, DEFAULT_PAGE_WIDTH, 0, SystemBreaks::Never, options).svg.build()
+}
+
+/// A score drawn at one page width, before it is serialized.
+struct Drawing {
+    svg: SvgBuilder,
+    layout: ScoreLayout,
+    /// Index of the first element of each system in `svg.elements`;
+    /// everything before the first one is the background and header.
+    system_starts: Vec<usize>,
+}
+
+/// Lay out and draw every system of a score that has at least one part.
+/// `measure_offset` is the number of measures before the score's first
+/// one when it is an excerpt, so systems keep their original numbering.
+/// `breaks` chooses how measures are broken into systems, and `options`
+/// the theme, fonts, staff scale and what is drawn; a scaled drawing is
+/// laid out at `page_width` divided by the scale and enlarged when built.
+fn draw_score(
+    score: &Score,
+    page_width: f64,
+    measure_offset: usize,
+    breaks: SystemBreaks,
+    options: &RenderOptions,
+) -> Drawing {
+    let score = &*options.visible(score);
+    let page_width = page_width / options.scale();
+
     // Determine staves per part
     let parts_staves: Vec<(usize, usize)> = score
         .parts
@@ -68,15 +162,19 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
         .map(|(i, part)| (i, detect_staves(part)))
         .collect();
 
-    let layout = compute_layout(score, &parts_staves, page_width);
+    let name_room = if options.names_below() { teaching::NAME_ROOM } else { 0.0 };
+    let layout = compute_layout(score, &parts_staves, page_width, breaks, name_room);
 
-    let mut svg = SvgBuilder::new(page_width, layout.total_height);
+    let mut svg = SvgBuilder::with_options(layout.width, layout.total_height, options);
 
     // Background
-    svg.rect(0.0, 0.0, page_width, layout.total_height, "white", "none", 0.0);
+    svg.rect(0.0, 0.0, layout.width, layout.total_height, svg.palette.background, "none", 0.0);
 
-    // Title and composer
-    render_header(&mut svg, score, page_width);
+    // Title and composer, then the chord diagram legend
+    if breaks != SystemBreaks::Never {
+        render_header(&mut svg, score, page_width);
+        diagrams::render_legend(&mut svg, score, page_width, layout.legend_top);
+    }
 
     // Running attributes per part — (clefs vec indexed 1-based, key, time, divisions, transpose)
     struct PartState {
@@ -87,6 +185,8 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
         transpose_octave: i32,
         /// Active octave-shift display offset (e.g. -1 for 8va, +1 for 8vb)
         octave_shift: i32,
+        /// Line count of each tab staff, indexed like `clefs`
+        tab_lines: Vec<Option<usize>>,
     }
 
     let mut part_states: Vec<PartState> = parts_staves
@@ -134,7 +234,8 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
                 });
             }
 
-            PartState { clefs, key, time, divisions, transpose_octave, octave_shift: 0 }
+            let tab_lines = tab::tab_line_counts(part, ns);
+            PartState { clefs, key, time, divisions, transpose_octave, octave_shift: 0, tab_lines }
         })
         .collect();
 
@@ -142,9 +243,46 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
     let mut global_open_slurs: std::collections::HashMap<(usize, usize, i32), SlurStart> =
         std::collections::HashMap::new();
 
+    // Open hairpins, keyed by (part_idx, staff_num) then wedge number.
+    // Unlike slurs their y only depends on the staff and the measures
+    // they span, so they are kept in one map and just moved to the start
+    // of each new system.
+    let mut open_wedges: std::collections::HashMap<(usize, usize), std::collections::HashMap<i32, WedgeStart>> =
+        std::collections::HashMap::new();
+
+    // Open ties, keyed by (part_idx, staff_num) then MIDI pitch.  Their y
+    // is stored relative to the staff, so like hairpins they only need a
+    // new start x on each system.
+    let mut open_ties: std::collections::HashMap<(usize, usize), std::collections::HashMap<i32, TieStart>> =
+        std::collections::HashMap::new();
+
+    // Open tuplets, keyed by (part_idx, staff_num) then voice and tuplet
+    // number, carried the same way as ties.
+    let mut open_tuplets: std::collections::HashMap<(usize, usize), std::collections::HashMap<(i32, i32), TupletStart>> =
+        std::collections::HashMap::new();
+
     // Render each system
+    let mut system_starts = Vec::with_capacity(layout.systems.len());
     for system in &layout.systems {
         let system_y = system.y;
+        system_starts.push(svg.elements.len());
+
+        if let Some(first_ml) = system.measures.first() {
+            for start in open_wedges.values_mut().flat_map(|w| w.values_mut()) {
+                start.x = first_ml.x + first_ml.left_inset - NOTEHEAD_RX;
+                start.continued = true;
+                start.below_y = f64::MIN;
+            }
+            // Continued ties start a little ahead of the first note
+            let tie_x = (first_ml.x + first_ml.left_inset - 3.0 * NOTEHEAD_RX).max(first_ml.x);
+            for start in open_ties.values_mut().flat_map(|t| t.values_mut()) {
+                start.x = tie_x;
+            }
+            for start in open_tuplets.values_mut().flat_map(|t| t.values_mut()) {
+                start.x = first_ml.x + first_ml.left_inset - NOTEHEAD_RX;
+                start.continued = true;
+            }
+        }
 
         // Pre-update part states from the first measure of this system
         if let Some(first_ml) = system.measures.first() {
@@ -199,16 +337,22 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
                     + part_info.y_offset
                     + (staff_num as f64 - 1.0) * (STAFF_HEIGHT + GRAND_STAFF_GAP);
 
-                render_staff_lines(&mut svg, PAGE_MARGIN_LEFT, system.x_end, staff_y);
-
-                if system.show_clef {
-                    if let Some(ref clef) = ps.clefs[staff_num] {
-                        render_clef(&mut svg, PAGE_MARGIN_LEFT + 5.0, staff_y, clef);
+                let key_x = PAGE_MARGIN_LEFT + CLEF_SPACE;
+                if let Some(lines) = ps.tab_lines[staff_num] {
+                    tab::render_tab_lines(&mut svg, PAGE_MARGIN_LEFT, system.x_end, staff_y, lines);
+                    if system.show_clef {
+                        tab::render_tab_clef(&mut svg, PAGE_MARGIN_LEFT + 5.0, staff_y);
+                    }
+                } else {
+                    render_staff_lines(&mut svg, PAGE_MARGIN_LEFT, system.x_end, staff_y);
+                    if system.show_clef {
+                        if let Some(ref clef) = ps.clefs[staff_num] {
+                            render_clef(&mut svg, PAGE_MARGIN_LEFT + 5.0, staff_y, clef);
+                        }
                     }
                 }
 
-                let key_x = PAGE_MARGIN_LEFT + CLEF_SPACE;
-                if let Some(ref key) = ps.key {
+                if let (Some(ref key), None) = (&ps.key, ps.tab_lines[staff_num]) {
                     render_key_signature(
                         &mut svg, key_x, staff_y, key,
                         ps.clefs[staff_num].as_ref(),
@@ -223,8 +367,8 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
                 }
             }
 
-            // Brace for multi-staff parts
-            if part_info.num_staves > 1 {
+            // Brace for multi-staff parts (notation and tab are not braced)
+            if part_info.num_staves > 1 && ps.tab_lines.iter().all(Option::is_none) {
                 let top_y = system_y + part_info.y_offset;
                 let bottom_y = top_y
                     + (part_info.num_staves as f64 - 1.0) * (STAFF_HEIGHT + GRAND_STAFF_GAP)
@@ -245,17 +389,17 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
 
             svg.line(
                 PAGE_MARGIN_LEFT, top_y, PAGE_MARGIN_LEFT, bottom_y,
-                BARLINE_COLOR, BARLINE_WIDTH,
+                svg.palette.barline, BARLINE_WIDTH,
             );
         }
 
         // ── Measure number at the start of each system line ──
         if let Some(first_ml) = system.measures.first() {
-            let measure_num = first_ml.measure_idx + 1;
-            if measure_num > 1 {
+            let measure_num = measure_offset + first_ml.measure_idx + 1;
+            if measure_num > 1 && options.measure_numbers {
                 let first_part = system.parts.first().unwrap();
                 let top_staff_y = system_y + first_part.y_offset;
-                let color = "#555555";
+                let color = svg.palette.muted;
                 svg.elements.push(format!(
                     "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"15\" font-style=\"italic\" fill=\"{}\" text-anchor=\"start\">{}</text>",
                     PAGE_MARGIN_LEFT - 10.0, top_staff_y - 8.0, color, measure_num
@@ -282,11 +426,21 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
                 } else {
                     None
                 };
-                let lowest = measure_lowest_note_y(
-                    measure, staff_y_bottom,
-                    ps.clefs.get(bottom_staff_num).and_then(|c| c.as_ref()),
-                    ps.transpose_octave + ps.octave_shift, staff_filter,
-                );
+                let bottom_clef = ps.clefs.get(bottom_staff_num).and_then(|c| c.as_ref());
+                let transpose = ps.transpose_octave + ps.octave_shift;
+                let lowest = if ps.tab_lines[bottom_staff_num].is_some() {
+                    staff_y_bottom + STAFF_HEIGHT
+                } else if has_dynamics_below(measure, bottom_staff_num) {
+                    // Keep lyrics and words clear of the dynamics row
+                    dynamics_baseline_y(measure, staff_y_bottom, bottom_clef, transpose, staff_filter)
+                } else {
+                    measure_lowest_note_y(measure, staff_y_bottom, bottom_clef, transpose, staff_filter)
+                };
+                let lowest = if options.names_below() && ps.tab_lines[bottom_staff_num].is_none() {
+                    lowest.max(teaching::names_bottom(measure, staff_y_bottom, lowest, staff_filter))
+                } else {
+                    lowest
+                };
                 if lowest > system_lowest_y {
                     system_lowest_y = lowest;
                 }
@@ -301,7 +455,7 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
                     let count = score.parts[pidx].measures[ml_scan.measure_idx].directions.iter()
                         .filter(|dir| {
                             dir.placement.as_deref() == Some("below")
-                                && dir.words.as_ref().map_or(false, |w| !w.is_empty() && !is_jump_text(w))
+                                && dir.words.as_ref().is_some_and(|w| !w.is_empty() && !is_jump_text(w))
                         })
                         .count();
                     if count > max_below_dir_lines {
@@ -377,6 +531,9 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
                     continue;
                 }
                 let measure = &part.measures[ml.measure_idx];
+                let prev_clefs = ml.has_clef_change.then(|| ps.clefs.clone());
+                let at = MeasureRef { part: pidx, measure: measure_offset + ml.measure_idx };
+                let measure_start = svg.elements.len();
 
                 // Update running attributes for this part
                 if let Some(ref attrs) = measure.attributes {
@@ -438,7 +595,16 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
                             }
                         }
                     }
-                    if ml.has_key_change {
+                    let tab_lines = ps.tab_lines[staff_num];
+                    if let (Some(prev), None) = (&prev_clefs, tab_lines) {
+                        if let Some(ref clef) = ps.clefs[staff_num] {
+                            if prev[staff_num].as_ref() != Some(clef) {
+                                render_clef(&mut svg, inline_x - 4.0, staff_y, clef);
+                            }
+                        }
+                        inline_x += CLEF_CHANGE_SPACE;
+                    }
+                    if ml.has_key_change && tab_lines.is_none() {
                         if let Some(prev_fifths) = ml.prev_key_fifths {
                             let new_fifths = ps.key.as_ref().map_or(0, |k| k.fifths);
                             let num_naturals = cancellation_natural_count(prev_fifths, new_fifths) as usize;
@@ -448,8 +614,8 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
                                 } else {
                                     flat_positions(ps.clefs[staff_num].as_ref())
                                 };
-                                for i in 0..num_naturals.min(positions.len()) {
-                                    let ny = staff_y + positions[i] as f64 * 5.0;
+                                for &pos in positions.iter().take(num_naturals) {
+                                    let ny = staff_y + pos as f64 * 5.0;
                                     render_natural_sign(&mut svg, inline_x, ny);
                                     inline_x += KEY_SIG_NATURAL_SPACE;
                                 }
@@ -501,7 +667,7 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
 
                     // Chord symbols (only on top staff of first part)
                     if staff_num == 1 && pidx == parts_staves[0].0 {
-                        render_harmonies(&mut svg, measure, mx, mw, staff_y);
+                        render_harmonies(&mut svg, measure, ps.divisions, &ml.beat_x_map, mx, mw, staff_y, at);
                     }
 
                     // Notes and rests for this staff
@@ -513,24 +679,28 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
 
                     let effective_transpose = ps.transpose_octave + ps.octave_shift;
 
-                    render_notes(
-                        &mut svg,
-                        measure,
-                        staff_y,
-                        ps.clefs[staff_num].as_ref(),
-                        ps.divisions,
-                        effective_transpose,
-                        staff_filter,
-                        &ml.beat_x_map,
-                        mx, mw,
-                    );
+                    if let Some(lines) = tab_lines {
+                        tab::render_tab_notes(
+                            &mut svg, measure, staff_y, lines, ps.divisions,
+                            staff_filter, &ml.beat_x_map, at,
+                        );
+                    } else {
+                        // Dynamics (every part and staff)
+                        let dyn_below_y = dynamics_baseline_y(
+                            measure, staff_y, ps.clefs[staff_num].as_ref(),
+                            effective_transpose, staff_filter,
+                        );
+                        render_dynamics(
+                            &mut svg, measure, staff_num, ps.divisions, &ml.beat_x_map,
+                            mx, staff_y, dyn_below_y,
+                        );
+                        wedges::collect_and_render_wedges_for_measure(
+                            &mut svg, measure, staff_num, ps.divisions, &ml.beat_x_map,
+                            mx, mw, staff_y, dyn_below_y,
+                            open_wedges.entry((pidx, staff_num)).or_default(),
+                        );
 
-                    // Slurs
-                    {
-                        let staff_slurs = system_open_slurs
-                            .entry((pidx, staff_num))
-                            .or_insert_with(std::collections::HashMap::new);
-                        slurs::collect_and_render_slurs_for_measure(
+                        render_notes(
                             &mut svg,
                             measure,
                             staff_y,
@@ -539,23 +709,73 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
                             effective_transpose,
                             staff_filter,
                             &ml.beat_x_map,
-                            staff_slurs,
+                            mx, mw,
+                            at,
+                            ps.key.as_ref().map_or(0, |k| k.fifths),
+                            options,
+                        );
+                        notes::collect_and_render_tuplets_for_measure(
+                            &mut svg,
+                            measure,
+                            staff_y,
+                            ps.clefs[staff_num].as_ref(),
+                            ps.divisions,
+                            effective_transpose,
+                            staff_filter,
+                            &ml.beat_x_map,
+                            open_tuplets.entry((pidx, staff_num)).or_default(),
+                        );
+
+                        // Slurs
+                        {
+                            let staff_slurs = system_open_slurs
+                                .entry((pidx, staff_num))
+                                .or_default();
+                            slurs::collect_and_render_slurs_for_measure(
+                                &mut svg,
+                                measure,
+                                staff_y,
+                                ps.clefs[staff_num].as_ref(),
+                                ps.divisions,
+                                effective_transpose,
+                                staff_filter,
+                                &ml.beat_x_map,
+                                staff_slurs,
+                            );
+                        }
+
+                        // Ties
+                        ties::collect_and_render_ties_for_measure(
+                            &mut svg,
+                            measure,
+                            ml.measure_idx,
+                            staff_y,
+                            ps.clefs[staff_num].as_ref(),
+                            ps.divisions,
+                            effective_transpose,
+                            staff_filter,
+                            &ml.beat_x_map,
+                            open_ties.entry((pidx, staff_num)).or_default(),
                         );
                     }
 
                     // Barlines (per-staff)
                     if staff_num == 1 {
-                        render_barlines(&mut svg, measure, mx, mw, staff_y);
+                        render_barlines(&mut svg, &measure.barlines, mx, mw, staff_y);
                     }
 
-                    // Lyrics (render on bottom staff only)
+                    // Lyrics (render on bottom staff only, taking the
+                    // words from the notation staff above a tab staff)
                     if staff_num == part_info.num_staves {
                         let note_xs = note_x_positions_from_beat_map(
-                            &measure.notes, ps.divisions, &ml.beat_x_map,
+                            measure, ps.divisions, &ml.beat_x_map,
                         );
+                        let lyric_filter = staff_filter.map(|sf| {
+                            (1..=sf).rev().find(|&s| ps.tab_lines[s as usize].is_none()).unwrap_or(sf)
+                        });
                         render_lyrics(
                             &mut svg, measure, &note_xs,
-                            lyrics_base_y, staff_filter,
+                            lyrics_base_y, lyric_filter, at,
                         );
                     }
                 }
@@ -567,10 +787,11 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
                         ps.octave_shift = 0;
                     }
                 }
+                svg.wrap(measure_start, &at.attributes());
             }
 
             // Right barline spanning all staves across all parts.
-            let has_special_right_barline = system.parts.first().map_or(false, |pi| {
+            let has_special_right_barline = system.parts.first().is_some_and(|pi| {
                 let pidx = pi.part_idx;
                 if ml.measure_idx < score.parts[pidx].measures.len() {
                     score.parts[pidx].measures[ml.measure_idx].barlines.iter().any(|b| {
@@ -590,7 +811,29 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
                     + last_part.y_offset
                     + (last_part.num_staves as f64 - 1.0) * (STAFF_HEIGHT + GRAND_STAFF_GAP)
                     + STAFF_HEIGHT;
-                svg.line(mx + mw, top_y, mx + mw, bottom_y, BARLINE_COLOR, BARLINE_WIDTH);
+                svg.line(mx + mw, top_y, mx + mw, bottom_y, svg.palette.barline, BARLINE_WIDTH);
+            }
+        }
+
+        // ── End-of-system hairpin, tie and tuplet handling ──
+        for part_info in &system.parts {
+            for staff_num in 1..=part_info.num_staves {
+                let staff_y = system_y
+                    + part_info.y_offset
+                    + (staff_num as f64 - 1.0) * (STAFF_HEIGHT + GRAND_STAFF_GAP);
+                if let Some(staff_wedges) = open_wedges.get(&(part_info.part_idx, staff_num)) {
+                    wedges::render_open_wedge_continuations(&mut svg, staff_wedges, system.x_end, staff_y);
+                }
+                if let (Some(staff_ties), Some(last_ml)) =
+                    (open_ties.get_mut(&(part_info.part_idx, staff_num)), system.measures.last())
+                {
+                    ties::render_open_tie_continuations(
+                        &mut svg, staff_ties, last_ml.measure_idx, system.x_end, staff_y,
+                    );
+                }
+                if let Some(staff_tuplets) = open_tuplets.get(&(part_info.part_idx, staff_num)) {
+                    notes::render_open_tuplet_continuations(&mut svg, staff_tuplets, system.x_end, staff_y);
+                }
             }
         }
 
@@ -605,7 +848,7 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
         }
     }
 
-    svg.build()
+    Drawing { svg, layout, system_starts }
 }
 
 // ═══════════════════════════════════════════════════════════════════════
@@ -624,12 +867,70 @@ pub fn render_score_to_svg(score: &Score, page_width: Option<f64>) -> String {
 pub fn compute_measure_positions(
     score: &Score,
     page_width: Option<f64>,
-) -> (Vec<(usize, f64, f64, usize, Vec<(f64, f64)>)>, Vec<(f64, f64)>) {
+) -> (Vec<MeasurePlacement>, Vec<(f64, f64)>) {
     let page_width = match page_width {
         Some(w) if w > 0.0 => w,
         _ => DEFAULT_PAGE_WIDTH,
     };
+    positions_at(score, page_width, SystemBreaks::Fit, 0.0)
+}
+
+/// The measure and system positions of `render_score_to_svg_with_options`,
+/// as returned by `compute_measure_positions`, in the coordinates of the
+/// scaled SVG.
+pub fn compute_measure_positions_with_options(
+    score: &Score,
+    page_width: Option<f64>,
+    options: &RenderOptions,
+) -> (Vec<MeasurePlacement>, Vec<(f64, f64)>) {
+    let page_width = match page_width {
+        Some(w) if w > 0.0 => w,
+        _ => DEFAULT_PAGE_WIDTH,
+    };
+    scaled_positions_at(score, page_width, SystemBreaks::Fit, options)
+}
+
+/// The measure and system positions of `render_score_to_horizontal_svg`,
+/// as returned by `compute_measure_positions`: one system, with the x of
+/// every measure along the line.
+pub fn compute_horizontal_measure_positions(
+    score: &Score,
+    options: &RenderOptions,
+) -> (Vec<MeasurePlacement>, Vec<(f64, f64)>) {
+    scaled_positions_at(score, DEFAULT_PAGE_WIDTH, SystemBreaks::Never, options)
+}
+
+/// `positions_at` for a drawing in `options`, in the coordinates of the
+/// scaled SVG.
+fn scaled_positions_at(
+    score: &Score,
+    page_width: f64,
+    breaks: SystemBreaks,
+    options: &RenderOptions,
+) -> (Vec<MeasurePlacement>, Vec<(f64, f64)>) {
+    let scale = options.scale();
+    let name_room = if options.names_below() { teaching::NAME_ROOM } else { 0.0 };
+    let (mut measures, mut systems) = positions_at(&options.visible(score), page_width / scale, breaks, name_room);
+    for (_, x, width, _, beat_x_map) in &mut measures {
+        *x *= scale;
+        *width *= scale;
+        for (_, bx) in beat_x_map {
+            *bx *= scale;
+        }
+    }
+    for (y, height) in &mut systems {
+        *y *= scale;
+        *height *= scale;
+    }
+    (measures, systems)
+}
 
+fn positions_at(
+    score: &Score,
+    page_width: f64,
+    breaks: SystemBreaks,
+    name_room: f64,
+) -> (Vec<MeasurePlacement>, Vec<(f64, f64)>) {
     if score.parts.is_empty() {
         return (Vec::new(), Vec::new());
     }
@@ -641,23 +942,35 @@ pub fn compute_measure_positions(
         .map(|(i, part)| (i, detect_staves(part)))
         .collect();
 
-    let layout = compute_layout(score, &parts_staves, page_width);
+    let layout = compute_layout(score, &parts_staves, page_width, breaks, name_room);
+
+    layout_positions(&layout, staves_height)
+}
+
+/// Height of a system's staves, top line to bottom line.
+fn staves_height(system: &SystemLayout) -> f64 {
+    let mut height = 0.0;
+    for (i, pi) in system.parts.iter().enumerate() {
+        height += STAFF_HEIGHT
+            + (pi.num_staves as f64 - 1.0) * (STAFF_HEIGHT + GRAND_STAFF_GAP);
+        if i < system.parts.len() - 1 {
+            height += PART_GAP;
+        }
+    }
+    height
+}
 
+/// Flatten a layout into the measure and system positions returned by
+/// `compute_measure_positions`, given the height of each system.
+fn layout_positions(
+    layout: &ScoreLayout,
+    mut system_height: impl FnMut(&SystemLayout) -> f64,
+) -> (Vec<MeasurePlacement>, Vec<(f64, f64)>) {
     let mut measure_positions = Vec::new();
     let mut system_positions = Vec::new();
 
     for (sys_idx, system) in layout.systems.iter().enumerate() {
-        let mut y_offset = 0.0;
-        for (i, pi) in system.parts.iter().enumerate() {
-            let part_height = STAFF_HEIGHT
-                + (pi.num_staves as f64 - 1.0) * (STAFF_HEIGHT + GRAND_STAFF_GAP);
-            y_offset += part_height;
-            if i < system.parts.len() - 1 {
-                y_offset += PART_GAP;
-            }
-        }
-
-        system_positions.push((system.y, y_offset));
+        system_positions.push((system.y, system_height(system)));
 
         for ml in &system.measures {
             measure_positions.push((
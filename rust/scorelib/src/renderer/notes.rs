@@ -5,6 +5,10 @@ use super::constants::*;
 use super::glyphs::*;
 use super::svg_builder::{SvgBuilder, vexflow_outline_to_svg, vf_outline_to_svg};
 use super::beat_map::{pitch_to_staff_y, is_filled_note, note_x_positions_from_beat_map};
+use super::articulations::render_articulations;
+use super::ids::MeasureRef;
+use super::options::RenderOptions;
+use super::teaching::{notehead_color, render_fingerings, render_note_name};
 
 // ── Grace note constants ─────────────────────────────────────────────
 const GRACE_SCALE: f64 = 0.66;
@@ -14,6 +18,12 @@ const GRACE_STEM_LENGTH: f64 = STEM_LENGTH * GRACE_SCALE;
 const GRACE_STEM_WIDTH: f64 = STEM_WIDTH * 0.85;
 const GRACE_FLAG_GLYPH_SCALE: f64 = FLAG_GLYPH_SCALE * GRACE_SCALE;
 
+// ── Tuplet constants ────────────────────────────────────────────────
+const TUPLET_GAP: f64 = 8.0; // clearance between notes/stems and the bracket
+const TUPLET_HOOK: f64 = 5.0; // length of the bracket end hooks
+const TUPLET_FONT_SIZE: f64 = 11.0;
+
+#[allow(clippy::too_many_arguments)]
 pub(super) fn render_notes(
     svg: &mut SvgBuilder,
     measure: &Measure,
@@ -25,12 +35,15 @@ pub(super) fn render_notes(
     beat_x_map: &[(f64, f64)],
     measure_x: f64,
     measure_w: f64,
+    at: MeasureRef,
+    fifths: i32,
+    options: &RenderOptions,
 ) {
     if measure.notes.is_empty() {
         return;
     }
 
-    let note_positions = note_x_positions_from_beat_map(&measure.notes, divisions, beat_x_map);
+    let note_positions = note_x_positions_from_beat_map(measure, divisions, beat_x_map);
     let measure_center_x = measure_x + measure_w / 2.0;
 
     let staff_note_count = measure.notes.iter().filter(|n| {
@@ -51,6 +64,8 @@ pub(super) fn render_notes(
         });
 
     let beam_groups = find_beam_groups(measure, staff_filter);
+    // Stem direction used for each drawn stem (for articulation placement)
+    let mut stem_dirs: Vec<Option<bool>> = vec![None; measure.notes.len()];
 
     for (i, note) in measure.notes.iter().enumerate() {
         if let Some(sf) = staff_filter {
@@ -59,6 +74,7 @@ pub(super) fn render_notes(
         }
 
         let nx = note_positions[i];
+        let start = svg.elements.len();
 
         if note.rest {
             let rest_x = if note.measure_rest || note.note_type.is_none() || is_solo_rest {
@@ -67,14 +83,17 @@ pub(super) fn render_notes(
                 nx
             };
             render_rest(svg, rest_x, staff_y, note.note_type.as_deref(), note.measure_rest);
+            svg.wrap(start, &at.note(i, note));
             continue;
         }
 
         if note.grace {
             if let Some(ref pitch) = note.pitch {
                 let note_y = staff_y + pitch_to_staff_y(pitch, clef, transpose_octave);
-                render_grace_note(svg, note, nx, note_y, staff_y);
+                let color = notehead_color(svg, pitch, options);
+                render_grace_note(svg, note, nx, note_y, staff_y, color);
             }
+            svg.wrap(start, &at.note(i, note));
             continue;
         }
 
@@ -85,10 +104,20 @@ pub(super) fn render_notes(
 
             let filled = is_filled_note(note.note_type.as_deref());
             let is_whole = note.note_type.as_deref() == Some("whole");
-            svg.notehead(nx, note_y, filled, is_whole);
+            match note.notehead.as_deref() {
+                Some("slash") => svg.slash_notehead(nx, note_y, filled),
+                _ => {
+                    let color = notehead_color(svg, pitch, options);
+                    svg.notehead(nx, note_y, filled, is_whole, color);
+                    render_note_name(
+                        svg, measure, i, nx, note_y, filled, staff_y, clef, transpose_octave,
+                        fifths, options,
+                    );
+                }
+            }
 
             if note.dot {
-                svg.circle(nx + NOTEHEAD_RX + 4.0, note_y - 1.5, 1.8, NOTE_COLOR);
+                svg.circle(nx + NOTEHEAD_RX + 4.0, note_y - 1.5, 1.8, svg.palette.note);
             }
 
             if let Some(ref acc) = note.accidental {
@@ -98,7 +127,7 @@ pub(super) fn render_notes(
             // Draw stem only for principal notes (not chord notes).
             // For chords, the principal note draws a stem that spans
             // from the outermost notehead to the standard stem length.
-            if !is_whole && !note.chord {
+            if !is_whole && !note.chord && note.stem.as_deref() != Some("none") {
                 let in_beam = note.beams.iter().any(|b|
                     b.beam_type == "begin" || b.beam_type == "continue" || b.beam_type == "end");
 
@@ -108,6 +137,7 @@ pub(super) fn render_notes(
                         Some("down") => false,
                         _ => note_y >= staff_y + 20.0,
                     };
+                    stem_dirs[i] = Some(stem_up);
 
                     // Find the y-range of all chord notes that follow this principal note
                     let mut min_y = note_y; // topmost (smallest y)
@@ -148,7 +178,7 @@ pub(super) fn render_notes(
                         // Stem goes down: start from topmost note, end below bottommost
                         (nx - NOTEHEAD_RX + 1.0, min_y, max_y + stem_len)
                     };
-                    svg.line(sx, sy1, sx, sy2, NOTE_COLOR, STEM_WIDTH);
+                    svg.line(sx, sy1, sx, sy2, svg.palette.note, STEM_WIDTH);
 
                     if flag_count > 0 {
                         render_flags(svg, sx, sy2, flag_count, stem_up);
@@ -156,10 +186,23 @@ pub(super) fn render_notes(
                 }
             }
         }
+        svg.wrap(start, &at.note(i, note));
     }
 
     for group in &beam_groups {
-        render_beam_group(svg, measure, &note_positions, staff_y, clef, transpose_octave, group);
+        let stem_up = render_beam_group(svg, measure, &note_positions, staff_y, clef, transpose_octave, group);
+        for &idx in group {
+            stem_dirs[idx] = stem_up;
+        }
+    }
+
+    render_articulations(
+        svg, measure, &note_positions, &stem_dirs, staff_y, clef, transpose_octave, staff_filter,
+    );
+    if options.finger_numbers {
+        render_fingerings(
+            svg, measure, &note_positions, &stem_dirs, staff_y, clef, transpose_octave, staff_filter,
+        );
     }
 }
 
@@ -171,13 +214,14 @@ fn render_grace_note(
     nx: f64,
     note_y: f64,
     staff_y: f64,
+    color: &str,
 ) {
     let rx = GRACE_NOTEHEAD_RX;
     let ry = GRACE_NOTEHEAD_RY;
 
     svg.elements.push(format!(
         r#"<ellipse cx="{:.1}" cy="{:.1}" rx="{:.1}" ry="{:.1}" fill="{}" stroke="none" stroke-width="0" transform="rotate(-15,{:.1},{:.1})"/>"#,
-        nx, note_y, rx, ry, NOTE_COLOR, nx, note_y
+        nx, note_y, rx, ry, color, nx, note_y
     ));
 
     if let Some(ref acc) = note.accidental {
@@ -211,7 +255,7 @@ fn render_grace_note(
     } else {
         (nx - rx + 0.5, note_y, note_y + stem_len)
     };
-    svg.line(sx, sy1, sx, sy2, NOTE_COLOR, GRACE_STEM_WIDTH);
+    svg.line(sx, sy1, sx, sy2, svg.palette.note, GRACE_STEM_WIDTH);
 
     if flag_count > 0 {
         render_grace_flags(svg, sx, sy2, flag_count, stem_up);
@@ -226,7 +270,7 @@ fn render_grace_note(
         };
         let (x1, y1) = (sx - slash_len * 0.5, slash_mid_y + slash_len * 0.4);
         let (x2, y2) = (sx + slash_len * 0.5, slash_mid_y - slash_len * 0.4);
-        svg.line(x1, y1, x2, y2, NOTE_COLOR, 1.0);
+        svg.line(x1, y1, x2, y2, svg.palette.note, 1.0);
     }
 }
 
@@ -245,23 +289,23 @@ fn render_grace_flags(svg: &mut SvgBuilder, stem_x: f64, stem_end_y: f64, count:
 
     let s = GRACE_FLAG_GLYPH_SCALE;
     let path = vexflow_outline_to_svg(outline, s, stem_x, stem_end_y);
-    svg.path(&path, NOTE_COLOR, NOTE_COLOR, 0.2);
+    svg.path(&path, svg.palette.note, svg.palette.note, 0.2);
 }
 
 // ── Rest rendering ──────────────────────────────────────────────────
 
 fn render_rest(svg: &mut SvgBuilder, x: f64, staff_y: f64, note_type: Option<&str>, measure_rest: bool) {
     if measure_rest || note_type.is_none() {
-        svg.rect(x - 7.0, staff_y + 10.0, 14.0, 5.0, REST_COLOR, "none", 0.0);
+        svg.rect(x - 7.0, staff_y + 10.0, 14.0, 5.0, svg.palette.rest, "none", 0.0);
         return;
     }
 
     match note_type.unwrap() {
         "whole" => {
-            svg.rect(x - 7.0, staff_y + 10.0, 14.0, 5.0, REST_COLOR, "none", 0.0);
+            svg.rect(x - 7.0, staff_y + 10.0, 14.0, 5.0, svg.palette.rest, "none", 0.0);
         }
         "half" => {
-            svg.rect(x - 7.0, staff_y + 15.0, 14.0, 5.0, REST_COLOR, "none", 0.0);
+            svg.rect(x - 7.0, staff_y + 15.0, 14.0, 5.0, svg.palette.rest, "none", 0.0);
         }
         "quarter" => {
             let path = vf_outline_to_svg(VF_QUARTER_REST, VF_GLYPH_SCALE);
@@ -269,7 +313,7 @@ fn render_rest(svg: &mut SvgBuilder, x: f64, staff_y: f64, note_type: Option<&st
             let gy = staff_y + 20.0;
             svg.elements.push(format!(
                 r#"<path d="{}" fill="{}" transform="translate({:.1},{:.1})"/>"#,
-                path, REST_COLOR, gx, gy
+                path, svg.palette.rest, gx, gy
             ));
         }
         "eighth" => {
@@ -278,7 +322,7 @@ fn render_rest(svg: &mut SvgBuilder, x: f64, staff_y: f64, note_type: Option<&st
             let gy = staff_y + 20.0;
             svg.elements.push(format!(
                 r#"<path d="{}" fill="{}" transform="translate({:.1},{:.1})"/>"#,
-                path, REST_COLOR, gx, gy
+                path, svg.palette.rest, gx, gy
             ));
         }
         "16th" => {
@@ -287,11 +331,11 @@ fn render_rest(svg: &mut SvgBuilder, x: f64, staff_y: f64, note_type: Option<&st
             let gy = staff_y + 20.0;
             svg.elements.push(format!(
                 r#"<path d="{}" fill="{}" transform="translate({:.1},{:.1})"/>"#,
-                path, REST_COLOR, gx, gy
+                path, svg.palette.rest, gx, gy
             ));
         }
         _ => {
-            svg.rect(x - 7.0, staff_y + 10.0, 14.0, 5.0, REST_COLOR, "none", 0.0);
+            svg.rect(x - 7.0, staff_y + 10.0, 14.0, 5.0, svg.palette.rest, "none", 0.0);
         }
     }
 }
@@ -326,7 +370,7 @@ fn render_flags(svg: &mut SvgBuilder, stem_x: f64, stem_end_y: f64, count: usize
 
     let s = FLAG_GLYPH_SCALE;
     let path = vexflow_outline_to_svg(outline, s, stem_x, stem_end_y);
-    svg.path(&path, NOTE_COLOR, NOTE_COLOR, 0.3);
+    svg.path(&path, svg.palette.note, svg.palette.note, 0.3);
 }
 
 // ── Ledger lines ────────────────────────────────────────────────────
@@ -343,7 +387,7 @@ fn render_ledger_lines(svg: &mut SvgBuilder, x: f64, note_y: f64, staff_y: f64)
                 y,
                 x + NOTEHEAD_RX + LEDGER_LINE_EXTEND,
                 y,
-                STAFF_COLOR, LEDGER_LINE_WIDTH,
+                svg.palette.staff, LEDGER_LINE_WIDTH,
             );
             y -= STAFF_LINE_SPACING;
         }
@@ -357,7 +401,7 @@ fn render_ledger_lines(svg: &mut SvgBuilder, x: f64, note_y: f64, staff_y: f64)
                 y,
                 x + NOTEHEAD_RX + LEDGER_LINE_EXTEND,
                 y,
-                STAFF_COLOR, LEDGER_LINE_WIDTH,
+                svg.palette.staff, LEDGER_LINE_WIDTH,
             );
             y += STAFF_LINE_SPACING;
         }
@@ -397,6 +441,7 @@ fn find_beam_groups(measure: &Measure, staff_filter: Option<i32>) -> Vec<Vec<usi
     groups
 }
 
+/// Draw stems and beams for one beam group; returns the stem direction used.
 fn render_beam_group(
     svg: &mut SvgBuilder,
     measure: &Measure,
@@ -405,9 +450,9 @@ fn render_beam_group(
     clef: Option<&Clef>,
     transpose_octave: i32,
     group: &[usize],
-) {
+) -> Option<bool> {
     if group.len() < 2 {
-        return;
+        return None;
     }
 
     struct BeamNote { x: f64, note_y: f64, min_y: f64, max_y: f64, stem_x: f64 }
@@ -433,7 +478,7 @@ fn render_beam_group(
             notes.push(BeamNote { x: nx, note_y, min_y, max_y, stem_x: 0.0 });
         }
     }
-    if notes.len() < 2 { return; }
+    if notes.len() < 2 { return None; }
 
     let avg_y: f64 = notes.iter().map(|n| n.note_y).sum::<f64>() / notes.len() as f64;
     let middle_line = staff_y + 20.0;
@@ -485,7 +530,7 @@ fn render_beam_group(
         let by = beam_y_adj(n.stem_x);
         // Stem spans from outermost notehead to beam line
         let stem_start = if stem_up { n.max_y } else { n.min_y };
-        svg.line(n.stem_x, stem_start, n.stem_x, by, NOTE_COLOR, STEM_WIDTH);
+        svg.line(n.stem_x, stem_start, n.stem_x, by, svg.palette.note, STEM_WIDTH);
     }
 
     let by_first = beam_y_adj(first_x);
@@ -503,4 +548,166 @@ fn render_beam_group(
             BEAM_THICKNESS,
         );
     }
+    Some(stem_up)
+}
+
+// ── Tuplet rendering ────────────────────────────────────────────────
+
+/// An open tuplet: what its start said and the notes seen so far.  Like
+/// an open tie it carries across barlines and, with a new start x, onto
+/// the next system.  Open tuplets are keyed by voice and tuplet number.
+#[derive(Clone, Debug)]
+pub(super) struct TupletStart {
+    /// Left end of the bracket on the current system.
+    pub(super) x: f64,
+    /// Noteheads relative to the top staff line (the staff middle for
+    /// rests), so the tuplet can move to another system.
+    note_offsets: Vec<f64>,
+    /// Whether every note so far sits under one beam.
+    beamed: bool,
+    label: Option<String>,
+    bracket: Option<bool>,
+    placement: Option<String>,
+    stem: Option<String>,
+    /// Whether the tuplet started on an earlier system.
+    pub(super) continued: bool,
+}
+
+/// Draw the number (and bracket, unless the notes are beamed together)
+/// for every tuplet that stops on this staff within the measure.  Tuplets
+/// still open at the barline stay in `open` for the next measure.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn collect_and_render_tuplets_for_measure(
+    svg: &mut SvgBuilder,
+    measure: &Measure,
+    staff_y: f64,
+    clef: Option<&Clef>,
+    divisions: i32,
+    transpose_octave: i32,
+    staff_filter: Option<i32>,
+    beat_x_map: &[(f64, f64)],
+    open: &mut std::collections::HashMap<(i32, i32), TupletStart>,
+) {
+    if measure.notes.is_empty() {
+        return;
+    }
+    let note_positions = note_x_positions_from_beat_map(measure, divisions, beat_x_map);
+    // One beam can't join notes on both sides of a barline
+    for t in open.values_mut() {
+        t.beamed = false;
+    }
+
+    for (i, note) in measure.notes.iter().enumerate() {
+        if note.grace || staff_filter.is_some_and(|sf| note.staff.unwrap_or(1) != sf) {
+            continue;
+        }
+        let voice = note.voice.unwrap_or(1);
+        // A note without a time modification ends any tuplet its voice left open
+        let Some(tm) = note.time_modification.as_ref() else {
+            open.retain(|&(v, _), _| v != voice);
+            continue;
+        };
+        for t in note.tuplets.iter().filter(|t| t.tuplet_type == "start") {
+            let label = match t.show_number.as_deref() {
+                Some("none") => None,
+                Some("both") => Some(format!("{}:{}", tm.actual_notes, tm.normal_notes)),
+                _ => Some(tm.actual_notes.to_string()),
+            };
+            open.insert((voice, t.number), TupletStart {
+                x: note_positions[i] - NOTEHEAD_RX,
+                note_offsets: Vec::new(),
+                beamed: true,
+                label,
+                bracket: t.bracket,
+                placement: t.placement.clone(),
+                stem: note.stem.clone(),
+                continued: false,
+            });
+        }
+
+        let offset = match note.pitch {
+            Some(ref p) => pitch_to_staff_y(p, clef, transpose_octave),
+            None => 20.0,
+        };
+        let beamed = note.chord || (!note.rest && note.beams.iter().any(|b| b.number == 1));
+        for t in open.iter_mut().filter(|(&(v, _), _)| v == voice).map(|(_, t)| t) {
+            t.note_offsets.push(offset);
+            t.beamed &= beamed;
+        }
+
+        for t in note.tuplets.iter().filter(|t| t.tuplet_type == "stop") {
+            if let Some(start) = open.remove(&(voice, t.number)) {
+                render_tuplet(svg, &start, note_positions[i] + NOTEHEAD_RX, staff_y, true);
+            }
+        }
+    }
+}
+
+/// Draw the bracket of every still-open tuplet up to the system's end.
+/// The number goes on the part that closes the tuplet.
+pub(super) fn render_open_tuplet_continuations(
+    svg: &mut SvgBuilder,
+    open: &std::collections::HashMap<(i32, i32), TupletStart>,
+    system_x_end: f64,
+    staff_y: f64,
+) {
+    for start in open.values() {
+        render_tuplet(svg, start, system_x_end, staff_y, false);
+    }
+}
+
+/// Draw a tuplet from `start` to `x2`, with its number and closing hook
+/// when `closed`, and its opening hook unless it was continued.
+fn render_tuplet(svg: &mut SvgBuilder, start: &TupletStart, x2: f64, staff_y: f64, closed: bool) {
+    if start.note_offsets.is_empty() {
+        return;
+    }
+    let label = if closed { start.label.as_deref() } else { None };
+    let bracket = start.bracket.unwrap_or(!start.beamed);
+    if label.is_none() && !bracket { return; }
+
+    let stem_up = match start.stem.as_deref() {
+        Some("up") => true,
+        Some("down") => false,
+        _ => start.note_offsets.iter().sum::<f64>() / start.note_offsets.len() as f64 >= 20.0,
+    };
+    let above = match start.placement.as_deref() {
+        Some("above") => true,
+        Some("below") => false,
+        _ => stem_up,
+    };
+
+    // Clear the stems on the stem side, the noteheads on the other
+    let y = if above {
+        let top = staff_y + start.note_offsets.iter().cloned().fold(f64::MAX, f64::min);
+        let top = if stem_up { top - STEM_LENGTH } else { top };
+        (top - TUPLET_GAP).min(staff_y - TUPLET_GAP)
+    } else {
+        let bottom = staff_y + start.note_offsets.iter().cloned().fold(f64::MIN, f64::max);
+        let bottom = if stem_up { bottom } else { bottom + STEM_LENGTH };
+        (bottom + TUPLET_GAP).max(staff_y + STAFF_HEIGHT + TUPLET_GAP)
+    };
+
+    let x1 = start.x;
+    let mid_x = (x1 + x2) / 2.0;
+
+    if bracket {
+        let hook = if above { TUPLET_HOOK } else { -TUPLET_HOOK };
+        let half_gap = if label.is_some() { TUPLET_FONT_SIZE * 0.6 } else { 0.0 };
+        if !start.continued {
+            svg.line(x1, y + hook, x1, y, svg.palette.note, 1.0);
+        }
+        svg.line(x1, y, mid_x - half_gap, y, svg.palette.note, 1.0);
+        svg.line(mid_x + half_gap, y, x2, y, svg.palette.note, 1.0);
+        if closed {
+            svg.line(x2, y, x2, y + hook, svg.palette.note, 1.0);
+        }
+    }
+
+    if let Some(label) = label {
+        svg.styled_text(
+            mid_x, y + TUPLET_FONT_SIZE * 0.35, label, TUPLET_FONT_SIZE, "bold",
+            svg.palette.note, "middle", Some("Times New Roman, Times, serif"), Some("italic"),
+        );
+    }
 }
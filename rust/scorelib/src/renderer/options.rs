@@ -0,0 +1,312 @@
+//! Render options — color themes, staff size, fonts, which elements of
+//! the score are drawn, and note names and colors for beginners.
+
+use std::borrow::Cow;
+
+use crate::model::Score;
+
+/// Color scheme of a rendered score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+    /// Near-black notes on white paper
+    #[default]
+    Light,
+    /// Light notes on a near-black background, for dark mode
+    Dark,
+    /// Pure black on white, with no gray staff lines or colored chords
+    HighContrast,
+    /// Brown ink on cream paper
+    Sepia,
+}
+
+impl Theme {
+    /// Theme by name: `light`, `dark`, `high-contrast` or `sepia`.
+    pub fn from_name(name: &str) -> Option<Theme> {
+        match name.to_ascii_lowercase().as_str() {
+            "light" => Some(Theme::Light),
+            "dark" => Some(Theme::Dark),
+            "high-contrast" | "high_contrast" | "contrast" => Some(Theme::HighContrast),
+            "sepia" => Some(Theme::Sepia),
+            _ => None,
+        }
+    }
+
+    pub(super) fn palette(self) -> Palette {
+        match self {
+            Theme::Light => LIGHT,
+            Theme::Dark => DARK,
+            Theme::HighContrast => HIGH_CONTRAST,
+            Theme::Sepia => SEPIA,
+        }
+    }
+}
+
+/// The colors a score is drawn in, by role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Palette {
+    pub(super) background: &'static str,
+    /// Noteheads, stems, beams, clefs, accidentals, slurs, ties and marks
+    pub(super) note: &'static str,
+    pub(super) rest: &'static str,
+    pub(super) staff: &'static str,
+    pub(super) barline: &'static str,
+    pub(super) chord: &'static str,
+    pub(super) header: &'static str,
+    pub(super) lyrics: &'static str,
+    pub(super) diagram: &'static str,
+    /// Measure numbers and other secondary text
+    pub(super) muted: &'static str,
+}
+
+const LIGHT: Palette = Palette {
+    background: "white",
+    note: "#1a1a1a",
+    rest: "#1a1a1a",
+    staff: "#555555",
+    barline: "#333333",
+    chord: "#4a4a9a",
+    header: "#1a1a1a",
+    lyrics: "#333333",
+    diagram: "#333333",
+    muted: "#555555",
+};
+
+const DARK: Palette = Palette {
+    background: "#1c1c1e",
+    note: "#ececec",
+    rest: "#ececec",
+    staff: "#8e8e93",
+    barline: "#aeaeb2",
+    chord: "#a5adff",
+    header: "#f2f2f2",
+    lyrics: "#d1d1d6",
+    diagram: "#c7c7cc",
+    muted: "#98989d",
+};
+
+const HIGH_CONTRAST: Palette = Palette {
+    background: "white",
+    note: "#000000",
+    rest: "#000000",
+    staff: "#000000",
+    barline: "#000000",
+    chord: "#000000",
+    header: "#000000",
+    lyrics: "#000000",
+    diagram: "#000000",
+    muted: "#000000",
+};
+
+const SEPIA: Palette = Palette {
+    background: "#f4ecd8",
+    note: "#3b2f20",
+    rest: "#3b2f20",
+    staff: "#7a6a52",
+    barline: "#5b4a35",
+    chord: "#8a4b22",
+    header: "#3b2f20",
+    lyrics: "#5b4a35",
+    diagram: "#5b4a35",
+    muted: "#7a6a52",
+};
+
+/// Typeface of titles, lyrics, chord symbols and other text.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum FontFamily {
+    /// Georgia for text and Times New Roman for chord symbols
+    #[default]
+    Serif,
+    /// Helvetica or Arial, often easier to read at a distance
+    SansSerif,
+    /// A font installed on the device, e.g. "Atkinson Hyperlegible",
+    /// falling back to sans-serif
+    Named(String),
+}
+
+impl FontFamily {
+    /// Font by name: `serif`, `sans-serif`, or any other font name.
+    pub fn from_name(name: &str) -> FontFamily {
+        match name.to_ascii_lowercase().as_str() {
+            "serif" => FontFamily::Serif,
+            "sans-serif" | "sans" => FontFamily::SansSerif,
+            _ => FontFamily::Named(name.to_string()),
+        }
+    }
+
+    /// CSS font list for the text of the score.
+    pub(super) fn text(&self) -> String {
+        match self {
+            FontFamily::Serif => "'Georgia', 'Times New Roman', serif".to_string(),
+            FontFamily::SansSerif => "'Helvetica Neue', 'Helvetica', 'Arial', sans-serif".to_string(),
+            FontFamily::Named(name) => format!("'{}', sans-serif", name.replace(['\'', '"', ';'], "")),
+        }
+    }
+
+    /// Font list for chord symbols.
+    pub(super) fn chords(&self) -> String {
+        match self {
+            FontFamily::Serif => "Times New Roman, serif".to_string(),
+            _ => self.text().replace('\'', ""),
+        }
+    }
+}
+
+/// Names written on or under each notehead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoteNames {
+    #[default]
+    Off,
+    /// C, D, E… with ♯ or ♭
+    Letters,
+    /// Do, Re, Mi… where Do is always C
+    FixedDo,
+    /// Do, Re, Mi… where Do is the tonic of the key signature, with
+    /// chromatic syllables (Di, Ra, Fi…) for notes outside the key
+    MovableDo,
+}
+
+impl NoteNames {
+    /// Names by name: `letters`, `fixed-do`, `movable-do`, or `none`.
+    pub fn from_name(name: &str) -> Option<NoteNames> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" | "off" => Some(NoteNames::Off),
+            "letters" | "letter" => Some(NoteNames::Letters),
+            "fixed-do" | "fixed_do" | "solfege" => Some(NoteNames::FixedDo),
+            "movable-do" | "movable_do" => Some(NoteNames::MovableDo),
+            _ => None,
+        }
+    }
+}
+
+/// Where note names are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LabelPosition {
+    /// In a row under the staff
+    #[default]
+    Below,
+    /// Inside the notehead, in small type
+    Inside,
+}
+
+/// How `render_score_to_svg_with_options` draws a score.  The default
+/// draws it exactly as `render_score_to_svg`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptions {
+    pub theme: Theme,
+    /// Size of the staves and everything on them relative to the default,
+    /// e.g. 1.5 for half again as large.  The page width is unchanged, so
+    /// fewer measures fit on each system.  Clamped to 0.5–3.
+    pub staff_scale: f64,
+    pub font: FontFamily,
+    /// Draw chord symbols and chord diagrams
+    pub chord_symbols: bool,
+    pub lyrics: bool,
+    /// Number the first measure of each system
+    pub measure_numbers: bool,
+    pub note_names: NoteNames,
+    pub note_name_position: LabelPosition,
+    /// Color noteheads by pitch class, in the colors of Boomwhacker tubes
+    pub colored_noteheads: bool,
+    /// Draw the finger numbers the score gives its notes
+    pub finger_numbers: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            theme: Theme::Light,
+            staff_scale: 1.0,
+            font: FontFamily::Serif,
+            chord_symbols: true,
+            lyrics: true,
+            measure_numbers: true,
+            note_names: NoteNames::Off,
+            note_name_position: LabelPosition::Below,
+            colored_noteheads: false,
+            finger_numbers: false,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// The staff scale, clamped to a size that can still be laid out.
+    pub(super) fn scale(&self) -> f64 {
+        if self.staff_scale.is_finite() {
+            self.staff_scale.clamp(0.5, 3.0)
+        } else {
+            1.0
+        }
+    }
+
+    /// Whether note names take a row under each staff.
+    pub(super) fn names_below(&self) -> bool {
+        self.note_names != NoteNames::Off && self.note_name_position == LabelPosition::Below
+    }
+
+    /// The score without the chord symbols and lyrics that are switched
+    /// off, so the layout does not leave room for them.
+    pub(super) fn visible<'a>(&self, score: &'a Score) -> Cow<'a, Score> {
+        if self.chord_symbols && self.lyrics {
+            return Cow::Borrowed(score);
+        }
+        let mut score = score.clone();
+        for measure in score.parts.iter_mut().flat_map(|p| &mut p.measures) {
+            if !self.chord_symbols {
+                measure.harmonies.clear();
+            }
+            if !self.lyrics {
+                for note in &mut measure.notes {
+                    note.lyrics.clear();
+                }
+            }
+        }
+        Cow::Owned(score)
+    }
+}
+
+/// Read `RenderOptions` from the JSON given to the FFI: `theme`
+/// ("light"/"dark"/"high-contrast"/"sepia"), `staff_scale`, `font`
+/// ("serif"/"sans-serif"/a font name), the `chord_symbols`, `lyrics`
+/// and `measure_numbers` switches, `note_names` ("letters"/"fixed-do"/
+/// "movable-do"/"none"), `note_name_position` ("below"/"inside"), and the
+/// `colored_noteheads` and `finger_numbers` switches.  Missing or invalid
+/// fields keep their defaults.
+pub(crate) fn render_options_from_json(json_str: &str) -> RenderOptions {
+    let mut opts = RenderOptions::default();
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(json_str) else {
+        return opts;
+    };
+    if let Some(theme) = value.get("theme").and_then(|v| v.as_str()).and_then(Theme::from_name) {
+        opts.theme = theme;
+    }
+    if let Some(scale) = value.get("staff_scale").and_then(|v| v.as_f64()) {
+        opts.staff_scale = scale;
+    }
+    if let Some(font) = value.get("font").and_then(|v| v.as_str()) {
+        opts.font = FontFamily::from_name(font);
+    }
+    if let Some(b) = value.get("chord_symbols").and_then(|v| v.as_bool()) {
+        opts.chord_symbols = b;
+    }
+    if let Some(b) = value.get("lyrics").and_then(|v| v.as_bool()) {
+        opts.lyrics = b;
+    }
+    if let Some(b) = value.get("measure_numbers").and_then(|v| v.as_bool()) {
+        opts.measure_numbers = b;
+    }
+    if let Some(names) = value.get("note_names").and_then(|v| v.as_str()).and_then(NoteNames::from_name) {
+        opts.note_names = names;
+    }
+    match value.get("note_name_position").and_then(|v| v.as_str()) {
+        Some("inside") => opts.note_name_position = LabelPosition::Inside,
+        Some("below") => opts.note_name_position = LabelPosition::Below,
+        _ => {}
+    }
+    if let Some(b) = value.get("colored_noteheads").and_then(|v| v.as_bool()) {
+        opts.colored_noteheads = b;
+    }
+    if let Some(b) = value.get("finger_numbers").and_then(|v| v.as_bool()) {
+        opts.finger_numbers = b;
+    }
+    opts
+}
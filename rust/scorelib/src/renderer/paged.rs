@@ -0,0 +1,141 @@
+//! Paged SVG — the score as one SVG per page, sized and margined by its
+//! `<defaults>`.
+//!
+//! The pages are laid out as for PDF: the score drawn once at the
+//! printable width and stacked system by system, here honoring the
+//! score's system and page breaks.  Pages the music mostly fills are
+//! justified, their systems spread down to the bottom margin.  The title
+//! and credits open page 1 and every page carries its number.  Units are
+//! tenths, so positions from `compute_paged_measure_positions` line up
+//! with the pages' own coordinates.  A staff scale enlarges the drawing
+//! on the page, and the theme colors the paper.
+
+use crate::model::*;
+use super::constants::*;
+use super::pages::{
+    band_elements, bands, draw_for_pages, page_breaks, paginate, points_per_tenth, Page, PageGeometry,
+    PageOptions, PAGE_NUMBER_SIZE,
+};
+use super::layout::SystemBreaks;
+use super::options::RenderOptions;
+use super::svg_builder::{empty_svg, SvgBuilder};
+use super::{layout_positions, staves_height, Drawing, MeasurePlacement};
+
+/// Pages whose systems fill less of the printable height than this keep
+/// the drawing's spacing instead of being justified.
+const JUSTIFY_MIN_FILL: f64 = 0.5;
+
+/// A score drawn once and placed on pages.
+struct PagedDrawing {
+    geometry: PageGeometry,
+    drawing: Drawing,
+    pages: Vec<Page>,
+    /// Vertical shift of each band from the drawing onto its page
+    shifts: Vec<f64>,
+    /// Page index of each band
+    band_pages: Vec<usize>,
+}
+
+impl PagedDrawing {
+    /// Horizontal shift of the drawing onto every page.
+    fn dx(&self) -> f64 {
+        self.geometry.left - PAGE_MARGIN_LEFT * self.geometry.scale
+    }
+}
+
+/// Draw a score that has at least one part, paginate it and justify the
+/// pages.
+fn paginate_score(score: &Score, options: &PageOptions, render: &RenderOptions) -> PagedDrawing {
+    let geometry = PageGeometry::in_tenths(score, options).with_staff_scale(render);
+    let breaks = if options.breaks { SystemBreaks::Encoded } else { SystemBreaks::Fit };
+    let drawing = draw_for_pages(score, &geometry, 0, breaks, render);
+    let bands = bands(&drawing);
+    let pages = paginate(&geometry, &bands, &page_breaks(&drawing));
+    let printable_h = geometry.height - geometry.top - geometry.bottom;
+
+    let mut shifts = vec![0.0; bands.len()];
+    let mut band_pages = vec![0; bands.len()];
+    for (p, page) in pages.iter().enumerate() {
+        let Some(last) = page.bands.clone().last() else { continue };
+        // The header never moves; systems share out the spare height
+        let first_system = page.bands.start.max(1);
+        let systems = page.bands.end.saturating_sub(first_system);
+        let filled = (bands[last].ink.bottom - page.origin) * geometry.scale;
+        let gap = if systems > 1 && filled >= JUSTIFY_MIN_FILL * printable_h {
+            (printable_h - filled).max(0.0) / (systems - 1) as f64
+        } else {
+            0.0
+        };
+        for i in page.bands.clone() {
+            shifts[i] = geometry.top - page.origin * geometry.scale + i.saturating_sub(first_system) as f64 * gap;
+            band_pages[i] = p;
+        }
+    }
+
+    PagedDrawing { geometry, drawing, pages, shifts, band_pages }
+}
+
+/// Render a parsed score as one SVG per page.
+///
+/// The page size and margins come from `options`, then the score's
+/// `<defaults>`; one SVG unit is one tenth.  With `options.breaks` the
+/// score's `new-system` and `new-page` breaks are kept.  The score is
+/// drawn in the theme, staff size, fonts and elements of `render`.
+/// There is always at least one page.
+pub fn render_score_to_svg_pages(score: &Score, options: &PageOptions, render: &RenderOptions) -> Vec<String> {
+    if score.parts.is_empty() {
+        return vec![empty_svg("No parts in score")];
+    }
+    let paged = paginate_score(score, options, render);
+    let geometry = &paged.geometry;
+    let number_size = PAGE_NUMBER_SIZE / points_per_tenth(score);
+    // The staff scale enlarges the bands, not the page
+    let page_options = RenderOptions { staff_scale: 1.0, ..render.clone() };
+
+    paged.pages.iter()
+        .enumerate()
+        .map(|(p, page)| {
+            let mut svg = SvgBuilder::with_options(geometry.width, geometry.height, &page_options);
+            svg.rect(0.0, 0.0, geometry.width, geometry.height, svg.palette.background, "none", 0.0);
+            for i in page.bands.clone() {
+                svg.group(paged.dx(), paged.shifts[i], geometry.scale, band_elements(&paged.drawing, i));
+            }
+            svg.text(
+                geometry.width / 2.0, geometry.height - geometry.bottom / 2.0, &(p + 1).to_string(),
+                number_size, "normal", svg.palette.header, "middle",
+            );
+            svg.build()
+        })
+        .collect()
+}
+
+/// The measure and system positions of `compute_measure_positions` on
+/// the pages of `render_score_to_svg_pages`, each in its own page's
+/// coordinates, with the page index of every system.
+pub fn compute_paged_measure_positions(
+    score: &Score,
+    options: &PageOptions,
+    render: &RenderOptions,
+) -> (Vec<MeasurePlacement>, Vec<(f64, f64)>, Vec<usize>) {
+    if score.parts.is_empty() {
+        return (Vec::new(), Vec::new(), Vec::new());
+    }
+    let paged = paginate_score(score, options, render);
+    let (dx, scale) = (paged.dx(), paged.geometry.scale);
+    let (mut measures, mut systems) = layout_positions(&paged.drawing.layout, staves_height);
+
+    for (_, x, width, _, beat_x_map) in &mut measures {
+        *x = *x * scale + dx;
+        *width *= scale;
+        for (_, beat_x) in beat_x_map {
+            *beat_x = *beat_x * scale + dx;
+        }
+    }
+    // Band 0 is the header
+    for (i, (y, height)) in systems.iter_mut().enumerate() {
+        *y = *y * scale + paged.shifts[i + 1];
+        *height *= scale;
+    }
+    let pages = (1..=systems.len()).map(|band| paged.band_pages[band]).collect();
+    (measures, systems, pages)
+}
@@ -0,0 +1,263 @@
+//! Page geometry and pagination shared by the PDF, PNG and paged SVG
+//! backends.
+//!
+//! The score is drawn once by the SVG renderer at the printable width of
+//! the page, cut into bands (the header, then one per system) and the
+//! bands are stacked onto pages.  Sizes follow the score's `<defaults>`:
+//! one SVG unit is one tenth, so the scaling sets the staff size and the
+//! page margins are used as given.  A staff scale in the render options
+//! enlarges every tenth on the page.
+
+use std::ops::Range;
+
+use crate::model::*;
+use super::constants::*;
+use super::layout::SystemBreaks;
+use super::options::RenderOptions;
+use super::scene::{self, Band, Rgb};
+use super::{draw_score, Drawing};
+
+/// Paper size for PDF and PNG page export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PageSize {
+    /// 210 × 297 mm
+    #[default]
+    A4,
+    /// 8.5 × 11 in
+    Letter,
+}
+
+impl PageSize {
+    /// Width and height in points.
+    pub fn points(self) -> (f64, f64) {
+        match self {
+            PageSize::A4 => (595.276, 841.89),
+            PageSize::Letter => (612.0, 792.0),
+        }
+    }
+}
+
+/// Page layout for `render_score_to_svg_pages`.  Sizes are in tenths,
+/// the score's own unit; any left `None` come from the score's
+/// `<defaults>`, or else an A4 page with 15 mm margins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageOptions {
+    pub page_width: Option<f64>,
+    pub page_height: Option<f64>,
+    pub left_margin: Option<f64>,
+    pub right_margin: Option<f64>,
+    pub top_margin: Option<f64>,
+    pub bottom_margin: Option<f64>,
+    /// Start systems and pages where the score has `new-system` and
+    /// `new-page` breaks
+    pub breaks: bool,
+}
+
+impl Default for PageOptions {
+    fn default() -> Self {
+        PageOptions {
+            page_width: None,
+            page_height: None,
+            left_margin: None,
+            right_margin: None,
+            top_margin: None,
+            bottom_margin: None,
+            breaks: true,
+        }
+    }
+}
+
+/// Staff height used when the score has no scaling (a common engraving default).
+const DEFAULT_STAFF_HEIGHT_MM: f64 = 7.0;
+/// Page margin used when the score does not give one.
+const DEFAULT_MARGIN_MM: f64 = 15.0;
+const POINTS_PER_MM: f64 = 72.0 / 25.4;
+/// Room kept above a system's top staff line for chord symbols, tempo
+/// marks and volta brackets.
+const SYSTEM_TOP_ROOM: f64 = 45.0;
+pub(super) const PAGE_NUMBER_SIZE: f64 = 10.0;
+
+/// Page size and margins in points, and the size of one tenth.
+pub(super) struct PageGeometry {
+    pub width: f64,
+    pub height: f64,
+    pub left: f64,
+    pub right: f64,
+    pub top: f64,
+    pub bottom: f64,
+    /// Points per tenth (= per SVG unit)
+    pub scale: f64,
+}
+
+impl PageGeometry {
+    pub fn new(score: &Score, page_size: PageSize) -> Self {
+        let (width, height) = page_size.points();
+        let defaults = score.defaults.as_ref();
+
+        let scale = points_per_tenth(score);
+        let margin = |m: Option<f64>| {
+            m.map(|t| t * scale)
+                .filter(|m| m.is_finite() && *m >= 0.0)
+                .unwrap_or(DEFAULT_MARGIN_MM * POINTS_PER_MM)
+        };
+        PageGeometry {
+            width,
+            height,
+            left: margin(defaults.and_then(|d| d.left_margin)),
+            right: margin(defaults.and_then(|d| d.right_margin)),
+            top: margin(defaults.and_then(|d| d.top_margin)),
+            bottom: margin(defaults.and_then(|d| d.bottom_margin)),
+            scale,
+        }
+        .with_room_for_music()
+    }
+
+    /// Geometry of a paged SVG, measured in tenths (so `scale` is 1): the
+    /// options first, then the score's `<defaults>`, then A4.
+    pub fn in_tenths(score: &Score, options: &PageOptions) -> Self {
+        let defaults = score.defaults.as_ref();
+        let tenths = 1.0 / points_per_tenth(score);
+        // A4 to a hundredth of a tenth, for tidy SVG sizes
+        let (a4_width, a4_height) = PageSize::A4.points();
+        let a4 = |points: f64| (points * tenths * 100.0).round() / 100.0;
+        let size = |option: Option<f64>, default: Option<f64>, fallback: f64| {
+            option.or(default).filter(|v| v.is_finite() && *v > 0.0).unwrap_or(fallback)
+        };
+        let margin = |option: Option<f64>, default: Option<f64>| {
+            option.or(default)
+                .filter(|m| m.is_finite() && *m >= 0.0)
+                .unwrap_or(DEFAULT_MARGIN_MM * POINTS_PER_MM * tenths)
+        };
+        PageGeometry {
+            width: size(options.page_width, defaults.and_then(|d| d.page_width), a4(a4_width)),
+            height: size(options.page_height, defaults.and_then(|d| d.page_height), a4(a4_height)),
+            left: margin(options.left_margin, defaults.and_then(|d| d.left_margin)),
+            right: margin(options.right_margin, defaults.and_then(|d| d.right_margin)),
+            top: margin(options.top_margin, defaults.and_then(|d| d.top_margin)),
+            bottom: margin(options.bottom_margin, defaults.and_then(|d| d.bottom_margin)),
+            scale: 1.0,
+        }
+        .with_room_for_music()
+    }
+
+    /// The geometry with every tenth enlarged by the staff scale of
+    /// `options`, so fewer measures fit across the page.
+    pub fn with_staff_scale(mut self, options: &RenderOptions) -> Self {
+        self.scale *= options.scale();
+        self
+    }
+
+    /// Margins sized for a different paper must still leave room for music.
+    fn with_room_for_music(mut self) -> Self {
+        if self.left + self.right > self.width * 0.5 {
+            (self.left, self.right) = (self.width * 0.1, self.width * 0.1);
+        }
+        if self.top + self.bottom > self.height * 0.5 {
+            (self.top, self.bottom) = (self.height * 0.1, self.height * 0.1);
+        }
+        self
+    }
+
+    /// Width to draw the score at so its systems span the printable width.
+    pub fn drawing_width(&self) -> f64 {
+        (self.width - self.left - self.right) / self.scale + PAGE_MARGIN_LEFT + PAGE_MARGIN_RIGHT
+    }
+
+    /// Point coordinates (y down) of a page's drawing window: drawing
+    /// point `(x, y)` lands at `(a·x + e, a·y + f)`.
+    pub fn window(&self, origin: f64) -> [f64; 6] {
+        let s = self.scale;
+        [s, 0.0, 0.0, s, self.left - PAGE_MARGIN_LEFT * s, self.top - origin * s]
+    }
+}
+
+/// Points per tenth from the score's scaling.
+pub(super) fn points_per_tenth(score: &Score) -> f64 {
+    score.defaults.as_ref()
+        .and_then(|d| Some(d.millimeters? / d.tenths?))
+        .filter(|s| s.is_finite() && *s > 0.0)
+        .unwrap_or(DEFAULT_STAFF_HEIGHT_MM / STAFF_HEIGHT)
+        * POINTS_PER_MM
+}
+
+/// Draw a score across the printable width of `geometry`, which is
+/// already enlarged by the staff scale of `options`.
+pub(super) fn draw_for_pages(
+    score: &Score,
+    geometry: &PageGeometry,
+    measure_offset: usize,
+    breaks: SystemBreaks,
+    options: &RenderOptions,
+) -> Drawing {
+    // draw_score lays the score out at its width divided by the scale
+    draw_score(score, geometry.drawing_width() * options.scale(), measure_offset, breaks, options)
+}
+
+/// Paper and page-number colors of the theme of `options`.
+pub(super) fn page_colors(options: &RenderOptions) -> (Rgb, Rgb) {
+    let palette = options.theme.palette();
+    let white = (1.0, 1.0, 1.0);
+    (scene::color(palette.background).unwrap_or(white), scene::color(palette.header).unwrap_or_default())
+}
+
+/// The header band, then one band per system.  Element 0 of the drawing
+/// is the white page background and is left out.
+pub(super) fn bands(drawing: &Drawing) -> Vec<Band> {
+    let mut bands = Vec::with_capacity(drawing.layout.systems.len() + 1);
+    bands.push(Band::new(band_elements(drawing, 0), PAGE_MARGIN_TOP));
+    for (i, system) in drawing.layout.systems.iter().enumerate() {
+        bands.push(Band::new(band_elements(drawing, i + 1), system.y - SYSTEM_TOP_ROOM));
+    }
+    bands
+}
+
+/// The SVG elements of band `i` of `bands`.
+pub(super) fn band_elements(drawing: &Drawing, i: usize) -> &[String] {
+    let elements = &drawing.svg.elements;
+    let starts = &drawing.system_starts;
+    let header_end = starts.first().copied().unwrap_or(elements.len());
+    if i == 0 {
+        return &elements[1.min(header_end)..header_end];
+    }
+    let end = starts.get(i).copied().unwrap_or(elements.len());
+    &elements[starts[i - 1]..end]
+}
+
+/// Bands whose system the score starts on a new page.
+pub(super) fn page_breaks(drawing: &Drawing) -> Vec<usize> {
+    drawing.layout.systems.iter()
+        .enumerate()
+        .filter(|(_, system)| system.new_page)
+        .map(|(i, _)| i + 1)
+        .collect()
+}
+
+/// One page: a window onto the drawing starting at `origin`, showing
+/// `bands`.
+pub(super) struct Page {
+    pub origin: f64,
+    pub bands: Range<usize>,
+}
+
+/// Stack bands onto pages.  Systems on one page keep the drawing's
+/// spacing, so each page is a single window onto the drawing.  A system
+/// whose ink would cross the bottom margin starts a new page, as do the
+/// bands in `breaks`; the first system always shares page 1 with the
+/// header.  There is always at least one page.
+pub(super) fn paginate(geometry: &PageGeometry, bands: &[Band], breaks: &[usize]) -> Vec<Page> {
+    let Some(header) = bands.first() else {
+        return vec![Page { origin: 0.0, bands: 0..0 }];
+    };
+    let printable_h = (geometry.height - geometry.top - geometry.bottom) / geometry.scale;
+    let mut pages = Vec::new();
+    let mut page = Page { origin: header.top.min(header.ink.top), bands: 0..0 };
+    for (i, band) in bands.iter().enumerate() {
+        if i > 1 && (breaks.contains(&i) || band.ink.bottom - page.origin > printable_h) {
+            let origin = band.top.min(band.ink.top);
+            pages.push(std::mem::replace(&mut page, Page { origin, bands: i..i }));
+        }
+        page.bands.end = i + 1;
+    }
+    pages.push(page);
+    pages
+}
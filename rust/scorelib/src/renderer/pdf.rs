@@ -0,0 +1,405 @@
+//! PDF backend — prints the rendered score on A4 or Letter pages.
+//!
+//! The pages come from `pages`: the score drawn once at the printable
+//! width and stacked system by system.  Each band's display list is
+//! written as PDF path and text operators, so the PDF holds the same
+//! vector drawing as the SVG.  The title and credits open page 1 and
+//! every page carries its number at the bottom.  Text uses the standard
+//! Times and Helvetica fonts every PDF reader provides; characters their
+//! WinAnsi encoding lacks (the ♭ and ♯ of note names, say) are set in
+//! subsets of the bundled text faces (see `faces`), embedded as CID fonts
+//! with a ToUnicode map so the text can still be searched and copied.
+
+use std::fmt::Write as _;
+use std::io::Write as _;
+
+use crate::model::*;
+use super::faces::Faces;
+use super::layout::SystemBreaks;
+use super::options::RenderOptions;
+use super::pages::{bands, draw_for_pages, page_colors, paginate, PageGeometry, PageSize, PAGE_NUMBER_SIZE};
+use super::scene::{text_width, Anchor, Band, Font, Rgb, Segment, Shape, Text};
+
+/// Render a parsed score as a PDF document, drawn in the theme, staff
+/// size, fonts and elements of `options`.
+pub fn render_score_to_pdf(score: &Score, page_size: PageSize, options: &RenderOptions) -> Vec<u8> {
+    let geometry = PageGeometry::new(score, page_size).with_staff_scale(options);
+    let bands = if score.parts.is_empty() {
+        Vec::new()
+    } else {
+        bands(&draw_for_pages(score, &geometry, 0, SystemBreaks::Fit, options))
+    };
+    let (paper, ink) = page_colors(options);
+    let mut embedding = Embedding::new();
+
+    let mut contents = Vec::new();
+    for (i, page) in paginate(&geometry, &bands, &[]).iter().enumerate() {
+        let mut content = String::new();
+        if paper != (1.0, 1.0, 1.0) {
+            let _ = writeln!(content, "{} rg 0 0 {} {} re f", rgb(paper), num(geometry.width), num(geometry.height));
+        }
+        if !page.bands.is_empty() {
+            // The window flipped into PDF's y-up page space
+            let [s, _, _, _, e, f] = geometry.window(page.origin);
+            let _ = writeln!(content, "q {} 0 0 {} {} {} cm", num(s), num(-s), num(e), num(geometry.height - f));
+            for band in &bands[page.bands.clone()] {
+                write_band(band, &mut embedding, &mut content);
+            }
+            content.push_str("Q\n");
+        }
+        let label = (i + 1).to_string();
+        let x = geometry.width / 2.0 - text_width(&label, Font::SERIF, PAGE_NUMBER_SIZE) / 2.0;
+        let _ = writeln!(
+            content,
+            "{} rg BT /{} {} Tf {} {} Td {} Tj ET",
+            rgb(ink), Font::SERIF.resource(), num(PAGE_NUMBER_SIZE), num(x), num(geometry.bottom / 2.0), pdf_string(&label),
+        );
+        contents.push(content);
+    }
+
+    write_document(score, &contents, &embedding, geometry.width, geometry.height)
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Display list → PDF operators
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Content stream operators for one band, in drawing coordinates.
+fn write_band(band: &Band, embedding: &mut Embedding, out: &mut String) {
+    for shape in &band.shapes {
+        match shape {
+            Shape::Path { segments, fill, stroke } => {
+                for segment in segments {
+                    let _ = match segment {
+                        Segment::MoveTo(x, y) => write!(out, "{} {} m ", num(*x), num(*y)),
+                        Segment::LineTo(x, y) => write!(out, "{} {} l ", num(*x), num(*y)),
+                        Segment::CurveTo(points) => write!(out, "{} c ", points.map(num).join(" ")),
+                        Segment::Rect(rect) => write!(out, "{} re ", rect.map(num).join(" ")),
+                        Segment::Close => write!(out, "h "),
+                    };
+                }
+                if let Some(fill) = fill {
+                    let _ = write!(out, "{} rg ", rgb(*fill));
+                }
+                if let Some((color, width)) = stroke {
+                    let _ = write!(out, "{} RG {} w ", rgb(*color), num(*width));
+                }
+                out.push_str(match (fill.is_some(), stroke.is_some()) {
+                    (true, true) => "B\n",
+                    (true, false) => "f\n",
+                    (false, true) => "S\n",
+                    (false, false) => "n\n",
+                });
+            }
+            Shape::Text(text) => write_text(text, embedding, out),
+        }
+    }
+}
+
+/// A stretch of text set in one font.
+struct Run {
+    resource: String,
+    embedded: bool,
+    /// The characters for a standard font, hex CIDs for an embedded one
+    text: String,
+    width: f64,
+}
+
+/// Text operators, switching to an embedded font for the characters the
+/// standard font cannot encode.
+fn write_text(text: &Text, embedding: &mut Embedding, out: &mut String) {
+    let mut runs: Vec<Run> = Vec::new();
+    for c in text.text.chars() {
+        let embedded = if win_ansi(c).is_none() { embedding.encode(c, text.font.bold) } else { None };
+        let (resource, piece, width) = match embedded {
+            Some((slot, cid, advance)) => (format!("U{}", slot + 1), format!("{cid:04X}"), advance * text.size),
+            None => (text.font.resource(), c.to_string(), text_width(&c.to_string(), text.font, text.size)),
+        };
+        match runs.last_mut() {
+            Some(run) if run.resource == resource => {
+                run.text.push_str(&piece);
+                run.width += width;
+            }
+            _ => runs.push(Run { resource, embedded: embedded.is_some(), text: piece, width }),
+        }
+    }
+
+    let width: f64 = runs.iter().map(|run| run.width).sum();
+    let x = text.x - match text.anchor {
+        Anchor::Start => 0.0,
+        Anchor::Middle => width / 2.0,
+        Anchor::End => width,
+    };
+    // Flip the text matrix back upright inside the y-down space
+    let _ = write!(out, "{} rg BT 1 0 0 -1 {} {} Tm", rgb(text.color), num(x), num(text.y));
+    for run in &runs {
+        let operand = if run.embedded { format!("<{}>", run.text) } else { pdf_string(&run.text) };
+        let _ = write!(out, " /{} {} Tf {operand} Tj", run.resource, num(text.size));
+    }
+    out.push_str(" ET\n");
+}
+
+fn rgb((r, g, b): Rgb) -> String {
+    format!("{} {} {}", num(r), num(g), num(b))
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Fonts and strings
+// ═══════════════════════════════════════════════════════════════════════
+
+impl Font {
+    /// Resource name of the matching standard face: Times (F1–F4) or
+    /// Helvetica (F5–F8).
+    fn resource(self) -> String {
+        let index = (self.sans as usize) * 4 + (self.bold as usize) * 2 + self.italic as usize;
+        format!("F{}", index + 1)
+    }
+}
+
+const BASE_FONTS: [&str; 8] = [
+    "Times-Roman", "Times-Italic", "Times-Bold", "Times-BoldItalic",
+    "Helvetica", "Helvetica-Oblique", "Helvetica-Bold", "Helvetica-BoldOblique",
+];
+
+/// The WinAnsiEncoding byte for a character; `None` for characters
+/// that need an embedded font.
+fn win_ansi(c: char) -> Option<u8> {
+    Some(match c {
+        ' '..='~' | '\u{A0}'..='\u{FF}' => c as u8,
+        '\u{2013}' => 0x96,
+        '\u{2014}' => 0x97,
+        '\u{2018}' => 0x91,
+        '\u{2019}' => 0x92,
+        '\u{201C}' => 0x93,
+        '\u{201D}' => 0x94,
+        '\u{2022}' => 0x95,
+        '\u{2026}' => 0x85,
+        _ => return None,
+    })
+}
+
+/// A PDF string literal in WinAnsiEncoding, with characters outside it
+/// replaced by `?`.
+fn pdf_string(text: &str) -> String {
+    let mut out = String::from("(");
+    for c in text.chars() {
+        if matches!(c, '(' | ')' | '\\') {
+            out.push('\\');
+        }
+        match win_ansi(c).unwrap_or(b'?') {
+            byte @ ..0x80 => out.push(byte as char),
+            byte => {
+                let _ = write!(out, "\\{byte:03o}");
+            }
+        }
+    }
+    out.push(')');
+    out
+}
+
+/// Subset tag and name of each embedded face: the bundled regular and
+/// bold faces.
+const EMBEDDED_NAMES: [&str; 2] = ["SCOREA+DejaVuSerif", "SCOREB+DejaVuSerif-Bold"];
+
+/// The text faces embedded for characters outside WinAnsiEncoding, with
+/// the characters each has set.  A character's CID is its position in
+/// the list plus one; CID 0 is left to the missing glyph.
+struct Embedding {
+    faces: Option<Faces>,
+    used: [Vec<char>; 2],
+}
+
+impl Embedding {
+    fn new() -> Self {
+        Embedding { faces: Faces::load().ok(), used: Default::default() }
+    }
+
+    /// The face (0 regular, 1 bold) and CID to set `c` in, and its
+    /// advance in ems.
+    fn encode(&mut self, c: char, bold: bool) -> Option<(usize, u16, f64)> {
+        let face = self.faces.as_ref()?.face(bold);
+        let advance = face.advance(face.glyph_index(c)) / face.units_per_em();
+        let used = &mut self.used[bold as usize];
+        let cid = used.iter().position(|&u| u == c).unwrap_or_else(|| {
+            used.push(c);
+            used.len() - 1
+        });
+        Some((bold as usize, cid as u16 + 1, advance))
+    }
+
+    /// Resource name and objects — Type 0 font, CID font, descriptor,
+    /// font file, ToUnicode map and CID-to-glyph map — of each face in
+    /// use, numbered from `first`.
+    fn objects(&self, first: usize) -> Vec<(String, Vec<Vec<u8>>)> {
+        let mut fonts = Vec::new();
+        for (slot, chars) in self.used.iter().enumerate() {
+            let Some(faces) = self.faces.as_ref().filter(|_| !chars.is_empty()) else { continue };
+            let face = faces.face(slot == 1);
+            let n = first + 6 * fonts.len();
+            let name = EMBEDDED_NAMES[slot];
+            let glyphs: Vec<u16> = chars.iter().map(|&c| face.glyph_index(c)).collect();
+            let em = 1000.0 / face.units_per_em();
+
+            let widths: Vec<String> = glyphs.iter().map(|&g| num(face.advance(g) * em)).collect();
+            let (ascent, descent, bbox) = face.metrics();
+            let mut cid_to_gid = vec![0u8; 2];
+            for glyph in &glyphs {
+                cid_to_gid.extend_from_slice(&glyph.to_be_bytes());
+            }
+            let file = face.subset(&glyphs);
+
+            fonts.push((format!("U{}", slot + 1), vec![
+                format!(
+                    "<< /Type /Font /Subtype /Type0 /BaseFont /{name} /Encoding /Identity-H \
+                     /DescendantFonts [{} 0 R] /ToUnicode {} 0 R >>",
+                    n + 1, n + 4,
+                ).into_bytes(),
+                format!(
+                    "<< /Type /Font /Subtype /CIDFontType2 /BaseFont /{name} \
+                     /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> \
+                     /FontDescriptor {} 0 R /W [1 [{}]] /CIDToGIDMap {} 0 R >>",
+                    n + 2, widths.join(" "), n + 5,
+                ).into_bytes(),
+                format!(
+                    "<< /Type /FontDescriptor /FontName /{name} /Flags 4 /FontBBox [{}] /ItalicAngle 0 \
+                     /Ascent {} /Descent {} /CapHeight {} /StemV 80 /FontFile2 {} 0 R >>",
+                    bbox.map(|v| num(v * em)).join(" "), num(ascent * em), num(descent * em), num(ascent * em), n + 3,
+                ).into_bytes(),
+                stream_object(&format!(" /Length1 {}", file.len()), &file),
+                stream_object("", to_unicode(chars).as_bytes()),
+                stream_object("", &cid_to_gid),
+            ]));
+        }
+        fonts
+    }
+}
+
+/// A CMap mapping CIDs 1, 2, … back to `chars`.
+fn to_unicode(chars: &[char]) -> String {
+    let mut cmap = String::from(
+        "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+         /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+         1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+    );
+    // At most 100 mappings per block
+    for (block, chunk) in chars.chunks(100).enumerate() {
+        let _ = writeln!(cmap, "{} beginbfchar", chunk.len());
+        for (i, c) in chunk.iter().enumerate() {
+            let units: String = c.encode_utf16(&mut [0; 2]).iter().map(|u| format!("{u:04X}")).collect();
+            let _ = writeln!(cmap, "<{:04X}> <{units}>", 100 * block + i + 1);
+        }
+        cmap.push_str("endbfchar\n");
+    }
+    cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+    cmap
+}
+
+/// A text string for the document information dictionary (UTF-16BE).
+fn info_string(text: &str) -> String {
+    let mut out = String::from("<FEFF");
+    for unit in text.encode_utf16() {
+        let _ = write!(out, "{unit:04X}");
+    }
+    out.push('>');
+    out
+}
+
+/// A number with at most three decimals and no trailing zeros.
+fn num(value: f64) -> String {
+    let s = format!("{value:.3}");
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    match s {
+        "-0" | "" => "0".to_string(),
+        _ => s.to_string(),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Document structure
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Assemble the catalog, page tree, fonts and compressed page contents
+/// into a complete PDF file.
+fn write_document(score: &Score, pages: &[String], embedding: &Embedding, page_w: f64, page_h: f64) -> Vec<u8> {
+    // Objects: 1 catalog, 2 page tree, 3 info, 4–11 fonts, then a page
+    // and its content stream for each page, then the embedded fonts
+    const FIRST_PAGE: usize = 12;
+    let embedded = embedding.objects(FIRST_PAGE + 2 * pages.len());
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    let kids: Vec<String> = (0..pages.len()).map(|i| format!("{} 0 R", FIRST_PAGE + 2 * i)).collect();
+    objects.push(format!(
+        "<< /Type /Pages /Kids [{}] /Count {} /MediaBox [0 0 {} {}] >>",
+        kids.join(" "), pages.len(), num(page_w), num(page_h),
+    ).into_bytes());
+
+    let mut info = String::from("<< /Producer (scorelib)");
+    if let Some(title) = &score.title {
+        let _ = write!(info, " /Title {}", info_string(title));
+    }
+    if let Some(composer) = &score.composer {
+        let _ = write!(info, " /Author {}", info_string(composer));
+    }
+    info.push_str(" >>");
+    objects.push(info.into_bytes());
+
+    for base in BASE_FONTS {
+        objects.push(format!(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /{base} /Encoding /WinAnsiEncoding >>"
+        ).into_bytes());
+    }
+    let mut fonts: Vec<String> = (0..BASE_FONTS.len()).map(|i| format!("/F{} {} 0 R", i + 1, i + 4)).collect();
+    for (i, (resource, _)) in embedded.iter().enumerate() {
+        fonts.push(format!("/{resource} {} 0 R", FIRST_PAGE + 2 * pages.len() + 6 * i));
+    }
+    let resources = format!("<< /Font << {} >> >>", fonts.join(" "));
+
+    for (i, content) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /Resources {resources} /Contents {} 0 R >>",
+            FIRST_PAGE + 2 * i + 1,
+        ).into_bytes());
+        // Round caps and joins, as the SVG strokes use
+        objects.push(stream_object("", format!("1 J 1 j\n{content}").as_bytes()));
+    }
+    objects.extend(embedded.into_iter().flat_map(|(_, font)| font));
+
+    let mut out: Vec<u8> = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        out.extend_from_slice(object);
+        out.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = out.len();
+    let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(table, "{offset:010} 00000 n ");
+    }
+    let _ = write!(
+        table,
+        "trailer\n<< /Size {} /Root 1 0 R /Info 3 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1,
+    );
+    out.extend_from_slice(table.as_bytes());
+    out
+}
+
+/// A compressed stream object, with `entries` added to its dictionary.
+fn stream_object(entries: &str, data: &[u8]) -> Vec<u8> {
+    let stream = deflate(data);
+    let mut object = format!("<< /Length {}{entries} /Filter /FlateDecode >>\nstream\n", stream.len()).into_bytes();
+    object.extend_from_slice(&stream);
+    object.extend_from_slice(b"\nendstream");
+    object
+}
+
+pub(super) fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    // Writing to a Vec cannot fail
+    let _ = encoder.write_all(data);
+    encoder.finish().unwrap_or_default()
+}
@@ -0,0 +1,556 @@
+//! PNG backend — rasterizes the rendered score for thumbnails and share
+//! images.
+//!
+//! The display list the PDF backend prints (see `scene`) is filled here
+//! with an anti-aliased scanline rasterizer instead, so the music is drawn
+//! from the same glyph outlines as the SVG.  Text is set in DejaVu Serif,
+//! which is bundled with the crate (see `faces`), so an image comes out
+//! the same on every machine and needs no system fonts.
+//!
+//! Coverage is accumulated per pixel from the signed area each edge
+//! sweeps, and the non-zero fill rule is approximated by clamping the
+//! absolute winding to one.  Strokes are filled as one quadrilateral per
+//! segment plus round caps and joins, all wound the same way so they
+//! merge.
+
+use crate::model::*;
+use super::faces::Faces;
+use super::layout::SystemBreaks;
+use super::options::RenderOptions;
+use super::pages::{bands, draw_for_pages, page_colors, paginate, PageGeometry, PageSize, PAGE_NUMBER_SIZE};
+use super::pdf::deflate;
+use super::scene::{apply, concat, Anchor, Band, Bounds, Font, Matrix, Rgb, Segment, Shape, Text};
+
+/// Horizontal shear for italic text, which has no face of its own.
+const ITALIC_SLANT: f64 = 0.2;
+/// Space left around cropped regions, in tenths.
+const CROP_PADDING: f64 = 10.0;
+/// Largest image produced, in pixels (an A4 page at 600 dpi is 35 million).
+const MAX_PIXELS: f64 = 64_000_000.0;
+/// Largest distance, in pixels, between a curve and its flattened polyline.
+const FLATNESS: f64 = 0.1;
+
+/// Which part of the score a PNG shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RasterRegion {
+    /// The first system, cropped to its ink — the score's thumbnail
+    FirstSystem,
+    /// A whole page (1-based), exactly as the PDF export prints it
+    Page(usize),
+    /// Measures `first` to `last` (1-based, inclusive), laid out on
+    /// their own and cropped to their ink
+    Measures { first: usize, last: usize },
+}
+
+/// Options for PNG export.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RasterOptions {
+    /// Resolution in pixels per inch; at 72 one pixel is one point
+    pub dpi: f64,
+    pub region: RasterRegion,
+    /// Paper the score is laid out for; its printable width sets the
+    /// system width and its margins frame `Page` images
+    pub page_size: PageSize,
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        RasterOptions { dpi: 150.0, region: RasterRegion::FirstSystem, page_size: PageSize::A4 }
+    }
+}
+
+/// Render a region of a parsed score as a PNG image.
+///
+/// The staff size follows the score's `<defaults>` scaling as in PDF
+/// export, so `dpi` sets the physical resolution of the printed size;
+/// `render` chooses the theme, staff scale and what is drawn.  Fails when the region is not in the score or the image would be
+/// unreasonably large.
+pub fn render_score_to_png(
+    score: &Score,
+    options: &RasterOptions,
+    render: &RenderOptions,
+) -> Result<Vec<u8>, String> {
+    if !(options.dpi.is_finite() && options.dpi > 0.0) {
+        return Err(format!("invalid resolution {} dpi", options.dpi));
+    }
+    let geometry = PageGeometry::new(score, options.page_size).with_staff_scale(render);
+    let (paper, ink) = page_colors(render);
+    // Pixels per point
+    let px = options.dpi / 72.0;
+    let faces = Faces::load()?;
+
+    let canvas = match options.region {
+        RasterRegion::Page(number) => {
+            let bands = if score.parts.is_empty() {
+                Vec::new()
+            } else {
+                bands(&draw_for_pages(score, &geometry, 0, SystemBreaks::Fit, render))
+            };
+            let pages = paginate(&geometry, &bands, &[]);
+            let page = number
+                .checked_sub(1)
+                .and_then(|i| pages.get(i))
+                .ok_or_else(|| format!("page {number} is not in the score (1-{})", pages.len()))?;
+
+            let to_pixels = [px, 0.0, 0.0, px, 0.0, 0.0];
+            let mut canvas = Canvas::new(geometry.width * px, geometry.height * px, paper, &faces)?;
+            let window = concat(&to_pixels, &geometry.window(page.origin));
+            for band in &bands[page.bands.clone()] {
+                canvas.draw(&band.shapes, &window);
+            }
+            canvas.text(
+                &Text {
+                    x: geometry.width / 2.0,
+                    y: geometry.height - geometry.bottom / 2.0,
+                    anchor: Anchor::Middle,
+                    text: number.to_string(),
+                    size: PAGE_NUMBER_SIZE,
+                    font: Font::SERIF,
+                    color: ink,
+                },
+                &to_pixels,
+            );
+            canvas
+        }
+        RasterRegion::FirstSystem => {
+            if score.parts.is_empty() {
+                return Err("the score has no parts".to_string());
+            }
+            let bands = bands(&draw_for_pages(score, &geometry, 0, SystemBreaks::Fit, render));
+            // Band 0 is the header
+            cropped(bands.get(1..2).unwrap_or_default(), geometry.scale * px, paper, &faces)?
+        }
+        RasterRegion::Measures { first, last } => {
+            let excerpt = excerpt(score, first, last)?;
+            let bands = bands(&draw_for_pages(&excerpt, &geometry, first - 1, SystemBreaks::Fit, render));
+            cropped(bands.get(1..).unwrap_or_default(), geometry.scale * px, paper, &faces)?
+        }
+    };
+    Ok(canvas.encode_png(options.dpi))
+}
+
+/// A canvas of `paper` just big enough for the bands' ink, at `scale`
+/// pixels per tenth.
+fn cropped<'f>(bands: &[Band], scale: f64, paper: Rgb, faces: &'f Faces) -> Result<Canvas<'f>, String> {
+    let ink = bands.iter().fold(Bounds::EMPTY, |ink, band| ink.union(&band.ink));
+    if ink.is_empty() {
+        return Err("the score has no measures".to_string());
+    }
+    let (left, top) = (ink.left - CROP_PADDING, ink.top - CROP_PADDING);
+    let mut canvas = Canvas::new(
+        (ink.right + CROP_PADDING - left) * scale,
+        (ink.bottom + CROP_PADDING - top) * scale,
+        paper,
+        faces,
+    )?;
+    let to_pixels = [scale, 0.0, 0.0, scale, -left * scale, -top * scale];
+    for band in bands {
+        canvas.draw(&band.shapes, &to_pixels);
+    }
+    Ok(canvas)
+}
+
+/// Measures `first` to `last` (1-based, inclusive) of every part as a
+/// score of their own.  The first measure carries the clefs, key and time
+/// in force where the excerpt starts.
+fn excerpt(score: &Score, first: usize, last: usize) -> Result<Score, String> {
+    let count = score.parts.iter().map(|p| p.measures.len()).max().unwrap_or(0);
+    if first == 0 || first > last || last > count {
+        return Err(format!("measures {first}-{last} are not in the score (1-{count})"));
+    }
+    let mut excerpt = score.clone();
+    for part in &mut excerpt.parts {
+        let end = last.min(part.measures.len());
+        let start = (first - 1).min(end);
+        let mut attributes = None;
+        for measure in &part.measures[..start] {
+            if let Some(attrs) = &measure.attributes {
+                merge_attributes(&mut attributes, attrs);
+            }
+        }
+        part.measures.truncate(end);
+        part.measures.drain(..start);
+        if let Some(measure) = part.measures.first_mut() {
+            if let Some(own) = measure.attributes.take() {
+                merge_attributes(&mut attributes, &own);
+            }
+            measure.attributes = attributes;
+            measure.new_system = false;
+            measure.new_page = false;
+        }
+    }
+    Ok(excerpt)
+}
+
+/// Overlay a measure's attribute changes on the running ones.
+fn merge_attributes(running: &mut Option<Attributes>, changes: &Attributes) {
+    let running = running.get_or_insert_with(|| Attributes {
+        divisions: None,
+        key: None,
+        time: None,
+        clefs: Vec::new(),
+        transpose: None,
+        staves: None,
+        staff_details: Vec::new(),
+    });
+    if changes.divisions.is_some() {
+        running.divisions = changes.divisions;
+    }
+    if changes.key.is_some() {
+        running.key = changes.key.clone();
+    }
+    if changes.time.is_some() {
+        running.time = changes.time.clone();
+    }
+    if changes.transpose.is_some() {
+        running.transpose = changes.transpose.clone();
+    }
+    if changes.staves.is_some() {
+        running.staves = changes.staves;
+    }
+    for clef in &changes.clefs {
+        running.clefs.retain(|c| c.number != clef.number);
+        running.clefs.push(clef.clone());
+    }
+    for details in &changes.staff_details {
+        running.staff_details.retain(|d| d.number != details.number);
+        running.staff_details.push(details.clone());
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Canvas
+// ═══════════════════════════════════════════════════════════════════════
+
+/// An RGB image.
+struct Canvas<'f> {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+    faces: &'f Faces,
+}
+
+impl<'f> Canvas<'f> {
+    /// A canvas filled with `paper`.
+    fn new(width: f64, height: f64, paper: Rgb, faces: &'f Faces) -> Result<Self, String> {
+        let (width, height) = (width.ceil().max(1.0), height.ceil().max(1.0));
+        if width * height > MAX_PIXELS {
+            return Err(format!("a {width}×{height} pixel image is too large; lower the resolution"));
+        }
+        let (width, height) = (width as usize, height as usize);
+        let pixel = [paper.0, paper.1, paper.2].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        Ok(Canvas { width, height, pixels: pixel.repeat(width * height), faces })
+    }
+
+    /// Paint shapes given in drawing coordinates; `m` maps them to pixels.
+    fn draw(&mut self, shapes: &[Shape], m: &Matrix) {
+        for shape in shapes {
+            match shape {
+                Shape::Path { segments, fill, stroke } => {
+                    let lines = flatten(segments, m);
+                    if let Some(color) = fill {
+                        self.fill(&lines, *color);
+                    }
+                    if let Some((color, width)) = stroke {
+                        let width = width * (m[0] * m[3] - m[1] * m[2]).abs().sqrt();
+                        self.fill(&stroke_outline(&lines, width), *color);
+                    }
+                }
+                Shape::Text(text) => self.text(text, m),
+            }
+        }
+    }
+
+    fn text(&mut self, text: &Text, m: &Matrix) {
+        let face = self.faces.face(text.font.bold);
+        let em = text.size / face.units_per_em();
+        let glyphs: Vec<u16> = text.text.chars().map(|c| face.glyph_index(c)).collect();
+        let width: f64 = glyphs.iter().map(|&g| face.advance(g) * em).sum();
+        let mut pen = text.x - match text.anchor {
+            Anchor::Start => 0.0,
+            Anchor::Middle => width / 2.0,
+            Anchor::End => width,
+        };
+        let slant = if text.font.italic { ITALIC_SLANT } else { 0.0 };
+
+        let mut outlines = Vec::new();
+        for glyph in glyphs {
+            // Font units are y-up
+            let place = [em, 0.0, slant * em, -em, pen, text.y];
+            outlines.extend(flatten(&face.outline(glyph), &concat(m, &place)));
+            pen += face.advance(glyph) * em;
+        }
+        self.fill(&outlines, text.color);
+    }
+
+    /// Fill polygons given in pixels with the non-zero rule.
+    fn fill(&mut self, polygons: &[Polyline], (r, g, b): Rgb) {
+        let mut bounds = Bounds::EMPTY;
+        for &(x, y) in polygons.iter().flat_map(|p| &p.points) {
+            bounds.include(x, y);
+        }
+        if bounds.is_empty() {
+            return;
+        }
+        let clip = |v: f64, max: usize| (v.max(0.0) as usize).min(max);
+        let (x0, x1) = (clip(bounds.left.floor(), self.width), clip(bounds.right.ceil(), self.width));
+        let (y0, y1) = (clip(bounds.top.floor(), self.height), clip(bounds.bottom.ceil(), self.height));
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+
+        let mut coverage = Coverage::new(x1 - x0, y1 - y0);
+        for polygon in polygons {
+            let points = &polygon.points;
+            for (i, &(ax, ay)) in points.iter().enumerate() {
+                let (bx, by) = points[(i + 1) % points.len()];
+                coverage.edge((ax - x0 as f64, ay - y0 as f64), (bx - x0 as f64, by - y0 as f64));
+            }
+        }
+
+        let color = [r, g, b].map(|c| c.clamp(0.0, 1.0) * 255.0);
+        for row in 0..coverage.height {
+            let mut winding = 0.0;
+            let start = ((y0 + row) * self.width + x0) * 3;
+            for col in 0..coverage.width {
+                winding += coverage.cells[row * coverage.stride + col];
+                let alpha = winding.abs().min(1.0);
+                if alpha < 1.0 / 512.0 {
+                    continue;
+                }
+                let pixel = &mut self.pixels[start + col * 3..start + col * 3 + 3];
+                for (channel, target) in pixel.iter_mut().zip(color) {
+                    let value = *channel as f64;
+                    *channel = (value + (target - value) * alpha).round() as u8;
+                }
+            }
+        }
+    }
+
+    fn encode_png(&self, dpi: f64) -> Vec<u8> {
+        // Every scanline starts with its filter type, 0 (none)
+        let mut raw = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for row in self.pixels.chunks(self.width * 3) {
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // 8-bit RGB, deflate, adaptive filtering, not interlaced
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+        write_chunk(&mut png, b"IHDR", &header);
+
+        let per_meter = ((dpi / 0.0254).round() as u32).to_be_bytes();
+        let mut physical = Vec::with_capacity(9);
+        physical.extend_from_slice(&per_meter);
+        physical.extend_from_slice(&per_meter);
+        physical.push(1);
+        write_chunk(&mut png, b"pHYs", &physical);
+
+        write_chunk(&mut png, b"IDAT", &deflate(&raw));
+        write_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let mut crc = flate2::Crc::new();
+    crc.update(kind);
+    crc.update(data);
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    png.extend_from_slice(&crc.sum().to_be_bytes());
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Geometry
+// ═══════════════════════════════════════════════════════════════════════
+
+/// A flattened subpath in pixels.
+struct Polyline {
+    points: Vec<(f64, f64)>,
+    closed: bool,
+}
+
+/// Flatten path segments into polylines, mapping them through `m`.
+fn flatten(segments: &[Segment], m: &Matrix) -> Vec<Polyline> {
+    let mut lines = Vec::new();
+    let mut current = Polyline { points: Vec::new(), closed: false };
+    let finish = |lines: &mut Vec<Polyline>, line: Polyline| {
+        if line.points.len() > 1 {
+            lines.push(line);
+        }
+    };
+    for segment in segments {
+        match *segment {
+            Segment::MoveTo(x, y) => {
+                let next = Polyline { points: vec![apply(m, x, y)], closed: false };
+                finish(&mut lines, std::mem::replace(&mut current, next));
+            }
+            Segment::LineTo(x, y) => current.points.push(apply(m, x, y)),
+            Segment::CurveTo([ax, ay, bx, by, x, y]) => {
+                let p1 = apply(m, ax, ay);
+                let p2 = apply(m, bx, by);
+                let p3 = apply(m, x, y);
+                let p0 = current.points.last().copied().unwrap_or(p1);
+                if current.points.is_empty() {
+                    current.points.push(p0);
+                }
+                // Enough steps that no chord strays more than FLATNESS
+                let dd = |a: (f64, f64), b: (f64, f64), c: (f64, f64)| {
+                    (a.0 - 2.0 * b.0 + c.0).hypot(a.1 - 2.0 * b.1 + c.1)
+                };
+                let bend = dd(p0, p1, p2).max(dd(p1, p2, p3));
+                let steps = (0.75 * bend / FLATNESS).sqrt().ceil().clamp(1.0, 100.0) as usize;
+                for i in 1..=steps {
+                    let t = i as f64 / steps as f64;
+                    let u = 1.0 - t;
+                    let (w0, w1, w2, w3) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+                    current.points.push((
+                        w0 * p0.0 + w1 * p1.0 + w2 * p2.0 + w3 * p3.0,
+                        w0 * p0.1 + w1 * p1.1 + w2 * p2.1 + w3 * p3.1,
+                    ));
+                }
+            }
+            Segment::Rect([x, y, w, h]) => {
+                let points = vec![apply(m, x, y), apply(m, x + w, y), apply(m, x + w, y + h), apply(m, x, y + h)];
+                lines.push(Polyline { points, closed: true });
+            }
+            Segment::Close => {
+                // Drawing continues from the subpath's start
+                let start = current.points.first().copied();
+                current.closed = true;
+                let next = Polyline { points: start.into_iter().collect(), closed: false };
+                finish(&mut lines, std::mem::replace(&mut current, next));
+            }
+        }
+    }
+    finish(&mut lines, current);
+    lines
+}
+
+/// The area a stroke of `width` pixels covers, as polygons wound the
+/// same way.
+fn stroke_outline(lines: &[Polyline], width: f64) -> Vec<Polyline> {
+    let r = width / 2.0;
+    let mut outline = Vec::new();
+    for line in lines {
+        let mut points = line.points.clone();
+        if line.closed {
+            points.push(points[0]);
+        }
+        for pair in points.windows(2) {
+            let ((ax, ay), (bx, by)) = (pair[0], pair[1]);
+            let length = (bx - ax).hypot(by - ay);
+            if length < 1e-9 {
+                continue;
+            }
+            let (nx, ny) = (-(by - ay) / length * r, (bx - ax) / length * r);
+            outline.push(wound(vec![(ax + nx, ay + ny), (bx + nx, by + ny), (bx - nx, by - ny), (ax - nx, ay - ny)]));
+        }
+        // Round caps and joins; below half a pixel they would not show
+        if r >= 0.5 {
+            let sides = ((r * 2.0).ceil() as usize).clamp(8, 64);
+            for &(cx, cy) in &points {
+                let circle = (0..sides)
+                    .map(|i| {
+                        let (sin, cos) = (i as f64 / sides as f64 * std::f64::consts::TAU).sin_cos();
+                        (cx + r * cos, cy + r * sin)
+                    })
+                    .collect();
+                outline.push(wound(circle));
+            }
+        }
+    }
+    outline
+}
+
+/// A closed polygon turned to positive winding.
+fn wound(mut points: Vec<(f64, f64)>) -> Polyline {
+    let area: f64 = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+        .sum();
+    if area < 0.0 {
+        points.reverse();
+    }
+    Polyline { points, closed: true }
+}
+
+/// Signed area accumulation buffer: each cell holds the change in
+/// coverage from the cell to its left, so a running sum along a row gives
+/// each pixel's winding-weighted coverage.
+struct Coverage {
+    width: usize,
+    height: usize,
+    stride: usize,
+    cells: Vec<f64>,
+}
+
+impl Coverage {
+    fn new(width: usize, height: usize) -> Self {
+        // Two spare columns take the spill from edges at the right border
+        let stride = width + 2;
+        Coverage { width, height, stride, cells: vec![0.0; stride * height] }
+    }
+
+    /// Accumulate one polygon edge.  Points left or right of the buffer
+    /// are pinned to its sides, which keeps each row's sum balanced.
+    fn edge(&mut self, (ax, ay): (f64, f64), (bx, by): (f64, f64)) {
+        if !(ay - by).is_normal() {
+            return;
+        }
+        let (dir, (x0, y0), (x1, y1)) = if ay < by { (1.0, (ax, ay), (bx, by)) } else { (-1.0, (bx, by), (ax, ay)) };
+        if y1 <= 0.0 || y0 >= self.height as f64 {
+            return;
+        }
+        let dxdy = (x1 - x0) / (y1 - y0);
+        let top = y0.max(0.0);
+        let mut x = x0 + (top - y0) * dxdy;
+        let max_x = self.width as f64;
+        let rows = top as usize..(y1.ceil() as usize).min(self.height);
+        for row in rows {
+            let dy = ((row + 1) as f64).min(y1) - (row as f64).max(y0);
+            let x_next = x + dxdy * dy;
+            let d = dy * dir;
+            let (xa, xb) = if x < x_next { (x, x_next) } else { (x_next, x) };
+            let (xa, xb) = (xa.clamp(0.0, max_x), xb.clamp(0.0, max_x));
+            let line = &mut self.cells[row * self.stride..(row + 1) * self.stride];
+
+            let xa_floor = xa.floor();
+            let ia = xa_floor as usize;
+            let ib = xb.ceil() as usize;
+            if ib <= ia + 1 {
+                // Within one pixel: split by the edge's mean position
+                let frac = 0.5 * (xa + xb) - xa_floor;
+                line[ia] += d * (1.0 - frac);
+                line[ia + 1] += d * frac;
+            } else {
+                let s = 1.0 / (xb - xa);
+                let fa = xa - xa_floor;
+                let first = 0.5 * s * (1.0 - fa) * (1.0 - fa);
+                let fb = xb - xb.ceil() + 1.0;
+                let last = 0.5 * s * fb * fb;
+                line[ia] += d * first;
+                if ib == ia + 2 {
+                    line[ia + 1] += d * (1.0 - first - last);
+                } else {
+                    let second = s * (1.5 - fa);
+                    line[ia + 1] += d * (second - first);
+                    for cell in &mut line[ia + 2..ib - 1] {
+                        *cell += d * s;
+                    }
+                    let before_last = second + (ib - ia - 3) as f64 * s;
+                    line[ib - 1] += d * (1.0 - before_last - last);
+                }
+                line[ib] += d * last;
+            }
+            x = x_next;
+        }
+    }
+}
@@ -0,0 +1,549 @@
+//! Display list — the SVG renderer's elements as plain paths and text.
+//!
+//! The page backends (PDF and PNG) do not interpret SVG themselves.  Each
+//! element `SvgBuilder` produced is parsed once into filled or stroked
+//! paths and text runs, with transforms already applied, in drawing
+//! coordinates: one unit is one tenth and y grows downwards.
+
+use roxmltree::Node;
+
+/// Red, green and blue components in 0–1.
+pub(super) type Rgb = (f64, f64, f64);
+
+/// An affine transform `[a b c d e f]`, as in SVG `matrix()` and PDF `cm`.
+pub(super) type Matrix = [f64; 6];
+
+pub(super) const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// `outer` applied after `inner`.
+pub(super) fn concat(outer: &Matrix, inner: &Matrix) -> Matrix {
+    let [a, b, c, d, e, f] = *outer;
+    [
+        a * inner[0] + c * inner[1],
+        b * inner[0] + d * inner[1],
+        a * inner[2] + c * inner[3],
+        b * inner[2] + d * inner[3],
+        a * inner[4] + c * inner[5] + e,
+        b * inner[4] + d * inner[5] + f,
+    ]
+}
+
+pub(super) fn apply(m: &Matrix, x: f64, y: f64) -> (f64, f64) {
+    (m[0] * x + m[2] * y + m[4], m[1] * x + m[3] * y + m[5])
+}
+
+/// One step of a path outline.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Segment {
+    MoveTo(f64, f64),
+    LineTo(f64, f64),
+    /// Cubic Bézier: first control point, second control point, end point
+    CurveTo([f64; 6]),
+    /// An axis-aligned rectangle as a closed subpath: x, y, width, height
+    Rect([f64; 4]),
+    Close,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+/// A run of text on one baseline.
+#[derive(Debug, Clone)]
+pub(super) struct Text {
+    /// Anchor point on the baseline; which end of the text it marks is
+    /// given by `anchor`
+    pub x: f64,
+    pub y: f64,
+    pub anchor: Anchor,
+    pub text: String,
+    pub size: f64,
+    pub font: Font,
+    pub color: Rgb,
+}
+
+#[derive(Debug, Clone)]
+pub(super) enum Shape {
+    Path {
+        segments: Vec<Segment>,
+        fill: Option<Rgb>,
+        /// Color and line width
+        stroke: Option<(Rgb, f64)>,
+    },
+    Text(Text),
+}
+
+/// A rectangle that grows to enclose points; empty until the first one.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Bounds {
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+}
+
+impl Bounds {
+    pub const EMPTY: Bounds = Bounds {
+        left: f64::INFINITY,
+        top: f64::INFINITY,
+        right: f64::NEG_INFINITY,
+        bottom: f64::NEG_INFINITY,
+    };
+
+    pub fn is_empty(&self) -> bool {
+        self.left > self.right || self.top > self.bottom
+    }
+
+    pub fn include(&mut self, x: f64, y: f64) {
+        self.left = self.left.min(x);
+        self.right = self.right.max(x);
+        self.top = self.top.min(y);
+        self.bottom = self.bottom.max(y);
+    }
+
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            left: self.left.min(other.left),
+            top: self.top.min(other.top),
+            right: self.right.max(other.right),
+            bottom: self.bottom.max(other.bottom),
+        }
+    }
+}
+
+/// One horizontal slice of the drawing — the header or a system.
+pub(super) struct Band {
+    pub shapes: Vec<Shape>,
+    /// Nominal top edge in the drawing
+    pub top: f64,
+    /// Extent of everything the band draws
+    pub ink: Bounds,
+}
+
+impl Band {
+    pub fn new(elements: &[String], top: f64) -> Self {
+        let mut band = Band { shapes: Vec::new(), top, ink: Bounds::EMPTY };
+        for element in elements {
+            if let Ok(doc) = roxmltree::Document::parse(element) {
+                band.node(doc.root_element(), &IDENTITY);
+            }
+        }
+        band
+    }
+
+    /// Translate one element produced by `SvgBuilder`.
+    fn node(&mut self, node: Node, parent: &Matrix) {
+        let attr = |name: &str| node.attribute(name);
+        let number = |name: &str| attr(name).and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0);
+
+        let m = attr("transform")
+            .map(parse_transform)
+            .unwrap_or_default()
+            .iter()
+            .fold(*parent, |m, t| concat(&m, t));
+        let mut path = PathBuilder { m, segments: Vec::new(), ink: &mut self.ink };
+
+        match node.tag_name().name() {
+            "g" => {
+                for child in node.children().filter(|c| c.is_element()) {
+                    self.node(child, &m);
+                }
+                return;
+            }
+            "line" => {
+                path.move_to(number("x1"), number("y1"));
+                path.line_to(number("x2"), number("y2"));
+            }
+            "rect" => path.rect(number("x"), number("y"), number("width"), number("height")),
+            "circle" => path.ellipse(number("cx"), number("cy"), number("r"), number("r")),
+            "ellipse" => path.ellipse(number("cx"), number("cy"), number("rx"), number("ry")),
+            "path" => path.svg_path(attr("d").unwrap_or("")),
+            "text" => {
+                let text: String = node.text().unwrap_or("").split_whitespace().collect::<Vec<_>>().join(" ");
+                if text.is_empty() {
+                    return;
+                }
+                let size = attr("font-size").and_then(|v| v.parse().ok()).unwrap_or(12.0);
+                let font = Font::new(attr("font-family"), attr("font-weight"), attr("font-style"));
+                let anchor = match attr("text-anchor") {
+                    Some("middle") => Anchor::Middle,
+                    Some("end") => Anchor::End,
+                    _ => Anchor::Start,
+                };
+                let (x, y) = apply(&m, number("x"), number("y"));
+                let width = text_width(&text, font, size);
+                let left = x - match anchor {
+                    Anchor::Start => 0.0,
+                    Anchor::Middle => width / 2.0,
+                    Anchor::End => width,
+                };
+                self.ink.include(left, y - 0.75 * size);
+                self.ink.include(left + width, y + 0.25 * size);
+                let color = color(attr("fill").unwrap_or("#000000")).unwrap_or((0.0, 0.0, 0.0));
+                self.shapes.push(Shape::Text(Text { x, y, anchor, text, size, font, color }));
+                return;
+            }
+            _ => return,
+        }
+
+        let segments = path.segments;
+        if segments.is_empty() {
+            return;
+        }
+        // Lines have no fill; closed shapes are black unless told otherwise
+        let default_fill = (node.tag_name().name() != "line").then_some("#000000");
+        let fill = attr("fill").or(default_fill).and_then(color);
+        let stroke = attr("stroke").and_then(color).map(|c| {
+            let width = attr("stroke-width").and_then(|w| w.parse::<f64>().ok()).unwrap_or(1.0);
+            // Scaled by the transform's area factor
+            (c, width * (m[0] * m[3] - m[1] * m[2]).abs().sqrt())
+        });
+        if fill.is_some() || stroke.is_some() {
+            self.shapes.push(Shape::Path { segments, fill, stroke });
+        }
+    }
+}
+
+/// Collects segments in drawing coordinates while widening the ink extent.
+struct PathBuilder<'a> {
+    m: Matrix,
+    segments: Vec<Segment>,
+    ink: &'a mut Bounds,
+}
+
+impl PathBuilder<'_> {
+    fn point(&mut self, x: f64, y: f64) -> (f64, f64) {
+        let (x, y) = apply(&self.m, x, y);
+        self.ink.include(x, y);
+        (x, y)
+    }
+
+    fn move_to(&mut self, x: f64, y: f64) {
+        let (x, y) = self.point(x, y);
+        self.segments.push(Segment::MoveTo(x, y));
+    }
+
+    fn line_to(&mut self, x: f64, y: f64) {
+        let (x, y) = self.point(x, y);
+        self.segments.push(Segment::LineTo(x, y));
+    }
+
+    fn curve_to(&mut self, points: [f64; 6]) {
+        let (ax, ay) = self.point(points[0], points[1]);
+        let (bx, by) = self.point(points[2], points[3]);
+        let (x, y) = self.point(points[4], points[5]);
+        self.segments.push(Segment::CurveTo([ax, ay, bx, by, x, y]));
+    }
+
+    /// A quadratic Bézier raised to a cubic.
+    fn quad_to(&mut self, (x0, y0): (f64, f64), (qx, qy): (f64, f64), (x, y): (f64, f64)) {
+        self.curve_to([
+            x0 + 2.0 / 3.0 * (qx - x0), y0 + 2.0 / 3.0 * (qy - y0),
+            x + 2.0 / 3.0 * (qx - x), y + 2.0 / 3.0 * (qy - y),
+            x, y,
+        ]);
+    }
+
+    fn rect(&mut self, x: f64, y: f64, w: f64, h: f64) {
+        let m = self.m;
+        if m[1] == 0.0 && m[2] == 0.0 {
+            let (x0, y0) = self.point(x, y);
+            let (x1, y1) = self.point(x + w, y + h);
+            self.segments.push(Segment::Rect([x0, y0, x1 - x0, y1 - y0]));
+        } else {
+            self.move_to(x, y);
+            self.line_to(x + w, y);
+            self.line_to(x + w, y + h);
+            self.line_to(x, y + h);
+            self.segments.push(Segment::Close);
+        }
+    }
+
+    /// An ellipse as four Bézier quarter arcs.
+    fn ellipse(&mut self, cx: f64, cy: f64, rx: f64, ry: f64) {
+        const K: f64 = 0.552_284_75;
+        let (kx, ky) = (rx * K, ry * K);
+        self.move_to(cx + rx, cy);
+        self.curve_to([cx + rx, cy + ky, cx + kx, cy + ry, cx, cy + ry]);
+        self.curve_to([cx - kx, cy + ry, cx - rx, cy + ky, cx - rx, cy]);
+        self.curve_to([cx - rx, cy - ky, cx - kx, cy - ry, cx, cy - ry]);
+        self.curve_to([cx + kx, cy - ry, cx + rx, cy - ky, cx + rx, cy]);
+        self.segments.push(Segment::Close);
+    }
+
+    /// SVG path data.  Handles absolute and relative moves, lines, cubic
+    /// and quadratic curves and their smooth forms; arcs are drawn as
+    /// straight lines to their end point.
+    fn svg_path(&mut self, d: &str) {
+        let mut lexer = PathLexer { bytes: d.as_bytes(), pos: 0 };
+        let (mut x, mut y) = (0.0, 0.0);
+        let (mut start_x, mut start_y) = (0.0, 0.0);
+        // Control point to reflect for S/T: (command class, x, y)
+        let mut last_control: Option<(u8, f64, f64)> = None;
+        let mut command = 0u8;
+
+        loop {
+            match lexer.command() {
+                Some(c) => command = c,
+                None if command != 0 && lexer.has_number() => {
+                    // Implicit repeat; a repeated moveto is a lineto
+                    if command == b'M' { command = b'L' }
+                    if command == b'm' { command = b'l' }
+                }
+                None => break,
+            }
+            let relative = command.is_ascii_lowercase();
+            let (ox, oy) = if relative { (x, y) } else { (0.0, 0.0) };
+            let upper = command.to_ascii_uppercase();
+            let mut control = None;
+            match upper {
+                b'Z' => {
+                    self.segments.push(Segment::Close);
+                    (x, y) = (start_x, start_y);
+                    command = 0;
+                }
+                b'M' | b'L' | b'T' => {
+                    let Some([px, py]) = lexer.numbers::<2>() else { break };
+                    let (px, py) = (ox + px, oy + py);
+                    if upper == b'T' {
+                        let (qx, qy) = match last_control {
+                            Some((b'Q', cx, cy)) => (2.0 * x - cx, 2.0 * y - cy),
+                            _ => (x, y),
+                        };
+                        self.quad_to((x, y), (qx, qy), (px, py));
+                        control = Some((b'Q', qx, qy));
+                    } else if upper == b'M' {
+                        self.move_to(px, py);
+                        (start_x, start_y) = (px, py);
+                    } else {
+                        self.line_to(px, py);
+                    }
+                    (x, y) = (px, py);
+                }
+                b'H' | b'V' => {
+                    let Some([v]) = lexer.numbers::<1>() else { break };
+                    match (upper, relative) {
+                        (b'H', true) => x += v,
+                        (b'H', false) => x = v,
+                        (_, true) => y += v,
+                        (_, false) => y = v,
+                    }
+                    self.line_to(x, y);
+                }
+                b'C' | b'S' => {
+                    let (c1x, c1y, rest) = if upper == b'C' {
+                        let Some([ax, ay, bx, by, px, py]) = lexer.numbers::<6>() else { break };
+                        (ox + ax, oy + ay, [bx, by, px, py])
+                    } else {
+                        let Some(rest) = lexer.numbers::<4>() else { break };
+                        let (ax, ay) = match last_control {
+                            Some((b'C', cx, cy)) => (2.0 * x - cx, 2.0 * y - cy),
+                            _ => (x, y),
+                        };
+                        (ax, ay, rest)
+                    };
+                    let (c2x, c2y, px, py) = (ox + rest[0], oy + rest[1], ox + rest[2], oy + rest[3]);
+                    self.curve_to([c1x, c1y, c2x, c2y, px, py]);
+                    control = Some((b'C', c2x, c2y));
+                    (x, y) = (px, py);
+                }
+                b'Q' => {
+                    let Some([qx, qy, px, py]) = lexer.numbers::<4>() else { break };
+                    let (qx, qy, px, py) = (ox + qx, oy + qy, ox + px, oy + py);
+                    self.quad_to((x, y), (qx, qy), (px, py));
+                    control = Some((b'Q', qx, qy));
+                    (x, y) = (px, py);
+                }
+                b'A' => {
+                    let Some(args) = lexer.numbers::<7>() else { break };
+                    (x, y) = (ox + args[5], oy + args[6]);
+                    self.line_to(x, y);
+                }
+                _ => break,
+            }
+            last_control = control;
+        }
+    }
+}
+
+/// `#rgb`, `#rrggbb` or a basic color name as 0–1 components; `None`
+/// for `none`.
+pub(super) fn color(value: &str) -> Option<Rgb> {
+    let value = value.trim();
+    let hex = |s: &str| u8::from_str_radix(s, 16).ok().map(|v| v as f64 / 255.0);
+    match value {
+        "none" | "transparent" => None,
+        "white" => Some((1.0, 1.0, 1.0)),
+        "gray" | "grey" => Some((0.5, 0.5, 0.5)),
+        "red" => Some((1.0, 0.0, 0.0)),
+        "blue" => Some((0.0, 0.0, 1.0)),
+        _ if value.len() == 7 && value.starts_with('#') => {
+            Some((hex(&value[1..3])?, hex(&value[3..5])?, hex(&value[5..7])?))
+        }
+        _ if value.len() == 4 && value.starts_with('#') => {
+            let digit = |i: usize| hex(&value[i..i + 1].repeat(2));
+            Some((digit(1)?, digit(2)?, digit(3)?))
+        }
+        _ => Some((0.0, 0.0, 0.0)),
+    }
+}
+
+/// The matrices of an SVG `transform` list (`translate`, `scale`,
+/// `rotate` and `matrix`), outermost first.
+fn parse_transform(transform: &str) -> Vec<Matrix> {
+    let mut matrices = Vec::new();
+    for item in transform.split(')') {
+        let Some((name, args)) = item.split_once('(') else { continue };
+        let args: Vec<f64> = args
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter_map(|a| a.parse().ok())
+            .collect();
+        let arg = |i: usize| args.get(i).copied();
+        matrices.push(match name.trim() {
+            "translate" => [1.0, 0.0, 0.0, 1.0, arg(0).unwrap_or(0.0), arg(1).unwrap_or(0.0)],
+            "scale" => {
+                let sx = arg(0).unwrap_or(1.0);
+                [sx, 0.0, 0.0, arg(1).unwrap_or(sx), 0.0, 0.0]
+            }
+            "rotate" => {
+                let (sin, cos) = arg(0).unwrap_or(0.0).to_radians().sin_cos();
+                let (cx, cy) = (arg(1).unwrap_or(0.0), arg(2).unwrap_or(0.0));
+                // Rotation about (cx, cy)
+                [cos, sin, -sin, cos, cx - cos * cx + sin * cy, cy - sin * cx - cos * cy]
+            }
+            "matrix" if args.len() == 6 => [args[0], args[1], args[2], args[3], args[4], args[5]],
+            _ => continue,
+        });
+    }
+    matrices
+}
+
+/// Tokenizer for compact path data such as `M1.5-2c.5.5,1,1`.
+struct PathLexer<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl PathLexer<'_> {
+    fn skip_separators(&mut self) {
+        while self.pos < self.bytes.len() && (self.bytes[self.pos].is_ascii_whitespace() || self.bytes[self.pos] == b',') {
+            self.pos += 1;
+        }
+    }
+
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        let c = *self.bytes.get(self.pos)?;
+        if c.is_ascii_alphabetic() && c != b'e' && c != b'E' {
+            self.pos += 1;
+            Some(c)
+        } else {
+            None
+        }
+    }
+
+    fn has_number(&mut self) -> bool {
+        self.skip_separators();
+        self.bytes.get(self.pos).is_some_and(|c| c.is_ascii_digit() || matches!(c, b'-' | b'+' | b'.'))
+    }
+
+    fn number(&mut self) -> Option<f64> {
+        if !self.has_number() {
+            return None;
+        }
+        let start = self.pos;
+        let mut seen_dot = false;
+        let mut seen_exp = false;
+        if matches!(self.bytes[self.pos], b'-' | b'+') {
+            self.pos += 1;
+        }
+        while let Some(&c) = self.bytes.get(self.pos) {
+            match c {
+                b'0'..=b'9' => {}
+                b'.' if !seen_dot && !seen_exp => seen_dot = true,
+                b'e' | b'E' if !seen_exp => {
+                    seen_exp = true;
+                    if matches!(self.bytes.get(self.pos + 1), Some(b'-' | b'+')) {
+                        self.pos += 1;
+                    }
+                }
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.pos]).ok()?.parse().ok()
+    }
+
+    fn numbers<const N: usize>(&mut self) -> Option<[f64; N]> {
+        let mut values = [0.0; N];
+        for v in &mut values {
+            *v = self.number()?;
+        }
+        Some(values)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Fonts
+// ═══════════════════════════════════════════════════════════════════════
+
+/// The face a text element asks for, reduced to serif or sans, bold
+/// and italic.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Font {
+    pub sans: bool,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+impl Font {
+    pub const SERIF: Font = Font { sans: false, bold: false, italic: false };
+
+    fn new(family: Option<&str>, weight: Option<&str>, style: Option<&str>) -> Self {
+        let family = family.unwrap_or("").to_ascii_lowercase();
+        Font {
+            sans: ["sans", "arial", "helvetica", "verdana"].iter().any(|f| family.contains(f)),
+            bold: matches!(weight, Some("bold" | "bolder" | "600" | "700" | "800" | "900")),
+            italic: matches!(style, Some("italic" | "oblique")),
+        }
+    }
+}
+
+/// Advance widths (1/1000 em) of printable ASCII in Times-Roman.
+const TIMES_WIDTHS: [u16; 95] = [
+    250, 333, 408, 500, 500, 833, 778, 333, 333, 333, 500, 564, 250, 333, 250, 278,
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 278, 278, 564, 564, 564, 444,
+    921, 722, 667, 667, 722, 611, 556, 722, 722, 333, 389, 722, 611, 889, 722, 722,
+    556, 722, 667, 556, 611, 722, 722, 944, 722, 722, 611, 333, 278, 333, 469, 500,
+    333, 444, 500, 444, 500, 444, 333, 500, 500, 278, 278, 500, 278, 778, 500, 500,
+    500, 500, 333, 389, 278, 500, 500, 722, 500, 500, 444, 480, 200, 480, 541,
+];
+
+/// Advance widths (1/1000 em) of printable ASCII in Helvetica.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 556, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// Width of a string set in the standard Times or Helvetica faces.  Bold
+/// and italic faces are measured with the regular widths, which is close
+/// enough to anchor centered and right-aligned text.
+pub(super) fn text_width(text: &str, font: Font, size: f64) -> f64 {
+    let widths = if font.sans { &HELVETICA_WIDTHS } else { &TIMES_WIDTHS };
+    let bold = if font.bold { 1.04 } else { 1.0 };
+    let units: u32 = text
+        .chars()
+        .map(|c| match c as u32 {
+            code @ 32..=126 => widths[code as usize - 32] as u32,
+            _ => 500,
+        })
+        .sum();
+    units as f64 / 1000.0 * size * bold
+}
@@ -4,7 +4,6 @@ use crate::model::*;
 use super::svg_builder::SvgBuilder;
 use super::beat_map::{pitch_to_staff_y, note_x_positions_from_beat_map};
 
-const SLUR_COLOR: &str = "#1a1a1a";
 const SLUR_NOTEHEAD_Y_OFFSET: f64 = 3.0;
 const SLUR_ENDPOINT_THICKNESS: f64 = 0.5;
 const SLUR_MID_THICKNESS: f64 = 1.5;
@@ -24,6 +23,7 @@ pub(super) struct SlurStart {
 
 /// Collect slur positions for all notes in a single measure/staff and
 /// process start/stop events.
+#[allow(clippy::too_many_arguments)]
 pub(super) fn collect_and_render_slurs_for_measure(
     svg: &mut SvgBuilder,
     measure: &Measure,
@@ -39,7 +39,7 @@ pub(super) fn collect_and_render_slurs_for_measure(
         return;
     }
 
-    let note_positions = note_x_positions_from_beat_map(&measure.notes, divisions, beat_x_map);
+    let note_positions = note_x_positions_from_beat_map(measure, divisions, beat_x_map);
 
     for (i, note) in measure.notes.iter().enumerate() {
         if let Some(sf) = staff_filter {
@@ -135,7 +135,7 @@ fn render_slur(
         sx, sy + ep_off,
     );
 
-    svg.path(&path, SLUR_COLOR, "none", 0.0);
+    svg.path(&path, svg.palette.note, "none", 0.0);
 }
 
 /// Draw continuation slurs for any still-open slurs at the end of a system.
@@ -4,6 +4,9 @@ use crate::model::*;
 use super::constants::*;
 use super::glyphs::*;
 use super::svg_builder::{SvgBuilder, vexflow_outline_to_svg};
+use super::beat_map::lookup_beat_x;
+use super::diagrams::render_inline_frame;
+use super::ids::MeasureRef;
 
 // ═══════════════════════════════════════════════════════════════════════
 // Header rendering
@@ -19,7 +22,7 @@ pub(super) fn render_header(svg: &mut SvgBuilder, score: &Score, page_width: f64
         let family = style.and_then(|s| s.font_family.as_deref());
         let font_style = style.and_then(|s| s.font_style.as_deref());
         svg.styled_text(center_x, PAGE_MARGIN_TOP + 22.0, title, size, weight,
-                        HEADER_COLOR, "middle", family, font_style);
+                        svg.palette.header, "middle", family, font_style);
     }
 
     if let Some(ref subtitle) = score.subtitle {
@@ -29,7 +32,7 @@ pub(super) fn render_header(svg: &mut SvgBuilder, score: &Score, page_width: f64
         let family = style.and_then(|s| s.font_family.as_deref());
         let font_style = style.and_then(|s| s.font_style.as_deref());
         svg.styled_text(center_x, PAGE_MARGIN_TOP + 40.0, subtitle, size, weight,
-                        HEADER_COLOR, "middle", family, font_style);
+                        svg.palette.header, "middle", family, font_style);
     }
 
     if let Some(ref composer) = score.composer {
@@ -44,7 +47,7 @@ pub(super) fn render_header(svg: &mut SvgBuilder, score: &Score, page_width: f64
         let family = style.and_then(|s| s.font_family.as_deref());
         let font_style = style.and_then(|s| s.font_style.as_deref());
         svg.styled_text(page_width - PAGE_MARGIN_RIGHT, PAGE_MARGIN_TOP + 55.0,
-                        &label, size, weight, HEADER_COLOR, "end", family, font_style);
+                        &label, size, weight, svg.palette.header, "end", family, font_style);
     }
 }
 
@@ -68,13 +71,13 @@ pub(super) fn render_brace(svg: &mut SvgBuilder, x: f64, top_y: f64, bottom_y: f
         x, bottom_y - h * 0.28,
         x, bottom_y,
     );
-    svg.path(&path, "none", NOTE_COLOR, 2.5);
+    svg.path(&path, "none", svg.palette.note, 2.5);
 }
 
 pub(super) fn render_staff_lines(svg: &mut SvgBuilder, x1: f64, x2: f64, staff_y: f64) {
     for i in 0..5 {
         let y = staff_y + i as f64 * STAFF_LINE_SPACING;
-        svg.line(x1, y, x2, y, STAFF_COLOR, STAFF_LINE_WIDTH);
+        svg.line(x1, y, x2, y, svg.palette.staff, STAFF_LINE_WIDTH);
     }
 }
 
@@ -89,7 +92,7 @@ pub(super) fn render_clef(svg: &mut SvgBuilder, x: f64, staff_y: f64, clef: &Cle
             svg.treble_clef(x + 10.0, cy);
             if clef.octave_change == Some(-1) {
                 svg.text(x + 10.0, staff_y + STAFF_HEIGHT + 16.0,
-                         "8", 9.0, "normal", STAFF_COLOR, "middle");
+                         "8", 9.0, "normal", svg.palette.staff, "middle");
             }
         }
         "F" => {
@@ -116,24 +119,24 @@ pub(super) fn render_key_signature(
         return;
     }
 
-    let is_treble = clef.map_or(true, |c| c.sign == "G");
+    let is_treble = clef.is_none_or(|c| c.sign == "G");
 
     if key.fifths > 0 {
         let positions_treble: &[f64] = &[0.0, 15.0, -5.0, 10.0, 25.0, 5.0, 20.0];
         let positions_bass: &[f64]   = &[10.0, 25.0, 5.0, 20.0, 35.0, 15.0, 30.0];
         let positions = if is_treble { positions_treble } else { positions_bass };
-        for i in 0..key.fifths.min(7) as usize {
+        for (i, &pos) in positions.iter().take(key.fifths.min(7) as usize).enumerate() {
             let sx = x + i as f64 * KEY_SIG_SHARP_SPACE;
-            let sy = staff_y + positions[i];
+            let sy = staff_y + pos;
             svg.sharp_glyph(sx, sy);
         }
     } else {
         let positions_treble: &[f64] = &[20.0, 5.0, 25.0, 10.0, 30.0, 15.0, 35.0];
         let positions_bass: &[f64]   = &[30.0, 15.0, 35.0, 20.0, 40.0, 25.0, 45.0];
         let positions = if is_treble { positions_treble } else { positions_bass };
-        for i in 0..key.fifths.unsigned_abs().min(7) as usize {
+        for (i, &pos) in positions.iter().take(key.fifths.unsigned_abs().min(7) as usize).enumerate() {
             let sx = x + i as f64 * KEY_SIG_FLAT_SPACE;
-            let sy = staff_y + positions[i];
+            let sy = staff_y + pos;
             svg.flat_glyph(sx, sy);
         }
     }
@@ -141,7 +144,7 @@ pub(super) fn render_key_signature(
 
 /// Return staff-line positions (in half-space units) for sharp key signatures.
 pub(super) fn sharp_positions(clef: Option<&Clef>) -> Vec<i32> {
-    let is_treble = clef.map_or(true, |c| c.sign == "G");
+    let is_treble = clef.is_none_or(|c| c.sign == "G");
     if is_treble {
         vec![0, 3, -1, 2, 5, 1, 4]
     } else {
@@ -151,7 +154,7 @@ pub(super) fn sharp_positions(clef: Option<&Clef>) -> Vec<i32> {
 
 /// Return staff-line positions (in half-space units) for flat key signatures.
 pub(super) fn flat_positions(clef: Option<&Clef>) -> Vec<i32> {
-    let is_treble = clef.map_or(true, |c| c.sign == "G");
+    let is_treble = clef.is_none_or(|c| c.sign == "G");
     if is_treble {
         vec![4, 1, 5, 2, 6, 3, 7]
     } else {
@@ -185,44 +188,44 @@ pub(super) fn render_tempo_marking(svg: &mut SvgBuilder, x: f64, staff_y: f64, d
         "quarter" => {
             svg.elements.push(format!(
                 "<ellipse cx=\"{:.1}\" cy=\"{:.1}\" rx=\"3.8\" ry=\"2.8\" fill=\"{}\" stroke=\"none\" transform=\"rotate(-15,{:.1},{:.1})\"/>",
-                x + 3.8, ty, NOTE_COLOR, x + 3.8, ty
+                x + 3.8, ty, svg.palette.note, x + 3.8, ty
             ));
-            svg.line(x + 7.0, ty, x + 7.0, ty - 16.0, NOTE_COLOR, 1.0);
+            svg.line(x + 7.0, ty, x + 7.0, ty - 16.0, svg.palette.note, 1.0);
             note_end_x = x + 10.0;
         }
         "half" => {
             svg.elements.push(format!(
                 "<ellipse cx=\"{:.1}\" cy=\"{:.1}\" rx=\"3.8\" ry=\"2.8\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.2\" transform=\"rotate(-15,{:.1},{:.1})\"/>",
-                x + 3.8, ty, NOTE_COLOR, x + 3.8, ty
+                x + 3.8, ty, svg.palette.note, x + 3.8, ty
             ));
-            svg.line(x + 7.0, ty, x + 7.0, ty - 16.0, NOTE_COLOR, 1.0);
+            svg.line(x + 7.0, ty, x + 7.0, ty - 16.0, svg.palette.note, 1.0);
             note_end_x = x + 10.0;
         }
         "eighth" => {
             svg.elements.push(format!(
                 "<ellipse cx=\"{:.1}\" cy=\"{:.1}\" rx=\"3.8\" ry=\"2.8\" fill=\"{}\" stroke=\"none\" transform=\"rotate(-15,{:.1},{:.1})\"/>",
-                x + 3.8, ty, NOTE_COLOR, x + 3.8, ty
+                x + 3.8, ty, svg.palette.note, x + 3.8, ty
             ));
-            svg.line(x + 7.0, ty, x + 7.0, ty - 16.0, NOTE_COLOR, 1.0);
+            svg.line(x + 7.0, ty, x + 7.0, ty - 16.0, svg.palette.note, 1.0);
             svg.elements.push(format!(
                 "<path d=\"M{:.1},{:.1} c 0.7,1.4 2.8,3.5 5,7 c 1.4,2.1 1.4,4.2 -0.7,5.6\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.0\"/>",
-                x + 7.0, ty - 16.0, NOTE_COLOR
+                x + 7.0, ty - 16.0, svg.palette.note
             ));
             note_end_x = x + 14.0;
         }
         "whole" => {
             svg.elements.push(format!(
                 "<ellipse cx=\"{:.1}\" cy=\"{:.1}\" rx=\"4.5\" ry=\"3.0\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\" transform=\"rotate(-15,{:.1},{:.1})\"/>",
-                x + 4.5, ty, NOTE_COLOR, x + 4.5, ty
+                x + 4.5, ty, svg.palette.note, x + 4.5, ty
             ));
             note_end_x = x + 11.0;
         }
         _ => {
             svg.elements.push(format!(
                 "<ellipse cx=\"{:.1}\" cy=\"{:.1}\" rx=\"3.8\" ry=\"2.8\" fill=\"{}\" stroke=\"none\" transform=\"rotate(-15,{:.1},{:.1})\"/>",
-                x + 3.8, ty, NOTE_COLOR, x + 3.8, ty
+                x + 3.8, ty, svg.palette.note, x + 3.8, ty
             ));
-            svg.line(x + 7.0, ty, x + 7.0, ty - 16.0, NOTE_COLOR, 1.0);
+            svg.line(x + 7.0, ty, x + 7.0, ty - 16.0, svg.palette.note, 1.0);
             note_end_x = x + 10.0;
         }
     }
@@ -230,25 +233,25 @@ pub(super) fn render_tempo_marking(svg: &mut SvgBuilder, x: f64, staff_y: f64, d
     if dotted {
         svg.elements.push(format!(
             "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"1.2\" fill=\"{}\"/>",
-            note_end_x, ty - 1.0, NOTE_COLOR
+            note_end_x, ty - 1.0, svg.palette.note
         ));
         note_end_x += 3.0;
     }
 
     let text = format!(" = {}", bpm as i32);
-    svg.text(note_end_x, ty + 4.0, &text, 12.0, "bold", NOTE_COLOR, "start");
+    svg.text(note_end_x, ty + 4.0, &text, 12.0, "bold", svg.palette.note, "start");
 }
 
 pub(super) fn render_segno(svg: &mut SvgBuilder, x: f64, staff_y: f64) {
     let y = staff_y - 14.0;
     let path = vexflow_outline_to_svg(SEGNO_GLYPH, SEGNO_GLYPH_SCALE, x, y);
-    svg.path(&path, NOTE_COLOR, NOTE_COLOR, 0.3);
+    svg.path(&path, svg.palette.note, svg.palette.note, 0.3);
 }
 
 pub(super) fn render_coda(svg: &mut SvgBuilder, x: f64, staff_y: f64) {
     let y = staff_y - 14.0;
     let path = vexflow_outline_to_svg(CODA_GLYPH, CODA_GLYPH_SCALE, x, y);
-    svg.path(&path, NOTE_COLOR, NOTE_COLOR, 0.3);
+    svg.path(&path, svg.palette.note, svg.palette.note, 0.3);
 }
 
 /// Check whether a text string is a jump/navigation instruction.
@@ -278,7 +281,7 @@ pub(super) fn render_jump_text(svg: &mut SvgBuilder, x: f64, staff_y: f64, dir_w
     };
     svg.elements.push(format!(
         r#"<text x="{:.1}" y="{:.1}" font-family="Times New Roman, Times, serif" font-size="13" font-weight="bold" font-style="italic" fill="{}" text-anchor="end">{}</text>"#,
-        x, y, NOTE_COLOR,
+        x, y, svg.palette.note,
         text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
     ));
 }
@@ -304,12 +307,80 @@ pub(super) fn render_direction_words(svg: &mut SvgBuilder, x: f64, staff_y: f64,
 
         svg.elements.push(format!(
             r#"<text x="{:.1}" y="{:.1}" font-family="Times New Roman, Times, serif" font-size="12" font-weight="{}" font-style="{}" fill="{}" text-anchor="start">{}</text>"#,
-            x + 4.0, y, weight, style, NOTE_COLOR,
+            x + 4.0, y, weight, style, svg.palette.note,
             text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
         ));
     }
 }
 
+// ═══════════════════════════════════════════════════════════════════════
+// Dynamics rendering
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Baseline for dynamics placed below a staff: under the staff and clear of
+/// the lowest note or stem in the measure.
+pub(super) fn dynamics_baseline_y(
+    measure: &Measure, staff_y: f64, clef: Option<&Clef>,
+    transpose_octave: i32, staff_filter: Option<i32>,
+) -> f64 {
+    use super::lyrics::measure_lowest_note_y;
+    let lowest = measure_lowest_note_y(measure, staff_y, clef, transpose_octave, staff_filter);
+    (staff_y + STAFF_HEIGHT + DYNAMICS_OFFSET_Y).max(lowest + DYNAMICS_NOTE_CLEARANCE)
+}
+
+/// Whether the measure has dynamics or hairpins drawn below staff `staff_num`.
+pub(super) fn has_dynamics_below(measure: &Measure, staff_num: usize) -> bool {
+    measure.directions.iter().any(|d| {
+        (d.dynamics.is_some() || d.wedge_type.is_some())
+            && d.staff.unwrap_or(1) == staff_num as i32
+            && d.placement.as_deref() != Some("above")
+    })
+}
+
+/// Draw dynamics markings (p, mf, sfz…) for staff `staff_num`, centred on
+/// the note at each direction's onset.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn render_dynamics(
+    svg: &mut SvgBuilder, measure: &Measure, staff_num: usize, divisions: i32,
+    beat_x_map: &[(f64, f64)], mx: f64, staff_y: f64, below_y: f64,
+) {
+    for dir in &measure.directions {
+        let Some(ref mark) = dir.dynamics else { continue };
+        if dir.staff.unwrap_or(1) != staff_num as i32 {
+            continue;
+        }
+
+        let x = if beat_x_map.is_empty() {
+            mx + 10.0
+        } else {
+            lookup_beat_x(beat_x_map, dir.onset as f64 / divisions.max(1) as f64)
+        };
+        let y = if dir.placement.as_deref() == Some("above") {
+            staff_y - DYNAMICS_ABOVE_GAP
+        } else {
+            below_y
+        };
+
+        // Marks made of dynamic letters are drawn from their outlines,
+        // anything else (other-dynamics text) as bold italic text
+        let Some(width) = dynamic_mark_width(mark) else {
+            svg.styled_text(
+                x, y, mark, DYNAMICS_FONT_SIZE, "bold", svg.palette.note, "middle",
+                Some("Times New Roman, Times, serif"), Some("italic"),
+            );
+            continue;
+        };
+        let start = svg.elements.len();
+        let mut lx = x - width / 2.0;
+        for (outline, advance) in mark.chars().filter_map(dynamic_letter_glyph) {
+            let path = vexflow_outline_to_svg(outline, DYNAMIC_GLYPH_SCALE, lx, y);
+            svg.path(&path, svg.palette.note, "none", 0.0);
+            lx += advance * DYNAMIC_GLYPH_SCALE;
+        }
+        svg.wrap(start, &format!(r#"class="dynamics" data-dynamics="{mark}""#));
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 // Time signature rendering
 // ═══════════════════════════════════════════════════════════════════════
@@ -347,7 +418,7 @@ fn render_timesig_number(svg: &mut SvgBuilder, mut x: f64, y: f64, n: i32, scale
     for &d in &digits {
         let outline = timesig_digit_glyph(d);
         let path = vexflow_outline_to_svg(outline, scale, x, y);
-        svg.path(&path, NOTE_COLOR, "none", 0.0);
+        svg.path(&path, svg.palette.note, "none", 0.0);
         x += TIMESIG_DIGIT_HA[d as usize] * scale;
     }
 }
@@ -356,9 +427,12 @@ fn render_timesig_number(svg: &mut SvgBuilder, mut x: f64, y: f64, n: i32, scale
 // Harmony (chord symbol) rendering
 // ═══════════════════════════════════════════════════════════════════════
 
+/// Draw chord symbols above the staff. Each symbol is aligned with the note
+/// at its onset; without a beat map they are spread evenly across the measure.
+#[allow(clippy::too_many_arguments)]
 pub(super) fn render_harmonies(
-    svg: &mut SvgBuilder, measure: &Measure,
-    mx: f64, mw: f64, staff_y: f64,
+    svg: &mut SvgBuilder, measure: &Measure, divisions: i32,
+    beat_x_map: &[(f64, f64)], mx: f64, mw: f64, staff_y: f64, at: MeasureRef,
 ) {
     if measure.harmonies.is_empty() {
         return;
@@ -367,30 +441,47 @@ pub(super) fn render_harmonies(
     let spacing = mw / (measure.harmonies.len() as f64 + 1.0);
 
     for (i, harmony) in measure.harmonies.iter().enumerate() {
-        let x = mx + spacing * (i as f64 + 0.5);
+        let x = if beat_x_map.is_empty() {
+            mx + spacing * (i as f64 + 0.5)
+        } else {
+            let beat = harmony.onset as f64 / divisions.max(1) as f64;
+            lookup_beat_x(beat_x_map, beat) - NOTEHEAD_RX
+        };
         let y = staff_y + CHORD_SYMBOL_OFFSET_Y;
 
-        let kind_str = match harmony.kind.as_str() {
-            "major" => "",
-            "minor" => "m",
-            "dominant" => "7",
-            "dominant-seventh" => "7",
-            "major-seventh" => "maj7",
-            "minor-seventh" => "m7",
-            "diminished" => "dim",
-            "augmented" => "aug",
-            "half-diminished" => "m7b5",
-            other => other,
-        };
+        let start = svg.elements.len();
+        if let Some(ref frame) = harmony.frame {
+            render_inline_frame(svg, frame, x, y);
+        }
+        svg.chord_text(x, y, &harmony_label(harmony), 12.0, svg.palette.chord);
+        svg.wrap(start, &at.harmony(i));
+    }
+}
 
-        let alter_str = match harmony.root.alter {
-            Some(a) if a > 0.0 => "#",
-            Some(a) if a < 0.0 => "b",
-            _ => "",
-        };
+/// The text of a chord symbol, e.g. "Am7" or "N.C.".
+pub(super) fn harmony_label(harmony: &Harmony) -> String {
+    let kind_str = match harmony.kind.as_str() {
+        "major" => "",
+        "minor" => "m",
+        "dominant" => "7",
+        "dominant-seventh" => "7",
+        "major-seventh" => "maj7",
+        "minor-seventh" => "m7",
+        "diminished" => "dim",
+        "augmented" => "aug",
+        "half-diminished" => "m7b5",
+        other => other,
+    };
+
+    let alter_str = match harmony.root.alter {
+        Some(a) if a > 0.0 => "#",
+        Some(a) if a < 0.0 => "b",
+        _ => "",
+    };
 
-        let label = format!("{}{}{}", harmony.root.step, alter_str, kind_str);
-        svg.chord_text(x, y, &label, 12.0, CHORD_COLOR);
+    match harmony.kind.as_str() {
+        "none" => "N.C.".to_string(),
+        _ => format!("{}{}{}", harmony.root.step, alter_str, kind_str),
     }
 }
 
@@ -399,10 +490,10 @@ pub(super) fn render_harmonies(
 // ═══════════════════════════════════════════════════════════════════════
 
 pub(super) fn render_barlines(
-    svg: &mut SvgBuilder, measure: &Measure,
+    svg: &mut SvgBuilder, barlines: &[Barline],
     mx: f64, mw: f64, staff_y: f64,
 ) {
-    for barline in &measure.barlines {
+    for barline in barlines {
         let bx = match barline.location.as_str() {
             "left" => mx,
             "right" => mx + mw,
@@ -411,35 +502,35 @@ pub(super) fn render_barlines(
 
         match barline.bar_style.as_deref() {
             Some("heavy-light") => {
-                svg.line(bx, staff_y, bx, staff_y + STAFF_HEIGHT, BARLINE_COLOR, 3.0);
-                svg.line(bx + 5.0, staff_y, bx + 5.0, staff_y + STAFF_HEIGHT, BARLINE_COLOR, BARLINE_WIDTH);
-                svg.circle(bx + 10.0, staff_y + 15.0, 2.0, BARLINE_COLOR);
-                svg.circle(bx + 10.0, staff_y + 25.0, 2.0, BARLINE_COLOR);
+                svg.line(bx, staff_y, bx, staff_y + STAFF_HEIGHT, svg.palette.barline, 3.0);
+                svg.line(bx + 5.0, staff_y, bx + 5.0, staff_y + STAFF_HEIGHT, svg.palette.barline, BARLINE_WIDTH);
+                svg.circle(bx + 10.0, staff_y + 15.0, 2.0, svg.palette.barline);
+                svg.circle(bx + 10.0, staff_y + 25.0, 2.0, svg.palette.barline);
             }
             Some("light-heavy") => {
-                svg.circle(bx - 10.0, staff_y + 15.0, 2.0, BARLINE_COLOR);
-                svg.circle(bx - 10.0, staff_y + 25.0, 2.0, BARLINE_COLOR);
-                svg.line(bx - 5.0, staff_y, bx - 5.0, staff_y + STAFF_HEIGHT, BARLINE_COLOR, BARLINE_WIDTH);
-                svg.line(bx, staff_y, bx, staff_y + STAFF_HEIGHT, BARLINE_COLOR, 3.0);
+                svg.circle(bx - 10.0, staff_y + 15.0, 2.0, svg.palette.barline);
+                svg.circle(bx - 10.0, staff_y + 25.0, 2.0, svg.palette.barline);
+                svg.line(bx - 5.0, staff_y, bx - 5.0, staff_y + STAFF_HEIGHT, svg.palette.barline, BARLINE_WIDTH);
+                svg.line(bx, staff_y, bx, staff_y + STAFF_HEIGHT, svg.palette.barline, 3.0);
             }
             Some("light-light") => {
-                svg.line(bx - 2.0, staff_y, bx - 2.0, staff_y + STAFF_HEIGHT, BARLINE_COLOR, BARLINE_WIDTH);
-                svg.line(bx + 2.0, staff_y, bx + 2.0, staff_y + STAFF_HEIGHT, BARLINE_COLOR, BARLINE_WIDTH);
+                svg.line(bx - 2.0, staff_y, bx - 2.0, staff_y + STAFF_HEIGHT, svg.palette.barline, BARLINE_WIDTH);
+                svg.line(bx + 2.0, staff_y, bx + 2.0, staff_y + STAFF_HEIGHT, svg.palette.barline, BARLINE_WIDTH);
             }
             _ => {
                 if let Some(ref repeat) = barline.repeat {
                     match repeat.direction.as_str() {
                         "forward" => {
-                            svg.line(bx, staff_y, bx, staff_y + STAFF_HEIGHT, BARLINE_COLOR, 3.0);
-                            svg.line(bx + 5.0, staff_y, bx + 5.0, staff_y + STAFF_HEIGHT, BARLINE_COLOR, BARLINE_WIDTH);
-                            svg.circle(bx + 10.0, staff_y + 15.0, 2.0, BARLINE_COLOR);
-                            svg.circle(bx + 10.0, staff_y + 25.0, 2.0, BARLINE_COLOR);
+                            svg.line(bx, staff_y, bx, staff_y + STAFF_HEIGHT, svg.palette.barline, 3.0);
+                            svg.line(bx + 5.0, staff_y, bx + 5.0, staff_y + STAFF_HEIGHT, svg.palette.barline, BARLINE_WIDTH);
+                            svg.circle(bx + 10.0, staff_y + 15.0, 2.0, svg.palette.barline);
+                            svg.circle(bx + 10.0, staff_y + 25.0, 2.0, svg.palette.barline);
                         }
                         "backward" => {
-                            svg.circle(bx - 10.0, staff_y + 15.0, 2.0, BARLINE_COLOR);
-                            svg.circle(bx - 10.0, staff_y + 25.0, 2.0, BARLINE_COLOR);
-                            svg.line(bx - 5.0, staff_y, bx - 5.0, staff_y + STAFF_HEIGHT, BARLINE_COLOR, BARLINE_WIDTH);
-                            svg.line(bx, staff_y, bx, staff_y + STAFF_HEIGHT, BARLINE_COLOR, 3.0);
+                            svg.circle(bx - 10.0, staff_y + 15.0, 2.0, svg.palette.barline);
+                            svg.circle(bx - 10.0, staff_y + 25.0, 2.0, svg.palette.barline);
+                            svg.line(bx - 5.0, staff_y, bx - 5.0, staff_y + STAFF_HEIGHT, svg.palette.barline, BARLINE_WIDTH);
+                            svg.line(bx, staff_y, bx, staff_y + STAFF_HEIGHT, svg.palette.barline, 3.0);
                         }
                         _ => {}
                     }
@@ -452,9 +543,9 @@ pub(super) fn render_barlines(
                 let text = ending.text.as_deref()
                     .unwrap_or(&ending.number);
                 svg.text(bx + 5.0, staff_y - 10.0,
-                         &format!("{}.", text), 10.0, "normal", BARLINE_COLOR, "start");
-                svg.line(bx, staff_y - 5.0, bx, staff_y - 15.0, BARLINE_COLOR, BARLINE_WIDTH);
-                svg.line(bx, staff_y - 15.0, bx + mw, staff_y - 15.0, BARLINE_COLOR, BARLINE_WIDTH);
+                         &format!("{}.", text), 10.0, "normal", svg.palette.barline, "start");
+                svg.line(bx, staff_y - 5.0, bx, staff_y - 15.0, svg.palette.barline, BARLINE_WIDTH);
+                svg.line(bx, staff_y - 15.0, bx + mw, staff_y - 15.0, svg.palette.barline, BARLINE_WIDTH);
             }
         }
     }
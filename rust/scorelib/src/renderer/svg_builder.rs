@@ -4,6 +4,7 @@
 
 use super::constants::*;
 use super::glyphs::*;
+use super::options::{Palette, RenderOptions};
 
 // ═══════════════════════════════════════════════════════════════════════
 // SvgBuilder
@@ -11,30 +12,55 @@ use super::glyphs::*;
 
 pub(super) struct SvgBuilder {
     pub(super) elements: Vec<String>,
+    /// Colors of the theme being drawn
+    pub(super) palette: Palette,
     width: f64,
     height: f64,
+    /// Font lists for text and for chord symbols
+    text_font: String,
+    chord_font: String,
+    /// Factor the drawing is enlarged by when it is built
+    scale: f64,
 }
 
 impl SvgBuilder {
     pub(super) fn new(width: f64, height: f64) -> Self {
+        Self::with_options(width, height, &RenderOptions::default())
+    }
+
+    /// A builder drawing in the theme and fonts of `options`, enlarged by
+    /// its staff scale.  `width` and `height` are before scaling.
+    pub(super) fn with_options(width: f64, height: f64, options: &RenderOptions) -> Self {
         Self {
             elements: Vec::new(),
+            palette: options.theme.palette(),
             width,
             height,
+            text_font: options.font.text(),
+            chord_font: options.font.chords(),
+            scale: options.scale(),
         }
     }
 
     pub(super) fn build(self) -> String {
+        let (width, height) = (self.width * self.scale, self.height * self.scale);
         let mut svg = format!(
-            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}" width="{}" height="{}" style="font-family: 'Georgia', 'Times New Roman', serif;">"#,
-            self.width, self.height, self.width, self.height
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}" width="{}" height="{}" style="font-family: {};">"#,
+            width, height, width, height, self.text_font
         );
         svg.push('\n');
+        let scaled = self.scale != 1.0;
+        if scaled {
+            svg.push_str(&format!("<g transform=\"scale({})\">\n", self.scale));
+        }
         for el in &self.elements {
             svg.push_str("  ");
             svg.push_str(el);
             svg.push('\n');
         }
+        if scaled {
+            svg.push_str("</g>\n");
+        }
         svg.push_str("</svg>\n");
         svg
     }
@@ -46,6 +72,7 @@ impl SvgBuilder {
         ));
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn rect(&mut self, x: f64, y: f64, w: f64, h: f64, fill: &str, stroke: &str, stroke_width: f64) {
         if stroke_width > 0.0 {
             self.elements.push(format!(
@@ -67,6 +94,46 @@ impl SvgBuilder {
         ));
     }
 
+    /// Elements drawn enlarged by `scale`, then shifted by `dx`, `dy`.
+    pub(super) fn group(&mut self, dx: f64, dy: f64, scale: f64, elements: &[String]) {
+        let mut group = if scale == 1.0 {
+            format!(r#"<g transform="translate({:.1},{:.1})">"#, dx, dy)
+        } else {
+            format!(r#"<g transform="translate({:.1},{:.1}) scale({})">"#, dx, dy, scale)
+        };
+        for el in elements {
+            group.push_str("\n    ");
+            group.push_str(el);
+        }
+        group.push_str("\n  </g>");
+        self.elements.push(group);
+    }
+
+    /// Gather the elements drawn since `start` into one group with
+    /// `attributes`, such as the ids of `ids::MeasureRef`.  Nothing is
+    /// added when nothing was drawn.
+    pub(super) fn wrap(&mut self, start: usize, attributes: &str) {
+        if start >= self.elements.len() {
+            return;
+        }
+        let mut group = format!("<g {}>", attributes);
+        for el in self.elements.drain(start..) {
+            group.push_str("\n    ");
+            group.push_str(&el);
+        }
+        group.push_str("\n  </g>");
+        self.elements.push(group);
+    }
+
+    /// An unfilled circle.
+    pub(super) fn ring(&mut self, cx: f64, cy: f64, r: f64, stroke: &str, stroke_width: f64) {
+        self.elements.push(format!(
+            r#"<circle cx="{:.1}" cy="{:.1}" r="{:.1}" fill="none" stroke="{}" stroke-width="{:.1}"/>"#,
+            cx, cy, r, stroke, stroke_width
+        ));
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn text(&mut self, x: f64, y: f64, content: &str, size: f64, weight: &str, fill: &str, anchor: &str) {
         let escaped = content
             .replace('&', "&amp;")
@@ -79,6 +146,7 @@ impl SvgBuilder {
     }
 
     /// Render a styled text element with optional font-family and font-style attributes.
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn styled_text(
         &mut self, x: f64, y: f64, content: &str,
         size: f64, weight: &str, fill: &str, anchor: &str,
@@ -105,13 +173,18 @@ impl SvgBuilder {
 
     /// Render chord symbols matching OSMD style: Times New Roman, normal weight, no letter-spacing
     pub(super) fn chord_text(&mut self, x: f64, y: f64, content: &str, size: f64, fill: &str) {
+        self.chord_text_anchored(x, y, content, size, fill, "start");
+    }
+
+    /// A chord symbol anchored at its start, middle or end.
+    pub(super) fn chord_text_anchored(&mut self, x: f64, y: f64, content: &str, size: f64, fill: &str, anchor: &str) {
         let escaped = content
             .replace('&', "&amp;")
             .replace('<', "&lt;")
             .replace('>', "&gt;");
         self.elements.push(format!(
-            r#"<text x="{:.1}" y="{:.1}" font-family="Times New Roman, serif" font-size="{:.0}" font-weight="normal" fill="{}" text-anchor="start">{}</text>"#,
-            x, y, size, fill, escaped
+            r#"<text x="{:.1}" y="{:.1}" font-family="{}" font-size="{:.0}" font-weight="normal" fill="{}" text-anchor="{}">{}</text>"#,
+            x, y, self.chord_font, size, fill, anchor, escaped
         ));
     }
 
@@ -122,23 +195,40 @@ impl SvgBuilder {
         ));
     }
 
-    pub(super) fn notehead(&mut self, cx: f64, cy: f64, filled: bool, _is_whole: bool) {
+    pub(super) fn notehead(&mut self, cx: f64, cy: f64, filled: bool, _is_whole: bool, color: &str) {
         let rx = NOTEHEAD_RX;
         let ry = NOTEHEAD_RY;
         if filled {
             self.elements.push(format!(
                 r#"<ellipse cx="{:.1}" cy="{:.1}" rx="{:.1}" ry="{:.1}" fill="{}" stroke="none" stroke-width="0" transform="rotate(-15,{:.1},{:.1})"/>"#,
-                cx, cy, rx, ry, NOTE_COLOR, cx, cy
+                cx, cy, rx, ry, color, cx, cy
             ));
         } else {
             let sw = 2.0;
             self.elements.push(format!(
                 r#"<ellipse cx="{:.1}" cy="{:.1}" rx="{:.1}" ry="{:.1}" fill="none" stroke="{}" stroke-width="{:.1}" transform="rotate(-15,{:.1},{:.1})"/>"#,
-                cx, cy, rx - sw / 2.0, ry - sw / 2.0, NOTE_COLOR, sw, cx, cy
+                cx, cy, rx - sw / 2.0, ry - sw / 2.0, color, sw, cx, cy
             ));
         }
     }
 
+    /// A rhythm slash: a thick diagonal stroke for filled values, an
+    /// outlined parallelogram for halves and wholes.
+    pub(super) fn slash_notehead(&mut self, cx: f64, cy: f64, filled: bool) {
+        let (dx, dy, w) = (NOTEHEAD_RX, NOTEHEAD_RY * 2.0, 3.0);
+        let d = format!(
+            "M{:.1},{:.1} L{:.1},{:.1} L{:.1},{:.1} L{:.1},{:.1} Z",
+            cx - dx, cy + dy,
+            cx - dx + w, cy + dy,
+            cx + dx, cy - dy,
+            cx + dx - w, cy - dy,
+        );
+        match filled {
+            true => self.path(&d, self.palette.note, "none", 0.0),
+            false => self.path(&d, "none", self.palette.note, 1.2),
+        }
+    }
+
     pub(super) fn beam_line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, thickness: f64) {
         let half = thickness / 2.0;
         let dx = x2 - x1;
@@ -156,7 +246,7 @@ impl SvgBuilder {
         );
         self.elements.push(format!(
             r#"<path d="{}" fill="{}"/>"#,
-            path, NOTE_COLOR
+            path, self.palette.note
         ));
     }
 
@@ -205,7 +295,7 @@ c0,5.986-1.937,15.431-5.551,20.376L164.55,209.161z";
 
         self.elements.push(format!(
             r#"<g transform="translate({:.2},{:.2}) scale({})"><path d="{}" fill="{}"/><path d="{}" fill="{}"/></g>"#,
-            tx, ty, scale, p1, NOTE_COLOR, p2, NOTE_COLOR
+            tx, ty, scale, p1, self.palette.note, p2, self.palette.note
         ));
     }
 
@@ -247,14 +337,14 @@ C496.768,229.887,478.256,211.365,455.486,211.365z";
 
         self.elements.push(format!(
             r#"<g transform="translate({:.2},{:.2}) scale({})"><path d="{}" fill="{}"/><path d="{}" fill="{}"/><path d="{}" fill="{}"/></g>"#,
-            tx, ty, scale, p1, NOTE_COLOR, p2, NOTE_COLOR, p3, NOTE_COLOR
+            tx, ty, scale, p1, self.palette.note, p2, self.palette.note, p3, self.palette.note
         ));
     }
 
     pub(super) fn alto_clef(&mut self, _x: f64, y: f64) {
         let x = _x;
-        self.rect(x - 2.0, y - 20.0, 3.0, 80.0, NOTE_COLOR, "none", 0.0);
-        self.rect(x + 4.0, y - 20.0, 1.5, 80.0, NOTE_COLOR, "none", 0.0);
+        self.rect(x - 2.0, y - 20.0, 3.0, 80.0, self.palette.note, "none", 0.0);
+        self.rect(x + 4.0, y - 20.0, 1.5, 80.0, self.palette.note, "none", 0.0);
     }
 
     /// Render a sharp accidental glyph using VexFlow font outline.
@@ -263,7 +353,7 @@ C496.768,229.887,478.256,211.365,455.486,211.365z";
         let path = vexflow_outline_to_svg(SHARP_GLYPH, s, x, y);
         self.elements.push(format!(
             r#"<path d="{}" fill="{}" stroke="none"/>"#,
-            path, NOTE_COLOR
+            path, self.palette.note
         ));
     }
 
@@ -273,7 +363,7 @@ C496.768,229.887,478.256,211.365,455.486,211.365z";
         let path = vexflow_outline_to_svg(FLAT_GLYPH, s, x, y);
         self.elements.push(format!(
             r#"<path d="{}" fill="{}" stroke="none"/>"#,
-            path, NOTE_COLOR
+            path, self.palette.note
         ));
     }
 
@@ -283,7 +373,7 @@ C496.768,229.887,478.256,211.365,455.486,211.365z";
         let path = vexflow_outline_to_svg(NATURAL_GLYPH, s, x, y);
         self.elements.push(format!(
             r#"<path d="{}" fill="{}" stroke="none"/>"#,
-            path, NOTE_COLOR
+            path, self.palette.note
         ));
     }
 
@@ -293,7 +383,7 @@ C496.768,229.887,478.256,211.365,455.486,211.365z";
         let path = vexflow_outline_to_svg(DOUBLE_SHARP_GLYPH, s, x, y);
         self.elements.push(format!(
             r#"<path d="{}" fill="{}" stroke="none"/>"#,
-            path, NOTE_COLOR
+            path, self.palette.note
         ));
     }
 
@@ -303,7 +393,7 @@ C496.768,229.887,478.256,211.365,455.486,211.365z";
         let path = vexflow_outline_to_svg(DOUBLE_FLAT_GLYPH, s, x, y);
         self.elements.push(format!(
             r#"<path d="{}" fill="{}" stroke="none"/>"#,
-            path, NOTE_COLOR
+            path, self.palette.note
         ));
     }
 }
@@ -0,0 +1,97 @@
+//! Tablature staves — string lines, the TAB clef and fret numbers.
+//!
+//! A tab staff takes the same height as a five-line staff, so the layout
+//! treats it like any other: its lines are spread evenly over
+//! `STAFF_HEIGHT`, with the highest-pitched string on top.
+
+use crate::model::*;
+use super::constants::*;
+use super::svg_builder::SvgBuilder;
+use super::beat_map::note_x_positions_from_beat_map;
+use super::ids::MeasureRef;
+
+const TAB_CLEF_SIZE: f64 = 13.0;
+const FRET_SIZE: f64 = 12.0;
+const GRACE_FRET_SIZE: f64 = 9.0;
+/// Width of one fret digit, used to clear the line behind it.
+const FRET_DIGIT_WIDTH: f64 = 7.0;
+const DEFAULT_TAB_LINES: usize = 6;
+
+/// Number of lines of each staff of a part that is a tab staff, indexed
+/// by staff number (index 0 unused); `None` for ordinary staves.
+pub(super) fn tab_line_counts(part: &Part, num_staves: usize) -> Vec<Option<usize>> {
+    (0..=num_staves)
+        .map(|staff| {
+            if staff == 0 || !part.is_tab_staff(staff as i32) {
+                return None;
+            }
+            let details = part.measures.iter()
+                .filter_map(|m| m.attributes.as_ref())
+                .flat_map(|a| &a.staff_details)
+                .find(|d| d.number == staff as i32);
+            let lines = details
+                .and_then(|d| d.lines.map(|l| l as usize).or((!d.tuning.is_empty()).then_some(d.tuning.len())))
+                .unwrap_or(DEFAULT_TAB_LINES);
+            Some(lines.max(2))
+        })
+        .collect()
+}
+
+fn line_spacing(lines: usize) -> f64 {
+    STAFF_HEIGHT / (lines.max(2) - 1) as f64
+}
+
+pub(super) fn render_tab_lines(svg: &mut SvgBuilder, x1: f64, x2: f64, staff_y: f64, lines: usize) {
+    let spacing = line_spacing(lines);
+    for i in 0..lines {
+        let y = staff_y + i as f64 * spacing;
+        svg.line(x1, y, x2, y, svg.palette.staff, STAFF_LINE_WIDTH);
+    }
+}
+
+/// "TAB" written downward at the start of the staff.
+pub(super) fn render_tab_clef(svg: &mut SvgBuilder, x: f64, staff_y: f64) {
+    let top = staff_y + STAFF_HEIGHT / 2.0 - TAB_CLEF_SIZE * 1.5 + TAB_CLEF_SIZE * 0.85;
+    for (i, letter) in ["T", "A", "B"].iter().enumerate() {
+        svg.text(x + 10.0, top + i as f64 * TAB_CLEF_SIZE, letter, TAB_CLEF_SIZE, "bold", svg.palette.note, "middle");
+    }
+}
+
+/// Fret numbers of the notes on this staff, each on its string line with
+/// the line cleared behind it.  Tied continuations are shown in
+/// parentheses and grace notes smaller.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn render_tab_notes(
+    svg: &mut SvgBuilder,
+    measure: &Measure,
+    staff_y: f64,
+    lines: usize,
+    divisions: i32,
+    staff_filter: Option<i32>,
+    beat_x_map: &[(f64, f64)],
+    at: MeasureRef,
+) {
+    let spacing = line_spacing(lines);
+    let note_xs = note_x_positions_from_beat_map(measure, divisions, beat_x_map);
+
+    for (i, (note, &x)) in measure.notes.iter().zip(&note_xs).enumerate() {
+        if staff_filter.is_some_and(|sf| note.staff.unwrap_or(1) != sf) {
+            continue;
+        }
+        let (Some(string), Some(fret)) = (note.string, note.fret) else {
+            continue;
+        };
+        if string < 1 || string as usize > lines {
+            continue;
+        }
+
+        let y = staff_y + (string - 1) as f64 * spacing;
+        let label = if note.tie_stop { format!("({})", fret) } else { fret.to_string() };
+        let size = if note.grace { GRACE_FRET_SIZE } else { FRET_SIZE };
+        let width = label.len() as f64 * FRET_DIGIT_WIDTH * size / FRET_SIZE;
+        let start = svg.elements.len();
+        svg.rect(x - width / 2.0, y - size / 2.0, width, size, svg.palette.background, "none", 0.0);
+        svg.text(x, y + size * 0.35, &label, size, "normal", svg.palette.note, "middle");
+        svg.wrap(start, &at.note(i, note));
+    }
+}
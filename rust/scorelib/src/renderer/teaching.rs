@@ -0,0 +1,252 @@
+//! Aids for beginners — note names and solfège on or under the noteheads,
+//! noteheads colored by pitch class, and finger numbers.
+//!
+//! Names come from each note's written `Pitch` and the key signature in
+//! force, so a transposed score (whose key and spelling
+//! `transpose_score` updates) is named in its new key: letters and fixed
+//! do follow the new pitches, movable do stays on the same degrees.
+
+use crate::model::*;
+use super::constants::*;
+use super::svg_builder::SvgBuilder;
+use super::beat_map::pitch_to_staff_y;
+use super::jianpu::{key_alter, step_index, tonic_step};
+use super::options::{LabelPosition, NoteNames, RenderOptions};
+
+/// Baseline of the note-name row below the bottom staff line.
+const NAME_ROW_BELOW_STAFF: f64 = 24.0;
+/// Smallest gap between a notehead under the staff and its name.
+const NAME_NOTE_GAP: f64 = 13.0;
+const NAME_FONT_SIZE: f64 = 9.0;
+/// Vertical step between the names of a chord's notes.
+const NAME_ROW_HEIGHT: f64 = 10.0;
+/// Room the layout keeps under each system for the names row.
+pub(super) const NAME_ROOM: f64 = NAME_ROW_BELOW_STAFF;
+
+/// Clearance between the staff, notes or stem and the first finger number.
+const FINGER_GAP: f64 = 5.0;
+const FINGER_FONT_SIZE: f64 = 10.0;
+/// Vertical step between the finger numbers of a chord.
+const FINGER_STACK_STEP: f64 = 10.0;
+
+/// Boomwhacker tube colors for C, C♯, D … B, each with a text color
+/// that can be read on it.
+const BOOMWHACKER: [(&str, &str); 12] = [
+    ("#e21c48", "white"),
+    ("#f26622", "white"),
+    ("#f99d1c", "black"),
+    ("#ffcc33", "black"),
+    ("#fff32b", "black"),
+    ("#bcd85f", "black"),
+    ("#62bc47", "white"),
+    ("#009c95", "white"),
+    ("#0071bb", "white"),
+    ("#5e50a1", "white"),
+    ("#8d5ba6", "white"),
+    ("#cf3e96", "white"),
+];
+
+const FIXED_DO: [&str; 7] = ["Do", "Re", "Mi", "Fa", "Sol", "La", "Si"];
+const MOVABLE_DO: [&str; 7] = ["Do", "Re", "Mi", "Fa", "Sol", "La", "Ti"];
+/// Chromatic syllables for a degree raised or lowered by a semitone;
+/// None where the accidental is written instead (Mi♯, Do♭…).
+const RAISED: [Option<&str>; 7] = [Some("Di"), Some("Ri"), None, Some("Fi"), Some("Si"), Some("Li"), None];
+const LOWERED: [Option<&str>; 7] = [None, Some("Ra"), Some("Me"), None, Some("Se"), Some("Le"), Some("Te")];
+
+/// Name of a pitch in `names`, in a key of `fifths` sharps (negative for
+/// flats).  Movable do takes Do from the major tonic of the key, so minor
+/// keys are sung from La.  None when names are off.
+pub fn note_name(pitch: &Pitch, fifths: i32, names: NoteNames) -> Option<String> {
+    let step = step_index(&pitch.step)?;
+    let alter = pitch.alter.unwrap_or(0.0).round() as i32;
+    match names {
+        NoteNames::Off => None,
+        NoteNames::Letters => Some(format!("{}{}", pitch.step, accidental(alter))),
+        NoteNames::FixedDo => Some(format!("{}{}", FIXED_DO[step as usize], accidental(alter))),
+        NoteNames::MovableDo => {
+            let degree = (step - tonic_step(fifths)).rem_euclid(7) as usize;
+            let chromatic = alter - key_alter(fifths, step);
+            let syllable = match chromatic {
+                0 => Some(MOVABLE_DO[degree]),
+                1 => RAISED[degree],
+                -1 => LOWERED[degree],
+                _ => None,
+            };
+            Some(match syllable {
+                Some(s) => s.to_string(),
+                None => format!("{}{}", MOVABLE_DO[degree], accidental(chromatic)),
+            })
+        }
+    }
+}
+
+fn accidental(alter: i32) -> &'static str {
+    match alter {
+        1 => "♯",
+        2 => "♯♯",
+        -1 => "♭",
+        -2 => "♭♭",
+        _ => "",
+    }
+}
+
+/// Boomwhacker color of a pitch and the text color for it.
+fn pitch_colors(pitch: &Pitch) -> (&'static str, &'static str) {
+    BOOMWHACKER[pitch.to_midi().rem_euclid(12) as usize]
+}
+
+/// Fill of a pitch's notehead: its Boomwhacker color when noteheads are
+/// colored, otherwise the note color.
+pub(super) fn notehead_color(svg: &SvgBuilder, pitch: &Pitch, options: &RenderOptions) -> &'static str {
+    if options.colored_noteheads {
+        pitch_colors(pitch).0
+    } else {
+        svg.palette.note
+    }
+}
+
+/// Write the name of note `i` inside its notehead at (`nx`, `note_y`) or
+/// in the row under the staff.  The names of a chord are stacked under
+/// each other, highest note first.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn render_note_name(
+    svg: &mut SvgBuilder,
+    measure: &Measure,
+    i: usize,
+    nx: f64,
+    note_y: f64,
+    filled: bool,
+    staff_y: f64,
+    clef: Option<&Clef>,
+    transpose_octave: i32,
+    fifths: i32,
+    options: &RenderOptions,
+) {
+    let note = &measure.notes[i];
+    let Some(pitch) = note.pitch.as_ref() else { return };
+    let Some(name) = note_name(pitch, fifths, options.note_names) else { return };
+
+    match options.note_name_position {
+        LabelPosition::Inside => {
+            let size = match name.chars().count() {
+                1 => 7.0,
+                2 => 6.0,
+                _ => 5.0,
+            };
+            let color = match (filled, options.colored_noteheads) {
+                (true, true) => pitch_colors(pitch).1,
+                (true, false) => svg.palette.background,
+                (false, _) => svg.palette.note,
+            };
+            svg.text(nx, note_y + size * 0.35, &name, size, "bold", color, "middle");
+        }
+        LabelPosition::Below => {
+            let chord = chord_members(measure, i);
+            let mut lowest_y = note_y;
+            let mut rank = 0;
+            for member in &measure.notes[chord] {
+                let Some(p) = member.pitch.as_ref() else { continue };
+                lowest_y = lowest_y.max(staff_y + pitch_to_staff_y(p, clef, transpose_octave));
+                if p.to_midi() > pitch.to_midi() {
+                    rank += 1;
+                }
+            }
+            let y = name_row_y(staff_y, lowest_y) + rank as f64 * NAME_ROW_HEIGHT;
+            svg.text(nx, y, &name, NAME_FONT_SIZE, "normal", svg.palette.note, "middle");
+        }
+    }
+}
+
+/// Baseline of the first name under a staff whose chord reaches down to
+/// `lowest_y`.
+fn name_row_y(staff_y: f64, lowest_y: f64) -> f64 {
+    (staff_y + STAFF_HEIGHT + NAME_ROW_BELOW_STAFF).max(lowest_y + NAME_NOTE_GAP)
+}
+
+/// Indices of the notes sounding with note `i`: its principal note and
+/// the chord notes that follow it.
+fn chord_members(measure: &Measure, i: usize) -> std::ops::Range<usize> {
+    let start = (0..=i).rev().find(|&j| !measure.notes[j].chord).unwrap_or(0);
+    let end = (i + 1..measure.notes.len())
+        .find(|&j| !measure.notes[j].chord)
+        .unwrap_or(measure.notes.len());
+    start..end
+}
+
+/// Bottom of the note names under a staff, for keeping lyrics clear of
+/// them.  `lowest_y` is the lowest notehead or stem of the measure.
+pub(super) fn names_bottom(
+    measure: &Measure,
+    staff_y: f64,
+    lowest_y: f64,
+    staff_filter: Option<i32>,
+) -> f64 {
+    let mut rows = 1;
+    let mut run = 0;
+    for note in &measure.notes {
+        if staff_filter.is_some_and(|sf| note.staff.unwrap_or(1) != sf) || note.rest || note.grace {
+            continue;
+        }
+        run = if note.chord { run + 1 } else { 1 };
+        rows = rows.max(run);
+    }
+    name_row_y(staff_y, lowest_y) + (rows - 1) as f64 * NAME_ROW_HEIGHT + 3.0
+}
+
+/// Draw the finger numbers of every note on this staff next to its
+/// chord, on the side opposite the stem and beyond any articulations,
+/// stacked so they read top to bottom like the notes.  `stem_dirs[i]` is
+/// the stem direction used for note `i`.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn render_fingerings(
+    svg: &mut SvgBuilder,
+    measure: &Measure,
+    note_positions: &[f64],
+    stem_dirs: &[Option<bool>],
+    staff_y: f64,
+    clef: Option<&Clef>,
+    transpose_octave: i32,
+    staff_filter: Option<i32>,
+) {
+    for (i, note) in measure.notes.iter().enumerate() {
+        if note.chord || note.rest || note.grace {
+            continue;
+        }
+        if staff_filter.is_some_and(|sf| note.staff.unwrap_or(1) != sf) {
+            continue;
+        }
+        let members = &measure.notes[chord_members(measure, i)];
+        let mut fingered: Vec<(f64, &str)> = Vec::new();
+        let (mut min_y, mut max_y) = (f64::MAX, f64::MIN);
+        for member in members {
+            let Some(p) = member.pitch.as_ref() else { continue };
+            let y = staff_y + pitch_to_staff_y(p, clef, transpose_octave);
+            min_y = min_y.min(y);
+            max_y = max_y.max(y);
+            if let Some(finger) = member.fingering.as_deref() {
+                fingered.push((y, finger));
+            }
+        }
+        if fingered.is_empty() {
+            continue;
+        }
+        let below = stem_dirs[i] == Some(true);
+        // Nearest the chord first: its lowest note above, its highest below
+        if below {
+            fingered.sort_by(|a, b| a.0.total_cmp(&b.0));
+        } else {
+            fingered.sort_by(|a, b| b.0.total_cmp(&a.0));
+        }
+        let marked = members.iter().any(|m| !m.articulations.is_empty());
+        let clearance = FINGER_GAP + if marked { FINGER_STACK_STEP } else { 0.0 };
+        let (mut y, step) = if below {
+            (max_y + NOTEHEAD_RY + clearance + FINGER_FONT_SIZE * 0.7, FINGER_STACK_STEP)
+        } else {
+            (min_y - NOTEHEAD_RY - clearance, -FINGER_STACK_STEP)
+        };
+        for (_, finger) in fingered {
+            svg.text(note_positions[i], y, finger, FINGER_FONT_SIZE, "bold", svg.palette.note, "middle");
+            y += step;
+        }
+    }
+}
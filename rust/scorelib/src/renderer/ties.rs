@@ -0,0 +1,168 @@
+//! Tie rendering.
+//!
+//! A tie joins two noteheads of the same pitch.  Open ties are tracked per
+//! staff, keyed by MIDI pitch, so every tied member of a chord gets its own
+//! arc.  A tie still open at the end of a system is drawn to the system's
+//! edge and picked up again at the start of the next one, the same way
+//! open slurs are carried across systems.
+
+use std::collections::HashMap;
+
+use crate::model::*;
+use super::constants::*;
+use super::svg_builder::SvgBuilder;
+use super::beat_map::{pitch_to_staff_y, note_x_positions_from_beat_map};
+
+/// Gap between a notehead's edge and the end of its tie.
+const TIE_NOTE_GAP: f64 = 1.5;
+/// Distance of the tie ends from the notehead centre line.
+const TIE_Y_OFFSET: f64 = 3.5;
+const TIE_ENDPOINT_THICKNESS: f64 = 0.4;
+const TIE_MID_THICKNESS: f64 = 1.4;
+const TIE_HEIGHT_FACTOR: f64 = 0.12;
+const TIE_MIN_HEIGHT: f64 = 3.0;
+const TIE_MAX_HEIGHT: f64 = 9.0;
+
+/// Recorded start of an open tie.
+#[derive(Clone, Debug)]
+pub(super) struct TieStart {
+    pub(super) x: f64,
+    /// Notehead y relative to the top staff line, so the tie can move to
+    /// another system.
+    pub(super) y_offset: f64,
+    /// Whether the arc curves upward.
+    pub(super) above: bool,
+    /// Index of the measure holding the starting note.
+    pub(super) measure_idx: usize,
+}
+
+/// Process the tie starts and stops of one measure on one staff, drawing
+/// every tie that closes here.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn collect_and_render_ties_for_measure(
+    svg: &mut SvgBuilder,
+    measure: &Measure,
+    measure_idx: usize,
+    staff_y: f64,
+    clef: Option<&Clef>,
+    divisions: i32,
+    transpose_octave: i32,
+    staff_filter: Option<i32>,
+    beat_x_map: &[(f64, f64)],
+    open_ties: &mut HashMap<i32, TieStart>,
+) {
+    // A tie ends at the next measure at the latest; drop any whose stop
+    // never came (e.g. a tie into a repeat that jumps elsewhere)
+    open_ties.retain(|_, t| t.measure_idx + 1 >= measure_idx);
+    if measure.notes.is_empty() {
+        return;
+    }
+
+    let note_positions = note_x_positions_from_beat_map(measure, divisions, beat_x_map);
+    let middle_line = staff_y + 20.0;
+
+    let mut i = 0;
+    while i < measure.notes.len() {
+        // A principal note and the chord notes that follow it
+        let end = i + 1 + measure.notes[i + 1..].iter().take_while(|n| n.chord).count();
+        let group = &measure.notes[i..end];
+        let nx = note_positions[i];
+        i = end;
+
+        let principal = &group[0];
+        if principal.rest || principal.grace {
+            continue;
+        }
+        if staff_filter.is_some_and(|sf| principal.staff.unwrap_or(1) != sf) {
+            continue;
+        }
+
+        let ys: Vec<Option<f64>> = group.iter()
+            .map(|n| n.pitch.as_ref().map(|p| staff_y + pitch_to_staff_y(p, clef, transpose_octave)))
+            .collect();
+        let min_y = ys.iter().flatten().cloned().fold(f64::MAX, f64::min);
+        let max_y = ys.iter().flatten().cloned().fold(f64::MIN, f64::max);
+        if min_y > max_y {
+            continue;
+        }
+        let stem_up = match principal.stem.as_deref() {
+            Some("up") => true,
+            Some("down") => false,
+            _ => (min_y + max_y) / 2.0 >= middle_line,
+        };
+
+        for (note, y) in group.iter().zip(&ys) {
+            let (Some(pitch), Some(y)) = (note.pitch.as_ref(), *y) else { continue };
+            let key = pitch.to_midi();
+
+            if note.tie_stop {
+                if let Some(start) = open_ties.remove(&key) {
+                    render_tie(svg, &start, nx - NOTEHEAD_RX - TIE_NOTE_GAP, staff_y);
+                }
+            }
+            if note.tie_start {
+                // Single notes curve away from the stem; in a chord the
+                // upper half curves up and the lower half down
+                let above = if group.len() == 1 || (y - (min_y + max_y) / 2.0).abs() < 0.5 {
+                    !stem_up
+                } else {
+                    y < (min_y + max_y) / 2.0
+                };
+                open_ties.insert(key, TieStart {
+                    x: nx + NOTEHEAD_RX + TIE_NOTE_GAP,
+                    y_offset: y - staff_y,
+                    above,
+                    measure_idx,
+                });
+            }
+        }
+    }
+}
+
+/// Draw the part of every still-open tie that lies on this system.
+/// Only ties started in the system's last measure continue.
+pub(super) fn render_open_tie_continuations(
+    svg: &mut SvgBuilder,
+    open_ties: &mut HashMap<i32, TieStart>,
+    last_measure_idx: usize,
+    system_x_end: f64,
+    staff_y: f64,
+) {
+    open_ties.retain(|_, t| t.measure_idx == last_measure_idx);
+    for start in open_ties.values() {
+        render_tie(svg, start, system_x_end, staff_y);
+    }
+}
+
+/// Draw a tie from `start` to `end_x` as a filled crescent.
+fn render_tie(svg: &mut SvgBuilder, start: &TieStart, end_x: f64, staff_y: f64) {
+    if end_x - start.x < 2.0 {
+        return;
+    }
+    let y_dir = if start.above { -1.0 } else { 1.0 };
+    let y = staff_y + start.y_offset + y_dir * TIE_Y_OFFSET;
+
+    let sx = start.x;
+    let ex = end_x;
+    let dx = ex - sx;
+    let height = (dx * TIE_HEIGHT_FACTOR).clamp(TIE_MIN_HEIGHT, TIE_MAX_HEIGHT);
+
+    let cp1x = sx + dx * 0.25;
+    let cp2x = sx + dx * 0.75;
+    let cpy = y + y_dir * height;
+    let ep_off = TIE_ENDPOINT_THICKNESS * y_dir;
+    let cp_off = TIE_MID_THICKNESS * y_dir;
+
+    let path = format!(
+        "M{:.1},{:.1} C{:.1},{:.1} {:.1},{:.1} {:.1},{:.1} L{:.1},{:.1} C{:.1},{:.1} {:.1},{:.1} {:.1},{:.1} Z",
+        sx, y,
+        cp1x, cpy,
+        cp2x, cpy,
+        ex, y,
+        ex, y + ep_off,
+        cp2x, cpy + cp_off,
+        cp1x, cpy + cp_off,
+        sx, y + ep_off,
+    );
+    svg.path(&path, svg.palette.note, "none", 0.0);
+}
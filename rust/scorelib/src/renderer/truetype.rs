@@ -0,0 +1,427 @@
+//! Minimal TrueType reader for the bundled text fonts.
+//!
+//! Reads just what drawing text needs: the character map (format 4),
+//! advance widths and glyph outlines, simple and composite.  Hinting is
+//! ignored.  A malformed font yields empty outlines rather than errors.
+//! `subset` cuts a font down to the glyphs a PDF uses, for embedding.
+
+use super::scene::{apply, concat, Matrix, Segment, IDENTITY};
+
+// Composite glyph component flags
+const WORDS: u16 = 0x0001;
+const XY_VALUES: u16 = 0x0002;
+const SCALE: u16 = 0x0008;
+const MORE: u16 = 0x0020;
+const XY_SCALE: u16 = 0x0040;
+const TWO_BY_TWO: u16 = 0x0080;
+
+/// Tables kept by `TrueType::subset`: the ones PDF requires of an
+/// embedded TrueType font, in tag order.
+const SUBSET_TABLES: [&[u8; 4]; 9] = [b"cvt ", b"fpgm", b"glyf", b"head", b"hhea", b"hmtx", b"loca", b"maxp", b"prep"];
+
+pub(super) struct TrueType<'a> {
+    data: &'a [u8],
+    units_per_em: f64,
+    long_loca: bool,
+    num_glyphs: u16,
+    num_h_metrics: u16,
+    cmap: usize,
+    glyf: usize,
+    hmtx: usize,
+    loca: usize,
+}
+
+impl<'a> TrueType<'a> {
+    pub fn parse(data: &'a [u8]) -> Option<Self> {
+        let table = |tag: &[u8; 4]| find_table(data, tag).map(|(offset, _)| offset);
+        let head = table(b"head")?;
+        let hhea = table(b"hhea")?;
+        let maxp = table(b"maxp")?;
+        let cmap = table(b"cmap")?;
+
+        // The Windows Unicode BMP subtable
+        let subtables = read_u16(data, cmap + 2)? as usize;
+        let cmap = (0..subtables).find_map(|i| {
+            let record = cmap + 4 + 8 * i;
+            let platform = (read_u16(data, record)?, read_u16(data, record + 2)?);
+            let offset = cmap + read_u32(data, record + 4)? as usize;
+            (platform == (3, 1) && read_u16(data, offset)? == 4).then_some(offset)
+        })?;
+
+        Some(TrueType {
+            data,
+            units_per_em: read_u16(data, head + 18)?.max(1) as f64,
+            long_loca: read_u16(data, head + 50)? == 1,
+            num_glyphs: read_u16(data, maxp + 4)?,
+            num_h_metrics: read_u16(data, hhea + 34)?.max(1),
+            cmap,
+            glyf: table(b"glyf")?,
+            hmtx: table(b"hmtx")?,
+            loca: table(b"loca")?,
+        })
+    }
+
+    pub fn units_per_em(&self) -> f64 {
+        self.units_per_em
+    }
+
+    /// Ascent, descent (negative) and bounding box `[x_min, y_min, x_max,
+    /// y_max]` in font units.
+    pub fn metrics(&self) -> (f64, f64, [f64; 4]) {
+        let int = |table: &[u8; 4], at: usize| {
+            find_table(self.data, table)
+                .and_then(|(offset, _)| read_u16(self.data, offset + at))
+                .map_or(0.0, |v| v as i16 as f64)
+        };
+        (int(b"hhea", 4), int(b"hhea", 6), [int(b"head", 36), int(b"head", 38), int(b"head", 40), int(b"head", 42)])
+    }
+
+    /// Glyph for a character; 0 (the missing-glyph box) when the font
+    /// does not have it.
+    pub fn glyph_index(&self, c: char) -> u16 {
+        let code = c as u32;
+        if code > 0xFFFF {
+            return 0;
+        }
+        let code = code as u16;
+        let lookup = || {
+            let data = self.data;
+            let segments = read_u16(data, self.cmap + 6)? as usize / 2;
+            let ends = self.cmap + 14;
+            let starts = ends + 2 * segments + 2;
+            let deltas = starts + 2 * segments;
+            let range_offsets = deltas + 2 * segments;
+            for i in 0..segments {
+                if read_u16(data, ends + 2 * i)? < code {
+                    continue;
+                }
+                let start = read_u16(data, starts + 2 * i)?;
+                if start > code {
+                    return None;
+                }
+                let delta = read_u16(data, deltas + 2 * i)?;
+                let range_offset = read_u16(data, range_offsets + 2 * i)? as usize;
+                if range_offset == 0 {
+                    return Some(code.wrapping_add(delta));
+                }
+                let at = range_offsets + 2 * i + range_offset + 2 * (code - start) as usize;
+                let glyph = read_u16(data, at)?;
+                return Some(if glyph == 0 { 0 } else { glyph.wrapping_add(delta) });
+            }
+            None
+        };
+        lookup().filter(|&g| g < self.num_glyphs).unwrap_or(0)
+    }
+
+    /// Advance width in font units.
+    pub fn advance(&self, glyph: u16) -> f64 {
+        let index = glyph.min(self.num_h_metrics - 1) as usize;
+        read_u16(self.data, self.hmtx + 4 * index).unwrap_or(0) as f64
+    }
+
+    /// The glyph's contours in font units (y up), with quadratic curves
+    /// raised to cubics.
+    pub fn outline(&self, glyph: u16) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        self.append_outline(glyph, &IDENTITY, 0, &mut segments);
+        segments
+    }
+
+    fn glyph_range(&self, glyph: u16) -> Option<(usize, usize)> {
+        if glyph >= self.num_glyphs {
+            return None;
+        }
+        let i = glyph as usize;
+        let (start, end) = if self.long_loca {
+            (read_u32(self.data, self.loca + 4 * i)? as usize, read_u32(self.data, self.loca + 4 * i + 4)? as usize)
+        } else {
+            (
+                read_u16(self.data, self.loca + 2 * i)? as usize * 2,
+                read_u16(self.data, self.loca + 2 * i + 2)? as usize * 2,
+            )
+        };
+        (end > start).then_some((self.glyf + start, self.glyf + end))
+    }
+
+    fn append_outline(&self, glyph: u16, m: &Matrix, depth: u32, out: &mut Vec<Segment>) -> Option<()> {
+        let (start, _) = self.glyph_range(glyph)?;
+        let data = self.data;
+        let contours = read_u16(data, start)? as i16;
+        if contours < 0 {
+            return self.append_composite(start + 10, m, depth, out);
+        }
+
+        let contours = contours as usize;
+        let mut ends = Vec::with_capacity(contours);
+        for i in 0..contours {
+            ends.push(read_u16(data, start + 10 + 2 * i)? as usize);
+        }
+        let count = ends.last().map_or(0, |&e| e + 1);
+        let instructions = read_u16(data, start + 10 + 2 * contours)? as usize;
+        let mut pos = start + 12 + 2 * contours + instructions;
+
+        // Flags, with run-length repeats
+        let mut flags = Vec::with_capacity(count);
+        while flags.len() < count {
+            let flag = *data.get(pos)?;
+            pos += 1;
+            flags.push(flag);
+            if flag & 0x08 != 0 {
+                let repeat = *data.get(pos)?;
+                pos += 1;
+                for _ in 0..repeat {
+                    flags.push(flag);
+                }
+            }
+        }
+        flags.truncate(count);
+
+        // Delta-coded coordinates: x values, then y values
+        let mut coords = [vec![0i32; count], vec![0i32; count]];
+        for (axis, values) in coords.iter_mut().enumerate() {
+            let (short, same_or_positive) = if axis == 0 { (0x02, 0x10) } else { (0x04, 0x20) };
+            let mut value = 0i32;
+            for (v, &flag) in values.iter_mut().zip(&flags) {
+                if flag & short != 0 {
+                    let delta = *data.get(pos)? as i32;
+                    pos += 1;
+                    value += if flag & same_or_positive != 0 { delta } else { -delta };
+                } else if flag & same_or_positive == 0 {
+                    value += read_u16(data, pos)? as i16 as i32;
+                    pos += 2;
+                }
+                *v = value;
+            }
+        }
+
+        let point = |i: usize| apply(m, coords[0][i] as f64, coords[1][i] as f64);
+        let mut first = 0;
+        for &end in &ends {
+            if end < first || end >= count {
+                break;
+            }
+            let points: Vec<((f64, f64), bool)> =
+                (first..=end).map(|i| (point(i), flags[i] & 0x01 != 0)).collect();
+            append_contour(&points, out);
+            first = end + 1;
+        }
+        Some(())
+    }
+
+    fn append_composite(&self, mut pos: usize, m: &Matrix, depth: u32, out: &mut Vec<Segment>) -> Option<()> {
+        if depth > 8 {
+            return None;
+        }
+        let data = self.data;
+        let f2dot14 = |pos: usize| read_u16(data, pos).map(|v| v as i16 as f64 / 16384.0);
+        loop {
+            let flags = read_u16(data, pos)?;
+            let glyph = read_u16(data, pos + 2)?;
+            pos += 4;
+            let (dx, dy) = if flags & WORDS != 0 {
+                pos += 4;
+                (read_u16(data, pos - 4)? as i16 as f64, read_u16(data, pos - 2)? as i16 as f64)
+            } else {
+                pos += 2;
+                (*data.get(pos - 2)? as i8 as f64, *data.get(pos - 1)? as i8 as f64)
+            };
+            let (a, b, c, d) = if flags & SCALE != 0 {
+                pos += 2;
+                let s = f2dot14(pos - 2)?;
+                (s, 0.0, 0.0, s)
+            } else if flags & XY_SCALE != 0 {
+                pos += 4;
+                (f2dot14(pos - 4)?, 0.0, 0.0, f2dot14(pos - 2)?)
+            } else if flags & TWO_BY_TWO != 0 {
+                pos += 8;
+                (f2dot14(pos - 8)?, f2dot14(pos - 6)?, f2dot14(pos - 4)?, f2dot14(pos - 2)?)
+            } else {
+                (1.0, 0.0, 0.0, 1.0)
+            };
+            // Point-matching placement is not used by the bundled fonts
+            let (dx, dy) = if flags & XY_VALUES != 0 { (dx, dy) } else { (0.0, 0.0) };
+            let component = concat(m, &[a, b, c, d, dx, dy]);
+            self.append_outline(glyph, &component, depth + 1, out);
+            if flags & MORE == 0 {
+                return Some(());
+            }
+        }
+    }
+
+    /// Glyphs a composite glyph is built from; empty for simple glyphs.
+    fn components(&self, glyph: u16) -> Vec<u16> {
+        let mut components = Vec::new();
+        let Some((start, _)) = self.glyph_range(glyph) else { return components };
+        if read_u16(self.data, start).is_none_or(|contours| contours as i16 >= 0) {
+            return components;
+        }
+        let mut pos = start + 10;
+        while let (Some(flags), Some(component)) = (read_u16(self.data, pos), read_u16(self.data, pos + 2)) {
+            components.push(component);
+            pos += if flags & WORDS != 0 { 8 } else { 6 };
+            pos += if flags & SCALE != 0 {
+                2
+            } else if flags & XY_SCALE != 0 {
+                4
+            } else if flags & TWO_BY_TWO != 0 {
+                8
+            } else {
+                0
+            };
+            if flags & MORE == 0 || components.len() > 64 {
+                break;
+            }
+        }
+        components
+    }
+
+    /// A standalone TrueType font holding the outlines of `glyphs`, the
+    /// components they are built from and the missing-glyph box, with
+    /// every other glyph left empty so glyph indices are unchanged.  Only
+    /// the tables PDF needs to draw the glyphs are kept.
+    pub fn subset(&self, glyphs: &[u16]) -> Vec<u8> {
+        let mut kept = vec![false; self.num_glyphs as usize];
+        let mut pending = glyphs.to_vec();
+        pending.push(0);
+        while let Some(glyph) = pending.pop() {
+            if let Some(k) = kept.get_mut(glyph as usize).filter(|k| !**k) {
+                *k = true;
+                pending.extend(self.components(glyph));
+            }
+        }
+
+        // Long offsets, each glyph padded to four bytes
+        let mut glyf = Vec::new();
+        let mut loca = Vec::with_capacity(4 * (kept.len() + 1));
+        for (glyph, &keep) in kept.iter().enumerate() {
+            loca.extend_from_slice(&(glyf.len() as u32).to_be_bytes());
+            let outline = self.glyph_range(glyph as u16).and_then(|(start, end)| self.data.get(start..end));
+            if let Some(outline) = outline.filter(|_| keep) {
+                glyf.extend_from_slice(outline);
+                glyf.resize(glyf.len().next_multiple_of(4), 0);
+            }
+        }
+        loca.extend_from_slice(&(glyf.len() as u32).to_be_bytes());
+
+        let tables: Vec<(&[u8; 4], Vec<u8>)> = SUBSET_TABLES
+            .iter()
+            .filter_map(|&tag| {
+                let data = match tag {
+                    b"glyf" => std::mem::take(&mut glyf),
+                    b"loca" => std::mem::take(&mut loca),
+                    _ => {
+                        let (offset, length) = find_table(self.data, tag)?;
+                        let mut data = self.data.get(offset..offset + length)?.to_vec();
+                        if tag == b"head" && data.len() >= 52 {
+                            // No checksum adjustment; long loca offsets
+                            data[8..12].fill(0);
+                            data[50..52].copy_from_slice(&1u16.to_be_bytes());
+                        }
+                        data
+                    }
+                };
+                Some((tag, data))
+            })
+            .collect();
+        write_font(&tables)
+    }
+}
+
+/// Offset and length of a table.
+fn find_table(data: &[u8], tag: &[u8; 4]) -> Option<(usize, usize)> {
+    let count = read_u16(data, 4)? as usize;
+    let entry = (0..count)
+        .map(|i| 12 + 16 * i)
+        .find(|&entry| data.get(entry..entry + 4) == Some(&tag[..]))?;
+    Some((read_u32(data, entry + 8)? as usize, read_u32(data, entry + 12)? as usize))
+}
+
+/// A TrueType file of `tables`, which are in tag order.
+fn write_font(tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let count = tables.len() as u16;
+    let power = 1u16 << (15 - count.max(1).leading_zeros());
+    let mut out = 0x0001_0000u32.to_be_bytes().to_vec();
+    for value in [count, power * 16, power.trailing_zeros() as u16, count * 16 - power * 16] {
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+    let mut offset = out.len() + 16 * tables.len();
+    for (tag, data) in tables {
+        let checksum = data
+            .chunks(4)
+            .map(|word| {
+                let mut bytes = [0; 4];
+                bytes[..word.len()].copy_from_slice(word);
+                u32::from_be_bytes(bytes)
+            })
+            .fold(0u32, u32::wrapping_add);
+        out.extend_from_slice(&tag[..]);
+        for value in [checksum, offset as u32, data.len() as u32] {
+            out.extend_from_slice(&value.to_be_bytes());
+        }
+        offset += data.len().next_multiple_of(4);
+    }
+    for (_, data) in tables {
+        out.extend_from_slice(data);
+        out.resize(out.len().next_multiple_of(4), 0);
+    }
+    out
+}
+
+/// One closed contour of on- and off-curve points.  Two off-curve points
+/// in a row imply an on-curve point halfway between them.
+fn append_contour(points: &[((f64, f64), bool)], out: &mut Vec<Segment>) {
+    let Some(&(last, _)) = points.last() else { return };
+    let mid = |(ax, ay): (f64, f64), (bx, by): (f64, f64)| ((ax + bx) / 2.0, (ay + by) / 2.0);
+    // Start on an on-curve point, or between the last and first points
+    let (start, order): (_, Vec<usize>) = match points.iter().position(|p| p.1) {
+        Some(i) => (points[i].0, (1..=points.len()).map(|k| (i + k) % points.len()).collect()),
+        None => (mid(last, points[0].0), (0..points.len()).collect()),
+    };
+
+    out.push(Segment::MoveTo(start.0, start.1));
+    let mut current = start;
+    let mut control: Option<(f64, f64)> = None;
+    for i in order {
+        let (p, on_curve) = points[i];
+        match (control, on_curve) {
+            (Some(c), true) => {
+                out.push(quad_to(current, c, p));
+                control = None;
+            }
+            (None, true) => out.push(Segment::LineTo(p.0, p.1)),
+            (Some(c), false) => {
+                let m = mid(c, p);
+                out.push(quad_to(current, c, m));
+                current = m;
+                control = Some(p);
+                continue;
+            }
+            (None, false) => {
+                control = Some(p);
+                continue;
+            }
+        }
+        current = p;
+    }
+    if let Some(c) = control {
+        out.push(quad_to(current, c, start));
+    }
+    out.push(Segment::Close);
+}
+
+/// A quadratic Bézier from `(x0, y0)` raised to a cubic.
+fn quad_to((x0, y0): (f64, f64), (qx, qy): (f64, f64), (x, y): (f64, f64)) -> Segment {
+    Segment::CurveTo([
+        x0 + 2.0 / 3.0 * (qx - x0), y0 + 2.0 / 3.0 * (qy - y0),
+        x + 2.0 / 3.0 * (qx - x), y + 2.0 / 3.0 * (qy - y),
+        x, y,
+    ])
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*data.get(pos)?, *data.get(pos + 1)?]))
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_be_bytes([*data.get(pos)?, *data.get(pos + 1)?, *data.get(pos + 2)?, *data.get(pos + 3)?]))
+}
@@ -0,0 +1,140 @@
+//! Hairpin (crescendo / diminuendo wedge) rendering.
+//!
+//! Wedges are drawn in the dynamics row: below the staff they sit at the
+//! lowest dynamics baseline of the measures they span on a system, so they
+//! clear low notes the way the `p` and `f` beside them do.  A wedge that
+//! is still open at the end of a system is drawn to the system's edge and
+//! continued on the next one, the same way open slurs are carried across
+//! systems.
+
+use std::collections::HashMap;
+
+use crate::model::*;
+use super::constants::*;
+use super::svg_builder::SvgBuilder;
+use super::beat_map::lookup_beat_x;
+
+const WEDGE_LINE_WIDTH: f64 = 1.0;
+/// Full opening of a hairpin (distance between the two lines at the wide end).
+const WEDGE_HEIGHT: f64 = 10.0;
+/// Opening at a system break, as a fraction of the full height.
+const WEDGE_BREAK_OPENING: f64 = 0.5;
+/// Gap kept between a stop at the barline and the barline itself.
+const WEDGE_END_PAD: f64 = 6.0;
+
+/// Recorded start of an open wedge.
+#[derive(Clone, Debug)]
+pub(super) struct WedgeStart {
+    pub(super) x: f64,
+    pub(super) crescendo: bool,
+    pub(super) above: bool,
+    /// True when this wedge was carried over from a previous system.
+    pub(super) continued: bool,
+    /// Lowest dynamics baseline of the measures spanned on this system
+    pub(super) below_y: f64,
+}
+
+/// Vertical centre line of a wedge on a staff.
+fn wedge_y(staff_y: f64, start: &WedgeStart) -> f64 {
+    if start.above {
+        staff_y - DYNAMICS_ABOVE_GAP - WEDGE_HEIGHT / 2.0
+    } else {
+        start.below_y.max(staff_y + STAFF_HEIGHT + DYNAMICS_OFFSET_Y) - DYNAMICS_FONT_SIZE * 0.35
+    }
+}
+
+/// Process the wedge start/stop directions of one measure on staff
+/// `staff_num`, drawing every wedge that closes here.  `below_y` is the
+/// measure's dynamics baseline (`staff::dynamics_baseline_y`).
+#[allow(clippy::too_many_arguments)]
+pub(super) fn collect_and_render_wedges_for_measure(
+    svg: &mut SvgBuilder,
+    measure: &Measure,
+    staff_num: usize,
+    divisions: i32,
+    beat_x_map: &[(f64, f64)],
+    mx: f64,
+    mw: f64,
+    staff_y: f64,
+    below_y: f64,
+    open_wedges: &mut HashMap<i32, WedgeStart>,
+) {
+    for start in open_wedges.values_mut() {
+        start.below_y = start.below_y.max(below_y);
+    }
+    for dir in &measure.directions {
+        let Some(ref wedge_type) = dir.wedge_type else { continue };
+        if dir.staff.unwrap_or(1) != staff_num as i32 {
+            continue;
+        }
+        let x = direction_x(dir, divisions, beat_x_map, mx, mw);
+
+        match wedge_type.as_str() {
+            "crescendo" | "diminuendo" => {
+                open_wedges.insert(dir.wedge_number, WedgeStart {
+                    x,
+                    crescendo: wedge_type == "crescendo",
+                    above: dir.placement.as_deref() == Some("above"),
+                    continued: false,
+                    below_y,
+                });
+            }
+            "stop" => {
+                if let Some(start) = open_wedges.remove(&dir.wedge_number) {
+                    render_wedge(svg, &start, x, staff_y, false);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Draw the part of every still-open wedge that lies on this system.
+pub(super) fn render_open_wedge_continuations(
+    svg: &mut SvgBuilder,
+    open_wedges: &HashMap<i32, WedgeStart>,
+    system_x_end: f64,
+    staff_y: f64,
+) {
+    for start in open_wedges.values() {
+        render_wedge(svg, start, system_x_end, staff_y, true);
+    }
+}
+
+/// X position of a direction: at the note it's attached to, or near the
+/// barline when it comes after the last note (typical for wedge stops).
+fn direction_x(dir: &Direction, divisions: i32, beat_x_map: &[(f64, f64)], mx: f64, mw: f64) -> f64 {
+    let beat = dir.onset as f64 / divisions.max(1) as f64;
+    let last_beat = beat_x_map.last().map_or(0.0, |b| b.0);
+    if beat_x_map.is_empty() {
+        mx + 10.0
+    } else if beat > last_beat + 0.001 {
+        mx + mw - WEDGE_END_PAD
+    } else {
+        lookup_beat_x(beat_x_map, beat)
+    }
+}
+
+fn render_wedge(svg: &mut SvgBuilder, start: &WedgeStart, x2: f64, staff_y: f64, broken: bool) {
+    if x2 - start.x < 2.0 {
+        return;
+    }
+    let y = wedge_y(staff_y, start);
+
+    // Openings (half-heights) at the left and right ends of this segment
+    let partial = WEDGE_HEIGHT * WEDGE_BREAK_OPENING / 2.0;
+    let full = WEDGE_HEIGHT / 2.0;
+    let (left, right) = match (start.crescendo, start.continued, broken) {
+        (true, false, false) => (0.0, full),
+        (true, false, true) => (0.0, partial),
+        (true, true, false) => (partial, full),
+        (true, true, true) => (partial, partial),
+        (false, false, false) => (full, 0.0),
+        (false, false, true) => (full, partial),
+        (false, true, false) => (partial, 0.0),
+        (false, true, true) => (partial, partial),
+    };
+
+    svg.line(start.x, y - left, x2, y - right, svg.palette.note, WEDGE_LINE_WIDTH);
+    svg.line(start.x, y + left, x2, y + right, svg.palette.note, WEDGE_LINE_WIDTH);
+}
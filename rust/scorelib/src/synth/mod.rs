@@ -0,0 +1,622 @@
+//! SoundFont-based software synthesizer.
+//!
+//! Renders the MIDI produced by `midi::generate_midi` to interleaved
+//! stereo PCM (or a 16-bit WAV file) with the same result on every
+//! platform, so the apps no longer depend on their platform sequencers and
+//! audio can be rendered offline (servers, regression tests).
+//!
+//! The synth plays SoundFont 2 sample zones with linear interpolation,
+//! sample loops, the DAHDSR volume envelope, tuning, attenuation and pan
+//! generators.  Channels follow General MIDI: program change, bank select
+//! (channel 9 is the percussion bank 128), volume, expression, pan,
+//! sustain pedal and pitch bend (±2 semitones).  Filters, LFOs and the
+//! modulation envelope are not modelled.
+
+mod reverb;
+mod soundfont;
+
+pub use soundfont::{SampleHeader, SoundFont};
+
+use crate::midi::read_smf;
+use reverb::Reverb;
+use soundfont::*;
+
+/// Default output sample rate (Hz).
+pub const DEFAULT_SAMPLE_RATE: u32 = 44_100;
+
+/// Headroom applied before the master gain so a full chord doesn't clip.
+const MASTER_HEADROOM: f32 = 0.3;
+/// Maximum number of simultaneously sounding voices.
+const MAX_VOICES: usize = 256;
+/// Frames rendered per block between MIDI events.
+const BLOCK_FRAMES: usize = 256;
+/// Envelope attenuation (centibels) at which a voice is considered silent.
+const SILENCE_CB: f32 = 960.0;
+/// Release time (seconds) of a voice cut off by its exclusive class.
+const EXCLUSIVE_RELEASE_S: f32 = 0.01;
+/// Longest time (seconds) rendered after the last event while voices fade.
+const MAX_TAIL_S: f32 = 10.0;
+/// Extra time (seconds) rendered for the reverb to die away.
+const REVERB_TAIL_S: f32 = 1.5;
+/// General MIDI percussion channel.
+const PERCUSSION_CHANNEL: usize = 9;
+/// Bank used for percussion kits.
+const PERCUSSION_BANK: u16 = 128;
+/// Pitch bend range in semitones.
+const PITCH_BEND_RANGE: f32 = 2.0;
+
+/// Options for rendering audio.
+#[derive(Debug, Clone)]
+pub struct SynthOptions {
+    /// Output sample rate in Hz.
+    pub sample_rate: u32,
+    /// Master gain (linear; 1.0 = unchanged).
+    pub gain: f32,
+    /// Reverb wet level, 0.0 (dry) – 1.0.
+    pub reverb: f32,
+}
+
+impl Default for SynthOptions {
+    fn default() -> Self {
+        Self {
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            gain: 1.0,
+            reverb: 0.2,
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Voices
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EnvStage {
+    Delay,
+    Attack,
+    Hold,
+    Decay,
+    Sustain,
+    Release,
+    Done,
+}
+
+/// SoundFont volume envelope.  Attack is linear in amplitude; decay and
+/// release are linear in decibels, as the specification describes.
+#[derive(Debug, Clone)]
+struct Envelope {
+    stage: EnvStage,
+    /// Samples left in the current timed stage
+    remaining: f32,
+    delay: f32,
+    attack: f32,
+    hold: f32,
+    /// Attenuation change per sample during decay (cB)
+    decay_rate: f32,
+    /// Sustain attenuation (cB)
+    sustain_cb: f32,
+    /// Attenuation change per sample during release (cB)
+    release_rate: f32,
+    /// Current attenuation (cB); used from decay onwards
+    cb: f32,
+    /// Current amplitude
+    level: f32,
+}
+
+impl Envelope {
+    fn new(gens: &[i32], sample_rate: f32) -> Self {
+        let samples = |tc: i32| timecents_to_seconds(tc) * sample_rate;
+        let sustain_cb = (gens[GEN_SUSTAIN_VOL_ENV] as f32).clamp(0.0, 1440.0);
+        let mut env = Self {
+            stage: EnvStage::Delay,
+            remaining: samples(gens[GEN_DELAY_VOL_ENV]),
+            delay: samples(gens[GEN_DELAY_VOL_ENV]),
+            attack: samples(gens[GEN_ATTACK_VOL_ENV]),
+            hold: samples(gens[GEN_HOLD_VOL_ENV]),
+            // Decay and release times are for a full 100 dB change
+            decay_rate: 1000.0 / samples(gens[GEN_DECAY_VOL_ENV]).max(1.0),
+            sustain_cb,
+            release_rate: 1000.0 / samples(gens[GEN_RELEASE_VOL_ENV]).max(1.0),
+            cb: 0.0,
+            level: 0.0,
+        };
+        if env.delay < 1.0 {
+            env.enter(EnvStage::Attack);
+        }
+        env
+    }
+
+    fn enter(&mut self, stage: EnvStage) {
+        self.stage = stage;
+        self.remaining = match stage {
+            EnvStage::Attack => self.attack,
+            EnvStage::Hold => self.hold,
+            _ => 0.0,
+        };
+    }
+
+    fn release(&mut self) {
+        if matches!(self.stage, EnvStage::Release | EnvStage::Done) {
+            return;
+        }
+        if matches!(self.stage, EnvStage::Delay | EnvStage::Attack | EnvStage::Hold) {
+            self.cb = amplitude_to_cb(self.level);
+        }
+        self.enter(EnvStage::Release);
+    }
+
+    /// Advance one sample and return the amplitude.
+    fn next(&mut self) -> f32 {
+        match self.stage {
+            EnvStage::Delay => {
+                self.level = 0.0;
+                self.remaining -= 1.0;
+                if self.remaining <= 0.0 {
+                    self.enter(EnvStage::Attack);
+                }
+            }
+            EnvStage::Attack => {
+                self.level = if self.attack < 1.0 { 1.0 } else { 1.0 - self.remaining / self.attack };
+                self.remaining -= 1.0;
+                if self.remaining <= 0.0 {
+                    self.level = 1.0;
+                    self.enter(EnvStage::Hold);
+                }
+            }
+            EnvStage::Hold => {
+                self.level = 1.0;
+                self.remaining -= 1.0;
+                if self.remaining <= 0.0 {
+                    self.cb = 0.0;
+                    self.enter(EnvStage::Decay);
+                }
+            }
+            EnvStage::Decay => {
+                self.cb += self.decay_rate;
+                if self.cb >= self.sustain_cb {
+                    self.cb = self.sustain_cb;
+                    self.enter(EnvStage::Sustain);
+                }
+                self.level = cb_to_amplitude(self.cb);
+            }
+            EnvStage::Sustain => {
+                self.level = cb_to_amplitude(self.cb);
+            }
+            EnvStage::Release => {
+                self.cb += self.release_rate;
+                if self.cb >= SILENCE_CB {
+                    self.enter(EnvStage::Done);
+                    self.level = 0.0;
+                } else {
+                    self.level = cb_to_amplitude(self.cb);
+                }
+            }
+            EnvStage::Done => self.level = 0.0,
+        }
+        if self.stage != EnvStage::Done && self.stage != EnvStage::Delay
+            && self.cb >= SILENCE_CB
+        {
+            self.enter(EnvStage::Done);
+        }
+        self.level
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Voice {
+    channel: usize,
+    key: u8,
+    /// Position in `SoundFont::data`
+    pos: f64,
+    /// Playback step at zero pitch bend (source samples per output sample)
+    step: f64,
+    end: f64,
+    loop_start: f64,
+    loop_end: f64,
+    /// 0 = no loop, 1 = loop, 3 = loop until released
+    loop_mode: i32,
+    /// Static gain: attenuation × velocity
+    gain: f32,
+    /// Zone pan, -1.0 (left) – 1.0 (right)
+    pan: f32,
+    env: Envelope,
+    released: bool,
+    /// Note-off received while the sustain pedal was down
+    held_by_pedal: bool,
+    exclusive_class: i32,
+}
+
+impl Voice {
+    fn release(&mut self) {
+        self.released = true;
+        self.held_by_pedal = false;
+        self.env.release();
+    }
+
+    fn is_done(&self) -> bool {
+        self.env.stage == EnvStage::Done
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Channel {
+    program: u16,
+    bank: u16,
+    volume: u8,
+    expression: u8,
+    pan: u8,
+    /// -8192..8191
+    pitch_bend: i32,
+    sustain: bool,
+}
+
+impl Channel {
+    fn new(index: usize) -> Self {
+        Self {
+            program: 0,
+            bank: if index == PERCUSSION_CHANNEL { PERCUSSION_BANK } else { 0 },
+            volume: 100,
+            expression: 127,
+            pan: 64,
+            pitch_bend: 0,
+            sustain: false,
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Synthesizer
+// ═══════════════════════════════════════════════════════════════════════
+
+/// A 16-channel SoundFont synthesizer driven by raw MIDI messages.
+pub struct Synth<'a> {
+    font: &'a SoundFont,
+    sample_rate: u32,
+    gain: f32,
+    channels: Vec<Channel>,
+    voices: Vec<Voice>,
+    reverb: Reverb,
+}
+
+impl<'a> Synth<'a> {
+    pub fn new(font: &'a SoundFont, options: &SynthOptions) -> Self {
+        let sample_rate = options.sample_rate.max(1);
+        Self {
+            font,
+            sample_rate,
+            gain: options.gain.max(0.0),
+            channels: (0..16).map(Channel::new).collect(),
+            voices: Vec::new(),
+            reverb: Reverb::new(sample_rate, options.reverb),
+        }
+    }
+
+    /// Number of voices still sounding.
+    pub fn active_voices(&self) -> usize {
+        self.voices.len()
+    }
+
+    /// Handle one MIDI channel message (status byte first).  Meta events
+    /// and system messages are ignored.
+    pub fn handle_message(&mut self, msg: &[u8]) {
+        let Some(&status) = msg.first() else { return };
+        if status >= 0xF0 {
+            return;
+        }
+        let ch = (status & 0x0F) as usize;
+        let d1 = msg.get(1).copied().unwrap_or(0) & 0x7F;
+        let d2 = msg.get(2).copied().unwrap_or(0) & 0x7F;
+        match status & 0xF0 {
+            0x90 if d2 > 0 => self.note_on(ch, d1, d2),
+            0x80 | 0x90 => self.note_off(ch, d1),
+            0xB0 => self.control_change(ch, d1, d2),
+            0xC0 => self.channels[ch].program = d1 as u16,
+            0xE0 => self.channels[ch].pitch_bend = ((d2 as i32) << 7 | d1 as i32) - 8192,
+            _ => {}
+        }
+    }
+
+    fn note_on(&mut self, ch: usize, key: u8, vel: u8) {
+        let (bank, program) = (self.channels[ch].bank, self.channels[ch].program);
+        let zones = self.font.zones_for(bank, program, key, vel);
+
+        // A new note on the same key replaces the old one
+        for v in self.voices.iter_mut().filter(|v| v.channel == ch && v.key == key && !v.released) {
+            v.release();
+        }
+
+        for zone in zones {
+            let g = &zone.gens;
+            if g[GEN_EXCLUSIVE_CLASS] != 0 {
+                let rate = 1000.0 / (EXCLUSIVE_RELEASE_S * self.sample_rate as f32);
+                for v in self.voices.iter_mut()
+                    .filter(|v| v.channel == ch && v.exclusive_class == g[GEN_EXCLUSIVE_CLASS])
+                {
+                    v.release();
+                    v.env.release_rate = v.env.release_rate.max(rate);
+                }
+            }
+            if let Some(voice) = self.make_voice(ch, key, vel, &zone) {
+                self.voices.push(voice);
+            }
+        }
+
+        // Voice stealing: drop the oldest voices (released ones first)
+        while self.voices.len() > MAX_VOICES {
+            let idx = self.voices.iter().position(|v| v.released).unwrap_or(0);
+            self.voices.remove(idx);
+        }
+    }
+
+    fn make_voice(&self, ch: usize, key: u8, vel: u8, zone: &ZoneMatch) -> Option<Voice> {
+        let g = &zone.gens;
+        let sh = &self.font.samples[zone.sample];
+        let len = self.font.data.len() as i64;
+        let addr = |base: u32, fine: usize, coarse: usize| {
+            (base as i64 + g[fine] as i64 + g[coarse] as i64 * 32768).clamp(0, len)
+        };
+        let start = addr(sh.start, GEN_START_ADDRS_OFFSET, GEN_START_ADDRS_COARSE_OFFSET);
+        let end = addr(sh.end, GEN_END_ADDRS_OFFSET, GEN_END_ADDRS_COARSE_OFFSET);
+        let loop_start = addr(sh.loop_start, GEN_STARTLOOP_ADDRS_OFFSET, GEN_STARTLOOP_ADDRS_COARSE_OFFSET);
+        let loop_end = addr(sh.loop_end, GEN_ENDLOOP_ADDRS_OFFSET, GEN_ENDLOOP_ADDRS_COARSE_OFFSET);
+        if end - start < 2 {
+            return None;
+        }
+
+        let root = if g[GEN_OVERRIDING_ROOT_KEY] >= 0 { g[GEN_OVERRIDING_ROOT_KEY] } else { sh.original_pitch as i32 };
+        let key_for_pitch = if g[GEN_KEYNUM] >= 0 { g[GEN_KEYNUM] } else { key as i32 };
+        let vel = if g[GEN_VELOCITY] >= 0 { g[GEN_VELOCITY].min(127) as u8 } else { vel };
+        let cents = g[GEN_SCALE_TUNING] as f64 * (key_for_pitch - root) as f64
+            + g[GEN_COARSE_TUNE] as f64 * 100.0
+            + g[GEN_FINE_TUNE] as f64
+            + sh.pitch_correction as f64;
+        let step = 2f64.powf(cents / 1200.0) * sh.sample_rate.max(1) as f64 / self.sample_rate as f64;
+
+        // Attenuation in the usual EMU scaling (0.4 dB per unit), and the
+        // default velocity curve
+        let attenuation = cb_to_amplitude(g[GEN_INITIAL_ATTENUATION].max(0) as f32 * 0.4);
+        let velocity = (vel as f32 / 127.0).powi(2);
+        let loop_mode = g[GEN_SAMPLE_MODES] & 3;
+        let valid_loop = loop_end > loop_start + 1 && loop_end <= end;
+
+        Some(Voice {
+            channel: ch,
+            key,
+            pos: start as f64,
+            step,
+            end: end as f64,
+            loop_start: loop_start as f64,
+            loop_end: loop_end as f64,
+            loop_mode: if valid_loop { loop_mode } else { 0 },
+            gain: attenuation * velocity,
+            pan: (g[GEN_PAN] as f32 / 500.0).clamp(-1.0, 1.0),
+            env: Envelope::new(g, self.sample_rate as f32),
+            released: false,
+            held_by_pedal: false,
+            exclusive_class: g[GEN_EXCLUSIVE_CLASS],
+        })
+    }
+
+    fn note_off(&mut self, ch: usize, key: u8) {
+        let sustain = self.channels[ch].sustain;
+        for v in self.voices.iter_mut().filter(|v| v.channel == ch && v.key == key && !v.released) {
+            if sustain {
+                v.held_by_pedal = true;
+            } else {
+                v.release();
+            }
+        }
+    }
+
+    fn control_change(&mut self, ch: usize, controller: u8, value: u8) {
+        let channel = &mut self.channels[ch];
+        match controller {
+            0 => channel.bank = if ch == PERCUSSION_CHANNEL { PERCUSSION_BANK } else { value as u16 },
+            7 => channel.volume = value,
+            10 => channel.pan = value,
+            11 => channel.expression = value,
+            64 => {
+                channel.sustain = value >= 64;
+                if !channel.sustain {
+                    for v in self.voices.iter_mut().filter(|v| v.channel == ch && v.held_by_pedal) {
+                        v.release();
+                    }
+                }
+            }
+            120 => self.voices.retain(|v| v.channel != ch),
+            121 => {
+                let bank = channel.bank;
+                let program = channel.program;
+                *channel = Channel::new(ch);
+                channel.bank = bank;
+                channel.program = program;
+            }
+            123 => {
+                for v in self.voices.iter_mut().filter(|v| v.channel == ch) {
+                    v.release();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Render interleaved stereo frames into `out` (overwriting it).
+    pub fn render(&mut self, out: &mut [f32]) {
+        out.iter_mut().for_each(|s| *s = 0.0);
+        let data = &self.font.data;
+
+        for voice in &mut self.voices {
+            let channel = &self.channels[voice.channel];
+            let bend = 2f64.powf(channel.pitch_bend as f64 / 8192.0 * PITCH_BEND_RANGE as f64 / 12.0);
+            let step = voice.step * bend;
+            let ch_gain = (channel.volume as f32 / 127.0).powi(2) * (channel.expression as f32 / 127.0).powi(2);
+            let pan = (voice.pan + (channel.pan as f32 - 64.0) / 64.0).clamp(-1.0, 1.0);
+            let angle = (pan + 1.0) * std::f32::consts::FRAC_PI_4;
+            let (left, right) = (angle.cos() * voice.gain * ch_gain, angle.sin() * voice.gain * ch_gain);
+
+            for frame in out.chunks_exact_mut(2) {
+                let looping = voice.loop_mode == 1 || (voice.loop_mode == 3 && !voice.released);
+                if looping && voice.pos >= voice.loop_end {
+                    // A step longer than the loop wraps around it more than once
+                    let len = voice.loop_end - voice.loop_start;
+                    voice.pos = if len > 0.0 {
+                        voice.loop_start + (voice.pos - voice.loop_start) % len
+                    } else {
+                        voice.loop_start
+                    };
+                }
+                if voice.pos >= voice.end - 1.0 && !looping {
+                    voice.env.enter(EnvStage::Done);
+                    break;
+                }
+                let i = voice.pos as usize;
+                let frac = (voice.pos - i as f64) as f32;
+                let next_i = if looping && i + 1 >= voice.loop_end as usize { voice.loop_start as usize } else { i + 1 };
+                let here = data.get(i).copied().unwrap_or(0.0);
+                let s = here + (data.get(next_i).copied().unwrap_or(0.0) - here) * frac;
+                let amp = voice.env.next();
+                if voice.is_done() {
+                    break;
+                }
+                frame[0] += s * amp * left;
+                frame[1] += s * amp * right;
+                voice.pos += step;
+            }
+        }
+        self.voices.retain(|v| !v.is_done());
+
+        self.reverb.process(out);
+        let master = MASTER_HEADROOM * self.gain;
+        out.iter_mut().for_each(|s| *s *= master);
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Rendering MIDI files
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Render Standard MIDI File bytes (e.g. from `generate_midi`) to
+/// interleaved stereo PCM samples at `options.sample_rate`.
+pub fn render_midi(font: &SoundFont, smf: &[u8], options: &SynthOptions) -> Result<Vec<f32>, String> {
+    let data = read_smf(smf)?;
+    let tpq = data.ticks_per_quarter.max(1) as f64;
+
+    // Merge all tracks into one time-ordered stream (stable: track order
+    // breaks ties, so a program change precedes notes at the same tick)
+    let mut events: Vec<(u32, &[u8])> = data.tracks.iter()
+        .flat_map(|t| t.events.iter().map(|e| (e.tick, e.bytes.as_slice())))
+        .collect();
+    events.sort_by_key(|e| e.0);
+
+    let mut synth = Synth::new(font, options);
+    let rate = synth.sample_rate as f64;
+    let mut out: Vec<f32> = Vec::new();
+    let mut block = vec![0.0f32; BLOCK_FRAMES * 2];
+
+    let mut us_per_quarter = 500_000.0;
+    let mut last_tick = 0u32;
+    let mut seconds = 0.0;
+    for (tick, bytes) in events {
+        seconds += (tick - last_tick) as f64 / tpq * us_per_quarter / 1_000_000.0;
+        last_tick = tick;
+        render_until(&mut synth, &mut out, &mut block, (seconds * rate).round() as usize);
+
+        if bytes[0] == 0xFF {
+            // Tempo: FF 51 03 tt tt tt
+            if bytes.len() >= 6 && bytes[1] == 0x51 {
+                us_per_quarter = ((bytes[3] as u32) << 16 | (bytes[4] as u32) << 8 | bytes[5] as u32) as f64;
+            }
+        } else {
+            synth.handle_message(bytes);
+        }
+    }
+
+    // Let released notes fade, then the reverb
+    let end_frames = out.len() / 2;
+    let max_frames = end_frames + (MAX_TAIL_S as f64 * rate) as usize;
+    while synth.active_voices() > 0 && out.len() / 2 < max_frames {
+        let frames = out.len() / 2 + BLOCK_FRAMES;
+        render_until(&mut synth, &mut out, &mut block, frames);
+    }
+    if options.reverb > 0.0 {
+        let frames = out.len() / 2 + (REVERB_TAIL_S as f64 * rate) as usize;
+        render_until(&mut synth, &mut out, &mut block, frames);
+    }
+
+    Ok(out)
+}
+
+/// Render Standard MIDI File bytes to a 16-bit stereo WAV file.
+pub fn render_midi_to_wav(font: &SoundFont, smf: &[u8], options: &SynthOptions) -> Result<Vec<u8>, String> {
+    let pcm = render_midi(font, smf, options)?;
+    Ok(pcm_to_wav(&pcm, options.sample_rate))
+}
+
+/// Render frames until `out` holds `target_frames` frames.
+fn render_until(synth: &mut Synth, out: &mut Vec<f32>, block: &mut [f32], target_frames: usize) {
+    while out.len() / 2 < target_frames {
+        let frames = (target_frames - out.len() / 2).min(BLOCK_FRAMES);
+        let buf = &mut block[..frames * 2];
+        synth.render(buf);
+        out.extend_from_slice(buf);
+    }
+}
+
+/// Encode interleaved stereo samples as a 16-bit PCM WAV file.
+pub fn pcm_to_wav(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    let channels: u16 = 2;
+    let bits: u16 = 16;
+    let data_len = (samples.len() * 2) as u32;
+    let byte_rate = sample_rate * channels as u32 * bits as u32 / 8;
+
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVE");
+    out.extend_from_slice(b"fmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&channels.to_le_bytes());
+    out.extend_from_slice(&sample_rate.to_le_bytes());
+    out.extend_from_slice(&byte_rate.to_le_bytes());
+    out.extend_from_slice(&(channels * bits / 8).to_le_bytes());
+    out.extend_from_slice(&bits.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for &s in samples {
+        let v = (s.clamp(-1.0, 1.0) * 32767.0).round() as i16;
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+    out
+}
+
+/// Read the synth settings of an options JSON string:
+/// `sample_rate`, `gain` and `reverb`.  Missing keys keep their defaults.
+pub(crate) fn synth_options_from_json(json_str: &str) -> SynthOptions {
+    let mut opts = SynthOptions::default();
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(json_str) else {
+        return opts;
+    };
+    if let Some(rate) = value.get("sample_rate").and_then(|v| v.as_u64()) {
+        opts.sample_rate = rate.clamp(8_000, 192_000) as u32;
+    }
+    if let Some(gain) = value.get("gain").and_then(|v| v.as_f64()) {
+        opts.gain = gain.max(0.0) as f32;
+    }
+    if let Some(reverb) = value.get("reverb").and_then(|v| v.as_f64()) {
+        opts.reverb = reverb.clamp(0.0, 1.0) as f32;
+    }
+    opts
+}
+
+fn timecents_to_seconds(tc: i32) -> f32 {
+    2f32.powf(tc as f32 / 1200.0)
+}
+
+fn cb_to_amplitude(cb: f32) -> f32 {
+    10f32.powf(-cb / 200.0)
+}
+
+fn amplitude_to_cb(amp: f32) -> f32 {
+    if amp <= 0.0 { SILENCE_CB } else { (-200.0 * amp.log10()).min(SILENCE_CB) }
+}
@@ -0,0 +1,96 @@
+//! Stereo reverb (Freeverb topology: parallel comb filters into series
+//! all-pass filters, with slightly detuned delay lengths per channel).
+
+/// Comb filter delays at 44.1 kHz (Freeverb tunings).
+const COMB_TUNINGS: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+/// All-pass filter delays at 44.1 kHz.
+const ALLPASS_TUNINGS: [usize; 4] = [556, 441, 341, 225];
+/// Extra delay of the right channel, for stereo width.
+const STEREO_SPREAD: usize = 23;
+const ROOM_SIZE: f32 = 0.84;
+const DAMPING: f32 = 0.2;
+const ALLPASS_FEEDBACK: f32 = 0.5;
+/// Input attenuation so the comb bank doesn't clip.
+const INPUT_GAIN: f32 = 0.015;
+
+struct Comb {
+    buffer: Vec<f32>,
+    pos: usize,
+    store: f32,
+}
+
+impl Comb {
+    fn new(len: usize) -> Self {
+        Self { buffer: vec![0.0; len.max(1)], pos: 0, store: 0.0 }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let out = self.buffer[self.pos];
+        self.store = out * (1.0 - DAMPING) + self.store * DAMPING;
+        self.buffer[self.pos] = input + self.store * ROOM_SIZE;
+        self.pos = (self.pos + 1) % self.buffer.len();
+        out
+    }
+}
+
+struct AllPass {
+    buffer: Vec<f32>,
+    pos: usize,
+}
+
+impl AllPass {
+    fn new(len: usize) -> Self {
+        Self { buffer: vec![0.0; len.max(1)], pos: 0 }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.buffer[self.pos];
+        self.buffer[self.pos] = input + delayed * ALLPASS_FEEDBACK;
+        self.pos = (self.pos + 1) % self.buffer.len();
+        delayed - input
+    }
+}
+
+/// Freeverb-style stereo reverb.
+pub(super) struct Reverb {
+    combs: [Vec<Comb>; 2],
+    allpasses: [Vec<AllPass>; 2],
+    /// Wet level, 0.0–1.0
+    wet: f32,
+}
+
+impl Reverb {
+    pub(super) fn new(sample_rate: u32, wet: f32) -> Self {
+        let scale = |n: usize| n * sample_rate as usize / 44_100;
+        let channel = |spread: usize| {
+            (
+                COMB_TUNINGS.iter().map(|&n| Comb::new(scale(n + spread))).collect(),
+                ALLPASS_TUNINGS.iter().map(|&n| AllPass::new(scale(n + spread))).collect(),
+            )
+        };
+        let (left_combs, left_allpasses) = channel(0);
+        let (right_combs, right_allpasses) = channel(STEREO_SPREAD);
+        Self {
+            combs: [left_combs, right_combs],
+            allpasses: [left_allpasses, right_allpasses],
+            wet: wet.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Add the reverb of interleaved stereo `frames` in place.
+    pub(super) fn process(&mut self, frames: &mut [f32]) {
+        if self.wet <= 0.0 {
+            return;
+        }
+        for frame in frames.chunks_exact_mut(2) {
+            let input = (frame[0] + frame[1]) * INPUT_GAIN;
+            for (ch, sample) in frame.iter_mut().enumerate() {
+                let mut out: f32 = self.combs[ch].iter_mut().map(|c| c.process(input)).sum();
+                for ap in &mut self.allpasses[ch] {
+                    out = ap.process(out);
+                }
+                *sample += out * self.wet;
+            }
+        }
+    }
+}
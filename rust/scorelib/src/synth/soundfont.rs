@@ -0,0 +1,345 @@
+//! SoundFont 2 (.sf2) reader.
+//!
+//! Reads the sample data (`sdta/smpl`) and the preset / instrument / sample
+//! hierarchy (`pdta`) and resolves, for a given bank, program, key and
+//! velocity, the list of sample zones to play with their generator values.
+//! Modulators (`pmod`/`imod`) are not read; the synth applies the usual
+//! default velocity and controller curves itself.
+
+use std::path::Path;
+
+// ── Generator numbers (SoundFont 2.04, section 8.1.2) ───────────────
+pub(super) const GEN_START_ADDRS_OFFSET: usize = 0;
+pub(super) const GEN_END_ADDRS_OFFSET: usize = 1;
+pub(super) const GEN_STARTLOOP_ADDRS_OFFSET: usize = 2;
+pub(super) const GEN_ENDLOOP_ADDRS_OFFSET: usize = 3;
+pub(super) const GEN_START_ADDRS_COARSE_OFFSET: usize = 4;
+pub(super) const GEN_END_ADDRS_COARSE_OFFSET: usize = 12;
+pub(super) const GEN_PAN: usize = 17;
+pub(super) const GEN_DELAY_VOL_ENV: usize = 33;
+pub(super) const GEN_ATTACK_VOL_ENV: usize = 34;
+pub(super) const GEN_HOLD_VOL_ENV: usize = 35;
+pub(super) const GEN_DECAY_VOL_ENV: usize = 36;
+pub(super) const GEN_SUSTAIN_VOL_ENV: usize = 37;
+pub(super) const GEN_RELEASE_VOL_ENV: usize = 38;
+const GEN_INSTRUMENT: usize = 41;
+const GEN_KEY_RANGE: usize = 43;
+const GEN_VEL_RANGE: usize = 44;
+pub(super) const GEN_STARTLOOP_ADDRS_COARSE_OFFSET: usize = 45;
+pub(super) const GEN_KEYNUM: usize = 46;
+pub(super) const GEN_VELOCITY: usize = 47;
+pub(super) const GEN_INITIAL_ATTENUATION: usize = 48;
+pub(super) const GEN_ENDLOOP_ADDRS_COARSE_OFFSET: usize = 50;
+pub(super) const GEN_COARSE_TUNE: usize = 51;
+pub(super) const GEN_FINE_TUNE: usize = 52;
+const GEN_SAMPLE_ID: usize = 53;
+pub(super) const GEN_SAMPLE_MODES: usize = 54;
+pub(super) const GEN_SCALE_TUNING: usize = 56;
+pub(super) const GEN_EXCLUSIVE_CLASS: usize = 57;
+pub(super) const GEN_OVERRIDING_ROOT_KEY: usize = 58;
+const GEN_COUNT: usize = 61;
+
+/// Generators that only make sense at instrument level; a preset zone
+/// may not offset them.
+const INSTRUMENT_ONLY: [usize; 11] = [
+    GEN_START_ADDRS_OFFSET, GEN_END_ADDRS_OFFSET, GEN_STARTLOOP_ADDRS_OFFSET,
+    GEN_ENDLOOP_ADDRS_OFFSET, GEN_START_ADDRS_COARSE_OFFSET, GEN_END_ADDRS_COARSE_OFFSET,
+    GEN_STARTLOOP_ADDRS_COARSE_OFFSET, GEN_ENDLOOP_ADDRS_COARSE_OFFSET, GEN_SAMPLE_MODES,
+    GEN_EXCLUSIVE_CLASS, GEN_OVERRIDING_ROOT_KEY,
+];
+
+/// A sample header (`shdr` record).
+#[derive(Debug, Clone)]
+pub struct SampleHeader {
+    pub name: String,
+    /// First data point, in samples from the start of `smpl`
+    pub start: u32,
+    /// One past the last data point
+    pub end: u32,
+    pub loop_start: u32,
+    pub loop_end: u32,
+    pub sample_rate: u32,
+    /// MIDI key the sample was recorded at
+    pub original_pitch: u8,
+    /// Pitch correction in cents
+    pub pitch_correction: i8,
+}
+
+/// One zone (`bag`) of a preset or instrument.
+#[derive(Debug, Clone)]
+struct Zone {
+    /// Raw generator amounts, indexed by generator number
+    gens: Vec<(usize, i16)>,
+}
+
+impl Zone {
+    fn get(&self, gen: usize) -> Option<i16> {
+        self.gens.iter().rev().find(|g| g.0 == gen).map(|g| g.1)
+    }
+
+    /// Key or velocity range (lo, hi); full range if absent.
+    fn range(&self, gen: usize) -> (u8, u8) {
+        self.get(gen).map_or((0, 127), |v| {
+            let [lo, hi] = (v as u16).to_le_bytes();
+            (lo, hi)
+        })
+    }
+
+    fn matches(&self, key: u8, vel: u8) -> bool {
+        let (klo, khi) = self.range(GEN_KEY_RANGE);
+        let (vlo, vhi) = self.range(GEN_VEL_RANGE);
+        key >= klo && key <= khi && vel >= vlo && vel <= vhi
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Preset {
+    name: String,
+    program: u16,
+    bank: u16,
+    /// Zone whose generators apply to every other zone
+    global: Option<Zone>,
+    /// One zone per instrument (with its key/velocity range)
+    zones: Vec<Zone>,
+}
+
+#[derive(Debug, Clone)]
+struct Instrument {
+    global: Option<Zone>,
+    zones: Vec<Zone>,
+}
+
+/// A sample zone to play for a note: the sample plus the fully resolved
+/// generator values (instrument values plus preset offsets).
+#[derive(Debug, Clone)]
+pub(super) struct ZoneMatch {
+    pub(super) sample: usize,
+    pub(super) gens: [i32; GEN_COUNT],
+}
+
+/// A parsed SoundFont 2 bank.
+#[derive(Debug, Clone)]
+pub struct SoundFont {
+    /// 16-bit sample data, normalised to -1.0..1.0
+    pub(super) data: Vec<f32>,
+    pub(super) samples: Vec<SampleHeader>,
+    presets: Vec<Preset>,
+    instruments: Vec<Instrument>,
+}
+
+impl SoundFont {
+    /// Read a SoundFont from a file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let bytes = std::fs::read(path.as_ref())
+            .map_err(|e| format!("Failed to read {}: {}", path.as_ref().display(), e))?;
+        Self::parse(&bytes)
+    }
+
+    /// Parse SoundFont 2 bytes.
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"sfbk" {
+            return Err("Not a SoundFont 2 file (missing RIFF sfbk header)".to_string());
+        }
+
+        let mut smpl: &[u8] = &[];
+        let mut pdta: Vec<(&[u8; 4], &[u8])> = Vec::new();
+        for (id, body) in chunks(&bytes[12..]) {
+            if id != b"LIST" || body.len() < 4 {
+                continue;
+            }
+            match &body[0..4] {
+                b"sdta" => {
+                    for (sub_id, sub) in chunks(&body[4..]) {
+                        if sub_id == b"smpl" {
+                            smpl = sub;
+                        }
+                    }
+                }
+                b"pdta" => pdta = chunks(&body[4..]),
+                _ => {}
+            }
+        }
+        let find = |name: &[u8; 4]| -> Result<&[u8], String> {
+            pdta.iter()
+                .find(|(id, _)| *id == name)
+                .map(|(_, body)| *body)
+                .ok_or_else(|| format!("SoundFont is missing its {} chunk", String::from_utf8_lossy(name)))
+        };
+
+        let data: Vec<f32> = smpl.chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+            .collect();
+
+        let samples: Vec<SampleHeader> = find(b"shdr")?
+            .chunks_exact(46)
+            .map(|r| SampleHeader {
+                name: fixed_str(&r[0..20]),
+                start: u32_at(r, 20),
+                end: u32_at(r, 24),
+                loop_start: u32_at(r, 28),
+                loop_end: u32_at(r, 32),
+                sample_rate: u32_at(r, 36),
+                original_pitch: r[40],
+                pitch_correction: r[41] as i8,
+            })
+            .collect();
+
+        let inst_zones = read_zones(find(b"ibag")?, find(b"igen")?);
+        let inst_bags: Vec<usize> = find(b"inst")?
+            .chunks_exact(22)
+            .map(|r| u16_at(r, 20) as usize)
+            .collect();
+        let instruments = inst_bags.windows(2)
+            .map(|w| {
+                let (global, zones) = split_global(&inst_zones, w[0], w[1], GEN_SAMPLE_ID);
+                Instrument { global, zones }
+            })
+            .collect();
+
+        let preset_zones = read_zones(find(b"pbag")?, find(b"pgen")?);
+        let preset_records: Vec<(String, u16, u16, usize)> = find(b"phdr")?
+            .chunks_exact(38)
+            .map(|r| (fixed_str(&r[0..20]), u16_at(r, 20), u16_at(r, 22), u16_at(r, 24) as usize))
+            .collect();
+        let presets = preset_records.windows(2)
+            .map(|w| {
+                let (global, zones) = split_global(&preset_zones, w[0].3, w[1].3, GEN_INSTRUMENT);
+                Preset { name: w[0].0.clone(), program: w[0].1, bank: w[0].2, global, zones }
+            })
+            .collect();
+
+        Ok(SoundFont { data, samples, presets, instruments })
+    }
+
+    /// Names of the presets as (bank, program, name).
+    pub fn preset_names(&self) -> Vec<(u16, u16, String)> {
+        self.presets.iter().map(|p| (p.bank, p.program, p.name.clone())).collect()
+    }
+
+    /// The sample zones to play for `key` at `vel`.  Falls back to bank 0
+    /// (then to the first preset) when the requested one doesn't exist.
+    pub(super) fn zones_for(&self, bank: u16, program: u16, key: u8, vel: u8) -> Vec<ZoneMatch> {
+        let preset = self.presets.iter().find(|p| p.bank == bank && p.program == program)
+            .or_else(|| self.presets.iter().find(|p| p.bank == 0 && p.program == program))
+            .or_else(|| self.presets.iter().find(|p| p.bank == bank))
+            .or_else(|| self.presets.first());
+        let Some(preset) = preset else { return Vec::new() };
+
+        let mut matches = Vec::new();
+        for pzone in preset.zones.iter().filter(|z| z.matches(key, vel)) {
+            let Some(inst_idx) = pzone.get(GEN_INSTRUMENT) else { continue };
+            let Some(inst) = self.instruments.get(inst_idx as u16 as usize) else { continue };
+
+            for izone in inst.zones.iter().filter(|z| z.matches(key, vel)) {
+                let Some(sample) = izone.get(GEN_SAMPLE_ID) else { continue };
+                let sample = sample as u16 as usize;
+                if sample >= self.samples.len() {
+                    continue;
+                }
+
+                // Instrument level: defaults, then global, then local (absolute)
+                let mut gens = default_generators();
+                for zone in inst.global.iter().chain(std::iter::once(izone)) {
+                    for &(g, v) in &zone.gens {
+                        gens[g] = v as i32;
+                    }
+                }
+                // Preset level: global then local offsets, added on top
+                let mut offsets = [0i32; GEN_COUNT];
+                for zone in preset.global.iter().chain(std::iter::once(pzone)) {
+                    for &(g, v) in &zone.gens {
+                        offsets[g] = v as i32;
+                    }
+                }
+                for (g, off) in offsets.iter().enumerate() {
+                    if !INSTRUMENT_ONLY.contains(&g)
+                        && ![GEN_KEY_RANGE, GEN_VEL_RANGE, GEN_INSTRUMENT, GEN_SAMPLE_ID].contains(&g)
+                    {
+                        gens[g] += off;
+                    }
+                }
+                matches.push(ZoneMatch { sample, gens });
+            }
+        }
+        matches
+    }
+}
+
+/// Generator defaults (SoundFont 2.04, section 8.1.3).
+fn default_generators() -> [i32; GEN_COUNT] {
+    let mut gens = [0i32; GEN_COUNT];
+    gens[8] = 13500; // initialFilterFc
+    for g in [GEN_DELAY_VOL_ENV, GEN_ATTACK_VOL_ENV, GEN_HOLD_VOL_ENV, GEN_DECAY_VOL_ENV, GEN_RELEASE_VOL_ENV] {
+        gens[g] = -12000;
+    }
+    gens[GEN_KEY_RANGE] = 0x7F00;
+    gens[GEN_VEL_RANGE] = 0x7F00;
+    gens[GEN_KEYNUM] = -1;
+    gens[GEN_VELOCITY] = -1;
+    gens[GEN_SCALE_TUNING] = 100;
+    gens[GEN_OVERRIDING_ROOT_KEY] = -1;
+    gens
+}
+
+/// Build zones from a bag chunk and its generator chunk.
+fn read_zones(bags: &[u8], gens: &[u8]) -> Vec<Zone> {
+    let gen_records: Vec<(usize, i16)> = gens.chunks_exact(4)
+        .map(|r| (u16_at(r, 0) as usize, i16::from_le_bytes([r[2], r[3]])))
+        .collect();
+    let bag_starts: Vec<usize> = bags.chunks_exact(4).map(|r| u16_at(r, 0) as usize).collect();
+    bag_starts.windows(2)
+        .map(|w| {
+            let (lo, hi) = (w[0].min(gen_records.len()), w[1].min(gen_records.len()));
+            Zone {
+                gens: gen_records[lo..hi.max(lo)].iter()
+                    .filter(|g| g.0 < GEN_COUNT)
+                    .copied()
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Split the zones `[start, end)` into an optional global zone (the first
+/// zone, when it lacks the terminal generator `link_gen`) and the rest.
+fn split_global(zones: &[Zone], start: usize, end: usize, link_gen: usize) -> (Option<Zone>, Vec<Zone>) {
+    let end = end.min(zones.len());
+    if start >= end {
+        return (None, Vec::new());
+    }
+    let list = &zones[start..end];
+    if list[0].get(link_gen).is_none() {
+        (Some(list[0].clone()), list[1..].to_vec())
+    } else {
+        (None, list.to_vec())
+    }
+}
+
+/// Split RIFF data into (id, body) sub-chunks.
+fn chunks(data: &[u8]) -> Vec<(&[u8; 4], &[u8])> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while pos + 8 <= data.len() {
+        let id: &[u8; 4] = data[pos..pos + 4].try_into().unwrap();
+        let len = u32_at(data, pos + 4) as usize;
+        let end = (pos + 8 + len).min(data.len());
+        out.push((id, &data[pos + 8..end]));
+        // Chunks are padded to an even length
+        pos = pos + 8 + len + (len & 1);
+    }
+    out
+}
+
+fn u16_at(data: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([data[pos], data[pos + 1]])
+}
+
+fn u32_at(data: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]])
+}
+
+/// A NUL-padded fixed-length name.
+fn fixed_str(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+}
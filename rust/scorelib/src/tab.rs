@@ -0,0 +1,411 @@
+//! Guitar tablature: tunings, automatic fingering and generated tab staves.
+//!
+//! Fingering chooses a string and fret for every note so the fretting hand
+//! moves as little as possible.  Notes that start together form one shape;
+//! its candidates are the ways of putting them on different strings within
+//! the fretboard, each costed by its stretch and position.  A Viterbi pass
+//! over the shapes then adds the cost of moving the hand between them and
+//! keeps the cheapest path through the whole part.
+
+use crate::model::*;
+
+/// Highest fret the fingering uses unless told otherwise.
+pub const DEFAULT_MAX_FRET: i32 = 19;
+
+/// Frets one hand position covers without stretching.
+const COMFORTABLE_SPAN: i32 = 3;
+/// Cost per fret of stretch beyond the comfortable span.
+const STRETCH_COST: f64 = 10.0;
+/// Cost per fret of the hand position, so low positions win ties.
+const POSITION_COST: f64 = 0.3;
+/// Cost of a tied note that changes string.
+const TIE_STRING_COST: f64 = 20.0;
+/// Cost of leaving a note out of a shape (out of range or too many notes).
+const SKIP_COST: f64 = 1000.0;
+/// Shapes considered per group of notes.
+const MAX_CANDIDATES: usize = 256;
+
+/// How to build a tab staff.
+#[derive(Debug, Clone)]
+pub struct TabOptions {
+    /// Open-string pitches from the bottom tab line (the highest-numbered
+    /// string) up to string 1
+    pub tuning: Vec<Pitch>,
+    /// Highest fret the fingering may use
+    pub max_fret: i32,
+}
+
+impl Default for TabOptions {
+    fn default() -> Self {
+        TabOptions {
+            tuning: standard_tuning(),
+            max_fret: DEFAULT_MAX_FRET,
+        }
+    }
+}
+
+/// Standard six-string guitar tuning, E2 A2 D3 G3 B3 E4.
+pub fn standard_tuning() -> Vec<Pitch> {
+    parse_tuning("standard").expect("standard tuning")
+}
+
+/// Parse a tuning: a name ("standard", "drop-d", "dadgad", "open-g",
+/// "open-d", "bass", "bass5", "ukulele") or open-string pitches from the
+/// bottom tab line up, separated by spaces or commas ("D2 A2 D3 G3 B3 E4",
+/// "Eb2,Ab2,Db3,Gb3,Bb3,Eb4").
+pub fn parse_tuning(spec: &str) -> Result<Vec<Pitch>, String> {
+    let notes = match spec.trim().to_ascii_lowercase().as_str() {
+        "standard" | "guitar" => "E2 A2 D3 G3 B3 E4",
+        "drop-d" | "dropd" => "D2 A2 D3 G3 B3 E4",
+        "dadgad" => "D2 A2 D3 G3 A3 D4",
+        "open-g" => "D2 G2 D3 G3 B3 D4",
+        "open-d" => "D2 A2 D3 F#3 A3 D4",
+        "bass" => "E1 A1 D2 G2",
+        "bass5" => "B0 E1 A1 D2 G2",
+        "ukulele" => "G4 C4 E4 A4",
+        _ => spec,
+    };
+    let tuning = notes
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|t| !t.is_empty())
+        .map(|t| parse_pitch_name(t).ok_or_else(|| format!("invalid tuning note '{}'", t)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if tuning.is_empty() {
+        return Err("tuning has no strings".to_string());
+    }
+    Ok(tuning)
+}
+
+/// "F#3", "Bb2", "e4" → Pitch.
+fn parse_pitch_name(name: &str) -> Option<Pitch> {
+    let mut chars = name.chars();
+    let step = chars.next()?.to_ascii_uppercase();
+    if !('A'..='G').contains(&step) {
+        return None;
+    }
+    let rest = chars.as_str();
+    let digits = rest.find(|c: char| c.is_ascii_digit() || c == '-')?;
+    let alter: i32 = rest[..digits]
+        .chars()
+        .map(|c| match c {
+            '#' | '♯' => Some(1),
+            'b' | '♭' => Some(-1),
+            _ => None,
+        })
+        .sum::<Option<i32>>()?;
+    let octave = rest[digits..].parse().ok()?;
+    Some(Pitch {
+        step: step.to_string(),
+        octave,
+        alter: (alter != 0).then_some(alter as f64),
+    })
+}
+
+/// Add a tab staff under a single-staff part.  Its notes become staff 1
+/// and a fingered copy of every pitched note goes on a new staff 2 with a
+/// TAB clef and the tuning in `<staff-details>`.  Notes the tuning can't
+/// reach are left out of the tab.
+///
+/// MIDI generation skips tab staves that double a notation staff, so the
+/// added staff doesn't change playback.
+pub fn add_tab_staff(score: &mut Score, part_idx: usize, options: &TabOptions) -> Result<(), String> {
+    let part = score.parts
+        .get_mut(part_idx)
+        .ok_or_else(|| format!("no part {}", part_idx + 1))?;
+    if options.tuning.is_empty() {
+        return Err("tuning has no strings".to_string());
+    }
+    let multi_staff = part.measures.iter().any(|m| {
+        m.attributes.as_ref().is_some_and(|a| a.staves.unwrap_or(1) > 1 || a.clefs.iter().any(|c| c.number > 1))
+            || m.notes.iter().any(|n| n.staff.unwrap_or(1) > 1)
+    });
+    if multi_staff {
+        return Err(format!("part {} already has more than one staff", part_idx + 1));
+    }
+
+    for measure in &mut part.measures {
+        let mut tab_notes = Vec::new();
+        for note in &mut measure.notes {
+            note.staff = Some(1);
+            if note.pitch.is_none() || note.rest || note.grace || note.notehead.as_deref() == Some("slash") {
+                continue;
+            }
+            tab_notes.push(Note {
+                staff: Some(2),
+                voice: Some(note.voice.unwrap_or(1) + 4),
+                default_x: None,
+                default_y: None,
+                lyrics: Vec::new(),
+                slurs: Vec::new(),
+                tuplets: Vec::new(),
+                articulations: Vec::new(),
+                ornaments: Vec::new(),
+                ornament_upper_alter: None,
+                ornament_lower_alter: None,
+                fermata: None,
+                ..note.clone()
+            });
+        }
+        measure.notes.extend(tab_notes);
+    }
+
+    if let Some(first) = part.measures.first_mut() {
+        let attrs = first.attributes.get_or_insert_with(|| Attributes {
+            divisions: None,
+            key: None,
+            time: None,
+            clefs: Vec::new(),
+            transpose: None,
+            staves: None,
+            staff_details: Vec::new(),
+        });
+        attrs.staves = Some(2);
+        if attrs.clefs.is_empty() {
+            attrs.clefs.push(Clef { number: 1, sign: "G".to_string(), line: 2, octave_change: None });
+        }
+        attrs.clefs.push(Clef { number: 2, sign: "TAB".to_string(), line: 5, octave_change: None });
+        attrs.staff_details.push(StaffDetails {
+            number: 2,
+            lines: Some(options.tuning.len() as i32),
+            tuning: options.tuning.clone(),
+        });
+    }
+
+    finger_staff(part, 2, options);
+
+    for measure in &mut part.measures {
+        measure.notes.retain(|n| n.staff != Some(2) || n.fret.is_some());
+        // A chord whose first note was left out starts at its next note
+        let mut prev_onset = None;
+        for note in measure.notes.iter_mut().filter(|n| n.staff == Some(2)) {
+            if note.chord && prev_onset != Some(note.onset) {
+                note.chord = false;
+            }
+            prev_onset = Some(note.onset);
+        }
+    }
+    Ok(())
+}
+
+/// Re-finger the tab staves of a part whose pitches changed (e.g. after
+/// transposing), using each staff's own tuning.
+pub fn refinger_tab_staves(part: &mut Part, max_fret: i32) {
+    let staves: Vec<(i32, Vec<Pitch>)> = part.measures.iter()
+        .filter_map(|m| m.attributes.as_ref())
+        .flat_map(|a| &a.clefs)
+        .filter(|c| c.sign == "TAB")
+        .map(|c| c.number)
+        .map(|staff| (staff, staff_tuning(part, staff).unwrap_or_else(standard_tuning)))
+        .collect();
+    for (staff, tuning) in staves {
+        finger_staff(part, staff, &TabOptions { tuning, max_fret });
+    }
+}
+
+/// The tuning from a staff's `<staff-details>`, if it gives one.
+fn staff_tuning(part: &Part, staff: i32) -> Option<Vec<Pitch>> {
+    part.measures.iter()
+        .filter_map(|m| m.attributes.as_ref())
+        .flat_map(|a| &a.staff_details)
+        .find(|d| d.number == staff && !d.tuning.is_empty())
+        .map(|d| d.tuning.clone())
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Fingering
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Notes starting together: `(measure index, note index, MIDI pitch)`.
+type Group = Vec<(usize, usize, i32)>;
+
+/// A string and fret for each note of a group (None = left out).
+type Shape = Vec<Option<(i32, i32)>>;
+
+/// Give every pitched note on `staff` a string and fret.  A position the
+/// note already has is kept when it still sounds the note's pitch; notes
+/// out of reach get none.
+pub fn finger_staff(part: &mut Part, staff: i32, options: &TabOptions) {
+    // open[s - 1] is the pitch of string s; string 1 is the top line
+    let open: Vec<i32> = options.tuning.iter().rev().map(Pitch::to_midi).collect();
+
+    let mut groups: Vec<Group> = Vec::new();
+    for (mi, measure) in part.measures.iter().enumerate() {
+        let mut notes: Vec<(usize, &Note)> = measure.notes.iter()
+            .enumerate()
+            .filter(|(_, n)| n.staff.unwrap_or(1) == staff && !n.rest && !n.grace && n.pitch.is_some())
+            .collect();
+        notes.sort_by_key(|(_, n)| n.onset);
+        let mut prev_onset = None;
+        for (ni, note) in notes {
+            let midi = note.pitch.as_ref().map_or(0, Pitch::to_midi);
+            if prev_onset != Some(note.onset) {
+                groups.push(Vec::new());
+            }
+            groups.last_mut().unwrap().push((mi, ni, midi));
+            prev_onset = Some(note.onset);
+        }
+    }
+
+    let candidates: Vec<Vec<(Shape, f64)>> = groups.iter()
+        .map(|group| {
+            let fixed: Vec<Option<(i32, i32)>> = group.iter()
+                .map(|&(mi, ni, midi)| {
+                    let note = &part.measures[mi].notes[ni];
+                    match (note.string, note.fret) {
+                        (Some(s), Some(f)) if s >= 1
+                            && (s as usize) <= open.len()
+                            && open[s as usize - 1] + f == midi => Some((s, f)),
+                        _ => None,
+                    }
+                })
+                .collect();
+            shapes(group, &fixed, &open, options.max_fret)
+        })
+        .collect();
+
+    let ties: Vec<Vec<bool>> = groups.iter()
+        .map(|g| g.iter().map(|&(mi, ni, _)| part.measures[mi].notes[ni].tie_stop).collect())
+        .collect();
+    let path = cheapest_path(&groups, &candidates, &ties);
+
+    for ((group, cands), choice) in groups.iter().zip(&candidates).zip(path) {
+        for (&(mi, ni, _), position) in group.iter().zip(&cands[choice].0) {
+            let note = &mut part.measures[mi].notes[ni];
+            note.string = position.map(|(s, _)| s);
+            note.fret = position.map(|(_, f)| f);
+        }
+    }
+}
+
+/// Every way of placing a group's notes on different strings, with the
+/// cost of holding each shape.
+fn shapes(group: &Group, fixed: &[Option<(i32, i32)>], open: &[i32], max_fret: i32) -> Vec<(Shape, f64)> {
+    fn place(
+        i: usize,
+        group: &Group,
+        fixed: &[Option<(i32, i32)>],
+        open: &[i32],
+        max_fret: i32,
+        shape: &mut Shape,
+        out: &mut Vec<Shape>,
+    ) {
+        if out.len() >= MAX_CANDIDATES {
+            return;
+        }
+        if i == group.len() {
+            out.push(shape.clone());
+            return;
+        }
+        let used = |s: i32| shape.iter().flatten().any(|&(u, _)| u == s);
+        let options: Vec<(i32, i32)> = match fixed[i] {
+            Some((s, f)) if !used(s) => vec![(s, f)],
+            _ => (1..=open.len() as i32)
+                .filter(|&s| !used(s))
+                .map(|s| (s, group[i].2 - open[s as usize - 1]))
+                .filter(|&(_, f)| (0..=max_fret).contains(&f))
+                .collect(),
+        };
+        if options.is_empty() {
+            shape.push(None);
+            place(i + 1, group, fixed, open, max_fret, shape, out);
+            shape.pop();
+        }
+        for position in options {
+            shape.push(Some(position));
+            place(i + 1, group, fixed, open, max_fret, shape, out);
+            shape.pop();
+        }
+    }
+
+    let mut found = Vec::new();
+    place(0, group, fixed, open, max_fret, &mut Vec::new(), &mut found);
+    found.into_iter()
+        .map(|shape| {
+            let cost = shape_cost(&shape);
+            (shape, cost)
+        })
+        .collect()
+}
+
+/// Stretch, position and left-out notes of a shape.  Open strings need
+/// no finger, so they don't count towards the stretch.
+fn shape_cost(shape: &Shape) -> f64 {
+    let skipped = shape.iter().filter(|p| p.is_none()).count();
+    let fretted: Vec<i32> = shape.iter().flatten().map(|&(_, f)| f).filter(|&f| f > 0).collect();
+    let (Some(&low), Some(&high)) = (fretted.iter().min(), fretted.iter().max()) else {
+        return skipped as f64 * SKIP_COST;
+    };
+    let span = high - low;
+    skipped as f64 * SKIP_COST
+        + span as f64
+        + (span - COMFORTABLE_SPAN).max(0) as f64 * STRETCH_COST
+        + low as f64 * POSITION_COST
+}
+
+/// Average fret of the fretted notes, or None when only open strings sound.
+fn hand_position(shape: &Shape) -> Option<f64> {
+    let fretted: Vec<f64> = shape.iter().flatten().filter(|&&(_, f)| f > 0).map(|&(_, f)| f as f64).collect();
+    (!fretted.is_empty()).then(|| fretted.iter().sum::<f64>() / fretted.len() as f64)
+}
+
+/// Cost of moving from one shape to the next: how far the hand travels,
+/// and tied notes that would jump to another string.
+fn move_cost(prev: (&Group, &Shape), next: (&Group, &Shape), ties: &[bool]) -> f64 {
+    let travel = match (hand_position(prev.1), hand_position(next.1)) {
+        (Some(a), Some(b)) => (a - b).abs(),
+        _ => 0.0,
+    };
+    let restrung = next.0.iter()
+        .zip(next.1)
+        .zip(ties)
+        .filter(|&((_, pos), &tied)| tied && pos.is_some())
+        .filter(|&((&(_, _, midi), pos), _)| {
+            prev.0.iter()
+                .zip(prev.1)
+                .find(|(&(_, _, m), _)| m == midi)
+                .is_some_and(|(_, prev_pos)| prev_pos.map(|(s, _)| s) != pos.map(|(s, _)| s))
+        })
+        .count();
+    travel + restrung as f64 * TIE_STRING_COST
+}
+
+/// Viterbi over the groups: the candidate index chosen for each group.
+fn cheapest_path(groups: &[Group], candidates: &[Vec<(Shape, f64)>], ties: &[Vec<bool>]) -> Vec<usize> {
+    if groups.is_empty() {
+        return Vec::new();
+    }
+    let mut costs: Vec<f64> = candidates[0].iter().map(|(_, c)| *c).collect();
+    let mut back: Vec<Vec<usize>> = vec![Vec::new()];
+
+    for g in 1..groups.len() {
+        let mut next_costs = Vec::with_capacity(candidates[g].len());
+        let mut next_back = Vec::with_capacity(candidates[g].len());
+        for (shape, own) in &candidates[g] {
+            let (best, cost) = candidates[g - 1].iter()
+                .zip(&costs)
+                .enumerate()
+                .map(|(p, ((prev, _), &prev_cost))| {
+                    (p, prev_cost + move_cost((&groups[g - 1], prev), (&groups[g], shape), &ties[g]))
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap_or((0, 0.0));
+            next_costs.push(cost + own);
+            next_back.push(best);
+        }
+        costs = next_costs;
+        back.push(next_back);
+    }
+
+    let mut choice = costs.iter()
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(b.1))
+        .map_or(0, |(i, _)| i);
+    let mut path = vec![0; groups.len()];
+    for g in (0..groups.len()).rev() {
+        path[g] = choice;
+        if g > 0 {
+            choice = back[g][choice];
+        }
+    }
+    path
+}
@@ -27,6 +27,35 @@ pub struct TimemapEntry {
     /// For normal measures this equals `(beats / beat_type) * 4`.
     /// For pickup / implicit measures it reflects the actual note content.
     pub effective_quarters: f64,
+    /// Fermata holds: `(start_quarters, end_quarters, extra_ms)`.  The extra
+    /// time is spread over the held span and is already part of `duration_ms`.
+    pub fermata_holds: Vec<(f64, f64, f64)>,
+}
+
+impl TimemapEntry {
+    /// Absolute time (ms) of the position `quarters` into this measure,
+    /// accounting for fermata holds before (or around) that position.
+    pub fn time_at_quarters(&self, quarters: f64) -> f64 {
+        let total_extra: f64 = self.fermata_holds.iter().map(|h| h.2).sum();
+        let ms_per_quarter = if self.effective_quarters > 0.0 {
+            (self.duration_ms - total_extra) / self.effective_quarters
+        } else {
+            0.0
+        };
+        let held: f64 = self.fermata_holds
+            .iter()
+            .map(|&(start, end, extra)| {
+                if end > start {
+                    extra * ((quarters - start) / (end - start)).clamp(0.0, 1.0)
+                } else if quarters > start {
+                    extra
+                } else {
+                    0.0
+                }
+            })
+            .sum();
+        self.timestamp_ms + quarters * ms_per_quarter + held
+    }
 }
 
 /// Default tempo if none is specified in the score.
@@ -35,6 +64,13 @@ const DEFAULT_TEMPO: f64 = 120.0;
 const DEFAULT_TIME_SIG: (i32, i32) = (4, 4);
 /// Default divisions per quarter note.
 const DEFAULT_DIVISIONS: i32 = 1;
+/// How much longer a note under a fermata is held (1.0 = twice its value).
+const FERMATA_STRETCH: f64 = 1.0;
+
+/// Part whose repeats, jumps and tempo marks drive the timing of the whole
+/// score.  In partwise MusicXML every part has the same measures, so the
+/// other parts follow its timemap (see [`part_timemap`]).
+pub const CONDUCTOR_PART: usize = 0;
 
 /// State snapshot at a particular original measure position.
 /// Pre-computed by walking measures in score order so that jumps
@@ -109,6 +145,12 @@ pub fn generate_timemap(
 
     // Pre-compute the effective state at each original measure in score order.
     let states = precompute_measure_states(part);
+    // Other parts' divisions, for their fermatas
+    let other_parts: Vec<(&crate::model::Part, Vec<MeasureState>)> = score.parts.iter()
+        .enumerate()
+        .filter(|&(pidx, _)| pidx != part_idx)
+        .map(|(_, p)| (p, precompute_measure_states(p)))
+        .collect();
 
     let mut entries = Vec::with_capacity(unrolled.len());
     let mut current_time_ms: f64 = 0.0;
@@ -149,7 +191,16 @@ pub fn generate_timemap(
             nominal_quarters
         };
 
-        let duration_ms = effective_quarters * ms_per_quarter;
+        // A fermata in any part holds the whole score
+        let mut holds = fermata_holds(measure, divisions, ms_per_quarter);
+        for (other, other_states) in &other_parts {
+            let idx = um.original_index;
+            if let (Some(m), Some(st)) = (other.measures.get(idx), other_states.get(idx)) {
+                merge_holds(&mut holds, fermata_holds(m, st.divisions, ms_per_quarter));
+            }
+        }
+        let duration_ms = effective_quarters * ms_per_quarter
+            + holds.iter().map(|h| h.2).sum::<f64>();
 
         entries.push(TimemapEntry {
             index: i,
@@ -160,6 +211,7 @@ pub fn generate_timemap(
             time_sig,
             divisions,
             effective_quarters,
+            fermata_holds: holds,
         });
 
         current_time_ms += duration_ms;
@@ -168,22 +220,62 @@ pub fn generate_timemap(
     entries
 }
 
-/// Sum the actual note durations in a measure (in quarter-note units).
+/// Length of the actual note content in a measure (in quarter-note units).
 /// Used for pickup measures where the nominal duration doesn't match
 /// the actual content.
 fn actual_note_quarters(measure: &crate::model::Measure, divisions: i32) -> f64 {
     if divisions <= 0 {
         return 0.0;
     }
-    let mut total_divisions: i32 = 0;
-    for note in &measure.notes {
-        // Chord notes share time with the previous note — don't double-count
-        if note.chord || note.grace {
+    measure.content_quarters(divisions)
+}
+
+/// Collect the fermata holds of a measure.  Fermatas on simultaneous notes
+/// (a chord, or both staves of a piano part) share one hold — the longest.
+fn fermata_holds(
+    measure: &crate::model::Measure,
+    divisions: i32,
+    ms_per_quarter: f64,
+) -> Vec<(f64, f64, f64)> {
+    let mut holds: Vec<(f64, f64, f64)> = Vec::new();
+    let times = measure.note_times_quarters(divisions);
+    for (note, &(onset, dur)) in measure.notes.iter().zip(&times) {
+        if note.fermata.is_none() || note.grace || dur <= 0.0 {
             continue;
         }
-        total_divisions += note.duration;
+        let extra = dur * ms_per_quarter * FERMATA_STRETCH;
+        merge_holds(&mut holds, vec![(onset, onset + dur, extra)]);
+    }
+    holds
+}
+
+/// Add `more` fermata holds to `holds`; holds starting together keep the longest.
+fn merge_holds(holds: &mut Vec<(f64, f64, f64)>, more: Vec<(f64, f64, f64)>) {
+    for hold in more {
+        match holds.iter_mut().find(|h| (h.0 - hold.0).abs() < 1e-6) {
+            Some(h) => {
+                if hold.1 > h.1 {
+                    *h = hold;
+                }
+            }
+            None => holds.push(hold),
+        }
     }
-    total_divisions as f64 / divisions as f64
+}
+
+/// The timemap of `timemap`'s score as seen by another part: same times,
+/// but with that part's own divisions (parts may count in different units).
+pub fn part_timemap(part: &crate::model::Part, timemap: &[TimemapEntry]) -> Vec<TimemapEntry> {
+    let states = precompute_measure_states(part);
+    timemap.iter()
+        .map(|entry| {
+            let mut e = entry.clone();
+            if let Some(st) = states.get(entry.original_index) {
+                e.divisions = st.divisions;
+            }
+            e
+        })
+        .collect()
 }
 
 /// Total duration of the entire timemap in milliseconds.
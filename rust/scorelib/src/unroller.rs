@@ -244,10 +244,10 @@ pub fn unroll(score: &Score, part_idx: usize) -> Vec<UnrolledMeasure> {
         // Reset repeat pass when we've finished all passes and move past
         // the last volta bracket in a repeat section.
         if repeat_pass > 1 {
-            let prev_had_backward = measures.get(pos.wrapping_sub(1)).map_or(false, |pm| {
+            let prev_had_backward = measures.get(pos.wrapping_sub(1)).is_some_and(|pm| {
                 pm.barlines.iter().any(|bl| {
                     bl.location == "right"
-                        && bl.repeat.as_ref().map_or(false, |r| r.direction == "backward")
+                        && bl.repeat.as_ref().is_some_and(|r| r.direction == "backward")
                 })
             });
             if prev_had_backward && !volta_map.contains_key(&pos) {
@@ -286,7 +286,7 @@ fn measure_has_fine(m: &crate::model::Measure) -> bool {
 /// Supports comma-separated values and dash-separated ranges (e.g. "1-3" → [1,2,3]).
 fn parse_ending_numbers(s: &str) -> Vec<i32> {
     let mut result = Vec::new();
-    for part in s.split(|c: char| c == ',' || c == ' ') {
+    for part in s.split([',', ' ']) {
         let part = part.trim();
         if part.is_empty() {
             continue;
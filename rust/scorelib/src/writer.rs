@@ -0,0 +1,708 @@
+//! MusicXML writer — serializes the Score data model back to MusicXML 4.0.
+//!
+//! Everything the parser reads is written back, so `parse → write → parse`
+//! gives the same `Score`.  Note, harmony and direction onsets are
+//! restored with `<backup>`/`<forward>` and `<offset>`; elements the model
+//! doesn't keep (print layout, sound settings beyond tempo/dynamics/jumps)
+//! are not written.
+
+use crate::model::*;
+
+/// Serialize a score to a MusicXML 4.0 (score-partwise) string.
+pub fn write_musicxml(score: &Score) -> String {
+    let mut w = XmlWriter::new();
+    w.raw("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n");
+    w.raw("<!DOCTYPE score-partwise PUBLIC \"-//Recordare//DTD MusicXML 4.0 Partwise//EN\" \
+           \"http://www.musicxml.org/dtds/partwise.dtd\">\n");
+    w.open("score-partwise", &[("version", "4.0")]);
+
+    write_header(&mut w, score);
+    write_part_list(&mut w, score);
+    for part in &score.parts {
+        w.open("part", &[("id", &part.id)]);
+        for measure in &part.measures {
+            write_measure(&mut w, measure);
+        }
+        w.close("part");
+    }
+
+    w.close("score-partwise");
+    w.finish()
+}
+
+// ─── Header ──────────────────────────────────────────────────────────
+
+fn write_header(w: &mut XmlWriter, score: &Score) {
+    if let Some(title) = &score.title {
+        w.open("work", &[]);
+        w.leaf("work-title", &[], title);
+        w.close("work");
+    }
+
+    if score.composer.is_some() || score.arranger.is_some() || score.software.is_some() {
+        w.open("identification", &[]);
+        if let Some(composer) = &score.composer {
+            w.leaf("creator", &[("type", "composer")], composer);
+        }
+        if let Some(arranger) = &score.arranger {
+            w.leaf("creator", &[("type", "arranger")], arranger);
+        }
+        if let Some(software) = &score.software {
+            w.open("encoding", &[]);
+            w.leaf("software", &[], software);
+            w.close("encoding");
+        }
+        w.close("identification");
+    }
+
+    if let Some(d) = &score.defaults {
+        w.open("defaults", &[]);
+        if d.millimeters.is_some() || d.tenths.is_some() {
+            w.open("scaling", &[]);
+            w.leaf_opt("millimeters", d.millimeters);
+            w.leaf_opt("tenths", d.tenths);
+            w.close("scaling");
+        }
+        let margins = [
+            ("left-margin", d.left_margin),
+            ("right-margin", d.right_margin),
+            ("top-margin", d.top_margin),
+            ("bottom-margin", d.bottom_margin),
+        ];
+        if d.page_height.is_some() || d.page_width.is_some() || margins.iter().any(|m| m.1.is_some()) {
+            w.open("page-layout", &[]);
+            w.leaf_opt("page-height", d.page_height);
+            w.leaf_opt("page-width", d.page_width);
+            if margins.iter().any(|m| m.1.is_some()) {
+                w.open("page-margins", &[("type", "both")]);
+                for (tag, value) in margins {
+                    w.leaf_opt(tag, value);
+                }
+                w.close("page-margins");
+            }
+            w.close("page-layout");
+        }
+        w.close("defaults");
+    }
+
+    // Credits carry the displayed text and its style
+    let credits = [
+        ("title", &score.title, &score.title_style),
+        ("subtitle", &score.subtitle, &score.subtitle_style),
+        ("composer", &score.composer, &score.composer_style),
+    ];
+    for (credit_type, text, style) in credits {
+        let Some(text) = text else { continue };
+        w.open("credit", &[("page", "1")]);
+        w.leaf("credit-type", &[], credit_type);
+        let style = style.clone().unwrap_or_default();
+        let size = style.font_size.map(fmt_f64);
+        let mut attrs: Vec<(&str, &str)> = Vec::new();
+        if let Some(family) = &style.font_family {
+            attrs.push(("font-family", family));
+        }
+        if let Some(size) = &size {
+            attrs.push(("font-size", size));
+        }
+        if let Some(weight) = &style.font_weight {
+            attrs.push(("font-weight", weight));
+        }
+        if let Some(font_style) = &style.font_style {
+            attrs.push(("font-style", font_style));
+        }
+        w.leaf("credit-words", &attrs, text);
+        w.close("credit");
+    }
+}
+
+fn write_part_list(w: &mut XmlWriter, score: &Score) {
+    w.open("part-list", &[]);
+    for part in &score.parts {
+        w.open("score-part", &[("id", &part.id)]);
+        w.leaf("part-name", &[], &part.name);
+        if let Some(abbr) = &part.abbreviation {
+            w.leaf("part-abbreviation", &[], abbr);
+        }
+        if part.midi_channel.is_some() || part.midi_program.is_some() {
+            let instrument_id = format!("{}-I1", part.id);
+            w.open("score-instrument", &[("id", &instrument_id)]);
+            w.leaf("instrument-name", &[], &part.name);
+            w.close("score-instrument");
+            w.open("midi-instrument", &[("id", &instrument_id)]);
+            w.leaf_opt("midi-channel", part.midi_channel);
+            w.leaf_opt("midi-program", part.midi_program);
+            w.close("midi-instrument");
+        }
+        w.close("score-part");
+    }
+    w.close("part-list");
+}
+
+// ─── Measure ─────────────────────────────────────────────────────────
+
+fn write_measure(w: &mut XmlWriter, measure: &Measure) {
+    let number = measure.number.to_string();
+    let width = measure.width.map(fmt_f64);
+    let mut attrs: Vec<(&str, &str)> = vec![("number", &number)];
+    if measure.implicit {
+        attrs.push(("implicit", "yes"));
+    }
+    if let Some(width) = &width {
+        attrs.push(("width", width));
+    }
+    w.open("measure", &attrs);
+
+    if measure.new_system || measure.new_page {
+        let mut print_attrs = Vec::new();
+        if measure.new_system {
+            print_attrs.push(("new-system", "yes"));
+        }
+        if measure.new_page {
+            print_attrs.push(("new-page", "yes"));
+        }
+        w.empty("print", &print_attrs);
+    }
+    for barline in measure.barlines.iter().filter(|b| b.location == "left") {
+        write_barline(w, barline);
+    }
+    if let Some(attributes) = &measure.attributes {
+        write_attributes(w, attributes);
+    }
+
+    // Harmonies and directions keep their own order; each is written
+    // before the first note at or after its onset, with an <offset> when
+    // the cursor isn't exactly there
+    let mut harmonies = measure.harmonies.iter().peekable();
+    let mut directions = measure.directions.iter().peekable();
+
+    let mut cursor = 0;
+    for note in &measure.notes {
+        if !note.chord {
+            // Same clamping as the parser's <backup>
+            let target = note.onset.max(0);
+            if target < cursor {
+                w.open("backup", &[]);
+                w.leaf("duration", &[], &(cursor - target).to_string());
+                w.close("backup");
+            } else if target > cursor {
+                w.open("forward", &[]);
+                w.leaf("duration", &[], &(target - cursor).to_string());
+                w.close("forward");
+            }
+            cursor = target;
+            while let Some(h) = harmonies.next_if(|h| h.onset <= cursor) {
+                write_harmony(w, h, h.onset - cursor);
+            }
+            while let Some(d) = directions.next_if(|d| d.onset <= cursor) {
+                write_direction(w, d, d.onset - cursor);
+            }
+        }
+        write_note(w, note);
+        if !note.chord && !note.grace {
+            cursor += note.duration;
+        }
+    }
+    for h in harmonies {
+        write_harmony(w, h, h.onset - cursor);
+    }
+    for d in directions {
+        write_direction(w, d, d.onset - cursor);
+    }
+
+    for barline in measure.barlines.iter().filter(|b| b.location != "left") {
+        write_barline(w, barline);
+    }
+    w.close("measure");
+}
+
+// ─── Attributes ──────────────────────────────────────────────────────
+
+fn write_attributes(w: &mut XmlWriter, attrs: &Attributes) {
+    w.open("attributes", &[]);
+    w.leaf_opt("divisions", attrs.divisions);
+    if let Some(key) = &attrs.key {
+        w.open("key", &[]);
+        w.leaf("fifths", &[], &key.fifths.to_string());
+        if let Some(mode) = &key.mode {
+            w.leaf("mode", &[], mode);
+        }
+        w.close("key");
+    }
+    if let Some(time) = &attrs.time {
+        w.open("time", &[]);
+        w.leaf("beats", &[], &time.beats.to_string());
+        w.leaf("beat-type", &[], &time.beat_type.to_string());
+        w.close("time");
+    }
+    w.leaf_opt("staves", attrs.staves);
+    for clef in &attrs.clefs {
+        w.open("clef", &[("number", &clef.number.to_string())]);
+        w.leaf("sign", &[], &clef.sign);
+        w.leaf("line", &[], &clef.line.to_string());
+        w.leaf_opt("clef-octave-change", clef.octave_change);
+        w.close("clef");
+    }
+    for details in &attrs.staff_details {
+        w.open("staff-details", &[("number", &details.number.to_string())]);
+        w.leaf_opt("staff-lines", details.lines);
+        for (i, pitch) in details.tuning.iter().enumerate() {
+            w.open("staff-tuning", &[("line", &(i + 1).to_string())]);
+            w.leaf("tuning-step", &[], &pitch.step);
+            if let Some(alter) = pitch.alter {
+                w.leaf("tuning-alter", &[], &fmt_f64(alter));
+            }
+            w.leaf("tuning-octave", &[], &pitch.octave.to_string());
+            w.close("staff-tuning");
+        }
+        w.close("staff-details");
+    }
+    if let Some(t) = &attrs.transpose {
+        w.open("transpose", &[]);
+        w.leaf("diatonic", &[], &t.diatonic.to_string());
+        w.leaf("chromatic", &[], &t.chromatic.to_string());
+        w.leaf_opt("octave-change", t.octave_change);
+        w.close("transpose");
+    }
+    w.close("attributes");
+}
+
+// ─── Note ────────────────────────────────────────────────────────────
+
+fn write_note(w: &mut XmlWriter, note: &Note) {
+    let default_x = note.default_x.map(fmt_f64);
+    let default_y = note.default_y.map(fmt_f64);
+    let mut attrs: Vec<(&str, &str)> = Vec::new();
+    if let Some(x) = &default_x {
+        attrs.push(("default-x", x));
+    }
+    if let Some(y) = &default_y {
+        attrs.push(("default-y", y));
+    }
+    w.open("note", &attrs);
+
+    if note.grace {
+        w.empty("grace", if note.grace_slash { &[("slash", "yes")] } else { &[] });
+    }
+    if note.chord {
+        w.empty("chord", &[]);
+    }
+    if let Some(pitch) = &note.pitch {
+        w.open("pitch", &[]);
+        w.leaf("step", &[], &pitch.step);
+        if let Some(alter) = pitch.alter {
+            w.leaf("alter", &[], &fmt_f64(alter));
+        }
+        w.leaf("octave", &[], &pitch.octave.to_string());
+        w.close("pitch");
+    } else if note.rest {
+        w.empty("rest", if note.measure_rest { &[("measure", "yes")] } else { &[] });
+    }
+    if !note.grace {
+        w.leaf("duration", &[], &note.duration.to_string());
+    }
+    if note.tie_stop {
+        w.empty("tie", &[("type", "stop")]);
+    }
+    if note.tie_start {
+        w.empty("tie", &[("type", "start")]);
+    }
+    w.leaf_opt("voice", note.voice);
+    if let Some(t) = &note.note_type {
+        w.leaf("type", &[], t);
+    }
+    if note.dot {
+        w.empty("dot", &[]);
+    }
+    if let Some(acc) = &note.accidental {
+        w.leaf("accidental", &[], acc);
+    }
+    if let Some(tm) = &note.time_modification {
+        w.open("time-modification", &[]);
+        w.leaf("actual-notes", &[], &tm.actual_notes.to_string());
+        w.leaf("normal-notes", &[], &tm.normal_notes.to_string());
+        if let Some(nt) = &tm.normal_type {
+            w.leaf("normal-type", &[], nt);
+        }
+        w.close("time-modification");
+    }
+    if let Some(stem) = &note.stem {
+        w.leaf("stem", &[], stem);
+    }
+    if let Some(notehead) = &note.notehead {
+        w.leaf("notehead", &[], notehead);
+    }
+    w.leaf_opt("staff", note.staff);
+    for beam in &note.beams {
+        w.leaf("beam", &[("number", &beam.number.to_string())], &beam.beam_type);
+    }
+    write_notations(w, note);
+    for lyric in &note.lyrics {
+        w.open("lyric", &[("number", &lyric.number.to_string())]);
+        if let Some(syllabic) = &lyric.syllabic {
+            w.leaf("syllabic", &[], syllabic);
+        }
+        w.leaf("text", &[], &lyric.text);
+        w.close("lyric");
+    }
+
+    w.close("note");
+}
+
+fn write_notations(w: &mut XmlWriter, note: &Note) {
+    let has_notations = note.tie_start
+        || note.tie_stop
+        || !note.slurs.is_empty()
+        || !note.tuplets.is_empty()
+        || !note.articulations.is_empty()
+        || !note.ornaments.is_empty()
+        || note.fermata.is_some()
+        || note.string.is_some()
+        || note.fret.is_some()
+        || note.fingering.is_some();
+    if !has_notations {
+        return;
+    }
+
+    w.open("notations", &[]);
+    if note.tie_stop {
+        w.empty("tied", &[("type", "stop")]);
+    }
+    if note.tie_start {
+        w.empty("tied", &[("type", "start")]);
+    }
+    for slur in &note.slurs {
+        let number = slur.number.to_string();
+        let mut attrs = vec![("type", slur.slur_type.as_str()), ("number", &number)];
+        if let Some(p) = &slur.placement {
+            attrs.push(("placement", p));
+        }
+        w.empty("slur", &attrs);
+    }
+    for tuplet in &note.tuplets {
+        let number = tuplet.number.to_string();
+        let mut attrs = vec![("type", tuplet.tuplet_type.as_str()), ("number", &number)];
+        if let Some(bracket) = tuplet.bracket {
+            attrs.push(("bracket", if bracket { "yes" } else { "no" }));
+        }
+        if let Some(show) = &tuplet.show_number {
+            attrs.push(("show-number", show));
+        }
+        if let Some(p) = &tuplet.placement {
+            attrs.push(("placement", p));
+        }
+        w.empty("tuplet", &attrs);
+    }
+    if !note.ornaments.is_empty() {
+        w.open("ornaments", &[]);
+        for o in &note.ornaments {
+            w.empty(o, &[]);
+        }
+        for (alter, placement) in [(note.ornament_upper_alter, "above"), (note.ornament_lower_alter, "below")] {
+            if let Some(mark) = alter.and_then(accidental_mark_name) {
+                w.leaf("accidental-mark", &[("placement", placement)], mark);
+            }
+        }
+        w.close("ornaments");
+    }
+    if note.string.is_some() || note.fret.is_some() || note.fingering.is_some() {
+        w.open("technical", &[]);
+        if let Some(finger) = &note.fingering {
+            w.leaf("fingering", &[], finger);
+        }
+        w.leaf_opt("string", note.string);
+        w.leaf_opt("fret", note.fret);
+        w.close("technical");
+    }
+    if !note.articulations.is_empty() {
+        w.open("articulations", &[]);
+        for a in &note.articulations {
+            w.empty(a, &[]);
+        }
+        w.close("articulations");
+    }
+    if let Some(fermata) = &note.fermata {
+        w.empty("fermata", &[("type", fermata)]);
+    }
+    w.close("notations");
+}
+
+/// The <accidental-mark> text for an alteration.
+fn accidental_mark_name(alter: f64) -> Option<&'static str> {
+    match alter.round() as i32 {
+        2 => Some("double-sharp"),
+        1 => Some("sharp"),
+        0 => Some("natural"),
+        -1 => Some("flat"),
+        -2 => Some("flat-flat"),
+        _ => None,
+    }
+}
+
+// ─── Harmony ─────────────────────────────────────────────────────────
+
+fn write_harmony(w: &mut XmlWriter, harmony: &Harmony, offset: i32) {
+    w.open("harmony", &[]);
+    w.open("root", &[]);
+    w.leaf("root-step", &[], &harmony.root.step);
+    if let Some(alter) = harmony.root.alter {
+        w.leaf("root-alter", &[], &fmt_f64(alter));
+    }
+    w.close("root");
+    w.leaf("kind", &[], &harmony.kind);
+    if let Some(bass) = &harmony.bass {
+        w.open("bass", &[]);
+        w.leaf("bass-step", &[], &bass.step);
+        if let Some(alter) = bass.alter {
+            w.leaf("bass-alter", &[], &fmt_f64(alter));
+        }
+        w.close("bass");
+    }
+    if let Some(frame) = &harmony.frame {
+        write_frame(w, frame);
+    }
+    if offset != 0 {
+        w.leaf("offset", &[], &offset.to_string());
+    }
+    w.close("harmony");
+}
+
+fn write_frame(w: &mut XmlWriter, frame: &Frame) {
+    w.open("frame", &[]);
+    w.leaf("frame-strings", &[], &frame.strings.to_string());
+    w.leaf("frame-frets", &[], &frame.frets.to_string());
+    if let Some(first) = frame.first_fret {
+        w.leaf("first-fret", &[], &first.to_string());
+    }
+    for note in &frame.notes {
+        w.open("frame-note", &[]);
+        w.leaf("string", &[], &note.string.to_string());
+        w.leaf("fret", &[], &note.fret.to_string());
+        if let Some(fingering) = &note.fingering {
+            w.leaf("fingering", &[], fingering);
+        }
+        if let Some(barre) = &note.barre {
+            w.empty("barre", &[("type", barre)]);
+        }
+        w.close("frame-note");
+    }
+    w.close("frame");
+}
+
+// ─── Barline ─────────────────────────────────────────────────────────
+
+fn write_barline(w: &mut XmlWriter, barline: &Barline) {
+    w.open("barline", &[("location", &barline.location)]);
+    if let Some(style) = &barline.bar_style {
+        w.leaf("bar-style", &[], style);
+    }
+    if let Some(ending) = &barline.ending {
+        let attrs = [("number", ending.number.as_str()), ("type", ending.ending_type.as_str())];
+        match ending.text.as_deref() {
+            Some(text) if !text.is_empty() => w.leaf("ending", &attrs, text),
+            _ => w.empty("ending", &attrs),
+        }
+    }
+    if let Some(repeat) = &barline.repeat {
+        w.empty("repeat", &[("direction", &repeat.direction)]);
+    }
+    w.close("barline");
+}
+
+// ─── Direction ───────────────────────────────────────────────────────
+
+/// Dynamics with their own MusicXML element; others use `<other-dynamics>`.
+const DYNAMICS_ELEMENTS: [&str; 25] = [
+    "p", "pp", "ppp", "pppp", "ppppp", "pppppp", "f", "ff", "fff", "ffff", "fffff", "ffffff",
+    "mp", "mf", "sf", "sfp", "sfpp", "fp", "rf", "rfz", "sfz", "sffz", "fz", "n", "pf",
+];
+
+fn write_direction(w: &mut XmlWriter, dir: &Direction, offset: i32) {
+    let mut attrs = Vec::new();
+    if let Some(p) = &dir.placement {
+        attrs.push(("placement", p.as_str()));
+    }
+    w.open("direction", &attrs);
+
+    let mut has_type = false;
+    let mut direction_type = |w: &mut XmlWriter, body: &dyn Fn(&mut XmlWriter)| {
+        w.open("direction-type", &[]);
+        body(w);
+        w.close("direction-type");
+        has_type = true;
+    };
+    if let Some(r) = &dir.rehearsal {
+        direction_type(w, &|w| w.leaf("rehearsal", &[], r));
+    }
+    if dir.segno {
+        direction_type(w, &|w| w.empty("segno", &[]));
+    }
+    if dir.coda {
+        direction_type(w, &|w| w.empty("coda", &[]));
+    }
+    if let Some(words) = &dir.words {
+        let style = dir.words_font_style.as_deref().unwrap_or("");
+        let mut word_attrs = Vec::new();
+        if style.contains("bold") {
+            word_attrs.push(("font-weight", "bold"));
+        }
+        if style.contains("italic") {
+            word_attrs.push(("font-style", "italic"));
+        }
+        direction_type(w, &|w| w.leaf("words", &word_attrs, words));
+    }
+    if let Some(m) = &dir.metronome {
+        direction_type(w, &|w| {
+            w.open("metronome", &[]);
+            w.leaf("beat-unit", &[], &m.beat_unit);
+            if m.dotted {
+                w.empty("beat-unit-dot", &[]);
+            }
+            w.leaf("per-minute", &[], &m.per_minute.to_string());
+            w.close("metronome");
+        });
+    }
+    if let Some(d) = &dir.dynamics {
+        direction_type(w, &|w| {
+            w.open("dynamics", &[]);
+            if DYNAMICS_ELEMENTS.contains(&d.as_str()) {
+                w.empty(d, &[]);
+            } else {
+                w.leaf("other-dynamics", &[], d);
+            }
+            w.close("dynamics");
+        });
+    }
+    if let Some(t) = &dir.wedge_type {
+        let number = dir.wedge_number.to_string();
+        direction_type(w, &|w| w.empty("wedge", &[("type", t), ("number", &number)]));
+    }
+    if let Some(t) = &dir.octave_shift_type {
+        let size = dir.octave_shift_size.to_string();
+        direction_type(w, &|w| w.empty("octave-shift", &[("type", t), ("size", &size)]));
+    }
+    if !has_type {
+        // A direction needs a direction-type; empty words are read as none
+        w.open("direction-type", &[]);
+        w.empty("words", &[]);
+        w.close("direction-type");
+    }
+
+    if offset != 0 {
+        w.leaf("offset", &[], &offset.to_string());
+    }
+    w.leaf_opt("staff", dir.staff);
+
+    let tempo = dir.sound_tempo.map(fmt_f64);
+    let dynamics = dir.sound_dynamics.map(fmt_f64);
+    let mut sound: Vec<(&str, &str)> = Vec::new();
+    if let Some(t) = &tempo {
+        sound.push(("tempo", t));
+    }
+    if let Some(d) = &dynamics {
+        sound.push(("dynamics", d));
+    }
+    if dir.sound_dacapo {
+        sound.push(("dacapo", "yes"));
+    }
+    if dir.sound_dalsegno {
+        sound.push(("dalsegno", "segno"));
+    }
+    if dir.sound_fine {
+        sound.push(("fine", "yes"));
+    }
+    if dir.sound_tocoda {
+        sound.push(("tocoda", "coda"));
+    }
+    if !sound.is_empty() {
+        w.empty("sound", &sound);
+    }
+
+    w.close("direction");
+}
+
+// ─── Helpers ─────────────────────────────────────────────────────────
+
+/// Format a number without a trailing ".0" (1.0 → "1", -0.5 → "-0.5").
+fn fmt_f64(v: f64) -> String {
+    format!("{v}")
+}
+
+/// Minimal indenting XML writer.
+struct XmlWriter {
+    out: String,
+    depth: usize,
+}
+
+impl XmlWriter {
+    fn new() -> Self {
+        Self { out: String::new(), depth: 0 }
+    }
+
+    fn raw(&mut self, s: &str) {
+        self.out.push_str(s);
+    }
+
+    fn start_tag(&mut self, tag: &str, attrs: &[(&str, &str)]) {
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+        self.out.push('<');
+        self.out.push_str(tag);
+        for (name, value) in attrs {
+            self.out.push_str(&format!(" {name}=\"{}\"", escape(value)));
+        }
+    }
+
+    /// `<tag attrs>` on its own line; children follow indented.
+    fn open(&mut self, tag: &str, attrs: &[(&str, &str)]) {
+        self.start_tag(tag, attrs);
+        self.out.push_str(">\n");
+        self.depth += 1;
+    }
+
+    fn close(&mut self, tag: &str) {
+        self.depth -= 1;
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+        self.out.push_str(&format!("</{tag}>\n"));
+    }
+
+    /// `<tag attrs/>`
+    fn empty(&mut self, tag: &str, attrs: &[(&str, &str)]) {
+        self.start_tag(tag, attrs);
+        self.out.push_str("/>\n");
+    }
+
+    /// `<tag attrs>text</tag>`
+    fn leaf(&mut self, tag: &str, attrs: &[(&str, &str)], text: &str) {
+        self.start_tag(tag, attrs);
+        self.out.push_str(&format!(">{}</{tag}>\n", escape(text)));
+    }
+
+    /// A leaf holding a number, skipped when absent.
+    fn leaf_opt<T: std::fmt::Display>(&mut self, tag: &str, value: Option<T>) {
+        if let Some(v) = value {
+            self.leaf(tag, &[], &v.to_string());
+        }
+    }
+
+    fn finish(self) -> String {
+        self.out
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
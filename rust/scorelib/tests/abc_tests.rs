@@ -0,0 +1,147 @@
+//! Integration tests for ABC notation import and export.
+
+use scorelib::{generate_midi_from_score, parse_abc, parse_bytes, parse_file, unroll, write_abc, MidiOptions, Score};
+
+const TUNE: &str = r#"%abc-2.1
+X:1
+T:The Test Reel
+T:Second title
+C:Trad.
+M:6/8
+L:1/8
+Q:3/8=100
+K:D
+|:"D"A>BA (3Bcd e | !fermata!f3 {g}f2^g | [DF]2A- A2z |1 "G/B"d2c B2A :|2 d3- d2 z |]
+w: Hel-lo there my friend to-geth-er * go
+"#;
+
+/// Sounding notes of a measure as (pitch, quarters) for comparisons.
+fn notes_in_quarters(score: &Score, measure: usize) -> Vec<(String, f64)> {
+    let m = &score.parts[0].measures[measure];
+    let divisions = score.parts[0].measures[0].attributes.as_ref().unwrap().divisions.unwrap() as f64;
+    m.notes.iter()
+        .filter(|n| !n.grace)
+        .map(|n| {
+            let pitch = match &n.pitch {
+                Some(p) => format!("{}{}{}", p.step, p.alter.unwrap_or(0.0), p.octave),
+                None => "rest".to_string(),
+            };
+            (pitch, n.duration as f64 / divisions)
+        })
+        .collect()
+}
+
+#[test]
+fn abc_parses_header_and_body() {
+    let score = parse_abc(TUNE).unwrap();
+    assert_eq!(score.title.as_deref(), Some("The Test Reel"));
+    assert_eq!(score.subtitle.as_deref(), Some("Second title"));
+    assert_eq!(score.composer.as_deref(), Some("Trad."));
+    assert_eq!(score.parts.len(), 1);
+    let measures = &score.parts[0].measures;
+    assert_eq!(measures.len(), 5);
+    assert_eq!(measures[0].number, 1, "a full first bar is not a pickup");
+
+    let attrs = measures[0].attributes.as_ref().unwrap();
+    assert_eq!(attrs.key.as_ref().unwrap().fifths, 2);
+    let time = attrs.time.as_ref().unwrap();
+    assert_eq!((time.beats, time.beat_type), (6, 8));
+    let tempo = &measures[0].directions[0];
+    assert_eq!(tempo.sound_tempo, Some(150.0), "dotted quarter = 100 is quarter = 150");
+    assert!(tempo.metronome.as_ref().is_some_and(|m| m.dotted && m.per_minute == 100));
+    assert_eq!(measures[0].harmonies[0].root.step, "D");
+
+    // Broken rhythm, then a triplet: every bar adds up to 6/8
+    let m1 = notes_in_quarters(&score, 0);
+    assert_eq!(m1[0], ("A04".to_string(), 0.75));
+    assert_eq!(m1[1], ("B04".to_string(), 0.25));
+    let triplet = &measures[0].notes[3];
+    assert_eq!(triplet.time_modification.as_ref().map(|t| (t.actual_notes, t.normal_notes)), Some((3, 2)));
+    assert_eq!(triplet.tuplets[0].tuplet_type, "start");
+    assert_eq!(measures[0].notes[5].tuplets[0].tuplet_type, "stop");
+    let divisions = attrs.divisions.unwrap();
+    for m in measures {
+        let total: i32 = m.notes.iter().filter(|n| !n.chord).map(|n| n.duration).sum();
+        assert_eq!(total, 3 * divisions, "measure {}", m.number);
+    }
+
+    // Beams follow the spacing: A>BA is one group, (3Bcd another
+    let beams: Vec<&str> = measures[0].notes.iter().take(3).map(|n| n.beams[0].beam_type.as_str()).collect();
+    assert_eq!(beams, ["begin", "continue", "end"]);
+    assert!(measures[0].notes[6].beams.is_empty());
+
+    // Decorations, grace notes, accidentals, chords and ties
+    let m2 = &measures[1].notes;
+    assert_eq!(m2[0].fermata.as_deref(), Some("upright"));
+    assert!(m2[1].grace);
+    assert_eq!(m2[3].pitch.as_ref().unwrap().alter, Some(1.0));
+    assert_eq!(m2[3].accidental.as_deref(), Some("sharp"));
+    let m3 = &measures[2].notes;
+    assert!(m3[1].chord);
+    assert_eq!(m3[1].pitch.as_ref().unwrap().alter, Some(1.0), "F is sharp in D major");
+    assert!(m3[2].tie_start && m3[3].tie_stop);
+
+    // Slash chords keep their bass
+    let g = &measures[3].harmonies[0];
+    assert_eq!((g.root.step.as_str(), g.bass.as_ref().map(|b| b.step.as_str())), ("G", Some("B")));
+
+    // Lyrics, with syllables joined by hyphens
+    let lyrics: Vec<(String, String)> = measures.iter()
+        .flat_map(|m| &m.notes)
+        .flat_map(|n| &n.lyrics)
+        .map(|l| (l.text.clone(), l.syllabic.clone().unwrap()))
+        .collect();
+    assert_eq!(lyrics[0], ("Hel".to_string(), "begin".to_string()));
+    assert_eq!(lyrics[1], ("lo".to_string(), "end".to_string()));
+    assert_eq!(lyrics[6], ("geth".to_string(), "middle".to_string()));
+    assert_eq!(lyrics.last().unwrap().0, "go");
+
+    // Repeats and endings play 1 2 3 4 1 2 3 5
+    let order: Vec<usize> = unroll(&score, 0).iter().map(|u| u.original_index).collect();
+    assert_eq!(order, vec![0, 1, 2, 3, 0, 1, 2, 4]);
+    println!("✓ Parsed {} measures of ABC with repeats, endings and lyrics", measures.len());
+}
+
+#[test]
+fn abc_round_trip_keeps_the_music() {
+    let original = parse_file("../../sheetmusic/asa-branca.musicxml").unwrap();
+    let abc = write_abc(&original);
+    assert!(abc.starts_with("X:1\nT:Asa branca\n"));
+    assert!(abc.contains("M:2/4\nL:1/8\n"));
+
+    let reparsed = parse_bytes(abc.as_bytes(), None).unwrap();
+    let (a, b) = (&original.parts[0].measures, &reparsed.parts[0].measures);
+    assert_eq!(a.len(), b.len());
+    assert!(b[0].implicit && b[0].number == 0, "pickup measure");
+    for i in 0..a.len() {
+        assert_eq!(notes_in_quarters(&original, i), notes_in_quarters(&reparsed, i), "measure {i}");
+        let chords = |m: &scorelib::Measure| m.harmonies.iter()
+            .map(|h| (h.root.step.clone(), h.kind.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(chords(&a[i]), chords(&b[i]), "chords in measure {i}");
+    }
+    assert_eq!(unroll(&original, 0).len(), unroll(&reparsed, 0).len());
+
+    // Line breaks become system breaks, so writing again gives the same text
+    assert_eq!(write_abc(&reparsed), abc);
+    println!("✓ asa-branca survives MusicXML → ABC → Score ({} measures)", b.len());
+}
+
+#[test]
+fn abc_short_voice_is_padded_with_rests() {
+    let score = parse_abc("X:1\nM:4/4\nL:1/4\nK:C\nV:1\nCDEF|GABc|cBAG|FEDC|\nV:2\nC,4|\n").unwrap();
+    assert_eq!(score.parts.len(), 2);
+    let (melody, bass) = (&score.parts[0].measures, &score.parts[1].measures);
+    assert_eq!(bass.len(), melody.len());
+    assert_eq!(bass[0].notes.len(), 1);
+    for m in &bass[1..] {
+        assert!(m.notes.len() == 1 && m.notes[0].rest && m.notes[0].measure_rest, "measure {}", m.number);
+        assert_eq!(m.notes[0].duration, melody[0].notes.iter().map(|n| n.duration).sum::<i32>());
+    }
+    assert_eq!(bass.last().unwrap().number, 4);
+
+    // Both parts play through to the end
+    let midi = generate_midi_from_score(&score, &MidiOptions::default());
+    assert!(midi.starts_with(b"MThd"));
+    println!("✓ Short ABC voice padded to {} measures", bass.len());
+}
@@ -0,0 +1,215 @@
+//! Integration tests for chord chart import: iReal Pro URLs and
+//! plain-text charts.
+
+use scorelib::{
+    generate_midi_from_score, parse_bytes, parse_chord_chart, parse_ireal, parse_ireal_playlist,
+    parse_musicxml, unroll, write_musicxml, MidiOptions, Score,
+};
+
+/// An old-format (unscrambled) iReal Pro song.
+const BLUE_SKIES: &str = "irealbook://Blue Skies=Berlin Irving=Medium Swing=A-=n=\
+[T44*A{A-   |A-^7   |A-7   |A-6   |C^7/G   |F#h7 B7b9 |N1E-7 A7 |D-7 G7 }          \
+|N2E-7 A7 |D-7 G7 ]*B[C^7   |x |F7 Q|r|   Z";
+
+const AUTUMN_LEAVES: &str = "\
+Title: Autumn Leaves
+Composer: Joseph Kosma
+Key: G minor
+Tempo: 120
+Style: Medium Swing
+
+[A]
+|: Cm7 | F7 | Bbmaj7 | Ebmaj7 |
+| Am7b5 | D7 |1 Gm | % :|2 Gm / G7 / ||
+# Waltz time for the tag
+B:
+| 3/4 C G Am | N.C. | F6/9 |]
+";
+
+/// Chord symbols of a measure as ("root kind", onset) pairs.
+fn chords(score: &Score, measure: usize) -> Vec<(String, i32)> {
+    score.parts[0].measures[measure].harmonies.iter()
+        .map(|h| {
+            let alter = match h.root.alter {
+                Some(a) if a > 0.0 => "#",
+                Some(_) => "b",
+                None => "",
+            };
+            (format!("{}{alter} {}", h.root.step, h.kind), h.onset)
+        })
+        .collect()
+}
+
+/// Mirror the outer characters of each full 50-character block, as
+/// iReal Pro does when it writes an `irealb://` progression (the
+/// operation is its own inverse).
+fn scramble(music: &str) -> String {
+    let chars: Vec<char> = music.chars().collect();
+    let mut out = String::new();
+    let mut rest = chars.as_slice();
+    while rest.len() > 51 {
+        let (block, tail) = rest.split_at(50);
+        let mut mirrored = block.to_vec();
+        for i in (0..5).chain(10..24) {
+            mirrored[i] = block[49 - i];
+            mirrored[49 - i] = block[i];
+        }
+        out.extend(mirrored);
+        rest = tail;
+    }
+    out.extend(rest);
+    out
+}
+
+#[test]
+fn ireal_song_becomes_slash_notation_lead_sheet() {
+    let score = parse_ireal(BLUE_SKIES).unwrap();
+    assert_eq!(score.title.as_deref(), Some("Blue Skies"));
+    assert_eq!(score.composer.as_deref(), Some("Irving Berlin"), "iReal files composers last name first");
+    let measures = &score.parts[0].measures;
+    assert_eq!(measures.len(), 15, "row padding after a closing barline is not a bar");
+
+    let attrs = measures[0].attributes.as_ref().unwrap();
+    assert_eq!(attrs.key.as_ref().map(|k| (k.fifths, k.mode.as_deref())), Some((0, Some("minor"))));
+    assert_eq!(attrs.time.as_ref().map(|t| (t.beats, t.beat_type)), Some((4, 4)));
+    assert_eq!(measures[0].directions[0].words.as_deref(), Some("Medium Swing"));
+    assert_eq!(measures[0].directions[1].rehearsal.as_deref(), Some("A"));
+
+    // One silent slash per beat, chords on the beats where they start
+    for m in measures {
+        assert_eq!(m.notes.len(), 4);
+        assert!(m.notes.iter().all(|n| n.notehead.as_deref() == Some("slash") && n.note_type.as_deref() == Some("quarter")));
+    }
+    assert_eq!(chords(&score, 1), [("A major-minor".to_string(), 0)]);
+    assert_eq!(chords(&score, 5), [("F# half-diminished".to_string(), 0), ("B dominant".to_string(), 4)]);
+    assert_eq!(score.parts[0].measures[4].harmonies[0].bass.as_ref().map(|b| b.step.as_str()), Some("G"));
+
+    // Repeat with first and second endings
+    let right = |m: usize| measures[m].barlines.iter().find(|b| b.location == "right").unwrap();
+    let left = |m: usize| measures[m].barlines.iter().find(|b| b.location == "left").unwrap();
+    assert!(left(0).repeat.is_some());
+    assert_eq!(left(6).ending.as_ref().map(|e| e.number.as_str()), Some("1"));
+    assert!(right(7).repeat.is_some());
+    assert_eq!(right(7).ending.as_ref().map(|e| e.ending_type.as_str()), Some("stop"));
+    assert_eq!(left(8).ending.as_ref().map(|e| e.number.as_str()), Some("2"));
+    assert_eq!(right(9).ending.as_ref().map(|e| e.ending_type.as_str()), Some("discontinue"));
+
+    // Bar repeats: `x` repeats one bar, `r` the two before it
+    assert_eq!(chords(&score, 11), chords(&score, 10));
+    assert_eq!(chords(&score, 13), chords(&score, 11));
+    assert_eq!(chords(&score, 14), chords(&score, 12));
+    assert!(measures[12].directions.iter().any(|d| d.sound_tocoda));
+    assert_eq!(right(14).bar_style.as_deref(), Some("light-heavy"));
+
+    let unrolled = unroll(&score, 0);
+    assert_eq!(unrolled.len(), 8 + 6 + 7, "bars 1-6 play twice, each ending once");
+}
+
+#[test]
+fn ireal_scrambled_playlist_in_html() {
+    let music = "[T34*AC^7   |D-7 G7 |E-7 A7 |D-7 G7 |C^7   |F^7   |D-7 G7 |C6   Z";
+    assert!(music.len() > 52, "long enough to be scrambled");
+    let song = |title: &str| format!("{title}=Doe Jane==Jazz Waltz=C==1r34LbKcu7{}==180=3", scramble(music));
+    let link = format!("irealb://{}==={}===My List", song("Waltz, The"), song("Second"))
+        .replace(' ', "%20")
+        .replace('=', "%3D")
+        .replace('|', "%7C");
+    let html = format!("<html><body><a href=\"{link}\">My List</a></body></html>");
+
+    let songs = parse_ireal_playlist(&html).unwrap();
+    assert_eq!(songs.len(), 2, "the playlist name is not a song");
+    let score = &songs[0];
+    assert_eq!(score.title.as_deref(), Some("The Waltz"));
+    assert_eq!(songs[1].title.as_deref(), Some("Second"));
+
+    let measures = &score.parts[0].measures;
+    assert_eq!(measures.len(), 8);
+    let time = measures[0].attributes.as_ref().unwrap().time.as_ref().unwrap();
+    assert_eq!((time.beats, time.beat_type), (3, 4));
+    assert_eq!(measures[0].directions[0].sound_tempo, Some(180.0));
+    assert_eq!(chords(score, 1), [("D minor-seventh".to_string(), 0), ("G dominant".to_string(), 4)]);
+    assert_eq!(chords(score, 7), [("C major-sixth".to_string(), 0)]);
+
+    // parse_bytes finds the link in an exported page
+    let from_bytes = parse_bytes(html.as_bytes(), Some("html")).unwrap();
+    assert_eq!(from_bytes.title, score.title);
+}
+
+#[test]
+fn text_chart_parses_header_bars_and_endings() {
+    let score = parse_chord_chart(AUTUMN_LEAVES).unwrap();
+    assert_eq!(score.title.as_deref(), Some("Autumn Leaves"));
+    assert_eq!(score.composer.as_deref(), Some("Joseph Kosma"));
+    let measures = &score.parts[0].measures;
+    assert_eq!(measures.len(), 12);
+
+    let attrs = measures[0].attributes.as_ref().unwrap();
+    assert_eq!(attrs.key.as_ref().unwrap().fifths, -2, "G minor has two flats");
+    assert_eq!(measures[0].directions[0].sound_tempo, Some(120.0));
+    assert_eq!(measures[0].directions[1].rehearsal.as_deref(), Some("A"));
+
+    assert_eq!(chords(&score, 2), [("Bb major-seventh".to_string(), 0)]);
+    assert_eq!(chords(&score, 4), [("A half-diminished".to_string(), 0)]);
+    assert_eq!(chords(&score, 7), chords(&score, 6), "% repeats the previous bar");
+    assert_eq!(chords(&score, 8), [("G minor".to_string(), 0), ("G dominant".to_string(), 4)]);
+    assert!(measures[4].new_system, "each line of bars starts a system");
+
+    // 3/4 tag: three chords, one per beat, then N.C. and a 6/9 chord
+    let time = measures[9].attributes.as_ref().unwrap().time.as_ref().unwrap();
+    assert_eq!((time.beats, time.beat_type), (3, 4));
+    assert_eq!(measures[9].notes.len(), 3);
+    assert_eq!(measures[9].harmonies.iter().map(|h| h.onset).collect::<Vec<_>>(), [0, 2, 4]);
+    assert_eq!(measures[9].directions[0].rehearsal.as_deref(), Some("B"));
+    assert_eq!(measures[10].harmonies[0].kind, "none");
+    assert_eq!(chords(&score, 11), [("F major-sixth".to_string(), 0)]);
+
+    let unrolled = unroll(&score, 0);
+    assert_eq!(unrolled.len(), 12 + 6);
+
+    assert!(parse_chord_chart("| Cm7 | Xyz7 |").unwrap_err().contains("Xyz7"));
+    assert!(parse_chord_chart("Title: Nothing\n").is_err());
+}
+
+#[test]
+fn txt_and_unlabelled_charts_are_detected() {
+    // A .txt holding a chart is a chart, one holding a link is iReal Pro
+    let from_txt = parse_bytes(AUTUMN_LEAVES.as_bytes(), Some("txt")).unwrap();
+    assert_eq!(from_txt.title.as_deref(), Some("Autumn Leaves"));
+    assert_eq!(from_txt.parts[0].measures.len(), 12);
+    let link = "irealbook://Blues=Doe Jane=Medium Swing=F=n=[T44F7   |Bb7   |F7   |C7   Z";
+    let ireal = parse_bytes(format!("My song: {link}\n").as_bytes(), Some("txt")).unwrap();
+    assert_eq!(ireal.title.as_deref(), Some("Blues"));
+
+    // Without an extension, a header or a line of bars marks a chart
+    let sniffed = parse_bytes(AUTUMN_LEAVES.as_bytes(), None).unwrap();
+    assert_eq!(sniffed.parts[0].measures.len(), 12);
+    let bare = parse_bytes(b"| C | F | G7 | C |", None).unwrap();
+    assert_eq!(bare.parts[0].measures.len(), 4);
+    assert!(parse_bytes(b"just some words", None).is_err());
+}
+
+#[test]
+fn chart_drives_accompaniment_but_slashes_stay_silent() {
+    let score = parse_bytes(AUTUMN_LEAVES.as_bytes(), Some("chords")).unwrap();
+    let note_ons = |options: &MidiOptions| {
+        scorelib::midi::read_smf(&generate_midi_from_score(&score, options)).unwrap().tracks.iter()
+            .flat_map(|t| &t.events)
+            .filter(|e| e.bytes.len() == 3 && e.bytes[0] & 0xF0 == 0x90 && e.bytes[2] > 0)
+            .count()
+    };
+    let melody_only = MidiOptions { include_metronome: false, ..MidiOptions::default() };
+    assert_eq!(note_ons(&melody_only), 0, "rhythm slashes are not played");
+    let band = MidiOptions { include_piano: true, include_bass: true, ..melody_only };
+    assert!(note_ons(&band) > 0);
+}
+
+#[test]
+fn slash_noteheads_survive_musicxml() {
+    let score = parse_chord_chart(AUTUMN_LEAVES).unwrap();
+    let xml = write_musicxml(&score);
+    assert!(xml.contains("<notehead>slash</notehead>"));
+    let reparsed = parse_musicxml(&xml).unwrap();
+    let note = &reparsed.parts[0].measures[0].notes[0];
+    assert_eq!(note.notehead.as_deref(), Some("slash"));
+    assert_eq!(note.stem.as_deref(), Some("none"));
+}
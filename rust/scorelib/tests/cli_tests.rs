@@ -0,0 +1,298 @@
+//! Integration tests for the `scorelib` command-line tool.
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn scorelib(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_scorelib"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("failed to run scorelib")
+}
+
+/// A fresh directory under test_output/ for one test.
+fn output_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_output").join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn stdout(out: &Output) -> String {
+    String::from_utf8_lossy(&out.stdout).into_owned()
+}
+
+#[test]
+fn cli_info_describes_the_score() {
+    let out = scorelib(&["info", "../../sheetmusic/asa-branca.musicxml"]);
+    assert!(out.status.success());
+    let text = stdout(&out);
+    assert!(text.contains("Asa branca"), "title missing:\n{text}");
+    assert!(text.contains("Classical Guitar"), "part missing:\n{text}");
+    assert!(text.contains("Key        C major"), "key missing:\n{text}");
+    assert!(text.contains("Time       2/4"), "time missing:\n{text}");
+    assert!(text.contains("Measures   34 (65 played)"), "measures missing:\n{text}");
+    assert!(text.contains("Duration   1:05"), "duration missing:\n{text}");
+    println!("✓ info:\n{text}");
+}
+
+#[test]
+fn cli_unroll_prints_play_order() {
+    let out = scorelib(&["unroll", "../../sheetmusic/asa-branca.musicxml"]);
+    assert!(out.status.success());
+    let numbers: Vec<i32> = stdout(&out).split_whitespace().map(|n| n.parse().unwrap()).collect();
+    assert_eq!(numbers.len(), 65);
+    // The repeat sends playback back to the start after measure 32
+    let back = numbers.windows(2).position(|w| w[1] < w[0]).unwrap();
+    assert_eq!((numbers[back], numbers[back + 1]), (32, 1));
+    println!("✓ unroll: {} measures, jump back after {}", numbers.len(), numbers[back]);
+}
+
+#[test]
+fn cli_renders_a_glob_into_a_directory() {
+    let dir = output_dir("cli_render");
+    let out = scorelib(&[
+        "render", "-w", "500", "-t", "2", "-o", dir.to_str().unwrap(), "../../sheetmusic/*.musicxml",
+    ]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    for name in ["asa-branca.svg", "blue-bag-folly.svg"] {
+        let svg = std::fs::read_to_string(dir.join(name)).unwrap();
+        assert!(svg.starts_with("<svg") && svg.contains("width=\"500\""), "{name} is not a 500-wide SVG");
+    }
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+    println!("✓ render: glob rendered to {}", dir.display());
+}
+
+#[test]
+fn cli_renders_jianpu() {
+    let dir = output_dir("cli_jianpu");
+    let path = dir.join("童年.svg");
+    let out = scorelib(&["render", "--jianpu", "-o", path.to_str().unwrap(), "../../sheetmusic/童年.mxl"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let svg = std::fs::read_to_string(&path).unwrap();
+    assert!(svg.contains(">1=C  4/4</text>"));
+
+    let out = scorelib(&["playback-map", "--jianpu", "../../sheetmusic/童年.mxl"]);
+    assert!(out.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert!(json["systems"].as_array().is_some_and(|s| !s.is_empty()));
+    println!("✓ render --jianpu: numbered notation written");
+}
+
+#[test]
+fn cli_renders_pages() {
+    let dir = output_dir("cli_pages");
+    let path = dir.join("童年.svg");
+    let out = scorelib(&["render", "--pages", "-o", path.to_str().unwrap(), "../../sheetmusic/童年.mxl"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(dir.join("童年-1.svg").exists() && dir.join("童年-2.svg").exists());
+    assert!(!path.exists());
+
+    let out = scorelib(&["playback-map", "--pages", "../../sheetmusic/童年.mxl"]);
+    assert!(out.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(json["systems"].as_array().unwrap().last().unwrap()["page"], 1);
+
+    let out = scorelib(&["render", "--pages", "-o", "-", "../../sheetmusic/童年.mxl"]);
+    assert_eq!(out.status.code(), Some(1));
+    let out = scorelib(&["render", "--pages", "--jianpu", "../../sheetmusic/童年.mxl"]);
+    assert_eq!(out.status.code(), Some(2));
+    println!("✓ render --pages: one SVG per page");
+}
+
+#[test]
+fn cli_renders_horizontal() {
+    let dir = output_dir("cli_horizontal");
+    let path = dir.join("asa-branca.svg");
+    let out = scorelib(&["render", "--horizontal", "-o", path.to_str().unwrap(), "../../sheetmusic/asa-branca.musicxml"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let svg = std::fs::read_to_string(&path).unwrap();
+    assert!(svg.starts_with("<svg") && !svg.contains(">Asa branca</text>"));
+
+    let out = scorelib(&["playback-map", "--horizontal", "../../sheetmusic/asa-branca.musicxml"]);
+    assert!(out.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(json["systems"].as_array().unwrap().len(), 1);
+
+    let out = scorelib(&["render", "--horizontal", "--jianpu", "../../sheetmusic/asa-branca.musicxml"]);
+    assert_eq!(out.status.code(), Some(2));
+    println!("✓ render --horizontal: one line written");
+}
+
+#[test]
+fn cli_renders_with_display_options() {
+    let dir = output_dir("cli_display");
+    let path = dir.join("童年.svg");
+    let out = scorelib(&[
+        "render", "--theme", "dark", "--staff-scale", "1.5", "--font", "sans-serif", "--no-lyrics",
+        "-o", path.to_str().unwrap(), "../../sheetmusic/童年.mxl",
+    ]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let svg = std::fs::read_to_string(&path).unwrap();
+    assert!(svg.contains(r##"fill="#1c1c1e""##) && svg.contains("scale(1.5)") && !svg.contains(r#"class="lyric""#));
+
+    let out = scorelib(&["render", "--theme", "neon", "../../sheetmusic/童年.mxl"]);
+    assert_eq!(out.status.code(), Some(2));
+    let out = scorelib(&["render", "--jianpu", "--theme", "dark", "../../sheetmusic/童年.mxl"]);
+    assert_eq!(out.status.code(), Some(2));
+    println!("✓ render --theme dark --staff-scale 1.5: themed SVG written");
+}
+
+#[test]
+fn cli_renders_note_names() {
+    let dir = output_dir("cli_teaching");
+    let path = dir.join("童年.svg");
+    let out = scorelib(&[
+        "render", "--note-names", "movable-do", "--color-notes", "--transpose", "2",
+        "-o", path.to_str().unwrap(), "../../sheetmusic/童年.mxl",
+    ]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let svg = std::fs::read_to_string(&path).unwrap();
+    assert!(svg.contains(">Sol</text>") && svg.contains(r##"fill="#fff32b""##));
+
+    let pages = dir.join("童年-pages.svg");
+    let out = scorelib(&[
+        "render", "--pages", "--note-names", "letters", "-o", pages.to_str().unwrap(), "../../sheetmusic/童年.mxl",
+    ]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(std::fs::read_to_string(dir.join("童年-pages-1.svg")).unwrap().contains(">E</text>"));
+
+    let out = scorelib(&["render", "--note-names", "numbers", "../../sheetmusic/童年.mxl"]);
+    assert_eq!(out.status.code(), Some(2));
+    println!("✓ render --note-names movable-do --color-notes: labelled SVG written");
+}
+
+#[test]
+fn cli_renders_tab() {
+    let dir = output_dir("cli_tab");
+    let path = dir.join("asa-branca.svg");
+    let out = scorelib(&[
+        "render", "--tab", "0", "--tuning", "drop-d", "-o", path.to_str().unwrap(),
+        "../../sheetmusic/asa-branca.musicxml",
+    ]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let svg = std::fs::read_to_string(&path).unwrap();
+    assert!(svg.contains(">T</text>") && svg.contains(">A</text>") && svg.contains(">B</text>"));
+
+    let out = scorelib(&["render", "--tuning", "E A Q", "../../sheetmusic/asa-branca.musicxml"]);
+    assert_eq!(out.status.code(), Some(2));
+    println!("✓ render --tab: tablature staff added");
+}
+
+#[test]
+fn cli_renders_chord_diagrams() {
+    let dir = output_dir("cli_diagrams");
+    let path = dir.join("blue-bag-folly.svg");
+    let out = scorelib(&[
+        "render", "--diagrams", "--tuning", "ukulele", "-o", path.to_str().unwrap(),
+        "../../sheetmusic/blue-bag-folly.musicxml",
+    ]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let svg = std::fs::read_to_string(&path).unwrap();
+    // Open strings are drawn as rings
+    assert!(svg.contains(r##"fill="none" stroke="#333333""##));
+    println!("✓ render --diagrams: chord diagrams drawn");
+}
+
+#[test]
+fn cli_writes_letter_pdf() {
+    let dir = output_dir("cli_pdf");
+    let path = dir.join("asa-branca.pdf");
+    let out = scorelib(&[
+        "pdf", "--paper", "letter", "-o", path.to_str().unwrap(), "../../sheetmusic/asa-branca.musicxml",
+    ]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let pdf = std::fs::read(&path).unwrap();
+    assert!(pdf.starts_with(b"%PDF-"));
+    assert!(String::from_utf8_lossy(&pdf).contains("/MediaBox [0 0 612 792]"));
+
+    let out = scorelib(&["pdf", "--paper", "a3", "../../sheetmusic/asa-branca.musicxml"]);
+    assert_eq!(out.status.code(), Some(2));
+    println!("✓ pdf: {} bytes", pdf.len());
+}
+
+#[test]
+fn cli_writes_png_excerpt() {
+    let dir = output_dir("cli_png");
+    let path = dir.join("asa-branca.png");
+    let out = scorelib(&[
+        "png", "--dpi", "96", "--measures", "3-6", "-o", path.to_str().unwrap(),
+        "../../sheetmusic/asa-branca.musicxml",
+    ]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let png = std::fs::read(&path).unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+
+    let out = scorelib(&["png", "--measures", "6", "../../sheetmusic/asa-branca.musicxml"]);
+    assert_eq!(out.status.code(), Some(2));
+    let out = scorelib(&["png", "--page", "9", "-o", "-", "../../sheetmusic/asa-branca.musicxml"]);
+    assert_eq!(out.status.code(), Some(1));
+    println!("✓ png: {} bytes", png.len());
+}
+
+#[test]
+fn cli_midi_applies_options() {
+    let dir = output_dir("cli_midi");
+    let path = dir.join("asa-branca.mid");
+    let out = scorelib(&[
+        "midi", "--piano", "--bass", "--drums", "--no-metronome", "--energy", "strong",
+        "--volume", "0=90", "-o", path.to_str().unwrap(), "../../sheetmusic/asa-branca.musicxml",
+    ]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let smf = scorelib::midi::read_smf(&std::fs::read(&path).unwrap()).unwrap();
+    // Conductor, guitar, piano, bass, drums
+    assert_eq!(smf.tracks.len(), 5);
+    let volume = smf.tracks.iter()
+        .flat_map(|t| &t.events)
+        .find(|e| e.bytes.len() == 3 && e.bytes[0] & 0xF0 == 0xB0 && e.bytes[1] == 7)
+        .map(|e| e.bytes[2]);
+    assert_eq!(volume, Some(90));
+    println!("✓ midi: {} tracks written", smf.tracks.len());
+}
+
+#[test]
+fn cli_playback_map_prints_json() {
+    let out = scorelib(&["playback-map", "../../sheetmusic/asa-branca.musicxml"]);
+    assert!(out.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert!(json["measures"].as_array().is_some_and(|m| !m.is_empty()));
+    println!("✓ playback-map: valid JSON");
+}
+
+#[test]
+fn cli_exit_codes() {
+    let dir = output_dir("cli_errors");
+    let bad = dir.join("broken.musicxml");
+    std::fs::write(&bad, "<score-partwise><part").unwrap();
+
+    // A parse error fails the run, but the good file is still processed
+    let out = scorelib(&["info", bad.to_str().unwrap(), "../../sheetmusic/asa-branca.musicxml"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout(&out).contains("Asa branca"));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("broken.musicxml"));
+
+    assert_eq!(scorelib(&["info", "no-such-dir/*.mxl"]).status.code(), Some(1));
+    assert_eq!(scorelib(&["engrave", "x.musicxml"]).status.code(), Some(2));
+    assert_eq!(scorelib(&["midi", "--energy", "loud", "x.musicxml"]).status.code(), Some(2));
+    println!("✓ exit codes: 1 on parse errors, 2 on usage errors");
+}
+
+#[test]
+fn cli_refuses_inputs_that_share_an_output_name() {
+    let dir = output_dir("cli_collision");
+    for sub in ["a", "b"] {
+        std::fs::create_dir_all(dir.join(sub)).unwrap();
+        std::fs::copy("../../sheetmusic/asa-branca.musicxml", dir.join(sub).join("song.musicxml")).unwrap();
+    }
+    let out_dir = dir.join("out");
+    let out = scorelib(&[
+        "render", "-o", out_dir.to_str().unwrap(),
+        dir.join("a/song.musicxml").to_str().unwrap(), dir.join("b/song.musicxml").to_str().unwrap(),
+    ]);
+    assert_eq!(out.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("would both write") && stderr.contains("song.svg"), "{stderr}");
+    assert!(!out_dir.join("song.svg").exists(), "nothing is written");
+    println!("✓ colliding outputs: {}", stderr.trim());
+}
@@ -0,0 +1,233 @@
+//! Chord diagram tests — parsing `<frame>`, voicing chords for a tuning,
+//! and drawing diagrams above chord symbols.
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use scorelib::{
+    add_chord_diagrams, chord_frame, parse_file, parse_musicxml, parse_tuning, render_score_to_svg,
+    transpose_score, write_musicxml, Frame, Harmony, HarmonyRoot, Pitch,
+};
+
+fn sheetmusic_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../sheetmusic")
+}
+
+fn output_dir() -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_output");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+/// An open C and a barred F, as guitar editors export them.
+const CHORD_FRAMES: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list><score-part id="P1"><part-name>Guitar</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes>
+        <divisions>1</divisions>
+        <time><beats>4</beats><beat-type>4</beat-type></time>
+        <clef><sign>G</sign><line>2</line></clef>
+      </attributes>
+      <harmony>
+        <root><root-step>C</root-step></root><kind>major</kind>
+        <frame>
+          <frame-strings>6</frame-strings><frame-frets>4</frame-frets>
+          <frame-note><string>5</string><fret>3</fret><fingering>3</fingering></frame-note>
+          <frame-note><string>4</string><fret>2</fret><fingering>2</fingering></frame-note>
+          <frame-note><string>3</string><fret>0</fret></frame-note>
+          <frame-note><string>2</string><fret>1</fret><fingering>1</fingering></frame-note>
+          <frame-note><string>1</string><fret>0</fret></frame-note>
+        </frame>
+      </harmony>
+      <note><pitch><step>E</step><octave>4</octave></pitch><duration>2</duration><type>half</type></note>
+      <harmony>
+        <root><root-step>F</root-step></root><kind>major</kind>
+        <frame>
+          <frame-strings>6</frame-strings><frame-frets>4</frame-frets><first-fret>1</first-fret>
+          <frame-note><string>6</string><fret>1</fret><barre type="start"/></frame-note>
+          <frame-note><string>5</string><fret>3</fret></frame-note>
+          <frame-note><string>4</string><fret>3</fret></frame-note>
+          <frame-note><string>3</string><fret>2</fret></frame-note>
+          <frame-note><string>2</string><fret>1</fret></frame-note>
+          <frame-note><string>1</string><fret>1</fret><barre type="stop"/></frame-note>
+        </frame>
+      </harmony>
+      <note><pitch><step>F</step><octave>4</octave></pitch><duration>2</duration><type>half</type></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+fn chord(step: &str, alter: Option<f64>, kind: &str) -> Harmony {
+    Harmony {
+        root: HarmonyRoot { step: step.to_string(), alter },
+        kind: kind.to_string(),
+        bass: None,
+        onset: 0,
+        frame: None,
+    }
+}
+
+/// The diagram as a shape, lowest string first: "x32010".
+fn shape(frame: &Frame) -> String {
+    (1..=frame.strings).rev()
+        .map(|s| match frame.notes.iter().find(|n| n.string == s) {
+            Some(n) => n.fret.to_string(),
+            None => "x".to_string(),
+        })
+        .collect()
+}
+
+/// Pitch classes the diagram sounds on `tuning`.
+fn sounded(frame: &Frame, tuning: &[Pitch]) -> BTreeSet<i32> {
+    frame.notes.iter()
+        .map(|n| (tuning[tuning.len() - n.string as usize].to_midi() + n.fret).rem_euclid(12))
+        .collect()
+}
+
+fn pitch_classes(root: i32, intervals: &[i32]) -> BTreeSet<i32> {
+    intervals.iter().map(|i| (root + i).rem_euclid(12)).collect()
+}
+
+#[test]
+fn parses_frames() {
+    let score = parse_musicxml(CHORD_FRAMES).unwrap();
+    let harmonies = &score.parts[0].measures[0].harmonies;
+    let c = harmonies[0].frame.as_ref().unwrap();
+    assert_eq!((c.strings, c.frets, c.first_fret), (6, 4, None));
+    assert_eq!(shape(c), "x32010");
+    assert_eq!(c.notes[0].fingering.as_deref(), Some("3"));
+
+    let f = harmonies[1].frame.as_ref().unwrap();
+    assert_eq!((shape(f).as_str(), f.first_fret), ("133211", Some(1)));
+    let barre: Vec<(i32, &str)> = f.notes.iter()
+        .filter_map(|n| Some((n.string, n.barre.as_deref()?)))
+        .collect();
+    assert_eq!(barre, [(6, "start"), (1, "stop")]);
+}
+
+#[test]
+fn frames_round_trip_through_musicxml() {
+    let score = parse_musicxml(CHORD_FRAMES).unwrap();
+    let reparsed = parse_musicxml(&write_musicxml(&score)).unwrap();
+    assert_eq!(
+        serde_json::to_value(&score.parts).unwrap(),
+        serde_json::to_value(&reparsed.parts).unwrap(),
+    );
+}
+
+#[test]
+fn renders_frames_and_legend() {
+    let score = parse_musicxml(CHORD_FRAMES).unwrap();
+    let svg = render_score_to_svg(&score, None);
+    std::fs::write(output_dir().join("diagrams-frames.svg"), &svg).unwrap();
+
+    // Each chord name appears once in the legend and once over the staff
+    assert_eq!(svg.matches(">C</text>").count(), 2);
+    assert_eq!(svg.matches(">F</text>").count(), 2);
+    // Open strings are rings: two in C, drawn inline and in the legend
+    assert_eq!(svg.matches(r##"fill="none" stroke="#333333""##).count(), 4);
+}
+
+#[test]
+fn guitar_uses_familiar_shapes() {
+    let standard = parse_tuning("standard").unwrap();
+    let shapes: Vec<String> = [
+        chord("C", None, "major"),
+        chord("A", None, "minor"),
+        chord("G", None, "dominant"),
+        chord("F", None, "major"),
+        chord("B", Some(-1.0), "major"),
+    ].iter().map(|h| shape(&chord_frame(h, &standard).unwrap())).collect();
+    assert_eq!(shapes, ["x32010", "x02210", "320001", "133211", "x13331"]);
+
+    let f = chord_frame(&chord("F", None, "major"), &standard).unwrap();
+    assert!(f.notes.iter().any(|n| n.barre.as_deref() == Some("start")));
+    let bb = chord_frame(&chord("B", Some(-1.0), "major"), &standard).unwrap();
+    assert_eq!(bb.first_fret, None, "fits under the nut");
+}
+
+#[test]
+fn other_tunings_sound_every_chord_tone() {
+    let chords = [
+        (chord("C", None, "major"), 0, &[0, 4, 7][..]),
+        (chord("D", None, "minor"), 2, &[0, 3, 7]),
+        (chord("E", Some(-1.0), "dominant"), 3, &[0, 4, 7, 10]),
+        (chord("F", Some(1.0), "half-diminished"), 6, &[0, 3, 6, 10]),
+        (chord("B", None, "diminished"), 11, &[0, 3, 6]),
+    ];
+    for tuning in ["ukulele", "drop-d", "dadgad", "open-g"] {
+        let open = parse_tuning(tuning).unwrap();
+        for (harmony, root, intervals) in &chords {
+            let frame = chord_frame(harmony, &open).unwrap();
+            assert_eq!(frame.strings as usize, open.len());
+            let tones = sounded(&frame, &open);
+            assert!(tones.is_subset(&pitch_classes(*root, intervals)), "{tuning} {}", shape(&frame));
+            // Four-note chords may leave out the fifth
+            let required: Vec<i32> = intervals.iter().copied().filter(|&i| intervals.len() < 4 || i != 7).collect();
+            assert!(pitch_classes(*root, &required).is_subset(&tones), "{tuning} {}", shape(&frame));
+        }
+    }
+
+    assert_eq!(shape(&chord_frame(&chord("C", None, "major"), &parse_tuning("ukulele").unwrap()).unwrap()), "0003");
+
+    // Slash chords put the bass note lowest
+    let standard = parse_tuning("standard").unwrap();
+    let mut c_over_g = chord("C", None, "major");
+    c_over_g.bass = Some(HarmonyRoot { step: "G".to_string(), alter: None });
+    let frame = chord_frame(&c_over_g, &standard).unwrap();
+    let lowest = frame.notes.iter().max_by_key(|n| n.string).unwrap();
+    assert_eq!((standard[standard.len() - lowest.string as usize].to_midi() + lowest.fret) % 12, 7);
+}
+
+#[test]
+fn no_chord_has_no_diagram() {
+    let standard = parse_tuning("standard").unwrap();
+    assert!(chord_frame(&chord("C", None, "none"), &standard).is_none());
+}
+
+#[test]
+fn adds_diagrams_to_a_score() {
+    let path = sheetmusic_dir().join("blue-bag-folly.musicxml");
+    let mut score = parse_file(&path).unwrap();
+    let plain_svg = render_score_to_svg(&score, None);
+    let ukulele = parse_tuning("ukulele").unwrap();
+    add_chord_diagrams(&mut score, &ukulele);
+
+    let harmonies: Vec<&Harmony> = score.parts.iter()
+        .flat_map(|p| &p.measures)
+        .flat_map(|m| &m.harmonies)
+        .collect();
+    assert!(!harmonies.is_empty());
+    assert!(harmonies.iter().all(|h| h.frame.as_ref().is_some_and(|f| f.strings == 4)));
+
+    // The legend and diagrams push the music down the page
+    let svg = render_score_to_svg(&score, None);
+    std::fs::write(output_dir().join("blue-bag-folly-diagrams.svg"), &svg).unwrap();
+    let height = |svg: &str| -> f64 {
+        let start = svg.find("height=\"").unwrap() + 8;
+        svg[start..].split('"').next().unwrap().parse().unwrap()
+    };
+    assert!(height(&svg) > height(&plain_svg));
+
+    // Diagrams already in the score are kept
+    let before = score.clone();
+    add_chord_diagrams(&mut score, &parse_tuning("standard").unwrap());
+    assert_eq!(serde_json::to_value(&score).unwrap(), serde_json::to_value(&before).unwrap());
+}
+
+#[test]
+fn transposing_moves_diagrams() {
+    let standard = parse_tuning("standard").unwrap();
+    let mut score = parse_musicxml(CHORD_FRAMES).unwrap();
+    for semitones in [2, -3, 7] {
+        transpose_score(&mut score, semitones);
+        for harmony in &score.parts[0].measures[0].harmonies {
+            let frame = harmony.frame.as_ref().unwrap();
+            let root = chord_frame(&chord(&harmony.root.step, harmony.root.alter, "major"), &standard).unwrap();
+            assert_eq!(sounded(frame, &standard), sounded(&root, &standard), "{}", shape(frame));
+            assert!(frame.notes.iter().all(|n| n.fret >= 0), "{semitones} {}", shape(frame));
+        }
+    }
+}
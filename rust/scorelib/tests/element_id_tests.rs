@@ -0,0 +1,140 @@
+//! Element id tests — ids, classes and data attributes on notes, rests,
+//! chord symbols, lyrics and measures, and the note ids of the playback
+//! map.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use scorelib::renderer::note_id;
+use scorelib::{
+    generate_jianpu_playback_map, generate_playback_map, parse_file, render_score_to_horizontal_svg,
+    render_score_to_jianpu_svg, render_score_to_svg, RenderOptions, Score,
+};
+
+fn sheetmusic_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../sheetmusic")
+}
+
+fn output_dir() -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_output");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+fn tong_nian() -> Score {
+    parse_file(sheetmusic_dir().join("童年.mxl")).unwrap()
+}
+
+/// Every `id` in an SVG, in document order.
+fn ids(svg: &str) -> Vec<&str> {
+    svg.split(" id=\"").skip(1).map(|rest| &rest[..rest.find('"').unwrap()]).collect()
+}
+
+/// The opening tag of the element with `id`.
+fn tag<'a>(svg: &'a str, id: &str) -> &'a str {
+    let at = svg.find(&format!(" id=\"{id}\"")).unwrap_or_else(|| panic!("no element {id}"));
+    let start = svg[..at].rfind('<').unwrap();
+    &svg[start..start + svg[start..].find('>').unwrap() + 1]
+}
+
+fn tag_exists(svg: &str, id: &str) -> bool {
+    svg.contains(&format!(" id=\"{id}\""))
+}
+
+#[test]
+fn notes_rests_chords_and_lyrics_have_ids() {
+    let score = tong_nian();
+    let svg = render_score_to_svg(&score, None);
+    std::fs::write(output_dir().join("童年-ids.svg"), &svg).unwrap();
+
+    let ids = ids(&svg);
+    assert_eq!(ids.len(), ids.iter().collect::<HashSet<_>>().len(), "ids are unique");
+
+    for (mi, measure) in score.parts[0].measures.iter().enumerate() {
+        assert_eq!(
+            tag(&svg, &format!("m0-{mi}")),
+            format!(r#"<g id="m0-{mi}" class="measure" data-part="0" data-measure="{mi}">"#),
+        );
+        for (ni, note) in measure.notes.iter().enumerate() {
+            let note_tag = tag(&svg, &note_id(0, mi, ni));
+            let class = if note.rest { "rest" } else { "note" };
+            assert!(note_tag.contains(&format!(r#"class="{class}""#)), "{note_tag}");
+            let data = format!(r#"data-note="{ni}" data-voice="{}" data-staff="1""#, note.voice.unwrap_or(1));
+            assert!(note_tag.contains(&data), "{note_tag}");
+            for lyric in &note.lyrics {
+                assert!(tag_exists(&svg, &format!("l0-{mi}-{ni}-{}", lyric.number)));
+            }
+        }
+        for hi in 0..measure.harmonies.len() {
+            assert!(tag(&svg, &format!("h0-{mi}-{hi}")).contains(r#"class="chord-symbol""#));
+        }
+    }
+    assert!(svg.contains(r#"class="lyric""#));
+}
+
+#[test]
+fn playback_map_lists_the_note_ids() {
+    let score = tong_nian();
+    let svg = render_score_to_svg(&score, None);
+    let map = generate_playback_map(&score, None);
+
+    let mut listed = 0;
+    for measure in &map.measures {
+        for note in &measure.notes {
+            assert!(tag_exists(&svg, &note.id), "{} is not in the SVG", note.id);
+            assert_eq!(note.id, note_id(note.part, measure.measure_idx, note.note));
+            assert!((0.0..=1.0).contains(&note.onset) && note.end >= note.onset && note.end <= 1.0);
+            assert!(note.time_ms.is_some());
+        }
+        // Notes start in order within a voice
+        for voice in measure.notes.iter().map(|n| (n.staff, n.voice)).collect::<HashSet<_>>() {
+            let onsets: Vec<f64> = measure.notes.iter()
+                .filter(|n| (n.staff, n.voice) == voice)
+                .map(|n| n.onset)
+                .collect();
+            assert!(onsets.windows(2).all(|w| w[0] <= w[1]));
+        }
+        listed += measure.notes.len();
+    }
+    let pitched = score.parts[0].measures.iter()
+        .flat_map(|m| &m.notes)
+        .filter(|n| !n.rest && n.pitch.is_some())
+        .count();
+    assert_eq!(listed, pitched);
+
+    // Tap-to-seek: a note's time is inside its measure's first play
+    let measure = &map.measures[4];
+    let entry = map.timemap.iter().find(|e| e.original_index == measure.measure_idx).unwrap();
+    for note in &measure.notes {
+        let t = note.time_ms.unwrap();
+        assert!(t >= entry.timestamp_ms - 1e-6 && t < entry.timestamp_ms + entry.duration_ms);
+    }
+}
+
+#[test]
+fn every_rendering_uses_the_same_ids() {
+    let score = tong_nian();
+    let map = generate_jianpu_playback_map(&score, None);
+    let jianpu = render_score_to_jianpu_svg(&score, None);
+    let horizontal = render_score_to_horizontal_svg(&score, &RenderOptions::default());
+    for note in map.measures.iter().flat_map(|m| &m.notes) {
+        assert!(tag_exists(&jianpu, &note.id), "{} is not in the jianpu SVG", note.id);
+        assert!(tag_exists(&horizontal, &note.id), "{} is not in the horizontal SVG", note.id);
+    }
+
+    let ids = ids(&jianpu);
+    assert_eq!(ids.len(), ids.iter().collect::<HashSet<_>>().len(), "jianpu ids are unique");
+}
+
+#[test]
+fn grand_staff_ids_carry_the_staff() {
+    let score = parse_file(sheetmusic_dir().join("chopin-trois-valses.mxl")).unwrap();
+    let svg = render_score_to_svg(&score, None);
+    let ids = ids(&svg);
+    assert_eq!(ids.len(), ids.iter().collect::<HashSet<_>>().len(), "ids are unique");
+
+    let measure = &score.parts[0].measures[8];
+    let (ni, note) = measure.notes.iter().enumerate().find(|(_, n)| n.staff == Some(2)).unwrap();
+    let note_tag = tag(&svg, &note_id(0, 8, ni));
+    assert!(note_tag.contains(&format!(r#"data-voice="{}" data-staff="2""#, note.voice.unwrap_or(1))), "{note_tag}");
+}
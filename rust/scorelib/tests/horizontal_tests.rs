@@ -0,0 +1,103 @@
+//! Horizontal (single-line) rendering tests — one system, clefs and keys
+//! only where they change, and the playback map along the line.
+
+use std::path::PathBuf;
+
+use scorelib::renderer::{compute_horizontal_measure_positions, compute_measure_positions};
+use scorelib::{
+    generate_horizontal_playback_map, parse_file, render_score_to_horizontal_svg, RenderOptions, Score,
+};
+
+fn sheetmusic_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../sheetmusic")
+}
+
+fn output_dir() -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_output");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+fn chopin() -> Score {
+    parse_file(sheetmusic_dir().join("chopin-trois-valses.mxl")).unwrap()
+}
+
+/// Width and height from the SVG's viewBox.
+fn view_size(svg: &str) -> (f64, f64) {
+    let start = svg.find("viewBox=\"0 0 ").unwrap() + "viewBox=\"0 0 ".len();
+    let end = start + svg[start..].find('"').unwrap();
+    let mut size = svg[start..end].split(' ').map(|v| v.parse::<f64>().unwrap());
+    (size.next().unwrap(), size.next().unwrap())
+}
+
+#[test]
+fn every_measure_on_one_line() {
+    let score = chopin();
+    let svg = render_score_to_horizontal_svg(&score, &RenderOptions::default());
+    std::fs::write(output_dir().join("chopin-horizontal.svg"), &svg).unwrap();
+
+    let (measures, systems) = compute_horizontal_measure_positions(&score, &RenderOptions::default());
+    assert_eq!(systems.len(), 1);
+    assert_eq!(measures.len(), score.parts[0].measures.len());
+    for pair in measures.windows(2) {
+        assert_eq!(pair[1].0, pair[0].0 + 1);
+        assert!((pair[1].1 - (pair[0].1 + pair[0].2)).abs() < 1e-6, "measures {} and {} are not adjacent", pair[0].0 + 1, pair[1].0 + 1);
+        assert_eq!(pair[1].3, 0);
+    }
+
+    // The drawing is as wide as the line and as tall as one system, with
+    // no title
+    let last = measures.last().unwrap();
+    let (width, height) = view_size(&svg);
+    assert!(width > last.1 + last.2 && width < last.1 + last.2 + 50.0, "width {width}");
+    assert!(height < 400.0, "height {height}");
+    assert!(!svg.contains(">Trois Valses</text>"));
+
+    // Measures are roomier than on a page
+    let (wrapped, _) = compute_measure_positions(&score, None);
+    let line: f64 = measures.iter().map(|m| m.2).sum();
+    let pages: f64 = wrapped.iter().map(|m| m.2).sum();
+    assert!(line > pages, "{line} on one line, {pages} on pages");
+}
+
+#[test]
+fn clefs_are_drawn_where_they_change() {
+    let score = chopin();
+    let svg = render_score_to_horizontal_svg(&score, &RenderOptions::default());
+
+    // Opening clefs, then one for every change of a staff's clef
+    let mut current = std::collections::HashMap::new();
+    let (mut treble, mut bass) = (0, 0);
+    for clef in score.parts[0].measures.iter().flat_map(|m| m.attributes.iter().flat_map(|a| &a.clefs)) {
+        if current.insert(clef.number, clef.sign.clone()).as_ref() != Some(&clef.sign) {
+            match clef.sign.as_str() {
+                "G" => treble += 1,
+                "F" => bass += 1,
+                _ => {}
+            }
+        }
+    }
+    assert!(treble > 1 && bass > 1, "Chopin changes clefs");
+    assert_eq!(svg.matches("M156.716").count(), treble, "treble clefs");
+    assert_eq!(svg.matches("M176.014").count(), bass, "bass clefs");
+}
+
+#[test]
+fn playback_map_follows_the_line() {
+    let score = parse_file(sheetmusic_dir().join("asa-branca.musicxml")).unwrap();
+    let map = generate_horizontal_playback_map(&score, &RenderOptions::default());
+    assert_eq!(map.systems.len(), 1);
+    assert!(!map.timemap.is_empty());
+
+    let (measures, _) = compute_horizontal_measure_positions(&score, &RenderOptions::default());
+    for (position, (idx, x, width, _, _)) in map.measures.iter().zip(&measures) {
+        assert_eq!(position.measure_idx, *idx);
+        assert_eq!((position.x, position.width), (*x, *width));
+        assert!(position.note_positions.iter().all(|&(_, nx)| nx >= *x && nx <= x + width + 1e-6));
+        assert!(position.note_positions.windows(2).all(|p| p[0].1 <= p[1].1));
+    }
+
+    let empty = render_score_to_horizontal_svg(&Score::new(), &RenderOptions::default());
+    assert!(empty.contains("No parts in score"));
+    assert!(compute_horizontal_measure_positions(&Score::new(), &RenderOptions::default()).0.is_empty());
+}
@@ -0,0 +1,135 @@
+//! Jianpu (numbered notation) rendering tests — degrees, key labels,
+//! layout and the playback map that follows it.
+
+use std::path::PathBuf;
+
+use scorelib::{
+    generate_jianpu_playback_map, parse_file, render_score_to_jianpu_svg, Score,
+};
+use scorelib::renderer::compute_jianpu_measure_positions;
+
+fn sheetmusic_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../sheetmusic")
+}
+
+fn output_dir() -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_output");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+/// Contents of every `<text>` element, in document order.
+fn texts(svg: &str) -> Vec<&str> {
+    svg.lines()
+        .filter(|l| l.trim_start().starts_with("<text"))
+        .filter_map(|l| {
+            let start = l.find('>')? + 1;
+            let end = l.rfind("</text>")?;
+            Some(&l[start..end])
+        })
+        .collect()
+}
+
+#[test]
+fn jianpu_tong_nian() {
+    let score = parse_file(sheetmusic_dir().join("童年.mxl")).expect("Failed to parse 童年");
+    let svg = render_score_to_jianpu_svg(&score, None);
+    std::fs::write(output_dir().join("童年-jianpu.svg"), &svg).unwrap();
+
+    let texts = texts(&svg);
+    assert!(texts.contains(&"1=C  4/4"), "key and time label");
+    for degree in ["0", "1", "2", "3", "5", "6"] {
+        assert!(texts.contains(&degree), "degree {degree} should be drawn");
+    }
+    // Chord symbols and lyrics are kept
+    assert!(texts.contains(&"Am7") && texts.contains(&"G7"));
+    assert!(texts.contains(&"童"));
+    println!("✓ 童年: {} text elements", texts.len());
+}
+
+#[test]
+fn jianpu_key_changes_are_labelled() {
+    let score = parse_file(sheetmusic_dir().join("blue-bag-folly.musicxml"))
+        .expect("Failed to parse blue-bag-folly");
+    let svg = render_score_to_jianpu_svg(&score, None);
+    std::fs::write(output_dir().join("blue-bag-folly-jianpu.svg"), &svg).unwrap();
+
+    let texts = texts(&svg);
+    assert!(texts.contains(&"1=F  4/4"));
+    assert!(texts.contains(&"1=♭G"), "modulation up a half step");
+    assert!(texts.contains(&"1=G"), "second modulation");
+}
+
+#[test]
+fn jianpu_positions_match_svg() {
+    let score = parse_file(sheetmusic_dir().join("童年.mxl")).expect("Failed to parse 童年");
+    let svg = render_score_to_jianpu_svg(&score, None);
+    let (measures, systems) = compute_jianpu_measure_positions(&score, None);
+
+    let part = &score.parts[0];
+    assert_eq!(measures.len(), part.measures.len());
+    for (mi, &(measure_idx, x, width, system_idx, ref beats)) in measures.iter().enumerate() {
+        assert_eq!(measure_idx, mi);
+        assert!(system_idx < systems.len());
+        assert!(beats.iter().all(|&(_, bx)| bx >= x && bx <= x + width));
+
+        // Plain right barlines are drawn at the measure's right edge
+        let special = part.measures[mi].barlines.iter()
+            .any(|b| b.location == "right" && b.bar_style.is_some());
+        if !special {
+            let x1 = format!("x1=\"{:.1}\"", x + width);
+            assert!(svg.contains(&x1), "measure {} should end at {:.1}", mi + 1, x + width);
+        }
+    }
+    for &(y, height) in &systems {
+        let y1 = format!("y1=\"{:.1}\"", y);
+        assert!(svg.contains(&y1), "system at {y:.1}, height {height:.1}");
+    }
+}
+
+#[test]
+fn jianpu_playback_map() {
+    let score = parse_file(sheetmusic_dir().join("chopin-trois-valses.mxl"))
+        .expect("Failed to parse chopin");
+    let pmap = generate_jianpu_playback_map(&score, None);
+    assert_eq!(pmap.measures.len(), score.parts[0].measures.len());
+    assert!(!pmap.timemap.is_empty());
+
+    // Two rows per system for the piano's two staves
+    let (_, systems) = compute_jianpu_measure_positions(&score, None);
+    assert_eq!(pmap.systems.len(), systems.len());
+    for pair in pmap.systems.windows(2) {
+        assert!(pair[1].y >= pair[0].y + pair[0].height, "systems must not overlap");
+    }
+}
+
+#[test]
+fn jianpu_page_width() {
+    let score = parse_file(sheetmusic_dir().join("童年.mxl")).expect("Failed to parse 童年");
+    let (_, wide) = compute_jianpu_measure_positions(&score, Some(1200.0));
+    let (_, narrow) = compute_jianpu_measure_positions(&score, Some(400.0));
+    assert!(narrow.len() > wide.len(), "{} vs {} systems", narrow.len(), wide.len());
+    let svg = render_score_to_jianpu_svg(&score, Some(400.0));
+    assert!(svg.contains("width=\"400\""));
+}
+
+#[test]
+fn jianpu_empty_score() {
+    let score = Score {
+        title: None,
+        title_style: None,
+        subtitle: None,
+        subtitle_style: None,
+        composer: None,
+        composer_style: None,
+        arranger: None,
+        version: None,
+        software: None,
+        defaults: None,
+        parts: Vec::new(),
+    };
+    let svg = render_score_to_jianpu_svg(&score, None);
+    assert!(svg.starts_with("<svg"));
+    let (measures, systems) = compute_jianpu_measure_positions(&score, None);
+    assert!(measures.is_empty() && systems.is_empty());
+}
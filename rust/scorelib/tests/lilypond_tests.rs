@@ -0,0 +1,102 @@
+//! Integration tests for the LilyPond writer.
+
+use std::path::PathBuf;
+
+use scorelib::{parse_abc, parse_file, transpose_score, write_lilypond};
+
+const TUNE: &str = r#"X:1
+T:The Test Reel
+C:Trad.
+M:6/8
+L:1/8
+K:D
+|:"D"A>BA (3Bcd e | (f3 f2)^g | [DF]2A- A2z |1 "G/B"d2c B2A :|2 d3- d2 z |]
+w: Hel-lo there my friend to-geth-er * go
+"#;
+
+fn sheetmusic_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../sheetmusic")
+}
+
+/// Every `{`, `<<` and `(` has its closing partner.
+fn assert_balanced(ly: &str, name: &str) {
+    // Strip string literals so quoted text can't unbalance the counts
+    let mut code = String::new();
+    let mut in_string = false;
+    let mut chars = ly.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_string => { chars.next(); }
+            '"' => in_string = !in_string,
+            _ if !in_string => code.push(c),
+            _ => {}
+        }
+    }
+    assert_eq!(code.matches('{').count(), code.matches('}').count(), "{name}: braces");
+    assert_eq!(code.matches("<<").count(), code.matches(">>").count(), "{name}: simultaneous music");
+    assert_eq!(code.matches('(').count(), code.matches(')').count(), "{name}: slurs");
+}
+
+#[test]
+fn lilypond_writes_header_and_notes() {
+    let score = parse_abc(TUNE).unwrap();
+    let ly = write_lilypond(&score);
+    assert!(ly.starts_with("\\version \"2.24.0\""));
+    assert!(ly.contains("title = \"The Test Reel\""));
+    assert!(ly.contains("composer = \"Trad.\""));
+
+    // Clef, key and time, then a dotted rhythm, beams and a triplet
+    assert!(ly.contains("\\clef treble \\key d \\major \\time 6/8 a'8.[ b'16 a'8] \\tuplet 3/2 { b'8[ cis''8 d''8] } e''8 |"), "{ly}");
+    // Slur, accidental, chord and ties
+    assert!(ly.contains("fis''4.( fis''4) gis''8 |"), "{ly}");
+    assert!(ly.contains("<d' fis'>4 a'8~ a'4 r8 |"), "{ly}");
+    assert!(ly.contains("d''4.~ d''4 r8 \\bar \"|.\" |"), "{ly}");
+    assert_balanced(&ly, "tune");
+}
+
+#[test]
+fn lilypond_writes_repeats_chords_and_lyrics() {
+    let score = parse_abc(TUNE).unwrap();
+    let ly = write_lilypond(&score);
+    assert!(ly.contains("\\repeat volta 2 {"));
+    assert_eq!(ly.matches("\\alternative {").count(), 2, "notes and chords both have the endings");
+
+    assert!(ly.contains("partAChords = \\chordmode {"));
+    assert!(ly.contains("d2. |"));
+    assert!(ly.contains("g2./b |"), "slash chords keep their bass");
+    assert!(ly.contains("\\new ChordNames \\partAChords"));
+
+    assert!(ly.contains("\"Hel\" -- \"lo\" \"there\""), "{ly}");
+    assert!(ly.contains("\\addlyrics { \\partAVerseA }"));
+}
+
+#[test]
+fn lilypond_follows_transposition() {
+    let mut score = parse_abc(TUNE).unwrap();
+    transpose_score(&mut score, 2);
+    let ly = write_lilypond(&score);
+    assert!(ly.contains("\\key e \\major"), "{ly}");
+    assert!(ly.contains("b'8.[ cis''16 b'8]"), "{ly}");
+    assert!(ly.contains("a2./cis |"), "{ly}");
+}
+
+#[test]
+fn lilypond_output_is_well_formed_for_all_samples() {
+    let mut count = 0;
+    for entry in std::fs::read_dir(sheetmusic_dir()).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let score = parse_file(&path).unwrap();
+        let ly = write_lilypond(&score);
+        assert_balanced(&ly, &name);
+        assert!(ly.contains("\\score {"), "{name}: no score block");
+        for part in 0..score.parts.len() {
+            let var = (b'A' + part as u8) as char;
+            assert!(ly.contains(&format!("\\new Staff \\part{var}")) || ly.contains("\\new PianoStaff"),
+                "{name}: part {part} has no staff");
+        }
+        count += 1;
+        println!("✓ {name}: {} bytes of LilyPond", ly.len());
+    }
+    assert!(count >= 4);
+}
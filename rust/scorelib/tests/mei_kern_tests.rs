@@ -0,0 +1,329 @@
+//! Integration tests for MEI and Humdrum **kern import.
+
+use scorelib::{
+    generate_midi_from_score, parse_bytes, parse_kern, parse_mei, render_score_to_svg, unroll,
+    MidiOptions, Note, Score,
+};
+
+const MEI: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<mei xmlns="http://www.music-encoding.org/ns/mei" meiversion="5.0">
+  <meiHead>
+    <fileDesc>
+      <titleStmt>
+        <title>Little Song</title>
+        <title type="subtitle">for voice and piano</title>
+        <respStmt><persName role="composer">Anna Example</persName></respStmt>
+      </titleStmt>
+      <pubStmt/>
+    </fileDesc>
+  </meiHead>
+  <music><body><mdiv><score>
+    <scoreDef meter.count="4" meter.unit="4" key.sig="2f" key.mode="major">
+      <staffGrp>
+        <staffDef n="1" lines="5" clef.shape="G" clef.line="2"><label>Voice</label></staffDef>
+        <staffGrp symbol="brace" label="Piano">
+          <staffDef n="2" lines="5" clef.shape="G" clef.line="2"/>
+          <staffDef n="3" lines="5"><clef shape="F" line="4"/></staffDef>
+        </staffGrp>
+      </staffGrp>
+    </scoreDef>
+    <section>
+      <measure n="1" left="rptstart">
+        <staff n="1"><layer n="1">
+          <note xml:id="v1" pname="b" oct="4" dur="4" accid.ges="f"><verse n="1"><syl wordpos="i" con="d">Hel</syl></verse></note>
+          <note xml:id="v2" pname="c" oct="5" dur="4"><verse n="1"><syl wordpos="t">lo</syl></verse></note>
+          <note xml:id="v3" pname="d" oct="5" dur="2" tie="i"><verse n="1"><syl>world</syl></verse></note>
+        </layer></staff>
+        <staff n="2"><layer n="1">
+          <beam>
+            <note pname="b" oct="4" dur="8"/>
+            <note pname="e" oct="5" dur="8" accid="n"/>
+            <note pname="e" oct="5" dur="16"/>
+            <note pname="f" oct="5" dur="16"><accid accid="s"/></note>
+            <note pname="g" oct="5" dur="8" artic="stacc"/>
+          </beam>
+          <tuplet num="3" numbase="2">
+            <beam>
+              <note pname="f" oct="5" dur="8"/>
+              <note pname="e" oct="5" dur="8"/>
+              <note pname="d" oct="5" dur="8"/>
+            </beam>
+          </tuplet>
+          <rest dur="4"/>
+        </layer></staff>
+        <staff n="3"><layer n="1">
+          <chord dur="1"><note pname="b" oct="2"/><note pname="f" oct="3"/></chord>
+        </layer></staff>
+        <slur startid="#v1" endid="#v2"/>
+        <tempo tstamp="1" staff="1" midi.bpm="80">Andante</tempo>
+        <dynam tstamp="1" staff="2">p</dynam>
+      </measure>
+      <sb/>
+      <ending n="1">
+        <measure n="2" right="rptend">
+          <staff n="1"><layer n="1">
+            <note pname="d" oct="5" dur="2" tie="t"/>
+            <rest dur="2"/>
+          </layer></staff>
+          <staff n="2"><layer n="1"><mRest/></layer></staff>
+          <staff n="3"><layer n="1"><mRest/></layer></staff>
+        </measure>
+      </ending>
+      <ending n="2">
+        <measure n="3" right="end">
+          <staff n="1"><layer n="1">
+            <note xml:id="v5" pname="b" oct="4" dur="1" accid="f"/>
+          </layer></staff>
+          <staff n="2"><layer n="1">
+            <chord dur="2" dots="1"><note pname="d" oct="5"/><note pname="f" oct="5"/></chord>
+            <space dur="4"/>
+          </layer></staff>
+          <staff n="3"><layer n="1"><note pname="b" oct="2" dur="1"/></layer></staff>
+          <fermata startid="#v5" place="above"/>
+        </measure>
+      </ending>
+    </section>
+  </score></mdiv></body></music>
+</mei>
+"##;
+
+/// A voice with lyrics over a piano whose two staves share `*part1`;
+/// the treble staff splits into two voices in bar 2.
+const KERN: &str = "\
+!!!COM: Example, Anna
+!!!OTL: Test Chorale
+**kern\t**kern\t**kern\t**text
+*part1\t*part1\t*part2\t*part2
+*staff2\t*staff1\t*staff3\t*staff3
+*I\"Piano\t*I\"Piano\t*I\"Soprano\t*
+*clefF4\t*clefG2\t*clefG2\t*
+*k[f#]\t*k[f#]\t*k[f#]\t*
+*G:\t*G:\t*G:\t*
+*M3/4\t*M3/4\t*M3/4\t*
+*MM96\t*MM96\t*MM96\t*
+4GG\t4d\t8gL\tHal-
+.\t.\t8aJ\t.
+=1\t=1\t=1\t=1
+2G\t(4b\t4.b\t-le-
+.\t8cc#L\t.\t.
+.\t8ddJ)\t8a\t-lu
+4r\t4dd\t4g\tjah
+=2\t=2\t=2\t=2
+*\t*^\t*\t*
+2.G[\t12ddL\t2.g\t2d\tPraise
+.\t12cc\t.\t.\t.
+.\t12bJ\t.\t.\t.
+.\t2a\t.\t.\t.
+.\t.\t.\t4r\t.
+=3\t=3\t=3\t=3\t=3
+*\t*v\t*v\t*\t*
+2.G]\t2.b-\t2.g;\t.
+==\t==\t==\t==
+*-\t*-\t*-\t*-
+";
+
+fn pitch(note: &Note) -> String {
+    let p = note.pitch.as_ref().unwrap();
+    let alter = match p.alter {
+        Some(a) if a > 0.0 => "#",
+        Some(_) => "b",
+        None => "",
+    };
+    format!("{}{alter}{}", p.step, p.octave)
+}
+
+fn pitches(score: &Score, part: usize, measure: usize) -> Vec<String> {
+    score.parts[part].measures[measure].notes.iter()
+        .filter(|n| n.pitch.is_some())
+        .map(pitch)
+        .collect()
+}
+
+fn beams(note: &Note) -> Vec<(i32, &str)> {
+    note.beams.iter().map(|b| (b.number, b.beam_type.as_str())).collect()
+}
+
+#[test]
+fn mei_header_parts_and_attributes() {
+    let score = parse_mei(MEI).unwrap();
+    assert_eq!(score.title.as_deref(), Some("Little Song"));
+    assert_eq!(score.subtitle.as_deref(), Some("for voice and piano"));
+    assert_eq!(score.composer.as_deref(), Some("Anna Example"));
+
+    assert_eq!(score.parts.len(), 2, "the braced staves form one piano part");
+    assert_eq!(score.parts[0].name, "Voice");
+    assert_eq!(score.parts[1].name, "Piano");
+    assert!(score.parts.iter().all(|p| p.measures.len() == 3));
+
+    let attrs = score.parts[1].measures[0].attributes.as_ref().unwrap();
+    assert_eq!(attrs.divisions, Some(12), "sixteenths and triplet eighths");
+    assert_eq!(attrs.key.as_ref().map(|k| k.fifths), Some(-2));
+    assert_eq!(attrs.time.as_ref().map(|t| (t.beats, t.beat_type)), Some((4, 4)));
+    assert_eq!(attrs.staves, Some(2));
+    let clefs: Vec<(i32, &str, i32)> = attrs.clefs.iter().map(|c| (c.number, c.sign.as_str(), c.line)).collect();
+    assert_eq!(clefs, [(1, "G", 2), (2, "F", 4)]);
+}
+
+#[test]
+fn mei_notes_accidentals_beams_and_tuplets() {
+    let score = parse_mei(MEI).unwrap();
+
+    // Key signature, @accid.ges, @accid and <accid> all set the pitch
+    assert_eq!(pitches(&score, 0, 0), ["Bb4", "C5", "D5"]);
+    assert_eq!(pitches(&score, 1, 0)[..5], ["Bb4", "E5", "E5", "F#5", "G5"]);
+    let piano = &score.parts[1].measures[0].notes;
+    assert_eq!(piano[0].accidental, None);
+    assert_eq!(piano[1].accidental.as_deref(), Some("natural"));
+    assert_eq!(piano[2].accidental, None, "the natural holds for the rest of the measure");
+    assert_eq!(piano[3].accidental.as_deref(), Some("sharp"));
+    assert_eq!(piano[4].articulations, ["staccato"]);
+
+    assert_eq!(beams(&piano[0]), [(1, "begin")]);
+    assert_eq!(beams(&piano[2]), [(1, "continue"), (2, "begin")]);
+    assert_eq!(beams(&piano[3]), [(1, "continue"), (2, "end")]);
+    assert_eq!(beams(&piano[4]), [(1, "end")]);
+
+    // Triplet eighths take a third of a beat each
+    let triplet = &piano[5..8];
+    assert!(triplet.iter().all(|n| n.duration == 4 && n.time_modification.as_ref().is_some_and(|t| (t.actual_notes, t.normal_notes) == (3, 2))));
+    assert_eq!(triplet.iter().map(|n| n.onset).collect::<Vec<_>>(), [24, 28, 32]);
+    assert_eq!(triplet[0].tuplets[0].tuplet_type, "start");
+    assert_eq!(triplet[2].tuplets[0].tuplet_type, "stop");
+    assert!(piano[8].rest && piano[8].onset == 36);
+
+    // The bass chord is on staff 2 in its own voice
+    let chord = &piano[9..];
+    assert_eq!(chord.iter().map(pitch).collect::<Vec<_>>(), ["Bb2", "F3"]);
+    assert!(!chord[0].chord && chord[1].chord);
+    assert!(chord.iter().all(|n| n.staff == Some(2) && n.voice == Some(5) && n.duration == 48));
+}
+
+#[test]
+fn mei_ties_slurs_lyrics_and_structure() {
+    let score = parse_mei(MEI).unwrap();
+    let voice = &score.parts[0].measures;
+    let lyrics: Vec<(&str, &str)> = voice[0].notes.iter()
+        .map(|n| (n.lyrics[0].text.as_str(), n.lyrics[0].syllabic.as_deref().unwrap()))
+        .collect();
+    assert_eq!(lyrics, [("Hel", "begin"), ("lo", "end"), ("world", "single")]);
+    assert_eq!(voice[0].notes[0].slurs[0].slur_type, "start");
+    assert_eq!(voice[0].notes[1].slurs[0].slur_type, "stop");
+    assert!(voice[0].notes[2].tie_start && voice[1].notes[0].tie_stop);
+    assert_eq!(voice[2].notes[0].accidental.as_deref(), Some("flat"));
+    assert_eq!(voice[2].notes[0].fermata.as_deref(), Some("upright"));
+
+    let tempo = &voice[0].directions[0];
+    assert_eq!((tempo.sound_tempo, tempo.words.as_deref()), (Some(80.0), Some("Andante")));
+    assert_eq!(score.parts[1].measures[0].directions[0].dynamics.as_deref(), Some("p"));
+    assert!(score.parts[1].measures[1].notes.iter().all(|n| n.measure_rest));
+    assert!(voice[1].new_system);
+
+    // Repeat with first and second endings
+    let order: Vec<usize> = unroll(&score, 0).iter().map(|u| u.original_index).collect();
+    assert_eq!(order, [0, 1, 0, 2]);
+    let ending = |m: usize, location: &str| voice[m].barlines.iter()
+        .find(|b| b.location == location)
+        .and_then(|b| b.ending.as_ref())
+        .map(|e| (e.number.as_str(), e.ending_type.as_str()));
+    assert_eq!(ending(1, "left"), Some(("1", "start")));
+    assert_eq!(ending(1, "right"), Some(("1", "stop")));
+    assert_eq!(ending(2, "right"), Some(("2", "discontinue")));
+}
+
+#[test]
+fn kern_spines_become_parts_and_staves() {
+    let score = parse_kern(KERN).unwrap();
+    assert_eq!(score.title.as_deref(), Some("Test Chorale"));
+    assert_eq!(score.composer.as_deref(), Some("Example, Anna"));
+
+    // Rightmost spine is the top staff
+    assert_eq!(score.parts.len(), 2);
+    assert_eq!(score.parts[0].name, "Soprano");
+    assert_eq!(score.parts[1].name, "Piano");
+    let measures = &score.parts[1].measures;
+    assert_eq!(measures.len(), 4);
+    assert!(measures[0].implicit && measures[0].number == 0, "a one-beat pickup");
+    assert_eq!(measures[1].number, 1);
+
+    let attrs = measures[0].attributes.as_ref().unwrap();
+    assert_eq!(attrs.divisions, Some(6));
+    assert_eq!(attrs.key.as_ref().map(|k| (k.fifths, k.mode.as_deref())), Some((1, Some("major"))));
+    assert_eq!(attrs.time.as_ref().map(|t| (t.beats, t.beat_type)), Some((3, 4)));
+    assert_eq!(attrs.staves, Some(2));
+    let clefs: Vec<(i32, &str)> = attrs.clefs.iter().map(|c| (c.number, c.sign.as_str())).collect();
+    assert_eq!(clefs, [(1, "G"), (2, "F")]);
+    assert_eq!(score.parts[0].measures[0].directions[0].sound_tempo, Some(96.0));
+
+    // Pickup: the treble staff first, then the bass
+    assert_eq!(pitches(&score, 1, 0), ["D4", "G2"]);
+    assert_eq!(measures[0].notes[1].staff, Some(2));
+    assert_eq!(measures[0].notes[1].voice, Some(5));
+}
+
+#[test]
+fn kern_notes_voices_and_lyrics() {
+    let score = parse_kern(KERN).unwrap();
+    let piano = &score.parts[1].measures;
+
+    // Slur, beam and a sharp outside the key
+    let bar1 = &piano[1].notes;
+    assert_eq!(bar1.iter().filter(|n| n.staff == Some(1)).map(pitch).collect::<Vec<_>>(), ["B4", "C#5", "D5", "D5"]);
+    assert_eq!(bar1[0].slurs[0].slur_type, "start");
+    assert_eq!(bar1[2].slurs[0].slur_type, "stop");
+    assert_eq!(beams(&bar1[1]), [(1, "begin")]);
+    assert_eq!(beams(&bar1[2]), [(1, "end")]);
+    assert_eq!(bar1[1].accidental.as_deref(), Some("sharp"));
+    assert!(bar1[4].pitch.is_some() && bar1[5].rest);
+
+    // Split treble: triplet eighths in voice 1, a dotted half in voice 2
+    let bar2 = &piano[2].notes;
+    let triplet: Vec<&Note> = bar2.iter().filter(|n| n.voice == Some(1) && n.time_modification.is_some()).collect();
+    assert_eq!(triplet.len(), 3);
+    assert!(triplet.iter().all(|n| n.duration == 2 && n.note_type.as_deref() == Some("eighth")));
+    assert_eq!(triplet[0].tuplets[0].tuplet_type, "start");
+    assert_eq!(triplet[2].tuplets[0].tuplet_type, "stop");
+    assert_eq!(bar2.iter().filter(|n| n.voice == Some(2)).map(pitch).collect::<Vec<_>>(), ["G4"]);
+    assert!(bar2.iter().any(|n| n.voice == Some(2) && n.dot && n.duration == 18));
+
+    // Tie across the barline, and the flat after the voices join
+    assert!(bar2.iter().any(|n| n.staff == Some(2) && n.tie_start));
+    assert!(piano[3].notes.iter().any(|n| n.staff == Some(2) && n.tie_stop));
+    assert_eq!(piano[3].notes[0].accidental.as_deref(), Some("flat"));
+    assert!(piano[3].barlines.iter().any(|b| b.bar_style.as_deref() == Some("light-heavy")));
+
+    // Lyrics from the **text spine
+    let voice = &score.parts[0].measures;
+    let syllables: Vec<(String, String)> = voice.iter()
+        .flat_map(|m| &m.notes)
+        .flat_map(|n| &n.lyrics)
+        .map(|l| (l.text.clone(), l.syllabic.clone().unwrap()))
+        .collect();
+    let expected = [("Hal", "begin"), ("le", "middle"), ("lu", "end"), ("jah", "single"), ("Praise", "single")];
+    assert_eq!(syllables, expected.map(|(t, s)| (t.to_string(), s.to_string())));
+    assert_eq!(voice[3].notes[0].fermata.as_deref(), Some("upright"));
+}
+
+#[test]
+fn mei_and_kern_are_detected_and_play() {
+    let mei = parse_bytes(MEI.as_bytes(), None).unwrap();
+    assert_eq!(mei.title.as_deref(), Some("Little Song"));
+    let kern = parse_bytes(KERN.as_bytes(), None).unwrap();
+    assert_eq!(kern.title.as_deref(), Some("Test Chorale"));
+    assert!(parse_bytes(KERN.as_bytes(), Some("krn")).is_ok());
+    assert!(parse_bytes(MEI.as_bytes(), Some("mei")).is_ok());
+
+    for score in [&mei, &kern] {
+        let svg = render_score_to_svg(score, None);
+        assert!(svg.starts_with("<svg"));
+        let midi = generate_midi_from_score(score, &MidiOptions { include_metronome: false, ..MidiOptions::default() });
+        let note_ons = scorelib::midi::read_smf(&midi).unwrap().tracks.iter()
+            .flat_map(|t| &t.events)
+            .filter(|e| e.bytes.len() == 3 && e.bytes[0] & 0xF0 == 0x90 && e.bytes[2] > 0)
+            .count();
+        assert!(note_ons > 10);
+    }
+
+    assert!(parse_kern("**dynam\n*-\n").is_err());
+    assert!(parse_kern("**kern\t**kern\n4c\n").is_err(), "a record with the wrong number of spines");
+    assert!(parse_mei("<mei><music/></mei>").is_err());
+}
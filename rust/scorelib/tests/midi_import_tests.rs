@@ -0,0 +1,175 @@
+//! Integration tests for Standard MIDI File import.
+
+use scorelib::{
+    generate_midi_from_score, parse_bytes, parse_file, parse_midi_with_options, write_musicxml,
+    MidiImportOptions, MidiOptions, Note, Score,
+};
+
+/// Encode a single-track (Type 0) SMF from (delta, event bytes) pairs.
+fn type0_smf(ticks_per_quarter: u16, events: &[(u32, &[u8])]) -> Vec<u8> {
+    let mut track = Vec::new();
+    for &(delta, bytes) in events {
+        // Deltas in these tests stay below 2^14
+        if delta >= 0x80 {
+            track.push(0x80 | (delta >> 7) as u8);
+        }
+        track.push((delta & 0x7F) as u8);
+        track.extend_from_slice(bytes);
+    }
+    track.extend_from_slice(&[0x00, 0xFF, 0x2F, 0x00]);
+
+    let mut smf = b"MThd".to_vec();
+    smf.extend_from_slice(&6u32.to_be_bytes());
+    smf.extend_from_slice(&0u16.to_be_bytes());
+    smf.extend_from_slice(&1u16.to_be_bytes());
+    smf.extend_from_slice(&ticks_per_quarter.to_be_bytes());
+    smf.extend_from_slice(b"MTrk");
+    smf.extend_from_slice(&(track.len() as u32).to_be_bytes());
+    smf.extend(track);
+    smf
+}
+
+fn sounding(notes: &[Note]) -> Vec<&Note> {
+    notes.iter().filter(|n| !n.rest).collect()
+}
+
+fn pitch_name(note: &Note) -> String {
+    let p = note.pitch.as_ref().unwrap();
+    let alter = match p.alter.map(|a| a as i32) {
+        Some(1) => "#",
+        Some(-1) => "b",
+        _ => "",
+    };
+    format!("{}{}{}", p.step, alter, p.octave)
+}
+
+#[test]
+fn midi_import_reads_meta_events_and_ties_across_barlines() {
+    // 480 ticks per quarter: D major, 3/4, quarter = 90
+    let q = 480;
+    let smf = type0_smf(q as u16, &[
+        (0, &[0xFF, 0x03, 0x04, b'S', b'o', b'n', b'g']),
+        (0, &[0xFF, 0x58, 0x04, 0x03, 0x02, 0x18, 0x08]),
+        (0, &[0xFF, 0x59, 0x02, 0x02, 0x00]),
+        (0, &[0xFF, 0x51, 0x03, 0x0A, 0x2C, 0x2A]),
+        (0, &[0xC0, 40]),
+        (0, &[0xC1, 32]),
+        // Channel 1: D3 for a whole 3/4 measure
+        (0, &[0x91, 50, 80]),
+        // Channel 0: F#4 (slightly late), then a D4+A4 chord
+        (7, &[0x90, 66, 90]),
+        (q - 7, &[0x80, 66, 0]),
+        (0, &[0x90, 62, 90]),
+        (0, &[0x90, 69, 90]),
+        (q, &[0x80, 62, 0]),
+        (0, &[0x80, 69, 0]),
+        // C natural (outside D major), held across the barline for 2 beats
+        (0, &[0x90, 72, 90]),
+        (q, &[0x81, 50, 0]),
+        (q, &[0x80, 72, 0]),
+    ]);
+
+    let score = parse_bytes(&smf, None).unwrap();
+    assert_eq!(score.title.as_deref(), Some("Song"));
+    assert_eq!(score.parts.len(), 2);
+    let (melody, bass) = (&score.parts[0], &score.parts[1]);
+    assert_eq!((melody.midi_channel, melody.midi_program), (Some(1), Some(41)));
+    assert_eq!((bass.midi_channel, bass.midi_program), (Some(2), Some(33)));
+    assert_eq!(bass.name, "Bass");
+    assert_eq!(melody.measures.len(), 2);
+
+    let attrs = melody.measures[0].attributes.as_ref().unwrap();
+    let time = attrs.time.as_ref().unwrap();
+    assert_eq!((time.beats, time.beat_type), (3, 4));
+    assert_eq!(attrs.key.as_ref().unwrap().fifths, 2);
+    assert_eq!(attrs.clefs[0].sign, "G");
+    assert_eq!(bass.measures[0].attributes.as_ref().unwrap().clefs[0].sign, "F");
+    let tempo = melody.measures[0].directions[0].sound_tempo.unwrap();
+    assert!((tempo - 90.0).abs() < 0.01, "tempo {tempo}");
+
+    // Measure 1: F#4 quantized onto beat 1, the chord, then C5 tied over
+    let m1 = sounding(&melody.measures[0].notes);
+    let names: Vec<String> = m1.iter().map(|n| pitch_name(n)).collect();
+    assert_eq!(names, vec!["F#4", "D4", "A4", "C5"]);
+    assert_eq!(m1[0].onset, 0);
+    assert!(m1[0].accidental.is_none(), "F# is in the key");
+    assert!(m1[2].chord);
+    assert_eq!(m1[3].accidental.as_deref(), Some("natural"));
+    assert!(m1[3].tie_start && !m1[3].tie_stop);
+
+    // Measure 2: the tied continuation, then a rest to fill the bar
+    let m2 = &melody.measures[1].notes;
+    assert_eq!(pitch_name(&m2[0]), "C5");
+    assert!(m2[0].tie_stop && !m2[0].tie_start);
+    assert!(m2[0].accidental.is_none(), "tied notes don't repeat the accidental");
+    assert!(m2[1].rest);
+    let total: i32 = m2.iter().filter(|n| !n.chord).map(|n| n.duration).sum();
+    assert_eq!(total, 3 * 4, "3/4 at 4 divisions per quarter");
+
+    // Bass: dotted half in measure 1, a measure rest in measure 2
+    let b1 = &bass.measures[0].notes;
+    assert_eq!((b1.len(), b1[0].note_type.as_deref(), b1[0].dot), (1, Some("half"), true));
+    assert!(bass.measures[1].notes[0].measure_rest);
+
+    // The imported score can be written out as MusicXML
+    assert!(write_musicxml(&score).contains("<fifths>2</fifths>"));
+    println!("✓ Imported 2 parts in D major 3/4 with a tie across the barline");
+}
+
+#[test]
+fn midi_import_quantizes_to_the_grid() {
+    // Eighth notes, each a little early or late
+    let q = 96;
+    let mut events: Vec<(u32, Vec<u8>)> = Vec::new();
+    let offsets = [0i32, 5, -4, 3, -2, 6, -5, 1];
+    let mut now = 0i32;
+    for (i, off) in offsets.iter().enumerate() {
+        let start = i as i32 * q / 2 + off;
+        events.push(((start - now) as u32, vec![0x90, 60 + i as u8, 100]));
+        events.push(((q / 2 - 16) as u32, vec![0x80, 60 + i as u8, 0]));
+        now = start + q / 2 - 16;
+    }
+    let events: Vec<(u32, &[u8])> = events.iter().map(|(d, b)| (*d, b.as_slice())).collect();
+    let smf = type0_smf(q as u16, &events);
+
+    let score = parse_midi_with_options(&smf, &MidiImportOptions { grid: 2 }).unwrap();
+    let notes = sounding(&score.parts[0].measures[0].notes);
+    assert_eq!(notes.len(), 8);
+    for (i, n) in notes.iter().enumerate() {
+        assert_eq!(n.onset, i as i32, "note {i} onset");
+        assert_eq!(n.note_type.as_deref(), Some("eighth"));
+    }
+    // Eighths are beamed in pairs, one group per beat
+    let beams: Vec<&str> = notes.iter().map(|n| n.beams[0].beam_type.as_str()).collect();
+    assert_eq!(beams, ["begin", "end"].repeat(4));
+    println!("✓ Eight uneven notes quantized onto an eighth-note grid");
+}
+
+#[test]
+fn midi_import_round_trips_generated_midi() {
+    let original = parse_file("../../sheetmusic/asa-branca.musicxml").unwrap();
+    let options = MidiOptions { include_metronome: false, ..MidiOptions::default() };
+    let smf = generate_midi_from_score(&original, &options);
+
+    let score: Score = parse_bytes(&smf, Some("mid")).unwrap();
+    assert_eq!(score.parts.len(), 1);
+    let part = &score.parts[0];
+
+    // Every note-on in the file comes back as one untied-in note
+    let note_ons = scorelib::midi::read_smf(&smf).unwrap().tracks.iter()
+        .flat_map(|t| &t.events)
+        .filter(|e| e.bytes.len() == 3 && e.bytes[0] & 0xF0 == 0x90 && e.bytes[2] > 0)
+        .count();
+    let attacks = part.measures.iter()
+        .flat_map(|m| &m.notes)
+        .filter(|n| !n.rest && !n.tie_stop)
+        .count();
+    assert_eq!(attacks, note_ons);
+
+    // Each measure adds up to a 4/4 bar (no time signature in the file)
+    for m in &part.measures {
+        let voice1: i32 = m.notes.iter().filter(|n| !n.chord && n.voice == Some(1)).map(|n| n.duration).sum();
+        assert_eq!(voice1, 16, "measure {}", m.number);
+    }
+    println!("✓ {} notes survived MIDI export and import", attacks);
+}
@@ -3,7 +3,7 @@
 
 use scorelib::{
     parse_file, unroll, generate_timemap, generate_midi_from_score,
-    MidiOptions, Energy,
+    MidiOptions, PartOptions, Energy,
 };
 
 /// Write bytes to a path, creating parent directories if needed.
@@ -273,6 +273,7 @@ fn midi_blue_bag_folly_valid_smf() {
         melody_channel: 0,
         energy: Energy::Medium,
         transpose: 0,
+        parts: Vec::new(),
     };
     let midi = generate_midi_from_score(&score, &options);
 
@@ -326,6 +327,7 @@ fn midi_chopin_with_inferred_accompaniment() {
         melody_channel: 0,
         energy: Energy::Medium,
         transpose: 0,
+        parts: Vec::new(),
     };
     let midi = generate_midi_from_score(&score, &options);
 
@@ -365,3 +367,458 @@ fn midi_tongnian_with_accompaniment() {
     write_test_output(output_path, &midi);
     println!("✓ 童年 MIDI: {} bytes, {} tracks → {}", midi.len(), track_count, output_path);
 }
+
+// ═══════════════════════════════════════════════════════════════════════
+// Dynamics tests
+// ═══════════════════════════════════════════════════════════════════════
+
+#[test]
+fn dynamics_chopin_drive_melody_velocity() {
+    use scorelib::dynamics::{DynamicsTimeline, DEFAULT_VELOCITY};
+
+    let score = parse_file("../../sheetmusic/chopin-trois-valses.mxl").unwrap();
+    let part = &score.parts[0];
+    let dyn_count = part.measures.iter()
+        .flat_map(|m| &m.directions)
+        .filter(|d| d.dynamics.is_some())
+        .count();
+    assert!(dyn_count > 0, "Chopin should have dynamics markings");
+
+    let unrolled = unroll(&score, 0);
+    let timemap = generate_timemap(&score, 0, &unrolled);
+    let timeline = DynamicsTimeline::build(part, &unrolled, &timemap, Some(1));
+
+    // The introduction has no marking yet, so it plays at the default level
+    assert_eq!(timeline.velocity_at(0.0), DEFAULT_VELOCITY);
+
+    // Sample the whole piece: the level must change along the way
+    let total_ms = scorelib::timemap::total_duration_ms(&timemap);
+    let mut levels: Vec<u8> = (0..200)
+        .map(|i| timeline.velocity_at(total_ms * i as f64 / 200.0))
+        .collect();
+    levels.sort();
+    levels.dedup();
+    assert!(levels.len() >= 3, "Expected several dynamic levels, got {:?}", levels);
+    println!("✓ chopin dynamics: {} marks, levels {:?}", dyn_count, levels);
+}
+
+#[test]
+fn hairpin_ramps_velocity_between_marks() {
+    use scorelib::dynamics::{dynamics_velocity, DynamicsTimeline};
+
+    // p + crescendo over measure 1, stop at the barline, f in measure 2
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list><score-part id="P1"><part-name>Violin</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions><time><beats>4</beats><beat-type>4</beat-type></time></attributes>
+      <direction placement="below"><direction-type><dynamics><p/></dynamics></direction-type></direction>
+      <direction placement="below"><direction-type><wedge type="crescendo" number="1"/></direction-type></direction>
+      <note><pitch><step>C</step><octave>5</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <note><pitch><step>D</step><octave>5</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <note><pitch><step>E</step><octave>5</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <note><pitch><step>F</step><octave>5</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <direction placement="below"><direction-type><wedge type="stop" number="1"/></direction-type></direction>
+    </measure>
+    <measure number="2">
+      <direction placement="below"><direction-type><dynamics><f/></dynamics></direction-type></direction>
+      <note><pitch><step>G</step><octave>5</octave></pitch><duration>4</duration><type>whole</type></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+    let score = scorelib::parse_musicxml(xml).unwrap();
+    let m1 = &score.parts[0].measures[0];
+    assert_eq!(m1.directions[1].wedge_type.as_deref(), Some("crescendo"));
+    assert_eq!(m1.directions[2].wedge_type.as_deref(), Some("stop"));
+    assert_eq!(m1.directions[2].onset, 4);
+
+    let unrolled = unroll(&score, 0);
+    let timemap = generate_timemap(&score, 0, &unrolled);
+    let timeline = DynamicsTimeline::build(&score.parts[0], &unrolled, &timemap, None);
+
+    let p = dynamics_velocity("p").unwrap();
+    let f = dynamics_velocity("f").unwrap();
+    let beat_ms = timemap[0].duration_ms / 4.0;
+    let velocities: Vec<u8> = (0..5).map(|b| timeline.velocity_at(b as f64 * beat_ms)).collect();
+
+    assert_eq!(velocities[0], p, "Hairpin starts at the level in force");
+    assert!(velocities.windows(2).all(|w| w[1] > w[0]), "Crescendo should rise: {:?}", velocities);
+    assert_eq!(velocities[4], f, "Hairpin ends at the following mark");
+    println!("✓ hairpin velocities: {:?}", velocities);
+}
+
+/// Decode the channel events of track `track` of an SMF file as
+/// (tick, status, data bytes).  Meta and sysex events are skipped.
+fn track_events(midi: &[u8], track: usize) -> Vec<(u32, u8, Vec<u8>)> {
+    let mut pos = 14;
+    for _ in 0..track {
+        let len = u32::from_be_bytes([midi[pos + 4], midi[pos + 5], midi[pos + 6], midi[pos + 7]]);
+        pos += 8 + len as usize;
+    }
+    let len = u32::from_be_bytes([midi[pos + 4], midi[pos + 5], midi[pos + 6], midi[pos + 7]]) as usize;
+    let data = &midi[pos + 8..pos + 8 + len];
+
+    let read_vlq = |i: &mut usize| {
+        let mut value = 0usize;
+        loop {
+            let b = data[*i];
+            *i += 1;
+            value = (value << 7) | (b & 0x7F) as usize;
+            if b & 0x80 == 0 { break value; }
+        }
+    };
+
+    let mut events = Vec::new();
+    let (mut i, mut tick, mut status) = (0, 0u32, 0u8);
+    while i < data.len() {
+        tick += read_vlq(&mut i) as u32;
+        if data[i] & 0x80 != 0 {
+            status = data[i];
+            i += 1;
+        }
+        let data_len = match status & 0xF0 {
+            0xC0 | 0xD0 => 1,
+            0x80 | 0x90 | 0xA0 | 0xB0 | 0xE0 => 2,
+            _ => {
+                // Meta (FF type len data) or sysex (F0 len data)
+                if status == 0xFF { i += 1; }
+                let l = read_vlq(&mut i);
+                i += l;
+                continue;
+            }
+        };
+        events.push((tick, status, data[i..i + data_len].to_vec()));
+        i += data_len;
+    }
+    events
+}
+
+/// Decode (on_tick, off_tick, key, velocity) for every note in track `track`
+/// of an SMF file.
+fn track_notes(midi: &[u8], track: usize) -> Vec<(u32, u32, u8, u8)> {
+    let mut notes = Vec::new();
+    let mut sounding: Vec<(u32, u8, u8)> = Vec::new();
+    for (tick, status, data) in track_events(midi, track) {
+        let (key, vel) = match status & 0xF0 {
+            0x80 | 0x90 => (data[0], data[1]),
+            _ => continue,
+        };
+        if status & 0xF0 == 0x90 && vel > 0 {
+            sounding.push((tick, key, vel));
+        } else if let Some(p) = sounding.iter().position(|s| s.1 == key) {
+            let (on, key, vel) = sounding.remove(p);
+            notes.push((on, tick, key, vel));
+        }
+    }
+    notes.sort();
+    notes
+}
+
+#[test]
+fn articulations_shape_melody_playback() {
+    // staccato, accent, trill, and a fermata on the last note of measure 1
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list><score-part id="P1"><part-name>Flute</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions><key><fifths>1</fifths></key><time><beats>4</beats><beat-type>4</beat-type></time></attributes>
+      <sound tempo="60"/>
+      <note><pitch><step>C</step><octave>5</octave></pitch><duration>1</duration><type>quarter</type>
+        <notations><articulations><staccato/></articulations></notations></note>
+      <note><pitch><step>D</step><octave>5</octave></pitch><duration>1</duration><type>quarter</type>
+        <notations><articulations><accent/></articulations></notations></note>
+      <note><pitch><step>E</step><octave>5</octave></pitch><duration>1</duration><type>quarter</type>
+        <notations><ornaments><trill-mark/></ornaments></notations></note>
+      <note><pitch><step>G</step><octave>5</octave></pitch><duration>1</duration><type>quarter</type>
+        <notations><fermata type="upright"/></notations></note>
+    </measure>
+    <measure number="2">
+      <note><pitch><step>A</step><octave>5</octave></pitch><duration>4</duration><type>whole</type></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+    let score = scorelib::parse_musicxml(xml).unwrap();
+    let m1 = &score.parts[0].measures[0];
+    assert_eq!(m1.notes[0].articulations, vec!["staccato"]);
+    assert_eq!(m1.notes[2].ornaments, vec!["trill-mark"]);
+    assert_eq!(m1.notes[3].fermata.as_deref(), Some("upright"));
+
+    // The fermata lengthens measure 1 beyond four beats at 60 bpm
+    let unrolled = unroll(&score, 0);
+    let timemap = generate_timemap(&score, 0, &unrolled);
+    assert!(timemap[0].duration_ms > 4000.0, "Fermata should stretch: {}", timemap[0].duration_ms);
+    assert!((timemap[1].timestamp_ms - timemap[0].duration_ms).abs() < 1e-6);
+
+    let options = MidiOptions { include_metronome: false, ..MidiOptions::default() };
+    let midi = generate_midi_from_score(&score, &options);
+    let notes = track_notes(&midi, 1);
+
+    let c = notes.iter().find(|n| n.2 == 72).unwrap();
+    let d = notes.iter().find(|n| n.2 == 74).unwrap();
+    let quarter = d.0 - c.0;
+    assert!(c.1 - c.0 <= quarter / 2 + 1, "Staccato should halve the note: {:?}", c);
+    let a = notes.iter().find(|n| n.2 == 81).unwrap();
+    assert!(d.3 > a.3, "Accent should raise velocity: {} vs {}", d.3, a.3);
+
+    // Trill on E alternates with F# (key of G) and ends on E
+    let trill: Vec<u8> = notes.iter()
+        .filter(|n| n.0 >= d.0 + quarter && n.0 < d.0 + 2 * quarter)
+        .map(|n| n.2)
+        .collect();
+    assert!(trill.len() >= 3, "Trill should expand: {:?}", trill);
+    assert!(trill.contains(&78), "Upper neighbour should be F#: {:?}", trill);
+    assert_eq!(trill.first(), Some(&76));
+    assert_eq!(trill.last(), Some(&76));
+
+    // The held G sounds longer than a quarter
+    let g = notes.iter().find(|n| n.2 == 79).unwrap();
+    assert!(g.1 - g.0 > quarter, "Fermata note should be held: {:?}", g);
+    println!("✓ articulations: {} notes, trill {:?}", notes.len(), trill);
+}
+
+#[test]
+fn ornament_accidentals_and_the_first_ornament_are_played() {
+    // Key of G: a trill to B♭, a trill + mordent, and a mordent to C♯
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list><score-part id="P1"><part-name>Flute</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions><key><fifths>1</fifths></key><time><beats>3</beats><beat-type>4</beat-type></time></attributes>
+      <sound tempo="60"/>
+      <note><pitch><step>A</step><octave>5</octave></pitch><duration>1</duration><type>quarter</type>
+        <notations><ornaments><trill-mark/><accidental-mark placement="above">flat</accidental-mark></ornaments></notations></note>
+      <note><pitch><step>C</step><octave>5</octave></pitch><duration>1</duration><type>quarter</type>
+        <notations><ornaments><trill-mark/><mordent/></ornaments></notations></note>
+      <note><pitch><step>D</step><octave>5</octave></pitch><duration>1</duration><type>quarter</type>
+        <notations><ornaments><mordent/><accidental-mark>sharp</accidental-mark></ornaments></notations></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+    let score = scorelib::parse_musicxml(xml).unwrap();
+    let m1 = &score.parts[0].measures[0];
+    assert_eq!(m1.notes[0].ornaments, vec!["trill-mark"]);
+    assert_eq!(m1.notes[0].ornament_upper_alter, Some(-1.0));
+    assert_eq!(m1.notes[1].ornaments, vec!["trill-mark", "mordent"]);
+    assert_eq!(m1.notes[2].ornament_lower_alter, Some(1.0));
+
+    // The marks survive a round trip through the writer
+    let reparsed = scorelib::parse_musicxml(&scorelib::write_musicxml(&score)).unwrap();
+    assert_eq!(reparsed.parts[0].measures[0].notes[0].ornament_upper_alter, Some(-1.0));
+    assert_eq!(reparsed.parts[0].measures[0].notes[2].ornament_lower_alter, Some(1.0));
+
+    let options = MidiOptions { include_metronome: false, ..MidiOptions::default() };
+    let notes = track_notes(&generate_midi_from_score(&score, &options), 1);
+    let beat = |b: u32| -> Vec<u8> {
+        let quarter = scorelib::midi::TICKS_PER_QUARTER as u32;
+        notes.iter().filter(|n| n.0 >= b * quarter && n.0 < (b + 1) * quarter).map(|n| n.2).collect()
+    };
+
+    let trill = beat(0);
+    assert!(trill.contains(&82) && !trill.contains(&83), "A trill with a flat goes to B♭: {trill:?}");
+    // Only the trill is played: C alternates with D, the mordent's B is not heard
+    let both = beat(1);
+    assert!(both.len() >= 3 && both.contains(&74) && !both.contains(&71), "{both:?}");
+    assert_eq!(beat(2), vec![74, 73, 74], "mordent to C♯");
+    println!("✓ ornaments: trill {trill:?}, trill + mordent {both:?}");
+}
+
+#[test]
+fn midi_writes_every_part_on_its_own_channel() {
+    // Violin (channel 1), cello asking for channel 2 (taken by the piano
+    // accompaniment) and a two-staff piano part
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list>
+    <score-part id="P1"><part-name>Violin</part-name>
+      <midi-instrument id="P1-I1"><midi-channel>1</midi-channel><midi-program>41</midi-program></midi-instrument></score-part>
+    <score-part id="P2"><part-name>Cello</part-name>
+      <midi-instrument id="P2-I1"><midi-channel>2</midi-channel><midi-program>43</midi-program></midi-instrument></score-part>
+    <score-part id="P3"><part-name>Piano</part-name></score-part>
+  </part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions><time><beats>4</beats><beat-type>4</beat-type></time></attributes>
+      <note><pitch><step>E</step><octave>5</octave></pitch><duration>4</duration><type>whole</type></note>
+    </measure>
+  </part>
+  <part id="P2">
+    <measure number="1">
+      <attributes><divisions>2</divisions><time><beats>4</beats><beat-type>4</beat-type></time></attributes>
+      <note><pitch><step>C</step><octave>3</octave></pitch><duration>4</duration><type>half</type></note>
+      <note><pitch><step>G</step><octave>2</octave></pitch><duration>4</duration><type>half</type></note>
+    </measure>
+  </part>
+  <part id="P3">
+    <measure number="1">
+      <attributes><divisions>1</divisions><staves>2</staves><time><beats>4</beats><beat-type>4</beat-type></time></attributes>
+      <note><pitch><step>G</step><octave>4</octave></pitch><duration>4</duration><type>whole</type><staff>1</staff></note>
+      <backup><duration>4</duration></backup>
+      <note><pitch><step>C</step><octave>3</octave></pitch><duration>4</duration><type>whole</type><staff>2</staff></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+    let score = scorelib::parse_musicxml(xml).unwrap();
+    let options = MidiOptions { include_metronome: false, include_piano: true, ..MidiOptions::default() };
+    let midi = generate_midi_from_score(&score, &options);
+
+    // tempo + violin + cello + 2 piano staves + piano accompaniment
+    let track_count = u16::from_be_bytes([midi[10], midi[11]]) as usize;
+    assert_eq!(track_count, 6);
+
+    let programs: Vec<(u8, u8)> = (1..=4)
+        .map(|t| {
+            let pc = track_events(&midi, t).into_iter().find(|e| e.1 & 0xF0 == 0xC0).unwrap();
+            (pc.1 & 0x0F, pc.2[0])
+        })
+        .collect();
+    let channels: Vec<u8> = programs.iter().map(|p| p.0).collect();
+    assert_eq!(programs[0], (0, 40), "Violin keeps its channel; programs are 1-based in MusicXML");
+    assert_eq!(programs[1].1, 42);
+    assert!(channels.iter().all(|c| ![1, 2, 3, 9].contains(c)), "Channels {:?} collide", channels);
+    let mut distinct = channels.clone();
+    distinct.dedup();
+    assert_eq!(distinct.len(), channels.len(), "Channels {:?} should be distinct", channels);
+
+    // The cello counts in halves of a quarter: its G2 starts on beat 3
+    let cello = track_notes(&midi, 2);
+    assert_eq!(cello[1].0, 2 * 480);
+
+    // Leave out the cello, mute the piano
+    let mix = MidiOptions {
+        include_metronome: false,
+        parts: vec![
+            PartOptions::default(),
+            PartOptions { include: false, ..PartOptions::default() },
+            PartOptions { mute: true, ..PartOptions::default() },
+        ],
+        ..MidiOptions::default()
+    };
+    let midi = generate_midi_from_score(&score, &mix);
+    assert_eq!(u16::from_be_bytes([midi[10], midi[11]]), 4);
+    assert!(!track_notes(&midi, 1).is_empty());
+    assert!(track_notes(&midi, 2).is_empty(), "Muted part should play no notes");
+    let volume = track_events(&midi, 2).into_iter()
+        .find(|e| e.1 & 0xF0 == 0xB0 && e.2[0] == 7)
+        .unwrap();
+    assert_eq!(volume.2[1], 100, "Muting leaves the channel volume alone");
+    println!("✓ multi-part MIDI: channels {:?}", channels);
+}
+
+/// A score of `parts` one-measure parts, each with its program and
+/// channel (both 1-based, as in MusicXML).
+fn parts_score(parts: &[(u8, u8)]) -> scorelib::Score {
+    let mut list = String::new();
+    let mut body = String::new();
+    for (i, (program, channel)) in parts.iter().enumerate() {
+        list.push_str(&format!(
+            r#"<score-part id="P{i}"><part-name>Part {i}</part-name><midi-instrument id="P{i}-I1"><midi-channel>{channel}</midi-channel><midi-program>{program}</midi-program></midi-instrument></score-part>"#
+        ));
+        body.push_str(&format!(
+            r#"<part id="P{i}"><measure number="1"><attributes><divisions>1</divisions><time><beats>4</beats><beat-type>4</beat-type></time></attributes><note><pitch><step>C</step><octave>4</octave></pitch><duration>4</duration><type>whole</type></note></measure></part>"#
+        ));
+    }
+    let xml = format!(r#"<?xml version="1.0" encoding="UTF-8"?><score-partwise version="3.1"><part-list>{list}</part-list>{body}</score-partwise>"#);
+    scorelib::parse_musicxml(&xml).unwrap()
+}
+
+#[test]
+fn percussion_parts_leave_the_drum_channel_volume_alone() {
+    let score = parts_score(&[(1, 1), (1, 10)]);
+    let options = MidiOptions {
+        include_drums: true,
+        parts: vec![PartOptions::default(), PartOptions { volume: 50, ..PartOptions::default() }],
+        ..MidiOptions::default()
+    };
+    let midi = generate_midi_from_score(&score, &options);
+    let events = track_events(&midi, 2);
+    assert!(events.iter().all(|e| e.1 & 0x0F == 9), "the percussion part plays on channel 10");
+    assert!(!events.iter().any(|e| e.1 & 0xF0 == 0xB0), "no controllers on the drum channel");
+    let (plain, quiet) = (track_notes(&midi, 1), track_notes(&midi, 2));
+    assert_eq!(quiet[0].3 as u32, plain[0].3 as u32 / 2, "volume 50 halves the velocity");
+
+    // Muting it leaves the metronome and drums sounding
+    let options = MidiOptions {
+        include_drums: true,
+        parts: vec![PartOptions::default(), PartOptions { mute: true, ..PartOptions::default() }],
+        ..MidiOptions::default()
+    };
+    let midi = generate_midi_from_score(&score, &options);
+    assert!(track_notes(&midi, 2).is_empty());
+    assert!(!track_events(&midi, 2).iter().any(|e| e.1 & 0xF0 == 0xB0));
+    assert!(!track_notes(&midi, 3).is_empty(), "the metronome still plays");
+    println!("✓ percussion part on channel 10 keeps the drums' volume");
+}
+
+#[test]
+fn parts_beyond_the_free_channels_merge_into_a_track() {
+    // Twelve channels are free for parts: the thirteenth and fourteenth
+    // part join the tracks playing their instruments
+    let mut parts: Vec<(u8, u8)> = (0..12).map(|i| (i + 1, 1)).collect();
+    parts.push((5, 1));
+    parts.push((99, 1));
+    let score = parts_score(&parts);
+    let options = MidiOptions { include_metronome: false, ..MidiOptions::default() };
+    let midi = generate_midi_from_score(&score, &options);
+    assert_eq!(u16::from_be_bytes([midi[10], midi[11]]), 13, "tempo + 12 part tracks");
+
+    let mut channels = Vec::new();
+    for track in 1..=12 {
+        let events = track_events(&midi, track);
+        let programs: Vec<&(u32, u8, Vec<u8>)> = events.iter().filter(|e| e.1 & 0xF0 == 0xC0).collect();
+        assert_eq!(programs.len(), 1, "track {track} sets one program");
+        channels.push(programs[0].1 & 0x0F);
+    }
+    let mut distinct = channels.clone();
+    distinct.sort();
+    distinct.dedup();
+    assert_eq!(distinct.len(), 12, "channels {channels:?} are not shared");
+    assert_eq!(track_notes(&midi, 5).len(), 2, "the extra program-5 part plays with part 4");
+    assert_eq!(track_notes(&midi, 12).len(), 2, "the last part joins the latest track");
+    println!("✓ 14 parts fit 12 channels without sharing: {channels:?}");
+}
+
+#[test]
+fn parts_shorter_than_the_first_play_until_they_end() {
+    // The second part has one measure to the melody's three, with the
+    // chord symbols and a dynamic
+    let measure = |n: usize, step: &str| format!(
+        r#"<measure number="{n}"><note><pitch><step>{step}</step><octave>4</octave></pitch><duration>4</duration><type>whole</type></note></measure>"#
+    );
+    let xml = format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list>
+    <score-part id="P1"><part-name>Melody</part-name></score-part>
+    <score-part id="P2"><part-name>Bass</part-name></score-part>
+  </part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions><time><beats>4</beats><beat-type>4</beat-type></time></attributes>
+      <note><pitch><step>C</step><octave>5</octave></pitch><duration>4</duration><type>whole</type></note>
+    </measure>
+    {}
+    {}
+  </part>
+  <part id="P2">
+    <measure number="1">
+      <attributes><divisions>1</divisions><time><beats>4</beats><beat-type>4</beat-type></time></attributes>
+      <harmony><root><root-step>C</root-step></root><kind>major</kind></harmony>
+      <direction><direction-type><dynamics><f/></dynamics></direction-type></direction>
+      <note><pitch><step>C</step><octave>3</octave></pitch><duration>4</duration><type>whole</type></note>
+    </measure>
+  </part>
+</score-partwise>"#, measure(2, "D"), measure(3, "E"));
+
+    let score = scorelib::parse_musicxml(&xml).unwrap();
+    let options = MidiOptions { include_metronome: false, include_bass: true, ..MidiOptions::default() };
+    let midi = generate_midi_from_score(&score, &options);
+    assert_eq!(track_notes(&midi, 1).len(), 3);
+    assert_eq!(track_notes(&midi, 2).len(), 1);
+    println!("✓ unequal parts: the short part stops after its last measure");
+}
@@ -0,0 +1,155 @@
+//! Paged SVG tests — page size and margins, breaks, justification and the
+//! paged playback map.
+
+use std::path::PathBuf;
+
+use scorelib::playback::PlaybackMap;
+use scorelib::{
+    generate_paged_playback_map, parse_file, render_score_to_svg_pages, PageOptions, RenderOptions, Score, Theme,
+};
+
+fn sheetmusic_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../sheetmusic")
+}
+
+fn output_dir() -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_output").join("pages");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+fn chopin() -> Score {
+    parse_file(sheetmusic_dir().join("chopin-trois-valses.mxl")).unwrap()
+}
+
+/// Measure index of the first measure of each system.
+fn system_starts(map: &PlaybackMap) -> Vec<usize> {
+    (0..map.systems.len())
+        .map(|s| map.measures.iter().find(|m| m.system_idx == s).unwrap().measure_idx)
+        .collect()
+}
+
+#[test]
+fn pages_follow_the_score_defaults() {
+    let score = chopin();
+    let pages = render_score_to_svg_pages(&score, &PageOptions::default(), &RenderOptions::default());
+    for (i, page) in pages.iter().enumerate() {
+        std::fs::write(output_dir().join(format!("chopin-{}.svg", i + 1)), page).unwrap();
+        assert!(page.contains(r#"viewBox="0 0 1556 2199.48""#), "page {} size", i + 1);
+        assert!(page.contains(&format!(">{}</text>", i + 1)), "page {} is not numbered", i + 1);
+    }
+    assert!(pages.len() > 10, "expected many pages, got {}", pages.len());
+
+    // The title is only on page 1
+    assert!(pages[0].contains(">Trois Valses</text>"));
+    assert!(pages[1..].iter().all(|p| !p.contains(">Trois Valses</text>")));
+}
+
+#[test]
+fn breaks_are_honored() {
+    let score = chopin();
+    let measures = &score.parts[0].measures;
+    let map = generate_paged_playback_map(&score, &PageOptions::default(), &RenderOptions::default());
+
+    // Every encoded break starts a system, and page breaks start a page
+    let starts = system_starts(&map);
+    for (mi, measure) in measures.iter().enumerate().skip(1) {
+        if measure.new_system || measure.new_page {
+            assert!(starts.contains(&mi), "measure {} should start a system", mi + 1);
+        }
+        if measure.new_page {
+            let s = starts.iter().position(|&start| start == mi).unwrap();
+            assert_eq!(map.systems[s].page, map.systems[s - 1].page + 1, "measure {} should start a page", mi + 1);
+        }
+    }
+
+    // Without breaks the systems are filled as on screen
+    let unbroken = PageOptions { breaks: false, ..PageOptions::default() };
+    let unbroken = generate_paged_playback_map(&score, &unbroken, &RenderOptions::default());
+    assert_ne!(system_starts(&unbroken), starts);
+}
+
+#[test]
+fn systems_stay_inside_the_margins() {
+    let score = chopin();
+    let options = PageOptions::default();
+    let map = generate_paged_playback_map(&score, &options, &RenderOptions::default());
+    let (top, bottom, left) = (74.0741, 2199.48 - 148.148, 74.0741);
+
+    assert_eq!(map.systems[0].page, 0);
+    for pair in map.systems.windows(2) {
+        let (a, b) = (&pair[0], &pair[1]);
+        assert!(b.page == a.page && b.y > a.y + a.height || b.page == a.page + 1);
+    }
+    for system in &map.systems {
+        assert!(system.y > top && system.y + system.height < bottom, "system at {} off the page", system.y);
+    }
+    for measure in &map.measures {
+        assert!(measure.x >= left - 1e-6 && measure.x + measure.width <= 1556.0 - left + 1e-6);
+        assert!(measure.note_positions.iter().all(|&(_, x)| x >= measure.x && x <= measure.x + measure.width + 1e-6));
+    }
+
+    // Full pages are justified: their last system reaches down near the
+    // bottom margin
+    let full = map.systems.iter().rfind(|s| s.page == 1).unwrap();
+    assert!(full.y + full.height > bottom - 250.0, "page 2 ends at {}", full.y + full.height);
+}
+
+#[test]
+fn options_override_the_page() {
+    let score = chopin();
+    let default_pages = render_score_to_svg_pages(&score, &PageOptions::default(), &RenderOptions::default()).len();
+    let small = PageOptions {
+        page_width: Some(1200.0),
+        page_height: Some(1200.0),
+        left_margin: Some(100.0),
+        right_margin: Some(100.0),
+        top_margin: Some(60.0),
+        bottom_margin: Some(60.0),
+        breaks: true,
+    };
+    let pages = render_score_to_svg_pages(&score, &small, &RenderOptions::default());
+    assert!(pages.iter().all(|p| p.contains(r#"viewBox="0 0 1200 1200""#)));
+    assert!(pages.len() > default_pages);
+
+    let map = generate_paged_playback_map(&score, &small, &RenderOptions::default());
+    assert!(map.measures.iter().all(|m| m.x >= 100.0 - 1e-6 && m.x + m.width <= 1100.0 + 1e-6));
+    assert_eq!(map.systems.last().unwrap().page + 1, pages.len());
+}
+
+#[test]
+fn render_options_apply_to_pages() {
+    let score = chopin();
+    let page = PageOptions::default();
+    let plain = render_score_to_svg_pages(&score, &page, &RenderOptions::default());
+    let options = RenderOptions { theme: Theme::Dark, staff_scale: 1.5, ..RenderOptions::default() };
+    let pages = render_score_to_svg_pages(&score, &page, &options);
+    std::fs::write(output_dir().join("chopin-dark-1.svg"), &pages[0]).unwrap();
+
+    // The paper is dark, the page keeps its size and the larger staves
+    // take more pages
+    assert!(pages.iter().all(|p| p.contains(r##"fill="#1c1c1e""##) && p.contains(r#"viewBox="0 0 1556 2199.48""#)));
+    assert!(pages[0].contains(" scale(1.5)"));
+    assert!(pages.len() > plain.len(), "{} pages, {} unscaled", pages.len(), plain.len());
+
+    // The playback map follows the enlarged systems
+    let map = generate_paged_playback_map(&score, &page, &options);
+    let unscaled = generate_paged_playback_map(&score, &page, &RenderOptions::default());
+    assert_eq!(map.systems.last().unwrap().page + 1, pages.len());
+    assert!((map.systems[0].height - 1.5 * unscaled.systems[0].height).abs() < 1e-6);
+    let (left, right) = (74.0741, 1556.0 - 74.0741);
+    assert!(map.measures.iter().all(|m| m.x >= left - 1e-6 && m.x + m.width <= right + 1e-6));
+}
+
+#[test]
+fn scores_without_defaults_use_a4() {
+    let mut score = parse_file(sheetmusic_dir().join("asa-branca.musicxml")).unwrap();
+    score.defaults = None;
+    let pages = render_score_to_svg_pages(&score, &PageOptions::default(), &RenderOptions::default());
+    // A4 at the default 7 mm staff height: 40 tenths per 7 mm
+    let width = 210.0 * 40.0 / 7.0;
+    assert!(pages[0].contains(&format!(r#"viewBox="0 0 {width} "#)), "{}", &pages[0][..200]);
+
+    let empty = render_score_to_svg_pages(&Score::new(), &PageOptions::default(), &RenderOptions::default());
+    assert_eq!(empty.len(), 1);
+}
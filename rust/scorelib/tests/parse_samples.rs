@@ -339,6 +339,146 @@ fn parse_blue_bag_folly_musicxml() {
     }
 }
 
+// ─── Onsets from <backup>/<forward> ─────────────────────────────────
+
+#[test]
+fn onsets_follow_backup_and_forward() {
+    // Two voices on one staff: voice 1 is a half note + quarter chord +
+    // quarter, voice 2 backs up to the start, skips a quarter with <forward>
+    // and then plays a dotted half. Both voices deliberately reuse voice="1"
+    // so the onset must come from the cursor, not from voice bookkeeping.
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list><score-part id="P1"><part-name>Piano</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>2</divisions><time><beats>4</beats><beat-type>4</beat-type></time></attributes>
+      <harmony><root><root-step>C</root-step></root><kind>major</kind></harmony>
+      <note><pitch><step>E</step><octave>5</octave></pitch><duration>4</duration><voice>1</voice><type>half</type></note>
+      <note><pitch><step>D</step><octave>5</octave></pitch><duration>2</duration><voice>1</voice><type>quarter</type></note>
+      <note><chord/><pitch><step>F</step><octave>5</octave></pitch><duration>2</duration><voice>1</voice><type>quarter</type></note>
+      <harmony><root><root-step>G</root-step></root><kind>dominant</kind><offset>-2</offset></harmony>
+      <note><pitch><step>C</step><octave>5</octave></pitch><duration>2</duration><voice>1</voice><type>quarter</type></note>
+      <backup><duration>8</duration></backup>
+      <forward><duration>2</duration></forward>
+      <direction><direction-type><words>dolce</words></direction-type></direction>
+      <note><pitch><step>C</step><octave>4</octave></pitch><duration>6</duration><voice>1</voice><type>half</type><dot/></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+    let score = scorelib::parse_musicxml(xml).expect("Failed to parse inline MusicXML");
+    let measure = &score.parts[0].measures[0];
+
+    let onsets: Vec<i32> = measure.notes.iter().map(|n| n.onset).collect();
+    assert_eq!(onsets, vec![0, 4, 4, 6, 2]);
+
+    let harmony_onsets: Vec<i32> = measure.harmonies.iter().map(|h| h.onset).collect();
+    assert_eq!(harmony_onsets, vec![0, 4]);
+
+    assert_eq!(measure.directions.len(), 1);
+    assert_eq!(measure.directions[0].onset, 2);
+
+    assert_eq!(measure.content_divisions(), 8);
+    println!("✓ Onsets resolved from <backup>/<forward>: {:?}", onsets);
+}
+
+// ─── Tuplets ────────────────────────────────────────────────────────
+
+/// One 4/4 measure opening with eighth-note triplets whose durations were
+/// rounded by the exporter (divisions = 4 can't express 4/3).
+const TRIPLET_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list><score-part id="P1"><part-name>Flute</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>4</divisions><time><beats>4</beats><beat-type>4</beat-type></time></attributes>
+      <note><pitch><step>C</step><octave>5</octave></pitch><duration>1</duration><voice>1</voice><type>eighth</type>
+        <time-modification><actual-notes>3</actual-notes><normal-notes>2</normal-notes></time-modification>
+        <beam number="1">begin</beam><notations><tuplet type="start" bracket="no"/></notations></note>
+      <note><pitch><step>D</step><octave>5</octave></pitch><duration>1</duration><voice>1</voice><type>eighth</type>
+        <time-modification><actual-notes>3</actual-notes><normal-notes>2</normal-notes></time-modification>
+        <beam number="1">continue</beam></note>
+      <note><pitch><step>E</step><octave>5</octave></pitch><duration>2</duration><voice>1</voice><type>eighth</type>
+        <time-modification><actual-notes>3</actual-notes><normal-notes>2</normal-notes></time-modification>
+        <beam number="1">end</beam><notations><tuplet type="stop"/></notations></note>
+      <note><pitch><step>F</step><octave>5</octave></pitch><duration>4</duration><voice>1</voice><type>quarter</type></note>
+      <note><pitch><step>G</step><octave>5</octave></pitch><duration>8</duration><voice>1</voice><type>half</type></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+#[test]
+fn tuplets_parse_and_time_exactly() {
+    let score = scorelib::parse_musicxml(TRIPLET_XML).expect("Failed to parse inline MusicXML");
+    let measure = &score.parts[0].measures[0];
+
+    let tm = measure.notes[0].time_modification.as_ref().expect("time-modification");
+    assert_eq!((tm.actual_notes, tm.normal_notes), (3, 2));
+    assert_eq!(measure.notes[0].tuplets.len(), 1);
+    assert_eq!(measure.notes[0].tuplets[0].tuplet_type, "start");
+    assert_eq!(measure.notes[0].tuplets[0].bracket, Some(false));
+    assert_eq!(measure.notes[2].tuplets[0].tuplet_type, "stop");
+    assert!(measure.notes[3].time_modification.is_none());
+
+    // Rounded durations (1, 1, 2) are replaced by exact thirds of a quarter
+    let times = measure.note_times_quarters(4);
+    let expected = [(0.0, 1.0 / 3.0), (1.0 / 3.0, 1.0 / 3.0), (2.0 / 3.0, 1.0 / 3.0), (1.0, 1.0), (2.0, 2.0)];
+    for (got, want) in times.iter().zip(expected.iter()) {
+        assert!((got.0 - want.0).abs() < 1e-9 && (got.1 - want.1).abs() < 1e-9,
+            "got {:?}, want {:?}", got, want);
+    }
+    assert!((measure.content_quarters(4) - 4.0).abs() < 1e-9);
+    println!("✓ Triplet timing: {:?}", times);
+}
+
+/// Tuplet notes whose `<normal-type>` differs from their own type: a
+/// quarter and an eighth in an eighth-note triplet, then two sixteenths
+/// and two eighths in another, with exporter-rounded durations at
+/// divisions = 4.
+const NORMAL_TYPE_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list><score-part id="P1"><part-name>Flute</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>4</divisions><time><beats>2</beats><beat-type>4</beat-type></time></attributes>
+      <note><pitch><step>C</step><octave>5</octave></pitch><duration>3</duration><voice>1</voice><type>quarter</type>
+        <time-modification><actual-notes>3</actual-notes><normal-notes>2</normal-notes><normal-type>eighth</normal-type></time-modification></note>
+      <note><pitch><step>D</step><octave>5</octave></pitch><duration>1</duration><voice>1</voice><type>eighth</type>
+        <time-modification><actual-notes>3</actual-notes><normal-notes>2</normal-notes></time-modification></note>
+      <note><pitch><step>E</step><octave>5</octave></pitch><duration>1</duration><voice>1</voice><type>16th</type>
+        <time-modification><actual-notes>3</actual-notes><normal-notes>2</normal-notes><normal-type>eighth</normal-type></time-modification></note>
+      <note><pitch><step>F</step><octave>5</octave></pitch><duration>1</duration><voice>1</voice><type>16th</type>
+        <time-modification><actual-notes>3</actual-notes><normal-notes>2</normal-notes><normal-type>eighth</normal-type></time-modification></note>
+      <note><pitch><step>G</step><octave>5</octave></pitch><duration>1</duration><voice>1</voice><type>eighth</type>
+        <time-modification><actual-notes>3</actual-notes><normal-notes>2</normal-notes></time-modification></note>
+      <note><pitch><step>A</step><octave>5</octave></pitch><duration>1</duration><voice>1</voice><type>eighth</type>
+        <time-modification><actual-notes>3</actual-notes><normal-notes>2</normal-notes></time-modification></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+#[test]
+fn tuplet_normal_type_times_exactly() {
+    let score = scorelib::parse_musicxml(NORMAL_TYPE_XML).expect("Failed to parse inline MusicXML");
+    let measure = &score.parts[0].measures[0];
+    let tm = measure.notes[2].time_modification.as_ref().expect("time-modification");
+    assert_eq!(tm.normal_type.as_deref(), Some("eighth"));
+
+    let third = 1.0 / 3.0;
+    let sixth = 1.0 / 6.0;
+    let times = measure.note_times_quarters(4);
+    let expected = [(0.0, 2.0 * third), (2.0 * third, third), (1.0, sixth), (1.0 + sixth, sixth),
+        (1.0 + third, third), (1.0 + 2.0 * third, third)];
+    assert_eq!(times.len(), expected.len());
+    for (got, want) in times.iter().zip(expected.iter()) {
+        assert!((got.0 - want.0).abs() < 1e-9 && (got.1 - want.1).abs() < 1e-9,
+            "got {:?}, want {:?}", got, want);
+    }
+    assert!((measure.content_quarters(4) - 2.0).abs() < 1e-9);
+    println!("✓ Normal-type tuplet timing: {:?}", times);
+}
+
 // ─── JSON serialization ─────────────────────────────────────────────
 
 #[test]
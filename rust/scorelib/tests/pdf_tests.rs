@@ -0,0 +1,183 @@
+//! PDF export tests — page count, page size, structure and content.
+
+use std::io::Read;
+use std::path::PathBuf;
+
+use scorelib::{parse_file, render_score_to_pdf, Defaults, NoteNames, PageSize, RenderOptions, Score};
+
+fn sheetmusic_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../sheetmusic")
+}
+
+fn output_dir() -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_output");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+/// Number of times `needle` occurs in `pdf`.
+fn count(pdf: &[u8], needle: &str) -> usize {
+    pdf.windows(needle.len()).filter(|w| *w == needle.as_bytes()).count()
+}
+
+/// Every decompressed stream, in order.
+fn streams(pdf: &[u8]) -> Vec<Vec<u8>> {
+    let mut streams = Vec::new();
+    let mut rest = pdf;
+    while let Some(start) = rest.windows(10).position(|w| w == b">>\nstream\n") {
+        let data = &rest[start + 10..];
+        let end = data.windows(10).position(|w| w == b"\nendstream").unwrap();
+        let mut stream = Vec::new();
+        flate2::read::ZlibDecoder::new(&data[..end]).read_to_end(&mut stream).unwrap();
+        streams.push(stream);
+        rest = &data[end..];
+    }
+    streams
+}
+
+/// Decompressed content streams, one per page, in order.
+fn page_contents(pdf: &[u8]) -> Vec<String> {
+    streams(pdf)
+        .into_iter()
+        .filter(|s| s.starts_with(b"1 J 1 j\n"))
+        .map(|s| String::from_utf8(s).unwrap())
+        .collect()
+}
+
+#[test]
+fn long_score_fills_several_a4_pages() {
+    let score = parse_file(sheetmusic_dir().join("chopin-trois-valses.mxl")).unwrap();
+    let pdf = render_score_to_pdf(&score, PageSize::A4, &RenderOptions::default());
+    std::fs::write(output_dir().join("chopin-trois-valses.pdf"), &pdf).unwrap();
+
+    assert!(pdf.starts_with(b"%PDF-1.4"));
+    assert!(pdf.ends_with(b"%%EOF\n"));
+    let pages = count(&pdf, "/Type /Page ");
+    assert!(pages > 2, "expected several pages, got {pages}");
+    assert_eq!(count(&pdf, &format!("/Count {pages} ")), 1);
+    assert_eq!(count(&pdf, "/MediaBox [0 0 595.276 841.89]"), 1);
+
+    // Vector music on every page, with its number at the bottom
+    let contents = page_contents(&pdf);
+    assert_eq!(contents.len(), pages);
+    for (i, content) in contents.iter().enumerate() {
+        assert!(content.contains(" c "), "page {} has no curves", i + 1);
+        assert!(content.contains(" re "), "page {} has no rectangles", i + 1);
+        assert!(content.contains(&format!("({}) Tj", i + 1)), "page {} is not numbered", i + 1);
+    }
+
+    // The title is only on page 1
+    let title = score.title.as_deref().unwrap();
+    let title = title.split_whitespace().next().unwrap();
+    assert!(contents[0].contains(title));
+    assert!(contents[1..].iter().all(|c| !c.contains(title)));
+}
+
+#[test]
+fn letter_pages_and_header() {
+    let score = parse_file(sheetmusic_dir().join("asa-branca.musicxml")).unwrap();
+    let pdf = render_score_to_pdf(&score, PageSize::Letter, &RenderOptions::default());
+    std::fs::write(output_dir().join("asa-branca.pdf"), &pdf).unwrap();
+
+    assert_eq!(count(&pdf, "/MediaBox [0 0 612 792]"), 1);
+    assert!(count(&pdf, "/Type /Page ") >= 1);
+    let contents = page_contents(&pdf);
+    assert!(contents[0].contains("(Asa branca) Tj"));
+    assert!(contents[0].contains("(Luiz Gonzaga Arr. Karim Ratib) Tj"));
+    assert!(count(&pdf, "/BaseFont /Times-Roman") == 1);
+
+    // Every cross-reference entry points at its object
+    let text = String::from_utf8_lossy(&pdf);
+    let xref = text.rfind("\nxref\n").unwrap() + 1;
+    for (i, line) in text[xref..].lines().skip(3).take_while(|l| l.ends_with(" n ")).enumerate() {
+        let offset: usize = line[..10].parse().unwrap();
+        assert!(pdf[offset..].starts_with(format!("{} 0 obj", i + 1).as_bytes()));
+    }
+}
+
+#[test]
+fn chinese_text_is_embedded_not_replaced() {
+    let score = parse_file(sheetmusic_dir().join("童年.mxl")).unwrap();
+    let pdf = render_score_to_pdf(&score, PageSize::A4, &RenderOptions::default());
+    std::fs::write(output_dir().join("童年.pdf"), &pdf).unwrap();
+
+    let contents = page_contents(&pdf);
+    assert!(contents.iter().all(|c| !c.contains('?')), "text was replaced");
+    assert_eq!(count(&pdf, "/Subtype /Type0"), count(&pdf, "/Subtype /CIDFontType2"));
+    assert!(count(&pdf, "/Encoding /Identity-H") >= 1);
+    assert!(count(&pdf, "/FontFile2 ") >= 1);
+
+    // The title and lyrics map back to their characters for search and copy
+    let maps: Vec<String> = streams(&pdf)
+        .into_iter()
+        .filter(|s| s.starts_with(b"/CIDInit"))
+        .map(|s| String::from_utf8(s).unwrap())
+        .collect();
+    assert!(!maps.is_empty());
+    for unit in ["<7AE5>", "<5E74>"] {
+        assert!(maps.iter().any(|m| m.contains(unit)), "{unit} is not mapped");
+    }
+    assert!(contents[0].contains("/U1 "), "the title is not set in an embedded font");
+}
+
+#[test]
+fn accidental_signs_use_the_bundled_font() {
+    let score = parse_file(sheetmusic_dir().join("chopin-trois-valses.mxl")).unwrap();
+    let options = RenderOptions { note_names: NoteNames::Letters, ..RenderOptions::default() };
+    let pdf = render_score_to_pdf(&score, PageSize::A4, &options);
+
+    // Note names like A♭ set the sign from the embedded DejaVu Serif
+    assert_eq!(count(&pdf, "/BaseFont /SCOREA+DejaVuSerif "), 2);
+    let maps: Vec<Vec<u8>> = streams(&pdf).into_iter().filter(|s| s.starts_with(b"/CIDInit")).collect();
+    assert!(maps.iter().any(|m| count(m, "<266D>") == 1));
+    assert!(page_contents(&pdf).iter().all(|c| !c.contains("(b)") && !c.contains('?')));
+}
+
+#[test]
+fn margins_and_staff_size_follow_defaults() {
+    let mut score = parse_file(sheetmusic_dir().join("asa-branca.musicxml")).unwrap();
+    let pages_with = |score: &Score| count(&render_score_to_pdf(score, PageSize::A4, &RenderOptions::default()), "/Type /Page ");
+    let normal = pages_with(&score);
+
+    // Twice the staff size needs more pages
+    score.defaults = Some(Defaults {
+        millimeters: Some(14.0),
+        tenths: Some(40.0),
+        page_height: None,
+        page_width: None,
+        left_margin: Some(40.0),
+        right_margin: Some(40.0),
+        top_margin: Some(40.0),
+        bottom_margin: Some(40.0),
+    });
+    assert!(pages_with(&score) > normal);
+
+    // The first system starts at the left margin: 40 tenths at 0.35 mm each
+    let pdf = render_score_to_pdf(&score, PageSize::A4, &RenderOptions::default());
+    let contents = page_contents(&pdf);
+    let cm = contents[0].lines().find(|l| l.ends_with(" cm") && l.starts_with("q ")).unwrap();
+    let fields: Vec<f64> = cm[2..cm.len() - 3].split(' ').map(|v| v.parse().unwrap()).collect();
+    let scale = 14.0 / 40.0 * 72.0 / 25.4;
+    assert!((fields[0] - scale).abs() < 1e-3);
+    assert!((fields[4] + 50.0 * scale - 40.0 * scale).abs() < 1e-2, "x origin {}", fields[4]);
+}
+
+#[test]
+fn empty_score_is_one_blank_page() {
+    let score = Score {
+        title: None,
+        title_style: None,
+        subtitle: None,
+        subtitle_style: None,
+        composer: None,
+        composer_style: None,
+        arranger: None,
+        version: None,
+        software: None,
+        defaults: None,
+        parts: Vec::new(),
+    };
+    let pdf = render_score_to_pdf(&score, PageSize::default(), &RenderOptions::default());
+    assert_eq!(count(&pdf, "/Type /Page "), 1);
+    assert_eq!(count(&pdf, "/MediaBox [0 0 595.276 841.89]"), 1);
+}
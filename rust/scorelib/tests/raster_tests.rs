@@ -0,0 +1,161 @@
+//! PNG export tests — image structure, size, resolution and regions.
+
+use std::io::Read;
+use std::path::PathBuf;
+
+use scorelib::{parse_file, render_score_to_png, PageSize, RasterOptions, RasterRegion, RenderOptions, Score};
+
+fn sheetmusic_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../sheetmusic")
+}
+
+fn output_dir() -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_output");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+/// A decoded 8-bit RGB PNG with the resolution it records.
+struct Image {
+    width: usize,
+    height: usize,
+    pixels_per_meter: u32,
+    rgb: Vec<u8>,
+}
+
+impl Image {
+    fn decode(png: &[u8]) -> Image {
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        let (mut width, mut height, mut pixels_per_meter) = (0, 0, 0);
+        let mut data = Vec::new();
+        let mut pos = 8;
+        while pos < png.len() {
+            let len = u32::from_be_bytes(png[pos..pos + 4].try_into().unwrap()) as usize;
+            let kind = &png[pos + 4..pos + 8];
+            let body = &png[pos + 8..pos + 8 + len];
+            let crc = u32::from_be_bytes(png[pos + 8 + len..pos + 12 + len].try_into().unwrap());
+            let mut check = flate2::Crc::new();
+            check.update(&png[pos + 4..pos + 8 + len]);
+            assert_eq!(check.sum(), crc, "bad CRC on {}", String::from_utf8_lossy(kind));
+            match kind {
+                b"IHDR" => {
+                    width = u32::from_be_bytes(body[0..4].try_into().unwrap()) as usize;
+                    height = u32::from_be_bytes(body[4..8].try_into().unwrap()) as usize;
+                    assert_eq!(&body[8..10], &[8, 2], "expected 8-bit RGB");
+                }
+                b"pHYs" => pixels_per_meter = u32::from_be_bytes(body[0..4].try_into().unwrap()),
+                b"IDAT" => data.extend_from_slice(body),
+                _ => {}
+            }
+            pos += 12 + len;
+        }
+        let mut raw = Vec::new();
+        flate2::read::ZlibDecoder::new(&data[..]).read_to_end(&mut raw).unwrap();
+        assert_eq!(raw.len(), (width * 3 + 1) * height);
+        let rgb = raw.chunks(width * 3 + 1).flat_map(|row| row[1..].to_vec()).collect();
+        Image { width, height, pixels_per_meter, rgb }
+    }
+
+    /// Number of dark pixels in a rectangle.
+    fn ink(&self, x0: usize, y0: usize, x1: usize, y1: usize) -> usize {
+        (y0..y1.min(self.height))
+            .flat_map(|y| (x0..x1.min(self.width)).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.rgb[(y * self.width + x) * 3] < 128)
+            .count()
+    }
+}
+
+fn options(dpi: f64, region: RasterRegion) -> RasterOptions {
+    RasterOptions { dpi, region, page_size: PageSize::A4 }
+}
+
+#[test]
+fn first_system_thumbnail() {
+    let score = parse_file(sheetmusic_dir().join("chopin-trois-valses.mxl")).unwrap();
+    let png = render_score_to_png(&score, &RasterOptions::default(), &RenderOptions::default()).unwrap();
+    std::fs::write(output_dir().join("chopin-first-system.png"), &png).unwrap();
+    let image = Image::decode(&png);
+
+    // One grand-staff system, cropped: wide and short, with music across it
+    assert!(image.width > 2 * image.height, "{}×{}", image.width, image.height);
+    assert!(image.width < 1240, "wider than an A4 page at 150 dpi");
+    let third = image.width / 3;
+    for i in 0..3 {
+        assert!(image.ink(i * third, 0, (i + 1) * third, image.height) > 500, "empty third {i}");
+    }
+    // Padding keeps the corners clear
+    assert_eq!(image.ink(0, 0, 5, 5), 0);
+    assert_eq!(image.ink(image.width - 5, image.height - 5, image.width, image.height), 0);
+}
+
+#[test]
+fn resolution_scales_the_image() {
+    let score = parse_file(sheetmusic_dir().join("asa-branca.musicxml")).unwrap();
+    let low = Image::decode(&render_score_to_png(&score, &options(72.0, RasterRegion::FirstSystem), &RenderOptions::default()).unwrap());
+    let high = Image::decode(&render_score_to_png(&score, &options(144.0, RasterRegion::FirstSystem), &RenderOptions::default()).unwrap());
+    assert!(high.width.abs_diff(2 * low.width) <= 2, "{} vs {}", high.width, low.width);
+    assert!(high.height.abs_diff(2 * low.height) <= 2, "{} vs {}", high.height, low.height);
+    assert_eq!(high.pixels_per_meter, 5669);
+    assert!(render_score_to_png(&score, &options(0.0, RasterRegion::FirstSystem), &RenderOptions::default()).is_err());
+}
+
+#[test]
+fn page_matches_the_printed_page() {
+    let score = parse_file(sheetmusic_dir().join("asa-branca.musicxml")).unwrap();
+    let letter = RasterOptions { dpi: 72.0, region: RasterRegion::Page(1), page_size: PageSize::Letter };
+    let png = render_score_to_png(&score, &letter, &RenderOptions::default()).unwrap();
+    std::fs::write(output_dir().join("asa-branca-page1.png"), &png).unwrap();
+    let image = Image::decode(&png);
+    assert_eq!((image.width, image.height), (612, 792));
+
+    // Title centered at the top, page number centered at the bottom,
+    // nothing in the side margins
+    assert!(image.ink(206, 20, 406, 80) > 100, "no title");
+    assert!(image.ink(286, 740, 326, 792) > 0, "no page number");
+    assert_eq!(image.ink(0, 0, 10, 792), 0);
+
+    let err = render_score_to_png(&score, &RasterOptions { region: RasterRegion::Page(2), ..letter }, &RenderOptions::default()).unwrap_err();
+    assert!(err.contains("page 2"), "{err}");
+}
+
+#[test]
+fn measure_range_excerpt() {
+    let score = parse_file(sheetmusic_dir().join("chopin-trois-valses.mxl")).unwrap();
+    let excerpt = options(100.0, RasterRegion::Measures { first: 17, last: 20 });
+    let png = render_score_to_png(&score, &excerpt, &RenderOptions::default()).unwrap();
+    std::fs::write(output_dir().join("chopin-measures-17-20.png"), &png).unwrap();
+    let image = Image::decode(&png);
+    assert!(image.width > image.height);
+
+    // More measures need more systems
+    let longer = options(100.0, RasterRegion::Measures { first: 17, last: 48 });
+    let longer = Image::decode(&render_score_to_png(&score, &longer, &RenderOptions::default()).unwrap());
+    assert!(longer.height > 2 * image.height);
+
+    let count = score.parts[0].measures.len();
+    for (first, last) in [(0, 3), (5, 4), (1, count + 1)] {
+        let region = RasterRegion::Measures { first, last };
+        assert!(render_score_to_png(&score, &options(100.0, region), &RenderOptions::default()).is_err(), "{first}-{last}");
+    }
+}
+
+#[test]
+fn empty_score() {
+    let score = Score {
+        title: None,
+        title_style: None,
+        subtitle: None,
+        subtitle_style: None,
+        composer: None,
+        composer_style: None,
+        arranger: None,
+        version: None,
+        software: None,
+        defaults: None,
+        parts: Vec::new(),
+    };
+    assert!(render_score_to_png(&score, &RasterOptions::default(), &RenderOptions::default()).is_err());
+    let page = render_score_to_png(&score, &options(36.0, RasterRegion::Page(1)), &RenderOptions::default()).unwrap();
+    let image = Image::decode(&page);
+    assert_eq!((image.width, image.height), (298, 421));
+}
@@ -0,0 +1,123 @@
+//! Render option tests — themes, staff scale, fonts, and switching off
+//! chord symbols, lyrics and measure numbers.
+
+use std::path::PathBuf;
+
+use scorelib::{
+    generate_playback_map, generate_playback_map_with_options, parse_file, render_score_to_svg,
+    render_score_to_svg_with_options, FontFamily, RenderOptions, Score, Theme,
+};
+
+fn sheetmusic_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../sheetmusic")
+}
+
+fn output_dir() -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_output").join("themes");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+fn tong_nian() -> Score {
+    parse_file(sheetmusic_dir().join("童年.mxl")).unwrap()
+}
+
+/// Every distinct `fill` and `stroke` color in an SVG.
+fn colors(svg: &str) -> Vec<String> {
+    let mut colors: Vec<String> = ["fill=\"", "stroke=\""].iter()
+        .flat_map(|attr| svg.split(attr).skip(1).map(|rest| rest[..rest.find('"').unwrap()].to_string()))
+        .filter(|c| c != "none")
+        .collect();
+    colors.sort();
+    colors.dedup();
+    colors
+}
+
+#[test]
+fn default_options_render_as_before() {
+    let score = tong_nian();
+    assert_eq!(
+        render_score_to_svg_with_options(&score, Some(600.0), &RenderOptions::default()),
+        render_score_to_svg(&score, Some(600.0)),
+    );
+}
+
+#[test]
+fn themes_recolor_everything() {
+    let score = parse_file(sheetmusic_dir().join("blue-bag-folly.musicxml")).unwrap();
+    let light = colors(&render_score_to_svg(&score, None));
+
+    let mut seen = vec![light.clone()];
+    for (theme, background) in [(Theme::Dark, "#1c1c1e"), (Theme::HighContrast, "white"), (Theme::Sepia, "#f4ecd8")] {
+        let options = RenderOptions { theme, ..RenderOptions::default() };
+        let svg = render_score_to_svg_with_options(&score, None, &options);
+        std::fs::write(output_dir().join(format!("blue-bag-folly-{theme:?}.svg")), &svg).unwrap();
+
+        assert!(svg.contains(&format!(r#"fill="{background}"/>"#)), "{theme:?} background");
+        let themed = colors(&svg);
+        if theme != Theme::HighContrast {
+            // No light-theme ink is left over
+            for color in light.iter().filter(|c| *c != "white") {
+                assert!(!themed.contains(color), "{theme:?} still draws in {color}");
+            }
+        }
+        assert!(!seen.contains(&themed));
+        seen.push(themed);
+    }
+
+    let contrast = RenderOptions { theme: Theme::HighContrast, ..RenderOptions::default() };
+    assert_eq!(colors(&render_score_to_svg_with_options(&score, None, &contrast)), ["#000000", "white"]);
+}
+
+#[test]
+fn staff_scale_enlarges_within_the_width() {
+    let score = tong_nian();
+    let options = RenderOptions { staff_scale: 1.5, ..RenderOptions::default() };
+    let svg = render_score_to_svg_with_options(&score, Some(820.0), &options);
+    std::fs::write(output_dir().join("童年-large.svg"), &svg).unwrap();
+    assert!(svg.contains(r#"viewBox="0 0 820 "#) && svg.contains(r#"<g transform="scale(1.5)">"#));
+
+    // Fewer measures fit on each system, and the cursor positions are in
+    // the coordinates of the enlarged SVG
+    let normal = generate_playback_map(&score, Some(820.0));
+    let large = generate_playback_map_with_options(&score, Some(820.0), &options);
+    assert!(large.systems.len() > normal.systems.len());
+    assert_eq!(large.measures.len(), normal.measures.len());
+    assert!(large.measures.iter().all(|m| m.x >= 50.0 * 1.5 - 1e-6 && m.x + m.width <= 820.0 + 1e-6));
+    assert!(large.measures.iter().any(|m| m.x + m.width > 820.0 - 30.0 * 1.5 - 1.0));
+    let height: f64 = svg.split("height=\"").nth(1).unwrap().split('"').next().unwrap().parse().unwrap();
+    let last = large.systems.last().unwrap();
+    assert!(last.y + last.height < height);
+
+    // Out-of-range scales are clamped
+    let tiny = RenderOptions { staff_scale: 0.01, ..RenderOptions::default() };
+    assert!(render_score_to_svg_with_options(&score, None, &tiny).contains("scale(0.5)"));
+}
+
+#[test]
+fn elements_can_be_switched_off() {
+    let score = tong_nian();
+    let full = render_score_to_svg(&score, None);
+    assert!(full.contains(r#"class="lyric""#) && full.contains(r#"class="chord-symbol""#));
+    assert!(full.contains("font-style=\"italic\" fill=\"#555555\" text-anchor=\"start\">4</text>"));
+
+    let options = RenderOptions {
+        chord_symbols: false,
+        lyrics: false,
+        measure_numbers: false,
+        font: FontFamily::Named("Atkinson Hyperlegible".into()),
+        ..RenderOptions::default()
+    };
+    let bare = render_score_to_svg_with_options(&score, None, &options);
+    std::fs::write(output_dir().join("童年-bare.svg"), &bare).unwrap();
+    assert!(!bare.contains(r#"class="lyric""#) && !bare.contains(r#"class="chord-symbol""#));
+    assert!(!bare.contains("font-style=\"italic\" fill=\"#555555\" text-anchor=\"start\""));
+    assert!(bare.contains("font-family: 'Atkinson Hyperlegible', sans-serif;"));
+
+    // Without lyrics and chords the systems close up
+    let full_map = generate_playback_map(&score, None);
+    let bare_map = generate_playback_map_with_options(&score, None, &options);
+    let bottom = |map: &scorelib::PlaybackMap| map.systems.last().map(|s| s.y).unwrap();
+    assert!(bottom(&bare_map) < bottom(&full_map));
+    assert_eq!(bare_map.measures.len(), full_map.measures.len());
+}
@@ -60,6 +60,8 @@ fn render_chopin_trois_valses_svg() {
     assert!(svg.starts_with("<svg"));
     assert!(svg.contains("</svg>"));
     assert!(svg.contains("<ellipse"), "SVG should contain noteheads");
+    assert!(svg.contains(r#"class="dynamics" data-dynamics="mf""#), "SVG should contain dynamics markings");
+    assert!(!svg.contains(">mf</text>"), "dynamics are drawn from glyph outlines, not text");
 
     let out = output_dir().join("chopin-trois-valses.svg");
     std::fs::write(&out, &svg).expect("Failed to write SVG");
@@ -137,6 +139,91 @@ fn render_blue_bag_folly_svg() {
     println!("  Output: {}", out.display());
 }
 
+#[test]
+fn render_tuplet_numbers_and_brackets() {
+    // Beamed triplet (number only) followed by an unbeamed quarter-note
+    // triplet (bracket + number).
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list><score-part id="P1"><part-name>Flute</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>3</divisions><time><beats>4</beats><beat-type>4</beat-type></time>
+        <clef><sign>G</sign><line>2</line></clef></attributes>
+      <note><pitch><step>C</step><octave>5</octave></pitch><duration>1</duration><type>eighth</type>
+        <time-modification><actual-notes>3</actual-notes><normal-notes>2</normal-notes></time-modification>
+        <beam number="1">begin</beam><notations><tuplet type="start"/></notations></note>
+      <note><pitch><step>D</step><octave>5</octave></pitch><duration>1</duration><type>eighth</type>
+        <time-modification><actual-notes>3</actual-notes><normal-notes>2</normal-notes></time-modification>
+        <beam number="1">continue</beam></note>
+      <note><pitch><step>E</step><octave>5</octave></pitch><duration>1</duration><type>eighth</type>
+        <time-modification><actual-notes>3</actual-notes><normal-notes>2</normal-notes></time-modification>
+        <beam number="1">end</beam><notations><tuplet type="stop"/></notations></note>
+      <note><pitch><step>F</step><octave>4</octave></pitch><duration>2</duration><type>quarter</type>
+        <time-modification><actual-notes>3</actual-notes><normal-notes>2</normal-notes></time-modification>
+        <notations><tuplet type="start" show-number="both"/></notations></note>
+      <note><pitch><step>G</step><octave>4</octave></pitch><duration>2</duration><type>quarter</type>
+        <time-modification><actual-notes>3</actual-notes><normal-notes>2</normal-notes></time-modification></note>
+      <note><pitch><step>A</step><octave>4</octave></pitch><duration>2</duration><type>quarter</type>
+        <time-modification><actual-notes>3</actual-notes><normal-notes>2</normal-notes></time-modification>
+        <notations><tuplet type="stop"/></notations></note>
+      <note><rest/><duration>3</duration><type>quarter</type></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+    let score = scorelib::parse_musicxml(xml).expect("Failed to parse inline MusicXML");
+    let svg = render_score_to_svg(&score, None);
+
+    assert!(svg.contains(">3</text>"), "Beamed triplet should show its number");
+    assert!(svg.contains(">3:2</text>"), "show-number=\"both\" should show the ratio");
+
+    let out = output_dir().join("tuplets.svg");
+    std::fs::write(&out, &svg).expect("Failed to write SVG");
+    println!("✓ Rendered tuplets.svg ({} bytes)", svg.len());
+}
+
+#[test]
+fn render_tuplet_across_a_barline() {
+    // A quarter-note triplet whose middle note is split by the barline
+    let trip = |step: &str, dur: u32, kind: &str, tuplet: &str| format!(
+        r#"<note><pitch><step>{step}</step><octave>4</octave></pitch><duration>{dur}</duration><type>{kind}</type>
+        <time-modification><actual-notes>3</actual-notes><normal-notes>2</normal-notes></time-modification>{tuplet}</note>"#);
+    let half = |step: &str| format!(
+        r#"<note><pitch><step>{step}</step><octave>4</octave></pitch><duration>6</duration><type>half</type></note>"#);
+    let xml = format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list><score-part id="P1"><part-name>Flute</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>3</divisions><time><beats>3</beats><beat-type>4</beat-type></time>
+        <clef><sign>G</sign><line>2</line></clef></attributes>
+      {}{}{}
+    </measure>
+    <measure number="2">{}{}{}</measure>
+  </part>
+</score-partwise>"#,
+        half("C"),
+        trip("D", 2, "quarter", r#"<notations><tuplet type="start"/></notations>"#),
+        trip("E", 1, "eighth", ""),
+        trip("E", 1, "eighth", ""),
+        trip("F", 2, "quarter", r#"<notations><tuplet type="stop"/></notations>"#),
+        half("G"),
+    );
+
+    let score = scorelib::parse_musicxml(&xml).expect("Failed to parse inline MusicXML");
+    let svg = render_score_to_svg(&score, None);
+    assert_eq!(svg.matches(">3</text>").count(), 1, "the tuplet crossing the barline gets one number");
+
+    // Split across two systems it still closes, on the second
+    let narrow = scorelib::render_score_to_svg(&score, Some(160.0));
+    assert_eq!(narrow.matches(">3</text>").count(), 1);
+
+    let out = output_dir().join("tuplet-across-barline.svg");
+    std::fs::write(&out, &svg).expect("Failed to write SVG");
+    println!("✓ Rendered tuplet-across-barline.svg ({} bytes)", svg.len());
+}
+
 fn extract_height(svg: &str) -> f64 {
     // Extract height from: height="1234"
     svg.split("height=\"")
@@ -145,3 +232,143 @@ fn extract_height(svg: &str) -> f64 {
         .and_then(|s| s.parse::<f64>().ok())
         .unwrap_or(0.0)
 }
+
+#[test]
+fn render_articulations_and_ornaments() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list><score-part id="P1"><part-name>Flute</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions><time><beats>4</beats><beat-type>4</beat-type></time>
+        <clef><sign>G</sign><line>2</line></clef></attributes>
+      <note><pitch><step>C</step><octave>5</octave></pitch><duration>1</duration><type>quarter</type>
+        <notations><articulations><staccato/><accent/></articulations></notations></note>
+      <note><pitch><step>E</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type>
+        <notations><articulations><tenuto/></articulations></notations></note>
+      <note><pitch><step>A</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type>
+        <notations><ornaments><trill-mark/></ornaments></notations></note>
+      <note><pitch><step>G</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type>
+        <notations><ornaments><mordent/></ornaments><fermata type="upright"/></notations></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+    let score = scorelib::parse_musicxml(xml).expect("Failed to parse inline MusicXML");
+    // Same notes with the notations commented out
+    let bare = xml.replace("<notations>", "<!--").replace("</notations>", "-->");
+    let plain = render_score_to_svg(&scorelib::parse_musicxml(&bare).unwrap(), None);
+    let svg = render_score_to_svg(&score, None);
+
+    assert!(svg.contains(">tr</text>"), "Trill should render as 'tr'");
+    assert!(svg.matches("<path").count() > plain.matches("<path").count() + 2,
+        "Accent, mordent and fermata should add paths");
+
+    let out = output_dir().join("articulations.svg");
+    std::fs::write(&out, &svg).expect("Failed to write SVG");
+    println!("✓ Rendered articulations.svg ({} bytes)", svg.len());
+}
+
+#[test]
+fn render_ties_across_barlines_and_systems() {
+    // Twelve measures of a tied two-note chord: every barline carries two
+    // ties, and the ties split at each system break.
+    let mut measures = String::new();
+    for m in 1..=12 {
+        let tie = match m {
+            1 => r#"<tie type="start"/>"#.to_string(),
+            12 => r#"<tie type="stop"/>"#.to_string(),
+            _ => r#"<tie type="stop"/><tie type="start"/>"#.to_string(),
+        };
+        let attrs = if m == 1 {
+            "<attributes><divisions>1</divisions><time><beats>4</beats><beat-type>4</beat-type></time>\
+             <clef><sign>G</sign><line>2</line></clef></attributes>"
+        } else {
+            ""
+        };
+        measures.push_str(&format!(
+            r#"<measure number="{m}">{attrs}
+      <note><pitch><step>C</step><octave>5</octave></pitch><duration>4</duration>{tie}<type>whole</type></note>
+      <note><chord/><pitch><step>E</step><octave>5</octave></pitch><duration>4</duration>{tie}<type>whole</type></note>
+    </measure>"#
+        ));
+    }
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list><score-part id="P1"><part-name>Flute</part-name></score-part></part-list>
+  <part id="P1">{measures}</part>
+</score-partwise>"#
+    );
+
+    let score = scorelib::parse_musicxml(&xml).expect("Failed to parse inline MusicXML");
+    let untied = scorelib::parse_musicxml(&xml.replace("<tie ", "<!-- ").replace("\"/>", "\"-->")).unwrap();
+    let svg = render_score_to_svg(&score, None);
+    let plain = render_score_to_svg(&untied, None);
+
+    let ties = svg.matches("<path").count() - plain.matches("<path").count();
+    assert!(ties >= 24, "Expected 22 ties plus continuations, got {}", ties);
+    assert_eq!(ties % 2, 0, "Both chord members should be tied");
+
+    let out = output_dir().join("ties.svg");
+    std::fs::write(&out, &svg).expect("Failed to write SVG");
+    println!("✓ Rendered ties.svg: {} tie segments", ties);
+}
+
+#[test]
+fn hairpin_under_low_notes_sits_on_the_dynamics_row() {
+    // p, crescendo, f under notes on ledger lines below the treble staff
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list><score-part id="P1"><part-name>Violin</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions><time><beats>4</beats><beat-type>4</beat-type></time>
+        <clef><sign>G</sign><line>2</line></clef></attributes>
+      <direction placement="below"><direction-type><dynamics><p/></dynamics></direction-type></direction>
+      <direction placement="below"><direction-type><wedge type="crescendo" number="1"/></direction-type></direction>
+      <note><pitch><step>G</step><octave>3</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <note><pitch><step>A</step><octave>3</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <note><pitch><step>G</step><octave>3</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <note><pitch><step>A</step><octave>3</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <direction placement="below"><direction-type><wedge type="stop" number="1"/></direction-type></direction>
+    </measure>
+    <measure number="2">
+      <direction placement="below"><direction-type><dynamics><f/></dynamics></direction-type></direction>
+      <note><pitch><step>G</step><octave>3</octave></pitch><duration>4</duration><type>whole</type></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+    let score = scorelib::parse_musicxml(xml).unwrap();
+    let svg = render_score_to_svg(&score, None);
+
+    let attr = |el: &str, name: &str| -> f64 {
+        let at = el.find(&format!(" {name}=\"")).unwrap() + name.len() + 3;
+        el[at..at + el[at..].find('"').unwrap()].parse().unwrap()
+    };
+    let lines: Vec<[f64; 4]> = svg.split("<line").skip(1)
+        .map(|el| [attr(el, "x1"), attr(el, "y1"), attr(el, "x2"), attr(el, "y2")])
+        .collect();
+    // The crescendo: two lines from one point that open apart
+    let (upper, lower) = lines.iter()
+        .flat_map(|a| lines.iter().map(move |b| (a, b)))
+        .find(|(a, b)| a[0] == b[0] && a[1] == b[1] && a[2] == b[2] && a[3] < b[3] && a[2] > a[0])
+        .expect("no crescendo drawn");
+    let wedge_y = (upper[3] + lower[3]) / 2.0;
+
+    // The wedge runs through the p, between its top and its descender
+    let p_group = &svg[svg.find(r#"data-dynamics="p""#).expect("no p drawn")..];
+    let p_path = &p_group[p_group.find(" d=\"").unwrap() + 4..];
+    let p_path = &p_path[..p_path.find('"').unwrap()];
+    let numbers: Vec<f64> = p_path.split(|c: char| c.is_ascii_alphabetic() || c == ' ')
+        .filter(|t| !t.is_empty())
+        .map(|t| t.parse().unwrap())
+        .collect();
+    let ys = numbers.iter().skip(1).step_by(2);
+    let p_top = ys.clone().cloned().fold(f64::MAX, f64::min);
+    let p_bottom = ys.cloned().fold(f64::MIN, f64::max);
+    assert!(p_top < wedge_y && wedge_y < p_bottom, "wedge at {wedge_y}, p from {p_top} to {p_bottom}");
+
+    let lowest_note = svg.split("<ellipse").skip(1).map(|el| attr(el, "cy")).fold(f64::MIN, f64::max);
+    assert!(upper[1] > lowest_note + 4.0, "wedge at {} collides with notes at {lowest_note}", upper[1]);
+}
@@ -0,0 +1,255 @@
+//! Integration tests for the SoundFont synthesizer: SoundFont parsing,
+//! note rendering, gain/reverb options and WAV output.
+
+use scorelib::synth::{pcm_to_wav, render_midi, Synth};
+use scorelib::{
+    generate_midi_from_score, generate_wav_from_score, parse_musicxml, MidiOptions, SoundFont,
+    SynthOptions,
+};
+
+const SAMPLE_RATE: u32 = 44_100;
+/// Frequency of the test sample: 100 samples per cycle at 44.1 kHz.
+const SAMPLE_HZ: f64 = 441.0;
+
+// ═══════════════════════════════════════════════════════════════════════
+// Test SoundFont
+// ═══════════════════════════════════════════════════════════════════════
+
+fn chunk(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut out = id.to_vec();
+    out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    out.extend_from_slice(body);
+    if body.len() % 2 == 1 {
+        out.push(0);
+    }
+    out
+}
+
+fn list(kind: &[u8; 4], chunks: &[Vec<u8>]) -> Vec<u8> {
+    let mut body = kind.to_vec();
+    for c in chunks {
+        body.extend_from_slice(c);
+    }
+    chunk(b"LIST", &body)
+}
+
+fn name20(name: &str) -> Vec<u8> {
+    let mut out = name.as_bytes().to_vec();
+    out.resize(20, 0);
+    out
+}
+
+fn gen(oper: u16, amount: i16) -> Vec<u8> {
+    [oper.to_le_bytes(), amount.to_le_bytes()].concat()
+}
+
+/// A minimal SoundFont: one preset (bank 0, program 0) playing one looped
+/// sine-wave sample at 441 Hz, recorded at A4 (key 69).
+fn sine_soundfont() -> Vec<u8> {
+    looped_soundfont(0, 1000)
+}
+
+/// The sine SoundFont with its loop on frames `loop_start..loop_end` of
+/// the 1000-frame sample.
+fn looped_soundfont(loop_start: u32, loop_end: u32) -> Vec<u8> {
+    let frames = 1000u32;
+    let smpl: Vec<u8> = (0..frames)
+        .flat_map(|i| {
+            let v = (i as f64 / 100.0 * std::f64::consts::TAU).sin() * 16000.0;
+            (v as i16).to_le_bytes()
+        })
+        .collect();
+
+    let mut phdr = name20("Sine");
+    phdr.extend_from_slice(&[0, 0, 0, 0, 0, 0]); // program, bank, bag 0
+    phdr.extend_from_slice(&[0; 12]);
+    phdr.extend(name20("EOP"));
+    phdr.extend_from_slice(&[0, 0, 0, 0, 1, 0]);
+    phdr.extend_from_slice(&[0; 12]);
+
+    let pbag = [0u16, 0, 1, 0].iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<u8>>();
+    let pgen = [gen(41, 0), gen(0, 0)].concat();
+
+    let mut inst = name20("Sine");
+    inst.extend_from_slice(&0u16.to_le_bytes());
+    inst.extend(name20("EOI"));
+    inst.extend_from_slice(&1u16.to_le_bytes());
+
+    let ibag = [0u16, 0, 2, 0].iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<u8>>();
+    // Loop continuously, then the sample link
+    let igen = [gen(54, 1), gen(53, 0), gen(0, 0)].concat();
+
+    let mut shdr = name20("Sine");
+    for v in [0, frames, loop_start, loop_end, SAMPLE_RATE] {
+        shdr.extend_from_slice(&v.to_le_bytes());
+    }
+    shdr.extend_from_slice(&[69, 0, 0, 0, 1, 0]); // pitch, correction, link, mono
+    shdr.extend(name20("EOS"));
+    shdr.extend_from_slice(&[0; 26]);
+
+    let mut body = b"sfbk".to_vec();
+    body.extend(list(b"INFO", &[chunk(b"ifil", &[2, 0, 1, 0])]));
+    body.extend(list(b"sdta", &[chunk(b"smpl", &smpl)]));
+    body.extend(list(b"pdta", &[
+        chunk(b"phdr", &phdr),
+        chunk(b"pbag", &pbag),
+        chunk(b"pmod", &[0; 10]),
+        chunk(b"pgen", &pgen),
+        chunk(b"inst", &inst),
+        chunk(b"ibag", &ibag),
+        chunk(b"imod", &[0; 10]),
+        chunk(b"igen", &igen),
+        chunk(b"shdr", &shdr),
+    ]));
+    chunk(b"RIFF", &body)
+}
+
+fn dry_options() -> SynthOptions {
+    SynthOptions { reverb: 0.0, ..SynthOptions::default() }
+}
+
+/// Left-channel samples of interleaved stereo PCM.
+fn left(pcm: &[f32]) -> Vec<f32> {
+    pcm.iter().step_by(2).copied().collect()
+}
+
+fn peak(samples: &[f32]) -> f32 {
+    samples.iter().fold(0.0f32, |m, s| m.max(s.abs()))
+}
+
+/// Estimated frequency from upward zero crossings.
+fn frequency(samples: &[f32], sample_rate: u32) -> f64 {
+    let crossings = samples.windows(2).filter(|w| w[0] < 0.0 && w[1] >= 0.0).count();
+    crossings as f64 * sample_rate as f64 / samples.len() as f64
+}
+
+/// One measure of a whole note on `step`/`octave`, at 120 BPM.
+fn whole_note_score(step: &str, octave: i32) -> scorelib::Score {
+    let xml = format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="4.0">
+  <part-list><score-part id="P1"><part-name>Melody</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions><time><beats>4</beats><beat-type>4</beat-type></time>
+        <clef><sign>G</sign><line>2</line></clef></attributes>
+      <direction placement="above"><sound tempo="120"/></direction>
+      <note><pitch><step>{step}</step><octave>{octave}</octave></pitch><duration>4</duration><type>whole</type></note>
+    </measure>
+  </part>
+</score-partwise>"#);
+    parse_musicxml(&xml).unwrap()
+}
+
+fn melody_only() -> MidiOptions {
+    MidiOptions { include_metronome: false, ..MidiOptions::default() }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// Tests
+// ═══════════════════════════════════════════════════════════════════════
+
+#[test]
+fn soundfont_parses_presets() {
+    let font = SoundFont::parse(&sine_soundfont()).unwrap();
+    assert_eq!(font.preset_names(), vec![(0, 0, "Sine".to_string())]);
+    assert!(SoundFont::parse(b"not a soundfont").is_err());
+    println!("✓ SoundFont presets parsed; bad input rejected");
+}
+
+#[test]
+fn synth_is_silent_until_note_on_and_plays_the_right_pitch() {
+    let font = SoundFont::parse(&sine_soundfont()).unwrap();
+    let mut synth = Synth::new(&font, &dry_options());
+
+    let mut buf = vec![1.0f32; 4410 * 2];
+    synth.render(&mut buf);
+    assert_eq!(peak(&buf), 0.0, "no sound before a note-on");
+
+    // A4: the sample's own pitch
+    synth.handle_message(&[0x90, 69, 100]);
+    assert_eq!(synth.active_voices(), 1);
+    let mut buf = vec![0.0f32; SAMPLE_RATE as usize * 2];
+    synth.render(&mut buf);
+    assert!(peak(&buf) > 0.01, "note-on should sound, peak {}", peak(&buf));
+    let hz = frequency(&left(&buf), SAMPLE_RATE);
+    assert!((hz - SAMPLE_HZ).abs() < SAMPLE_HZ * 0.02, "A4 played at {hz:.1} Hz");
+
+    // A5: one octave up
+    synth.handle_message(&[0x80, 69, 0]);
+    synth.handle_message(&[0x90, 81, 100]);
+    let mut buf = vec![0.0f32; SAMPLE_RATE as usize * 2];
+    synth.render(&mut buf);
+    let hz = frequency(&left(&buf[4410..]), SAMPLE_RATE);
+    assert!((hz - 2.0 * SAMPLE_HZ).abs() < SAMPLE_HZ * 0.04, "A5 played at {hz:.1} Hz");
+    println!("✓ Synth silent before note-on; A4/A5 at the expected pitch");
+}
+
+#[test]
+fn short_loop_played_high_stays_inside_the_sample() {
+    // A 10-frame loop at the end of the sample, played 58 semitones above
+    // its root, steps ~28 frames per output frame: past the whole loop
+    let font = SoundFont::parse(&looped_soundfont(990, 1000)).unwrap();
+    let mut synth = Synth::new(&font, &dry_options());
+    synth.handle_message(&[0x90, 127, 100]);
+    synth.handle_message(&[0xE0, 0x7F, 0x7F]);
+    let mut buf = vec![0.0f32; 4410 * 2];
+    synth.render(&mut buf);
+    assert!(buf.iter().all(|s| s.is_finite()));
+    assert_eq!(synth.active_voices(), 1, "the looped note keeps sounding");
+    println!("✓ Short sustain loop wraps correctly at a high pitch");
+}
+
+#[test]
+fn gain_scales_rendered_audio() {
+    let font = SoundFont::parse(&sine_soundfont()).unwrap();
+    let midi = generate_midi_from_score(&whole_note_score("A", 4), &melody_only());
+
+    let full = render_midi(&font, &midi, &dry_options()).unwrap();
+    let half = render_midi(&font, &midi, &SynthOptions { gain: 0.5, ..dry_options() }).unwrap();
+    assert_eq!(full.len(), half.len());
+    let ratio = peak(&half) / peak(&full);
+    assert!((ratio - 0.5).abs() < 0.01, "gain 0.5 should halve the peak, got ratio {ratio}");
+    println!("✓ Gain 0.5 halves the output level");
+}
+
+#[test]
+fn reverb_adds_a_tail() {
+    let font = SoundFont::parse(&sine_soundfont()).unwrap();
+    let midi = generate_midi_from_score(&whole_note_score("A", 4), &melody_only());
+
+    let dry = render_midi(&font, &midi, &dry_options()).unwrap();
+    let wet = render_midi(&font, &midi, &SynthOptions { reverb: 0.5, ..dry_options() }).unwrap();
+    assert!(wet.len() > dry.len(), "reverb should lengthen the output");
+    let tail = &wet[dry.len()..];
+    assert!(peak(tail) > 0.0, "reverb tail should not be silent");
+    assert!(peak(&dry[dry.len() - 200..]) < 1e-3, "dry output should end in silence");
+    println!("✓ Reverb adds a {:.2} s tail", (wet.len() - dry.len()) as f64 / 2.0 / SAMPLE_RATE as f64);
+}
+
+#[test]
+fn score_renders_to_wav() {
+    let font = SoundFont::parse(&sine_soundfont()).unwrap();
+    let options = SynthOptions { sample_rate: 22_050, ..dry_options() };
+    let wav = generate_wav_from_score(&whole_note_score("C", 5), &melody_only(), &font, &options).unwrap();
+
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(&wav[8..12], b"WAVE");
+    assert_eq!(u32::from_le_bytes(wav[4..8].try_into().unwrap()) as usize, wav.len() - 8);
+    assert_eq!(u32::from_le_bytes(wav[24..28].try_into().unwrap()), 22_050);
+    assert_eq!(&wav[36..40], b"data");
+    let data_len = u32::from_le_bytes(wav[40..44].try_into().unwrap()) as usize;
+    assert_eq!(data_len, wav.len() - 44);
+
+    // A whole note at 120 BPM lasts 2 s
+    let seconds = data_len as f64 / 4.0 / 22_050.0;
+    assert!((2.0..2.5).contains(&seconds), "expected ~2 s of audio, got {seconds:.2} s");
+    println!("✓ Score rendered to a {:.2} s WAV", seconds);
+}
+
+#[test]
+fn pcm_to_wav_clamps_samples() {
+    let wav = pcm_to_wav(&[2.0, -2.0, 0.5, 0.0], SAMPLE_RATE);
+    let samples: Vec<i16> = wav[44..].chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]])).collect();
+    assert_eq!(samples, vec![32767, -32767, 16384, 0]);
+    println!("✓ PCM encoded as clamped 16-bit samples");
+}
@@ -0,0 +1,204 @@
+//! Guitar tablature tests — parsing tab staves, rendering them, and
+//! generating fingered tab under a melody.
+
+use std::path::PathBuf;
+
+use scorelib::midi::read_smf;
+use scorelib::{
+    add_tab_staff, generate_midi_from_score, parse_abc, parse_file, parse_musicxml, parse_tuning,
+    render_score_to_svg, transpose_score, write_musicxml, MidiOptions, Part, Score, TabOptions,
+};
+
+fn sheetmusic_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../sheetmusic")
+}
+
+fn output_dir() -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_output");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+/// Notation on staff 1 and drop-D tab on staff 2, as guitar editors export it.
+const NOTATION_AND_TAB: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list><score-part id="P1"><part-name>Guitar</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes>
+        <divisions>1</divisions>
+        <time><beats>4</beats><beat-type>4</beat-type></time>
+        <staves>2</staves>
+        <clef number="1"><sign>G</sign><line>2</line><clef-octave-change>-1</clef-octave-change></clef>
+        <clef number="2"><sign>TAB</sign><line>5</line></clef>
+        <staff-details number="2">
+          <staff-lines>6</staff-lines>
+          <staff-tuning line="1"><tuning-step>D</tuning-step><tuning-octave>2</tuning-octave></staff-tuning>
+          <staff-tuning line="2"><tuning-step>A</tuning-step><tuning-octave>2</tuning-octave></staff-tuning>
+          <staff-tuning line="3"><tuning-step>D</tuning-step><tuning-octave>3</tuning-octave></staff-tuning>
+          <staff-tuning line="4"><tuning-step>G</tuning-step><tuning-octave>3</tuning-octave></staff-tuning>
+          <staff-tuning line="5"><tuning-step>B</tuning-step><tuning-octave>3</tuning-octave></staff-tuning>
+          <staff-tuning line="6"><tuning-step>E</tuning-step><tuning-octave>4</tuning-octave></staff-tuning>
+        </staff-details>
+      </attributes>
+      <note><pitch><step>D</step><octave>2</octave></pitch><duration>2</duration><voice>1</voice><type>half</type><staff>1</staff></note>
+      <note><pitch><step>F</step><alter>1</alter><octave>4</octave></pitch><duration>2</duration><voice>1</voice><type>half</type><staff>1</staff></note>
+      <backup><duration>4</duration></backup>
+      <note><pitch><step>D</step><octave>2</octave></pitch><duration>2</duration><voice>5</voice><type>half</type><staff>2</staff>
+        <notations><technical><string>6</string><fret>0</fret></technical></notations></note>
+      <note><pitch><step>F</step><alter>1</alter><octave>4</octave></pitch><duration>2</duration><voice>5</voice><type>half</type><staff>2</staff>
+        <notations><technical><string>1</string><fret>14</fret></technical></notations></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+/// Every fingered note sounds its pitch on the part's tab tuning.
+fn assert_frets_match_pitches(part: &Part, tuning: &[i32]) {
+    for measure in &part.measures {
+        for note in measure.notes.iter().filter(|n| n.staff == Some(2)) {
+            let (string, fret) = (note.string.unwrap(), note.fret.unwrap());
+            let open = tuning[tuning.len() - string as usize];
+            assert_eq!(open + fret, note.pitch.as_ref().unwrap().to_midi(), "string {string} fret {fret}");
+        }
+    }
+}
+
+#[test]
+fn parses_tab_staff() {
+    let score = parse_musicxml(NOTATION_AND_TAB).unwrap();
+    let part = &score.parts[0];
+    assert!(part.is_tab_staff(2) && !part.is_tab_staff(1));
+
+    let attrs = part.measures[0].attributes.as_ref().unwrap();
+    let details = &attrs.staff_details[0];
+    assert_eq!((details.number, details.lines), (2, Some(6)));
+    let tuning: Vec<String> = details.tuning.iter().map(|p| format!("{}{}", p.step, p.octave)).collect();
+    assert_eq!(tuning, ["D2", "A2", "D3", "G3", "B3", "E4"]);
+
+    let tab: Vec<(Option<i32>, Option<i32>)> = part.measures[0].notes.iter()
+        .filter(|n| n.staff == Some(2))
+        .map(|n| (n.string, n.fret))
+        .collect();
+    assert_eq!(tab, [(Some(6), Some(0)), (Some(1), Some(14))]);
+}
+
+#[test]
+fn renders_tab_staff() {
+    let score = parse_musicxml(NOTATION_AND_TAB).unwrap();
+    let svg = render_score_to_svg(&score, None);
+    std::fs::write(output_dir().join("tab-notation-and-tab.svg"), &svg).unwrap();
+
+    for text in [">T</text>", ">A</text>", ">B</text>", ">0</text>", ">14</text>"] {
+        assert!(svg.contains(text), "missing {text}");
+    }
+    // Five notation lines plus six tab lines, all the width of the system
+    let staff_lines = svg.lines()
+        .filter(|l| l.starts_with("  <line x1=\"50.0\"") && l.contains("stroke-width=\"0.8\""))
+        .count();
+    assert_eq!(staff_lines, 11);
+}
+
+#[test]
+fn tab_round_trips_through_musicxml() {
+    let score = parse_musicxml(NOTATION_AND_TAB).unwrap();
+    let reparsed = parse_musicxml(&write_musicxml(&score)).unwrap();
+    assert_eq!(
+        serde_json::to_value(&score.parts).unwrap(),
+        serde_json::to_value(&reparsed.parts).unwrap(),
+    );
+}
+
+#[test]
+fn tab_staff_is_not_played_twice() {
+    let score = parse_musicxml(NOTATION_AND_TAB).unwrap();
+    let options = MidiOptions { include_metronome: false, ..MidiOptions::default() };
+    let smf = read_smf(&generate_midi_from_score(&score, &options)).unwrap();
+    let note_ons = smf.tracks.iter()
+        .flat_map(|t| &t.events)
+        .filter(|e| e.bytes.len() == 3 && e.bytes[0] & 0xF0 == 0x90 && e.bytes[2] > 0)
+        .count();
+    assert_eq!(note_ons, 2);
+}
+
+#[test]
+fn parses_tunings() {
+    let names = |spec: &str| -> Vec<String> {
+        parse_tuning(spec).unwrap().iter()
+            .map(|p| format!("{}{}{}", p.step, if p.alter == Some(-1.0) { "b" } else { "" }, p.octave))
+            .collect()
+    };
+    assert_eq!(names("standard"), ["E2", "A2", "D3", "G3", "B3", "E4"]);
+    assert_eq!(names("Drop-D")[0], "D2");
+    assert_eq!(names("bass").len(), 4);
+    assert_eq!(names("Eb2,Ab2,Db3, Gb3 Bb3 Eb4"), ["Eb2", "Ab2", "Db3", "Gb3", "Bb3", "Eb4"]);
+    assert!(parse_tuning("X2 A2").is_err());
+    assert!(parse_tuning("E A D").is_err());
+    assert!(parse_tuning(" ").is_err());
+}
+
+#[test]
+fn fingers_a_scale_in_first_position() {
+    let mut score = parse_abc("X:1\nT:Scale\nM:4/4\nL:1/4\nK:C\nC,D,E,F,|G,A,B,C|\n").unwrap();
+    add_tab_staff(&mut score, 0, &TabOptions::default()).unwrap();
+
+    let frets: Vec<(i32, i32)> = score.parts[0].measures.iter()
+        .flat_map(|m| &m.notes)
+        .filter(|n| n.staff == Some(2))
+        .map(|n| (n.string.unwrap(), n.fret.unwrap()))
+        .collect();
+    assert_eq!(frets, [(5, 3), (4, 0), (4, 2), (4, 3), (3, 0), (3, 2), (2, 0), (2, 1)]);
+}
+
+#[test]
+fn generates_tab_under_a_melody() {
+    let mut score = parse_file(sheetmusic_dir().join("asa-branca.musicxml")).unwrap();
+    let midi_before = generate_midi_from_score(&score, &MidiOptions::default());
+    let melody_notes = score.parts[0].measures.iter()
+        .flat_map(|m| &m.notes)
+        .filter(|n| n.pitch.is_some() && !n.rest && !n.grace)
+        .count();
+
+    add_tab_staff(&mut score, 0, &TabOptions::default()).unwrap();
+    let part = &score.parts[0];
+    assert!(part.is_tab_staff(2));
+    let standard = [40, 45, 50, 55, 59, 64];
+    assert_frets_match_pitches(part, &standard);
+    let tab_notes = part.measures.iter().flat_map(|m| &m.notes).filter(|n| n.staff == Some(2)).count();
+    assert_eq!(tab_notes, melody_notes);
+
+    // Chord members never share a string
+    for measure in &part.measures {
+        let tab: Vec<_> = measure.notes.iter().filter(|n| n.staff == Some(2)).collect();
+        for (i, a) in tab.iter().enumerate() {
+            assert!(tab[i + 1..].iter().all(|b| b.onset != a.onset || b.string != a.string));
+        }
+    }
+
+    // The tab doubles the melody without adding to playback
+    assert_eq!(generate_midi_from_score(&score, &MidiOptions::default()), midi_before);
+
+    let svg = render_score_to_svg(&score, None);
+    std::fs::write(output_dir().join("asa-branca-tab.svg"), &svg).unwrap();
+    assert!(svg.contains(">T</text>"));
+
+    // Transposing fingers the tab again
+    transpose_score(&mut score, 3);
+    assert_frets_match_pitches(&score.parts[0], &standard);
+}
+
+#[test]
+fn custom_tuning_and_errors() {
+    let mut score = parse_file(sheetmusic_dir().join("asa-branca.musicxml")).unwrap();
+    let options = TabOptions { tuning: parse_tuning("dadgad").unwrap(), ..TabOptions::default() };
+    add_tab_staff(&mut score, 0, &options).unwrap();
+    assert_frets_match_pitches(&score.parts[0], &[38, 45, 50, 55, 57, 62]);
+    let details = &score.parts[0].measures[0].attributes.as_ref().unwrap().staff_details[0];
+    assert_eq!(details.tuning[4].step, "A");
+
+    assert!(add_tab_staff(&mut score, 0, &options).is_err(), "already has a tab staff");
+    assert!(add_tab_staff(&mut score, 3, &options).is_err(), "no such part");
+    let mut piano = parse_file(sheetmusic_dir().join("chopin-trois-valses.mxl")).unwrap();
+    assert!(add_tab_staff(&mut piano, 0, &options).is_err(), "grand staff");
+    let mut empty = Score::new();
+    assert!(add_tab_staff(&mut empty, 0, &options).is_err());
+}
@@ -0,0 +1,173 @@
+//! Beginner aid tests — note names and solfège, Boomwhacker-colored
+//! noteheads and finger numbers, before and after transposing.
+
+use std::path::PathBuf;
+
+use scorelib::renderer::compute_measure_positions_with_options;
+use scorelib::{
+    note_name, parse_file, parse_musicxml, render_score_to_svg, render_score_to_svg_pages,
+    render_score_to_svg_with_options, transpose_score, write_musicxml, LabelPosition, NoteNames, PageOptions,
+    Pitch, RenderOptions, Score,
+};
+
+fn sheetmusic_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../sheetmusic")
+}
+
+fn output_dir() -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_output").join("teaching");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+fn tong_nian() -> Score {
+    parse_file(sheetmusic_dir().join("童年.mxl")).unwrap()
+}
+
+fn pitch(step: &str, alter: f64) -> Pitch {
+    Pitch { step: step.to_string(), octave: 4, alter: (alter != 0.0).then_some(alter) }
+}
+
+fn named(names: NoteNames) -> RenderOptions {
+    RenderOptions { note_names: names, ..RenderOptions::default() }
+}
+
+/// The text drawn inside each note's group, in document order.
+fn note_texts(svg: &str) -> Vec<String> {
+    svg.split(r#"class="note""#).skip(1)
+        .flat_map(|group| {
+            let group = &group[..group.find("</g>").unwrap()];
+            group.split("<text").skip(1).map(|t| {
+                let t = &t[t.find('>').unwrap() + 1..];
+                t[..t.find("</text>").unwrap()].to_string()
+            }).collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[test]
+fn names_letters_and_solfege_in_a_key() {
+    let g_major = 1;
+    let names = |names| {
+        [pitch("G", 0.0), pitch("F", 1.0), pitch("F", 0.0), pitch("C", 1.0), pitch("B", -1.0)]
+            .iter()
+            .map(|p| note_name(p, g_major, names).unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(NoteNames::Letters), ["G", "F♯", "F", "C♯", "B♭"]);
+    assert_eq!(names(NoteNames::FixedDo), ["Sol", "Fa♯", "Fa", "Do♯", "Si♭"]);
+    assert_eq!(names(NoteNames::MovableDo), ["Do", "Ti", "Te", "Fi", "Me"]);
+    assert_eq!(note_name(&pitch("E", -1.0), -3, NoteNames::MovableDo).as_deref(), Some("Do"));
+    assert_eq!(note_name(&pitch("E", 1.0), 0, NoteNames::MovableDo).as_deref(), Some("Mi♯"));
+    assert_eq!(note_name(&pitch("C", 0.0), 0, NoteNames::Off), None);
+}
+
+#[test]
+fn movable_do_stays_on_the_degrees_after_transposing() {
+    let score = tong_nian();
+    let mut up = score.clone();
+    transpose_score(&mut up, 2);
+
+    let movable = named(NoteNames::MovableDo);
+    let before = render_score_to_svg_with_options(&score, None, &movable);
+    let after = render_score_to_svg_with_options(&up, None, &movable);
+    std::fs::write(output_dir().join("童年-movable-do.svg"), &after).unwrap();
+    let syllables = note_texts(&before);
+    assert_eq!(syllables.len(), score.parts[0].measures.iter().flat_map(|m| &m.notes).filter(|n| !n.rest).count());
+    assert_eq!(syllables, note_texts(&after));
+    assert!(syllables.iter().all(|s| ["Do", "Re", "Mi", "Fa", "Sol", "La", "Ti"].contains(&s.as_str())));
+
+    let letters = named(NoteNames::Letters);
+    let before = note_texts(&render_score_to_svg_with_options(&score, None, &letters));
+    let after = note_texts(&render_score_to_svg_with_options(&up, None, &letters));
+    assert_eq!(before.first().map(String::as_str), Some("E"));
+    assert_eq!(after.first().map(String::as_str), Some("F♯"));
+}
+
+#[test]
+fn names_below_make_room_and_inside_names_do_not() {
+    let score = tong_nian();
+    let height = |svg: &str| -> f64 {
+        let at = svg.find("height=\"").unwrap() + 8;
+        svg[at..at + svg[at..].find('"').unwrap()].parse().unwrap()
+    };
+    let plain = render_score_to_svg(&score, None);
+    let below = render_score_to_svg_with_options(&score, None, &named(NoteNames::FixedDo));
+    let inside = RenderOptions { note_name_position: LabelPosition::Inside, ..named(NoteNames::FixedDo) };
+    let inside = render_score_to_svg_with_options(&score, None, &inside);
+    assert!(height(&below) > height(&plain));
+    assert_eq!(height(&inside), height(&plain));
+
+    // The playback map follows the taller layout
+    let (_, systems) = compute_measure_positions_with_options(&score, None, &named(NoteNames::FixedDo));
+    let last_y = systems.last().unwrap().0;
+    assert!(below.contains(&format!(r#"y1="{:.1}""#, last_y)), "no staff line at y={last_y}");
+}
+
+#[test]
+fn names_and_colors_are_printed_on_pages() {
+    let score = tong_nian();
+    let options = RenderOptions { colored_noteheads: true, ..named(NoteNames::FixedDo) };
+    let pages = render_score_to_svg_pages(&score, &PageOptions::default(), &options);
+    std::fs::write(output_dir().join("童年-page-1.svg"), &pages[0]).unwrap();
+
+    let on_screen = note_texts(&render_score_to_svg_with_options(&score, None, &options));
+    let printed: Vec<String> = pages.iter().flat_map(|page| note_texts(page)).collect();
+    assert!(!printed.is_empty());
+    assert_eq!(printed, on_screen);
+    assert!(pages[0].contains(r##"fill="#fff32b""##), "no colored E notehead");
+}
+
+#[test]
+fn noteheads_are_colored_by_pitch_class() {
+    let score = parse_file(sheetmusic_dir().join("asa-branca.musicxml")).unwrap();
+    let options = RenderOptions { colored_noteheads: true, ..RenderOptions::default() };
+    let svg = render_score_to_svg_with_options(&score, None, &options);
+    std::fs::write(output_dir().join("asa-branca-colored.svg"), &svg).unwrap();
+
+    let plain = render_score_to_svg(&score, None);
+    for color in ["#e21c48", "#f99d1c", "#fff32b", "#bcd85f", "#009c95", "#5e50a1"] {
+        assert!(svg.contains(&format!(r#"fill="{color}""#)), "no {color} notehead");
+        assert!(!plain.contains(color));
+    }
+}
+
+#[test]
+fn finger_numbers_are_read_written_and_drawn() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="4.0">
+  <part-list><score-part id="P1"><part-name>Piano</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions><time><beats>4</beats><beat-type>4</beat-type></time>
+        <clef><sign>G</sign><line>2</line></clef></attributes>
+      <note><pitch><step>C</step><octave>4</octave></pitch><duration>4</duration><type>whole</type>
+        <notations><technical><fingering> 1 </fingering></technical></notations></note>
+      <note><chord/><pitch><step>E</step><octave>4</octave></pitch><duration>4</duration><type>whole</type>
+        <notations><technical><fingering>3</fingering></technical></notations></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+    let score = parse_musicxml(xml).unwrap();
+    let notes = &score.parts[0].measures[0].notes;
+    assert_eq!(notes[0].fingering.as_deref(), Some("1"));
+    assert_eq!(notes[1].fingering.as_deref(), Some("3"));
+
+    let written = write_musicxml(&score);
+    assert!(written.contains("<fingering>1</fingering>") && written.contains("<fingering>3</fingering>"));
+    let reparsed = parse_musicxml(&written).unwrap();
+    assert_eq!(reparsed.parts[0].measures[0].notes[1].fingering.as_deref(), Some("3"));
+
+    let options = RenderOptions { finger_numbers: true, ..RenderOptions::default() };
+    let svg = render_score_to_svg_with_options(&score, None, &options);
+    assert!(svg.contains(">1</text>") && svg.contains(">3</text>"));
+    // Stacked like the chord: the finger of the higher note is drawn higher
+    let y_of = |finger: &str| -> f64 {
+        let end = svg.find(&format!(">{finger}</text>")).unwrap();
+        let tag = &svg[svg[..end].rfind("<text").unwrap()..end];
+        let at = tag.find(" y=\"").unwrap() + 4;
+        tag[at..at + tag[at..].find('"').unwrap()].parse().unwrap()
+    };
+    assert!(y_of("3") < y_of("1"));
+    assert!(!render_score_to_svg(&score, None).contains(">3</text>"));
+}
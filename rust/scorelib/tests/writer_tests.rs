@@ -0,0 +1,110 @@
+//! Integration tests for the MusicXML writer: parse → write → parse
+//! round trips over the sample files, and the MXL variant.
+
+use std::path::PathBuf;
+
+use scorelib::{parse_bytes, parse_file, parse_musicxml, transpose_score, write_musicxml, write_mxl, Score};
+
+fn sheetmusic_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../sheetmusic")
+}
+
+/// Score as JSON, ignoring the MusicXML version (the writer always emits 4.0).
+fn normalized(score: &Score) -> serde_json::Value {
+    let mut value = serde_json::to_value(score).unwrap();
+    value["version"] = serde_json::Value::Null;
+    value
+}
+
+/// Report the first measure that differs, to keep failures readable.
+fn assert_same_score(original: &Score, reparsed: &Score, name: &str) {
+    let (a, b) = (normalized(original), normalized(reparsed));
+    if a == b {
+        return;
+    }
+    for (pidx, (pa, pb)) in a["parts"].as_array().unwrap().iter()
+        .zip(b["parts"].as_array().unwrap()).enumerate()
+    {
+        for (midx, (ma, mb)) in pa["measures"].as_array().unwrap().iter()
+            .zip(pb["measures"].as_array().unwrap()).enumerate()
+        {
+            assert_eq!(ma, mb, "{name}: part {pidx} measure {midx} differs after round trip");
+        }
+    }
+    assert_eq!(a, b, "{name}: score differs after round trip");
+}
+
+#[test]
+fn musicxml_round_trip_is_stable_for_all_samples() {
+    let mut count = 0;
+    for entry in std::fs::read_dir(sheetmusic_dir()).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let original = parse_file(&path).unwrap();
+
+        let xml = write_musicxml(&original);
+        let reparsed = parse_musicxml(&xml).unwrap_or_else(|e| panic!("{name}: {e}"));
+        assert_same_score(&original, &reparsed, &name);
+
+        // Writing the re-parsed score gives the same document again
+        assert_eq!(write_musicxml(&reparsed), xml, "{name}: second write differs");
+        count += 1;
+        println!("✓ {name}: {} measures round-trip", original.measure_count());
+    }
+    assert!(count >= 4);
+}
+
+#[test]
+fn mxl_round_trip_keeps_transposition() {
+    let mut score = parse_file(sheetmusic_dir().join("asa-branca.musicxml")).unwrap();
+    transpose_score(&mut score, 3);
+
+    let mxl = write_mxl(&score).unwrap();
+    assert_eq!(&mxl[0..2], b"PK");
+    // mimetype comes first and uncompressed, per the MXL spec
+    assert_eq!(&mxl[30..38], b"mimetype");
+    assert_eq!(&mxl[38..72], b"application/vnd.recordare.musicxml");
+
+    let reparsed = parse_bytes(&mxl, Some("mxl")).unwrap();
+    assert_same_score(&score, &reparsed, "asa-branca.mxl");
+    let key = reparsed.parts[0].measures[0].attributes.as_ref().unwrap().key.as_ref().unwrap();
+    assert_eq!(key.fifths, -3, "C major + 3 semitones = Eb major");
+    println!("✓ Transposed score saved as {} bytes of MXL and read back", mxl.len());
+}
+
+#[test]
+fn writer_restores_onsets_and_escapes_text() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <work><work-title>Tom &amp; Jerry &lt;live&gt;</work-title></work>
+  <part-list><score-part id="P1"><part-name>Piano</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>2</divisions><staves>2</staves>
+        <clef number="1"><sign>G</sign><line>2</line></clef>
+        <clef number="2"><sign>F</sign><line>4</line></clef></attributes>
+      <harmony><root><root-step>C</root-step></root><kind>major</kind></harmony>
+      <note><pitch><step>C</step><octave>5</octave></pitch><duration>4</duration><voice>1</voice><type>half</type><staff>1</staff></note>
+      <direction placement="below"><direction-type><dynamics><mf/></dynamics></direction-type><offset>1</offset><staff>1</staff></direction>
+      <harmony><root><root-step>G</root-step></root><kind>dominant</kind><offset>-1</offset></harmony>
+      <note><pitch><step>E</step><octave>5</octave></pitch><duration>4</duration><voice>1</voice><type>half</type><staff>1</staff></note>
+      <backup><duration>8</duration></backup>
+      <forward><duration>2</duration></forward>
+      <note><pitch><step>C</step><octave>3</octave></pitch><duration>6</duration><voice>2</voice><type>half</type><dot/><staff>2</staff></note>
+      <note><chord/><pitch><step>G</step><octave>3</octave></pitch><duration>6</duration><voice>2</voice><type>half</type><dot/><staff>2</staff></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+    let score = parse_musicxml(xml).unwrap();
+    let written = write_musicxml(&score);
+    assert!(written.contains("Tom &amp; Jerry &lt;live&gt;"));
+    let reparsed = parse_musicxml(&written).unwrap();
+    assert_same_score(&score, &reparsed, "inline");
+
+    let m = &reparsed.parts[0].measures[0];
+    let onsets: Vec<i32> = m.notes.iter().map(|n| n.onset).collect();
+    assert_eq!(onsets, vec![0, 4, 2, 2]);
+    assert_eq!(m.harmonies.iter().map(|h| h.onset).collect::<Vec<_>>(), vec![0, 3]);
+    assert_eq!(m.directions[0].onset, 5);
+    println!("✓ Onsets restored: notes {:?}", onsets);
+}